@@ -0,0 +1,766 @@
+// SPDX-License-Identifier: MIT
+
+//! Directory and multi-file downloads as tar archives
+//!
+//! `GET /<dir>/?download=tar` streams the directory as a POSIX (ustar) tar
+//! file, `?download=tar.gz` (or `tgz`) as a gzipped one. `POST /_api/archive`
+//! with `{"paths":["/docs/a.txt","/docs/img"],"format":"tar.gz"}` streams just
+//! the listed files and folders, named relative to the folder they share.
+//!
+//! Entries keep their unix permission bits and modification times; paths
+//! longer than ustar allows and files over 8 GiB get a pax extended header.
+//! The archive is written while it is sent, in chunked encoding, so its size is
+//! not known up front. The gzip variant is a series of gzip members, one per
+//! [`GZIP_MEMBER_SIZE`] of tar data, which `gunzip` and `tar -z` read as one
+//! stream.
+//!
+//! Only what a listing would show goes in: ignored and hidden entries,
+//! symlinks the `follow_symlinks` policy refuses, files outside
+//! `allowed_extensions` and `[auth.paths]` directories below the requested
+//! one are left out.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::fs::SymlinkRules;
+use crate::http::{Request, RequestBody, Response, ResponseBody};
+use crate::middleware::PathAuthRule;
+use log::{debug, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tokio::sync::mpsc::Sender;
+
+/// Route for archives of selected entries
+pub const ARCHIVE_ROUTE: &str = "/_api/archive";
+
+/// Tar data compressed into each gzip member
+pub const GZIP_MEMBER_SIZE: usize = 1024 * 1024;
+
+/// Most entries one `POST /_api/archive` may select
+pub const MAX_ARCHIVE_PATHS: usize = 1000;
+
+/// Largest JSON body accepted on [`ARCHIVE_ROUTE`]
+const MAX_ARCHIVE_BODY: usize = 256 * 1024;
+
+/// Bytes handed to the connection at a time for plain tar
+const SEND_CHUNK_SIZE: usize = 64 * 1024;
+
+const BLOCK: usize = 512;
+
+/// Largest size the ustar header can hold (11 octal digits)
+const MAX_USTAR_SIZE: u64 = 0o77777777777;
+
+/// Output format requested with `?download=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "tar" => Some(Self::Tar),
+            "tar.gz" | "tgz" => Some(Self::TarGz),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Tar => "application/x-tar",
+            Self::TarGz => "application/gzip",
+        }
+    }
+}
+
+/// One top-level file or folder of an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveItem {
+    pub path: PathBuf,
+    /// Name inside the archive
+    pub name: String,
+    /// URL path on the server, such as `/docs/report.pdf`
+    pub url_path: String,
+}
+
+/// What to put in an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveSource {
+    /// Download file name, without the extension
+    pub name: String,
+    pub items: Vec<ArchiveItem>,
+    /// Files outside these patterns are skipped
+    pub allowed_extensions: Vec<glob::Pattern>,
+    pub symlinks: SymlinkRules,
+    /// `[auth.paths]` rules for directories that are left out
+    pub excluded: Vec<PathAuthRule>,
+}
+
+/// `[auth.paths]` rules configured on the command line or in the config file
+fn path_rules(cli: Option<&Cli>) -> Vec<PathAuthRule> {
+    cli.and_then(|cli| cli.auth_paths.as_deref())
+        .map(|list| {
+            let entries: Vec<String> = list.split(',').map(str::to_string).collect();
+            PathAuthRule::parse_list(&entries)
+        })
+        .unwrap_or_default()
+}
+
+/// `?download=tar` or `?download=tar.gz` on the directory `dir`
+pub fn directory_response(
+    dir: &Path,
+    url_path: &str,
+    allowed_extensions: &[glob::Pattern],
+    symlinks: SymlinkRules,
+    cli: Option<&Cli>,
+    format: ArchiveFormat,
+) -> Response {
+    let url_path = url_path.trim_end_matches('/');
+    // The request already passed this directory's own credentials; protected
+    // directories below it need theirs
+    let excluded = path_rules(cli)
+        .into_iter()
+        .filter(|rule| !rule.covers(url_path))
+        .collect();
+    let name = dir.file_name().map_or_else(
+        || "download".to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    info!("Streaming {} as {}", dir.display(), format.extension());
+    archive_response(
+        ArchiveSource {
+            name: name.clone(),
+            items: vec![ArchiveItem {
+                path: dir.to_path_buf(),
+                name,
+                url_path: url_path.to_string(),
+            }],
+            allowed_extensions: allowed_extensions.to_vec(),
+            symlinks,
+            excluded,
+        },
+        format,
+    )
+}
+
+/// `POST` on [`ARCHIVE_ROUTE`]: an archive of the selected entries
+pub fn handle_archive_request(
+    request: &Request,
+    cli: &Cli,
+    base_dir: &Path,
+) -> Result<Response, AppError> {
+    let body = match &request.body {
+        Some(RequestBody::Memory(data)) if data.len() <= MAX_ARCHIVE_BODY => data,
+        Some(_) => return Err(AppError::PayloadTooLarge(MAX_ARCHIVE_BODY as u64)),
+        None => return Err(bad_request("missing JSON body")),
+    };
+    let text = std::str::from_utf8(body).map_err(|_| bad_request("body is not valid UTF-8"))?;
+    let fields = crate::utils::parse_json_string_lists(text)
+        .ok_or_else(|| bad_request("body must be a JSON object of strings and string lists"))?;
+    let format = match fields.get("format").map(Vec::as_slice) {
+        None => ArchiveFormat::Tar,
+        Some([format]) => {
+            ArchiveFormat::parse(format).ok_or_else(|| bad_request("unknown archive format"))?
+        }
+        Some(_) => return Err(bad_request("'format' must be a string")),
+    };
+    let paths = fields
+        .get("paths")
+        .filter(|paths| !paths.is_empty())
+        .ok_or_else(|| bad_request("missing 'paths' list"))?;
+    if paths.len() > MAX_ARCHIVE_PATHS {
+        return Err(bad_request("too many paths"));
+    }
+
+    let symlinks = SymlinkRules::new(
+        cli.follow_symlinks
+            .as_deref()
+            .and_then(crate::fs::SymlinkPolicy::parse)
+            .unwrap_or_default(),
+        base_dir,
+    );
+    let rules = path_rules(Some(cli));
+    let mut selected: Vec<(PathBuf, String)> = Vec::new();
+    for path in paths {
+        let full_path = crate::webdav::resolve_request_path(base_dir, path)?;
+        let relative = full_path.strip_prefix(base_dir).unwrap_or(&full_path);
+        if full_path == base_dir || crate::fs::ignore_rules().excludes(relative) {
+            return Err(bad_request("path cannot be archived"));
+        }
+        if !full_path.exists() {
+            return Err(AppError::NotFound);
+        }
+        symlinks.check(&full_path)?;
+        let url_path = format!("/{}", relative.to_string_lossy().replace('\\', "/"));
+        // The body is not seen by the auth middleware, so protected
+        // directories are only archived through `?download=`
+        if rules.iter().any(|rule| rule.covers(&url_path)) {
+            debug!("Archive of protected path refused: {url_path}");
+            return Err(AppError::Forbidden);
+        }
+        if !selected.iter().any(|(seen, _)| *seen == full_path) {
+            selected.push((full_path, url_path));
+        }
+    }
+
+    // Name entries relative to the folder they all share
+    let mut common = selected[0].0.parent().unwrap_or(base_dir).to_path_buf();
+    while !selected.iter().all(|(path, _)| path.starts_with(&common)) {
+        if !common.pop() {
+            break;
+        }
+    }
+    let items = selected
+        .into_iter()
+        .map(|(path, url_path)| ArchiveItem {
+            name: path
+                .strip_prefix(&common)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            path,
+            url_path,
+        })
+        .collect::<Vec<_>>();
+    let name = match common.file_name() {
+        Some(name) if common != base_dir => format!("{}-selection", name.to_string_lossy()),
+        _ => "selection".to_string(),
+    };
+    info!(
+        "Streaming {} selected entries as {}",
+        items.len(),
+        format.extension()
+    );
+    Ok(archive_response(
+        ArchiveSource {
+            name,
+            items,
+            allowed_extensions: cli.allowed_extension_patterns()?,
+            symlinks,
+            excluded: rules,
+        },
+        format,
+    ))
+}
+
+fn bad_request(reason: &str) -> AppError {
+    debug!("Rejected archive request: {reason}");
+    AppError::BadRequest
+}
+
+/// Stream `source` as an archive
+pub fn archive_response(source: ArchiveSource, format: ArchiveFormat) -> Response {
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    let filename = format!("{}.{}", source.name, format.extension());
+    std::thread::spawn(move || {
+        let result = match format {
+            ArchiveFormat::Tar => write_archive(&source, ChannelWriter::new(tx, SEND_CHUNK_SIZE)),
+            ArchiveFormat::TarGz => write_archive(
+                &source,
+                GzipMembers::new(ChannelWriter::new(tx, GZIP_MEMBER_SIZE)),
+            ),
+        };
+        match result {
+            Ok(()) => debug!("Archive {} sent", source.name),
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                debug!("Archive download {} cancelled", source.name);
+            }
+            Err(e) => warn!("Archive {} failed: {e}", source.name),
+        }
+    });
+
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        format.content_type().to_string(),
+    );
+    headers.insert(
+        "Content-Disposition".to_string(),
+        crate::utils::content_disposition("attachment", &filename),
+    );
+    headers.insert("Cache-Control".to_string(), "no-store".to_string());
+    Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::AsyncStream(rx),
+    }
+}
+
+/// Write the tar archive of `source` to `out`
+pub fn write_archive<W: Write>(source: &ArchiveSource, out: W) -> io::Result<()> {
+    let mut tar = TarWriter::new(out);
+    let mut visited = HashSet::new();
+    for item in &source.items {
+        let metadata = fs::metadata(&item.path)?;
+        add_entry(
+            source,
+            &mut tar,
+            &item.path,
+            &metadata,
+            (&item.name, item.url_path.trim_end_matches('/')),
+            &mut visited,
+        )?;
+    }
+    tar.finish()
+}
+
+/// Add a file, or a folder with everything below it, named `name` in the
+/// archive and `url` on the server
+fn add_entry<W: Write>(
+    source: &ArchiveSource,
+    tar: &mut TarWriter<W>,
+    path: &Path,
+    metadata: &Metadata,
+    (name, url): (&str, &str),
+    visited: &mut HashSet<PathBuf>,
+) -> io::Result<()> {
+    if metadata.is_file() {
+        if !source
+            .allowed_extensions
+            .iter()
+            .any(|pattern| pattern.matches_path(path))
+        {
+            return Ok(());
+        }
+        return match File::open(path) {
+            Ok(file) => tar.file(name, metadata, file),
+            Err(e) => {
+                debug!("Skipping unreadable {}: {e}", path.display());
+                Ok(())
+            }
+        };
+    }
+    if !metadata.is_dir() {
+        return Ok(());
+    }
+    // Followed symlinks could otherwise lead back into a parent
+    if !visited.insert(fs::canonicalize(path)?) {
+        debug!("Skipping directory loop at {}", path.display());
+        return Ok(());
+    }
+    tar.directory(&format!("{name}/"), metadata)?;
+    let mut entries: Vec<_> = fs::read_dir(path)?.flatten().collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let entry_path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let entry_url = format!("{url}/{file_name}");
+        if crate::fs::ignore_rules().excludes_entry(&entry_path, &file_name)
+            || source.excluded.iter().any(|rule| rule.covers(&entry_url))
+        {
+            continue;
+        }
+        let symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+        if symlink && !source.symlinks.lists(&entry_path) {
+            continue;
+        }
+        let Ok(metadata) = fs::metadata(&entry_path) else {
+            continue;
+        };
+        add_entry(
+            source,
+            tar,
+            &entry_path,
+            &metadata,
+            (&format!("{name}/{file_name}"), &entry_url),
+            visited,
+        )?;
+    }
+    Ok(())
+}
+
+/// Minimal tar encoder
+struct TarWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    fn directory(&mut self, name: &str, metadata: &Metadata) -> io::Result<()> {
+        self.header(name, metadata, 0, b'5')
+    }
+
+    /// Add a file, sending exactly the size in `metadata` even if it changes meanwhile
+    fn file(&mut self, name: &str, metadata: &Metadata, file: File) -> io::Result<()> {
+        let size = metadata.len();
+        self.header(name, metadata, size, b'0')?;
+        let mut reader = file.take(size);
+        let copied = io::copy(&mut reader, &mut self.out)?;
+        if copied < size {
+            warn!("{name} shrank while it was archived; padding it with zeros");
+            io::copy(&mut io::repeat(0).take(size - copied), &mut self.out)?;
+        }
+        self.pad(size)
+    }
+
+    fn header(&mut self, name: &str, metadata: &Metadata, size: u64, kind: u8) -> io::Result<()> {
+        let mut pax = Vec::new();
+        let (prefix, short_name) = split_name(name).unwrap_or_else(|| {
+            pax_record(&mut pax, "path", name);
+            ("", truncate(name, 100))
+        });
+        if size > MAX_USTAR_SIZE {
+            pax_record(&mut pax, "size", &size.to_string());
+        }
+        if !pax.is_empty() {
+            let mut block = ustar_header(&truncate(&format!("PaxHeader/{short_name}"), 100), "");
+            write_octal(&mut block[100..108], 0o644);
+            write_octal(&mut block[124..136], pax.len() as u64);
+            block[156] = b'x';
+            checksum(&mut block);
+            self.out.write_all(&block)?;
+            self.out.write_all(&pax)?;
+            self.pad(pax.len() as u64)?;
+        }
+
+        let mut block = ustar_header(&short_name, prefix);
+        write_octal(
+            &mut block[100..108],
+            u64::from(mode(metadata, kind == b'5')),
+        );
+        write_octal(&mut block[108..116], 0);
+        write_octal(&mut block[116..124], 0);
+        write_octal(&mut block[124..136], size.min(MAX_USTAR_SIZE));
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        write_octal(&mut block[136..148], mtime.min(0o77777777777));
+        block[156] = kind;
+        checksum(&mut block);
+        self.out.write_all(&block)
+    }
+
+    fn pad(&mut self, size: u64) -> io::Result<()> {
+        let rest = (BLOCK - (size % BLOCK as u64) as usize) % BLOCK;
+        self.out.write_all(&[0u8; BLOCK][..rest])
+    }
+
+    /// End-of-archive marker: two empty blocks
+    fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(&[0u8; BLOCK * 2])?;
+        self.out.flush()
+    }
+}
+
+/// Header block with `name`, `prefix` and the ustar magic filled in
+fn ustar_header(name: &str, prefix: &str) -> [u8; BLOCK] {
+    let mut block = [0u8; BLOCK];
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+    block
+}
+
+/// Split `name` into the ustar prefix (155 bytes) and name (100 bytes) at a
+/// `/`, if it fits
+fn split_name(name: &str) -> Option<(&str, String)> {
+    if name.len() <= 100 {
+        return Some(("", name.to_string()));
+    }
+    // A trailing slash belongs to the name part
+    let search = name.strip_suffix('/').unwrap_or(name);
+    search
+        .match_indices('/')
+        .map(|(i, _)| i)
+        .find(|&i| i <= 155 && name.len() - i - 1 <= 100)
+        .map(|i| (&name[..i], name[i + 1..].to_string()))
+}
+
+/// `name` cut to at most `max` bytes on a character boundary
+fn truncate(name: &str, max: usize) -> String {
+    let mut end = name.len().min(max);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name[..end].to_string()
+}
+
+/// Append a pax record: "<length> <key>=<value>\n", where the length counts itself
+fn pax_record(out: &mut Vec<u8>, key: &str, value: &str) {
+    let body = key.len() + value.len() + 3;
+    let mut len = body + 1;
+    while len.to_string().len() + body != len {
+        len += 1;
+    }
+    out.extend_from_slice(format!("{len} {key}={value}\n").as_bytes());
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let text = format!("{value:0digits$o}");
+    field[..digits].copy_from_slice(&text.as_bytes()[text.len() - digits..]);
+    field[digits] = 0;
+}
+
+fn checksum(block: &mut [u8; BLOCK]) {
+    block[148..156].fill(b' ');
+    let sum: u32 = block.iter().map(|&b| u32::from(b)).sum();
+    let text = format!("{sum:06o}\0 ");
+    block[148..156].copy_from_slice(text.as_bytes());
+}
+
+#[cfg(unix)]
+fn mode(metadata: &Metadata, _dir: bool) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &Metadata, dir: bool) -> u32 {
+    match (dir, metadata.permissions().readonly()) {
+        (true, _) => 0o755,
+        (false, true) => 0o444,
+        (false, false) => 0o644,
+    }
+}
+
+/// Collects output into chunks and hands them to the connection
+struct ChannelWriter {
+    tx: Sender<Vec<u8>>,
+    buffer: Vec<u8>,
+    chunk_size: usize,
+}
+
+impl ChannelWriter {
+    fn new(tx: Sender<Vec<u8>>, chunk_size: usize) -> Self {
+        Self {
+            tx,
+            buffer: Vec::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+
+    fn send(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(self.chunk_size));
+        self.tx
+            .blocking_send(chunk)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let room = self.chunk_size - self.buffer.len();
+        let taken = data.len().min(room);
+        self.buffer.extend_from_slice(&data[..taken]);
+        if self.buffer.len() >= self.chunk_size {
+            self.send()?;
+        }
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send()
+    }
+}
+
+/// Compresses each full [`ChannelWriter`] chunk into its own gzip member
+struct GzipMembers {
+    inner: ChannelWriter,
+}
+
+impl GzipMembers {
+    fn new(inner: ChannelWriter) -> Self {
+        Self { inner }
+    }
+
+    fn send(&mut self) -> io::Result<()> {
+        if self.inner.buffer.is_empty() {
+            return Ok(());
+        }
+        let member = crate::compression::gzip(&self.inner.buffer);
+        self.inner.buffer.clear();
+        self.inner
+            .tx
+            .blocking_send(member)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+impl Write for GzipMembers {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let room = self.inner.chunk_size - self.inner.buffer.len();
+        let taken = data.len().min(room);
+        self.inner.buffer.extend_from_slice(&data[..taken]);
+        if self.inner.buffer.len() >= self.inner.chunk_size {
+            self.send()?;
+        }
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn field(block: &[u8], range: std::ops::Range<usize>) -> String {
+        let bytes = &block[range];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    }
+
+    /// (name, type, size, mode, content); pax paths replace the header name
+    fn parse(data: &[u8]) -> Vec<(String, u8, u64, u32, Vec<u8>)> {
+        let mut entries = Vec::new();
+        let mut pos = 0;
+        let mut pax_path = None;
+        while pos + BLOCK <= data.len() && data[pos..pos + BLOCK].iter().any(|&b| b != 0) {
+            let block = &data[pos..pos + BLOCK];
+            let stored: u32 = u32::from_str_radix(field(block, 148..154).trim(), 8).unwrap();
+            let mut copy = block.to_vec();
+            copy[148..156].fill(b' ');
+            assert_eq!(stored, copy.iter().map(|&b| u32::from(b)).sum::<u32>());
+            assert_eq!(&block[257..263], b"ustar\0");
+
+            let size = u64::from_str_radix(&field(block, 124..135), 8).unwrap();
+            let mode = u32::from_str_radix(&field(block, 100..107), 8).unwrap();
+            let content = data[pos + BLOCK..pos + BLOCK + size as usize].to_vec();
+            pos += BLOCK + (size as usize).div_ceil(BLOCK) * BLOCK;
+            if block[156] == b'x' {
+                let text = String::from_utf8(content).unwrap();
+                let record = text.split_once(" path=").unwrap().1;
+                pax_path = Some(record.trim_end_matches('\n').to_string());
+                continue;
+            }
+            let mut name = field(block, 0..100);
+            let prefix = field(block, 345..500);
+            if !prefix.is_empty() {
+                name = format!("{prefix}/{name}");
+            }
+            entries.push((
+                pax_path.take().unwrap_or(name),
+                block[156],
+                size,
+                mode,
+                content,
+            ));
+        }
+        assert_eq!(
+            data.len(),
+            pos + 2 * BLOCK,
+            "two zero blocks end the archive"
+        );
+        entries
+    }
+
+    fn source(dir: &Path) -> ArchiveSource {
+        ArchiveSource {
+            name: "docs".to_string(),
+            items: vec![ArchiveItem {
+                path: dir.to_path_buf(),
+                name: "docs".to_string(),
+                url_path: "/docs/".to_string(),
+            }],
+            allowed_extensions: vec![glob::Pattern::new("*").unwrap()],
+            symlinks: SymlinkRules::default(),
+            excluded: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_write_archive() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        let long = "d".repeat(120);
+        fs::create_dir_all(dir.path().join("sub").join(&long)).unwrap();
+        fs::write(dir.path().join("sub").join(&long).join("b.txt"), "").unwrap();
+        fs::create_dir(dir.path().join("private")).unwrap();
+        fs::write(dir.path().join("private").join("secret.txt"), "x").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let script = dir.path().join("run.sh");
+            fs::write(&script, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o750)).unwrap();
+        }
+
+        let mut source = source(dir.path());
+        source.excluded = PathAuthRule::parse_list(&["/docs/Private=u:p".to_string()]);
+        let mut out = Vec::new();
+        write_archive(&source, &mut out).unwrap();
+        let entries = parse(&out);
+        let names: Vec<&str> = entries.iter().map(|e| e.0.as_str()).collect();
+        let long_dir = format!("docs/sub/{long}/");
+        let long_file = format!("docs/sub/{long}/b.txt");
+        let mut expected = vec!["docs/", "docs/a.txt"];
+        if cfg!(unix) {
+            expected.push("docs/run.sh");
+        }
+        expected.extend(["docs/sub/", &long_dir, &long_file]);
+        assert_eq!(names, expected);
+
+        assert_eq!(entries[1].1, b'0');
+        assert_eq!(entries[1].4, b"hello");
+        assert_eq!(entries[0].1, b'5');
+        if cfg!(unix) {
+            assert_eq!(entries[2].3, 0o750);
+        }
+    }
+
+    #[test]
+    fn test_split_name_and_pax_record() {
+        assert_eq!(
+            split_name("docs/a.txt"),
+            Some(("", "docs/a.txt".to_string()))
+        );
+        let deep = format!("{}/{}", "p".repeat(150), "n".repeat(90));
+        assert_eq!(split_name(&deep), Some((&deep[..150], "n".repeat(90))));
+        assert_eq!(split_name(&"x".repeat(300)), None);
+
+        let mut record = Vec::new();
+        pax_record(&mut record, "path", "abc");
+        assert_eq!(record, b"12 path=abc\n");
+        let mut record = Vec::new();
+        // 98 bytes plus a two-digit length would be 100, which needs three digits
+        pax_record(&mut record, "path", &"a".repeat(91));
+        assert_eq!(record.len(), 101);
+        assert!(record.starts_with(b"101 path="));
+    }
+
+    #[test]
+    fn test_gzip_members() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let mut writer = GzipMembers::new(ChannelWriter::new(tx, 1000));
+        let data: Vec<u8> = (0..2500u32).map(|i| (i % 7) as u8).collect();
+        writer.write_all(&data).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        let mut members = Vec::new();
+        while let Ok(member) = rx.try_recv() {
+            members.push(member);
+        }
+        assert_eq!(members.len(), 3);
+        for (member, chunk) in members.iter().zip(data.chunks(1000)) {
+            assert_eq!(&member[..2], &[0x1f, 0x8b]);
+            let trailer = &member[member.len() - 8..];
+            assert_eq!(trailer[..4], crate::compression::crc32(chunk).to_le_bytes());
+            assert_eq!(trailer[4..], (chunk.len() as u32).to_le_bytes());
+        }
+    }
+}
@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: MIT
+
+//! Audit log of security-relevant events
+//!
+//! With `[logging] audit = true` (or `--audit true`) every event below is
+//! appended to a JSON lines file, one object per line:
+//!
+//! ```json
+//! {"timestamp":1735689600,"event":"file.deleted","user":"alice","ip":"192.168.1.20","path":"/old.txt","result":"success","source":"file_management"}
+//! ```
+//!
+//! - `auth.login` - a login through the session login form
+//! - `auth.failure` - wrong credentials, an unknown API token or a failed login form
+//! - `upload.completed` - an upload (form, resumable session or WebDAV `PUT`) was stored
+//! - `file.deleted` - a file or directory was removed
+//! - `config.reload` - the configuration was reloaded, or a reload was rejected
+//! - `share.created` - a share link was issued
+//! - `rate_limit.ban` - a client was locked out after failed logins, or refused by
+//!   the rate limiter (recorded once per minute per client)
+//!
+//! Basic Auth is checked on every request, so only its failures are recorded.
+//! `user` is the account the request authenticated as (or tried to), `ip` the
+//! client address (behind a trusted proxy the forwarded one). The file is
+//! `[logging] audit_file`, or `irondrop_audit.jsonl` in the log directory (in the
+//! working directory without one). It is only ever appended to and is not rotated.
+
+use crate::hooks::HookValue;
+use crate::http::Request;
+use crate::utils::json_escape;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// File name used when `audit_file` is not configured
+pub const DEFAULT_AUDIT_FILE: &str = "irondrop_audit.jsonl";
+
+/// Minimum gap between rate-limit records for the same client
+const BAN_RECORD_WINDOW: Duration = Duration::from_secs(60);
+
+/// Clients remembered for rate-limit records before the table is reset
+const MAX_BAN_ENTRIES: usize = 1024;
+
+/// Events written to the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuditEvent {
+    Login,
+    AuthFailure,
+    Upload,
+    Delete,
+    ConfigReload,
+    ShareCreated,
+    RateLimitBan,
+}
+
+impl AuditEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEvent::Login => "auth.login",
+            AuditEvent::AuthFailure => "auth.failure",
+            AuditEvent::Upload => "upload.completed",
+            AuditEvent::Delete => "file.deleted",
+            AuditEvent::ConfigReload => "config.reload",
+            AuditEvent::ShareCreated => "share.created",
+            AuditEvent::RateLimitBan => "rate_limit.ban",
+        }
+    }
+}
+
+/// Who did something, from where, to what
+#[derive(Debug, Clone, Default)]
+pub struct AuditRecord {
+    pub user: Option<String>,
+    pub ip: Option<String>,
+    pub path: Option<String>,
+    /// `success`, `failure` or `denied`
+    pub result: &'static str,
+    pub extra: Vec<(&'static str, HookValue)>,
+}
+
+impl AuditRecord {
+    /// Record for `request`, with the user it authenticated as
+    pub fn for_request(request: &Request, result: &'static str) -> Self {
+        Self {
+            user: request_user(request),
+            ip: request.peer_addr.map(|addr| addr.ip().to_string()),
+            path: Some(request.path.as_str().to_string()),
+            result,
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn with_user(mut self, user: Option<String>) -> Self {
+        self.user = user;
+        self
+    }
+
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn with(mut self, key: &'static str, value: impl Into<HookValue>) -> Self {
+        self.extra.push((key, value.into()));
+        self
+    }
+}
+
+struct AuditLog {
+    path: PathBuf,
+    file: File,
+    /// Last rate-limit record per client
+    bans: HashMap<String, Instant>,
+}
+
+static AUDIT_LOG: Mutex<Option<AuditLog>> = Mutex::new(None);
+
+/// Where the audit log goes for a given `audit_file` and log directory
+pub fn audit_path(audit_file: Option<&Path>, log_dir: Option<&Path>) -> PathBuf {
+    match (audit_file, log_dir) {
+        (Some(file), _) => file.to_path_buf(),
+        (None, Some(dir)) => dir.join(DEFAULT_AUDIT_FILE),
+        (None, None) => PathBuf::from(DEFAULT_AUDIT_FILE),
+    }
+}
+
+/// Start (or stop, with `None`) writing the audit log. Called on startup and
+/// on every configuration reload; the same path keeps the open file.
+pub fn configure(path: Option<PathBuf>) {
+    let Ok(mut slot) = AUDIT_LOG.lock() else {
+        return;
+    };
+    let Some(path) = path else {
+        *slot = None;
+        return;
+    };
+    if slot.as_ref().is_some_and(|log| log.path == path) {
+        return;
+    }
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => {
+            info!("📝 Audit log: {}", path.display());
+            *slot = Some(AuditLog {
+                path,
+                file,
+                bans: HashMap::new(),
+            });
+        }
+        Err(e) => {
+            warn!("Cannot open audit log {}: {e}", path.display());
+            *slot = None;
+        }
+    }
+}
+
+/// Append `record` to the audit log, if there is one
+pub fn record(event: AuditEvent, record: AuditRecord) {
+    let Ok(mut slot) = AUDIT_LOG.lock() else {
+        return;
+    };
+    let Some(log) = slot.as_mut() else {
+        return;
+    };
+    if event == AuditEvent::RateLimitBan
+        && let Some(ip) = &record.ip
+    {
+        if log
+            .bans
+            .get(ip)
+            .is_some_and(|at| at.elapsed() < BAN_RECORD_WINDOW)
+        {
+            return;
+        }
+        if log.bans.len() >= MAX_BAN_ENTRIES {
+            log.bans.clear();
+        }
+        log.bans.insert(ip.clone(), Instant::now());
+    }
+
+    let mut line = render_line(event, crate::session::unix_now(), &record);
+    line.push('\n');
+    // One write per line, so concurrent writers never interleave within a record
+    if let Err(e) = log.file.write_all(line.as_bytes()) {
+        warn!("Cannot write audit log {}: {e}", log.path.display());
+    }
+}
+
+/// Render one audit log line, without the newline
+pub fn render_line(event: AuditEvent, timestamp: u64, record: &AuditRecord) -> String {
+    let optional = |value: &Option<String>| match value {
+        Some(value) => format!("\"{}\"", json_escape(value)),
+        None => "null".to_string(),
+    };
+    let mut line = format!(
+        r#"{{"timestamp":{timestamp},"event":"{}","user":{},"ip":{},"path":{},"result":"{}""#,
+        event.as_str(),
+        optional(&record.user),
+        optional(&record.ip),
+        optional(&record.path),
+        record.result,
+    );
+    for (key, value) in &record.extra {
+        let value = match value {
+            HookValue::Str(s) => format!("\"{}\"", json_escape(s)),
+            HookValue::Num(n) => n.to_string(),
+            HookValue::Bool(b) => b.to_string(),
+        };
+        line.push_str(&format!(",\"{}\":{value}", json_escape(key)));
+    }
+    line.push('}');
+    line
+}
+
+/// User a request authenticated as: the Basic Auth name, the session
+/// cookie's account, or `api-token`. Only meaningful once the auth
+/// middleware has let the request through.
+pub fn request_user(request: &Request) -> Option<String> {
+    if let Some(name) = crate::users::basic_username(request) {
+        return Some(name);
+    }
+    if let Some(name) = crate::session::cookie_user(request) {
+        return Some(name);
+    }
+    let token = request.headers.contains_key("x-api-key")
+        || request
+            .headers
+            .get("authorization")
+            .is_some_and(|h| h.to_ascii_lowercase().starts_with("bearer "));
+    token.then(|| "api-token".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_line() {
+        let record = AuditRecord {
+            user: Some("al\"ice".to_string()),
+            ip: Some("10.0.0.5".to_string()),
+            path: None,
+            result: "success",
+            extra: vec![("size", 42u64.into()), ("renamed", false.into())],
+        };
+        assert_eq!(
+            render_line(AuditEvent::Upload, 1700000000, &record),
+            r#"{"timestamp":1700000000,"event":"upload.completed","user":"al\"ice","ip":"10.0.0.5","path":null,"result":"success","size":42,"renamed":false}"#
+        );
+    }
+
+    #[test]
+    fn test_audit_path() {
+        assert_eq!(
+            audit_path(
+                Some(Path::new("/var/log/a.jsonl")),
+                Some(Path::new("/logs"))
+            ),
+            PathBuf::from("/var/log/a.jsonl")
+        );
+        assert_eq!(
+            audit_path(None, Some(Path::new("/logs"))),
+            Path::new("/logs").join(DEFAULT_AUDIT_FILE)
+        );
+        assert_eq!(audit_path(None, None), PathBuf::from(DEFAULT_AUDIT_FILE));
+    }
+}
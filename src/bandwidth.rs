@@ -0,0 +1,363 @@
+// SPDX-License-Identifier: MIT
+
+//! Bandwidth throttling for response and request bodies
+//!
+//! Two optional token buckets limit how fast bodies are written:
+//!
+//! - a global bucket shared by every connection (`--max-bandwidth`)
+//! - a bucket per connection (`--per-conn-bandwidth`)
+//!
+//! Before each slice of body is written the connection takes tokens from
+//! both buckets and sleeps until the slower one has refilled. Buckets may go
+//! into debt, so concurrent downloads queue behind each other instead of all
+//! bursting at once. Response headers are never throttled.
+//!
+//! Request bodies streamed to disk go through an [`UploadFlow`]: a second
+//! limiter with the same two levels (`--max-upload-bandwidth`,
+//! `--per-upload-bandwidth`) paces how fast they are read, and no body gets
+//! more than [`UPLOAD_WINDOW_BYTES`] ahead of the disk. Once that much is
+//! unflushed the reader waits for the writes to drain before reading on, so a
+//! slow disk slows senders down through TCP flow control instead of piling
+//! up buffers. How long the drains take gives the disk write throughput
+//! reported on `/_status`.
+
+use crate::cli::Cli;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Smallest slice written between throttle checks
+const MIN_SLICE_BYTES: u64 = 1024;
+
+/// Largest slice written between throttle checks
+const MAX_SLICE_BYTES: u64 = 64 * 1024;
+
+/// Unflushed bytes a request body may have on their way to disk
+pub const UPLOAD_WINDOW_BYTES: usize = 256 * 1024;
+
+/// Limits applied to request bodies, set by [`configure_uploads`]
+static UPLOAD_LIMITER: RwLock<Option<Arc<BandwidthLimiter>>> = RwLock::new(None);
+
+/// Recent disk write throughput of request bodies in bytes per second
+static WRITE_RATE: AtomicU64 = AtomicU64::new(0);
+
+/// Parse a rate such as "10MB/s", "512KB" or "1048576" into bytes per second
+///
+/// Units are binary (1 KB = 1024 bytes), matching the other size options;
+/// a trailing "/s" or "ps" is optional.
+pub fn parse_bandwidth(value: &str) -> Option<u64> {
+    let mut size = value.trim().to_ascii_uppercase();
+    for suffix in ["/S", "PS"] {
+        if let Some(stripped) = size.strip_suffix(suffix) {
+            size = stripped.trim_end().to_string();
+            break;
+        }
+    }
+    // Allow "10M/s" and "2Mbps" as well as "10MB/s"
+    if size.ends_with(['K', 'M', 'G', 'T']) {
+        size.push('B');
+    }
+    crate::config::ini_parser::parse_file_size(&size).filter(|&bytes| bytes > 0)
+}
+
+/// Human-readable rate for logs, e.g. "10.0 MB/s"
+pub fn format_bandwidth(bytes_per_second: u64) -> String {
+    format!("{}/s", crate::fs::format_file_size(bytes_per_second))
+}
+
+/// Classic token bucket measured in bytes
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(bytes_per_second: u64) -> Self {
+        let burst = slice_size(bytes_per_second) as f64;
+        Self {
+            rate: bytes_per_second as f64,
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take `amount` tokens and return how long to wait before using them
+    pub fn reserve(&mut self, amount: u64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.burst);
+        self.tokens -= amount as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Bytes written between throttle checks: about 1/10 s of traffic
+fn slice_size(bytes_per_second: u64) -> u64 {
+    (bytes_per_second / 10).clamp(MIN_SLICE_BYTES, MAX_SLICE_BYTES)
+}
+
+/// Server-wide throttling settings and the shared global bucket
+#[derive(Debug, Default)]
+pub struct BandwidthLimiter {
+    global: Option<Mutex<TokenBucket>>,
+    global_rate: Option<u64>,
+    per_connection_rate: Option<u64>,
+}
+
+impl BandwidthLimiter {
+    /// Rates in bytes per second; `None` leaves that limit off
+    pub fn new(global: Option<u64>, per_connection: Option<u64>) -> Self {
+        Self {
+            global: global.map(|rate| Mutex::new(TokenBucket::new(rate))),
+            global_rate: global,
+            per_connection_rate: per_connection,
+        }
+    }
+
+    pub fn from_cli(cli: &Cli) -> Self {
+        Self::new(cli.max_bandwidth, cli.per_conn_bandwidth)
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.global_rate.is_none() && self.per_connection_rate.is_none()
+    }
+
+    /// Throttle state for one connection; `None` when nothing is limited
+    pub fn connection(self: &Arc<Self>) -> Option<ConnectionThrottle> {
+        if self.is_unlimited() {
+            return None;
+        }
+        let slowest = [self.global_rate, self.per_connection_rate]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(u64::MAX);
+        Some(ConnectionThrottle {
+            limiter: Arc::clone(self),
+            local: self.per_connection_rate.map(TokenBucket::new),
+            slice: slice_size(slowest) as usize,
+        })
+    }
+}
+
+/// Per-connection view of the limiter used by the response writer
+#[derive(Debug)]
+pub struct ConnectionThrottle {
+    limiter: Arc<BandwidthLimiter>,
+    local: Option<TokenBucket>,
+    slice: usize,
+}
+
+impl ConnectionThrottle {
+    /// Largest write to make before calling [`ConnectionThrottle::throttle`] again
+    pub fn slice_size(&self) -> usize {
+        self.slice
+    }
+
+    /// Wait until `amount` bytes may be sent
+    pub async fn throttle(&mut self, amount: usize) {
+        let now = Instant::now();
+        let amount = amount as u64;
+        let global_wait = self
+            .limiter
+            .global
+            .as_ref()
+            .map_or(Duration::ZERO, |bucket| {
+                bucket
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .reserve(amount, now)
+            });
+        let local_wait = self
+            .local
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.reserve(amount, now));
+        let wait = global_wait.max(local_wait);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Pace request bodies with `total` shared by all uploads and `per_upload`
+/// for each; `None` leaves that limit off
+pub fn configure_uploads(total: Option<u64>, per_upload: Option<u64>) {
+    if let Ok(mut limiter) = UPLOAD_LIMITER.write() {
+        *limiter = Some(Arc::new(BandwidthLimiter::new(total, per_upload)));
+    }
+}
+
+fn upload_limiter() -> Option<Arc<BandwidthLimiter>> {
+    UPLOAD_LIMITER
+        .read()
+        .ok()
+        .and_then(|limiter| limiter.clone())
+}
+
+/// Recent disk write throughput of request bodies, 0 before the first window
+pub fn upload_write_rate() -> u64 {
+    WRITE_RATE.load(Ordering::Relaxed)
+}
+
+fn record_write_rate(bytes: usize, busy: Duration) {
+    let secs = busy.as_secs_f64().max(1e-6);
+    let sample = (bytes as f64 / secs) as u64;
+    // Smooth over a few windows so one slow fsync does not dominate
+    let _ = WRITE_RATE.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+        Some(if old == 0 {
+            sample
+        } else {
+            (old / 4).saturating_mul(3).saturating_add(sample / 4)
+        })
+    });
+}
+
+/// `"upload_flow":{...}` section for the status JSON
+pub fn status_json() -> String {
+    let limiter = upload_limiter();
+    let rate = |rate: Option<u64>| rate.map_or("null".to_string(), |rate| rate.to_string());
+    format!(
+        r#""upload_flow":{{"max_bandwidth":{},"per_upload_bandwidth":{},"window_bytes":{UPLOAD_WINDOW_BYTES},"write_bytes_per_second":{}}}"#,
+        rate(limiter.as_ref().and_then(|l| l.global_rate)),
+        rate(limiter.as_ref().and_then(|l| l.per_connection_rate)),
+        upload_write_rate()
+    )
+}
+
+/// Flow control for one request body being streamed to disk
+#[derive(Debug)]
+pub struct UploadFlow {
+    throttle: Option<ConnectionThrottle>,
+    unflushed: usize,
+    busy: Duration,
+}
+
+impl Default for UploadFlow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UploadFlow {
+    /// Flow control under the limits set by [`configure_uploads`]
+    pub fn new() -> Self {
+        Self::with_limiter(upload_limiter())
+    }
+
+    pub fn with_limiter(limiter: Option<Arc<BandwidthLimiter>>) -> Self {
+        Self {
+            throttle: limiter.and_then(|limiter| limiter.connection()),
+            unflushed: 0,
+            busy: Duration::ZERO,
+        }
+    }
+
+    /// Largest read to make before calling [`UploadFlow::received`] again
+    pub fn read_size(&self, max: usize) -> usize {
+        self.throttle
+            .as_ref()
+            .map_or(max, |throttle| throttle.slice_size().min(max))
+    }
+
+    /// Wait until `amount` more bytes of body may be read
+    pub async fn received(&mut self, amount: usize) {
+        if let Some(throttle) = self.throttle.as_mut() {
+            throttle.throttle(amount).await;
+        }
+    }
+
+    /// Write `data` to `sink`, draining it once a window is unflushed
+    pub async fn write<W>(&mut self, sink: &mut W, data: &[u8]) -> std::io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let started = Instant::now();
+        sink.write_all(data).await?;
+        self.unflushed += data.len();
+        if self.unflushed >= UPLOAD_WINDOW_BYTES {
+            sink.flush().await?;
+            self.busy += started.elapsed();
+            record_write_rate(self.unflushed, self.busy);
+            self.unflushed = 0;
+            self.busy = Duration::ZERO;
+        } else {
+            self.busy += started.elapsed();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bandwidth() {
+        assert_eq!(parse_bandwidth("10MB/s"), Some(10 * 1024 * 1024));
+        assert_eq!(parse_bandwidth("512KB"), Some(512 * 1024));
+        assert_eq!(parse_bandwidth("2mbps"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_bandwidth("1.5M/s"), Some(1024 * 1024 * 3 / 2));
+        assert_eq!(parse_bandwidth("4096"), Some(4096));
+        assert_eq!(parse_bandwidth("0"), None);
+        assert_eq!(parse_bandwidth("fast"), None);
+    }
+
+    #[test]
+    fn test_token_bucket_waits_for_debt() {
+        let mut bucket = TokenBucket::new(100 * 1024);
+        let start = bucket.last_refill;
+        // The initial burst (1/10 s worth) goes out immediately
+        assert_eq!(bucket.reserve(10 * 1024, start), Duration::ZERO);
+        // The next 50 KB needs half a second of refill
+        let wait = bucket.reserve(50 * 1024, start);
+        assert!((wait.as_secs_f64() - 0.5).abs() < 0.01, "{wait:?}");
+        // Once that time has passed the debt is paid off
+        let later = start + Duration::from_millis(500);
+        assert!(bucket.reserve(1, later) < Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_unlimited_limiter_has_no_connection_throttle() {
+        assert!(Arc::new(BandwidthLimiter::default()).connection().is_none());
+        let throttle = Arc::new(BandwidthLimiter::new(
+            Some(10 * 1024 * 1024),
+            Some(20 * 1024),
+        ))
+        .connection()
+        .unwrap();
+        assert_eq!(throttle.slice_size(), 2048);
+    }
+
+    #[tokio::test]
+    async fn test_upload_flow_drains_each_window() {
+        let mut flow = UploadFlow::with_limiter(None);
+        assert_eq!(flow.read_size(64 * 1024), 64 * 1024);
+        let mut sink = Vec::new();
+        let chunk = vec![0u8; 64 * 1024];
+        for _ in 0..5 {
+            flow.write(&mut sink, &chunk).await.unwrap();
+        }
+        assert_eq!(sink.len(), 5 * 64 * 1024);
+        // Four chunks filled the window; the fifth is still unflushed
+        assert_eq!(flow.unflushed, 64 * 1024);
+        assert!(upload_write_rate() > 0);
+
+        let limited = Arc::new(BandwidthLimiter::new(None, Some(20 * 1024)));
+        let mut flow = UploadFlow::with_limiter(Some(limited));
+        assert_eq!(flow.read_size(64 * 1024), 2048);
+        let started = Instant::now();
+        flow.received(2048).await;
+        flow.received(2048).await;
+        // The second slice waits for 1/10 s of refill
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+}
@@ -0,0 +1,543 @@
+// SPDX-License-Identifier: MIT
+
+//! File checksums for verifying downloads
+//!
+//! `GET /_api/checksum?path=<path>&algo=sha256` (also reachable as
+//! `/_irondrop/checksum`) streams the file through a hasher and returns the
+//! hex digest. `algo` is `sha256` (default) or `md5`; `format=text` answers
+//! with a `sha256sum`/`md5sum` compatible line instead of JSON.
+//!
+//! Hashing a large file is expensive, so recent results are kept in a small
+//! LRU keyed by path, size and modification time; editing the file changes
+//! the key and the next request hashes it again.
+//!
+//! Both digests are implemented here to avoid pulling in a crypto crate. MD5
+//! is offered for compatibility with published checksums only.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Request, Response, ResponseBody};
+use crate::utils::json_escape;
+use log::debug;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, UNIX_EPOCH};
+
+/// Namespaced checksum route
+pub const CHECKSUM_ROUTE: &str = "/_irondrop/checksum";
+
+/// Short checksum route linked from the UI
+pub const CHECKSUM_SHORT_ROUTE: &str = "/_api/checksum";
+
+/// Results kept in the LRU
+const CACHE_CAPACITY: usize = 256;
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Supported digest algorithms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    #[default]
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "md5" => Some(Self::Md5),
+            "sha256" | "sha-256" => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha256 => "sha256",
+        }
+    }
+}
+
+/// Hex digest of everything `reader` yields
+pub fn checksum_reader<R: Read>(mut reader: R, algorithm: ChecksumAlgorithm) -> io::Result<String> {
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    let digest = match algorithm {
+        ChecksumAlgorithm::Md5 => {
+            let mut hasher = Md5::new();
+            loop {
+                let n = reader.read(&mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..n]);
+            }
+            hasher.finalize().to_vec()
+        }
+        ChecksumAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            loop {
+                let n = reader.read(&mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..n]);
+            }
+            hasher.finalize().to_vec()
+        }
+    };
+    Ok(to_hex(&digest))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Streaming MD5 (RFC 1321)
+pub struct Md5 {
+    state: [u32; 4],
+    buffer: [u8; 64],
+    buffered: usize,
+    length: u64,
+}
+
+const MD5_SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const MD5_CONSTANTS: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Md5 {
+    pub fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            buffer: [0; 64],
+            buffered: 0,
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let take = (64 - self.buffered).min(data.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+            self.buffered += take;
+            data = &data[take..];
+            if self.buffered == 64 {
+                let block = self.buffer;
+                self.compress(&block);
+                self.buffered = 0;
+            }
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 16] {
+        let bit_length = self.length.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.buffered != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bit_length.to_le_bytes());
+
+        let mut digest = [0u8; 16];
+        for (chunk, word) in digest.chunks_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut m = [0u32; 16];
+        for (word, bytes) in m.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(MD5_CONSTANTS[i])
+                .wrapping_add(m[g])
+                .rotate_left(MD5_SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+
+        self.state[0] = self.state[0].wrapping_add(a);
+        self.state[1] = self.state[1].wrapping_add(b);
+        self.state[2] = self.state[2].wrapping_add(c);
+        self.state[3] = self.state[3].wrapping_add(d);
+    }
+}
+
+/// Streaming SHA-256 (FIPS 180-4)
+pub struct Sha256 {
+    state: [u32; 8],
+    buffer: [u8; 64],
+    buffered: usize,
+    length: u64,
+}
+
+const SHA256_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha256 {
+    pub fn new() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            buffer: [0; 64],
+            buffered: 0,
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let take = (64 - self.buffered).min(data.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+            self.buffered += take;
+            data = &data[take..];
+            if self.buffered == 64 {
+                let block = self.buffer;
+                self.compress(&block);
+                self.buffered = 0;
+            }
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 32] {
+        let bit_length = self.length.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.buffered != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bit_length.to_be_bytes());
+
+        let mut digest = [0u8; 32];
+        for (chunk, word) in digest.chunks_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (word, bytes) in w.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_CONSTANTS[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct CacheKey {
+    path: PathBuf,
+    algorithm: ChecksumAlgorithm,
+    size: u64,
+    modified_nanos: u128,
+}
+
+/// Recently computed digests, most recently used first
+pub struct ChecksumCache {
+    entries: Mutex<VecDeque<(CacheKey, String)>>,
+    capacity: usize,
+}
+
+impl ChecksumCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Process-wide cache used by the checksum route
+    pub fn global() -> &'static ChecksumCache {
+        static CACHE: OnceLock<ChecksumCache> = OnceLock::new();
+        CACHE.get_or_init(|| ChecksumCache::new(CACHE_CAPACITY))
+    }
+
+    /// Digest of the file at `path`, and whether it came from the cache
+    pub fn checksum(
+        &self,
+        path: &Path,
+        algorithm: ChecksumAlgorithm,
+    ) -> io::Result<(String, bool)> {
+        let metadata = std::fs::metadata(path)?;
+        let key = CacheKey {
+            path: path.to_path_buf(),
+            algorithm,
+            size: metadata.len(),
+            modified_nanos: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_nanos()),
+        };
+
+        if let Some(digest) = self.lookup(&key) {
+            return Ok((digest, true));
+        }
+        let digest = checksum_reader(File::open(path)?, algorithm)?;
+        self.insert(key, digest.clone());
+        Ok((digest, false))
+    }
+
+    fn lookup(&self, key: &CacheKey) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let index = entries.iter().position(|(k, _)| k == key)?;
+        let entry = entries.remove(index)?;
+        let digest = entry.1.clone();
+        entries.push_front(entry);
+        Some(digest)
+    }
+
+    fn insert(&self, key: CacheKey, digest: String) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        // A changed file leaves a stale entry under its old key; drop it early
+        entries.retain(|(k, _)| !(k.path == key.path && k.algorithm == key.algorithm));
+        entries.push_front((key, digest));
+        entries.truncate(self.capacity);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Serve the checksum of a file inside `base_dir`
+pub fn handle_checksum_request(
+    request: &Request,
+    cli: &Cli,
+    base_dir: &Path,
+) -> Result<Response, AppError> {
+    let params = request.query_params();
+    let Some(relative) = params.get("path").filter(|p| !p.is_empty()) else {
+        debug!("Checksum request without a path");
+        return Err(AppError::BadRequest);
+    };
+    let algorithm = match params.get("algo") {
+        Some(value) => ChecksumAlgorithm::parse(value).ok_or_else(|| {
+            debug!("Unknown checksum algorithm: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => ChecksumAlgorithm::default(),
+    };
+
+    let file_path = crate::webdav::resolve_request_path(base_dir, relative)?;
+    if !file_path.is_file()
+        || crate::fs::ignore_rules()
+            .excludes(file_path.strip_prefix(base_dir).unwrap_or(&file_path))
+    {
+        return Err(AppError::NotFound);
+    }
+    if !cli
+        .allowed_extension_patterns()?
+        .iter()
+        .any(|p| p.matches_path(&file_path))
+    {
+        debug!(
+            "Checksum rejected, extension not allowed: {}",
+            file_path.display()
+        );
+        return Err(AppError::Forbidden);
+    }
+
+    let started = Instant::now();
+    let (digest, cached) = ChecksumCache::global().checksum(&file_path, algorithm)?;
+    debug!(
+        "{} of {} ({}) in {:?}",
+        algorithm.as_str(),
+        file_path.display(),
+        if cached { "cached" } else { "computed" },
+        started.elapsed()
+    );
+
+    let display_path = format!("/{}", relative.trim_start_matches('/'));
+    let file_name = display_path.rsplit('/').next().unwrap_or_default();
+    let (content_type, body) = if params.get("format").is_some_and(|f| f == "text") {
+        (
+            "text/plain; charset=utf-8",
+            format!("{digest}  {file_name}\n"),
+        )
+    } else {
+        let size = std::fs::metadata(&file_path).map_or(0, |m| m.len());
+        (
+            "application/json",
+            format!(
+                r#"{{"path":"{}","algorithm":"{}","checksum":"{}","size":{},"cached":{}}}"#,
+                json_escape(&display_path),
+                algorithm.as_str(),
+                digest,
+                size,
+                cached
+            ),
+        )
+    };
+
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), content_type.to_string());
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(body),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(algorithm: ChecksumAlgorithm, data: &[u8]) -> String {
+        checksum_reader(data, algorithm).unwrap()
+    }
+
+    #[test]
+    fn test_known_digests() {
+        use ChecksumAlgorithm::{Md5, Sha256};
+        assert_eq!(hex(Md5, b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(Md5, b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            hex(
+                Md5,
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+        assert_eq!(
+            hex(Sha256, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(Sha256, b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(
+                Sha256,
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            ),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        // Spans many read buffers
+        assert_eq!(
+            hex(Sha256, &vec![b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn test_cache_hits_until_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "abc").unwrap();
+        let cache = ChecksumCache::new(2);
+
+        let (first, cached) = cache.checksum(&path, ChecksumAlgorithm::Md5).unwrap();
+        assert_eq!(first, "900150983cd24fb0d6963f7d28e17f72");
+        assert!(!cached);
+        assert!(cache.checksum(&path, ChecksumAlgorithm::Md5).unwrap().1);
+
+        std::fs::write(&path, "abcd").unwrap();
+        let (second, cached) = cache.checksum(&path, ChecksumAlgorithm::Md5).unwrap();
+        assert!(!cached);
+        assert_ne!(first, second);
+        assert_eq!(cache.len(), 1);
+
+        let other = dir.path().join("b.txt");
+        let third = dir.path().join("c.txt");
+        std::fs::write(&other, "b").unwrap();
+        std::fs::write(&third, "c").unwrap();
+        cache.checksum(&other, ChecksumAlgorithm::Md5).unwrap();
+        cache.checksum(&third, ChecksumAlgorithm::Md5).unwrap();
+        assert_eq!(cache.len(), 2);
+        // The least recently used entry (a.txt) was evicted
+        assert!(!cache.checksum(&path, ChecksumAlgorithm::Md5).unwrap().1);
+    }
+}
@@ -12,7 +12,8 @@ use std::path::PathBuf;
      author = "Harshit Jain",
      version = crate::VERSION, //  Version of our IronDrop - feels like we're shipping software! 🚢
      long_about = "This is a simple configurable download server that serves files from a directory with sophisticated error reporting and handling.\n It can be used to share files with others or to download files from a remote server.\n The server can be configured to serve only specific file extensions and can be run on a specific host and port.\n If the requested path is a directory, the server will generate an HTML page with a list of files and subdirectories in the directory.\n The server will respond with detailed error logs for various scenarios, enhancing operational visibility.\n The server can be configured to serve only specific file extensions and can be run on a specific host and port.\n The server will respond with a 403 Forbidden error if the requested file extension is not allowed.\n The server will respond with a 404 Not Found error if the requested file or directory does not exist.\n The server will respond with a 400 Bad Request error if the request is invalid.\n Follow & conribute with devlopment efforts at: git.harsh1998.dev \n Author: Harshit Jain, UI Design by: Sonu Kr. Saw\n",
-     about = "A simple configurable download server with sophisticated error reporting." // Short description for `irondrop --help`.
+     about = "A simple configurable download server with sophisticated error reporting.", // Short description for `irondrop --help`.
+     after_help = "Run `irondrop hash-password` to create a password hash for --password-hash / [auth] password_hash.\nRun `irondrop send <files> --to http://host:port/folder` to upload files to another IronDrop server.\nRun `irondrop serve --once <path>` to share one file or folder until it has been downloaded."
  )]
 pub struct Cli {
     /// Directory path to serve, mandatory -  This is the *only* required argument. 📂
@@ -31,7 +32,7 @@ pub struct Cli {
     #[arg(short, long)]
     pub allowed_extensions: Option<String>,
 
-    /// Number of threads in the thread pool -  More threads = handle more downloads at once, up to a point. 🧵🧵🧵
+    /// Number of Tokio worker threads (default: 8) -  Sockets are async, so a few threads serve thousands of slow clients; blocking file work gets a separate pool. 🧵🧵🧵
     #[arg(short, long)]
     pub threads: Option<usize>,
 
@@ -56,6 +57,82 @@ pub struct Cli {
     #[arg(long)]
     pub password: Option<String>,
 
+    /// Argon2 or bcrypt hash of the basic authentication password, used instead of `--password` (create one with `irondrop hash-password`).
+    #[arg(long, value_parser = validate_password_hash)]
+    pub password_hash: Option<String>,
+
+    /// Failed logins from one IP before it is locked out (0 disables the lockout, default: 10).
+    #[arg(long)]
+    pub auth_max_failures: Option<u32>,
+
+    /// Seconds an IP stays locked out after too many failed logins; failures older than this are forgotten (default: 300).
+    #[arg(long, value_parser = validate_timeout_secs)]
+    pub auth_lockout_secs: Option<u64>,
+
+    /// Log browsers in through a login page and a signed session cookie instead of the Basic Auth dialog. Basic Auth and API tokens keep working for scripts (default: false).
+    #[arg(long)]
+    pub auth_sessions: Option<bool>,
+
+    /// Seconds a login session lasts (default: 86400).
+    #[arg(long, value_parser = validate_timeout_secs)]
+    pub session_lifetime: Option<u64>,
+
+    /// Secret used to sign session cookies; unset uses a random one, so sessions end when the server restarts. Prefer `[auth] session_secret` in the config file.
+    #[arg(long)]
+    pub session_secret: Option<String>,
+
+    /// API tokens for scripted clients, comma-separated `label:token` pairs. Sent as `Authorization: Bearer <token>` or `X-API-Key: <token>`. Prefer `[auth] tokens` in the config file so tokens do not show up in process listings.
+    #[arg(long)]
+    pub auth_tokens: Option<String>,
+
+    /// Directories with their own Basic Auth credentials, comma-separated `/prefix=user:password` entries (e.g. "/private=alice:secret"). Prefer `[auth.paths]` in the config file.
+    #[arg(long)]
+    pub auth_paths: Option<String>,
+
+    /// Extra accounts with roles, comma-separated `name=role:password` entries where role is read-only, upload or admin and the password may be an Argon2/bcrypt hash (e.g. "bob=upload:secret"). Prefer `[users]` in the config file.
+    #[arg(long)]
+    pub users: Option<String>,
+
+    /// Only accept connections from these IPs/CIDR ranges (comma-separated, e.g. "192.168.1.0/24,10.0.0.5").
+    #[arg(long)]
+    pub allow_ips: Option<String>,
+
+    /// Reject connections from these IPs/CIDR ranges (comma-separated). Deny rules win over allow rules.
+    #[arg(long)]
+    pub deny_ips: Option<String>,
+
+    /// Reverse proxies whose X-Forwarded-For / Forwarded headers name the real client (comma-separated IPs/CIDR ranges, e.g. "127.0.0.1,10.0.0.0/8").
+    #[arg(long)]
+    pub trusted_proxies: Option<String>,
+
+    /// Log connections rejected by the IP allow/deny lists.
+    #[arg(long)]
+    pub log_rejected_ips: Option<bool>,
+
+    /// Hide dotfiles from listings and search and answer 404 for them (default: true).
+    #[arg(long)]
+    pub hide_dotfiles: Option<bool>,
+
+    /// Further paths to hide everywhere, as comma-separated globs, e.g. ".git/**,*.key". Patterns without '/' match any file or folder name.
+    #[arg(long)]
+    pub ignore_patterns: Option<String>,
+
+    /// Symlinks inside the served directory: "never" (hidden, 404), "safe" (only links resolving inside the directory) or "always" (default).
+    #[arg(long, value_parser = validate_follow_symlinks)]
+    pub follow_symlinks: Option<String>,
+
+    /// Extra or replacement MIME types as comma-separated `ext=type` pairs, e.g. "md=text/markdown; charset=utf-8,log=text/plain".
+    #[arg(long, value_parser = validate_mime_types)]
+    pub mime_types: Option<String>,
+
+    /// Charset added to text types that do not name one (default: utf-8; "none" to omit).
+    #[arg(long)]
+    pub default_charset: Option<String>,
+
+    /// Extensions always sent with `Content-Disposition: attachment`, e.g. "html,svg". Defaults to html,htm,xhtml,svg when uploads are enabled; "" to disable.
+    #[arg(long)]
+    pub force_download: Option<String>,
+
     /// Enable direct streaming upload functionality - Allows clients to upload files using efficient direct disk streaming. Upload endpoint available at /_irondrop/upload. 📤
     #[arg(long)]
     pub enable_upload: Option<bool>,
@@ -64,14 +141,130 @@ pub struct Cli {
     #[arg(long, value_parser = validate_upload_size)]
     pub max_upload_size: Option<u64>,
 
+    /// Command run on every finished upload before it is moved into place, e.g. "clamdscan --no-summary %f". `%f` is replaced by the file path; a non-zero exit rejects the upload with 422.
+    #[arg(long)]
+    pub upload_scan_command: Option<String>,
+
+    /// Sort uploads into subfolders: "flat" (default), "date" (YYYY/MM/DD/) or "type" (per file extension).
+    #[arg(long, value_parser = validate_upload_organize)]
+    pub upload_organize: Option<String>,
+
+    /// What to do when an upload's name is taken: "rename" (default, name_1.ext), "overwrite", "reject" (409) or "version" (name.v2.ext). Clients may pick one per upload with X-Conflict-Strategy.
+    #[arg(long, value_parser = validate_upload_conflict)]
+    pub upload_conflict: Option<String>,
+
+    /// Free space to keep on the upload filesystem, e.g. "1GB" (default: 0). Uploads that would eat into it are refused with 507 before their body is read.
+    #[arg(long, value_parser = validate_disk_reserve)]
+    pub upload_disk_reserve: Option<u64>,
+
+    /// Store an upload identical (by SHA-256) to a file already in the upload directory as a hard link to it instead of a second copy (default: false).
+    #[arg(long)]
+    pub upload_dedup: Option<bool>,
+
+    /// Daily upload quota per client (IP or authenticated user), e.g. "2GB". Exceeding it returns 429 until 00:00 UTC.
+    #[arg(long, value_parser = validate_quota_size)]
+    pub upload_quota_daily: Option<u64>,
+
+    /// Total upload quota per client (IP or authenticated user), e.g. "20GB". Exceeding it returns 507.
+    #[arg(long, value_parser = validate_quota_size)]
+    pub upload_quota_total: Option<u64>,
+
     /// Enable WebDAV methods (`OPTIONS`,`PROPFIND`,`PROPPATCH`,`MKCOL`,`PUT`,`DELETE`,`COPY`,`MOVE`,`LOCK`,`UNLOCK`).
     #[arg(long)]
     pub enable_webdav: Option<bool>,
 
+    /// Allow deleting, renaming and moving files from the web UI via `/_irondrop/files`. Protect it with authentication.
+    #[arg(long)]
+    pub enable_file_management: Option<bool>,
+
+    /// Allow editing small text files in the browser via `/_edit/<path>`. Only takes effect with a username/password, tokens or `[users]` accounts.
+    #[arg(long)]
+    pub enable_edit: Option<bool>,
+
+    /// Static site mode: a directory request serves its `index.html` (404 without one) instead of a listing.
+    #[arg(long)]
+    pub index: Option<bool>,
+
+    /// Single-page app mode: like --index, and browser requests for missing paths get the root `index.html`.
+    #[arg(long)]
+    pub spa: Option<bool>,
+
+    /// Redirects for missing paths as comma-separated `from to [status]` rules, e.g. "/blog/* /posts/:splat 301"; the status defaults to 301. Prefer `[redirects]` in the config file.
+    #[arg(long, value_parser = validate_redirects)]
+    pub redirects: Option<String>,
+
+    /// `_redirects` file with one `from to [status]` rule per line, checked after --redirects.
+    #[arg(long)]
+    pub redirects_file: Option<PathBuf>,
+
+    /// Page inside the served directory sent with status 404 for missing paths, e.g. "404.html".
+    #[arg(long)]
+    pub not_found_page: Option<String>,
+
+    /// Refuse uploads, deletions and every other change to the served directory. With authentication it can be switched at runtime via `/_irondrop/read-only`.
+    #[arg(long)]
+    pub read_only: Option<bool>,
+
+    /// Index the contents of small text files so `/_irondrop/search?scope=content` can match words inside them.
+    #[arg(long)]
+    pub search_content: Option<bool>,
+
+    /// Comma-separated file extensions indexed by `--search-content` (default: common text and source formats).
+    #[arg(long)]
+    pub search_content_extensions: Option<String>,
+
+    /// Largest file indexed by `--search-content`, e.g. "256KB" (default: 256KB).
+    #[arg(long, value_parser = validate_content_size)]
+    pub search_content_max_size: Option<u64>,
+
+    /// Keep exact file sizes in the search index (8 more bytes per entry) instead of rounding them to a power of two (default: false).
+    #[arg(long)]
+    pub search_exact_sizes: Option<bool>,
+
+    /// Memory allowed for the trigram index that speeds up substring search, e.g. "64MB"; it is skipped when larger, and 0 disables it (default: 32MB).
+    #[arg(long, value_parser = validate_trigram_budget)]
+    pub search_trigram_budget: Option<u64>,
+
+    /// Keep paths out of the search index as comma-separated globs, e.g. "node_modules,.git,*.iso"; they can still be browsed. Patterns follow the --ignore-patterns rules.
+    #[arg(long)]
+    pub search_exclude: Option<String>,
+
+    /// Folder levels indexed for search below the served directory (default: 25).
+    #[arg(long, value_parser = validate_search_max_depth)]
+    pub search_max_depth: Option<usize>,
+
+    /// Folder levels indexed inside particular folders, e.g. "media:1,archive/old:2"; the most specific folder wins over --search-max-depth.
+    #[arg(long, value_parser = validate_depth_limits)]
+    pub search_depth_limits: Option<String>,
+
     /// Disable request rate limiting. Effective only when WebDAV is enabled.
     #[arg(long)]
     pub disable_rate_limit: Option<bool>,
 
+    /// Connections accepted per client IP per minute (default: 120, or 3500 with WebDAV).
+    #[arg(long, value_parser = validate_rate_limit)]
+    pub rate_limit: Option<u32>,
+
+    /// Simultaneous connections per client IP (default: 10, or 128 with WebDAV).
+    #[arg(long, value_parser = validate_rate_limit)]
+    pub rate_limit_concurrent: Option<u32>,
+
+    /// Upload requests per client IP per minute, on top of the global limit (default: unlimited).
+    #[arg(long, value_parser = validate_rate_limit)]
+    pub rate_limit_upload: Option<u32>,
+
+    /// Search requests per client IP per minute, on top of the global limit (default: unlimited).
+    #[arg(long, value_parser = validate_rate_limit)]
+    pub rate_limit_search: Option<u32>,
+
+    /// Load and validate the configuration (command line and config file), print the effective settings with secrets masked, then exit: 0 when the server would start, 1 when it would not. Nothing is served.
+    #[arg(long)]
+    pub check_config: bool,
+
+    /// Install, uninstall or run IronDrop as a background service: "install" registers a systemd unit (Linux), launchd agent (macOS) or Windows service with the other options given here, "uninstall" removes it, and "run" is what the Windows service manager starts.
+    #[arg(long, value_parser = validate_service)]
+    pub service: Option<String>,
+
     /// Configuration file path - Specify a custom configuration file (INI format). If not provided, looks for irondrop.ini in current directory or ~/.config/irondrop/config.ini 🛠️
     #[arg(long, value_parser = validate_config_file)]
     pub config_file: Option<String>,
@@ -80,18 +273,443 @@ pub struct Cli {
     #[arg(long, value_parser = validate_log_dir)]
     pub log_dir: Option<PathBuf>,
 
+    /// Rotate the log file once it reaches this size, e.g. "50MB" (requires --log-dir).
+    #[arg(long, value_parser = validate_log_rotate_size)]
+    pub log_rotate_size: Option<u64>,
+
+    /// Rotate the log file once it is this old, e.g. "12h" or "7d" (requires --log-dir).
+    #[arg(long, value_parser = validate_log_rotate_age)]
+    pub log_rotate_age: Option<u64>,
+
+    /// Number of rotated log files to keep (default: 5).
+    #[arg(long)]
+    pub log_rotate_keep: Option<usize>,
+
+    /// Compress rotated log files with gzip (default: false).
+    #[arg(long)]
+    pub log_rotate_gzip: Option<bool>,
+
+    /// Append security events (logins, failed logins, uploads, deletions, config reloads, share links, rate-limit bans) to a JSON lines audit log (default: false).
+    #[arg(long)]
+    pub audit: Option<bool>,
+
+    /// Audit log file (default: irondrop_audit.jsonl in the log directory, or the working directory without one).
+    #[arg(long)]
+    pub audit_file: Option<PathBuf>,
+
     /// Path to SSL/TLS certificate file (PEM format) for HTTPS support
-    #[arg(long, value_parser = validate_ssl_file)]
+    #[arg(long, alias = "tls-cert", value_parser = validate_ssl_file)]
     pub ssl_cert: Option<PathBuf>,
 
     /// Path to SSL/TLS private key file (PEM format) for HTTPS support
-    #[arg(long, value_parser = validate_ssl_file)]
+    #[arg(long, alias = "tls-key", value_parser = validate_ssl_file)]
     pub ssl_key: Option<PathBuf>,
 
+    /// Offer HTTP/2 to TLS clients via ALPN (default: false).
+    /// Requires HTTPS and a build with the `http2` feature.
+    #[arg(long)]
+    pub http2: Option<bool>,
+
     /// Base URL path prefix for reverse proxy sub-path deployments (e.g., "/webstorage").
     /// When set, all generated URLs are prefixed and incoming requests must start with this path.
     #[arg(long, value_parser = validate_base_path)]
     pub base_path: Option<String>,
+
+    /// Directory with custom error page templates named after the status code
+    /// (e.g. "404.html", "403.html", "500.html"); missing pages use the built-in ones.
+    #[arg(long, value_parser = validate_template_dir)]
+    pub template_dir: Option<PathBuf>,
+
+    /// Default web UI theme: "dark", "light" or "auto" (follow the browser). Visitors can switch with the header toggle (default: dark).
+    #[arg(long, value_parser = validate_theme)]
+    pub theme: Option<String>,
+
+    /// Extra stylesheet loaded after the built-in styles on every page, for branding.
+    #[arg(long, value_parser = validate_custom_css)]
+    pub custom_css: Option<PathBuf>,
+
+    /// Enable gzip/deflate compression of text responses (HTML, CSS, JS, JSON) when the client sends Accept-Encoding. Enabled by default.
+    #[arg(long)]
+    pub compression: Option<bool>,
+
+    /// Minimum response body size in bytes before compression is applied (default: 1024).
+    #[arg(long)]
+    pub compression_min_size: Option<usize>,
+
+    /// Seconds to wait for each read from a client before the request times out with 408 (default: 30).
+    #[arg(long, value_parser = validate_timeout_secs)]
+    pub read_timeout: Option<u64>,
+
+    /// Seconds a single write to a client may take before the connection is dropped (default: 60).
+    #[arg(long, value_parser = validate_timeout_secs)]
+    pub write_timeout: Option<u64>,
+
+    /// Maximum seconds a client may take to send the complete request headers (default: 10).
+    /// Protects against slow-loris clients that trickle header bytes.
+    #[arg(long, value_parser = validate_timeout_secs)]
+    pub header_timeout: Option<u64>,
+
+    /// Largest body accepted for requests other than uploads, e.g. "4MB" (default: 2MB).
+    /// Larger bodies are refused with 413 before they are read; uploads use --max-upload-size.
+    #[arg(long, value_parser = validate_request_body)]
+    pub max_request_body: Option<u64>,
+
+    /// Total download bandwidth shared by all clients, e.g. "10MB/s" (default: unlimited).
+    #[arg(long, value_parser = validate_bandwidth)]
+    pub max_bandwidth: Option<u64>,
+
+    /// Download bandwidth for each connection, e.g. "2MB/s" (default: unlimited).
+    #[arg(long, value_parser = validate_bandwidth)]
+    pub per_conn_bandwidth: Option<u64>,
+
+    /// Total upload bandwidth shared by all request bodies, e.g. "50MB/s" (default: unlimited).
+    #[arg(long, value_parser = validate_bandwidth)]
+    pub max_upload_bandwidth: Option<u64>,
+
+    /// Upload bandwidth for each request body, e.g. "5MB/s" (default: unlimited).
+    #[arg(long, value_parser = validate_bandwidth)]
+    pub per_upload_bandwidth: Option<u64>,
+
+    /// Maximum simultaneous downloads of files of 1MB or more (default: unlimited).
+    /// Further requests wait in the download queue or get 503 with Retry-After.
+    #[arg(long, value_parser = validate_download_slots)]
+    pub max_concurrent_downloads: Option<usize>,
+
+    /// Large-download requests allowed to wait up to 30s for a free slot (default: 0, reject at once).
+    #[arg(long)]
+    pub download_queue: Option<usize>,
+
+    /// Maximum file requests handled at once on the blocking pool (default: unlimited).
+    /// Further requests wait in the worker queue or get 503 with Retry-After.
+    #[arg(long, value_parser = validate_max_workers)]
+    pub max_workers: Option<usize>,
+
+    /// File requests allowed to wait up to 10s for a free worker (default: 256).
+    #[arg(long)]
+    pub worker_queue: Option<usize>,
+
+    /// Keep hot files memory-mapped, using at most this much in total, e.g. "256MB" (default: disabled).
+    /// 64-bit Unix only; files must not be truncated in place while served.
+    #[arg(long, value_parser = validate_mmap_size)]
+    pub mmap_cache_size: Option<u64>,
+
+    /// Largest file kept in the mmap cache, e.g. "8MB" (default: 8MB).
+    #[arg(long, value_parser = validate_mmap_size)]
+    pub mmap_max_file: Option<u64>,
+
+    /// Keep small files in memory, using at most this much in total, e.g. "32MB" (default: disabled).
+    #[arg(long, value_parser = validate_mmap_size)]
+    pub file_cache_size: Option<u64>,
+
+    /// Largest file kept in the file cache, e.g. "128KB" (default: 64KB).
+    #[arg(long, value_parser = validate_mmap_size)]
+    pub file_cache_max_file: Option<u64>,
+
+    /// Accept backlog of the listening socket (default: 1024).
+    #[arg(long, value_parser = validate_tcp_backlog)]
+    pub tcp_backlog: Option<u32>,
+
+    /// Set TCP_NODELAY on accepted connections (default: false).
+    #[arg(long)]
+    pub tcp_nodelay: Option<bool>,
+
+    /// Set SO_REUSEADDR on the listening socket (default: true on Unix).
+    #[arg(long)]
+    pub tcp_reuse_address: Option<bool>,
+
+    /// Set SO_REUSEPORT so several processes can share the port (default: false, Unix only).
+    #[arg(long)]
+    pub tcp_reuse_port: Option<bool>,
+
+    /// Send TCP keepalive probes after this many idle seconds (default: off).
+    #[arg(long, value_parser = validate_timeout_secs)]
+    pub tcp_keepalive: Option<u64>,
+
+    /// Seconds between TCP keepalive probes (default: system setting).
+    #[arg(long, value_parser = validate_timeout_secs)]
+    pub tcp_keepalive_interval: Option<u64>,
+
+    /// Unanswered keepalive probes before a connection is dropped (default: system setting).
+    #[arg(long, value_parser = validate_keepalive_retries)]
+    pub tcp_keepalive_retries: Option<u32>,
+
+    /// Connections taken from the backlog per accept-loop wake-up (default: 1).
+    #[arg(long, value_parser = validate_accept_batch)]
+    pub accept_batch: Option<usize>,
+
+    /// Advertise the server on the local network via mDNS/Bonjour as "IronDrop on <hostname>" (default: false).
+    #[arg(long)]
+    pub mdns: Option<bool>,
+
+    /// Comma-separated origins allowed to call the JSON APIs from a browser, or "*" (default: none).
+    /// Example: "https://app.example.com,http://localhost:3000"
+    #[arg(long)]
+    pub cors_origins: Option<String>,
+
+    /// Methods allowed in CORS preflight responses (default: "GET, POST, PUT, DELETE, OPTIONS").
+    #[arg(long)]
+    pub cors_methods: Option<String>,
+
+    /// Request headers allowed in CORS preflight responses, or "*" to allow any
+    /// (default: "Authorization, Content-Type, X-API-Key, X-Filename, X-Upload-Length").
+    #[arg(long)]
+    pub cors_headers: Option<String>,
+
+    /// Seconds browsers may cache a CORS preflight response (default: 600).
+    #[arg(long)]
+    pub cors_max_age: Option<u64>,
+
+    /// Allow cross-origin requests to send credentials (cookies, Authorization); needs explicit --cors-origins (default: false).
+    #[arg(long)]
+    pub cors_credentials: Option<bool>,
+
+    /// Add security headers (CSP, X-Content-Type-Options, X-Frame-Options, Referrer-Policy,
+    /// and HSTS with TLS) to every response (default: true).
+    #[arg(long)]
+    pub security_headers: Option<bool>,
+
+    /// Content-Security-Policy value, or "off" to omit it (default: a policy fitting the built-in pages).
+    #[arg(long)]
+    pub content_security_policy: Option<String>,
+
+    /// X-Frame-Options value, e.g. DENY, or "off" to omit it (default: SAMEORIGIN).
+    #[arg(long)]
+    pub frame_options: Option<String>,
+
+    /// Referrer-Policy value, or "off" to omit it (default: same-origin).
+    #[arg(long)]
+    pub referrer_policy: Option<String>,
+
+    /// Strict-Transport-Security max-age in seconds when TLS is enabled; 0 omits it (default: 31536000).
+    #[arg(long)]
+    pub hsts_max_age: Option<u64>,
+
+    /// Comma-separated http:// URLs that receive a JSON POST for server events (default: none).
+    #[arg(long)]
+    pub webhook_urls: Option<String>,
+
+    /// Events sent to the webhooks: upload.completed, file.deleted, auth.failure,
+    /// rate_limit.triggered, or "all" (default: all).
+    #[arg(long)]
+    pub webhook_events: Option<String>,
+
+    /// Delivery retries after a failed webhook POST (default: 3).
+    #[arg(long)]
+    pub webhook_retries: Option<u32>,
+
+    /// Events that may wait for delivery before new ones are dropped (default: 256).
+    #[arg(long)]
+    pub webhook_queue_size: Option<usize>,
+
+    /// Secret used to sign expiring share links from /_api/share; unset disables share links.
+    #[arg(long)]
+    pub share_secret: Option<String>,
+
+    /// Longest lifetime in seconds a share link may be issued for (default: 604800, 7 days).
+    #[arg(long)]
+    pub share_max_ttl: Option<u64>,
+
+    /// Set by `irondrop serve --once`: only the shared item is served
+    #[arg(skip)]
+    pub quick_share: Option<std::sync::Arc<crate::quick_share::QuickShare>>,
+}
+
+/// Validate the concurrent download cap (at least one slot)
+fn validate_download_slots(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("Concurrent downloads must be at least 1".to_string()),
+        Ok(slots) => Ok(slots),
+        Err(_) => Err(format!("Invalid download slot count: '{s}'")),
+    }
+}
+
+fn validate_max_workers(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("Max workers must be at least 1".to_string()),
+        Ok(workers) => Ok(workers),
+        Err(_) => Err(format!("Invalid worker count: '{s}'")),
+    }
+}
+
+fn validate_tcp_backlog(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(0) => Err("TCP backlog must be at least 1".to_string()),
+        Ok(backlog) => Ok(backlog),
+        Err(_) => Err(format!("Invalid TCP backlog: '{s}'")),
+    }
+}
+
+fn validate_keepalive_retries(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(0) => Err("Keepalive retries must be at least 1".to_string()),
+        Ok(retries) => Ok(retries),
+        Err(_) => Err(format!("Invalid keepalive retry count: '{s}'")),
+    }
+}
+
+fn validate_accept_batch(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("Accept batch size must be at least 1".to_string()),
+        Ok(batch) => Ok(batch),
+        Err(_) => Err(format!("Invalid accept batch size: '{s}'")),
+    }
+}
+
+/// Validate a per-IP rate limit value (at least 1)
+fn validate_rate_limit(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(0) => Err("Rate limits must be at least 1".to_string()),
+        Ok(limit) => Ok(limit),
+        Err(_) => Err(format!("Invalid rate limit: '{s}'")),
+    }
+}
+
+fn validate_password_hash(s: &str) -> Result<String, String> {
+    crate::password::PasswordHash::parse(s)
+        .map(|_| s.trim().to_string())
+        .map_err(|e| format!("Invalid password hash: {e}"))
+}
+
+fn validate_service(s: &str) -> Result<String, String> {
+    crate::service::ServiceAction::parse(s)
+        .map(|action| action.as_str().to_string())
+        .ok_or_else(|| format!("Invalid service action: '{s}' (use install, uninstall or run)"))
+}
+
+/// Validate a rate such as "10MB/s" or "512KB/s" (plain numbers are bytes per second)
+fn validate_bandwidth(s: &str) -> Result<u64, String> {
+    crate::bandwidth::parse_bandwidth(s)
+        .ok_or_else(|| format!("Invalid bandwidth: '{s}' (use e.g. 512KB/s or 10MB/s)"))
+}
+
+/// Validate a timeout in whole seconds
+fn validate_timeout_secs(s: &str) -> Result<u64, String> {
+    match s.parse::<u64>() {
+        Ok(0) => Err("Timeout must be at least 1 second".to_string()),
+        Ok(secs) => Ok(secs),
+        Err(_) => Err(format!("Invalid timeout: '{s}' (use a number of seconds)")),
+    }
+}
+
+/// Validate a quota size such as "500MB" or "2GB" (plain numbers are bytes)
+fn validate_quota_size(s: &str) -> Result<u64, String> {
+    match crate::config::ini_parser::parse_file_size(s) {
+        Some(0) => Err("Quota must be greater than zero".to_string()),
+        Some(bytes) => Ok(bytes),
+        None => Err(format!("Invalid quota size: '{s}' (use e.g. 500MB or 2GB)")),
+    }
+}
+
+/// Validate the free-space margin such as "1GB" (plain numbers are bytes)
+fn validate_disk_reserve(s: &str) -> Result<u64, String> {
+    crate::config::ini_parser::parse_file_size(s)
+        .ok_or_else(|| format!("Invalid disk reserve: '{s}' (use e.g. 500MB or 2GB)"))
+}
+
+/// Validate the request body limit such as "4MB" (plain numbers are bytes)
+fn validate_request_body(s: &str) -> Result<u64, String> {
+    match crate::config::ini_parser::parse_file_size(s) {
+        Some(size) if size > 0 => Ok(size),
+        _ => Err(format!(
+            "Invalid request body limit: '{s}' (use e.g. 512KB or 4MB)"
+        )),
+    }
+}
+
+/// Validate the trigram index budget such as "64MB" (plain numbers are bytes)
+fn validate_trigram_budget(s: &str) -> Result<u64, String> {
+    crate::config::ini_parser::parse_file_size(s).ok_or_else(|| {
+        format!("Invalid trigram index budget: '{s}' (use e.g. 64MB, or 0 to disable)")
+    })
+}
+
+/// Validate the search index depth (at least one level)
+fn validate_search_max_depth(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("Search index depth must be at least 1".to_string()),
+        Ok(depth) => Ok(depth),
+        Err(_) => Err(format!("Invalid search index depth: '{s}'")),
+    }
+}
+
+/// Validate per-folder depth limits such as "media:1,archive/old:2"
+fn validate_depth_limits(s: &str) -> Result<String, String> {
+    crate::search::parse_depth_limits(s)?;
+    Ok(s.to_string())
+}
+
+/// Validate the content search size limit such as "256KB" (plain numbers are bytes)
+fn validate_content_size(s: &str) -> Result<u64, String> {
+    match crate::config::ini_parser::parse_file_size(s) {
+        Some(0) => Err("Content search size limit must be greater than zero".to_string()),
+        Some(bytes) => Ok(bytes),
+        None => Err(format!("Invalid size: '{s}' (use e.g. 64KB or 1MB)")),
+    }
+}
+
+/// Validate a log rotation size such as "50MB" (plain numbers are bytes)
+fn validate_log_rotate_size(s: &str) -> Result<u64, String> {
+    match crate::config::ini_parser::parse_file_size(s) {
+        Some(0) => Err("Log rotation size must be greater than zero".to_string()),
+        Some(bytes) => Ok(bytes),
+        None => Err(format!("Invalid size: '{s}' (use e.g. 10MB or 1GB)")),
+    }
+}
+
+/// Validate a cache size such as "256MB" (0 disables the cache)
+fn validate_mmap_size(s: &str) -> Result<u64, String> {
+    crate::config::ini_parser::parse_file_size(s)
+        .ok_or_else(|| format!("Invalid size: '{s}' (use e.g. 8MB or 1GB)"))
+}
+
+/// Validate a log rotation age such as "12h" or "7d", returned in seconds
+fn validate_log_rotate_age(s: &str) -> Result<u64, String> {
+    crate::log_rotation::parse_rotate_age(s)
+        .map(|age| age.as_secs())
+        .ok_or_else(|| format!("Invalid age: '{s}' (use e.g. 30m, 12h or 7d)"))
+}
+
+fn validate_follow_symlinks(s: &str) -> Result<String, String> {
+    crate::fs::SymlinkPolicy::parse(s)
+        .map(|policy| policy.as_str().to_string())
+        .ok_or_else(|| format!("Invalid symlink policy: '{s}' (use never, safe or always)"))
+}
+
+fn validate_mime_types(s: &str) -> Result<String, String> {
+    for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.split_once('=') {
+            Some((ext, mime)) if !ext.trim().is_empty() && mime.contains('/') => {}
+            _ => {
+                return Err(format!(
+                    "Invalid MIME mapping: '{entry}' (use ext=type/subtype)"
+                ));
+            }
+        }
+    }
+    Ok(s.to_string())
+}
+
+fn validate_redirects(s: &str) -> Result<String, String> {
+    for rule in s.split(',').filter(|rule| !rule.trim().is_empty()) {
+        crate::redirects::Redirect::parse(rule).map_err(|e| format!("Invalid redirect: {e}"))?;
+    }
+    Ok(s.to_string())
+}
+
+fn validate_upload_organize(s: &str) -> Result<String, String> {
+    crate::upload::UploadOrganize::parse(s)
+        .map(|organize| organize.as_str().to_string())
+        .ok_or_else(|| format!("Invalid upload organization: '{s}' (use flat, date or type)"))
+}
+
+fn validate_upload_conflict(s: &str) -> Result<String, String> {
+    crate::upload::UploadConflict::parse(s)
+        .map(|conflict| conflict.as_str().to_string())
+        .ok_or_else(|| {
+            format!(
+                "Invalid upload conflict strategy: '{s}' (use rename, overwrite, reject or version)"
+            )
+        })
 }
 
 /// Validate upload size (minimum 1 MB, no upper limit for direct streaming)
@@ -157,6 +775,25 @@ impl Cli {
                 "Both --ssl-cert and --ssl-key must be provided together for HTTPS".to_string(),
             ));
         }
+        if self.http2.unwrap_or(false) {
+            if !cfg!(feature = "http2") {
+                return Err(AppError::InvalidConfiguration(
+                    "--http2 needs a build with the `http2` feature".to_string(),
+                ));
+            }
+            if self.ssl_cert.is_none() {
+                return Err(AppError::InvalidConfiguration(
+                    "--http2 requires HTTPS (--ssl-cert and --ssl-key)".to_string(),
+                ));
+            }
+        }
+
+        if self.password.is_some() && self.password_hash.is_some() {
+            return Err(AppError::InvalidConfiguration(
+                "Set either a password or a password hash for basic authentication, not both"
+                    .to_string(),
+            ));
+        }
 
         // Validate main serving directory
         if !self.directory.exists() {
@@ -181,6 +818,22 @@ impl Cli {
             .map_or(u64::MAX, |size| size * 1024 * 1024)
     }
 
+    /// Compile `--allowed-extensions` into glob patterns (defaults to `*`)
+    /// # Errors
+    ///
+    /// Returns an error if any pattern is not a valid glob.
+    pub fn allowed_extension_patterns(&self) -> Result<Vec<glob::Pattern>, AppError> {
+        self.allowed_extensions
+            .as_deref()
+            .unwrap_or("*")
+            .split(',')
+            .map(str::trim)
+            .filter(|ext| !ext.is_empty())
+            .map(glob::Pattern::new)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(AppError::from)
+    }
+
     /// Get the resolved upload directory, using OS defaults if not specified
     /// # Errors
     ///
@@ -237,6 +890,108 @@ mod tests {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         };
 
         // Test conversion
@@ -277,6 +1032,108 @@ mod tests {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         };
 
         assert!(cli.validate().is_ok());
@@ -332,6 +1189,40 @@ fn validate_log_dir(s: &str) -> Result<PathBuf, String> {
     }
 }
 
+/// Validate that the error template directory exists
+fn validate_template_dir(s: &str) -> Result<PathBuf, String> {
+    if s.is_empty() {
+        return Err("Template directory path cannot be empty".to_string());
+    }
+
+    let path = PathBuf::from(s);
+    if !path.is_dir() {
+        return Err(format!(
+            "Template directory does not exist or is not a directory: {}",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+fn validate_theme(s: &str) -> Result<String, String> {
+    crate::templates::Theme::parse(s)
+        .map(|theme| theme.as_str().to_string())
+        .ok_or_else(|| format!("Invalid theme: '{s}' (use dark, light or auto)"))
+}
+
+/// Validate that the custom stylesheet exists
+fn validate_custom_css(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    if !path.is_file() {
+        return Err(format!(
+            "Custom CSS file does not exist: {}",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
 /// Validate SSL certificate/key file path exists and is readable
 fn validate_ssl_file(s: &str) -> Result<PathBuf, String> {
     if s.is_empty() {
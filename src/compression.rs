@@ -0,0 +1,527 @@
+// SPDX-License-Identifier: MIT
+
+//! Response compression for text-heavy bodies
+//!
+//! Implements `Accept-Encoding` negotiation with gzip and deflate (zlib) output
+//! using a small built-in DEFLATE encoder (LZ77 with hash chains + fixed Huffman
+//! codes, RFC 1951). This keeps IronDrop dependency free while still cutting
+//! directory listings, search JSON and embedded templates by 60-80%.
+//!
+//! Only buffered bodies are compressed; streamed file downloads are sent as-is
+//! so `Content-Length`, range requests and zero-copy streaming stay intact.
+
+use crate::http::{Response, ResponseBody};
+use log::trace;
+
+/// Default minimum body size worth compressing
+pub const DEFAULT_MIN_COMPRESS_SIZE: usize = 1024;
+
+/// Runtime compression settings derived from CLI/INI configuration
+#[derive(Debug, Clone, Copy)]
+pub struct CompressionSettings {
+    pub enabled: bool,
+    pub min_size: usize,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_size: DEFAULT_MIN_COMPRESS_SIZE,
+        }
+    }
+}
+
+impl CompressionSettings {
+    pub fn from_cli(cli: Option<&crate::cli::Cli>) -> Self {
+        let defaults = Self::default();
+        match cli {
+            Some(cli) => Self {
+                enabled: cli.compression.unwrap_or(defaults.enabled),
+                min_size: cli.compression_min_size.unwrap_or(defaults.min_size),
+            },
+            None => defaults,
+        }
+    }
+}
+
+/// Content codings we can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// Pick the preferred supported coding from an `Accept-Encoding` header.
+/// Honors `q=0` exclusions; gzip wins ties because every client supports it.
+pub fn negotiate_encoding(accept_encoding: &str) -> Option<Encoding> {
+    let mut best: Option<(Encoding, f32)> = None;
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let coding = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+        let quality = parts
+            .find_map(|p| p.trim().strip_prefix("q="))
+            .and_then(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if quality <= 0.0 {
+            continue;
+        }
+        let encoding = match coding.as_str() {
+            "gzip" | "x-gzip" | "*" => Encoding::Gzip,
+            "deflate" => Encoding::Deflate,
+            _ => continue,
+        };
+        let better = match best {
+            None => true,
+            Some((current, q)) => {
+                quality > q || (quality == q && encoding == Encoding::Gzip && current != encoding)
+            }
+        };
+        if better {
+            best = Some((encoding, quality));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+/// Whether a Content-Type is worth compressing
+pub fn is_compressible(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || matches!(
+            mime.as_str(),
+            "application/json"
+                | "application/javascript"
+                | "application/xml"
+                | "application/xhtml+xml"
+                | "image/svg+xml"
+        )
+}
+
+/// Compress a buffered response in place when the client accepts it and it is worthwhile
+pub fn maybe_compress(
+    mut response: Response,
+    accept_encoding: Option<&str>,
+    settings: CompressionSettings,
+) -> Response {
+    if !settings.enabled {
+        return response;
+    }
+    let Some(encoding) = accept_encoding.and_then(negotiate_encoding) else {
+        return response;
+    };
+    if matches!(response.status_code, 204 | 206 | 304) || response.status_code < 200 {
+        return response;
+    }
+    let has_header = |name: &str| {
+        response
+            .headers
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case(name))
+    };
+    if has_header("content-encoding") || has_header("content-range") {
+        return response;
+    }
+    let compressible = response
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .is_some_and(|(_, v)| is_compressible(v));
+    if !compressible {
+        return response;
+    }
+
+    let data: &[u8] = match &response.body {
+        ResponseBody::Text(text) => text.as_bytes(),
+        ResponseBody::StaticText(text) => text.as_bytes(),
+        ResponseBody::Binary(bytes) => bytes,
+        ResponseBody::StaticBinary(bytes) => bytes,
+        ResponseBody::Stream(_)
+        | ResponseBody::Mapped(_)
+        | ResponseBody::Cached(_)
+        | ResponseBody::AsyncStream(_) => {
+            return response;
+        }
+    };
+    if data.len() < settings.min_size {
+        return response;
+    }
+
+    let compressed = match encoding {
+        Encoding::Gzip => gzip(data),
+        Encoding::Deflate => zlib(data),
+    };
+    if compressed.len() >= data.len() {
+        return response;
+    }
+    trace!(
+        "Compressed response body with {}: {} -> {} bytes",
+        encoding.as_str(),
+        data.len(),
+        compressed.len()
+    );
+
+    response
+        .headers
+        .retain(|k, _| !k.eq_ignore_ascii_case("content-length"));
+    response.headers.insert(
+        "Content-Encoding".to_string(),
+        encoding.as_str().to_string(),
+    );
+    let vary = match response.headers.get("Vary") {
+        Some(existing) => format!("Accept-Encoding, {existing}"),
+        None => "Accept-Encoding".to_string(),
+    };
+    response.headers.insert("Vary".to_string(), vary);
+    response.body = ResponseBody::Binary(compressed);
+    response
+}
+
+/// Wrap DEFLATE output in a gzip container (RFC 1952)
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 3 + 32);
+    // ID1 ID2 CM FLG MTIME(4) XFL OS(unknown)
+    out.extend_from_slice(&[0x1f, 0x8b, 0x08, 0x00, 0, 0, 0, 0, 0x00, 0xff]);
+    out.extend_from_slice(&deflate(data));
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Wrap DEFLATE output in a zlib container (RFC 1950), which is what HTTP "deflate" means
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 3 + 16);
+    out.extend_from_slice(&[0x78, 0x01]);
+    out.extend_from_slice(&deflate(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// CRC-32 (IEEE) as used by gzip
+pub fn crc32(data: &[u8]) -> u32 {
+    static TABLE: std::sync::OnceLock<[u32; 256]> = std::sync::OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let mut table = [0u32; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut c = i as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 {
+                    0xEDB8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+        table
+    });
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc = table[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const HASH_BITS: u32 = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// LSB-first bit packer for DEFLATE streams
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new(capacity: usize) -> Self {
+        Self {
+            out: Vec::with_capacity(capacity),
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        self.acc |= (value as u64) << self.bits;
+        self.bits += count;
+        while self.bits >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// Huffman codes are defined MSB-first, so reverse before packing
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+fn write_literal(w: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => w.write_code(0x30 + symbol, 8),
+        144..=255 => w.write_code(0x190 + (symbol - 144), 9),
+        256..=279 => w.write_code(symbol - 256, 7),
+        _ => w.write_code(0xC0 + (symbol - 280), 8),
+    }
+}
+
+fn write_match(w: &mut BitWriter, length: usize, distance: usize) {
+    let li = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base as usize <= length)
+        .unwrap_or(0);
+    write_literal(w, 257 + li as u32);
+    if LENGTH_EXTRA[li] > 0 {
+        w.write(
+            (length - LENGTH_BASE[li] as usize) as u32,
+            LENGTH_EXTRA[li] as u32,
+        );
+    }
+
+    let di = DIST_BASE
+        .iter()
+        .rposition(|&base| base as usize <= distance)
+        .unwrap_or(0);
+    w.write_code(di as u32, 5);
+    if DIST_EXTRA[di] > 0 {
+        w.write(
+            (distance - DIST_BASE[di] as usize) as u32,
+            DIST_EXTRA[di] as u32,
+        );
+    }
+}
+
+fn hash3(data: &[u8], pos: usize) -> usize {
+    let v = (data[pos] as u32) | ((data[pos + 1] as u32) << 8) | ((data[pos + 2] as u32) << 16);
+    (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Raw DEFLATE (RFC 1951) encoder: a single fixed-Huffman block with LZ77 matching
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::new(data.len() / 2 + 16);
+    // BFINAL = 1, BTYPE = 01 (fixed Huffman)
+    w.write(1, 1);
+    w.write(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, pos: usize| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash3(data, pos);
+            prev[pos % WINDOW_SIZE] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if pos + MIN_MATCH <= data.len() {
+            let mut candidate = head[hash3(data, pos)];
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut chain = 0;
+            while candidate != usize::MAX && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = pos - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            write_match(&mut w, best_len, best_dist);
+            for p in pos..pos + best_len {
+                insert(&mut head, &mut prev, p);
+            }
+            pos += best_len;
+        } else {
+            write_literal(&mut w, data[pos] as u32);
+            insert(&mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+
+    write_literal(&mut w, 256); // end of block
+    w.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal fixed-Huffman inflater used to verify the encoder round-trips
+    fn inflate_fixed(data: &[u8]) -> Vec<u8> {
+        let mut bit_pos = 0usize;
+        let mut read = |count: u32| -> u32 {
+            let mut v = 0u32;
+            for i in 0..count {
+                let byte = data[bit_pos / 8];
+                v |= (((byte >> (bit_pos % 8)) & 1) as u32) << i;
+                bit_pos += 1;
+            }
+            v
+        };
+        assert_eq!(read(1), 1);
+        assert_eq!(read(2), 1);
+
+        let mut out: Vec<u8> = Vec::new();
+        loop {
+            // Read a fixed Huffman lit/len symbol MSB-first
+            let mut code = 0u32;
+            for _ in 0..7 {
+                code = (code << 1) | read(1);
+            }
+            let symbol = if code <= 0x17 {
+                code + 256
+            } else {
+                code = (code << 1) | read(1);
+                if (0x30..=0xBF).contains(&code) {
+                    code - 0x30
+                } else if (0xC0..=0xC7).contains(&code) {
+                    code - 0xC0 + 280
+                } else {
+                    code = (code << 1) | read(1);
+                    code - 0x190 + 144
+                }
+            };
+            match symbol {
+                0..=255 => out.push(symbol as u8),
+                256 => break,
+                _ => {
+                    let li = (symbol - 257) as usize;
+                    let length = LENGTH_BASE[li] as usize + read(LENGTH_EXTRA[li] as u32) as usize;
+                    let mut dcode = 0u32;
+                    for _ in 0..5 {
+                        dcode = (dcode << 1) | read(1);
+                    }
+                    let di = dcode as usize;
+                    let distance = DIST_BASE[di] as usize + read(DIST_EXTRA[di] as u32) as usize;
+                    let start = out.len() - distance;
+                    for i in 0..length {
+                        let b = out[start + i];
+                        out.push(b);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_deflate_round_trip() {
+        let mut sample = String::new();
+        for i in 0..500 {
+            sample.push_str(&format!(
+                "<tr><td>file_{i}.txt</td><td>{} KB</td></tr>\n",
+                i * 3
+            ));
+        }
+        let bytes = sample.as_bytes();
+        let compressed = deflate(bytes);
+        assert!(compressed.len() < bytes.len() / 3);
+        assert_eq!(inflate_fixed(&compressed), bytes);
+
+        let binary: Vec<u8> = (0..5000u32).map(|i| (i * 7919 % 251) as u8).collect();
+        assert_eq!(inflate_fixed(&deflate(&binary)), binary);
+        assert_eq!(inflate_fixed(&deflate(b"")), b"");
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        assert_eq!(
+            negotiate_encoding("gzip, deflate, br"),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(negotiate_encoding("deflate"), Some(Encoding::Deflate));
+        assert_eq!(
+            negotiate_encoding("gzip;q=0.5, deflate;q=0.8"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(negotiate_encoding("gzip;q=0, br"), None);
+        assert_eq!(negotiate_encoding("identity"), None);
+    }
+
+    #[test]
+    fn test_is_compressible() {
+        assert!(is_compressible("text/html; charset=utf-8"));
+        assert!(is_compressible("application/json"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("application/zip"));
+    }
+}
@@ -86,8 +86,13 @@ impl IniConfig {
                     return Err(format!("Empty key at line {line_number}: {line}"));
                 }
 
-                // Handle inline comments - remove everything after # or ;
-                if let Some(comment_pos) = value.find('#') {
+                // Quoted values are taken literally, so they may contain # or ;
+                if let Some(quoted) = value.strip_prefix('"')
+                    && let Some(end) = quoted.find('"')
+                {
+                    value = &quoted[..end];
+                } else if let Some(comment_pos) = value.find('#') {
+                    // Handle inline comments - remove everything after # or ;
                     value = value[..comment_pos].trim();
                 } else if let Some(comment_pos) = value.find(';') {
                     value = value[..comment_pos].trim();
@@ -195,6 +200,17 @@ impl IniConfig {
         }
     }
 
+    /// Get every key/value pair of a section, sorted by key
+    pub fn section_entries(&self, section: &str) -> Vec<(String, String)> {
+        let mut entries: Vec<(String, String)> = self
+            .sections
+            .get(section)
+            .map(|s| s.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        entries.sort();
+        entries
+    }
+
     /// Get all section names
     #[allow(dead_code)]
     pub fn sections(&self) -> Vec<String> {
@@ -203,7 +219,7 @@ impl IniConfig {
 }
 
 /// Helper function to parse file sizes like "10GB", "500MB", etc.
-fn parse_file_size(value: &str) -> Option<u64> {
+pub fn parse_file_size(value: &str) -> Option<u64> {
     let value = value.trim().to_uppercase();
 
     if let Ok(num) = value.parse::<u64>() {
@@ -337,4 +353,47 @@ allowed=jpg,png,pdf,txt
         let list = config.get_list("extensions", "allowed");
         assert_eq!(list, vec!["jpg", "png", "pdf", "txt"]);
     }
+
+    #[test]
+    fn test_section_entries() {
+        let content = r"
+[auth.paths]
+/private = alice:pw1
+/docs/internal = bob:pw2
+        ";
+
+        let config = IniConfig::parse(content).unwrap();
+        assert_eq!(
+            config.section_entries("auth.paths"),
+            vec![
+                ("/docs/internal".to_string(), "bob:pw2".to_string()),
+                ("/private".to_string(), "alice:pw1".to_string()),
+            ]
+        );
+        assert!(config.section_entries("missing").is_empty());
+    }
+
+    #[test]
+    fn test_quoted_values() {
+        let content = r##"
+[mime]
+md = "text/markdown; charset=utf-8" ; trailing comment
+txt = text/plain; charset=latin1
+css = "#not-a-comment"
+        "##;
+
+        let config = IniConfig::parse(content).unwrap();
+        assert_eq!(
+            config.get_string("mime", "md"),
+            Some("text/markdown; charset=utf-8".to_string())
+        );
+        assert_eq!(
+            config.get_string("mime", "txt"),
+            Some("text/plain".to_string())
+        );
+        assert_eq!(
+            config.get_string("mime", "css"),
+            Some("#not-a-comment".to_string())
+        );
+    }
 }
@@ -4,6 +4,7 @@
 //! Supports INI files with CLI argument overrides
 
 pub mod ini_parser;
+pub mod reload;
 
 use crate::cli::Cli;
 use ini_parser::IniConfig;
@@ -21,28 +22,218 @@ pub struct Config {
     // Upload settings
     pub enable_upload: bool,
     pub max_upload_size: u64,
+    pub upload_quota_daily: Option<u64>,
+    pub upload_quota_total: Option<u64>,
+    pub upload_scan_command: Option<String>,
+    /// Upload folder layout: `flat`, `date` or `type`
+    pub upload_organize: String,
+    pub upload_conflict: String,
+    pub upload_disk_reserve: u64,
+    pub upload_dedup: bool,
     pub enable_webdav: bool,
+    pub enable_file_management: bool,
+    /// In-browser text editing at `/_edit/`; needs authentication
+    pub enable_edit: bool,
+    /// Serve `index.html` for directories instead of listing them
+    pub index: bool,
+    /// Fall back to the root `index.html` for missing paths; implies `index`
+    pub spa: bool,
+    /// `from to [status]` rules from `--redirects` or `[redirects]`
+    pub redirects: Vec<String>,
+    pub redirects_file: Option<PathBuf>,
+    /// Page in the served directory sent for missing paths
+    pub not_found_page: Option<String>,
+    /// Initial read-only state; can be switched at runtime
+    pub read_only: bool,
+    /// `ext=type` MIME overrides from `[mime]`
+    pub mime_types: Vec<String>,
+    pub default_charset: String,
     pub disable_rate_limit: bool,
+    /// Per-IP limits from `[rate_limit]`; `None` keeps the built-in defaults
+    pub rate_limit: Option<u32>,
+    pub rate_limit_concurrent: Option<u32>,
+    pub rate_limit_upload: Option<u32>,
+    pub rate_limit_search: Option<u32>,
 
     // Security settings
     pub username: Option<String>,
     pub password: Option<String>,
+    /// Argon2 or bcrypt hash used instead of `password`
+    pub password_hash: Option<String>,
+    /// Failed logins per IP before a lockout (0 = no lockout)
+    pub auth_max_failures: u32,
+    /// Length of a lockout, also how long failures are remembered
+    pub auth_lockout_secs: u64,
+    /// Login page and session cookies for browsers
+    pub auth_sessions: bool,
+    pub session_lifetime: u64,
+    pub session_secret: Option<String>,
+    pub auth_tokens: Vec<String>,
+    /// `/prefix=user:password` entries for password-protected directories
+    pub auth_paths: Vec<String>,
+    /// `name=role:password` entries for `[users]` accounts
+    pub users: Vec<String>,
     pub allowed_extensions: Vec<String>,
+    pub allowed_ips: Vec<String>,
+    pub denied_ips: Vec<String>,
+    /// Proxies allowed to name the client in `X-Forwarded-For` / `Forwarded`
+    pub trusted_proxies: Vec<String>,
+    pub log_rejected_ips: bool,
+    /// `never`, `safe` or `always`
+    pub follow_symlinks: String,
+    pub hide_dotfiles: bool,
+    pub ignore_patterns: Vec<String>,
+    /// Extensions always served as attachments; `None` picks a default based on uploads
+    pub force_download: Option<Vec<String>>,
 
     // Logging settings
     pub verbose: bool,
     pub detailed_logging: bool,
     pub log_dir: Option<PathBuf>,
+    pub log_rotate_size: Option<u64>,
+    pub log_rotate_age: Option<u64>,
+    pub log_rotate_keep: usize,
+    pub log_rotate_gzip: bool,
+    pub audit: bool,
+    pub audit_file: Option<PathBuf>,
 
     // SSL settings
     pub ssl_cert: Option<PathBuf>,
     pub ssl_key: Option<PathBuf>,
+    pub http2: bool,
 
     // Reverse proxy settings
     pub base_path: String,
+
+    // Custom error page templates ("<status>.html")
+    pub template_dir: Option<PathBuf>,
+
+    // Web UI appearance
+    pub theme: String,
+    pub custom_css: Option<PathBuf>,
+
+    // Response compression settings
+    pub compression: bool,
+    pub compression_min_size: usize,
+
+    // Search settings
+    pub search_content: bool,
+    pub search_content_extensions: Vec<String>,
+    pub search_content_max_size: u64,
+    pub search_exact_sizes: bool,
+    /// Memory allowed for the search trigram index, 0 when disabled
+    pub search_trigram_budget: u64,
+    /// Globs kept out of the search index
+    pub search_exclude: Vec<String>,
+    pub search_max_depth: usize,
+    /// (folder relative to the served directory, levels indexed inside it)
+    pub search_depth_limits: Vec<(PathBuf, usize)>,
+
+    // Connection timeout settings (seconds)
+    pub read_timeout: u64,
+    pub write_timeout: u64,
+    pub header_timeout: u64,
+
+    // Largest body of a request that is not an upload (bytes)
+    pub max_request_body: u64,
+
+    // Download bandwidth limits (bytes per second, None = unlimited)
+    pub max_bandwidth: Option<u64>,
+    pub per_conn_bandwidth: Option<u64>,
+    pub max_upload_bandwidth: Option<u64>,
+    pub per_upload_bandwidth: Option<u64>,
+
+    // Concurrent large downloads (None = unlimited) and waiting room
+    pub max_concurrent_downloads: Option<usize>,
+    pub download_queue: usize,
+    pub max_workers: Option<usize>,
+    pub worker_queue: usize,
+
+    // Listener and connection socket options
+    pub tcp_backlog: u32,
+    pub tcp_nodelay: bool,
+    pub tcp_reuse_address: bool,
+    pub tcp_reuse_port: bool,
+    pub tcp_keepalive: Option<u64>,
+    pub tcp_keepalive_interval: Option<u64>,
+    pub tcp_keepalive_retries: Option<u32>,
+    pub accept_batch: usize,
+
+    // Memory-mapped hot files (0 = disabled)
+    pub mmap_cache_size: u64,
+    pub mmap_max_file: u64,
+
+    // Small files served from memory (0 = disabled)
+    pub file_cache_size: u64,
+    pub file_cache_max_file: u64,
+
+    // Advertise on the LAN via mDNS
+    pub mdns: bool,
+
+    // Cross-origin access to the JSON APIs (no origins = CORS disabled)
+    pub cors_origins: Vec<String>,
+    pub cors_methods: Vec<String>,
+    pub cors_headers: Vec<String>,
+    pub cors_max_age: u64,
+    pub cors_credentials: bool,
+
+    // Security response headers ("off" omits a header, hsts_max_age 0 omits HSTS)
+    pub security_headers: bool,
+    pub content_security_policy: String,
+    pub frame_options: String,
+    pub referrer_policy: String,
+    pub hsts_max_age: u64,
+
+    // Webhook notifications (no endpoints = disabled, no events = all events)
+    pub webhook_urls: Vec<String>,
+    pub webhook_events: Vec<String>,
+    pub webhook_retries: u32,
+    pub webhook_queue_size: usize,
+
+    // Expiring share links (no secret = disabled)
+    pub share_secret: Option<String>,
+    pub share_max_ttl: u64,
 }
 
 impl Config {
+    /// Copy for display, with passwords, tokens and secrets masked
+    pub fn masked(&self) -> Self {
+        const MASK: &str = "********";
+        let mut masked = self.clone();
+        if masked.password.is_some() {
+            masked.password = Some(MASK.to_string());
+        }
+        if masked.password_hash.is_some() {
+            masked.password_hash = Some(MASK.to_string());
+        }
+        if masked.session_secret.is_some() {
+            masked.session_secret = Some(MASK.to_string());
+        }
+        if masked.share_secret.is_some() {
+            masked.share_secret = Some(MASK.to_string());
+        }
+        // Keep token labels and path prefixes, they help telling entries apart
+        for token in &mut masked.auth_tokens {
+            *token = match token.split_once(':') {
+                Some((label, _)) => format!("{label}:{MASK}"),
+                None => MASK.to_string(),
+            };
+        }
+        for entry in &mut masked.auth_paths {
+            *entry = match entry.split_once(':') {
+                Some((prefix_and_user, _)) => format!("{prefix_and_user}:{MASK}"),
+                None => MASK.to_string(),
+            };
+        }
+        for entry in &mut masked.users {
+            *entry = match entry.split_once(':') {
+                Some((name_and_role, _)) => format!("{name_and_role}:{MASK}"),
+                None => MASK.to_string(),
+            };
+        }
+        masked
+    }
+
     /// Load configuration with precedence: CLI args > INI file > Defaults
     pub fn load(cli: &Cli) -> Result<Self, String> {
         log::debug!("Starting configuration loading process");
@@ -69,6 +260,8 @@ impl Config {
         log::debug!("Building final configuration with precedence rules");
         let enable_webdav = Self::get_enable_webdav(&ini, cli);
         let disable_rate_limit = Self::get_disable_rate_limit(&ini, cli, enable_webdav);
+        let cors_origins = Self::get_cors_list(&ini, cli.cors_origins.as_deref(), "origins", "");
+        let cors_credentials = Self::get_cors_credentials(&ini, cli, &cors_origins)?;
         let config = Self {
             listen: Self::get_listen(&ini, cli),
             port: Self::get_port(&ini, cli),
@@ -78,19 +271,274 @@ impl Config {
 
             enable_upload: Self::get_enable_upload(&ini, cli),
             max_upload_size: Self::get_max_upload_size(&ini, cli),
+            upload_quota_daily: Self::get_upload_quota_daily(&ini, cli),
+            upload_quota_total: Self::get_upload_quota_total(&ini, cli),
+            upload_scan_command: Self::get_upload_scan_command(&ini, cli),
+            upload_organize: Self::get_upload_organize(&ini, cli),
+            upload_conflict: Self::get_upload_conflict(&ini, cli),
+            upload_disk_reserve: Self::get_upload_disk_reserve(&ini, cli),
+            upload_dedup: Self::get_upload_dedup(&ini, cli),
             enable_webdav,
+            enable_file_management: Self::get_enable_file_management(&ini, cli),
+            enable_edit: cli
+                .enable_edit
+                .unwrap_or_else(|| ini.get_bool_or("server", "enable_edit", false)),
+            index: cli
+                .index
+                .unwrap_or_else(|| ini.get_bool_or("server", "index", false)),
+            spa: cli
+                .spa
+                .unwrap_or_else(|| ini.get_bool_or("server", "spa", false)),
+            redirects: Self::get_redirects(&ini, cli),
+            redirects_file: cli.redirects_file.clone().or_else(|| {
+                ini.get_string("server", "redirects_file")
+                    .map(PathBuf::from)
+            }),
+            not_found_page: cli
+                .not_found_page
+                .clone()
+                .or_else(|| ini.get_string("server", "not_found_page")),
+            read_only: cli
+                .read_only
+                .unwrap_or_else(|| ini.get_bool_or("server", "read_only", false)),
+            mime_types: Self::get_mime_types(&ini, cli),
+            default_charset: cli.default_charset.clone().unwrap_or_else(|| {
+                ini.get_string_or(
+                    "server",
+                    "default_charset",
+                    crate::response::DEFAULT_CHARSET,
+                )
+            }),
             disable_rate_limit,
+            rate_limit: Self::get_rate_limit(&ini, cli.rate_limit, "requests_per_minute"),
+            rate_limit_concurrent: Self::get_rate_limit(
+                &ini,
+                cli.rate_limit_concurrent,
+                "concurrent_per_ip",
+            ),
+            rate_limit_upload: Self::get_rate_limit(
+                &ini,
+                cli.rate_limit_upload,
+                "upload_per_minute",
+            ),
+            rate_limit_search: Self::get_rate_limit(
+                &ini,
+                cli.rate_limit_search,
+                "search_per_minute",
+            ),
 
             username: Self::get_username(&ini, cli),
             password: Self::get_password(&ini, cli),
+            password_hash: Self::get_password_hash(&ini, cli)?,
+            auth_max_failures: Self::get_auth_max_failures(&ini, cli),
+            auth_lockout_secs: Self::get_auth_lockout_secs(&ini, cli),
+            auth_sessions: Self::get_auth_sessions(&ini, cli),
+            session_lifetime: Self::get_session_lifetime(&ini, cli),
+            session_secret: Self::get_session_secret(&ini, cli),
+            auth_tokens: Self::get_auth_tokens(&ini, cli),
+            auth_paths: Self::get_auth_paths(&ini, cli),
+            users: Self::get_users(&ini, cli),
             allowed_extensions: Self::get_allowed_extensions(&ini, cli),
+            allowed_ips: Self::get_ip_list(&ini, cli.allow_ips.as_deref(), "allow_ips"),
+            denied_ips: Self::get_ip_list(&ini, cli.deny_ips.as_deref(), "deny_ips"),
+            trusted_proxies: Self::get_ip_list(
+                &ini,
+                cli.trusted_proxies.as_deref(),
+                "trusted_proxies",
+            ),
+            log_rejected_ips: Self::get_log_rejected_ips(&ini, cli),
+            follow_symlinks: Self::get_follow_symlinks(&ini, cli),
+            hide_dotfiles: cli
+                .hide_dotfiles
+                .unwrap_or_else(|| ini.get_bool_or("security", "hide_dotfiles", true)),
+            ignore_patterns: Self::get_ip_list(
+                &ini,
+                cli.ignore_patterns.as_deref(),
+                "ignore_patterns",
+            ),
+            force_download: match cli.force_download.as_deref() {
+                Some(list) => Some(
+                    list.split(',')
+                        .map(|ext| ext.trim().to_string())
+                        .filter(|ext| !ext.is_empty())
+                        .collect(),
+                ),
+                None => ini
+                    .has_key("security", "force_download")
+                    .then(|| ini.get_list("security", "force_download")),
+            },
 
             verbose: Self::get_verbose(&ini, cli),
             detailed_logging: Self::get_detailed_logging(&ini, cli),
             log_dir: Self::get_log_dir(&ini, cli),
+            log_rotate_size: Self::get_log_rotate_size(&ini, cli),
+            log_rotate_age: Self::get_log_rotate_age(&ini, cli),
+            log_rotate_keep: Self::get_log_rotate_keep(&ini, cli),
+            log_rotate_gzip: Self::get_log_rotate_gzip(&ini, cli),
+            audit: cli
+                .audit
+                .unwrap_or_else(|| ini.get_bool_or("logging", "audit", false)),
+            audit_file: cli
+                .audit_file
+                .clone()
+                .or_else(|| ini.get_string("logging", "audit_file").map(PathBuf::from)),
             ssl_cert: Self::get_ssl_cert(&ini, cli),
             ssl_key: Self::get_ssl_key(&ini, cli),
+            http2: Self::get_http2(&ini, cli),
             base_path: Self::get_base_path(&ini, cli),
+            template_dir: Self::get_template_dir(&ini, cli),
+            theme: Self::get_theme(&ini, cli),
+            custom_css: Self::get_custom_css(&ini, cli),
+
+            compression: Self::get_compression(&ini, cli),
+            compression_min_size: Self::get_compression_min_size(&ini, cli),
+
+            search_content: Self::get_search_content(&ini, cli),
+            search_content_extensions: Self::get_search_content_extensions(&ini, cli),
+            search_content_max_size: Self::get_search_content_max_size(&ini, cli),
+            search_exact_sizes: Self::get_search_exact_sizes(&ini, cli),
+            search_trigram_budget: Self::get_search_trigram_budget(&ini, cli),
+            search_exclude: Self::get_search_exclude(&ini, cli),
+            search_max_depth: Self::get_search_max_depth(&ini, cli),
+            search_depth_limits: Self::get_search_depth_limits(&ini, cli),
+
+            read_timeout: Self::get_timeout(
+                &ini,
+                cli.read_timeout,
+                "read_timeout",
+                crate::http::DEFAULT_READ_TIMEOUT_SECS,
+            ),
+            write_timeout: Self::get_timeout(
+                &ini,
+                cli.write_timeout,
+                "write_timeout",
+                crate::http::DEFAULT_WRITE_TIMEOUT_SECS,
+            ),
+            header_timeout: Self::get_timeout(
+                &ini,
+                cli.header_timeout,
+                "header_timeout",
+                crate::http::DEFAULT_HEADER_TIMEOUT_SECS,
+            ),
+            max_request_body: Self::get_mmap_size(&ini, cli.max_request_body, "max_request_body")
+                .filter(|&size| size > 0)
+                .unwrap_or(crate::http::DEFAULT_MAX_REQUEST_BODY),
+            max_bandwidth: Self::get_bandwidth(&ini, cli.max_bandwidth, "server", "max_bandwidth"),
+            per_conn_bandwidth: Self::get_bandwidth(
+                &ini,
+                cli.per_conn_bandwidth,
+                "server",
+                "per_conn_bandwidth",
+            ),
+            max_upload_bandwidth: Self::get_bandwidth(
+                &ini,
+                cli.max_upload_bandwidth,
+                "upload",
+                "max_bandwidth",
+            ),
+            per_upload_bandwidth: Self::get_bandwidth(
+                &ini,
+                cli.per_upload_bandwidth,
+                "upload",
+                "per_upload_bandwidth",
+            ),
+            max_concurrent_downloads: Self::get_max_concurrent_downloads(&ini, cli),
+            download_queue: Self::get_download_queue(&ini, cli),
+            max_workers: cli.max_workers.or_else(|| {
+                ini.get_u64("server", "max_workers")
+                    .filter(|&workers| workers > 0)
+                    .map(|workers| workers as usize)
+            }),
+            worker_queue: cli.worker_queue.unwrap_or_else(|| {
+                ini.get_u64("server", "worker_queue")
+                    .map_or(crate::worker_pool::DEFAULT_QUEUE, |queue| queue as usize)
+            }),
+            tcp_backlog: Self::get_tcp_u64(&ini, cli.tcp_backlog.map(u64::from), "backlog")
+                .map_or(crate::listener::DEFAULT_BACKLOG, |v| v as u32),
+            tcp_nodelay: Self::get_tcp_bool(&ini, cli.tcp_nodelay, "nodelay", false),
+            tcp_reuse_address: Self::get_tcp_bool(
+                &ini,
+                cli.tcp_reuse_address,
+                "reuse_address",
+                cfg!(unix),
+            ),
+            tcp_reuse_port: Self::get_tcp_bool(&ini, cli.tcp_reuse_port, "reuse_port", false),
+            tcp_keepalive: Self::get_tcp_u64(&ini, cli.tcp_keepalive, "keepalive"),
+            tcp_keepalive_interval: Self::get_tcp_u64(
+                &ini,
+                cli.tcp_keepalive_interval,
+                "keepalive_interval",
+            ),
+            tcp_keepalive_retries: Self::get_tcp_u64(
+                &ini,
+                cli.tcp_keepalive_retries.map(u64::from),
+                "keepalive_retries",
+            )
+            .map(|v| v.min(u32::MAX as u64) as u32),
+            accept_batch: Self::get_tcp_u64(
+                &ini,
+                cli.accept_batch.map(|v| v as u64),
+                "accept_batch",
+            )
+            .map_or(crate::listener::DEFAULT_ACCEPT_BATCH, |v| v as usize),
+            mmap_cache_size: Self::get_mmap_size(&ini, cli.mmap_cache_size, "mmap_cache_size")
+                .unwrap_or(0),
+            mmap_max_file: Self::get_mmap_size(&ini, cli.mmap_max_file, "mmap_max_file")
+                .unwrap_or(crate::mmap_cache::DEFAULT_MAX_FILE_BYTES),
+            file_cache_size: Self::get_mmap_size(&ini, cli.file_cache_size, "file_cache_size")
+                .unwrap_or(0),
+            file_cache_max_file: Self::get_mmap_size(
+                &ini,
+                cli.file_cache_max_file,
+                "file_cache_max_file",
+            )
+            .unwrap_or(crate::file_cache::DEFAULT_MAX_FILE_BYTES),
+            mdns: Self::get_mdns(&ini, cli),
+
+            cors_origins,
+            cors_methods: Self::get_cors_list(
+                &ini,
+                cli.cors_methods.as_deref(),
+                "methods",
+                crate::middleware::DEFAULT_CORS_METHODS,
+            ),
+            cors_headers: Self::get_cors_list(
+                &ini,
+                cli.cors_headers.as_deref(),
+                "headers",
+                crate::middleware::DEFAULT_CORS_HEADERS,
+            ),
+            cors_max_age: Self::get_cors_max_age(&ini, cli),
+            cors_credentials,
+
+            security_headers: Self::get_security_headers(&ini, cli),
+            content_security_policy: Self::get_header_value(
+                &ini,
+                cli.content_security_policy.as_deref(),
+                "content_security_policy",
+                crate::middleware::DEFAULT_CONTENT_SECURITY_POLICY,
+            ),
+            frame_options: Self::get_header_value(
+                &ini,
+                cli.frame_options.as_deref(),
+                "frame_options",
+                crate::middleware::DEFAULT_FRAME_OPTIONS,
+            ),
+            referrer_policy: Self::get_header_value(
+                &ini,
+                cli.referrer_policy.as_deref(),
+                "referrer_policy",
+                crate::middleware::DEFAULT_REFERRER_POLICY,
+            ),
+            hsts_max_age: Self::get_hsts_max_age(&ini, cli),
+
+            webhook_urls: Self::get_webhook_list(&ini, cli.webhook_urls.as_deref(), "endpoints"),
+            webhook_events: Self::get_webhook_list(&ini, cli.webhook_events.as_deref(), "events"),
+            webhook_retries: Self::get_webhook_retries(&ini, cli),
+            webhook_queue_size: Self::get_webhook_queue_size(&ini, cli),
+
+            share_secret: Self::get_share_secret(&ini, cli),
+            share_max_ttl: Self::get_share_max_ttl(&ini, cli),
         };
 
         log::debug!("Configuration loading completed successfully");
@@ -106,7 +554,7 @@ impl Config {
     }
 
     /// Find configuration file in order of preference
-    fn find_config_file(cli: &Cli) -> Result<Option<PathBuf>, String> {
+    pub(crate) fn find_config_file(cli: &Cli) -> Result<Option<PathBuf>, String> {
         // 1. Check if config file is explicitly specified via CLI
         if let Some(ref config_path) = cli.config_file {
             let path = PathBuf::from(config_path);
@@ -254,6 +702,86 @@ impl Config {
         u64::MAX
     }
 
+    fn get_upload_quota_daily(ini: &IniConfig, cli: &Cli) -> Option<u64> {
+        if let Some(bytes) = cli.upload_quota_daily {
+            return Some(bytes);
+        }
+        ini.get_file_size("upload", "quota_daily")
+            .filter(|&bytes| bytes > 0)
+    }
+
+    fn get_upload_quota_total(ini: &IniConfig, cli: &Cli) -> Option<u64> {
+        if let Some(bytes) = cli.upload_quota_total {
+            return Some(bytes);
+        }
+        ini.get_file_size("upload", "quota_total")
+            .filter(|&bytes| bytes > 0)
+    }
+
+    fn get_upload_disk_reserve(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(bytes) = cli.upload_disk_reserve {
+            return bytes;
+        }
+        ini.get_file_size("upload", "disk_reserve").unwrap_or(0)
+    }
+
+    fn get_upload_scan_command(ini: &IniConfig, cli: &Cli) -> Option<String> {
+        cli.upload_scan_command
+            .clone()
+            .or_else(|| ini.get_string("upload", "scan_command"))
+            .map(|command| command.trim().to_string())
+            .filter(|command| !command.is_empty())
+    }
+
+    fn get_upload_organize(ini: &IniConfig, cli: &Cli) -> String {
+        use crate::upload::UploadOrganize;
+        if let Some(organize) = cli
+            .upload_organize
+            .as_deref()
+            .and_then(UploadOrganize::parse)
+        {
+            return organize.as_str().to_string();
+        }
+        let Some(value) = ini.get_string("upload", "organize") else {
+            return UploadOrganize::default().as_str().to_string();
+        };
+        UploadOrganize::parse(&value)
+            .unwrap_or_else(|| {
+                log::warn!("Unknown [upload] organize value '{value}', keeping uploads flat");
+                UploadOrganize::default()
+            })
+            .as_str()
+            .to_string()
+    }
+
+    fn get_upload_conflict(ini: &IniConfig, cli: &Cli) -> String {
+        use crate::upload::UploadConflict;
+        if let Some(conflict) = cli
+            .upload_conflict
+            .as_deref()
+            .and_then(UploadConflict::parse)
+        {
+            return conflict.as_str().to_string();
+        }
+        let Some(value) = ini.get_string("upload", "conflict") else {
+            return UploadConflict::default().as_str().to_string();
+        };
+        UploadConflict::parse(&value)
+            .unwrap_or_else(|| {
+                log::warn!("Unknown [upload] conflict value '{value}', renaming on conflicts");
+                UploadConflict::default()
+            })
+            .as_str()
+            .to_string()
+    }
+
+    fn get_upload_dedup(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.upload_dedup {
+            return enabled;
+        }
+        ini.get_bool_or("upload", "dedup", false)
+    }
+
     fn get_enable_webdav(ini: &IniConfig, cli: &Cli) -> bool {
         if let Some(enable_webdav) = cli.enable_webdav {
             return enable_webdav;
@@ -267,6 +795,50 @@ impl Config {
         false
     }
 
+    /// `ext=type` entries from `--mime-types`, else from the `[mime]` section
+    fn get_mime_types(ini: &IniConfig, cli: &Cli) -> Vec<String> {
+        if let Some(list) = &cli.mime_types {
+            return list
+                .split(',')
+                .map(|entry| entry.trim().to_string())
+                .filter(|entry| !entry.is_empty())
+                .collect();
+        }
+        ini.section_entries("mime")
+            .into_iter()
+            .filter(|(_, mime)| mime.contains('/'))
+            .map(|(ext, mime)| {
+                format!(
+                    "{}={mime}",
+                    ext.trim_start_matches('.').to_ascii_lowercase()
+                )
+            })
+            .collect()
+    }
+
+    /// Rules from `--redirects`, else `from = to [status]` entries of `[redirects]`
+    fn get_redirects(ini: &IniConfig, cli: &Cli) -> Vec<String> {
+        if let Some(list) = &cli.redirects {
+            return list
+                .split(',')
+                .map(|rule| rule.trim().to_string())
+                .filter(|rule| !rule.is_empty())
+                .collect();
+        }
+        ini.section_entries("redirects")
+            .into_iter()
+            .map(|(from, to)| format!("{from} {to}"))
+            .collect()
+    }
+
+    fn get_enable_file_management(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.enable_file_management {
+            return enabled;
+        }
+        ini.get_bool("server", "enable_file_management")
+            .unwrap_or(false)
+    }
+
     fn get_disable_rate_limit(ini: &IniConfig, cli: &Cli, webdav_enabled: bool) -> bool {
         let requested = if let Some(disable) = cli.disable_rate_limit {
             disable
@@ -304,6 +876,130 @@ impl Config {
         ini.get_string("auth", "password")
     }
 
+    /// A hash that does not parse is an error rather than a warning, since
+    /// ignoring it could leave the server without authentication
+    fn get_password_hash(ini: &IniConfig, cli: &Cli) -> Result<Option<String>, String> {
+        let Some(hash) = cli
+            .password_hash
+            .clone()
+            .or_else(|| ini.get_string("auth", "password_hash"))
+        else {
+            return Ok(None);
+        };
+        crate::password::PasswordHash::parse(&hash)
+            .map(|_| Some(hash.trim().to_string()))
+            .map_err(|e| format!("Invalid [auth] password_hash: {e}"))
+    }
+
+    fn get_auth_max_failures(ini: &IniConfig, cli: &Cli) -> u32 {
+        if let Some(failures) = cli.auth_max_failures {
+            return failures;
+        }
+        ini.get_u64("auth", "max_failures")
+            .map(|failures| failures.min(u64::from(u32::MAX)) as u32)
+            .unwrap_or(crate::middleware::DEFAULT_AUTH_MAX_FAILURES)
+    }
+
+    fn get_auth_lockout_secs(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(secs) = cli.auth_lockout_secs {
+            return secs;
+        }
+        match ini.get_u64("auth", "lockout_secs") {
+            Some(0) => {
+                log::warn!(
+                    "Ignoring [auth] lockout_secs = 0, using {} seconds",
+                    crate::middleware::DEFAULT_AUTH_LOCKOUT_SECS
+                );
+                crate::middleware::DEFAULT_AUTH_LOCKOUT_SECS
+            }
+            Some(secs) => secs,
+            None => crate::middleware::DEFAULT_AUTH_LOCKOUT_SECS,
+        }
+    }
+
+    fn get_auth_sessions(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.auth_sessions {
+            return enabled;
+        }
+        ini.get_bool_or("auth", "sessions", false)
+    }
+
+    fn get_session_lifetime(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(secs) = cli.session_lifetime {
+            return secs;
+        }
+        match ini.get_u64("auth", "session_lifetime") {
+            Some(0) => {
+                log::warn!(
+                    "Ignoring [auth] session_lifetime = 0, using {} seconds",
+                    crate::session::DEFAULT_SESSION_LIFETIME
+                );
+                crate::session::DEFAULT_SESSION_LIFETIME
+            }
+            Some(secs) => secs,
+            None => crate::session::DEFAULT_SESSION_LIFETIME,
+        }
+    }
+
+    fn get_session_secret(ini: &IniConfig, cli: &Cli) -> Option<String> {
+        cli.session_secret
+            .clone()
+            .or_else(|| ini.get_string("auth", "session_secret"))
+            .map(|secret| secret.trim().to_string())
+            .filter(|secret| !secret.is_empty())
+    }
+
+    fn get_auth_tokens(ini: &IniConfig, cli: &Cli) -> Vec<String> {
+        // CLI argument
+        if let Some(ref tokens) = cli.auth_tokens {
+            return tokens
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect();
+        }
+
+        // INI file
+        ini.get_list("auth", "tokens")
+    }
+
+    fn get_users(ini: &IniConfig, cli: &Cli) -> Vec<String> {
+        // CLI argument
+        if let Some(ref users) = cli.users {
+            return crate::users::split_entries(users);
+        }
+
+        // INI file: `name = role:password`
+        ini.section_entries("users")
+            .into_iter()
+            .map(|(name, account)| format!("{name}={}", account.trim()))
+            .collect()
+    }
+
+    fn get_auth_paths(ini: &IniConfig, cli: &Cli) -> Vec<String> {
+        // CLI argument
+        if let Some(ref paths) = cli.auth_paths {
+            return paths
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect();
+        }
+
+        // INI file: `/prefix = user:password, user2:password2`
+        ini.section_entries("auth.paths")
+            .into_iter()
+            .flat_map(|(prefix, users)| {
+                users
+                    .split(',')
+                    .map(|user| user.trim())
+                    .filter(|user| !user.is_empty())
+                    .map(|user| format!("{prefix}={user}"))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
     fn get_allowed_extensions(ini: &IniConfig, cli: &Cli) -> Vec<String> {
         // CLI argument takes precedence if explicitly provided
         if let Some(allowed_extensions) = &cli.allowed_extensions {
@@ -324,6 +1020,48 @@ impl Config {
         vec!["*.zip".to_string(), "*.txt".to_string()]
     }
 
+    fn get_ip_list(ini: &IniConfig, cli_value: Option<&str>, key: &str) -> Vec<String> {
+        // CLI argument takes precedence if explicitly provided
+        if let Some(list) = cli_value {
+            return list
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+
+        // INI file
+        ini.get_list("security", key)
+    }
+
+    fn get_log_rejected_ips(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(log_rejected) = cli.log_rejected_ips {
+            return log_rejected;
+        }
+        ini.get_bool("security", "log_rejected").unwrap_or(false)
+    }
+
+    fn get_follow_symlinks(ini: &IniConfig, cli: &Cli) -> String {
+        use crate::fs::SymlinkPolicy;
+        if let Some(policy) = cli
+            .follow_symlinks
+            .as_deref()
+            .and_then(SymlinkPolicy::parse)
+        {
+            return policy.as_str().to_string();
+        }
+        let Some(value) = ini.get_string("security", "follow_symlinks") else {
+            return SymlinkPolicy::default().as_str().to_string();
+        };
+        SymlinkPolicy::parse(&value)
+            .unwrap_or_else(|| {
+                log::warn!("Unknown [security] follow_symlinks value '{value}', using 'safe'");
+                SymlinkPolicy::Safe
+            })
+            .as_str()
+            .to_string()
+    }
+
     fn get_verbose(ini: &IniConfig, cli: &Cli) -> bool {
         // CLI argument takes precedence if explicitly provided
         if let Some(verbose) = cli.verbose {
@@ -354,18 +1092,250 @@ impl Config {
         ini.get_string("logging", "log_dir").map(PathBuf::from)
     }
 
+    fn get_mdns(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.mdns {
+            return enabled;
+        }
+        ini.get_bool_or("server", "mdns", false)
+    }
+
+    fn get_cors_list(
+        ini: &IniConfig,
+        cli_value: Option<&str>,
+        key: &str,
+        default: &str,
+    ) -> Vec<String> {
+        let split = |list: &str| -> Vec<String> {
+            list.split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        };
+        if let Some(list) = cli_value {
+            return split(list);
+        }
+        let list = ini.get_list("cors", key);
+        if list.is_empty() {
+            split(default)
+        } else {
+            list
+        }
+    }
+
+    fn get_cors_max_age(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(secs) = cli.cors_max_age {
+            return secs;
+        }
+        ini.get_u64("cors", "max_age")
+            .unwrap_or(crate::middleware::DEFAULT_CORS_MAX_AGE)
+    }
+
+    /// Credentials need an explicit origin list: with `*` any site could
+    /// make authenticated calls on a visitor's behalf
+    fn get_cors_credentials(
+        ini: &IniConfig,
+        cli: &Cli,
+        origins: &[String],
+    ) -> Result<bool, String> {
+        let enabled = cli
+            .cors_credentials
+            .unwrap_or_else(|| ini.get_bool_or("cors", "credentials", false));
+        if enabled && origins.iter().any(|origin| origin == "*") {
+            return Err(
+                "CORS credentials require an explicit list of origins, not '*'".to_string(),
+            );
+        }
+        Ok(enabled)
+    }
+
+    fn get_security_headers(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.security_headers {
+            return enabled;
+        }
+        ini.get_bool_or("headers", "enabled", true)
+    }
+
+    fn get_header_value(
+        ini: &IniConfig,
+        cli_value: Option<&str>,
+        key: &str,
+        default: &str,
+    ) -> String {
+        if let Some(value) = cli_value {
+            return value.trim().to_string();
+        }
+        ini.get_string("headers", key)
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    fn get_hsts_max_age(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(secs) = cli.hsts_max_age {
+            return secs;
+        }
+        ini.get_u64("headers", "hsts_max_age")
+            .unwrap_or(crate::middleware::DEFAULT_HSTS_MAX_AGE)
+    }
+
+    fn get_webhook_list(ini: &IniConfig, cli_value: Option<&str>, key: &str) -> Vec<String> {
+        match cli_value {
+            Some(list) => list
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+            None => ini.get_list("hooks", key),
+        }
+        .into_iter()
+        .filter(|item| item != "all" && item != "*")
+        .collect()
+    }
+
+    fn get_webhook_retries(ini: &IniConfig, cli: &Cli) -> u32 {
+        if let Some(retries) = cli.webhook_retries {
+            return retries;
+        }
+        ini.get_u64("hooks", "retries")
+            .and_then(|retries| u32::try_from(retries).ok())
+            .unwrap_or(crate::hooks::DEFAULT_WEBHOOK_RETRIES)
+    }
+
+    fn get_webhook_queue_size(ini: &IniConfig, cli: &Cli) -> usize {
+        if let Some(size) = cli.webhook_queue_size {
+            return size;
+        }
+        ini.get_usize("hooks", "queue_size")
+            .unwrap_or(crate::hooks::DEFAULT_WEBHOOK_QUEUE_SIZE)
+    }
+
+    fn get_share_secret(ini: &IniConfig, cli: &Cli) -> Option<String> {
+        cli.share_secret
+            .clone()
+            .or_else(|| ini.get_string("share", "secret"))
+            .map(|secret| secret.trim().to_string())
+            .filter(|secret| !secret.is_empty())
+    }
+
+    fn get_share_max_ttl(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(secs) = cli.share_max_ttl {
+            return secs;
+        }
+        ini.get_u64("share", "max_ttl")
+            .filter(|&secs| secs > 0)
+            .unwrap_or(crate::share::DEFAULT_SHARE_MAX_TTL)
+    }
+
+    fn get_log_rotate_size(ini: &IniConfig, cli: &Cli) -> Option<u64> {
+        if let Some(size) = cli.log_rotate_size {
+            return Some(size);
+        }
+        ini.get_file_size("logging", "rotate_size")
+            .filter(|&size| size > 0)
+    }
+
+    fn get_log_rotate_age(ini: &IniConfig, cli: &Cli) -> Option<u64> {
+        if let Some(secs) = cli.log_rotate_age {
+            return Some(secs);
+        }
+        ini.get_string("logging", "rotate_age")
+            .and_then(|age| crate::log_rotation::parse_rotate_age(&age))
+            .map(|age| age.as_secs())
+    }
+
+    fn get_log_rotate_keep(ini: &IniConfig, cli: &Cli) -> usize {
+        if let Some(keep) = cli.log_rotate_keep {
+            return keep;
+        }
+        ini.get_u64("logging", "rotate_keep")
+            .map_or(crate::log_rotation::DEFAULT_ROTATE_KEEP, |keep| {
+                keep as usize
+            })
+    }
+
+    fn get_log_rotate_gzip(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(gzip) = cli.log_rotate_gzip {
+            return gzip;
+        }
+        ini.get_bool_or("logging", "rotate_gzip", false)
+    }
+
+    /// Log rotation settings for the `--log-dir` file
+    pub fn log_rotation_policy(&self) -> crate::log_rotation::RotationPolicy {
+        crate::log_rotation::RotationPolicy {
+            max_size: self.log_rotate_size,
+            max_age: self.log_rotate_age.map(std::time::Duration::from_secs),
+            keep: self.log_rotate_keep,
+            gzip: self.log_rotate_gzip,
+        }
+    }
+
+    /// Audit log file, when the audit log is on
+    pub fn audit_path(&self) -> Option<PathBuf> {
+        self.audit
+            .then(|| crate::audit::audit_path(self.audit_file.as_deref(), self.log_dir.as_deref()))
+    }
+
     fn get_ssl_cert(ini: &IniConfig, cli: &Cli) -> Option<PathBuf> {
         if let Some(ref cert) = cli.ssl_cert {
             return Some(cert.clone());
         }
-        ini.get_string("ssl", "cert").map(PathBuf::from)
+        ini.get_string("ssl", "cert")
+            .or_else(|| ini.get_string("tls", "cert"))
+            .map(PathBuf::from)
     }
 
     fn get_ssl_key(ini: &IniConfig, cli: &Cli) -> Option<PathBuf> {
         if let Some(ref key) = cli.ssl_key {
             return Some(key.clone());
         }
-        ini.get_string("ssl", "key").map(PathBuf::from)
+        ini.get_string("ssl", "key")
+            .or_else(|| ini.get_string("tls", "key"))
+            .map(PathBuf::from)
+    }
+
+    fn get_http2(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.http2 {
+            return enabled;
+        }
+        ini.get_bool("ssl", "http2")
+            .or_else(|| ini.get_bool("tls", "http2"))
+            .unwrap_or(false)
+    }
+
+    fn get_template_dir(ini: &IniConfig, cli: &Cli) -> Option<PathBuf> {
+        // CLI argument takes precedence
+        if let Some(ref dir) = cli.template_dir {
+            return Some(dir.clone());
+        }
+        // INI file
+        ini.get_string("server", "template_dir")
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from)
+    }
+
+    fn get_theme(ini: &IniConfig, cli: &Cli) -> String {
+        use crate::templates::Theme;
+        if let Some(theme) = cli.theme.as_deref().and_then(Theme::parse) {
+            return theme.as_str().to_string();
+        }
+        let Some(value) = ini.get_string("ui", "theme") else {
+            return Theme::default().as_str().to_string();
+        };
+        Theme::parse(&value)
+            .unwrap_or_else(|| {
+                log::warn!("Unknown [ui] theme '{value}', using the dark theme");
+                Theme::default()
+            })
+            .as_str()
+            .to_string()
+    }
+
+    fn get_custom_css(ini: &IniConfig, cli: &Cli) -> Option<PathBuf> {
+        if let Some(ref path) = cli.custom_css {
+            return Some(path.clone());
+        }
+        ini.get_string("ui", "custom_css")
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from)
     }
 
     fn get_base_path(ini: &IniConfig, cli: &Cli) -> String {
@@ -390,6 +1360,167 @@ impl Config {
         String::new()
     }
 
+    fn get_compression(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.compression {
+            return enabled;
+        }
+        ini.get_bool("compression", "enabled").unwrap_or(true)
+    }
+
+    fn get_compression_min_size(ini: &IniConfig, cli: &Cli) -> usize {
+        if let Some(min_size) = cli.compression_min_size {
+            return min_size;
+        }
+        ini.get_usize("compression", "min_size")
+            .unwrap_or(crate::compression::DEFAULT_MIN_COMPRESS_SIZE)
+    }
+
+    fn get_search_content(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.search_content {
+            return enabled;
+        }
+        ini.get_bool("search", "content").unwrap_or(false)
+    }
+
+    fn get_search_content_extensions(ini: &IniConfig, cli: &Cli) -> Vec<String> {
+        let list = if let Some(extensions) = &cli.search_content_extensions {
+            extensions.split(',').map(str::to_string).collect()
+        } else {
+            ini.get_list("search", "content_extensions")
+        };
+        let extensions = crate::search::normalize_content_extensions(&list);
+        if extensions.is_empty() {
+            let defaults: Vec<String> = crate::search::DEFAULT_CONTENT_EXTENSIONS
+                .split(',')
+                .map(str::to_string)
+                .collect();
+            return crate::search::normalize_content_extensions(&defaults);
+        }
+        extensions
+    }
+
+    fn get_search_content_max_size(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(max_size) = cli.search_content_max_size {
+            return max_size;
+        }
+        ini.get_file_size("search", "content_max_size")
+            .filter(|size| *size > 0)
+            .unwrap_or(crate::search::DEFAULT_CONTENT_MAX_SIZE)
+    }
+
+    fn get_search_exact_sizes(ini: &IniConfig, cli: &Cli) -> bool {
+        if let Some(enabled) = cli.search_exact_sizes {
+            return enabled;
+        }
+        ini.get_bool("search", "exact_sizes").unwrap_or(false)
+    }
+
+    fn get_search_trigram_budget(ini: &IniConfig, cli: &Cli) -> u64 {
+        if let Some(bytes) = cli.search_trigram_budget {
+            return bytes;
+        }
+        ini.get_file_size("search", "trigram_budget")
+            .unwrap_or(crate::search::DEFAULT_TRIGRAM_BUDGET)
+    }
+
+    fn get_search_exclude(ini: &IniConfig, cli: &Cli) -> Vec<String> {
+        if let Some(list) = &cli.search_exclude {
+            return list
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        ini.get_list("search", "exclude")
+    }
+
+    fn get_search_max_depth(ini: &IniConfig, cli: &Cli) -> usize {
+        if let Some(depth) = cli.search_max_depth {
+            return depth;
+        }
+        ini.get_usize("search", "max_depth")
+            .filter(|depth| *depth > 0)
+            .unwrap_or(crate::search::DEFAULT_MAX_DEPTH)
+    }
+
+    fn get_search_depth_limits(ini: &IniConfig, cli: &Cli) -> Vec<(PathBuf, usize)> {
+        if let Some(list) = &cli.search_depth_limits {
+            return crate::search::parse_depth_limits(list).unwrap_or_default();
+        }
+
+        // INI file: `media = 1` under [search.depth]
+        let mut limits = Vec::new();
+        for (dir, levels) in ini.section_entries("search.depth") {
+            match crate::search::parse_depth_limits(&format!("{dir}:{levels}")) {
+                Ok(parsed) => limits.extend(parsed),
+                Err(e) => log::warn!("Ignoring [search.depth] entry: {e}"),
+            }
+        }
+        limits
+    }
+
+    fn get_timeout(ini: &IniConfig, cli_value: Option<u64>, key: &str, default: u64) -> u64 {
+        if let Some(secs) = cli_value {
+            return secs;
+        }
+        ini.get_u64("server", key)
+            .filter(|secs| *secs > 0)
+            .unwrap_or(default)
+    }
+
+    fn get_bandwidth(
+        ini: &IniConfig,
+        cli_value: Option<u64>,
+        section: &str,
+        key: &str,
+    ) -> Option<u64> {
+        if let Some(rate) = cli_value {
+            return Some(rate);
+        }
+        ini.get_string(section, key)
+            .and_then(|value| crate::bandwidth::parse_bandwidth(&value))
+    }
+
+    fn get_rate_limit(ini: &IniConfig, cli_value: Option<u32>, key: &str) -> Option<u32> {
+        if let Some(limit) = cli_value {
+            return Some(limit);
+        }
+        ini.get_u64("rate_limit", key)
+            .filter(|&limit| limit > 0)
+            .map(|limit| limit.min(u64::from(u32::MAX)) as u32)
+    }
+
+    fn get_max_concurrent_downloads(ini: &IniConfig, cli: &Cli) -> Option<usize> {
+        if let Some(slots) = cli.max_concurrent_downloads {
+            return Some(slots);
+        }
+        ini.get_u64("server", "max_concurrent_downloads")
+            .filter(|&slots| slots > 0)
+            .map(|slots| slots as usize)
+    }
+
+    fn get_download_queue(ini: &IniConfig, cli: &Cli) -> usize {
+        if let Some(queue) = cli.download_queue {
+            return queue;
+        }
+        ini.get_u64("server", "download_queue").unwrap_or(0) as usize
+    }
+
+    /// Positive `[tcp]` number; zero counts as unset
+    fn get_tcp_u64(ini: &IniConfig, cli_value: Option<u64>, key: &str) -> Option<u64> {
+        cli_value
+            .or_else(|| ini.get_u64("tcp", key))
+            .filter(|&value| value > 0)
+    }
+
+    fn get_tcp_bool(ini: &IniConfig, cli_value: Option<bool>, key: &str, default: bool) -> bool {
+        cli_value.unwrap_or_else(|| ini.get_bool_or("tcp", key, default))
+    }
+
+    fn get_mmap_size(ini: &IniConfig, cli_value: Option<u64>, key: &str) -> Option<u64> {
+        cli_value.or_else(|| ini.get_file_size("server", key))
+    }
+
     /// Print configuration summary
     pub fn print_summary(&self) {
         log::info!("Configuration Summary:");
@@ -404,8 +1535,45 @@ impl Config {
                 self.max_upload_size / (1024 * 1024)
             );
         }
+        if let Some(daily) = self.upload_quota_daily {
+            log::info!("  Upload Quota (daily): {} MB", daily / (1024 * 1024));
+        }
+        if let Some(total) = self.upload_quota_total {
+            log::info!("  Upload Quota (total): {} MB", total / (1024 * 1024));
+        }
+        if let Some(command) = &self.upload_scan_command {
+            log::info!("  Upload Scan Command: {command}");
+        }
+        log::info!("  Upload Organization: {}", self.upload_organize);
+        log::info!("  Upload Conflicts: {}", self.upload_conflict);
+        if self.upload_disk_reserve > 0 {
+            log::info!(
+                "  Upload Disk Reserve: {} MB",
+                self.upload_disk_reserve / (1024 * 1024)
+            );
+        }
+        log::info!("  Upload Deduplication: {}", self.upload_dedup);
         log::info!("  WebDAV Enabled: {}", self.enable_webdav);
-        log::info!(
+        log::info!("  File Management Enabled: {}", self.enable_file_management);
+        log::info!("  Editing Enabled: {}", self.enable_edit);
+        if self.index || self.spa {
+            log::info!("  Static Site: index.html (SPA fallback: {})", self.spa);
+        }
+        if !self.redirects.is_empty() {
+            log::info!("  Redirects: {:?}", self.redirects);
+        }
+        if let Some(file) = &self.redirects_file {
+            log::info!("  Redirects File: {}", file.display());
+        }
+        if let Some(page) = &self.not_found_page {
+            log::info!("  Not Found Page: {page}");
+        }
+        log::info!("  Read-Only: {}", self.read_only);
+        if !self.mime_types.is_empty() {
+            log::info!("  MIME Overrides: {:?}", self.mime_types);
+        }
+        log::info!("  Default Charset: {}", self.default_charset);
+        log::info!(
             "  WebDAV Rate Limiting: {}",
             if self.disable_rate_limit {
                 "Disabled"
@@ -413,27 +1581,259 @@ impl Config {
                 "Enabled"
             }
         );
+        if let Some(limit) = self.rate_limit {
+            log::info!("  Rate Limit: {limit} connections/min per IP");
+        }
+        if let Some(limit) = self.rate_limit_concurrent {
+            log::info!("  Concurrent Connections: {limit} per IP");
+        }
+        if let Some(limit) = self.rate_limit_upload {
+            log::info!("  Upload Rate Limit: {limit} requests/min per IP");
+        }
+        if let Some(limit) = self.rate_limit_search {
+            log::info!("  Search Rate Limit: {limit} requests/min per IP");
+        }
         log::info!(
             "  Authentication: {}",
-            if self.username.is_some() {
+            if self.username.is_some() && self.password_hash.is_some() {
+                "Enabled (hashed password)"
+            } else if self.username.is_some() {
                 "Enabled"
             } else {
                 "Disabled"
             }
         );
+        if self.auth_max_failures > 0 {
+            log::info!(
+                "  Login Lockout: {}s per IP after {} failures",
+                self.auth_lockout_secs,
+                self.auth_max_failures
+            );
+        }
+        if self.auth_sessions {
+            log::info!(
+                "  Login Sessions: enabled ({}s, {} secret)",
+                self.session_lifetime,
+                if self.session_secret.is_some() {
+                    "configured"
+                } else {
+                    "random"
+                }
+            );
+        }
+        if !self.auth_tokens.is_empty() {
+            log::info!("  API Tokens: {} configured", self.auth_tokens.len());
+        }
+        if !self.auth_paths.is_empty() {
+            let mut prefixes: Vec<&str> = self
+                .auth_paths
+                .iter()
+                .filter_map(|entry| entry.split('=').next())
+                .collect();
+            prefixes.dedup();
+            log::info!("  Protected Directories: {prefixes:?}");
+        }
+        if !self.users.is_empty() {
+            log::info!("  User Accounts: {} configured", self.users.len());
+        }
         log::info!("  Allowed Extensions: {:?}", self.allowed_extensions);
+        log::info!("  Follow Symlinks: {}", self.follow_symlinks);
+        log::info!("  Hide Dotfiles: {}", self.hide_dotfiles);
+        if !self.ignore_patterns.is_empty() {
+            log::info!("  Ignore Patterns: {:?}", self.ignore_patterns);
+        }
+        if let Some(force_download) = &self.force_download {
+            log::info!("  Force Download: {force_download:?}");
+        }
+        if !self.allowed_ips.is_empty() {
+            log::info!("  Allowed IPs: {:?}", self.allowed_ips);
+        }
+        if !self.denied_ips.is_empty() {
+            log::info!("  Denied IPs: {:?}", self.denied_ips);
+        }
+        if !self.trusted_proxies.is_empty() {
+            log::info!("  Trusted Proxies: {:?}", self.trusted_proxies);
+        }
         log::info!("  Verbose Logging: {}", self.verbose);
         log::info!("  Detailed Logging: {}", self.detailed_logging);
         if let (Some(cert), Some(key)) = (&self.ssl_cert, &self.ssl_key) {
             log::info!("  SSL/TLS: Enabled");
             log::info!("  SSL Certificate: {}", cert.display());
             log::info!("  SSL Key: {}", key.display());
+            log::info!("  HTTP/2: {}", self.http2);
         } else {
             log::info!("  SSL/TLS: Disabled (HTTP only)");
         }
         if !self.base_path.is_empty() {
             log::info!("  Base Path: {}", self.base_path);
         }
+        if let Some(ref dir) = self.template_dir {
+            log::info!("  Error Templates: {}", dir.display());
+        }
+        log::info!("  Theme: {}", self.theme);
+        if let Some(ref css) = self.custom_css {
+            log::info!("  Custom CSS: {}", css.display());
+        }
+        if self.compression {
+            log::info!(
+                "  Compression: Enabled (min {} bytes)",
+                self.compression_min_size
+            );
+        } else {
+            log::info!("  Compression: Disabled");
+        }
+        if self.search_content {
+            log::info!(
+                "  Content Search: Enabled ({} extensions, max {} KB per file)",
+                self.search_content_extensions.len(),
+                self.search_content_max_size / 1024
+            );
+        } else {
+            log::info!("  Content Search: Disabled");
+        }
+        if self.search_exact_sizes {
+            log::info!("  Search Index Sizes: Exact");
+        }
+        if self.search_trigram_budget > 0 {
+            log::info!(
+                "  Search Trigram Index: up to {}MB",
+                self.search_trigram_budget / (1024 * 1024)
+            );
+        } else {
+            log::info!("  Search Trigram Index: Disabled");
+        }
+        if !self.search_exclude.is_empty() {
+            log::info!("  Search Exclusions: {}", self.search_exclude.join(", "));
+        }
+        if self.search_max_depth != crate::search::DEFAULT_MAX_DEPTH
+            || !self.search_depth_limits.is_empty()
+        {
+            log::info!(
+                "  Search Index Depth: {} levels, {} folder limits",
+                self.search_max_depth,
+                self.search_depth_limits.len()
+            );
+        }
+        log::info!(
+            "  Timeouts: read {}s, write {}s, headers {}s",
+            self.read_timeout,
+            self.write_timeout,
+            self.header_timeout
+        );
+        log::info!(
+            "  Max Request Body: {} bytes (uploads excluded)",
+            self.max_request_body
+        );
+        if self.max_bandwidth.is_some() || self.per_conn_bandwidth.is_some() {
+            let describe = |rate: Option<u64>| {
+                rate.map_or("unlimited".to_string(), crate::bandwidth::format_bandwidth)
+            };
+            log::info!(
+                "  Bandwidth Limit: {} total, {} per connection",
+                describe(self.max_bandwidth),
+                describe(self.per_conn_bandwidth)
+            );
+        }
+        if self.max_upload_bandwidth.is_some() || self.per_upload_bandwidth.is_some() {
+            let describe = |rate: Option<u64>| {
+                rate.map_or("unlimited".to_string(), crate::bandwidth::format_bandwidth)
+            };
+            log::info!(
+                "  Upload Bandwidth Limit: {} total, {} per upload",
+                describe(self.max_upload_bandwidth),
+                describe(self.per_upload_bandwidth)
+            );
+        }
+        if let Some(ref log_dir) = self.log_dir {
+            log::info!("  Log Directory: {}", log_dir.display());
+            if self.log_rotation_policy().is_enabled() {
+                let size = self
+                    .log_rotate_size
+                    .map_or("-".to_string(), crate::fs::format_file_size);
+                let age = self
+                    .log_rotate_age
+                    .map_or("-".to_string(), |secs| format!("{secs}s"));
+                log::info!(
+                    "  Log Rotation: size {}, age {}, keep {}{}",
+                    size,
+                    age,
+                    self.log_rotate_keep,
+                    if self.log_rotate_gzip { ", gzip" } else { "" }
+                );
+            }
+        }
+        if let Some(path) = self.audit_path() {
+            log::info!("  Audit Log: {}", path.display());
+        }
+        if self.mdns {
+            log::info!("  mDNS Advertisement: enabled");
+        }
+        if !self.security_headers {
+            log::info!("  Security Headers: disabled");
+        }
+        if self.share_secret.is_some() {
+            log::info!(
+                "  Share Links: enabled (max lifetime {}s)",
+                self.share_max_ttl
+            );
+        }
+        if !self.webhook_urls.is_empty() {
+            log::info!(
+                "  Webhooks: {} (events: {})",
+                self.webhook_urls.join(", "),
+                if self.webhook_events.is_empty() {
+                    "all".to_string()
+                } else {
+                    self.webhook_events.join(", ")
+                }
+            );
+        }
+        if !self.cors_origins.is_empty() {
+            log::info!(
+                "  CORS Origins: {}{}",
+                self.cors_origins.join(", "),
+                if self.cors_credentials {
+                    " (with credentials)"
+                } else {
+                    ""
+                }
+            );
+        }
+        if let Some(slots) = self.max_concurrent_downloads {
+            log::info!(
+                "  Concurrent Downloads: {} (queue {})",
+                slots,
+                self.download_queue
+            );
+        }
+        if let Some(workers) = self.max_workers {
+            log::info!("  Max Workers: {} (queue {})", workers, self.worker_queue);
+        }
+        log::info!(
+            "  TCP: backlog {}, nodelay {}, reuse_address {}, reuse_port {}, accept_batch {}",
+            self.tcp_backlog,
+            self.tcp_nodelay,
+            self.tcp_reuse_address,
+            self.tcp_reuse_port,
+            self.accept_batch
+        );
+        if let Some(idle) = self.tcp_keepalive {
+            log::info!("  TCP Keepalive: {idle}s idle");
+        }
+        if self.mmap_cache_size > 0 {
+            log::info!(
+                "  Mmap Cache: {} bytes (files up to {} bytes)",
+                self.mmap_cache_size,
+                self.mmap_max_file
+            );
+        }
+        if self.file_cache_size > 0 {
+            log::info!(
+                "  File Cache: {} bytes (files up to {} bytes)",
+                self.file_cache_size,
+                self.file_cache_max_file
+            );
+        }
     }
 }
 
@@ -464,6 +1864,108 @@ mod tests {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         }
     }
 
@@ -661,6 +2163,1201 @@ max_upload_size = 1.5GB
         }
     }
 
+    #[test]
+    fn test_config_tls_section_alias() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+
+        let ini_content = r"
+[tls]
+cert = /etc/irondrop/cert.pem
+key = /etc/irondrop/key.pem
+http2 = true
+";
+        fs::write(&config_file, ini_content).unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+
+        let config = Config::load(&cli).unwrap();
+
+        // [tls] is accepted as an alias for [ssl]
+        assert_eq!(
+            config.ssl_cert,
+            Some(PathBuf::from("/etc/irondrop/cert.pem"))
+        );
+        assert_eq!(config.ssl_key, Some(PathBuf::from("/etc/irondrop/key.pem")));
+        assert!(config.http2);
+
+        cli.http2 = Some(false);
+        assert!(!Config::load(&cli).unwrap().http2);
+    }
+
+    #[test]
+    fn test_config_auth_tokens() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+
+        let ini_content = r"
+[auth]
+tokens = ci:abc123, backup:def456
+";
+        fs::write(&config_file, ini_content).unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.auth_tokens, vec!["ci:abc123", "backup:def456"]);
+
+        cli.auth_tokens = Some("cli:xyz".to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.auth_tokens, vec!["cli:xyz"]);
+    }
+
+    #[test]
+    fn test_config_auth_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+
+        let ini_content = r"
+[auth.paths]
+/private = alice:pw1, bob:pw2
+/team/reports = carol:pw3
+";
+        fs::write(&config_file, ini_content).unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.auth_paths.is_empty());
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.auth_paths,
+            vec![
+                "/private=alice:pw1",
+                "/private=bob:pw2",
+                "/team/reports=carol:pw3"
+            ]
+        );
+
+        cli.auth_paths = Some("/cli=dave:pw4".to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.auth_paths, vec!["/cli=dave:pw4"]);
+    }
+
+    #[test]
+    fn test_config_auth_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[auth]\nsessions = true\nsession_lifetime = 3600\nsession_secret = correct-horse-battery-staple\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.auth_sessions);
+        assert_eq!(
+            config.session_lifetime,
+            crate::session::DEFAULT_SESSION_LIFETIME
+        );
+        assert_eq!(config.session_secret, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.auth_sessions);
+        assert_eq!(config.session_lifetime, 3600);
+        assert_eq!(
+            config.session_secret.as_deref(),
+            Some("correct-horse-battery-staple")
+        );
+        assert_eq!(config.masked().session_secret.as_deref(), Some("********"));
+
+        cli.auth_sessions = Some(false);
+        cli.session_lifetime = Some(60);
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.auth_sessions);
+        assert_eq!(config.session_lifetime, 60);
+    }
+
+    #[test]
+    fn test_config_audit() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[logging]\naudit = true\nlog_dir = /var/log/irondrop\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.audit);
+        assert_eq!(config.audit_path(), None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.audit);
+        assert_eq!(
+            config.audit_path(),
+            Some(PathBuf::from("/var/log/irondrop/irondrop_audit.jsonl"))
+        );
+
+        cli.audit_file = Some(PathBuf::from("/srv/audit.jsonl"));
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.audit_path(), Some(PathBuf::from("/srv/audit.jsonl")));
+
+        cli.audit = Some(false);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.audit_path(), None);
+    }
+
+    #[test]
+    fn test_config_users() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+
+        let ini_content = r"
+[users]
+alice = admin:$argon2id$v=19$m=64,t=1,p=1$c2FsdHNhbHQ$aGFzaGhhc2hoYXNoaGFzaA
+bob = upload:bobs-password
+";
+        fs::write(&config_file, ini_content).unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.users,
+            vec![
+                "alice=admin:$argon2id$v=19$m=64,t=1,p=1$c2FsdHNhbHQ$aGFzaGhhc2hoYXNoaGFzaA",
+                "bob=upload:bobs-password"
+            ]
+        );
+        assert_eq!(
+            config.masked().users,
+            vec!["alice=admin:********", "bob=upload:********"]
+        );
+
+        cli.users = Some("carol=read-only:pw,dave=admin:pw2".to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.users, vec!["carol=read-only:pw", "dave=admin:pw2"]);
+    }
+
+    #[test]
+    fn test_config_upload_quotas() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+
+        let ini_content = r"
+[upload]
+quota_daily = 2GB
+quota_total = 500MB
+";
+        fs::write(&config_file, ini_content).unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.upload_quota_daily, Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(config.upload_quota_total, Some(500 * 1024 * 1024));
+
+        cli.upload_quota_total = Some(1024);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.upload_quota_total, Some(1024));
+    }
+
+    #[test]
+    fn test_config_upload_scan_command() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[upload]\nscan_command = clamdscan %f\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert_eq!(Config::load(&cli).unwrap().upload_scan_command, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.upload_scan_command.as_deref(), Some("clamdscan %f"));
+
+        cli.upload_scan_command = Some("/usr/local/bin/scan %f".to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.upload_scan_command.as_deref(),
+            Some("/usr/local/bin/scan %f")
+        );
+    }
+
+    #[test]
+    fn test_config_ignore_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[security]\nhide_dotfiles = false\nignore_patterns = .git/**, *.key\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.hide_dotfiles);
+        assert!(config.ignore_patterns.is_empty());
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.hide_dotfiles);
+        assert_eq!(config.ignore_patterns, vec![".git/**", "*.key"]);
+
+        cli.ignore_patterns = Some("*.pem".to_string());
+        assert_eq!(Config::load(&cli).unwrap().ignore_patterns, vec!["*.pem"]);
+    }
+
+    #[test]
+    fn test_config_follow_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[security]\nfollow_symlinks = Never\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert_eq!(Config::load(&cli).unwrap().follow_symlinks, "always");
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert_eq!(Config::load(&cli).unwrap().follow_symlinks, "never");
+        cli.follow_symlinks = Some("safe".to_string());
+        assert_eq!(Config::load(&cli).unwrap().follow_symlinks, "safe");
+
+        // A typo falls back to the restrictive choice rather than following everything
+        fs::write(&config_file, "[security]\nfollow_symlinks = sometimes\n").unwrap();
+        cli.follow_symlinks = None;
+        assert_eq!(Config::load(&cli).unwrap().follow_symlinks, "safe");
+    }
+
+    #[test]
+    fn test_config_upload_organize() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[upload]\norganize = Date\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert_eq!(Config::load(&cli).unwrap().upload_organize, "flat");
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert_eq!(Config::load(&cli).unwrap().upload_organize, "date");
+
+        cli.upload_organize = Some("type".to_string());
+        assert_eq!(Config::load(&cli).unwrap().upload_organize, "type");
+
+        fs::write(&config_file, "[upload]\norganize = weekly\n").unwrap();
+        cli.upload_organize = None;
+        assert_eq!(Config::load(&cli).unwrap().upload_organize, "flat");
+    }
+
+    #[test]
+    fn test_config_upload_conflict() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[upload]\nconflict = Version\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert_eq!(Config::load(&cli).unwrap().upload_conflict, "rename");
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert_eq!(Config::load(&cli).unwrap().upload_conflict, "version");
+
+        cli.upload_conflict = Some("reject".to_string());
+        assert_eq!(Config::load(&cli).unwrap().upload_conflict, "reject");
+
+        fs::write(&config_file, "[upload]\nconflict = merge\n").unwrap();
+        cli.upload_conflict = None;
+        assert_eq!(Config::load(&cli).unwrap().upload_conflict, "rename");
+    }
+
+    #[test]
+    fn test_config_upload_disk_reserve() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[upload]\ndisk_reserve = 2GB\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert_eq!(Config::load(&cli).unwrap().upload_disk_reserve, 0);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert_eq!(
+            Config::load(&cli).unwrap().upload_disk_reserve,
+            2 * 1024 * 1024 * 1024
+        );
+
+        cli.upload_disk_reserve = Some(0);
+        assert_eq!(Config::load(&cli).unwrap().upload_disk_reserve, 0);
+    }
+
+    #[test]
+    fn test_config_upload_dedup() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[upload]\ndedup = yes\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert!(!Config::load(&cli).unwrap().upload_dedup);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert!(Config::load(&cli).unwrap().upload_dedup);
+
+        cli.upload_dedup = Some(false);
+        assert!(!Config::load(&cli).unwrap().upload_dedup);
+    }
+
+    #[test]
+    fn test_config_template_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\ntemplate_dir = /srv/irondrop/errors\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert_eq!(Config::load(&cli).unwrap().template_dir, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert_eq!(
+            Config::load(&cli).unwrap().template_dir,
+            Some(PathBuf::from("/srv/irondrop/errors"))
+        );
+
+        cli.template_dir = Some(temp_dir.path().to_path_buf());
+        assert_eq!(
+            Config::load(&cli).unwrap().template_dir,
+            Some(temp_dir.path().to_path_buf())
+        );
+    }
+
+    #[test]
+    fn test_config_theme() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[ui]\ntheme = Light\ncustom_css = /etc/irondrop/brand.css\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.theme, "dark");
+        assert_eq!(config.custom_css, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.theme, "light");
+        assert_eq!(
+            config.custom_css,
+            Some(PathBuf::from("/etc/irondrop/brand.css"))
+        );
+
+        cli.theme = Some("auto".to_string());
+        assert_eq!(Config::load(&cli).unwrap().theme, "auto");
+
+        fs::write(&config_file, "[ui]\ntheme = purple\n").unwrap();
+        cli.theme = None;
+        assert_eq!(Config::load(&cli).unwrap().theme, "dark");
+    }
+
+    #[test]
+    fn test_config_enable_file_management() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[server]\nenable_file_management = true\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert!(!Config::load(&cli).unwrap().enable_file_management);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert!(Config::load(&cli).unwrap().enable_file_management);
+
+        cli.enable_file_management = Some(false);
+        assert!(!Config::load(&cli).unwrap().enable_file_management);
+    }
+
+    #[test]
+    fn test_config_enable_edit() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[server]\nenable_edit = true\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert!(!Config::load(&cli).unwrap().enable_edit);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert!(Config::load(&cli).unwrap().enable_edit);
+
+        cli.enable_edit = Some(false);
+        assert!(!Config::load(&cli).unwrap().enable_edit);
+    }
+
+    #[test]
+    fn test_config_static_site() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[server]\nindex = true\nspa = true\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.index && !config.spa);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.index && config.spa);
+
+        cli.spa = Some(false);
+        let config = Config::load(&cli).unwrap();
+        assert!(config.index && !config.spa);
+    }
+
+    #[test]
+    fn test_config_redirects() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nnot_found_page = 404.html\nredirects_file = /srv/_redirects\n\n[redirects]\n/blog/* = /posts/:splat\n/docs = https://docs.example.com/ 302\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.redirects.is_empty());
+        assert_eq!(config.not_found_page, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.redirects,
+            vec![
+                "/blog/* /posts/:splat",
+                "/docs https://docs.example.com/ 302"
+            ]
+        );
+        assert_eq!(config.not_found_page.as_deref(), Some("404.html"));
+        assert_eq!(
+            config.redirects_file,
+            Some(PathBuf::from("/srv/_redirects"))
+        );
+
+        cli.redirects = Some("/a /b".to_string());
+        assert_eq!(Config::load(&cli).unwrap().redirects, vec!["/a /b"]);
+    }
+
+    #[test]
+    fn test_config_read_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[server]\nread_only = true\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert!(!Config::load(&cli).unwrap().read_only);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert!(Config::load(&cli).unwrap().read_only);
+
+        cli.read_only = Some(false);
+        assert!(!Config::load(&cli).unwrap().read_only);
+    }
+
+    #[test]
+    fn test_config_mime_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[mime]\n.MD = \"text/markdown; charset=utf-8\"\nlog = text/plain\nbad = nonsense\n\n[server]\ndefault_charset = iso-8859-1\n\n[security]\nforce_download = html, svg\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.mime_types.is_empty());
+        assert_eq!(config.default_charset, "utf-8");
+        assert_eq!(config.force_download, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.mime_types,
+            vec!["md=text/markdown; charset=utf-8", "log=text/plain"]
+        );
+        assert_eq!(config.default_charset, "iso-8859-1");
+        assert_eq!(
+            config.force_download,
+            Some(vec!["html".to_string(), "svg".to_string()])
+        );
+
+        cli.mime_types = Some("txt=text/x-notes".to_string());
+        cli.force_download = Some(String::new());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.mime_types, vec!["txt=text/x-notes"]);
+        assert_eq!(config.force_download, Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_config_search_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[search]\ncontent = true\ncontent_extensions = .MD, *.txt\ncontent_max_size = 64KB\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.search_content);
+        assert!(config.search_content_extensions.contains(&"rs".to_string()));
+        assert_eq!(
+            config.search_content_max_size,
+            crate::search::DEFAULT_CONTENT_MAX_SIZE
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.search_content);
+        assert_eq!(config.search_content_extensions, vec!["md", "txt"]);
+        assert_eq!(config.search_content_max_size, 64 * 1024);
+
+        cli.search_content_extensions = Some("log".to_string());
+        assert_eq!(
+            Config::load(&cli).unwrap().search_content_extensions,
+            vec!["log"]
+        );
+    }
+
+    #[test]
+    fn test_config_search_exact_sizes() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[search]
+exact_sizes = true
+",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert!(!Config::load(&cli).unwrap().search_exact_sizes);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert!(Config::load(&cli).unwrap().search_exact_sizes);
+
+        cli.search_exact_sizes = Some(false);
+        assert!(!Config::load(&cli).unwrap().search_exact_sizes);
+    }
+
+    #[test]
+    fn test_config_search_trigram_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[search]\ntrigram_budget = 128MB\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert_eq!(
+            Config::load(&cli).unwrap().search_trigram_budget,
+            crate::search::DEFAULT_TRIGRAM_BUDGET
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert_eq!(
+            Config::load(&cli).unwrap().search_trigram_budget,
+            128 * 1024 * 1024
+        );
+
+        cli.search_trigram_budget = Some(0);
+        assert_eq!(Config::load(&cli).unwrap().search_trigram_budget, 0);
+    }
+
+    #[test]
+    fn test_config_search_scope() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[search]\nexclude = node_modules, .git\nmax_depth = 8\n\n[search.depth]\nmedia = 1\n/archive/old/ = 3\nbroken = many\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.search_exclude.is_empty());
+        assert_eq!(config.search_max_depth, crate::search::DEFAULT_MAX_DEPTH);
+        assert!(config.search_depth_limits.is_empty());
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.search_exclude, vec!["node_modules", ".git"]);
+        assert_eq!(config.search_max_depth, 8);
+        assert_eq!(
+            config.search_depth_limits,
+            vec![
+                (PathBuf::from("archive/old"), 3),
+                (PathBuf::from("media"), 1)
+            ]
+        );
+
+        cli.search_exclude = Some("*.iso".to_string());
+        cli.search_depth_limits = Some("media:2".to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.search_exclude, vec!["*.iso"]);
+        assert_eq!(
+            config.search_depth_limits,
+            vec![(PathBuf::from("media"), 2)]
+        );
+    }
+
+    #[test]
+    fn test_config_timeouts() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nread_timeout = 45\nheader_timeout = 0\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.read_timeout, crate::http::DEFAULT_READ_TIMEOUT_SECS);
+        assert_eq!(
+            config.write_timeout,
+            crate::http::DEFAULT_WRITE_TIMEOUT_SECS
+        );
+        assert_eq!(
+            config.header_timeout,
+            crate::http::DEFAULT_HEADER_TIMEOUT_SECS
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.write_timeout = Some(5);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.read_timeout, 45);
+        assert_eq!(config.write_timeout, 5);
+        // Zero is not a usable timeout and falls back to the default
+        assert_eq!(
+            config.header_timeout,
+            crate::http::DEFAULT_HEADER_TIMEOUT_SECS
+        );
+    }
+
+    #[test]
+    fn test_config_max_request_body() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[server]\nmax_request_body = 512KB\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.max_request_body,
+            crate::http::DEFAULT_MAX_REQUEST_BODY
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_request_body, 512 * 1024);
+
+        cli.max_request_body = Some(4 * 1024 * 1024);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_request_body, 4 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_config_bandwidth_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nmax_bandwidth = 10MB/s\nper_conn_bandwidth = 512KB/s\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_bandwidth, None);
+        assert_eq!(config.per_conn_bandwidth, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.per_conn_bandwidth = Some(1024);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_bandwidth, Some(10 * 1024 * 1024));
+        assert_eq!(config.per_conn_bandwidth, Some(1024));
+    }
+
+    #[test]
+    fn test_config_upload_bandwidth_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nmax_bandwidth = 1MB/s\n[upload]\nmax_bandwidth = 50MB/s\nper_upload_bandwidth = fast\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_upload_bandwidth, Some(50 * 1024 * 1024));
+        assert_eq!(config.per_upload_bandwidth, None);
+
+        cli.max_upload_bandwidth = Some(4096);
+        cli.per_upload_bandwidth = Some(1024);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_upload_bandwidth, Some(4096));
+        assert_eq!(config.per_upload_bandwidth, Some(1024));
+        assert_eq!(config.max_bandwidth, Some(1024 * 1024));
+    }
+
+    #[test]
+    fn test_config_password_hash_and_lockout() {
+        let hash = "$2b$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            format!(
+                "[auth]\nusername = admin\npassword_hash = {hash}\nmax_failures = 5\nlockout_secs = 0\n"
+            ),
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.password_hash, None);
+        assert_eq!(
+            config.auth_max_failures,
+            crate::middleware::DEFAULT_AUTH_MAX_FAILURES
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.password_hash.as_deref(), Some(hash));
+        assert_eq!(config.auth_max_failures, 5);
+        assert_eq!(
+            config.auth_lockout_secs,
+            crate::middleware::DEFAULT_AUTH_LOCKOUT_SECS
+        );
+        assert_eq!(config.masked().password_hash.as_deref(), Some("********"));
+
+        cli.auth_max_failures = Some(0);
+        cli.auth_lockout_secs = Some(60);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            (config.auth_max_failures, config.auth_lockout_secs),
+            (0, 60)
+        );
+
+        // An unusable hash must not silently turn authentication off
+        fs::write(
+            &config_file,
+            "[auth]\nusername = admin\npassword_hash = hunter2\n",
+        )
+        .unwrap();
+        assert!(Config::load(&cli).is_err());
+    }
+
+    #[test]
+    fn test_config_log_rotation() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[logging]\nrotate_size = 50MB\nrotate_age = 1d\nrotate_keep = 3\nrotate_gzip = true\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let policy = Config::load(&cli).unwrap().log_rotation_policy();
+        assert!(!policy.is_enabled());
+        assert_eq!(policy.keep, crate::log_rotation::DEFAULT_ROTATE_KEEP);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.log_rotate_keep = Some(7);
+        let policy = Config::load(&cli).unwrap().log_rotation_policy();
+        assert_eq!(policy.max_size, Some(50 * 1024 * 1024));
+        assert_eq!(policy.max_age, Some(std::time::Duration::from_secs(86400)));
+        assert_eq!(policy.keep, 7);
+        assert!(policy.gzip);
+    }
+
+    #[test]
+    fn test_config_download_slots() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nmax_concurrent_downloads = 4\ndownload_queue = 8\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_concurrent_downloads, None);
+        assert_eq!(config.download_queue, 0);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.download_queue = Some(2);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_concurrent_downloads, Some(4));
+        assert_eq!(config.download_queue, 2);
+    }
+
+    #[test]
+    fn test_config_worker_pool() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nmax_workers = 16\nworker_queue = 32\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_workers, None);
+        assert_eq!(config.worker_queue, crate::worker_pool::DEFAULT_QUEUE);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.worker_queue = Some(0);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.max_workers, Some(16));
+        assert_eq!(config.worker_queue, 0);
+    }
+
+    #[test]
+    fn test_config_tcp_options() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[tcp]\nbacklog = 4096\nnodelay = true\nreuse_port = true\nkeepalive = 60\nkeepalive_interval = 10\nkeepalive_retries = 0\naccept_batch = 32\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.tcp_backlog, crate::listener::DEFAULT_BACKLOG);
+        assert!(!config.tcp_nodelay);
+        assert_eq!(config.tcp_reuse_address, cfg!(unix));
+        assert_eq!(config.tcp_keepalive, None);
+        assert_eq!(config.accept_batch, 1);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.tcp_nodelay = Some(false);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.tcp_backlog, 4096);
+        assert!(!config.tcp_nodelay);
+        assert!(config.tcp_reuse_port);
+        assert_eq!(config.tcp_keepalive, Some(60));
+        assert_eq!(config.tcp_keepalive_interval, Some(10));
+        assert_eq!(config.tcp_keepalive_retries, None);
+        assert_eq!(config.accept_batch, 32);
+    }
+
+    #[test]
+    fn test_config_mmap_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nmmap_cache_size = 256MB\nmmap_max_file = 4MB\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.mmap_cache_size, 0);
+        assert_eq!(
+            config.mmap_max_file,
+            crate::mmap_cache::DEFAULT_MAX_FILE_BYTES
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.mmap_max_file = Some(1024);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.mmap_cache_size, 256 * 1024 * 1024);
+        assert_eq!(config.mmap_max_file, 1024);
+    }
+
+    #[test]
+    fn test_config_file_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[server]\nfile_cache_size = 32MB\nfile_cache_max_file = 128KB\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.file_cache_size, 0);
+        assert_eq!(
+            config.file_cache_max_file,
+            crate::file_cache::DEFAULT_MAX_FILE_BYTES
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.file_cache_size, 32 * 1024 * 1024);
+        assert_eq!(config.file_cache_max_file, 128 * 1024);
+
+        cli.file_cache_size = Some(0);
+        assert_eq!(Config::load(&cli).unwrap().file_cache_size, 0);
+    }
+
+    #[test]
+    fn test_config_rate_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[rate_limit]\nrequests_per_minute = 600\nconcurrent_per_ip = 20\nupload_per_minute = 5\nsearch_per_minute = 0\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.rate_limit, None);
+        assert_eq!(config.rate_limit_upload, None);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        cli.rate_limit_concurrent = Some(4);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.rate_limit, Some(600));
+        assert_eq!(config.rate_limit_concurrent, Some(4));
+        assert_eq!(config.rate_limit_upload, Some(5));
+        // Zero is not a usable limit and falls back to the default
+        assert_eq!(config.rate_limit_search, None);
+    }
+
+    #[test]
+    fn test_config_mdns() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(&config_file, "[server]\nmdns = true\n").unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        assert!(!Config::load(&cli).unwrap().mdns);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        assert!(Config::load(&cli).unwrap().mdns);
+
+        cli.mdns = Some(false);
+        assert!(!Config::load(&cli).unwrap().mdns);
+    }
+
+    #[test]
+    fn test_config_cors() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[cors]\norigins = https://app.example.com, http://localhost:3000\n\
+             methods = GET, POST\nmax_age = 120\ncredentials = true\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.cors_origins.is_empty());
+        assert_eq!(config.cors_methods.len(), 5);
+        assert_eq!(config.cors_max_age, 600);
+        assert!(!config.cors_credentials);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.cors_origins,
+            vec!["https://app.example.com", "http://localhost:3000"]
+        );
+        assert_eq!(config.cors_methods, vec!["GET", "POST"]);
+        assert_eq!(config.cors_headers[0], "Authorization");
+        assert_eq!(config.cors_max_age, 120);
+        assert!(config.cors_credentials);
+
+        cli.cors_origins = Some("*".to_string());
+        cli.cors_credentials = Some(false);
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.cors_origins, vec!["*"]);
+        assert!(!config.cors_credentials);
+
+        // A wildcard would let any site send credentialed requests
+        cli.cors_credentials = None;
+        let err = Config::load(&cli).unwrap_err();
+        assert!(err.contains("explicit list of origins"), "{err}");
+        cli.cors_credentials = Some(true);
+        assert!(Config::load(&cli).is_err());
+    }
+
+    #[test]
+    fn test_config_security_headers() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[headers]\nframe_options = DENY\nreferrer_policy = off\nhsts_max_age = 600\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.security_headers);
+        assert_eq!(
+            config.content_security_policy,
+            crate::middleware::DEFAULT_CONTENT_SECURITY_POLICY
+        );
+        assert_eq!(config.frame_options, "SAMEORIGIN");
+        assert_eq!(config.hsts_max_age, 31_536_000);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.frame_options, "DENY");
+        assert_eq!(config.referrer_policy, "off");
+        assert_eq!(config.hsts_max_age, 600);
+
+        cli.security_headers = Some(false);
+        cli.frame_options = Some("SAMEORIGIN".to_string());
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.security_headers);
+        assert_eq!(config.frame_options, "SAMEORIGIN");
+    }
+
+    #[test]
+    fn test_config_webhooks() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[hooks]\nendpoints = http://127.0.0.1:9000/a, http://hooks.lan/b\nevents = upload.completed,file.deleted\nretries = 5\nqueue_size = 32\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert!(config.webhook_urls.is_empty());
+        assert_eq!(
+            config.webhook_retries,
+            crate::hooks::DEFAULT_WEBHOOK_RETRIES
+        );
+        assert_eq!(
+            config.webhook_queue_size,
+            crate::hooks::DEFAULT_WEBHOOK_QUEUE_SIZE
+        );
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.webhook_urls,
+            vec!["http://127.0.0.1:9000/a", "http://hooks.lan/b"]
+        );
+        assert_eq!(
+            config.webhook_events,
+            vec!["upload.completed", "file.deleted"]
+        );
+        assert_eq!(config.webhook_retries, 5);
+        assert_eq!(config.webhook_queue_size, 32);
+
+        cli.webhook_events = Some("all".to_string());
+        cli.webhook_retries = Some(0);
+        let config = Config::load(&cli).unwrap();
+        assert!(config.webhook_events.is_empty());
+        assert_eq!(config.webhook_retries, 0);
+    }
+
+    #[test]
+    fn test_config_share_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+        fs::write(
+            &config_file,
+            "[share]\nsecret = correct-horse-battery-staple\nmax_ttl = 86400\n",
+        )
+        .unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.share_secret, None);
+        assert_eq!(config.share_max_ttl, crate::share::DEFAULT_SHARE_MAX_TTL);
+
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(
+            config.share_secret.as_deref(),
+            Some("correct-horse-battery-staple")
+        );
+        assert_eq!(config.share_max_ttl, 86400);
+
+        cli.share_max_ttl = Some(60);
+        assert_eq!(Config::load(&cli).unwrap().share_max_ttl, 60);
+    }
+
+    #[test]
+    fn test_config_ip_filter_lists() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+
+        let ini_content = r"
+[security]
+allow_ips = 192.168.1.0/24, 10.0.0.5
+deny_ips = 192.168.1.13
+log_rejected = true
+trusted_proxies = 127.0.0.1, 10.0.0.0/8
+";
+        fs::write(&config_file, ini_content).unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.allowed_ips, vec!["192.168.1.0/24", "10.0.0.5"]);
+        assert_eq!(config.denied_ips, vec!["192.168.1.13"]);
+        assert!(config.log_rejected_ips);
+        assert_eq!(config.trusted_proxies, vec!["127.0.0.1", "10.0.0.0/8"]);
+
+        cli.deny_ips = Some("0.0.0.0/0".to_string());
+        cli.trusted_proxies = Some("::1".to_string());
+        let config = Config::load(&cli).unwrap();
+        assert_eq!(config.denied_ips, vec!["0.0.0.0/0"]);
+        assert_eq!(config.trusted_proxies, vec!["::1"]);
+    }
+
+    #[test]
+    fn test_config_compression_section() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("test.ini");
+
+        let ini_content = r"
+[compression]
+enabled = false
+min_size = 4096
+";
+        fs::write(&config_file, ini_content).unwrap();
+
+        let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+        cli.config_file = Some(config_file.to_string_lossy().to_string());
+
+        let config = Config::load(&cli).unwrap();
+        assert!(!config.compression);
+        assert_eq!(config.compression_min_size, 4096);
+
+        // CLI overrides INI
+        cli.compression = Some(true);
+        let config = Config::load(&cli).unwrap();
+        assert!(config.compression);
+    }
+
     #[test]
     fn test_config_print_summary() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MIT
+
+//! Configuration hot reload
+//!
+//! [`ConfigReloader`] remembers the original command line and re-runs
+//! [`Config::load`] with it, so CLI flags keep overriding the INI file after a
+//! reload. The server polls [`ConfigReloader::poll`] every
+//! [`RELOAD_POLL_INTERVAL`]; it reports a change when the resolved INI file's
+//! modification time or size changes, or when the process received `SIGHUP`
+//! (Unix only, see [`install_sighup_handler`]).
+//!
+//! Reloaded settings apply to connections accepted afterwards. Connections
+//! already in flight finish with the settings they started with.
+
+use super::Config;
+use crate::cli::Cli;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// How often the server checks for a changed config file or `SIGHUP`
+pub const RELOAD_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static SIGHUP_RECEIVED: AtomicBool = AtomicBool::new(false);
+
+/// Modification time and size of the watched file
+type FileStamp = (SystemTime, u64);
+
+/// Watches the INI file in use and reloads the configuration on change
+#[derive(Clone)]
+pub struct ConfigReloader {
+    cli: Cli,
+    path: Option<PathBuf>,
+    stamp: Option<FileStamp>,
+}
+
+impl ConfigReloader {
+    /// `cli` is the command line as parsed at startup
+    pub fn new(cli: Cli) -> Self {
+        let path = Config::find_config_file(&cli).ok().flatten();
+        let stamp = path.as_deref().and_then(file_stamp);
+        Self { cli, path, stamp }
+    }
+
+    /// INI file currently being watched, if any
+    pub fn config_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Load the configuration again from the INI file and the original flags
+    pub fn load(&self) -> Result<Config, String> {
+        Config::load(&self.cli)
+    }
+
+    /// True once per change of the config file, or after `SIGHUP`
+    pub fn poll(&mut self) -> bool {
+        let signalled = SIGHUP_RECEIVED.swap(false, Ordering::AcqRel);
+        // Re-resolve so a config file created after startup is picked up too
+        self.path = Config::find_config_file(&self.cli).ok().flatten();
+        let stamp = self.path.as_deref().and_then(file_stamp);
+        let changed = stamp != self.stamp;
+        self.stamp = stamp;
+        signalled || changed
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Request a reload on `SIGHUP`; a no-op on platforms without signals
+#[cfg(unix)]
+pub fn install_sighup_handler() {
+    const SIGHUP: i32 = 1;
+
+    unsafe extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+
+    extern "C" fn on_sighup(_signum: i32) {
+        SIGHUP_RECEIVED.store(true, Ordering::Release);
+    }
+
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        signal(SIGHUP, on_sighup);
+    }
+}
+
+/// Request a reload on `SIGHUP`; a no-op on platforms without signals
+#[cfg(not(unix))]
+pub fn install_sighup_handler() {}
@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: MIT
+
+//! Free-space checks and reservations for uploads
+//!
+//! Before the body of a `POST` or `PUT` is read, its declared
+//! `Content-Length` is compared with the space left on the served directory's
+//! filesystem, so an upload that cannot fit fails with `507 Insufficient
+//! Storage` instead of halfway through. The handler checks again right before
+//! writing and holds a [`Reservation`] until the file is in place; space held
+//! by uploads in progress does not count as available, so concurrent uploads
+//! cannot each take the same free bytes.
+//!
+//! `--upload-disk-reserve` (`[upload] disk_reserve`) keeps a margin free on top
+//! of that. Free space comes from `statvfs(3)` on Linux and macOS; elsewhere
+//! the checks are skipped and uploads fall back to a trial write.
+
+use crate::error::AppError;
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Bytes held by uploads in progress
+static RESERVED: AtomicU64 = AtomicU64::new(0);
+
+/// Served directory and reserve margin for the HTTP-layer check
+static SETTINGS: RwLock<Option<(PathBuf, u64)>> = RwLock::new(None);
+
+/// Size and free space of a filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpace {
+    /// Bytes available to unprivileged users
+    pub free: u64,
+    /// Size of the filesystem
+    pub total: u64,
+}
+
+/// Space of the filesystem holding `path`, `None` where unsupported
+#[cfg(any(
+    all(target_os = "linux", target_pointer_width = "64"),
+    target_os = "macos"
+))]
+#[allow(clippy::unnecessary_cast)] // the field types differ between platforms
+pub fn disk_space(path: &Path) -> Option<DiskSpace> {
+    use std::ffi::{CString, c_char, c_int, c_ulong};
+    use std::os::unix::ffi::OsStrExt;
+
+    #[cfg(target_os = "linux")]
+    type BlockCount = u64;
+    #[cfg(target_os = "macos")]
+    type BlockCount = u32;
+
+    #[repr(C)]
+    struct StatVfs {
+        f_bsize: c_ulong,
+        f_frsize: c_ulong,
+        f_blocks: BlockCount,
+        f_bfree: BlockCount,
+        f_bavail: BlockCount,
+        f_files: BlockCount,
+        f_ffree: BlockCount,
+        f_favail: BlockCount,
+        f_fsid: c_ulong,
+        f_flag: c_ulong,
+        f_namemax: c_ulong,
+        // glibc and musl pad the struct; never read
+        _spare: [c_int; 6],
+    }
+
+    unsafe extern "C" {
+        fn statvfs(path: *const c_char, buf: *mut StatVfs) -> c_int;
+    }
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<StatVfs>::zeroed();
+    // SAFETY: `path` is NUL-terminated and `stat` is large enough for the
+    // platform's `struct statvfs`
+    if unsafe { statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        debug!("statvfs failed: {}", std::io::Error::last_os_error());
+        return None;
+    }
+    // SAFETY: initialized by the successful call above
+    let stat = unsafe { stat.assume_init() };
+    let fragment = if stat.f_frsize > 0 {
+        stat.f_frsize
+    } else {
+        stat.f_bsize
+    } as u64;
+    Some(DiskSpace {
+        free: (stat.f_bavail as u64).saturating_mul(fragment),
+        total: (stat.f_blocks as u64).saturating_mul(fragment),
+    })
+}
+
+/// Space of the filesystem holding `path`, `None` where unsupported
+#[cfg(not(any(
+    all(target_os = "linux", target_pointer_width = "64"),
+    target_os = "macos"
+)))]
+pub fn disk_space(_path: &Path) -> Option<DiskSpace> {
+    None
+}
+
+/// Whether `a` and `b` live on the same filesystem (`false` when unknown)
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        false
+    }
+}
+
+/// Bytes held by uploads in progress
+pub fn reserved_bytes() -> u64 {
+    RESERVED.load(Ordering::SeqCst)
+}
+
+/// Space held for an upload until it is dropped
+#[derive(Debug, Default)]
+pub struct Reservation {
+    bytes: u64,
+}
+
+impl Reservation {
+    /// Bytes still held
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Give back `bytes` that have been written to disk meanwhile
+    pub fn release(&mut self, bytes: u64) {
+        let bytes = bytes.min(self.bytes);
+        self.bytes -= bytes;
+        RESERVED.fetch_sub(bytes, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        RESERVED.fetch_sub(self.bytes, Ordering::SeqCst);
+    }
+}
+
+/// Reserve `bytes` on the filesystem of `dir`, keeping `margin` bytes free.
+///
+/// Fails with [`AppError::UploadDiskFull`] when they do not fit; returns
+/// `None` when free space cannot be determined on this platform.
+pub fn reserve(dir: &Path, margin: u64, bytes: u64) -> Result<Option<Reservation>, AppError> {
+    let Some(space) = disk_space(dir) else {
+        return Ok(None);
+    };
+    let held = RESERVED.fetch_add(bytes, Ordering::SeqCst);
+    let reservation = Reservation { bytes };
+    let available = space.free.saturating_sub(margin).saturating_sub(held);
+    if bytes > available {
+        warn!(
+            "Not enough disk space in {} for {bytes} bytes ({} free, {margin} kept in reserve, {held} held by other uploads)",
+            dir.display(),
+            space.free
+        );
+        return Err(AppError::upload_disk_full(available));
+    }
+    debug!("Reserved {bytes} bytes in {}", dir.display());
+    Ok(Some(reservation))
+}
+
+/// Use `dir` and `margin` for [`check_declared_length`]
+pub fn configure(dir: &Path, margin: u64) {
+    if let Ok(mut settings) = SETTINGS.write() {
+        *settings = Some((dir.to_path_buf(), margin));
+    }
+}
+
+/// Refuse a request body of `length` bytes that cannot fit on the served
+/// directory's filesystem, before it is read. Nothing is reserved.
+pub fn check_declared_length(length: u64) -> Result<(), AppError> {
+    let Some((dir, margin)) = SETTINGS.read().ok().and_then(|settings| settings.clone()) else {
+        return Ok(());
+    };
+    let Some(space) = disk_space(&dir) else {
+        return Ok(());
+    };
+    let available = space
+        .free
+        .saturating_sub(margin)
+        .saturating_sub(reserved_bytes());
+    if length > available {
+        warn!("Refusing a {length} byte request body: only {available} bytes can be stored");
+        return Err(AppError::upload_disk_full(available));
+    }
+    Ok(())
+}
+
+/// `"disk":{...}` section for the status JSON
+pub fn status_json() -> String {
+    let settings = SETTINGS.read().ok().and_then(|settings| settings.clone());
+    let space = settings.as_ref().and_then(|(dir, _)| disk_space(dir));
+    match (settings, space) {
+        (Some((_, margin)), Some(space)) => format!(
+            r#""disk":{{"available":true,"free_bytes":{},"total_bytes":{},"reserve_bytes":{margin},"reserved_bytes":{}}}"#,
+            space.free,
+            space.total,
+            reserved_bytes()
+        ),
+        _ => r#""disk":{"available":false,"path":null,"free_bytes":null,"total_bytes":null,"reserve_bytes":null,"reserved_bytes":0}"#
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_and_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(space) = disk_space(dir.path()) else {
+            return; // not supported on this platform
+        };
+        assert!(space.total >= space.free && space.total > 0);
+        assert!(same_filesystem(dir.path(), dir.path()));
+
+        assert!(matches!(
+            reserve(dir.path(), 0, space.free.saturating_add(1 << 30)),
+            Err(AppError::UploadDiskFull(_))
+        ));
+        assert!(matches!(
+            reserve(dir.path(), u64::MAX, 1),
+            Err(AppError::UploadDiskFull(_))
+        ));
+
+        let mut reservation = reserve(dir.path(), 0, 4096).unwrap().unwrap();
+        reservation.release(1024);
+        assert_eq!(reservation.bytes(), 3072);
+        drop(reservation);
+    }
+}
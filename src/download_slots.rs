@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: MIT
+
+//! Concurrent download slots for large files
+//!
+//! `--max-concurrent-downloads` caps how many responses of at least
+//! [`LARGE_DOWNLOAD_BYTES`] are streamed at once, independently of the rate
+//! limiter's per-IP connection cap. Small files, listings and API responses
+//! never take a slot.
+//!
+//! When every slot is busy, up to `--download-queue` requests wait for one to
+//! free up (at most [`QUEUE_WAIT`]); anything beyond that gets
+//! `503 Service Unavailable` with `Retry-After`. Usage is reported under
+//! `downloads` in the monitor JSON.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Responses at least this large need a download slot
+pub const LARGE_DOWNLOAD_BYTES: u64 = 1024 * 1024;
+
+/// Longest a queued request waits for a slot
+pub const QUEUE_WAIT: Duration = Duration::from_secs(30);
+
+/// `Retry-After` sent with 503 responses
+pub const RETRY_AFTER_SECS: u64 = 5;
+
+/// Slot accounting shared by every connection
+#[derive(Debug, Default)]
+pub struct DownloadSlots {
+    /// 0 means unlimited
+    limit: AtomicUsize,
+    queue_capacity: AtomicUsize,
+    active: AtomicUsize,
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+    released: Notify,
+}
+
+/// Held while a large download is being sent; frees the slot on drop
+#[derive(Debug)]
+pub struct DownloadSlot {
+    slots: Arc<DownloadSlots>,
+}
+
+impl Drop for DownloadSlot {
+    fn drop(&mut self) {
+        self.slots.active.fetch_sub(1, Ordering::AcqRel);
+        self.slots.released.notify_one();
+    }
+}
+
+impl DownloadSlots {
+    /// Set the slot limit (`None` = unlimited) and how many requests may wait
+    pub fn configure(&self, limit: Option<usize>, queue_capacity: usize) {
+        self.limit.store(limit.unwrap_or(0), Ordering::Release);
+        self.queue_capacity.store(queue_capacity, Ordering::Release);
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        match self.limit.load(Ordering::Acquire) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Acquire)
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Acquire)
+    }
+
+    /// `"downloads"` fields for the monitor JSON (without braces)
+    pub fn usage_json(&self) -> String {
+        format!(
+            r#""active":{},"limit":{},"queued":{},"queue_capacity":{},"rejected":{}"#,
+            self.active(),
+            self.limit()
+                .map_or("null".to_string(), |limit| limit.to_string()),
+            self.queued(),
+            self.queue_capacity.load(Ordering::Acquire),
+            self.rejected()
+        )
+    }
+
+    fn try_acquire(self: &Arc<Self>) -> Option<DownloadSlot> {
+        let limit = self.limit();
+        let mut active = self.active.load(Ordering::Acquire);
+        loop {
+            if limit.is_some_and(|limit| active >= limit) {
+                return None;
+            }
+            match self.active.compare_exchange_weak(
+                active,
+                active + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    return Some(DownloadSlot {
+                        slots: Arc::clone(self),
+                    });
+                }
+                Err(current) => active = current,
+            }
+        }
+    }
+
+    /// Take a slot, queueing for up to `wait`; `None` means answer with 503
+    pub async fn acquire(self: &Arc<Self>, wait: Duration) -> Option<DownloadSlot> {
+        if let Some(slot) = self.try_acquire() {
+            return Some(slot);
+        }
+
+        let capacity = self.queue_capacity.load(Ordering::Acquire);
+        let position = self.queued.fetch_add(1, Ordering::AcqRel);
+        if position >= capacity {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+            self.rejected.fetch_add(1, Ordering::AcqRel);
+            return None;
+        }
+
+        let deadline = tokio::time::Instant::now() + wait;
+        let slot = loop {
+            // Register for wakeups before checking, so a release in between is not missed
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            if let Some(slot) = self.try_acquire() {
+                break Some(slot);
+            }
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                break self.try_acquire();
+            }
+        };
+
+        self.queued.fetch_sub(1, Ordering::AcqRel);
+        if slot.is_none() {
+            self.rejected.fetch_add(1, Ordering::AcqRel);
+        }
+        slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_slots_queue_and_reject() {
+        let slots = Arc::new(DownloadSlots::default());
+        slots.configure(Some(1), 1);
+
+        let first = slots.acquire(Duration::ZERO).await.unwrap();
+        assert_eq!(slots.active(), 1);
+
+        // One waiter fits in the queue and gets the slot once it is released
+        let waiter = {
+            let slots = slots.clone();
+            tokio::spawn(async move { slots.acquire(Duration::from_secs(5)).await.is_some() })
+        };
+        while slots.queued() == 0 {
+            tokio::task::yield_now().await;
+        }
+        // The queue is full, so the next request is turned away immediately
+        assert!(slots.acquire(Duration::from_secs(5)).await.is_none());
+        assert_eq!(slots.rejected(), 1);
+
+        drop(first);
+        assert!(waiter.await.unwrap());
+        assert_eq!(slots.active(), 0);
+        assert_eq!(slots.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_slots_never_block() {
+        let slots = Arc::new(DownloadSlots::default());
+        let mut held = Vec::new();
+        for _ in 0..10 {
+            held.push(slots.acquire(Duration::ZERO).await.unwrap());
+        }
+        assert_eq!(slots.active(), 10);
+        drop(held);
+        assert_eq!(slots.active(), 0);
+        assert!(slots.usage_json().contains(r#""limit":null"#));
+    }
+}
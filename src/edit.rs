@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MIT
+
+//! In-browser editing of small text files
+//!
+//! With `--enable-edit` and authentication configured:
+//!
+//! - `GET  /_edit/<path>` - the file in a textarea editor
+//! - `POST /_edit/<path>` with the new text as body and `If-Match: <etag>`
+//!
+//! A save only goes through while the file still has the ETag it had when the
+//! editor was opened (the same validator downloads report). Otherwise the
+//! answer is `412 Precondition Failed` with the current ETag, so a second
+//! editor cannot silently overwrite a change. The text is written to a
+//! temporary file next to the original, synced and renamed over it, keeping
+//! the original's permissions.
+//!
+//! Only existing files below [`MAX_EDIT_BYTES`] that are valid UTF-8 and match
+//! `allowed_extensions` can be edited; editing never creates files.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Request, RequestBody, Response, ResponseBody};
+use crate::templates::TemplateEngine;
+use crate::utils::{etag_for_resource, json_escape};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Route for the editor and for saves
+pub const EDIT_ROUTE: &str = "/_edit/";
+
+/// Largest file the editor opens, and largest text it saves
+pub const MAX_EDIT_BYTES: u64 = 1024 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Saves run one at a time, so the ETag check and the rename cannot interleave
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the edit routes are registered; previews link to the editor then
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+/// `GET` or `POST` on [`EDIT_ROUTE`]
+pub fn handle_edit_request(
+    request: &Request,
+    cli: &Cli,
+    base_dir: &Path,
+) -> Result<Response, AppError> {
+    let path_only = request.path.as_str();
+    let relative = path_only
+        .strip_prefix(EDIT_ROUTE)
+        .unwrap_or("")
+        .trim_start_matches('/');
+    let file_path = editable_path(cli, base_dir, relative)?;
+
+    match request.method.as_str() {
+        "POST" => save(request, &file_path, &format!("/{relative}")),
+        _ => editor_page(&file_path, relative),
+    }
+}
+
+/// Resolve `relative` to an existing file that may be edited
+fn editable_path(cli: &Cli, base_dir: &Path, relative: &str) -> Result<PathBuf, AppError> {
+    let file_path = crate::webdav::resolve_request_path(base_dir, relative)?;
+    if !file_path.is_file()
+        || crate::fs::ignore_rules()
+            .excludes(file_path.strip_prefix(base_dir).unwrap_or(&file_path))
+    {
+        return Err(AppError::NotFound);
+    }
+    if !cli
+        .allowed_extension_patterns()?
+        .iter()
+        .any(|p| p.matches_path(&file_path))
+    {
+        debug!(
+            "Edit rejected, extension not allowed: {}",
+            file_path.display()
+        );
+        return Err(AppError::Forbidden);
+    }
+    Ok(file_path)
+}
+
+fn editor_page(file_path: &Path, relative: &str) -> Result<Response, AppError> {
+    let metadata = std::fs::metadata(file_path)?;
+    if metadata.len() > MAX_EDIT_BYTES {
+        debug!("Too large to edit: {}", file_path.display());
+        return Err(AppError::PayloadTooLarge(MAX_EDIT_BYTES));
+    }
+    let bytes = std::fs::read(file_path)?;
+    let text = String::from_utf8(bytes).map_err(|_| {
+        debug!("Not UTF-8 text, cannot edit: {}", file_path.display());
+        AppError::UnsupportedMediaType("binary or non-UTF-8 file".to_string())
+    })?;
+
+    let raw_href = crate::templates::prefixed(&format!(
+        "/{}",
+        crate::utils::percent_encode_path(Path::new(relative))
+    ));
+    let save_href = crate::templates::prefixed(&format!(
+        "{EDIT_ROUTE}{}",
+        crate::utils::percent_encode_path(Path::new(relative))
+    ));
+    let etag = etag_for_resource(file_path, &metadata);
+    let html = TemplateEngine::global().render_edit_page(
+        &format!("/{relative}"),
+        &raw_href,
+        &save_href,
+        &text,
+        &etag,
+    )?;
+
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "text/html; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-store".to_string());
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(html),
+    })
+}
+
+fn save(request: &Request, file_path: &Path, url_path: &str) -> Result<Response, AppError> {
+    let text = match &request.body {
+        Some(RequestBody::Memory(data)) if data.len() as u64 <= MAX_EDIT_BYTES => data.as_slice(),
+        Some(RequestBody::Memory(_)) | Some(RequestBody::File { .. }) => {
+            return Err(AppError::PayloadTooLarge(MAX_EDIT_BYTES));
+        }
+        None => &[],
+    };
+    if std::str::from_utf8(text).is_err() {
+        debug!("Edit of {url_path} rejected, body is not UTF-8");
+        return Err(AppError::BadRequest);
+    }
+    let Some(expected) = request.headers.get("if-match").map(|v| v.trim()) else {
+        return Ok(json_response(
+            428,
+            "Precondition Required",
+            r#"{"success":false,"error":"Saving needs an If-Match header with the file's ETag"}"#
+                .to_string(),
+        ));
+    };
+
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let metadata = std::fs::metadata(file_path)?;
+    let current = etag_for_resource(file_path, &metadata);
+    if expected != "*" && expected != current {
+        debug!("Edit of {url_path} rejected, ETag {expected} is now {current}");
+        return Ok(json_response(
+            412,
+            "Precondition Failed",
+            format!(
+                r#"{{"success":false,"error":"The file was changed since it was opened","etag":"{}"}}"#,
+                json_escape(&current)
+            ),
+        ));
+    }
+
+    write_atomically(file_path, text, &metadata)?;
+    let etag = etag_for_resource(file_path, &std::fs::metadata(file_path)?);
+    info!("Saved {} via the editor", file_path.display());
+    crate::audit::record(
+        crate::audit::AuditEvent::Upload,
+        crate::audit::AuditRecord::for_request(request, "success")
+            .with_path(file_path.display().to_string())
+            .with("size", text.len() as u64)
+            .with("source", "edit"),
+    );
+
+    let mut response = json_response(
+        200,
+        "OK",
+        format!(
+            r#"{{"success":true,"path":"{}","etag":"{}"}}"#,
+            json_escape(url_path),
+            json_escape(&etag)
+        ),
+    );
+    response.headers.insert("ETag".to_string(), etag);
+    Ok(response)
+}
+
+/// Replace `path` with `data` through a synced temporary file in the same folder
+fn write_atomically(
+    path: &Path,
+    data: &[u8],
+    metadata: &std::fs::Metadata,
+) -> Result<(), AppError> {
+    let dir = path.parent().ok_or(AppError::Forbidden)?;
+    let temp_path = dir.join(format!(
+        "{}edit_{}_{}.tmp",
+        crate::upload::TEMP_FILE_PREFIX,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let mut file = std::fs::OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&temp_path)?;
+        file.write_all(data)?;
+        file.set_permissions(metadata.permissions())?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn json_response(status_code: u16, status_text: &str, body: String) -> Response {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-store".to_string());
+    Response {
+        status_code,
+        status_text: status_text.to_string(),
+        headers,
+        body: ResponseBody::Text(body),
+    }
+}
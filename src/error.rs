@@ -13,7 +13,14 @@ pub enum AppError {
     NotFound,
     BadRequest,
     Unauthorized,
+    UnauthorizedRealm(String), // Realm of the protected directory
+    LoginRequired(String),     // Path to return to after the login page
     MethodNotAllowed,
+    MethodNotAllowedFor(String), // Value for the Allow header
+    RequestTimeout,
+    UriTooLong,
+    HeaderFieldsTooLarge,
+    TooManyRequests(u64), // Seconds until the client may retry
     InternalServerError(String),
     // Upload-specific errors
     PayloadTooLarge(u64),         // Contains the maximum allowed size
@@ -21,6 +28,8 @@ pub enum AppError {
     UploadDiskFull(u64),          // Contains available space in bytes
     UnsupportedMediaType(String), // Contains the rejected media type
     UploadDisabled,
+    UploadRejected(String),       // Contains the scan command's verdict
+    UploadConflict(String),       // Contains the name that is already taken
     InvalidConfiguration(String), // Contains configuration error details
 }
 
@@ -36,7 +45,18 @@ impl fmt::Display for AppError {
             AppError::NotFound => write!(f, "Not Found"),
             AppError::BadRequest => write!(f, "Bad request"),
             AppError::Unauthorized => write!(f, "Unauthorized"),
+            AppError::UnauthorizedRealm(realm) => write!(f, "Unauthorized for realm: {realm}"),
+            AppError::LoginRequired(next) => write!(f, "Login required for {next}"),
             AppError::MethodNotAllowed => write!(f, "Method not allowed"),
+            AppError::MethodNotAllowedFor(allow) => {
+                write!(f, "Method not allowed (allow: {allow})")
+            }
+            AppError::RequestTimeout => write!(f, "Request timed out"),
+            AppError::UriTooLong => write!(f, "Request URI too long"),
+            AppError::HeaderFieldsTooLarge => write!(f, "Request header fields too large"),
+            AppError::TooManyRequests(retry_after) => {
+                write!(f, "Too many requests, retry in {retry_after}s")
+            }
             AppError::InternalServerError(msg) => write!(f, "Internal server error: {msg}"),
             AppError::PayloadTooLarge(max_size) => {
                 write!(
@@ -63,6 +83,8 @@ impl fmt::Display for AppError {
                 )
             }
             AppError::UploadDisabled => write!(f, "Upload functionality is disabled"),
+            AppError::UploadRejected(reason) => write!(f, "Upload rejected by scan: {reason}"),
+            AppError::UploadConflict(name) => write!(f, "A file named '{name}' already exists"),
             AppError::InvalidConfiguration(msg) => write!(f, "Invalid configuration: {msg}"),
         }
     }
@@ -115,6 +137,16 @@ impl AppError {
         AppError::UploadDisabled
     }
 
+    /// Creates an UploadRejected error
+    pub fn upload_rejected<S: Into<String>>(reason: S) -> Self {
+        AppError::UploadRejected(reason.into())
+    }
+
+    /// Creates an UploadConflict error
+    pub fn upload_conflict<S: Into<String>>(name: S) -> Self {
+        AppError::UploadConflict(name.into())
+    }
+
     /// Checks if the error is upload-related
     pub fn is_upload_error(&self) -> bool {
         matches!(
@@ -124,6 +156,8 @@ impl AppError {
                 | AppError::UploadDiskFull(_)
                 | AppError::UnsupportedMediaType(_)
                 | AppError::UploadDisabled
+                | AppError::UploadRejected(_)
+                | AppError::UploadConflict(_)
                 | AppError::InvalidConfiguration(_)
         )
     }
@@ -142,6 +176,8 @@ mod tests {
             AppError::upload_disk_full(512),
             AppError::unsupported_media_type("application/x-executable"),
             AppError::upload_disabled(),
+            AppError::upload_rejected("exit status 1"),
+            AppError::upload_conflict("report.pdf"),
         ];
 
         let expected = [
@@ -151,6 +187,8 @@ mod tests {
             "Insufficient disk space for upload. Available: 512 bytes",
             "Unsupported media type 'application/x-executable': file type not allowed",
             "Upload functionality is disabled",
+            "Upload rejected by scan: exit status 1",
+            "A file named 'report.pdf' already exists",
         ];
 
         for (error, expected_msg) in errors.iter().zip(expected.iter()) {
@@ -167,6 +205,8 @@ mod tests {
             AppError::upload_disk_full(512),
             AppError::unsupported_media_type("test"),
             AppError::upload_disabled(),
+            AppError::upload_rejected("test"),
+            AppError::upload_conflict("test"),
         ];
 
         let non_upload_errors = vec![
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MIT
+
+//! In-memory cache of small hot files
+//!
+//! With `--file-cache-size` set, files of at most `--file-cache-max-file`
+//! bytes are read into memory on first download and answered from there
+//! afterwards, so repeated requests for icons, stylesheets and small
+//! documents skip disk I/O entirely. Entries are keyed on path and checked
+//! against the file's size and modification time on every request; a changed
+//! file is simply read again. The least recently used entries are evicted
+//! once the total would exceed the cache size.
+//!
+//! Unlike the mmap cache this works on every target and is safe for files
+//! that are modified in place. Hits and misses are reported under
+//! `file_cache` in the monitor JSON.
+
+use log::debug;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Default `--file-cache-max-file`
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 64 * 1024;
+
+struct Entry {
+    data: Arc<[u8]>,
+    modified: Option<SystemTime>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<PathBuf, Entry>,
+    /// Cached paths by last use, oldest first
+    lru: BTreeMap<u64, PathBuf>,
+    total: u64,
+    tick: u64,
+    /// 0 means disabled
+    max_total: u64,
+    max_file: u64,
+}
+
+impl CacheState {
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.lru.remove(&entry.last_used);
+            self.total -= entry.data.len() as u64;
+        }
+    }
+
+    fn touch(&mut self, path: &Path) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(path) {
+            self.lru.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.lru.insert(self.tick, path.to_path_buf());
+        }
+    }
+
+    /// Evict least recently used entries until at most `limit` bytes remain
+    fn shrink_to(&mut self, limit: u64) {
+        while self.total > limit {
+            let Some((_, victim)) = self.lru.pop_first() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&victim) {
+                self.total -= evicted.data.len() as u64;
+                debug!("Evicted {} from the file cache", victim.display());
+            }
+        }
+    }
+}
+
+/// Small files kept in memory, bounded by total size
+#[derive(Default)]
+pub struct FileCache {
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl FileCache {
+    pub fn new(max_total: u64, max_file: u64) -> Self {
+        let cache = Self::default();
+        cache.configure(max_total, max_file);
+        cache
+    }
+
+    /// Set the total and per-file limits; a zero total disables the cache
+    ///
+    /// Entries that no longer fit are dropped; hit and miss counts are kept.
+    pub fn configure(&self, max_total: u64, max_file: u64) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.max_total = max_total;
+        state.max_file = max_file.min(max_total);
+        let max_file = state.max_file;
+        let too_big: Vec<PathBuf> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.data.len() as u64 > max_file)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in too_big {
+            state.remove(&path);
+        }
+        state.shrink_to(max_total);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().is_ok_and(|state| state.max_total > 0)
+    }
+
+    /// Contents of `path` as described by `metadata`, reading it if needed
+    ///
+    /// Returns `None` when the cache is disabled, for empty files, files over
+    /// the per-file limit and files that cannot be read; callers then stream
+    /// the file as usual.
+    pub fn get(&self, path: &Path, metadata: &Metadata) -> Option<Arc<[u8]>> {
+        let len = metadata.len();
+        let modified = metadata.modified().ok();
+        {
+            let mut state = self.state.lock().ok()?;
+            if len == 0 || len > state.max_file {
+                return None;
+            }
+            match state.entries.get(path) {
+                Some(entry) if entry.data.len() as u64 == len && entry.modified == modified => {
+                    let data = entry.data.clone();
+                    state.touch(path);
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(data);
+                }
+                Some(_) => state.remove(path),
+                None => {}
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        // Read outside the lock; a concurrent miss for the same file just
+        // replaces the other entry
+        let data: Arc<[u8]> = match std::fs::read(path) {
+            Ok(data) if data.len() as u64 == len => data.into(),
+            Ok(_) => return None,
+            Err(e) => {
+                debug!("Not caching {}: {e}", path.display());
+                return None;
+            }
+        };
+
+        let mut state = self.state.lock().ok()?;
+        state.remove(path);
+        if len > state.max_file {
+            // Reconfigured while reading
+            return Some(data);
+        }
+        let limit = state.max_total - len;
+        state.shrink_to(limit);
+        state.tick += 1;
+        let last_used = state.tick;
+        state.total += len;
+        state.lru.insert(last_used, path.to_path_buf());
+        state.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                data: data.clone(),
+                modified,
+                last_used,
+            },
+        );
+        debug!("Cached {} ({len} bytes)", path.display());
+        Some(data)
+    }
+
+    /// Requests answered from memory
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Requests for cacheable files that had to read from disk
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Number of cached files and their total size in bytes
+    pub fn usage(&self) -> (usize, u64) {
+        self.state
+            .lock()
+            .map(|state| (state.entries.len(), state.total))
+            .unwrap_or((0, 0))
+    }
+
+    /// `"file_cache"` object for the monitor JSON
+    pub fn usage_json(&self) -> String {
+        let (max_total, max_file) = self
+            .state
+            .lock()
+            .map(|state| (state.max_total, state.max_file))
+            .unwrap_or((0, 0));
+        let (entries, bytes) = self.usage();
+        format!(
+            r#""file_cache":{{"enabled":{},"entries":{entries},"bytes":{bytes},"max_bytes":{max_total},"max_file_bytes":{max_file},"hits":{},"misses":{}}}"#,
+            max_total > 0,
+            self.hits(),
+            self.misses()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_lru_eviction_and_invalidation() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, len: usize| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![name.as_bytes()[0]; len]).unwrap();
+            path
+        };
+        let a = write("a", 400);
+        let b = write("b", 400);
+        let c = write("c", 400);
+        let big = write("big", 2000);
+        let cache = FileCache::new(1000, 1000);
+        let get = |path: &Path| cache.get(path, &fs::metadata(path).unwrap());
+
+        assert_eq!(&get(&a).unwrap()[..], &[b'a'; 400][..]);
+        assert!(get(&b).is_some());
+        assert!(Arc::ptr_eq(&get(&a).unwrap(), &get(&a).unwrap()));
+        assert_eq!((cache.hits(), cache.misses()), (2, 2));
+        // `b` is the least recently used and makes room for `c`
+        assert!(get(&c).is_some());
+        assert_eq!(cache.usage(), (2, 800));
+        assert!(cache.state.lock().unwrap().entries.contains_key(&a));
+        assert!(!cache.state.lock().unwrap().entries.contains_key(&b));
+
+        // Too big files are neither cached nor counted
+        assert!(get(&big).is_none());
+        assert_eq!((cache.usage(), cache.misses()), ((2, 800), 3));
+
+        // A changed file is read again
+        fs::write(&a, [b'z'; 100]).unwrap();
+        assert_eq!(&get(&a).unwrap()[..], &[b'z'; 100][..]);
+        assert_eq!((cache.usage(), cache.misses()), ((2, 500), 4));
+
+        // Shrinking evicts, disabling empties the cache
+        cache.configure(1000, 200);
+        assert_eq!(cache.usage(), (1, 100));
+        cache.configure(0, 200);
+        assert_eq!(cache.usage(), (0, 0));
+        assert!(get(&a).is_none());
+        assert!(!cache.is_enabled());
+    }
+}
@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: MIT
+
+//! File management operations for the web UI
+//!
+//! When `--enable-file-management` is set, the served directory can be managed
+//! from the browser:
+//!
+//! - `DELETE /_irondrop/files?path=/docs/old.txt` - delete a file or directory
+//! - `POST   /_irondrop/files` with `{"action":"delete","path":"/docs/old.txt"}`
+//! - `POST   /_irondrop/files` with `{"action":"move","path":"/a.txt","to":"/docs/b.txt"}`
+//!   (`rename` is accepted as an alias of `move`)
+//! - `POST   /_api/mkdir` with `{"path":"/docs","name":"reports"}` - create a folder
+//!
+//! Paths are resolved inside the served directory with the same traversal and
+//! symlink checks as WebDAV. New names are validated with the upload rules, so a
+//! rename cannot produce a file type that could not have been uploaded, and a
+//! new folder gets a name an upload could have had.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Request, RequestBody, Response, ResponseBody};
+use crate::upload::validate_filename;
+use crate::utils::{json_escape, parse_json_string_map};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Route for file management requests
+pub const FILES_ROUTE: &str = "/_irondrop/files";
+
+/// Route for creating folders
+pub const MKDIR_ROUTE: &str = "/_api/mkdir";
+
+/// Largest JSON body accepted for a file operation
+const MAX_OPERATION_BODY: usize = 16 * 1024;
+
+/// Dispatch a `POST` or `DELETE` on [`FILES_ROUTE`]
+pub fn handle_file_operation(
+    request: &Request,
+    cli: &Cli,
+    base_dir: &Path,
+) -> Result<Response, AppError> {
+    if !cli.enable_file_management.unwrap_or(false) {
+        debug!("File management request rejected - feature disabled");
+        return Err(AppError::MethodNotAllowed);
+    }
+
+    match request.method.as_str() {
+        "DELETE" => {
+            let params = request.query_params();
+            let path = params
+                .get("path")
+                .ok_or_else(|| bad_request("missing 'path' query parameter"))?;
+            delete_entry(request, base_dir, path)
+        }
+        "POST" => {
+            let fields = parse_operation_body(request)?;
+            let field = |name: &str| {
+                fields
+                    .get(name)
+                    .ok_or_else(|| bad_request(&format!("missing '{name}' field")))
+            };
+            match field("action")?.as_str() {
+                "delete" => delete_entry(request, base_dir, field("path")?),
+                "move" | "rename" => move_entry(cli, base_dir, field("path")?, field("to")?),
+                other => Err(bad_request(&format!("unknown action '{other}'"))),
+            }
+        }
+        _ => Err(AppError::MethodNotAllowed),
+    }
+}
+
+/// `POST` on [`MKDIR_ROUTE`]: create the folder `name` in the folder `path`
+pub fn handle_mkdir_request(
+    request: &Request,
+    cli: &Cli,
+    base_dir: &Path,
+) -> Result<Response, AppError> {
+    if !cli.enable_file_management.unwrap_or(false) {
+        debug!("Folder creation rejected - file management disabled");
+        return Err(AppError::MethodNotAllowed);
+    }
+    let fields = parse_operation_body(request)?;
+    let parent_path = fields.get("path").map_or("/", String::as_str);
+    let name = fields
+        .get("name")
+        .map(|name| name.trim())
+        .ok_or_else(|| bad_request("missing 'name' field"))?;
+    validate_filename(name)?;
+
+    let parent = crate::webdav::resolve_request_path(base_dir, parent_path)?;
+    if !parent.is_dir() {
+        return Err(AppError::NotFound);
+    }
+    let target = parent.join(name);
+    let relative = target.strip_prefix(base_dir).unwrap_or(&target);
+    if crate::fs::ignore_rules().excludes(relative) {
+        // It would never show up in a listing
+        return Err(bad_request("folder name is excluded from listings"));
+    }
+    let path = format!("/{}", relative.to_string_lossy().replace('\\', "/"));
+    match std::fs::create_dir(&target) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Ok(json_response(
+                409,
+                "Conflict",
+                format!(
+                    r#"{{"success":false,"error":"Already exists","path":"{}"}}"#,
+                    json_escape(&path)
+                ),
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    }
+    info!("Created folder {} via file management", target.display());
+
+    Ok(json_response(
+        201,
+        "Created",
+        format!(
+            r#"{{"success":true,"action":"mkdir","path":"{}"}}"#,
+            json_escape(&path)
+        ),
+    ))
+}
+
+fn parse_operation_body(request: &Request) -> Result<HashMap<String, String>, AppError> {
+    let body = match &request.body {
+        Some(RequestBody::Memory(data)) if data.len() <= MAX_OPERATION_BODY => data,
+        Some(_) => return Err(AppError::PayloadTooLarge(MAX_OPERATION_BODY as u64)),
+        None => return Err(bad_request("missing JSON body")),
+    };
+    let text = std::str::from_utf8(body).map_err(|_| bad_request("body is not valid UTF-8"))?;
+    parse_json_string_map(text).ok_or_else(|| bad_request("body must be a flat JSON object"))
+}
+
+fn delete_entry(request: &Request, base_dir: &Path, path: &str) -> Result<Response, AppError> {
+    let target = crate::webdav::resolve_request_path(base_dir, path)?;
+    if target == base_dir {
+        return Err(AppError::Forbidden);
+    }
+    let metadata = std::fs::symlink_metadata(&target).map_err(|_| AppError::NotFound)?;
+
+    if metadata.is_dir() {
+        std::fs::remove_dir_all(&target)?;
+    } else {
+        std::fs::remove_file(&target)?;
+        crate::upload_meta::remove_sidecar(&target);
+    }
+    info!("Deleted {} via file management", target.display());
+    crate::hooks::emit(
+        crate::hooks::HookEvent::FileDeleted,
+        &[
+            ("path", path.into()),
+            ("directory", metadata.is_dir().into()),
+            ("source", "file_management".into()),
+            ("client", crate::hooks::client_of(request).into()),
+        ],
+    );
+    crate::audit::record(
+        crate::audit::AuditEvent::Delete,
+        crate::audit::AuditRecord::for_request(request, "success")
+            .with_path(path)
+            .with("directory", metadata.is_dir())
+            .with("source", "file_management"),
+    );
+
+    Ok(json_response(
+        200,
+        "OK",
+        format!(
+            r#"{{"success":true,"action":"delete","path":"{}"}}"#,
+            json_escape(path)
+        ),
+    ))
+}
+
+fn move_entry(cli: &Cli, base_dir: &Path, from: &str, to: &str) -> Result<Response, AppError> {
+    let source = crate::webdav::resolve_request_path(base_dir, from)?;
+    let destination = crate::webdav::resolve_request_path(base_dir, to)?;
+    if source == base_dir || destination == base_dir {
+        return Err(AppError::Forbidden);
+    }
+    if !source.exists() {
+        return Err(AppError::NotFound);
+    }
+    if destination.exists() {
+        return Ok(json_response(
+            409,
+            "Conflict",
+            format!(
+                r#"{{"success":false,"error":"Destination already exists","path":"{}"}}"#,
+                json_escape(to)
+            ),
+        ));
+    }
+    if destination.starts_with(&source) {
+        return Err(bad_request("cannot move a directory inside itself"));
+    }
+
+    let parent = destination.parent().ok_or(AppError::Forbidden)?;
+    if !parent.is_dir() {
+        return Err(AppError::NotFound);
+    }
+    let new_name = destination
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::invalid_filename(to))?;
+    validate_filename(new_name)?;
+    if source.is_file() {
+        validate_extension(cli, new_name)?;
+    }
+
+    std::fs::rename(&source, &destination)?;
+    if destination.is_file() {
+        crate::upload_meta::move_sidecar(&source, &destination);
+    }
+    info!(
+        "Moved {} -> {} via file management",
+        source.display(),
+        destination.display()
+    );
+
+    Ok(json_response(
+        200,
+        "OK",
+        format!(
+            r#"{{"success":true,"action":"move","path":"{}","to":"{}"}}"#,
+            json_escape(from),
+            json_escape(to)
+        ),
+    ))
+}
+
+/// Apply the `--allowed-extensions` patterns to a renamed file
+fn validate_extension(cli: &Cli, filename: &str) -> Result<(), AppError> {
+    let patterns = cli.allowed_extension_patterns()?;
+    let path = Path::new(filename);
+    if patterns.is_empty() || patterns.iter().any(|p| p.matches_path(path)) {
+        return Ok(());
+    }
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("(no extension)");
+    Err(AppError::unsupported_media_type(format!(
+        "File extension '{extension}' not allowed"
+    )))
+}
+
+fn bad_request(reason: &str) -> AppError {
+    debug!("Rejected file operation: {reason}");
+    AppError::BadRequest
+}
+
+fn json_response(status_code: u16, status_text: &str, body: String) -> Response {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Response {
+        status_code,
+        status_text: status_text.to_string(),
+        headers,
+        body: ResponseBody::Text(body),
+    }
+}
@@ -2,13 +2,16 @@
 
 use crate::config::Config;
 use crate::error::AppError;
+use crate::search::SortOrder;
 use crate::templates::TemplateEngine;
-use crate::utils::is_hidden_file;
+use crate::upload_meta::UploadMetadata;
+use crate::utils::{is_hidden_file, json_escape, percent_encode_path, percent_encode_query};
 use log::{debug, trace};
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::time::SystemTime;
 
 #[derive(Eq, PartialEq)]
@@ -16,6 +19,12 @@ struct ListingEntry {
     path: PathBuf,
     file_name: String,
     is_dir: bool,
+    symlink: bool,
+    /// Size or mtime for non-name sorts, bit-inverted when descending so the
+    /// selection heap keeps a single ascending ordering
+    sort_key: u64,
+    /// Name sort in Z-A order
+    reverse_name: bool,
 }
 
 impl Ord for ListingEntry {
@@ -30,21 +39,389 @@ impl PartialOrd for ListingEntry {
     }
 }
 
-/// Enhanced directory listing using modular templates - dark mode only
-pub fn generate_directory_listing(
-    path: &Path,
-    request_path: &str,
-    config: Option<&Config>,
+/// Entries shown per listing page
+pub const LISTING_PAGE_SIZE: usize = 1000;
+
+/// Field a directory listing is ordered by; folders always come first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListingSort {
+    #[default]
+    Name,
+    Size,
+    Modified,
+}
+
+impl ListingSort {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(Self::Name),
+            "size" => Some(Self::Size),
+            "modified" | "mtime" => Some(Self::Modified),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Size => "size",
+            Self::Modified => "modified",
+        }
+    }
+}
+
+/// How a listing is rendered; the gallery is only offered for folders that
+/// are mostly pictures
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListingView {
+    #[default]
+    List,
+    Gallery,
+}
+
+impl ListingView {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "list" => Some(Self::List),
+            "gallery" => Some(Self::Gallery),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Gallery => "gallery",
+        }
+    }
+}
+
+/// How symlinks inside the served tree are treated (`--follow-symlinks`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Symlinks are hidden from listings and answer 404
+    Never,
+    /// Only symlinks that resolve inside the served directory are followed
+    Safe,
+    /// Every symlink is followed, wherever it points
+    #[default]
+    Always,
+}
+
+impl SymlinkPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "never" => Some(Self::Never),
+            "safe" => Some(Self::Safe),
+            "always" => Some(Self::Always),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Never => "never",
+            Self::Safe => "safe",
+            Self::Always => "always",
+        }
+    }
+}
+
+/// A [`SymlinkPolicy`] applied below a served directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymlinkRules {
+    pub policy: SymlinkPolicy,
+    /// Served directory (canonical for `safe`)
+    root: PathBuf,
+}
+
+impl SymlinkRules {
+    pub fn new(policy: SymlinkPolicy, root: &Path) -> Self {
+        // `safe` compares resolved paths; `never` walks the requested path as given
+        let root = match policy {
+            SymlinkPolicy::Safe => fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()),
+            _ => root.to_path_buf(),
+        };
+        Self { policy, root }
+    }
+
+    /// Check an existing `path` below the root before serving it
+    ///
+    /// `never` answers 404 when any component is a symlink, so links look
+    /// absent; `safe` answers 403 when the path resolves outside the root.
+    pub fn check(&self, path: &Path) -> Result<(), AppError> {
+        match self.policy {
+            SymlinkPolicy::Always => Ok(()),
+            SymlinkPolicy::Never => {
+                let relative = path
+                    .strip_prefix(&self.root)
+                    .map_err(|_| AppError::Forbidden)?;
+                let mut current = self.root.clone();
+                for component in relative.components() {
+                    current.push(component);
+                    if fs::symlink_metadata(&current).is_ok_and(|m| m.file_type().is_symlink()) {
+                        debug!("Refusing symlink {}", current.display());
+                        return Err(AppError::NotFound);
+                    }
+                }
+                Ok(())
+            }
+            SymlinkPolicy::Safe => {
+                let resolved = fs::canonicalize(path).map_err(|_| AppError::NotFound)?;
+                if resolved.starts_with(&self.root) {
+                    Ok(())
+                } else {
+                    debug!("Symlink escape blocked: {}", path.display());
+                    Err(AppError::Forbidden)
+                }
+            }
+        }
+    }
+
+    /// Whether a symlinked listing entry is shown
+    pub fn lists(&self, link: &Path) -> bool {
+        match self.policy {
+            SymlinkPolicy::Always => true,
+            SymlinkPolicy::Never => false,
+            SymlinkPolicy::Safe => {
+                fs::canonicalize(link).is_ok_and(|resolved| resolved.starts_with(&self.root))
+            }
+        }
+    }
+}
+
+static IGNORE_RULES: OnceLock<IgnoreRules> = OnceLock::new();
+
+/// Set the process-wide exclusion rules; only the first call takes effect
+pub fn init_ignore_rules(rules: IgnoreRules) {
+    let _ = IGNORE_RULES.set(rules);
+}
+
+/// The process-wide exclusion rules (dotfiles only until initialized)
+pub fn ignore_rules() -> &'static IgnoreRules {
+    IGNORE_RULES.get_or_init(IgnoreRules::default)
+}
+
+/// Entries kept out of listings, search and direct access
+///
+/// Dotfiles are excluded unless `hide_dotfiles` is off. Each
+/// `ignore_patterns` glob without a `/` is matched against every path
+/// component (`*.key`, `node_modules`); one with a `/` is matched against the
+/// path relative to the served directory (`.git/**`, `/private/*`), and a
+/// trailing `/**` also covers the directory itself.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    root: PathBuf,
+    hide_dotfiles: bool,
+    name_patterns: Vec<glob::Pattern>,
+    path_patterns: Vec<glob::Pattern>,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::new(),
+            hide_dotfiles: true,
+            name_patterns: Vec::new(),
+            path_patterns: Vec::new(),
+        }
+    }
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path, hide_dotfiles: bool, patterns: &[String]) -> Result<Self, AppError> {
+        let mut rules = Self {
+            root: root.to_path_buf(),
+            hide_dotfiles,
+            ..Self::default()
+        };
+        for pattern in patterns {
+            let pattern = pattern.trim().trim_start_matches('/');
+            if pattern.is_empty() {
+                continue;
+            }
+            let compile = |p: &str| {
+                glob::Pattern::new(p).map_err(|e| {
+                    AppError::InvalidConfiguration(format!("Invalid ignore pattern '{p}': {e}"))
+                })
+            };
+            if !pattern.contains('/') {
+                rules.name_patterns.push(compile(pattern)?);
+                continue;
+            }
+            if let Some(dir) = pattern.strip_suffix("/**") {
+                rules.path_patterns.push(compile(dir)?);
+            }
+            rules.path_patterns.push(compile(pattern)?);
+        }
+        Ok(rules)
+    }
+
+    fn excludes_name(&self, name: &str) -> bool {
+        (self.hide_dotfiles && is_hidden_file(name))
+            || self.name_patterns.iter().any(|p| p.matches(name))
+    }
+
+    fn excludes_relative(&self, relative: &str) -> bool {
+        self.path_patterns.iter().any(|p| p.matches(relative))
+    }
+
+    /// Whether `relative` (to the served directory) or any directory above
+    /// it is excluded; used for direct requests
+    pub fn excludes(&self, relative: &Path) -> bool {
+        let mut prefix = String::new();
+        for component in relative.components() {
+            let std::path::Component::Normal(name) = component else {
+                continue;
+            };
+            let name = name.to_string_lossy();
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(&name);
+            if self.excludes_name(&name) || self.excludes_relative(&prefix) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether a directory entry named `name` at `path` is excluded, given
+    /// that its parent was not; used while walking the tree
+    pub fn excludes_entry(&self, path: &Path, name: &str) -> bool {
+        if self.excludes_name(name) {
+            return true;
+        }
+        if self.path_patterns.is_empty() {
+            return false;
+        }
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        self.excludes_relative(&relative.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+/// Page, sort and filter options from the listing query string
+/// (`?p=`, `?sort=`, `?order=`, `?filter=`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingOptions {
+    pub page: usize,
+    pub sort: ListingSort,
+    pub order: SortOrder,
+    /// Case-insensitive substring the entry name must contain
+    pub filter: Option<String>,
+    /// Which symlinked entries are shown (server setting, not from the query)
+    pub symlinks: SymlinkRules,
+    /// From `?view=` or the preference remembered for the session; not
+    /// repeated in page and sort links
+    pub view: ListingView,
+}
+
+impl Default for ListingOptions {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            sort: ListingSort::default(),
+            order: SortOrder::Asc,
+            filter: None,
+            symlinks: SymlinkRules::default(),
+            view: ListingView::default(),
+        }
+    }
+}
+
+impl ListingOptions {
+    /// Options for `page` with the default name order and no filter
+    pub fn page(page: usize) -> Self {
+        Self {
+            page,
+            ..Self::default()
+        }
+    }
+
+    /// Query string (with leading `?`) linking to `page` with the same sort
+    /// and filter; defaults are omitted to keep links short
+    pub fn query_for_page(&self, page: usize) -> String {
+        self.query(page, self.sort, self.order)
+    }
+
+    /// Query string for page 1 ordered by `sort`, flipping the direction
+    /// when the listing is already ordered by that field
+    pub fn query_for_sort(&self, sort: ListingSort) -> String {
+        let order = if sort == self.sort && self.order == SortOrder::Asc {
+            SortOrder::Desc
+        } else {
+            SortOrder::Asc
+        };
+        self.query(1, sort, order)
+    }
+
+    /// Query string switching the current page to `view`
+    pub fn query_for_view(&self, view: ListingView) -> String {
+        let query = self.query(self.page, self.sort, self.order);
+        let separator = if query.is_empty() { '?' } else { '&' };
+        format!("{query}{separator}view={}", view.as_str())
+    }
+
+    fn query(&self, page: usize, sort: ListingSort, order: SortOrder) -> String {
+        let mut params = Vec::new();
+        if page > 1 {
+            params.push(format!("p={page}"));
+        }
+        if sort != ListingSort::Name {
+            params.push(format!("sort={}", sort.as_str()));
+        }
+        if order != SortOrder::Asc {
+            params.push(format!("order={}", order.as_str()));
+        }
+        if let Some(filter) = &self.filter {
+            params.push(format!("filter={}", percent_encode_query(filter)));
+        }
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", params.join("&"))
+        }
+    }
+
+    fn matches(&self, file_name: &str) -> bool {
+        match &self.filter {
+            Some(filter) => file_name.to_lowercase().contains(&filter.to_lowercase()),
+            None => true,
+        }
+    }
+
+    fn sort_key(&self, path: &Path) -> u64 {
+        let key = match self.sort {
+            ListingSort::Name => return 0,
+            ListingSort::Size => fs::metadata(path).map_or(0, |m| m.len()),
+            ListingSort::Modified => fs::metadata(path)
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs()),
+        };
+        match self.order {
+            SortOrder::Asc => key,
+            SortOrder::Desc => !key,
+        }
+    }
+}
+
+/// One page of a directory in the requested order
+struct ListingPage {
+    entries: Vec<ListingEntry>,
+    total_count: usize,
     page: usize,
-) -> Result<String, AppError> {
-    debug!("Generating directory listing for: '{}'", path.display());
-    trace!("Request path: '{}'", request_path);
+    total_pages: usize,
+}
 
-    let limit = 1000;
-    let page_size = page.max(1);
+fn read_listing_page(path: &Path, options: &ListingOptions) -> Result<ListingPage, AppError> {
+    let limit = LISTING_PAGE_SIZE;
+    let page_size = options.page.max(1);
     let selection_size = page_size.saturating_mul(limit);
     let mut selected_entries = BinaryHeap::with_capacity(selection_size);
     let mut total_count = 0usize;
+    let reverse_name = options.sort == ListingSort::Name && options.order == SortOrder::Desc;
 
     // Keep only the best entries for the requested page instead of sorting the entire directory.
     trace!("Reading directory entries from: {}", path.display());
@@ -53,20 +430,37 @@ pub fn generate_directory_listing(
         let file_type = entry.file_type()?;
         let file_name = entry.file_name().into_string().unwrap_or_default();
 
-        if is_hidden_file(&file_name) {
+        let entry_path = entry.path();
+        if ignore_rules().excludes_entry(&entry_path, &file_name)
+            || crate::upload_meta::is_sidecar(&file_name)
+            || !options.matches(&file_name)
+        {
+            continue;
+        }
+
+        let symlink = file_type.is_symlink();
+        if symlink && !options.symlinks.lists(&entry_path) {
             continue;
         }
 
         total_count += 1;
+        if selection_size == 0 {
+            continue;
+        }
+        let is_dir = if symlink {
+            entry_path.is_dir()
+        } else {
+            file_type.is_dir()
+        };
         let listing_entry = ListingEntry {
-            path: entry.path(),
+            sort_key: options.sort_key(&entry_path),
+            path: entry_path,
             file_name,
-            is_dir: file_type.is_dir(),
+            is_dir,
+            symlink,
+            reverse_name,
         };
 
-        if selection_size == 0 {
-            continue;
-        }
         if selected_entries.len() < selection_size {
             selected_entries.push(listing_entry);
             continue;
@@ -82,6 +476,40 @@ pub fn generate_directory_listing(
     let mut entries = selected_entries.into_vec();
     entries.sort_unstable_by(compare_listing_entries);
 
+    let total_pages = total_count.div_ceil(limit);
+    let safe_page = options.page.max(1).min(total_pages.max(1));
+    let offset = (safe_page - 1) * limit;
+
+    Ok(ListingPage {
+        entries: entries.into_iter().skip(offset).take(limit).collect(),
+        total_count,
+        page: safe_page,
+        total_pages,
+    })
+}
+
+/// Enhanced directory listing using modular templates - dark mode only
+pub fn generate_directory_listing(
+    path: &Path,
+    request_path: &str,
+    config: Option<&Config>,
+    page: usize,
+) -> Result<String, AppError> {
+    generate_directory_listing_with_options(path, request_path, config, &ListingOptions::page(page))
+}
+
+/// Directory listing sorted, filtered and paged according to `options`
+pub fn generate_directory_listing_with_options(
+    path: &Path,
+    request_path: &str,
+    config: Option<&Config>,
+    options: &ListingOptions,
+) -> Result<String, AppError> {
+    debug!("Generating directory listing for: '{}'", path.display());
+    trace!("Request path: '{}', options: {:?}", request_path, options);
+
+    let listing = read_listing_page(path, options)?;
+
     let display_path = if request_path.is_empty() || request_path == "/" {
         "/"
     } else {
@@ -90,20 +518,17 @@ pub fn generate_directory_listing(
 
     debug!(
         "Preparing {} selected entries for template rendering",
-        entries.len()
+        listing.entries.len()
     );
-    let total_pages = total_count.div_ceil(limit);
-    let safe_page = page.max(1).min(total_pages.max(1));
-    let offset = (safe_page - 1) * limit;
+    let mut template_entries = Vec::with_capacity(listing.entries.len());
 
-    let page_entries: Vec<_> = entries.into_iter().skip(offset).take(limit).collect();
-    let mut template_entries = Vec::with_capacity(page_entries.len());
-
-    for entry in page_entries {
+    for entry in listing.entries {
         let ListingEntry {
             path: entry_path,
             file_name,
             is_dir,
+            symlink,
+            ..
         } = entry;
         let link_name = if is_dir {
             format!("{file_name}/")
@@ -130,26 +555,130 @@ pub fn generate_directory_listing(
             .map(|duration| format_timestamp(duration.as_secs()))
             .unwrap_or_else(|| "-".to_string());
 
-        template_entries.push((link_name, size, modified));
+        let metadata = (!is_dir)
+            .then(|| UploadMetadata::load(&entry_path))
+            .flatten();
+        template_entries.push((link_name, size, modified, symlink, metadata));
     }
 
     debug!("Creating template engine and rendering directory listing");
     let engine = TemplateEngine::global();
 
     let upload_enabled = config.map(|c| c.enable_upload).unwrap_or(false);
-    engine.render_directory_listing(
+    let file_management = config.map(|c| c.enable_file_management).unwrap_or(false);
+    engine.render_directory_listing_with_file_management(
         display_path,
         &template_entries,
-        total_count,
+        listing.total_count,
         upload_enabled,
         request_path,
-        safe_page,
-        total_pages,
+        &ListingOptions {
+            page: listing.page,
+            ..options.clone()
+        },
+        listing.total_pages,
+        file_management,
     )
 }
 
+/// Machine-readable directory listing
+///
+/// Entries use the same field names as search results (`name`, `path`,
+/// `size`, `size_bytes`, `modified`, `type`) plus `url`, the link to fetch
+/// the entry including any base path.
+pub fn generate_directory_listing_json(
+    path: &Path,
+    request_path: &str,
+    options: &ListingOptions,
+) -> Result<String, AppError> {
+    debug!(
+        "Generating JSON directory listing for: '{}'",
+        path.display()
+    );
+    let listing = read_listing_page(path, options)?;
+
+    let dir = request_path.trim_matches('/');
+    let display_path = if dir.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{dir}/")
+    };
+
+    let mut items = Vec::with_capacity(listing.entries.len());
+    for entry in listing.entries {
+        // Directory paths end with '/', as in search results
+        let entry_path = if entry.is_dir {
+            format!("{display_path}{}/", entry.file_name)
+        } else {
+            format!("{display_path}{}", entry.file_name)
+        };
+        let metadata = std::fs::metadata(&entry.path).ok();
+        let size_bytes = if entry.is_dir {
+            0
+        } else {
+            metadata.as_ref().map_or(0, |m| m.len())
+        };
+        let modified = metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or("null".to_string(), |d| d.as_secs().to_string());
+        let mut url = crate::templates::prefixed(&percent_encode_path(Path::new(&entry_path)));
+        if entry.is_dir && !url.ends_with('/') {
+            url.push('/');
+        }
+        let upload_metadata = (!entry.is_dir)
+            .then(|| UploadMetadata::load(&entry.path))
+            .flatten()
+            .map_or("null".to_string(), |meta| meta.to_json());
+
+        items.push(format!(
+            r#"{{"name":"{}","path":"{}","url":"{}","size":"{}","size_bytes":{},"modified":{},"type":"{}","symlink":{},"metadata":{}}}"#,
+            json_escape(&entry.file_name),
+            json_escape(&entry_path),
+            json_escape(&url),
+            if entry.is_dir {
+                "-".to_string()
+            } else {
+                format_file_size(size_bytes)
+            },
+            size_bytes,
+            modified,
+            if entry.is_dir { "directory" } else { "file" },
+            entry.symlink,
+            upload_metadata
+        ));
+    }
+
+    let parent = if dir.is_empty() {
+        "null".to_string()
+    } else {
+        let parent = match dir.rsplit_once('/') {
+            Some((parent, _)) => format!("/{parent}/"),
+            None => "/".to_string(),
+        };
+        format!(r#""{}""#, json_escape(&parent))
+    };
+
+    Ok(format!(
+        r#"{{"path":"{}","parent":{},"entries":[{}],"total":{},"page":{},"total_pages":{},"limit":{},"sort":"{}","order":"{}","filter":{}}}"#,
+        json_escape(&display_path),
+        parent,
+        items.join(","),
+        listing.total_count,
+        listing.page,
+        listing.total_pages.max(1),
+        LISTING_PAGE_SIZE,
+        options.sort.as_str(),
+        options.order.as_str(),
+        options
+            .filter
+            .as_ref()
+            .map_or("null".to_string(), |f| format!(r#""{}""#, json_escape(f)))
+    ))
+}
+
 /// Format file size in human-readable format
-fn format_file_size(size: u64) -> String {
+pub(crate) fn format_file_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     const THRESHOLD: u64 = 1024;
 
@@ -196,7 +725,14 @@ fn compare_listing_entries(a: &ListingEntry, b: &ListingEntry) -> Ordering {
     match (a.is_dir, b.is_dir) {
         (true, false) => Ordering::Less,
         (false, true) => Ordering::Greater,
-        _ => cmp_case_insensitive_ascii(&a.file_name, &b.file_name),
+        _ => a.sort_key.cmp(&b.sort_key).then_with(|| {
+            let by_name = cmp_case_insensitive_ascii(&a.file_name, &b.file_name);
+            if a.reverse_name {
+                by_name.reverse()
+            } else {
+                by_name
+            }
+        }),
     }
 }
 
@@ -6,13 +6,33 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::archive::{ARCHIVE_ROUTE, handle_archive_request};
+use crate::checksum::{CHECKSUM_ROUTE, CHECKSUM_SHORT_ROUTE, handle_checksum_request};
 use crate::error::AppError;
+use crate::file_ops::{FILES_ROUTE, MKDIR_ROUTE, handle_file_operation, handle_mkdir_request};
 use crate::http::{Request, Response, ResponseBody};
-use crate::search::{SearchParams, perform_search};
+use crate::preview::{PREVIEW_ROUTE, PREVIEW_SHORT_ROUTE, handle_preview_request};
+use crate::qr::{QR_ROUTE, QR_SHORT_ROUTE, handle_qr_request};
+use crate::quota::UploadQuota;
+use crate::response::{ByteRange, parse_range_header};
+use crate::saved_search::{
+    SAVED_SEARCHES_ROUTE, SUGGEST_ROUTE, SavedSearches, default_saved_searches_path,
+    handle_saved_searches_request, handle_suggest_request, remember_search,
+};
+use crate::search::{
+    INDEX_STATUS_ROUTE, INDEX_STATUS_SHORT_ROUTE, SearchFilters, SearchMode, SearchParams,
+    SearchScope, SearchSort, SortOrder, perform_search_page_filtered,
+};
+use crate::share::{SHARE_ROUTE, SHARE_SHORT_ROUTE, ShareSigner, handle_share_request};
 use crate::upload::DirectUploadHandler;
-use crate::utils::parse_query_params;
-use log::{debug, error, info, trace};
-use std::time::Instant;
+use crate::upload_progress::{PROGRESS_ROUTE, handle_progress_request};
+use crate::upload_session::{SESSION_ROUTE, UploadSessionManager};
+use crate::utils::{decode_query_component, json_escape, normalize_path};
+use log::{debug, error, info, trace, warn};
+use std::time::{Instant, SystemTime};
+
+/// `Allow` header for files and directories when WebDAV is off
+const FILE_ALLOW: &str = "OPTIONS, GET, HEAD";
 
 /// Register all internal routes under /_irondrop/.
 pub fn register_internal_routes(
@@ -21,6 +41,56 @@ pub fn register_internal_routes(
     stats: Option<Arc<crate::server::ServerStats>>,
     base_dir: Option<Arc<std::path::PathBuf>>,
 ) {
+    let quota = Arc::new(
+        cli.as_deref()
+            .map(UploadQuota::from_cli)
+            .unwrap_or_default(),
+    );
+    let state = InternalState::new(quota, base_dir.as_deref().map(|dir| dir.as_path()));
+    register_internal_routes_with_state(router, cli, stats, base_dir, &state, None);
+}
+
+/// State the internal routes keep between requests
+///
+/// Created once per server and handed to every router rebuild, so a config
+/// reload neither forgets upload usage nor drops uploads in progress.
+#[derive(Clone)]
+pub struct InternalState {
+    /// Upload usage per client
+    pub quota: Arc<UploadQuota>,
+    /// Open resumable upload sessions
+    pub upload_sessions: Arc<UploadSessionManager>,
+    /// Saved searches of the served directory
+    pub saved_searches: Arc<SavedSearches>,
+}
+
+impl InternalState {
+    /// Starts the thread that expires idle upload sessions
+    pub fn new(quota: Arc<UploadQuota>, base_dir: Option<&std::path::Path>) -> Self {
+        let upload_sessions = Arc::new(UploadSessionManager::default());
+        UploadSessionManager::spawn_cleanup_thread(&upload_sessions);
+        Self {
+            quota,
+            upload_sessions,
+            saved_searches: Arc::new(SavedSearches::load(
+                base_dir.and_then(default_saved_searches_path),
+            )),
+        }
+    }
+}
+
+/// Like [`register_internal_routes`], but reusing existing [`InternalState`]
+/// so usage and upload sessions survive a router rebuild on config reload.
+/// `rate_limiter` feeds the tracked-client count shown on `/_status`.
+pub fn register_internal_routes_with_state(
+    router: &mut crate::router::Router,
+    cli: Option<Arc<crate::cli::Cli>>,
+    stats: Option<Arc<crate::server::ServerStats>>,
+    base_dir: Option<Arc<std::path::PathBuf>>,
+    state: &InternalState,
+    rate_limiter: Option<Arc<crate::server::RateLimiter>>,
+) {
+    let quota = state.quota.clone();
     // Health & status
     router.register_exact(
         "GET",
@@ -40,12 +110,35 @@ pub fn register_internal_routes(
         Box::new(|_| Ok(create_health_check_response())),
     );
 
+    // Liveness and readiness probes
+    router.register_exact(
+        "GET",
+        crate::health::LIVE_ROUTE,
+        Box::new(|_| Ok(crate::health::liveness_response())),
+    );
+    if let Some(base_arc) = base_dir.clone() {
+        let cli_for_ready = cli.clone();
+        router.register_exact(
+            "GET",
+            crate::health::READY_ROUTE,
+            Box::new(move |_| {
+                Ok(crate::health::readiness_response(
+                    &base_arc,
+                    cli_for_ready.as_deref(),
+                ))
+            }),
+        );
+    }
+
     // Legacy monitor endpoint compatibility
     if let Some(stats_arc) = stats.clone() {
+        let quota_for_monitor = quota.clone();
         router.register_exact(
             "GET",
             "/monitor",
-            Box::new(move |req: &Request| handle_monitor_request(req, Some(stats_arc.as_ref()))),
+            Box::new(move |req: &Request| {
+                handle_monitor_request(req, Some(stats_arc.as_ref()), Some(&quota_for_monitor))
+            }),
         );
     }
 
@@ -73,6 +166,16 @@ pub fn register_internal_routes(
         );
     }
 
+    // QR code for opening the share on a phone
+    for route in [QR_SHORT_ROUTE, QR_ROUTE] {
+        let cli_for_qr = cli.clone();
+        router.register_exact(
+            "GET",
+            route,
+            Box::new(move |req: &Request| handle_qr_request(req, cli_for_qr.as_deref())),
+        );
+    }
+
     // Upload endpoints
     if let Some(cli_arc) = cli.clone() {
         let cli_for_get = cli_arc.clone();
@@ -87,6 +190,7 @@ pub fn register_internal_routes(
         let cli_for_post = cli_arc.clone();
         let stats_for_post = stats.clone();
         let base_for_post = base_dir.clone();
+        let quota_for_post = quota.clone();
         router.register_exact(
             "POST",
             "/_irondrop/upload",
@@ -96,6 +200,228 @@ pub fn register_internal_routes(
                     Some(cli_for_post.as_ref()),
                     stats_for_post.as_deref(),
                     base_for_post.as_deref(),
+                    Some(&quota_for_post),
+                )
+            }),
+        );
+        router.register_prefix(
+            "GET",
+            format!("{PROGRESS_ROUTE}/"),
+            Box::new(handle_progress_request),
+        );
+        // With WebDAV on, its own PUT handler stores files instead
+        if let Some(base_arc) = base_dir.clone()
+            && cli_arc.enable_upload.unwrap_or(false)
+            && !cli_arc.enable_webdav.unwrap_or(false)
+        {
+            let cli_for_put = cli_arc.clone();
+            let stats_for_put = stats.clone();
+            let quota_for_put = quota.clone();
+            router.register_fallback(
+                "PUT",
+                Box::new(move |req: &Request| {
+                    handle_put_upload_request(
+                        req,
+                        cli_for_put.as_ref(),
+                        stats_for_put.as_deref(),
+                        base_arc.as_ref(),
+                        Some(&quota_for_put),
+                    )
+                }),
+            );
+        }
+    }
+
+    // File management (delete / move / rename)
+    if let (Some(cli_arc), Some(base_arc)) = (cli.clone(), base_dir.clone()) {
+        for method in ["POST", "DELETE"] {
+            let cli_for_files = cli_arc.clone();
+            let base_for_files = base_arc.clone();
+            router.register_exact(
+                method,
+                FILES_ROUTE,
+                Box::new(move |req: &Request| {
+                    handle_file_operation(req, cli_for_files.as_ref(), base_for_files.as_ref())
+                }),
+            );
+        }
+        router.register_exact(
+            "POST",
+            MKDIR_ROUTE,
+            Box::new(move |req: &Request| {
+                handle_mkdir_request(req, cli_arc.as_ref(), base_arc.as_ref())
+            }),
+        );
+    }
+
+    // Archives of selected entries
+    if let (Some(cli_arc), Some(base_arc)) = (cli.clone(), base_dir.clone()) {
+        router.register_exact(
+            "POST",
+            ARCHIVE_ROUTE,
+            Box::new(move |req: &Request| {
+                handle_archive_request(req, cli_arc.as_ref(), base_arc.as_ref())
+            }),
+        );
+    }
+
+    // Markdown and text previews
+    if let (Some(cli_arc), Some(base_arc)) = (cli.clone(), base_dir.clone()) {
+        for prefix in [PREVIEW_SHORT_ROUTE, PREVIEW_ROUTE] {
+            let cli_for_preview = cli_arc.clone();
+            let base_for_preview = base_arc.clone();
+            router.register_prefix(
+                "GET",
+                prefix,
+                Box::new(move |req: &Request| {
+                    handle_preview_request(req, cli_for_preview.as_ref(), base_for_preview.as_ref())
+                }),
+            );
+        }
+    }
+
+    // File checksums
+    if let (Some(cli_arc), Some(base_arc)) = (cli.clone(), base_dir.clone()) {
+        for route in [CHECKSUM_SHORT_ROUTE, CHECKSUM_ROUTE] {
+            let cli_for_checksum = cli_arc.clone();
+            let base_for_checksum = base_arc.clone();
+            router.register_exact(
+                "GET",
+                route,
+                Box::new(move |req: &Request| {
+                    handle_checksum_request(
+                        req,
+                        cli_for_checksum.as_ref(),
+                        base_for_checksum.as_ref(),
+                    )
+                }),
+            );
+        }
+    }
+
+    // Expiring share links
+    if let (Some(cli_arc), Some(base_arc)) = (cli.clone(), base_dir.clone())
+        && let Some(signer) = ShareSigner::from_cli(&cli_arc)
+    {
+        let signer = Arc::new(signer);
+        for route in [SHARE_SHORT_ROUTE, SHARE_ROUTE] {
+            let signer_for_share = signer.clone();
+            let cli_for_share = cli_arc.clone();
+            let base_for_share = base_arc.clone();
+            router.register_exact(
+                "GET",
+                route,
+                Box::new(move |req: &Request| {
+                    handle_share_request(
+                        req,
+                        signer_for_share.as_ref(),
+                        cli_for_share.as_ref(),
+                        base_for_share.as_ref(),
+                    )
+                }),
+            );
+        }
+    }
+
+    // Resumable upload sessions
+    if let Some(cli_arc) = cli.clone() {
+        let sessions = state.upload_sessions.clone();
+
+        let cli_for_create = cli_arc.clone();
+        let base_for_create = base_dir.clone();
+        let sessions_for_create = sessions.clone();
+        let quota_for_create = quota.clone();
+        router.register_exact(
+            "POST",
+            SESSION_ROUTE,
+            Box::new(move |req: &Request| {
+                let handler = resolve_upload_handler(
+                    req,
+                    cli_for_create.as_ref(),
+                    base_for_create.as_deref(),
+                )?;
+                // Reject up front if the declared length cannot fit the quota
+                let declared = req
+                    .headers
+                    .get("x-upload-length")
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .unwrap_or(0);
+                let client = UploadQuota::client_key(req);
+                if let Err(exceeded) = quota_for_create.check(&client, declared) {
+                    return Ok(into_response(exceeded.to_response(&client)));
+                }
+                sessions_for_create
+                    .create_session(req, &handler)
+                    .map(into_response)
+            }),
+        );
+
+        let cli_for_put = cli_arc.clone();
+        let stats_for_put = stats.clone();
+        let sessions_for_put = sessions.clone();
+        let quota_for_put = quota.clone();
+        router.register_prefix(
+            "PUT",
+            format!("{SESSION_ROUTE}/"),
+            Box::new(move |req: &Request| {
+                ensure_upload_enabled(cli_for_put.as_ref())?;
+                let chunk_len = req.body.as_ref().map_or(0, |b| b.len() as u64);
+                let client = UploadQuota::client_key(req);
+                let reservation = match quota_for_put.reserve(&client, chunk_len) {
+                    Ok(reservation) => reservation,
+                    Err(exceeded) => return Ok(into_response(exceeded.to_response(&client))),
+                };
+                let response = sessions_for_put.write_chunk(
+                    session_id_from_path(&req.path),
+                    req,
+                    cli_for_put.as_ref(),
+                    stats_for_put.as_deref(),
+                )?;
+                if response.status_code < 300 {
+                    reservation.commit();
+                }
+                Ok(into_response(response))
+            }),
+        );
+
+        let cli_for_status = cli_arc.clone();
+        let sessions_for_status = sessions.clone();
+        router.register_prefix(
+            "GET",
+            format!("{SESSION_ROUTE}/"),
+            Box::new(move |req: &Request| {
+                ensure_upload_enabled(cli_for_status.as_ref())?;
+                sessions_for_status
+                    .session_status(session_id_from_path(&req.path))
+                    .map(into_response)
+            }),
+        );
+
+        let cli_for_delete = cli_arc;
+        router.register_prefix(
+            "DELETE",
+            format!("{SESSION_ROUTE}/"),
+            Box::new(move |req: &Request| {
+                ensure_upload_enabled(cli_for_delete.as_ref())?;
+                sessions
+                    .abort_session(session_id_from_path(&req.path))
+                    .map(into_response)
+            }),
+        );
+    }
+
+    // Status dashboard (HTML + JSON feed)
+    if let Some(stats_arc) = stats.clone() {
+        let quota_for_status = quota.clone();
+        router.register_exact(
+            "GET",
+            "/_status",
+            Box::new(move |req: &Request| {
+                handle_status_request(
+                    req,
+                    &stats_arc,
+                    Some(&quota_for_status),
+                    rate_limiter.as_deref(),
                 )
             }),
         );
@@ -106,17 +432,47 @@ pub fn register_internal_routes(
         router.register_exact(
             "GET",
             "/_irondrop/monitor",
-            Box::new(move |req: &Request| handle_monitor_request(req, Some(stats_arc.as_ref()))),
+            Box::new(move |req: &Request| {
+                handle_monitor_request(req, Some(stats_arc.as_ref()), Some(&quota))
+            }),
         );
     }
 
-    // Search endpoint
+    // Search endpoint, with recent-query suggestions and saved searches
     if let Some(base_arc) = base_dir {
+        let saved = state.saved_searches.clone();
         router.register_exact(
             "GET",
             "/_irondrop/search",
-            Box::new(move |req: &Request| handle_search_api_request(req, &base_arc)),
+            Box::new(move |req: &Request| {
+                let mut response = handle_search_api_request(req, &base_arc)?;
+                remember_search(req, &mut response);
+                Ok(response)
+            }),
         );
+        for route in [INDEX_STATUS_SHORT_ROUTE, INDEX_STATUS_ROUTE] {
+            router.register_exact(
+                "GET",
+                route,
+                Box::new(|_: &Request| handle_index_status_request()),
+            );
+        }
+        let saved_for_suggest = saved.clone();
+        router.register_exact(
+            "GET",
+            SUGGEST_ROUTE,
+            Box::new(move |req: &Request| handle_suggest_request(req, &saved_for_suggest)),
+        );
+        for method in ["GET", "POST", "DELETE"] {
+            let saved_for_method = saved.clone();
+            router.register_exact(
+                method,
+                SAVED_SEARCHES_ROUTE,
+                Box::new(move |req: &Request| {
+                    handle_saved_searches_request(req, &saved_for_method)
+                }),
+            );
+        }
     }
 
     // Memory cleanup endpoint for long-running processes
@@ -258,7 +614,7 @@ pub fn handle_upload_form_request(
     }
 
     // Parse query parameters to get upload directory
-    let query_params = parse_query_params(&request.path);
+    let query_params = request.query_params();
     let upload_to = query_params.get("upload_to").map(String::as_str);
 
     let engine = crate::templates::TemplateEngine::global();
@@ -283,6 +639,7 @@ pub fn handle_upload_request(
     cli_config: Option<&crate::cli::Cli>,
     stats: Option<&crate::server::ServerStats>,
     base_dir: Option<&std::path::PathBuf>,
+    quota: Option<&UploadQuota>,
 ) -> Result<Response, AppError> {
     debug!(
         "Processing upload request: method={}, path={}",
@@ -309,9 +666,12 @@ pub fn handle_upload_request(
         return Err(AppError::upload_disabled());
     }
 
-    // Parse query parameters to get upload directory
-    let query_params = parse_query_params(&request.path);
-    let upload_to = query_params.get("upload_to").map(String::as_str);
+    // Parse query parameters (or X-Upload-To) to get upload directory
+    let query_params = request.query_params();
+    let upload_to = query_params
+        .get("upload_to")
+        .or_else(|| request.headers.get("x-upload-to"))
+        .map(String::as_str);
 
     // Resolve target directory
     let upload_handler = if let Some(base) = base_dir {
@@ -328,12 +688,64 @@ pub fn handle_upload_request(
         debug!("Using default upload handler without base directory");
         DirectUploadHandler::new(cli)?
     };
+    run_upload(request, upload_handler, stats, quota)
+}
+
+/// Handle `PUT /<dir>/<name>` (e.g. `curl -T file http://host/dir/`) while
+/// WebDAV is off: the body is uploaded into `<dir>` as `<name>` with the same
+/// checks and quotas as the upload form
+pub fn handle_put_upload_request(
+    request: &Request,
+    cli: &crate::cli::Cli,
+    stats: Option<&crate::server::ServerStats>,
+    base_dir: &std::path::Path,
+    quota: Option<&UploadQuota>,
+) -> Result<Response, AppError> {
+    let path_only = request.path.as_str();
+    if path_only.starts_with("/_irondrop/") {
+        return Err(AppError::NotFound);
+    }
+    ensure_upload_enabled(cli)?;
+    // The request parser has already percent-decoded the path
+    let (dir, name) = path_only.rsplit_once('/').unwrap_or(("", path_only));
+    if name.is_empty() {
+        debug!("PUT upload to {path_only} rejected, the URL names no file");
+        return Err(AppError::BadRequest);
+    }
+    let target_dir = crate::utils::resolve_upload_directory(base_dir, Some(dir))?;
+    if crate::fs::ignore_rules().excludes(target_dir.strip_prefix(base_dir).unwrap_or(&target_dir))
+    {
+        return Err(AppError::NotFound);
+    }
+    let mut upload_handler = DirectUploadHandler::new_with_directory(cli, target_dir)?;
+    upload_handler.set_filename(name.to_string());
+    run_upload(request, upload_handler, stats, quota)
+}
+
+/// Run an upload through `upload_handler`, charging it to the client's quota
+fn run_upload(
+    request: &Request,
+    mut upload_handler: DirectUploadHandler,
+    stats: Option<&crate::server::ServerStats>,
+    quota: Option<&UploadQuota>,
+) -> Result<Response, AppError> {
+    let client = UploadQuota::client_key(request);
+    let incoming = request.body.as_ref().map_or(0, |b| b.len() as u64);
+    let reservation = match quota
+        .map(|quota| quota.reserve(&client, incoming))
+        .transpose()
+    {
+        Ok(reservation) => reservation,
+        Err(exceeded) => return Ok(into_response(exceeded.to_response(&client))),
+    };
 
-    let mut upload_handler = upload_handler;
     let start_time = std::time::Instant::now();
 
     match upload_handler.handle_upload_with_stats(request, stats) {
         Ok(http_response) => {
+            if let Some(reservation) = reservation {
+                reservation.commit();
+            }
             let upload_time = start_time.elapsed();
             debug!("Upload completed successfully in {:?}", upload_time);
             trace!("Upload response status: {}", http_response.status_code);
@@ -359,18 +771,63 @@ pub fn handle_upload_request(
     }
 }
 
+fn ensure_upload_enabled(cli: &crate::cli::Cli) -> Result<(), AppError> {
+    if cli.enable_upload.unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(AppError::upload_disabled())
+    }
+}
+
+/// Build an upload handler for the directory named by `?upload_to=`
+fn resolve_upload_handler(
+    request: &Request,
+    cli: &crate::cli::Cli,
+    base_dir: Option<&std::path::PathBuf>,
+) -> Result<DirectUploadHandler, AppError> {
+    ensure_upload_enabled(cli)?;
+    match base_dir {
+        Some(base) => {
+            let query_params = request.query_params();
+            let upload_to = query_params.get("upload_to").map(String::as_str);
+            let target_dir = crate::utils::resolve_upload_directory(base, upload_to)?;
+            DirectUploadHandler::new_with_directory(cli, target_dir)
+        }
+        None => DirectUploadHandler::new(cli),
+    }
+}
+
+/// Extract `<id>` from `/_irondrop/upload/session/<id>`
+fn session_id_from_path(path: &str) -> &str {
+    path.strip_prefix(SESSION_ROUTE)
+        .unwrap_or("")
+        .trim_matches('/')
+}
+
+/// Convert a buffered `HttpResponse` into the router's `Response` type
+fn into_response(http_response: crate::response::HttpResponse) -> Response {
+    let mut headers = HashMap::new();
+    for (k, v) in http_response.headers {
+        headers.insert(k, v);
+    }
+    Response {
+        status_code: http_response.status_code,
+        status_text: http_response.status_text,
+        headers,
+        body: ResponseBody::Binary(http_response.body),
+    }
+}
+
 pub fn handle_monitor_request(
     request: &Request,
     stats: Option<&crate::server::ServerStats>,
+    quota: Option<&UploadQuota>,
 ) -> Result<Response, AppError> {
     debug!("Handling monitor request for path: {}", request.path);
-    trace!(
-        "Monitor request query params: {:?}",
-        parse_query_params(&request.path)
-    );
+    trace!("Monitor request query params: {:?}", request.query_params());
     // Check if JSON response is requested
-    if request.path.contains("json=1") {
-        return Ok(create_monitor_json(stats));
+    if request.query_params().get("json").map(String::as_str) == Some("1") {
+        return Ok(create_monitor_json(stats, quota));
     }
 
     // Return HTML response
@@ -389,58 +846,109 @@ pub fn handle_monitor_request(
         }),
         Err(_) => {
             // Fallback to JSON if HTML rendering fails
-            Ok(create_monitor_json(stats))
+            Ok(create_monitor_json(stats, quota))
         }
     }
 }
 
-fn create_monitor_json(stats: Option<&crate::server::ServerStats>) -> Response {
-    if let Some(s) = stats {
-        let (total, successful, errors, bytes, uptime) = s.get_stats();
-        let up = s.get_upload_stats();
-        let (current_memory, peak_memory, memory_available) = s.get_memory_usage();
-
-        // Build memory section based on availability
-        let memory_section = if memory_available {
-            let current_bytes = current_memory.unwrap_or(0);
-            let peak_bytes = peak_memory.unwrap_or(0);
-            format!(
-                r#""memory":{{"available":true,"current_bytes":{},"peak_bytes":{},"current_mb":{:.2},"peak_mb":{:.2}}}"#,
-                current_bytes,
-                peak_bytes,
-                current_bytes as f64 / 1024.0 / 1024.0,
-                peak_bytes as f64 / 1024.0 / 1024.0
-            )
-        } else {
-            r#""memory":{"available":false,"current_bytes":null,"peak_bytes":null,"current_mb":null,"peak_mb":null}"#.to_string()
+/// Handle `/_status`: a lightweight HTML dashboard, or its JSON feed
+/// (`?json=1`) with the monitor counters plus rate limiter, search index and
+/// disk space stats
+pub fn handle_status_request(
+    request: &Request,
+    stats: &crate::server::ServerStats,
+    quota: Option<&UploadQuota>,
+    rate_limiter: Option<&crate::server::RateLimiter>,
+) -> Result<Response, AppError> {
+    debug!("Handling status request for path: {}", request.path);
+    if request.query_params().get("json").map(String::as_str) == Some("1") {
+        let rate_limiter_section = match rate_limiter {
+            Some(limiter) => {
+                let (entries, memory_bytes) = limiter.get_memory_stats();
+                let routes: Vec<String> = crate::server::RouteClass::ALL
+                    .iter()
+                    .map(|&class| {
+                        format!(
+                            r#""{}":{}"#,
+                            class.as_str(),
+                            limiter
+                                .route_limit(class)
+                                .map_or("null".to_string(), |limit| limit.to_string())
+                        )
+                    })
+                    .collect();
+                format!(
+                    r#""rate_limiter":{{"available":true,"entries":{entries},"memory_bytes":{memory_bytes},"limits":{{"requests_per_minute":{},"concurrent_per_ip":{},"routes":{{{}}}}}}}"#,
+                    limiter.max_requests_per_minute(),
+                    limiter.max_concurrent_per_ip(),
+                    routes.join(",")
+                )
+            }
+            None => r#""rate_limiter":{"available":false,"entries":null,"memory_bytes":null,"limits":null}"#
+                .to_string(),
         };
-
-        let json = format!(
-            r#"{{"requests":{{"total":{total},"successful":{successful},"errors":{errors}}},"downloads":{{"bytes_served":{bytes}}},"uptime_secs":{},{},"uploads":{{"total_uploads":{},"successful_uploads":{},"failed_uploads":{},"files_uploaded":{},"upload_bytes":{},"average_upload_size":{},"largest_upload":{},"concurrent_uploads":{},"average_processing_ms":{:.2},"success_rate":{:.2}}}}}"#,
-            uptime.as_secs(),
-            memory_section,
-            up.total_uploads,
-            up.successful_uploads,
-            up.failed_uploads,
-            up.files_uploaded,
-            up.upload_bytes,
-            up.average_upload_size,
-            up.largest_upload,
-            up.concurrent_uploads,
-            up.average_processing_time,
-            up.success_rate
-        );
-        return Response {
-            status_code: 200,
-            status_text: "OK".into(),
-            headers: {
-                let mut h = HashMap::new();
-                h.insert("Content-Type".into(), "application/json".into());
-                h.insert("Cache-Control".into(), "no-cache".into());
-                h
+        // The index is locked while it builds; report progress instead
+        let search_section = match crate::search::index_status() {
+            Some(status) if status.state != crate::search::IndexState::Ready => format!(
+                r#""search":{{"available":true,"entries":null,"memory_bytes":null,"updating":true,"state":"{}","entries_scanned":{}}}"#,
+                status.state.as_str(),
+                status.entries_scanned
+            ),
+            _ => match crate::search::get_search_index_stats() {
+                Some((entries, memory_bytes, updating)) => format!(
+                    r#""search":{{"available":true,"entries":{entries},"memory_bytes":{memory_bytes},"updating":{updating},"state":"ready"}}"#
+                ),
+                None => {
+                    r#""search":{"available":false,"entries":null,"memory_bytes":null,"updating":false,"state":"unavailable"}"#
+                        .to_string()
+                }
             },
-            body: ResponseBody::Text(json),
         };
+        return Ok(json_no_cache_response(monitor_json(
+            stats,
+            quota,
+            &format!(
+                ",{rate_limiter_section},{search_section},{},{}",
+                crate::disk_space::status_json(),
+                crate::bandwidth::status_json()
+            ),
+        )));
+    }
+
+    let html = crate::templates::TemplateEngine::global().render_status_page()?;
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".into(),
+        headers: {
+            let mut h = HashMap::new();
+            h.insert("Content-Type".into(), "text/html; charset=utf-8".into());
+            h.insert("Cache-Control".into(), "no-cache".into());
+            h
+        },
+        body: ResponseBody::Text(html),
+    })
+}
+
+fn json_no_cache_response(json: String) -> Response {
+    Response {
+        status_code: 200,
+        status_text: "OK".into(),
+        headers: {
+            let mut h = HashMap::new();
+            h.insert("Content-Type".into(), "application/json".into());
+            h.insert("Cache-Control".into(), "no-cache".into());
+            h
+        },
+        body: ResponseBody::Text(json),
+    }
+}
+
+fn create_monitor_json(
+    stats: Option<&crate::server::ServerStats>,
+    quota: Option<&UploadQuota>,
+) -> Response {
+    if let Some(s) = stats {
+        return json_no_cache_response(monitor_json(s, quota, ""));
     }
     Response {
         status_code: 503,
@@ -454,6 +962,120 @@ fn create_monitor_json(stats: Option<&crate::server::ServerStats>) -> Response {
     }
 }
 
+/// Monitor counters as a JSON object; `extra` is appended before the closing brace
+fn monitor_json(
+    s: &crate::server::ServerStats,
+    quota: Option<&UploadQuota>,
+    extra: &str,
+) -> String {
+    let (total, successful, errors, bytes, uptime) = s.get_stats();
+    let timeouts = s.get_timeout_count();
+    let up = s.get_upload_stats();
+    let (current_memory, peak_memory, memory_available) = s.get_memory_usage();
+
+    // Build memory section based on availability
+    let memory_section = if memory_available {
+        let current_bytes = current_memory.unwrap_or(0);
+        let peak_bytes = peak_memory.unwrap_or(0);
+        format!(
+            r#""memory":{{"available":true,"current_bytes":{},"peak_bytes":{},"current_mb":{:.2},"peak_mb":{:.2}}}"#,
+            current_bytes,
+            peak_bytes,
+            current_bytes as f64 / 1024.0 / 1024.0,
+            peak_bytes as f64 / 1024.0 / 1024.0
+        )
+    } else {
+        r#""memory":{"available":false,"current_bytes":null,"peak_bytes":null,"current_mb":null,"peak_mb":null}"#.to_string()
+    };
+
+    format!(
+        r#"{{"requests":{{"total":{total},"successful":{successful},"errors":{errors},"timeouts":{timeouts}}},"downloads":{{"bytes_served":{bytes},{slots}}},{workers},{file_cache},"uptime_secs":{},{},"uploads":{{"total_uploads":{},"successful_uploads":{},"failed_uploads":{},"files_uploaded":{},"upload_bytes":{},"average_upload_size":{},"largest_upload":{},"concurrent_uploads":{},"average_processing_ms":{:.2},"success_rate":{:.2}}}{}{}}}"#,
+        uptime.as_secs(),
+        memory_section,
+        up.total_uploads,
+        up.successful_uploads,
+        up.failed_uploads,
+        up.files_uploaded,
+        up.upload_bytes,
+        up.average_upload_size,
+        up.largest_upload,
+        up.concurrent_uploads,
+        up.average_processing_time,
+        up.success_rate,
+        quota
+            .map(|q| format!(",{}", q.usage_json()))
+            .unwrap_or_default(),
+        extra,
+        slots = s.download_slots().usage_json(),
+        workers = s.workers().usage_json(),
+        file_cache = s.file_cache().usage_json()
+    )
+}
+
+/// Cookie remembering the listing view picked with `?view=` for the session
+const LISTING_VIEW_COOKIE: &str = "irondrop_view";
+
+/// Page served for directories with `--index` / `--spa`
+const INDEX_FILE: &str = "index.html";
+
+/// Listing options from `?p=`, `?sort=`, `?order=`, `?filter=` and `?view=`;
+/// unknown sort fields, directions or views are rejected like in search.
+/// Without `?view=` the view remembered in [`LISTING_VIEW_COOKIE`] is used.
+fn listing_options(
+    query_params: &HashMap<String, String>,
+    request: &Request,
+) -> Result<crate::fs::ListingOptions, AppError> {
+    use crate::fs::{ListingOptions, ListingSort, ListingView};
+
+    let page = query_params
+        .get("p")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(1);
+    let sort = match query_params.get("sort") {
+        Some(value) => ListingSort::parse(value).ok_or_else(|| {
+            debug!("Unknown listing sort field: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => ListingSort::default(),
+    };
+    let order = match query_params.get("order") {
+        Some(value) => SortOrder::parse(value).ok_or_else(|| {
+            debug!("Unknown listing sort order: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => SortOrder::Asc,
+    };
+    let filter = query_params
+        .get("filter")
+        .map(|f| f.trim())
+        .filter(|f| !f.is_empty())
+        .map(str::to_string);
+    let view = match query_params.get("view") {
+        Some(value) => ListingView::parse(value).ok_or_else(|| {
+            debug!("Unknown listing view: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => request
+            .headers
+            .get("cookie")
+            .into_iter()
+            .flat_map(|cookies| cookies.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, _)| *name == LISTING_VIEW_COOKIE)
+            .and_then(|(_, value)| ListingView::parse(value.trim()))
+            .unwrap_or_default(),
+    };
+
+    Ok(ListingOptions {
+        page,
+        sort,
+        order,
+        filter,
+        view,
+        ..ListingOptions::default()
+    })
+}
+
 /// Handle file and directory serving requests
 /// This moves the file serving logic from http.rs to handlers.rs for better separation of concerns
 pub fn handle_file_request(
@@ -462,14 +1084,15 @@ pub fn handle_file_request(
     allowed_extensions: &[glob::Pattern],
     chunk_size: usize,
     cli_config: Option<&crate::cli::Cli>,
+    file_cache: Option<&crate::file_cache::FileCache>,
 ) -> Result<Response, AppError> {
     debug!(
         "Handling file request: method={}, path={}",
         request.method, request.path
     );
     trace!("Base directory: {:?}, chunk size: {}", base_dir, chunk_size);
-    use crate::fs::generate_directory_listing;
-    use crate::response::get_mime_type;
+    use crate::fs::{generate_directory_listing_json, generate_directory_listing_with_options};
+    use crate::response::{FileContentType, get_file_content_type};
     use log::debug;
     use std::path::PathBuf;
 
@@ -488,10 +1111,11 @@ pub fn handle_file_request(
     );
 
     // Handle different methods appropriately
+    let webdav_enabled = cli_config.and_then(|c| c.enable_webdav).unwrap_or(false);
     match request.method.as_str() {
-        "GET" => {
-            // GET requests are handled normally
-            trace!("Processing GET request");
+        "GET" | "HEAD" => {
+            // HEAD builds the same response; the HTTP layer drops the body
+            trace!("Processing {} request", request.method);
         }
         "POST" => {
             // For now, POST requests are only accepted but not fully implemented
@@ -499,25 +1123,48 @@ pub fn handle_file_request(
             // For the current implementation, we'll allow POST but treat it like GET for basic functionality
             debug!("POST request received, treating as GET for basic functionality");
         }
+        "OPTIONS" if !webdav_enabled => {
+            let mut headers = HashMap::new();
+            headers.insert("Allow".to_string(), FILE_ALLOW.to_string());
+            return Ok(Response {
+                status_code: 204,
+                status_text: "No Content".to_string(),
+                headers,
+                body: ResponseBody::Text(String::new()),
+            });
+        }
         "OPTIONS" | "PROPFIND" | "PROPPATCH" | "MKCOL" | "PUT" | "DELETE" | "COPY" | "MOVE"
         | "LOCK" | "UNLOCK" => {
-            if !cli_config.and_then(|c| c.enable_webdav).unwrap_or(false) {
+            if !webdav_enabled {
                 debug!("WebDAV method rejected because WebDAV is disabled");
-                return Err(AppError::MethodNotAllowed);
+                return Err(AppError::MethodNotAllowedFor(FILE_ALLOW.to_string()));
             }
             return crate::webdav::handle_webdav_request(request, base_dir, allowed_extensions);
         }
         _ => {
             debug!("Method not allowed: {}", request.method);
-            return Err(AppError::MethodNotAllowed);
+            let allow = if webdav_enabled {
+                crate::webdav::allow_header_value()
+            } else {
+                FILE_ALLOW
+            };
+            return Err(AppError::MethodNotAllowedFor(allow.to_string()));
         }
     }
 
-    let requested_path = PathBuf::from(request.path.strip_prefix('/').unwrap_or(&request.path));
+    let path_only = request.path.as_str();
+    let requested_path = PathBuf::from(path_only.strip_prefix('/').unwrap_or(path_only));
     debug!("Requested path: {}", requested_path.display());
 
-    let safe_path = normalize_path(&requested_path)?;
-    let full_path = base_dir.join(safe_path);
+    let mut safe_path = normalize_path(&requested_path)?;
+    let mut full_path = base_dir.join(&safe_path);
+    if !full_path.exists()
+        && let Some(variant) = crate::utils::find_unicode_variant(base_dir, &safe_path)
+    {
+        debug!("Serving {} for {}", variant.display(), safe_path.display());
+        full_path = base_dir.join(&variant);
+        safe_path = variant;
+    }
 
     debug!("Full resolved path: {}", full_path.display());
     trace!(
@@ -531,12 +1178,59 @@ pub fn handle_file_request(
         return Err(AppError::Forbidden);
     }
 
-    if !full_path.exists() {
-        debug!("Path does not exist: {}", full_path.display());
-        trace!("File system check failed for path");
+    // A device or trailing-dot name would open something else on Windows
+    if crate::utils::has_unusable_component(&safe_path) {
+        debug!("Unusable file name requested: {}", safe_path.display());
         return Err(AppError::NotFound);
     }
 
+    if crate::fs::ignore_rules().excludes(&safe_path) {
+        debug!("Excluded path requested: {}", safe_path.display());
+        return Err(AppError::NotFound);
+    }
+
+    let spa = cli_config.and_then(|cli| cli.spa).unwrap_or(false);
+    let static_site = spa || cli_config.and_then(|cli| cli.index).unwrap_or(false);
+    if !full_path.exists() {
+        if let Some(response) = crate::redirects::lookup(&request.path, request.query.as_deref()) {
+            return Ok(response);
+        }
+        // Client-side routes of a single-page app: pages get the app itself,
+        // while missing scripts and images stay a 404
+        let index = base_dir.join(INDEX_FILE);
+        let wants_page = request
+            .headers
+            .get("accept")
+            .is_some_and(|accept| accept.contains("text/html"));
+        let is_read = matches!(request.method.as_str(), "GET" | "HEAD");
+        if !(spa && is_read && wants_page && index.is_file()) {
+            debug!("Path does not exist: {}", full_path.display());
+            trace!("File system check failed for path");
+            return not_found_page(base_dir, cli_config);
+        }
+        debug!("SPA fallback to {} for {}", INDEX_FILE, path_only);
+        full_path = index;
+    }
+
+    let symlinks = crate::fs::SymlinkRules::new(
+        cli_config
+            .and_then(|cli| cli.follow_symlinks.as_deref())
+            .and_then(crate::fs::SymlinkPolicy::parse)
+            .unwrap_or_default(),
+        base_dir,
+    );
+    symlinks.check(&full_path)?;
+
+    // Static site mode never lists a directory; canonical URLs end in '/'
+    if static_site && full_path.is_dir() && path_only.ends_with('/') {
+        full_path.push(INDEX_FILE);
+        if !full_path.is_file() {
+            debug!("No {} in {}", INDEX_FILE, path_only);
+            return not_found_page(base_dir, cli_config);
+        }
+        symlinks.check(&full_path)?;
+    }
+
     trace!("Path exists, checking if directory or file");
 
     if full_path.is_dir() {
@@ -544,9 +1238,13 @@ pub fn handle_file_request(
         trace!("Directory listing requested for path: {}", request.path);
 
         // Redirect directory paths without trailing slash to canonical URL with slash
-        if !request.path.ends_with('/') {
+        if !path_only.ends_with('/') {
             let mut headers = HashMap::new();
-            let canonical = crate::templates::prefixed(&format!("{}/", request.path));
+            let encoded = crate::utils::percent_encode_path(std::path::Path::new(path_only));
+            let canonical = match &request.query {
+                Some(query) => crate::templates::prefixed(&format!("{encoded}/?{query}")),
+                None => crate::templates::prefixed(&format!("{encoded}/")),
+            };
             headers.insert("Location".to_string(), canonical);
             return Ok(Response {
                 status_code: 301,
@@ -559,23 +1257,75 @@ pub fn handle_file_request(
         // Only serve directory listings for GET requests
         if request.method == "POST" {
             debug!("POST method not allowed for directory listings");
-            return Err(AppError::MethodNotAllowed);
+            return Err(AppError::MethodNotAllowedFor(FILE_ALLOW.to_string()));
+        }
+
+        if let Some(download) = request.query_params().get("download") {
+            let format = crate::archive::ArchiveFormat::parse(download).ok_or_else(|| {
+                debug!("Unknown archive format: {download}");
+                AppError::BadRequest
+            })?;
+            return Ok(crate::archive::directory_response(
+                &full_path,
+                path_only,
+                allowed_extensions,
+                symlinks,
+                cli_config,
+                format,
+            ));
         }
 
         // Create a config from CLI if available
+        // Read-only mode, and accounts without the role, hide the upload and management controls
+        let read_only = crate::read_only::is_enabled();
+        let role = crate::users::request_role(request);
         let config = cli_config.map(|cli| crate::config::Config {
             listen: "127.0.0.1".to_string(),
             port: 8080,
             threads: 8,
             chunk_size: 1024,
             directory: cli.directory.clone(),
-            enable_upload: cli.enable_upload.unwrap_or(false),
+            enable_upload: cli.enable_upload.unwrap_or(false)
+                && !read_only
+                && role >= crate::users::Role::Upload,
             max_upload_size: cli.max_upload_size_bytes(),
+            upload_quota_daily: cli.upload_quota_daily,
+            upload_scan_command: cli.upload_scan_command.clone(),
+            upload_organize: cli
+                .upload_organize
+                .clone()
+                .unwrap_or_else(|| "flat".to_string()),
+            upload_conflict: cli
+                .upload_conflict
+                .clone()
+                .unwrap_or_else(|| "rename".to_string()),
+            upload_disk_reserve: cli.upload_disk_reserve.unwrap_or(0),
+            upload_dedup: cli.upload_dedup.unwrap_or(false),
+            upload_quota_total: cli.upload_quota_total,
             enable_webdav: cli.enable_webdav.unwrap_or(false),
             disable_rate_limit: cli.enable_webdav.unwrap_or(false)
                 && cli.disable_rate_limit.unwrap_or(false),
+            rate_limit: cli.rate_limit,
+            rate_limit_concurrent: cli.rate_limit_concurrent,
+            rate_limit_upload: cli.rate_limit_upload,
+            rate_limit_search: cli.rate_limit_search,
             username: cli.username.clone(),
             password: cli.password.clone(),
+            password_hash: cli.password_hash.clone(),
+            auth_max_failures: cli
+                .auth_max_failures
+                .unwrap_or(crate::middleware::DEFAULT_AUTH_MAX_FAILURES),
+            auth_lockout_secs: cli
+                .auth_lockout_secs
+                .unwrap_or(crate::middleware::DEFAULT_AUTH_LOCKOUT_SECS),
+            auth_tokens: Vec::new(),
+            auth_paths: Vec::new(),
+            users: Vec::new(),
+            auth_sessions: cli.auth_sessions.unwrap_or(false),
+            session_lifetime: cli
+                .session_lifetime
+                .unwrap_or(crate::session::DEFAULT_SESSION_LIFETIME),
+            session_secret: None,
             allowed_extensions: cli
                 .allowed_extensions
                 .as_ref()
@@ -583,49 +1333,235 @@ pub fn handle_file_request(
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect(),
+            allowed_ips: Vec::new(),
+            denied_ips: Vec::new(),
+            trusted_proxies: Vec::new(),
+            log_rejected_ips: false,
+            hide_dotfiles: cli.hide_dotfiles.unwrap_or(true),
+            ignore_patterns: cli
+                .ignore_patterns
+                .as_deref()
+                .map(|list| list.split(',').map(|p| p.trim().to_string()).collect())
+                .unwrap_or_default(),
+            follow_symlinks: cli
+                .follow_symlinks
+                .clone()
+                .unwrap_or_else(|| "always".to_string()),
+            enable_file_management: cli.enable_file_management.unwrap_or(false)
+                && !read_only
+                && role >= crate::users::Role::Admin,
+            enable_edit: cli.enable_edit.unwrap_or(false),
+            index: cli.index.unwrap_or(false),
+            spa: cli.spa.unwrap_or(false),
+            redirects: Vec::new(),
+            redirects_file: None,
+            not_found_page: cli.not_found_page.clone(),
+            read_only,
+            mime_types: Vec::new(),
+            default_charset: String::new(),
+            force_download: None,
             verbose: cli.verbose.unwrap_or(false),
             detailed_logging: cli.detailed_logging.unwrap_or(false),
             log_dir: cli.log_dir.clone(),
+            log_rotate_size: cli.log_rotate_size,
+            log_rotate_age: cli.log_rotate_age,
+            log_rotate_keep: cli
+                .log_rotate_keep
+                .unwrap_or(crate::log_rotation::DEFAULT_ROTATE_KEEP),
+            log_rotate_gzip: cli.log_rotate_gzip.unwrap_or(false),
+            audit: cli.audit.unwrap_or(false),
+            audit_file: cli.audit_file.clone(),
             ssl_cert: cli.ssl_cert.clone(),
             ssl_key: cli.ssl_key.clone(),
+            http2: cli.http2.unwrap_or(false),
             base_path: cli.base_path.clone().unwrap_or_default(),
+            template_dir: cli.template_dir.clone(),
+            theme: cli.theme.clone().unwrap_or_else(|| "dark".to_string()),
+            custom_css: cli.custom_css.clone(),
+            compression: cli.compression.unwrap_or(true),
+            compression_min_size: cli
+                .compression_min_size
+                .unwrap_or(crate::compression::DEFAULT_MIN_COMPRESS_SIZE),
+            search_content: cli.search_content.unwrap_or(false),
+            search_content_extensions: Vec::new(),
+            search_content_max_size: cli
+                .search_content_max_size
+                .unwrap_or(crate::search::DEFAULT_CONTENT_MAX_SIZE),
+            search_exact_sizes: cli.search_exact_sizes.unwrap_or(false),
+            search_trigram_budget: cli
+                .search_trigram_budget
+                .unwrap_or(crate::search::DEFAULT_TRIGRAM_BUDGET),
+            search_exclude: Vec::new(),
+            search_max_depth: cli
+                .search_max_depth
+                .unwrap_or(crate::search::DEFAULT_MAX_DEPTH),
+            search_depth_limits: Vec::new(),
+            read_timeout: cli
+                .read_timeout
+                .unwrap_or(crate::http::DEFAULT_READ_TIMEOUT_SECS),
+            write_timeout: cli
+                .write_timeout
+                .unwrap_or(crate::http::DEFAULT_WRITE_TIMEOUT_SECS),
+            header_timeout: cli
+                .header_timeout
+                .unwrap_or(crate::http::DEFAULT_HEADER_TIMEOUT_SECS),
+            max_request_body: cli
+                .max_request_body
+                .unwrap_or(crate::http::DEFAULT_MAX_REQUEST_BODY),
+            max_bandwidth: cli.max_bandwidth,
+            per_conn_bandwidth: cli.per_conn_bandwidth,
+            max_upload_bandwidth: cli.max_upload_bandwidth,
+            per_upload_bandwidth: cli.per_upload_bandwidth,
+            max_concurrent_downloads: cli.max_concurrent_downloads,
+            download_queue: cli.download_queue.unwrap_or(0),
+            max_workers: cli.max_workers,
+            worker_queue: cli
+                .worker_queue
+                .unwrap_or(crate::worker_pool::DEFAULT_QUEUE),
+            tcp_backlog: cli.tcp_backlog.unwrap_or(crate::listener::DEFAULT_BACKLOG),
+            tcp_nodelay: cli.tcp_nodelay.unwrap_or(false),
+            tcp_reuse_address: cli.tcp_reuse_address.unwrap_or(cfg!(unix)),
+            tcp_reuse_port: cli.tcp_reuse_port.unwrap_or(false),
+            tcp_keepalive: cli.tcp_keepalive,
+            tcp_keepalive_interval: cli.tcp_keepalive_interval,
+            tcp_keepalive_retries: cli.tcp_keepalive_retries,
+            accept_batch: cli
+                .accept_batch
+                .unwrap_or(crate::listener::DEFAULT_ACCEPT_BATCH),
+            mmap_cache_size: cli.mmap_cache_size.unwrap_or(0),
+            mmap_max_file: cli
+                .mmap_max_file
+                .unwrap_or(crate::mmap_cache::DEFAULT_MAX_FILE_BYTES),
+            file_cache_size: cli.file_cache_size.unwrap_or(0),
+            file_cache_max_file: cli
+                .file_cache_max_file
+                .unwrap_or(crate::file_cache::DEFAULT_MAX_FILE_BYTES),
+            mdns: cli.mdns.unwrap_or(false),
+            cors_origins: Vec::new(),
+            cors_methods: Vec::new(),
+            cors_headers: Vec::new(),
+            cors_max_age: cli
+                .cors_max_age
+                .unwrap_or(crate::middleware::DEFAULT_CORS_MAX_AGE),
+            cors_credentials: cli.cors_credentials.unwrap_or(false),
+            security_headers: cli.security_headers.unwrap_or(true),
+            content_security_policy: String::new(),
+            frame_options: String::new(),
+            referrer_policy: String::new(),
+            hsts_max_age: cli
+                .hsts_max_age
+                .unwrap_or(crate::middleware::DEFAULT_HSTS_MAX_AGE),
+            webhook_urls: Vec::new(),
+            webhook_events: Vec::new(),
+            webhook_retries: cli
+                .webhook_retries
+                .unwrap_or(crate::hooks::DEFAULT_WEBHOOK_RETRIES),
+            webhook_queue_size: cli
+                .webhook_queue_size
+                .unwrap_or(crate::hooks::DEFAULT_WEBHOOK_QUEUE_SIZE),
+            share_secret: None,
+            share_max_ttl: cli
+                .share_max_ttl
+                .unwrap_or(crate::share::DEFAULT_SHARE_MAX_TTL),
         });
 
-        // Extract page from query parameters
-        let query_params: HashMap<String, String> =
-            if let Some(query_string) = request.path.split('?').nth(1) {
+        // Extract page, sort and filter from query parameters
+        let query_params: HashMap<String, String> = request
+            .query
+            .as_deref()
+            .map(|query_string| {
                 query_string
                     .split('&')
                     .filter_map(|param| {
                         let mut parts = param.splitn(2, '=');
                         match (parts.next(), parts.next()) {
-                            (Some(key), Some(value)) => Some((url_decode(key), url_decode(value))),
+                            (Some(key), Some(value)) => {
+                                Some((decode_query_component(key), decode_query_component(value)))
+                            }
                             _ => None,
                         }
                     })
                     .collect()
-            } else {
-                HashMap::new()
-            };
-        let page = query_params
-            .get("p")
-            .and_then(|v| v.parse::<usize>().ok())
-            .unwrap_or(1);
+            })
+            .unwrap_or_default();
+        let options = crate::fs::ListingOptions {
+            symlinks,
+            ..listing_options(&query_params, request)?
+        };
+
+        let wants_json = query_params.get("format").is_some_and(|f| f == "json")
+            || request
+                .headers
+                .get("accept")
+                .is_some_and(|accept| accept.contains("application/json"));
+        let content_type = if wants_json {
+            "application/json; charset=utf-8"
+        } else {
+            "text/html; charset=utf-8"
+        };
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), content_type.to_string());
+        headers.insert("Vary".to_string(), "Accept, Cookie".to_string());
+        // A session cookie, so the choice lasts until the browser closes
+        if query_params.contains_key("view") {
+            let base = crate::templates::base_path();
+            headers.insert(
+                "Set-Cookie".to_string(),
+                format!(
+                    "{LISTING_VIEW_COOKIE}={}; Path={}; SameSite=Lax",
+                    options.view.as_str(),
+                    if base.is_empty() { "/" } else { &base }
+                ),
+            );
+        }
 
-        let html_content =
-            generate_directory_listing(&full_path, &request.path, config.as_ref(), page)?;
+        // Adding, removing or renaming an entry changes the folder's mtime,
+        // so clients can revalidate without the listing being generated
+        let modified = std::fs::metadata(&full_path)?.modified().ok();
+        if let Some(modified) = modified {
+            let etag = weak_etag((
+                modified,
+                &request.path,
+                &request.query,
+                wants_json,
+                options.view.as_str(),
+                config.as_ref().map(|config| {
+                    (
+                        config.enable_upload,
+                        config.enable_file_management,
+                        config.enable_edit,
+                        config.read_only,
+                    )
+                }),
+            ));
+            headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+            headers.insert("ETag".to_string(), etag.clone());
+            if is_not_modified(request, &etag, None) {
+                debug!("Client listing of {} is current", full_path.display());
+                return Ok(Response {
+                    status_code: 304,
+                    status_text: "Not Modified".to_string(),
+                    headers,
+                    body: ResponseBody::StaticText(""),
+                });
+            }
+        }
+
+        let content = if wants_json {
+            generate_directory_listing_json(&full_path, path_only, &options)?
+        } else {
+            generate_directory_listing_with_options(
+                &full_path,
+                path_only,
+                config.as_ref(),
+                &options,
+            )?
+        };
         Ok(Response {
             status_code: 200,
             status_text: "OK".to_string(),
-            headers: {
-                let mut map = HashMap::new();
-                map.insert(
-                    "Content-Type".to_string(),
-                    "text/html; charset=utf-8".to_string(),
-                );
-                map
-            },
-            body: ResponseBody::Text(html_content),
+            headers,
+            body: ResponseBody::Text(content),
         })
     } else if full_path.is_file() {
         debug!("Serving file: {}", full_path.display());
@@ -641,97 +1577,215 @@ pub fn handle_file_request(
 
         trace!("File extension validation passed");
 
-        let size = std::fs::metadata(&full_path)?.len();
-        let mime_type = get_mime_type(&full_path);
+        let metadata = std::fs::metadata(&full_path)?;
+        let size = metadata.len();
+        let FileContentType {
+            content_type: mime_type,
+            attachment,
+        } = get_file_content_type(&full_path, cli_config);
+        let modified = metadata.modified().ok();
+        let last_modified = modified.and_then(crate::utils::format_http_date);
+        let etag = crate::utils::etag_for_resource(&full_path, &metadata);
 
         debug!(
             "File details - size: {} bytes, mime_type: {}",
             size, mime_type
         );
         trace!("Chunk size for streaming: {}", chunk_size);
-        Ok(Response {
-            status_code: 200,
-            status_text: "OK".to_string(),
-            headers: {
-                let mut map = HashMap::new();
-                map.insert("Content-Type".to_string(), mime_type.to_string());
-                map.insert("Content-Length".to_string(), size.to_string());
-                map.insert("Accept-Ranges".to_string(), "bytes".to_string());
-                map.insert(
-                    "Cache-Control".to_string(),
-                    "public, max-age=3600".to_string(),
-                );
-                map
-            },
-            body: ResponseBody::Stream(crate::http::StreamBody {
+
+        let media = crate::response::is_media_type(&mime_type);
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), mime_type);
+        if attachment {
+            headers.insert("Content-Disposition".to_string(), "attachment".to_string());
+        } else if media {
+            // Played in place; "Save as" still gets the real name
+            headers.insert(
+                "Content-Disposition".to_string(),
+                crate::utils::content_disposition(
+                    "inline",
+                    &full_path
+                        .file_name()
+                        .map(|name| name.to_string_lossy())
+                        .unwrap_or_default(),
+                ),
+            );
+        }
+        headers.insert("Accept-Ranges".to_string(), "bytes".to_string());
+        headers.insert(
+            "Cache-Control".to_string(),
+            "public, max-age=3600".to_string(),
+        );
+        headers.insert("ETag".to_string(), etag.clone());
+        if let Some(ref date) = last_modified {
+            headers.insert("Last-Modified".to_string(), date.clone());
+        }
+
+        if is_not_modified(request, &etag, modified) {
+            debug!("Client copy of {} is current", full_path.display());
+            return Ok(Response {
+                status_code: 304,
+                status_text: "Not Modified".to_string(),
+                headers,
+                body: ResponseBody::StaticText(""),
+            });
+        }
+
+        // Honor Range only when If-Range (if present) still matches the current file
+        let range = match request.headers.get("range") {
+            Some(value) if if_range_matches(request, &etag, last_modified.as_deref()) => {
+                parse_range_header(value, size)
+            }
+            _ => ByteRange::Full,
+        };
+
+        // Small hot files come from memory, others from the mmap cache when
+        // either is enabled
+        let cached = file_cache.and_then(|cache| cache.get(&full_path, &metadata));
+        let mapped = match cached {
+            Some(_) => None,
+            None => crate::mmap_cache::global().and_then(|cache| cache.get(&full_path, &metadata)),
+        };
+        let file_body = |offset: u64, length: u64| match (&cached, &mapped) {
+            (Some(data), _) => ResponseBody::Cached(crate::http::CachedBody {
+                data: data.clone(),
+                offset: offset as usize,
+                size: length as usize,
+            }),
+            (None, Some(file)) => ResponseBody::Mapped(crate::http::MappedBody {
+                file: file.clone(),
+                offset: offset as usize,
+                size: length as usize,
+            }),
+            (None, None) => ResponseBody::Stream(crate::http::StreamBody {
                 path: full_path.clone(),
-                size,
+                size: length,
                 chunk_size,
+                offset,
             }),
-        })
+        };
+
+        match range {
+            ByteRange::Full => {
+                headers.insert("Content-Length".to_string(), size.to_string());
+                Ok(Response {
+                    status_code: 200,
+                    status_text: "OK".to_string(),
+                    headers,
+                    body: file_body(0, size),
+                })
+            }
+            ByteRange::Partial { start, end } => {
+                let length = end - start + 1;
+                debug!("Serving byte range {}-{}/{}", start, end, size);
+                headers.insert("Content-Length".to_string(), length.to_string());
+                headers.insert(
+                    "Content-Range".to_string(),
+                    format!("bytes {start}-{end}/{size}"),
+                );
+                Ok(Response {
+                    status_code: 206,
+                    status_text: "Partial Content".to_string(),
+                    headers,
+                    body: file_body(start, length),
+                })
+            }
+            ByteRange::Unsatisfiable => {
+                debug!("Unsatisfiable range requested for {} byte file", size);
+                headers.insert("Content-Range".to_string(), format!("bytes */{size}"));
+                Ok(Response {
+                    status_code: 416,
+                    status_text: "Range Not Satisfiable".to_string(),
+                    headers,
+                    body: ResponseBody::StaticText(""),
+                })
+            }
+        }
     } else {
         Err(AppError::NotFound)
     }
 }
 
-/// A safe, manual path normalization function.
-fn normalize_path(path: &std::path::Path) -> Result<std::path::PathBuf, AppError> {
-    use std::path::Component;
-
-    let mut components = Vec::new();
-    for component in path.components() {
-        match component {
-            Component::Normal(name) => {
-                components.push(name);
-            }
-            Component::ParentDir => {
-                if components.pop().is_none() {
-                    return Err(AppError::Forbidden);
-                }
-            }
-            _ => {}
+/// Evaluate `If-Range`: a range is only honored if the validator still matches.
+/// Without an `If-Range` header the range always applies.
+fn if_range_matches(request: &Request, etag: &str, last_modified: Option<&str>) -> bool {
+    match request.headers.get("if-range").map(|v| v.trim()) {
+        None => true,
+        Some(validator) if validator.starts_with('"') || validator.starts_with("W/") => {
+            validator == etag
         }
+        Some(validator) => last_modified.is_some_and(|date| date == validator),
     }
-    Ok(components.iter().collect())
 }
 
-/// URL decode function for parsing query parameters
-fn url_decode(s: &str) -> String {
-    let mut result = String::with_capacity(s.len());
-    let mut chars = s.chars();
-    while let Some(ch) = chars.next() {
-        if ch == '%' {
-            let hex1 = chars.next();
-            let hex2 = chars.next();
-            match (hex1, hex2) {
-                (Some(h1), Some(h2)) => {
-                    if let (Some(d1), Some(d2)) = (h1.to_digit(16), h2.to_digit(16)) {
-                        let byte = ((d1 << 4) | d2) as u8;
-                        result.push(byte as char);
-                    } else {
-                        result.push(ch);
-                        result.push(h1);
-                        result.push(h2);
-                    }
-                }
-                (Some(h1), None) => {
-                    result.push(ch);
-                    result.push(h1);
-                }
-                (None, None) => {
-                    result.push(ch);
-                }
-                (None, Some(_)) => {
-                    result.push(ch);
-                }
-            }
-        } else if ch == '+' {
-            result.push(' ');
-        } else {
-            result.push(ch);
+/// The `--not-found-page` document with status 404, or a plain 404 without one
+fn not_found_page(
+    base_dir: &std::path::Path,
+    cli_config: Option<&crate::cli::Cli>,
+) -> Result<Response, AppError> {
+    let Some(page) = cli_config.and_then(|cli| cli.not_found_page.as_deref()) else {
+        return Err(AppError::NotFound);
+    };
+    let path = base_dir.join(normalize_path(std::path::Path::new(
+        page.trim_start_matches('/'),
+    ))?);
+    let body = std::fs::read(&path).map_err(|e| {
+        warn!("Cannot read not-found page {}: {e}", path.display());
+        AppError::NotFound
+    })?;
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        crate::response::get_file_content_type(&path, cli_config).content_type,
+    );
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Ok(Response {
+        status_code: 404,
+        status_text: "Not Found".to_string(),
+        headers,
+        body: ResponseBody::Binary(body),
+    })
+}
+
+/// Evaluate `If-None-Match` / `If-Modified-Since` for a cacheable GET or HEAD.
+/// Per RFC 7232, `If-Modified-Since` is ignored when `If-None-Match` is present.
+fn is_not_modified(request: &Request, etag: &str, modified: Option<SystemTime>) -> bool {
+    if request.method != "GET" && request.method != "HEAD" {
+        return false;
+    }
+    if let Some(candidates) = request.headers.get("if-none-match") {
+        // Weak comparison, as RFC 7232 requires for If-None-Match
+        let etag = etag.strip_prefix("W/").unwrap_or(etag);
+        return candidates.split(',').map(str::trim).any(|candidate| {
+            candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+        });
+    }
+    let since = request
+        .headers
+        .get("if-modified-since")
+        .and_then(|value| crate::utils::parse_http_date(value));
+    match (since, modified) {
+        (Some(since), Some(modified)) => {
+            // HTTP dates have one-second resolution
+            let secs = |t: SystemTime| {
+                t.duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default()
+            };
+            secs(modified) <= secs(since)
         }
+        _ => false,
     }
-    result
+}
+
+/// Weak `ETag` for generated content, derived from what it is generated from.
+/// Tags handed out by an earlier run of the server never match.
+fn weak_etag(inputs: impl std::hash::Hash) -> String {
+    use std::hash::{Hash, Hasher};
+    static STARTED: std::sync::LazyLock<SystemTime> = std::sync::LazyLock::new(SystemTime::now);
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (*STARTED, inputs).hash(&mut hasher);
+    format!("W/\"{:x}\"", hasher.finish())
 }
 
 /// Handle search API requests with optimizations
@@ -746,21 +1800,22 @@ pub fn handle_search_api_request(
     trace!("Search base directory: {}", base_dir.display());
 
     // Parse query parameters manually
-    let query_params: HashMap<String, String> =
-        if let Some(query_string) = request.path.split('?').nth(1) {
-            query_string
-                .split('&')
-                .filter_map(|param| {
-                    let mut parts = param.splitn(2, '=');
-                    match (parts.next(), parts.next()) {
-                        (Some(key), Some(value)) => Some((url_decode(key), url_decode(value))),
-                        _ => None,
+    let query_params: HashMap<String, String> = if let Some(query_string) = &request.query {
+        query_string
+            .split('&')
+            .filter_map(|param| {
+                let mut parts = param.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) => {
+                        Some((decode_query_component(key), decode_query_component(value)))
                     }
-                })
-                .collect()
-        } else {
-            HashMap::new()
-        };
+                    _ => None,
+                }
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
 
     let search_query = query_params.get("q").ok_or_else(|| {
         debug!("Search query parameter 'q' missing");
@@ -790,9 +1845,59 @@ pub fn handle_search_api_request(
         .and_then(|v| v.parse::<usize>().ok())
         .unwrap_or(0);
 
+    // `token` is the opaque next-page token from a previous response
+    let offset = match query_params.get("token") {
+        Some(token) => token.parse::<usize>().map_err(|_| {
+            debug!("Invalid search page token: '{}'", token);
+            AppError::BadRequest
+        })?,
+        None => offset,
+    };
+    let sort = match query_params.get("sort") {
+        Some(value) => SearchSort::parse(value).ok_or_else(|| {
+            debug!("Unknown search sort field: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => SearchSort::default(),
+    };
+    let order = match query_params.get("order") {
+        Some(value) => SortOrder::parse(value).ok_or_else(|| {
+            debug!("Unknown search sort order: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => sort.default_order(),
+    };
+    let scope = match query_params.get("scope") {
+        Some(value) => SearchScope::parse(value).ok_or_else(|| {
+            debug!("Unknown search scope: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => SearchScope::default(),
+    };
+    let mode = match query_params.get("mode") {
+        Some(value) => SearchMode::parse(value).ok_or_else(|| {
+            debug!("Unknown search mode: '{}'", value);
+            AppError::BadRequest
+        })?,
+        None => SearchMode::default(),
+    };
+    let filters = SearchFilters::from_query(&query_params)?;
+    let mount = match query_params.get("mount") {
+        Some(name) if !crate::search::is_valid_mount_name(name) => {
+            debug!("Invalid search mount: '{}'", name);
+            return Err(AppError::BadRequest);
+        }
+        name => name.cloned(),
+    };
+
     debug!(
-        "Search parameters - path: '{}', limit: {}, offset: {}",
-        search_path, limit, offset
+        "Search parameters - path: '{}', limit: {}, offset: {}, sort: {}, order: {}, scope: {}",
+        search_path,
+        limit,
+        offset,
+        sort.as_str(),
+        order.as_str(),
+        scope.as_str()
     );
     trace!("Search query validation passed");
 
@@ -802,11 +1907,48 @@ pub fn handle_search_api_request(
         limit,
         offset,
         case_sensitive: false,
+        sort,
+        order,
+        scope,
+        mode,
+        filters,
+        mount,
     };
 
+    // Results only change with the indexes they come from
+    let etag = weak_etag((
+        crate::search::index_generation(),
+        base_dir.as_path(),
+        &request.path,
+        &request.query,
+    ));
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers.insert("Access-Control-Allow-Origin".to_string(), "*".to_string());
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    headers.insert("ETag".to_string(), etag.clone());
+    if is_not_modified(request, &etag, None) {
+        debug!("Client copy of search '{}' is current", search_query);
+        return Ok(Response {
+            status_code: 304,
+            status_text: "Not Modified".to_string(),
+            headers,
+            body: ResponseBody::StaticText(""),
+        });
+    }
+
     debug!("Performing search with parameters: {:?}", params);
-    let results = perform_search(base_dir, &params)?;
-    debug!("Search returned {} results", results.len());
+    // Like archives, results are not seen by the auth middleware
+    let locked = crate::users::locked_paths(request);
+    let page = perform_search_page_filtered(base_dir, &params, |result| {
+        result.mount != crate::search::DEFAULT_MOUNT || !locked.hides(&result.path)
+    })?;
+    let results = &page.results;
+    debug!(
+        "Search returned {} of {} results",
+        results.len(),
+        page.total
+    );
 
     let elapsed_ms = start_time.elapsed().as_millis();
     debug!(
@@ -821,27 +1963,87 @@ pub fn handle_search_api_request(
         .iter()
         .map(|result| {
             format!(
-                r#"{{"name":"{}","path":"{}","size":"{}","type":"{}"}}"#,
-                result.name.replace('"', r#"\""#),
-                result.path.replace('"', r#"\""#),
+                r#"{{"name":"{}","path":"{}","url":"{}","mount":"{}","size":"{}","size_bytes":{},"modified":{},"type":"{}","score":{},"matches":[{}],"highlighted":"{}"}}"#,
+                json_escape(&result.name),
+                json_escape(&result.path),
+                json_escape(&crate::templates::prefixed(
+                    &crate::utils::percent_encode_path(std::path::Path::new(&result.path))
+                )),
+                json_escape(&result.mount),
                 result.size,
-                result.file_type
+                result.size_bytes,
+                result
+                    .last_modified
+                    .map_or("null".to_string(), |t| t.to_string()),
+                result.file_type,
+                result.score,
+                result
+                    .matched_ranges
+                    .iter()
+                    .map(|(start, end)| format!("[{start},{end}]"))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                json_escape(&result.highlighted_name())
             )
         })
         .collect();
 
-    let json_response = format!("[{}]", json_items.join(","));
+    let next_token = page
+        .next_offset
+        .map_or("null".to_string(), |next| format!(r#""{next}""#));
+    let json_response = format!(
+        r#"{{"query":"{}","results":[{}],"total":{},"total_capped":{},"offset":{},"limit":{},"sort":"{}","order":"{}","scope":"{}","mode":"{}","index_state":"{}","next_token":{}}}"#,
+        json_escape(search_query),
+        json_items.join(","),
+        page.total,
+        page.total_capped,
+        offset,
+        limit,
+        sort.as_str(),
+        order.as_str(),
+        scope.as_str(),
+        mode.as_str(),
+        page.index_state.as_str(),
+        next_token
+    );
+
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(json_response),
+    })
+}
 
+/// Progress of the search index build as JSON
+pub fn handle_index_status_request() -> Result<Response, AppError> {
+    let body = match crate::search::index_status() {
+        Some(status) => format!(
+            r#"{{"state":"{}","entries_scanned":{},"directories_scanned":{},"directories_remaining":{},"elapsed_ms":{},"eta_secs":{}}}"#,
+            status.state.as_str(),
+            status.entries_scanned,
+            status.directories_scanned,
+            status.directories_remaining,
+            status.elapsed.as_millis(),
+            status
+                .eta
+                .map_or("null".to_string(), |eta| eta.as_secs().to_string())
+        ),
+        None => r#"{"state":"unavailable"}"#.to_string(),
+    };
     Ok(Response {
         status_code: 200,
         status_text: "OK".to_string(),
         headers: {
             let mut map = HashMap::new();
-            map.insert("Content-Type".to_string(), "application/json".to_string());
-            map.insert("Access-Control-Allow-Origin".to_string(), "*".to_string());
+            map.insert(
+                "Content-Type".to_string(),
+                "application/json; charset=utf-8".to_string(),
+            );
+            map.insert("Cache-Control".to_string(), "no-cache".to_string());
             map
         },
-        body: ResponseBody::Text(json_response),
+        body: ResponseBody::Text(body),
     })
 }
 
@@ -903,7 +2105,7 @@ pub fn handle_memory_cleanup_request() -> Result<Response, AppError> {
     }
 }
 
-/// Handle explicit logout requests for Basic Auth
+/// Handle explicit logout requests, ending the session cookie in session mode
 pub fn handle_logout_request() -> Result<Response, AppError> {
     debug!("Handling logout request");
 
@@ -917,6 +2119,16 @@ pub fn handle_logout_request() -> Result<Response, AppError> {
         "Content-Type".to_string(),
         "text/html; charset=utf-8".to_string(),
     );
+    if crate::session::is_enabled() {
+        headers.insert("Set-Cookie".to_string(), crate::session::clear_cookie());
+        headers.insert("Cache-Control".to_string(), "no-store".to_string());
+        return Ok(Response {
+            status_code: 200,
+            status_text: "OK".to_string(),
+            headers,
+            body: ResponseBody::Text(html),
+        });
+    }
     headers.insert(
         "WWW-Authenticate".to_string(),
         r#"Basic realm="IronDrop""#.to_string(),
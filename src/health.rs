@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MIT
+
+//! Liveness and readiness probes
+//!
+//! `GET /_health/live` answers `200` as long as the server can handle a
+//! request at all. `GET /_health/ready` runs a few cheap dependency checks
+//! (the served directory can be listed, uploads can create files there when
+//! they are enabled, and the search index is initialized) and answers `503`
+//! when any of them fails, so orchestrators stop routing traffic to an
+//! instance whose volume went away. Both bodies are JSON.
+
+use crate::cli::Cli;
+use crate::http::{Response, ResponseBody};
+use crate::upload::TEMP_FILE_PREFIX;
+use crate::utils::json_escape;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Liveness probe route
+pub const LIVE_ROUTE: &str = "/_health/live";
+
+/// Readiness probe route
+pub const READY_ROUTE: &str = "/_health/ready";
+
+/// Outcome of a single readiness check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not applicable with the current configuration
+    Skip,
+}
+
+impl CheckStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Fail => "fail",
+            CheckStatus::Skip => "skip",
+        }
+    }
+}
+
+/// A named readiness check with a short human-readable explanation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+/// The served directory exists and can be listed
+pub fn check_directory(base_dir: &Path) -> HealthCheck {
+    match fs::read_dir(base_dir) {
+        Ok(_) => HealthCheck::new("directory", CheckStatus::Pass, "directory is readable"),
+        Err(e) => HealthCheck::new(
+            "directory",
+            CheckStatus::Fail,
+            format!("cannot read {}: {e}", base_dir.display()),
+        ),
+    }
+}
+
+/// Uploads can create files in the served directory (skipped when uploads are off)
+pub fn check_upload_directory(base_dir: &Path, upload_enabled: bool) -> HealthCheck {
+    const NAME: &str = "upload_directory";
+    if !upload_enabled {
+        return HealthCheck::new(NAME, CheckStatus::Skip, "uploads are disabled");
+    }
+    let probe = base_dir.join(format!("{}health_{}", TEMP_FILE_PREFIX, std::process::id()));
+    match fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            HealthCheck::new(NAME, CheckStatus::Pass, "directory is writable")
+        }
+        Err(e) => HealthCheck::new(
+            NAME,
+            CheckStatus::Fail,
+            format!("cannot write to {}: {e}", base_dir.display()),
+        ),
+    }
+}
+
+/// The search index has been set up
+pub fn check_search_index() -> HealthCheck {
+    match crate::search::get_search_index_stats() {
+        Some((entries, _, updating)) => HealthCheck::new(
+            "search_index",
+            CheckStatus::Pass,
+            format!(
+                "{entries} entries indexed{}",
+                if updating { ", rebuild running" } else { "" }
+            ),
+        ),
+        None => HealthCheck::new(
+            "search_index",
+            CheckStatus::Fail,
+            "search index is not initialized",
+        ),
+    }
+}
+
+/// Run every readiness check for the given configuration
+pub fn readiness_checks(base_dir: &Path, cli: Option<&Cli>) -> Vec<HealthCheck> {
+    let upload_enabled = cli.is_some_and(|c| c.enable_upload.unwrap_or(false));
+    vec![
+        check_directory(base_dir),
+        check_upload_directory(base_dir, upload_enabled),
+        check_search_index(),
+    ]
+}
+
+/// `GET /_health/live`
+pub fn liveness_response() -> Response {
+    json_response(
+        200,
+        "OK",
+        format!(
+            r#"{{"status":"alive","service":"irondrop","version":"{}"}}"#,
+            crate::VERSION
+        ),
+    )
+}
+
+/// `GET /_health/ready`: `200` when no check failed, `503` otherwise
+pub fn readiness_response(base_dir: &Path, cli: Option<&Cli>) -> Response {
+    let checks = readiness_checks(base_dir, cli);
+    let ready = checks.iter().all(|c| c.status != CheckStatus::Fail);
+    let entries: Vec<String> = checks
+        .iter()
+        .map(|c| {
+            format!(
+                r#"{{"name":"{}","status":"{}","message":"{}"}}"#,
+                c.name,
+                c.status.as_str(),
+                json_escape(&c.message)
+            )
+        })
+        .collect();
+    let body = format!(
+        r#"{{"status":"{}","checks":[{}]}}"#,
+        if ready { "ready" } else { "not_ready" },
+        entries.join(",")
+    );
+    if ready {
+        json_response(200, "OK", body)
+    } else {
+        json_response(503, "Service Unavailable", body)
+    }
+}
+
+fn json_response(status_code: u16, status_text: &str, body: String) -> Response {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Response {
+        status_code,
+        status_text: status_text.to_string(),
+        headers,
+        body: ResponseBody::Text(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_and_upload_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_directory(dir.path()).status, CheckStatus::Pass);
+        assert_eq!(
+            check_upload_directory(dir.path(), false).status,
+            CheckStatus::Skip
+        );
+        assert_eq!(
+            check_upload_directory(dir.path(), true).status,
+            CheckStatus::Pass
+        );
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let missing = dir.path().join("gone");
+        assert_eq!(check_directory(&missing).status, CheckStatus::Fail);
+        assert_eq!(
+            check_upload_directory(&missing, true).status,
+            CheckStatus::Fail
+        );
+    }
+}
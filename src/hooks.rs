@@ -0,0 +1,644 @@
+// SPDX-License-Identifier: MIT
+
+//! Webhook notifications for server events
+//!
+//! When webhook endpoints are configured (`[hooks] endpoints` or
+//! `--webhook-urls`), the server POSTs a small JSON document to each of them
+//! whenever one of these events happens:
+//!
+//! - `upload.completed` - a direct or resumable upload was stored
+//! - `file.deleted` - a file or directory was removed (file management or WebDAV)
+//! - `auth.failure` - a request presented wrong credentials or an unknown API token
+//! - `rate_limit.triggered` - a connection was refused by the rate limiter
+//!
+//! ```json
+//! {"event":"upload.completed","timestamp":1735689600,"data":{"name":"report.pdf","size":1024}}
+//! ```
+//!
+//! Events are handed to a bounded queue served by a single background thread,
+//! so request handling never waits for a webhook. When the queue is full new
+//! events are dropped and counted. Failed deliveries (connection errors or
+//! non-2xx responses) are retried with exponential backoff. Auth failures and
+//! rate-limit events are sent at most once per minute per client, so a flood
+//! of bad requests does not turn into a flood of webhooks.
+//!
+//! Only plain `http://` endpoints are supported; put a local relay in front of
+//! HTTPS receivers.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::Request;
+use crate::utils::json_escape;
+use log::{debug, info, trace, warn};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Delivery attempts after the first one fails, unless configured otherwise
+pub const DEFAULT_WEBHOOK_RETRIES: u32 = 3;
+
+/// Events that may wait for delivery, unless configured otherwise
+pub const DEFAULT_WEBHOOK_QUEUE_SIZE: usize = 256;
+
+/// Connect, write and read timeout for a single delivery attempt
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay before the first retry; doubled for every further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Minimum gap between throttled events for the same client
+const THROTTLE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Clients remembered for throttling before the table is reset
+const MAX_THROTTLE_ENTRIES: usize = 1024;
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+    UploadCompleted,
+    FileDeleted,
+    AuthFailure,
+    RateLimited,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 4] = [
+        HookEvent::UploadCompleted,
+        HookEvent::FileDeleted,
+        HookEvent::AuthFailure,
+        HookEvent::RateLimited,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::UploadCompleted => "upload.completed",
+            HookEvent::FileDeleted => "file.deleted",
+            HookEvent::AuthFailure => "auth.failure",
+            HookEvent::RateLimited => "rate_limit.triggered",
+        }
+    }
+
+    /// Parse an event name as written in the configuration
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        Self::ALL.into_iter().find(|event| event.as_str() == name)
+    }
+
+    /// Events where a misbehaving client could trigger one notification per request
+    fn is_throttled(&self) -> bool {
+        matches!(self, HookEvent::AuthFailure | HookEvent::RateLimited)
+    }
+}
+
+/// A value in an event's `data` object
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookValue {
+    Str(String),
+    Num(u64),
+    Bool(bool),
+}
+
+impl From<&str> for HookValue {
+    fn from(value: &str) -> Self {
+        HookValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for HookValue {
+    fn from(value: String) -> Self {
+        HookValue::Str(value)
+    }
+}
+
+impl From<u64> for HookValue {
+    fn from(value: u64) -> Self {
+        HookValue::Num(value)
+    }
+}
+
+impl From<bool> for HookValue {
+    fn from(value: bool) -> Self {
+        HookValue::Bool(value)
+    }
+}
+
+/// A webhook receiver reachable over plain HTTP
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookEndpoint {
+    pub url: String,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl HookEndpoint {
+    /// Parse `http://host[:port][/path]`
+    pub fn parse(url: &str) -> Result<Self, AppError> {
+        let url = url.trim();
+        let invalid = |reason: &str| {
+            AppError::InvalidConfiguration(format!("Invalid webhook URL '{url}': {reason}"))
+        };
+        let rest = if let Some(rest) = url.strip_prefix("http://") {
+            rest
+        } else if url.starts_with("https://") {
+            return Err(invalid("only http:// endpoints are supported"));
+        } else {
+            return Err(invalid("expected an http:// URL"));
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        if authority.is_empty() || authority.contains('@') {
+            return Err(invalid("missing or unsupported host"));
+        }
+
+        // Bracketed IPv6 literals keep their colons
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, after) = v6.split_once(']').ok_or_else(|| invalid("unclosed '['"))?;
+            let port = match after.strip_prefix(':') {
+                Some(port) => port.parse().map_err(|_| invalid("bad port"))?,
+                None if after.is_empty() => 80,
+                None => return Err(invalid("bad port")),
+            };
+            (host.to_string(), port)
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (
+                    host.to_string(),
+                    port.parse().map_err(|_| invalid("bad port"))?,
+                ),
+                None => (authority.to_string(), 80),
+            }
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+
+        Ok(Self {
+            url: url.to_string(),
+            host,
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == 80 {
+            host
+        } else {
+            format!("{host}:{}", self.port)
+        }
+    }
+
+    /// POST one payload and report whether the receiver answered with 2xx
+    fn deliver(&self, event: HookEvent, payload: &str) -> Result<(), String> {
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("cannot resolve {}: {e}", self.host))?
+            .next()
+            .ok_or_else(|| format!("no address for {}", self.host))?;
+        let mut stream =
+            TcpStream::connect_timeout(&addr, DELIVERY_TIMEOUT).map_err(|e| e.to_string())?;
+        stream
+            .set_read_timeout(Some(DELIVERY_TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(DELIVERY_TIMEOUT)))
+            .map_err(|e| e.to_string())?;
+
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: IronDrop/{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nX-IronDrop-Event: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host_header(),
+            crate::VERSION,
+            payload.len(),
+            event.as_str(),
+            payload
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        // The status line is all we need
+        let mut head = [0u8; 64];
+        let mut read = 0;
+        while read < head.len() {
+            match stream.read(&mut head[read..]) {
+                Ok(0) => break,
+                Ok(n) => {
+                    read += n;
+                    if head[..read].contains(&b'\n') {
+                        break;
+                    }
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        let status_line = String::from_utf8_lossy(&head[..read]);
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| "malformed response".to_string())?;
+        if (200..300).contains(&status) {
+            Ok(())
+        } else {
+            Err(format!("receiver answered {status}"))
+        }
+    }
+}
+
+/// Webhook configuration taken from the CLI/INI settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookSettings {
+    pub endpoints: Vec<HookEndpoint>,
+    /// Subscribed events; empty means every event
+    pub events: Vec<HookEvent>,
+    pub retries: u32,
+    pub queue_size: usize,
+}
+
+impl HookSettings {
+    /// Build settings from the CLI, or `None` when no endpoint is configured
+    pub fn from_cli(cli: &Cli) -> Result<Option<Self>, AppError> {
+        let endpoints = cli
+            .webhook_urls
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(HookEndpoint::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if endpoints.is_empty() {
+            return Ok(None);
+        }
+
+        let mut events = Vec::new();
+        for name in cli.webhook_events.as_deref().unwrap_or("").split(',') {
+            let name = name.trim();
+            if name.is_empty() || name == "*" || name == "all" {
+                continue;
+            }
+            let event = HookEvent::parse(name).ok_or_else(|| {
+                AppError::InvalidConfiguration(format!("Unknown webhook event '{name}'"))
+            })?;
+            if !events.contains(&event) {
+                events.push(event);
+            }
+        }
+
+        Ok(Some(Self {
+            endpoints,
+            events,
+            retries: cli.webhook_retries.unwrap_or(DEFAULT_WEBHOOK_RETRIES),
+            queue_size: cli
+                .webhook_queue_size
+                .unwrap_or(DEFAULT_WEBHOOK_QUEUE_SIZE)
+                .max(1),
+        }))
+    }
+
+    pub fn wants(&self, event: HookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+struct Notification {
+    event: HookEvent,
+    payload: String,
+}
+
+/// Queues events and delivers them from a background thread
+pub struct HookDispatcher {
+    settings: Arc<HookSettings>,
+    sender: SyncSender<Notification>,
+    dropped: AtomicU64,
+    throttle: Mutex<HashMap<(HookEvent, String), Instant>>,
+}
+
+impl HookDispatcher {
+    /// Start the delivery thread; it exits once the dispatcher is dropped
+    /// and the queue has drained.
+    pub fn start(settings: HookSettings) -> Self {
+        let settings = Arc::new(settings);
+        let (sender, receiver) = sync_channel(settings.queue_size);
+        let worker_settings = settings.clone();
+        std::thread::Builder::new()
+            .name("irondrop-webhooks".to_string())
+            .spawn(move || delivery_loop(&worker_settings, &receiver))
+            .expect("failed to spawn webhook thread");
+        Self {
+            settings,
+            sender,
+            dropped: AtomicU64::new(0),
+            throttle: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn settings(&self) -> &HookSettings {
+        &self.settings
+    }
+
+    /// Events discarded because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Queue an event without blocking. Returns whether it was queued.
+    pub fn emit(&self, event: HookEvent, data: &[(&str, HookValue)]) -> bool {
+        if !self.settings.wants(event) {
+            return false;
+        }
+        if event.is_throttled() && self.is_throttled(event, data) {
+            trace!("Webhook {} throttled", event.as_str());
+            return false;
+        }
+
+        let notification = Notification {
+            event,
+            payload: render_payload(event, unix_timestamp(), data),
+        };
+        match self.sender.try_send(notification) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                warn!(
+                    "Webhook queue full, dropping {} event ({dropped} dropped so far)",
+                    event.as_str()
+                );
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    fn is_throttled(&self, event: HookEvent, data: &[(&str, HookValue)]) -> bool {
+        let client = data
+            .iter()
+            .find(|(key, _)| *key == "client")
+            .map(|(_, value)| match value {
+                HookValue::Str(s) => s.clone(),
+                other => format!("{other:?}"),
+            })
+            .unwrap_or_default();
+        let Ok(mut seen) = self.throttle.lock() else {
+            return false;
+        };
+        let now = Instant::now();
+        if seen.len() >= MAX_THROTTLE_ENTRIES {
+            seen.retain(|_, at| now.duration_since(*at) < THROTTLE_WINDOW);
+            if seen.len() >= MAX_THROTTLE_ENTRIES {
+                seen.clear();
+            }
+        }
+        match seen.get(&(event, client.clone())) {
+            Some(at) if now.duration_since(*at) < THROTTLE_WINDOW => true,
+            _ => {
+                seen.insert((event, client), now);
+                false
+            }
+        }
+    }
+}
+
+fn delivery_loop(settings: &HookSettings, receiver: &Receiver<Notification>) {
+    while let Ok(notification) = receiver.recv() {
+        for endpoint in &settings.endpoints {
+            deliver_with_retries(endpoint, &notification, settings.retries);
+        }
+    }
+    debug!("Webhook delivery thread stopped");
+}
+
+fn deliver_with_retries(endpoint: &HookEndpoint, notification: &Notification, retries: u32) {
+    let mut delay = RETRY_BASE_DELAY;
+    for attempt in 0..=retries {
+        match endpoint.deliver(notification.event, &notification.payload) {
+            Ok(()) => {
+                trace!(
+                    "Delivered {} webhook to {}",
+                    notification.event.as_str(),
+                    endpoint.url
+                );
+                return;
+            }
+            Err(e) if attempt < retries => {
+                debug!(
+                    "Webhook {} to {} failed ({e}), retrying in {}ms",
+                    notification.event.as_str(),
+                    endpoint.url,
+                    delay.as_millis()
+                );
+                std::thread::sleep(delay);
+                delay = delay.saturating_mul(2);
+            }
+            Err(e) => warn!(
+                "Giving up on {} webhook to {} after {} attempts: {e}",
+                notification.event.as_str(),
+                endpoint.url,
+                attempt + 1
+            ),
+        }
+    }
+}
+
+/// Client address reported in event data
+pub fn client_of(request: &Request) -> String {
+    request
+        .peer_addr
+        .map_or_else(|| "unknown".to_string(), |addr| addr.ip().to_string())
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Render the JSON body sent for an event
+pub fn render_payload(event: HookEvent, timestamp: u64, data: &[(&str, HookValue)]) -> String {
+    let fields: Vec<String> = data
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                HookValue::Str(s) => format!("\"{}\"", json_escape(s)),
+                HookValue::Num(n) => n.to_string(),
+                HookValue::Bool(b) => b.to_string(),
+            };
+            format!("\"{}\":{value}", json_escape(key))
+        })
+        .collect();
+    format!(
+        r#"{{"event":"{}","timestamp":{timestamp},"data":{{{}}}}}"#,
+        event.as_str(),
+        fields.join(",")
+    )
+}
+
+fn dispatcher_slot() -> &'static RwLock<Option<Arc<HookDispatcher>>> {
+    static DISPATCHER: RwLock<Option<Arc<HookDispatcher>>> = RwLock::new(None);
+    &DISPATCHER
+}
+
+/// Install (or remove) the process-wide dispatcher. Called on startup and on
+/// every configuration reload; unchanged settings keep the running dispatcher.
+pub fn configure(settings: Option<HookSettings>) {
+    let Ok(mut slot) = dispatcher_slot().write() else {
+        return;
+    };
+    match settings {
+        Some(settings) => {
+            if slot.as_ref().is_some_and(|d| *d.settings() == settings) {
+                return;
+            }
+            info!(
+                "🔔 Webhooks enabled: {} endpoint(s), events: {}",
+                settings.endpoints.len(),
+                if settings.events.is_empty() {
+                    "all".to_string()
+                } else {
+                    settings
+                        .events
+                        .iter()
+                        .map(HookEvent::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            );
+            *slot = Some(Arc::new(HookDispatcher::start(settings)));
+        }
+        None => *slot = None,
+    }
+}
+
+/// Send an event to the configured webhooks, if any. Never blocks.
+pub fn emit(event: HookEvent, data: &[(&str, HookValue)]) {
+    let dispatcher = match dispatcher_slot().read() {
+        Ok(slot) => slot.clone(),
+        Err(_) => return,
+    };
+    if let Some(dispatcher) = dispatcher {
+        dispatcher.emit(event, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    fn settings(url: &str, events: Vec<HookEvent>, retries: u32) -> HookSettings {
+        HookSettings {
+            endpoints: vec![HookEndpoint::parse(url).unwrap()],
+            events,
+            retries,
+            queue_size: 4,
+        }
+    }
+
+    #[test]
+    fn test_endpoint_parsing() {
+        let endpoint = HookEndpoint::parse("http://example.com:9000/hooks/irondrop").unwrap();
+        assert_eq!(endpoint.host, "example.com");
+        assert_eq!(endpoint.port, 9000);
+        assert_eq!(endpoint.path, "/hooks/irondrop");
+        assert_eq!(endpoint.host_header(), "example.com:9000");
+
+        let endpoint = HookEndpoint::parse("http://[::1]").unwrap();
+        assert_eq!((endpoint.host.as_str(), endpoint.port), ("::1", 80));
+        assert_eq!(endpoint.path, "/");
+        assert_eq!(endpoint.host_header(), "[::1]");
+
+        assert!(HookEndpoint::parse("https://example.com/hook").is_err());
+        assert!(HookEndpoint::parse("ftp://example.com").is_err());
+        assert!(HookEndpoint::parse("http://:80/").is_err());
+        assert!(HookEndpoint::parse("http://host:notaport/").is_err());
+    }
+
+    #[test]
+    fn test_payload_and_event_names() {
+        let payload = render_payload(
+            HookEvent::UploadCompleted,
+            42,
+            &[
+                ("name", "a \"b\".txt".into()),
+                ("size", 10u64.into()),
+                ("renamed", false.into()),
+            ],
+        );
+        assert_eq!(
+            payload,
+            r#"{"event":"upload.completed","timestamp":42,"data":{"name":"a \"b\".txt","size":10,"renamed":false}}"#
+        );
+        for event in HookEvent::ALL {
+            assert_eq!(HookEvent::parse(event.as_str()), Some(event));
+        }
+        assert_eq!(HookEvent::parse("nope"), None);
+    }
+
+    #[test]
+    fn test_event_filter_and_throttling() {
+        let dispatcher = HookDispatcher::start(settings(
+            "http://127.0.0.1:9/",
+            vec![HookEvent::AuthFailure],
+            0,
+        ));
+        assert!(!dispatcher.emit(HookEvent::UploadCompleted, &[]));
+
+        let client = [("client", HookValue::from("10.0.0.1"))];
+        assert!(dispatcher.emit(HookEvent::AuthFailure, &client));
+        assert!(!dispatcher.emit(HookEvent::AuthFailure, &client));
+        assert!(dispatcher.emit(HookEvent::AuthFailure, &[("client", "10.0.0.2".into())]));
+    }
+
+    #[test]
+    fn test_delivery_retries_until_success() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (i, stream) in listener.incoming().take(2).enumerate() {
+                let mut stream = stream.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let mut request = Vec::new();
+                while !String::from_utf8_lossy(&request).contains("}}") {
+                    let n = stream.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let status = if i == 0 {
+                    "503 Service Unavailable"
+                } else {
+                    "204 No Content"
+                };
+                let _ = stream.write_all(format!("HTTP/1.1 {status}\r\n\r\n").as_bytes());
+                tx.send(String::from_utf8_lossy(&request).into_owned())
+                    .unwrap();
+            }
+        });
+
+        let dispatcher = HookDispatcher::start(settings(&url, Vec::new(), 2));
+        assert!(dispatcher.emit(HookEvent::FileDeleted, &[("path", "/a.txt".into())]));
+
+        let first = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let second = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(first.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(first.contains("X-IronDrop-Event: file.deleted\r\n"));
+        assert_eq!(first, second);
+        assert!(second.contains(r#""data":{"path":"/a.txt"}"#));
+    }
+}
@@ -3,34 +3,155 @@
 //! Handles HTTP request parsing, routing, and response generation.
 
 use crate::error::AppError;
-use crate::response::create_error_response;
+use crate::response::{create_error_response, create_error_response_for_path};
 use crate::router::Router;
-use log::{error, info, trace};
+use crate::zero_copy::ZeroCopy;
+use log::{debug, error, info, trace};
+use std::any::Any;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::net::TcpStream;
 
-/// Maximum size for request body (10GB) to prevent memory exhaustion attacks
+/// Maximum size for upload bodies (10GB) to prevent disk exhaustion attacks
 const MAX_REQUEST_BODY_SIZE: usize = 10 * 1024 * 1024 * 1024;
 
-/// Maximum size for request headers (8KB) to prevent header buffer overflow
-const MAX_HEADERS_SIZE: usize = 8 * 1024;
+/// Default `--max-request-body`: largest body of a request that is not an
+/// upload, enough for edited text files and WebDAV property updates
+pub const DEFAULT_MAX_REQUEST_BODY: u64 = 2 * 1024 * 1024;
+
+/// Maximum size of the request line and headers together (16KB); larger
+/// header blocks are refused with 431
+const MAX_HEADERS_SIZE: usize = 16 * 1024;
+
+/// Maximum length of the request target (8KB); longer ones get 414
+const MAX_URI_LENGTH: usize = 8 * 1024;
+
+/// Maximum size of a single header line (8KB); longer ones get 431
+const MAX_HEADER_LINE_SIZE: usize = 8 * 1024;
+
+/// Maximum number of request headers; more get 431
+const MAX_HEADER_COUNT: usize = 100;
 
 /// Threshold for streaming request bodies to disk (2MB)
 /// This ensures total memory usage stays well below 128MB
 pub const STREAM_TO_DISK_THRESHOLD: usize = 2 * 1024 * 1024;
+
+/// Largest read of a request body being streamed to disk
+const DISK_BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default seconds to wait for each read from the client
+pub const DEFAULT_READ_TIMEOUT_SECS: u64 = 30;
+/// Default seconds a single write to the client may take
+pub const DEFAULT_WRITE_TIMEOUT_SECS: u64 = 60;
+/// Default seconds allowed for the client to send the complete request headers
+pub const DEFAULT_HEADER_TIMEOUT_SECS: u64 = 10;
 static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 /// Represents a parsed incoming HTTP request.
 #[derive(Debug)]
 pub struct Request {
     pub method: String,
+    /// Percent-decoded path, without the query string
     pub path: String,
+    /// Query string as sent, without the `?`; see [`Request::query_params`]
+    pub query: Option<String>,
     pub headers: HashMap<String, String>,
     pub body: Option<RequestBody>,
+    /// Remote address of the client, filled in by the connection handler.
+    /// Behind a trusted proxy this is the forwarded client address.
+    pub peer_addr: Option<std::net::SocketAddr>,
+    /// The trusted proxy the request came through, if any
+    pub forwarded_by: Option<std::net::SocketAddr>,
+}
+
+/// Per-connection I/O deadlines
+///
+/// `read` and `write` bound each individual socket operation, so a slow but
+/// steady transfer is never cut off. `header` bounds the whole header read,
+/// which stops slow-loris clients that trickle one header byte at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionTimeouts {
+    pub read: Duration,
+    pub write: Duration,
+    pub header: Duration,
+}
+
+impl Default for ConnectionTimeouts {
+    fn default() -> Self {
+        Self {
+            read: Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS),
+            write: Duration::from_secs(DEFAULT_WRITE_TIMEOUT_SECS),
+            header: Duration::from_secs(DEFAULT_HEADER_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl ConnectionTimeouts {
+    pub fn from_cli(cli: Option<&crate::cli::Cli>) -> Self {
+        let defaults = Self::default();
+        match cli {
+            Some(cli) => Self {
+                read: cli.read_timeout.map_or(defaults.read, Duration::from_secs),
+                write: cli
+                    .write_timeout
+                    .map_or(defaults.write, Duration::from_secs),
+                header: cli
+                    .header_timeout
+                    .map_or(defaults.header, Duration::from_secs),
+            },
+            None => defaults,
+        }
+    }
+}
+
+/// Body size limits checked while a request is read
+///
+/// Uploads (see [`crate::server::RouteClass::Upload`]) may send up to
+/// `upload` bytes; their handlers apply `--max-upload-size` on top. Every
+/// other request is refused with `413` once its body exceeds `request`, from
+/// `Content-Length` before anything is read, or while a chunked body arrives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimits {
+    pub upload: u64,
+    pub request: u64,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            upload: MAX_REQUEST_BODY_SIZE as u64,
+            request: DEFAULT_MAX_REQUEST_BODY,
+        }
+    }
+}
+
+impl BodyLimits {
+    pub fn from_cli(cli: Option<&crate::cli::Cli>) -> Self {
+        let defaults = Self::default();
+        Self {
+            request: cli
+                .and_then(|cli| cli.max_request_body)
+                .unwrap_or(defaults.request),
+            ..defaults
+        }
+    }
+
+    /// Limit for a request to `path` as sent, including any `--base-path`
+    pub fn for_request(&self, method: &str, path: &str) -> u64 {
+        let base_path = crate::templates::configured_base_path();
+        let routed = match path.strip_prefix(base_path) {
+            Some(rest) if !base_path.is_empty() => rest,
+            _ => path,
+        };
+        match crate::server::RouteClass::for_request(method, routed) {
+            Some(crate::server::RouteClass::Upload) => self.upload,
+            _ => self.request,
+        }
+    }
 }
 
 /// Request body can be either in memory or streamed to disk for large uploads
@@ -69,8 +190,37 @@ pub struct Response {
 
 pub struct StreamBody {
     pub path: PathBuf,
+    /// Number of bytes to send, starting at `offset`
     pub size: u64,
     pub chunk_size: usize,
+    /// Byte offset into the file where streaming starts (non-zero for range requests)
+    pub offset: u64,
+}
+
+/// Part of a file served from the mmap cache
+pub struct MappedBody {
+    pub file: Arc<crate::mmap_cache::MappedFile>,
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl MappedBody {
+    pub fn bytes(&self) -> &[u8] {
+        &self.file.as_slice()[self.offset..self.offset + self.size]
+    }
+}
+
+/// Part of a file served from the in-memory file cache
+pub struct CachedBody {
+    pub data: Arc<[u8]>,
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl CachedBody {
+    pub fn bytes(&self) -> &[u8] {
+        &self.data[self.offset..self.offset + self.size]
+    }
 }
 
 pub enum ResponseBody {
@@ -79,6 +229,8 @@ pub enum ResponseBody {
     Binary(Vec<u8>),
     StaticBinary(&'static [u8]),
     Stream(StreamBody),
+    Mapped(MappedBody),
+    Cached(CachedBody),
     AsyncStream(tokio::sync::mpsc::Receiver<Vec<u8>>),
 }
 
@@ -136,6 +288,24 @@ impl PendingBuffer {
 }
 
 impl Request {
+    /// Decoded parameters of the query string
+    pub fn query_params(&self) -> HashMap<String, String> {
+        self.query
+            .as_deref()
+            .map(crate::utils::parse_query_string)
+            .unwrap_or_default()
+    }
+
+    /// The request target with the path encoded again, for sending a client
+    /// back to this request
+    pub fn target(&self) -> String {
+        let path = crate::utils::percent_encode_path(std::path::Path::new(&self.path));
+        match &self.query {
+            Some(query) => format!("{path}?{query}"),
+            None => path,
+        }
+    }
+
     /// Validates if the given method is a valid HTTP method
     fn is_valid_http_method(method: &str) -> bool {
         matches!(
@@ -163,7 +333,31 @@ impl Request {
     where
         S: tokio::io::AsyncRead + Unpin,
     {
-        let (headers_data, remaining_bytes) = read_headers_with_remaining_async(stream).await?;
+        Self::from_async_stream_with_timeouts(
+            stream,
+            &ConnectionTimeouts::default(),
+            &BodyLimits::default(),
+        )
+        .await
+    }
+
+    /// Parse a request, failing with [`AppError::RequestTimeout`] when the
+    /// client is too slow to send its headers or body and with
+    /// [`AppError::PayloadTooLarge`] when the body exceeds `limits`
+    pub async fn from_async_stream_with_timeouts<S>(
+        stream: &mut S,
+        timeouts: &ConnectionTimeouts,
+        limits: &BodyLimits,
+    ) -> Result<Self, AppError>
+    where
+        S: tokio::io::AsyncRead + Unpin,
+    {
+        let (headers_data, remaining_bytes) = tokio::time::timeout(
+            timeouts.header,
+            read_headers_with_remaining_async(stream, timeouts.read),
+        )
+        .await
+        .map_err(|_| AppError::RequestTimeout)??;
         let mut lines = headers_data.lines();
 
         let request_line = lines.next().ok_or(AppError::BadRequest)?;
@@ -182,39 +376,119 @@ impl Request {
         if raw_path.contains('\0') || raw_path.is_empty() {
             return Err(AppError::BadRequest);
         }
+        if raw_path.len() > MAX_URI_LENGTH {
+            return Err(AppError::UriTooLong);
+        }
 
-        let path = Self::decode_url(raw_path)?;
+        let (path, query) = crate::utils::decode_request_target(raw_path)?;
         if !version.starts_with("HTTP/1.") {
             return Err(AppError::BadRequest);
         }
 
-        let mut headers = HashMap::new();
-        for line in lines {
-            let line = line.trim();
-            if line.is_empty() {
-                break;
+        let headers = Self::parse_headers(lines)?;
+
+        // Refuse a body that is too large or cannot be stored before reading it
+        let max_body = limits.for_request(&method, &path);
+        if let Some(length) = headers
+            .get("content-length")
+            .and_then(|length| length.parse::<u64>().ok())
+        {
+            if length > max_body {
+                debug!("Refusing {length} byte body for {method} {path} (limit {max_body})");
+                return Err(AppError::PayloadTooLarge(max_body));
             }
-            if let Some((key, value)) = line.split_once(':') {
-                let key = key.trim().to_lowercase();
-                let value = value.trim().to_string();
-                if let Some(existing) = headers.get(&key) {
-                    headers.insert(key, format!("{existing}, {value}"));
-                } else {
-                    headers.insert(key, value);
-                }
+            if matches!(method.as_str(), "POST" | "PUT") {
+                crate::disk_space::check_declared_length(length)?;
             }
         }
-
-        let body = read_request_body_async(stream, &headers, remaining_bytes).await?;
+        let max_body = usize::try_from(max_body).unwrap_or(usize::MAX);
+
+        let progress_id = matches!(method.as_str(), "POST" | "PUT")
+            .then(|| crate::upload_progress::upload_id(&headers))
+            .flatten();
+        let body = match progress_id {
+            Some(id) => {
+                read_tracked_body_async(
+                    stream,
+                    timeouts.read,
+                    &headers,
+                    remaining_bytes,
+                    id,
+                    max_body,
+                )
+                .await?
+            }
+            None => {
+                read_request_body_async(stream, timeouts.read, &headers, remaining_bytes, max_body)
+                    .await?
+            }
+        };
 
         Ok(Request {
             method,
             path,
+            query,
             headers,
             body,
+            peer_addr: None,
+            forwarded_by: None,
         })
     }
 
+    /// Parse header lines into lowercase names, joining repeated headers
+    ///
+    /// Lines without a valid `name: value` shape, obsolete line folding and
+    /// malformed `Content-Length` values are rejected with 400 so that a
+    /// proxy in front of the server cannot read the request differently.
+    fn parse_headers<'a>(
+        lines: impl Iterator<Item = &'a str>,
+    ) -> Result<HashMap<String, String>, AppError> {
+        let mut headers: HashMap<String, String> = HashMap::new();
+        for (count, line) in lines.enumerate() {
+            if line.is_empty() {
+                break;
+            }
+            if count >= MAX_HEADER_COUNT || line.len() > MAX_HEADER_LINE_SIZE {
+                return Err(AppError::HeaderFieldsTooLarge);
+            }
+            if line.starts_with([' ', '\t']) {
+                return Err(AppError::BadRequest);
+            }
+            let (key, value) = line.split_once(':').ok_or(AppError::BadRequest)?;
+            if !Self::is_valid_header_name(key) {
+                return Err(AppError::BadRequest);
+            }
+            let key = key.to_ascii_lowercase();
+            let value = value.trim();
+            if value.chars().any(|c| c.is_control() && c != '\t') {
+                return Err(AppError::BadRequest);
+            }
+            if key == "content-length"
+                && (value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()))
+            {
+                return Err(AppError::BadRequest);
+            }
+            match headers.get_mut(&key) {
+                Some(existing) => {
+                    existing.push_str(", ");
+                    existing.push_str(value);
+                }
+                None => {
+                    headers.insert(key, value.to_string());
+                }
+            }
+        }
+        Ok(headers)
+    }
+
+    /// RFC 9110 token characters
+    fn is_valid_header_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+    }
+
     fn has_chunked_transfer_encoding(headers: &HashMap<String, String>) -> bool {
         headers
             .get("transfer-encoding")
@@ -226,35 +500,6 @@ impl Request {
             })
             .unwrap_or(false)
     }
-
-    /// Simple URL decoding for percent-encoded paths
-    fn decode_url(path: &str) -> Result<String, AppError> {
-        let mut decoded = String::with_capacity(path.len());
-        let mut chars = path.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '%' {
-                // Try to decode percent-encoded character
-                let hex1 = chars.next().ok_or(AppError::BadRequest)?;
-                let hex2 = chars.next().ok_or(AppError::BadRequest)?;
-
-                if let (Some(d1), Some(d2)) = (hex1.to_digit(16), hex2.to_digit(16)) {
-                    let byte_val = ((d1 << 4) | d2) as u8;
-                    if let Some(decoded_char) = char::from_u32(byte_val as u32) {
-                        decoded.push(decoded_char);
-                        continue;
-                    }
-                }
-                decoded.push(ch);
-                decoded.push(hex1);
-                decoded.push(hex2);
-            } else {
-                decoded.push(ch);
-            }
-        }
-
-        Ok(decoded)
-    }
 }
 
 // Static asset, favicon, upload, and health handlers moved to handlers.rs
@@ -271,22 +516,76 @@ pub async fn handle_client_async<S>(
     cli_config: Option<Arc<crate::cli::Cli>>,
     stats: Option<Arc<crate::server::ServerStats>>,
     router: Arc<Router>,
+    bandwidth: Option<Arc<crate::bandwidth::BandwidthLimiter>>,
+    trusted_proxies: Option<Arc<crate::server::TrustedProxies>>,
 ) where
-    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send,
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
 {
-    let log_prefix = format!("[{}]", peer_addr);
-
-    let mut request = match Request::from_async_stream(&mut stream).await {
+    let mut log_prefix = format!("[{}]", peer_addr);
+    let timeouts = ConnectionTimeouts::from_cli(cli_config.as_deref());
+    let body_limits = BodyLimits::from_cli(cli_config.as_deref());
+    let security_headers = crate::middleware::SecurityHeaders::from_cli(cli_config.as_deref());
+    let mut extra_headers = HashMap::new();
+    if let Some(security_headers) = &security_headers {
+        security_headers.apply(&mut extra_headers);
+    }
+    let mut throttle = bandwidth.as_ref().and_then(|limiter| limiter.connection());
+
+    let mut request = match Request::from_async_stream_with_timeouts(
+        &mut stream,
+        &timeouts,
+        &body_limits,
+    )
+    .await
+    {
         Ok(req) => req,
         Err(e) => {
-            send_error_response_async(&mut stream, e, &log_prefix).await;
+            let timed_out = matches!(e, AppError::RequestTimeout);
+            if timed_out {
+                debug!("{log_prefix} closing connection after request timeout");
+            }
+            send_error_response_async(
+                &mut stream,
+                e,
+                "",
+                None,
+                &log_prefix,
+                timeouts.write,
+                HashMap::new(),
+                false,
+            )
+            .await;
             if let Some(stats) = stats {
+                if timed_out {
+                    stats.record_timeout();
+                }
                 stats.record_request(false, 0);
             }
             return;
         }
     };
 
+    request.peer_addr = Some(peer_addr);
+    if let Some(client) = trusted_proxies
+        .as_deref()
+        .and_then(|proxies| proxies.client_ip(peer_addr.ip(), &request.headers))
+    {
+        request.forwarded_by = Some(peer_addr);
+        if client != peer_addr.ip() {
+            trace!("{log_prefix} forwarded for {client}");
+            request.peer_addr = Some(std::net::SocketAddr::new(client, peer_addr.port()));
+            log_prefix = format!("[{client} via {peer_addr}]");
+        }
+    }
+    // Only a trusted proxy may say where it mounted us
+    let url_prefix = request
+        .forwarded_by
+        .and(request.headers.get("x-forwarded-prefix"))
+        .and_then(|value| crate::templates::parse_forwarded_prefix(value));
+    if let Some(prefix) = &url_prefix {
+        trace!("{log_prefix} generating URLs under forwarded prefix '{prefix}'");
+    }
+
     let cleanup_path = match &request.body {
         Some(RequestBody::File { path, .. }) => Some(path.clone()),
         _ => None,
@@ -294,17 +593,17 @@ pub async fn handle_client_async<S>(
 
     let request_method = request.method.clone();
     let request_path = request.path.clone();
+    let request_query = request.query.clone();
+    let accept_encoding = request.headers.get("accept-encoding").cloned();
+    let compression = crate::compression::CompressionSettings::from_cli(cli_config.as_deref());
+    let cors = crate::middleware::CorsPolicy::from_cli(cli_config.as_deref());
+    let origin = request.headers.get("origin").cloned();
+    let mut cors_covered = false;
 
     let response_result = {
-        let bp = crate::templates::base_path();
+        let bp = crate::templates::configured_base_path();
         let base_path_check: Result<(), AppError> = if !bp.is_empty() {
-            let path_only = request
-                .path
-                .split('?')
-                .next()
-                .unwrap_or(&request.path)
-                .to_string();
-            if path_only == bp || path_only.starts_with(&format!("{bp}/")) {
+            if request.path == bp || request.path.starts_with(&format!("{bp}/")) {
                 let stripped = request.path[bp.len()..].to_string();
                 request.path = if stripped.is_empty() || !stripped.starts_with('/') {
                     format!("/{stripped}")
@@ -321,13 +620,26 @@ pub async fn handle_client_async<S>(
         if let Err(e) = base_path_check {
             Err(e)
         } else {
-            let internal =
-                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| router.route(&request)))
-                    .unwrap_or_else(|_| {
-                        Some(Err(AppError::InternalServerError(
-                            "Client handler panicked".into(),
-                        )))
-                    });
+            cors_covered = crate::middleware::CorsPolicy::covers(&request.path);
+            // Preflights are answered before the router so auth never sees them
+            let preflight = cors
+                .as_ref()
+                .filter(|_| cors_covered)
+                .and_then(|cors| cors.preflight(&request));
+            let internal = if preflight.is_some() {
+                preflight.map(Ok)
+            } else {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    crate::templates::with_url_prefix(url_prefix.as_deref(), || {
+                        router.route(&request)
+                    })
+                }))
+                .unwrap_or_else(|_| {
+                    Some(Err(AppError::InternalServerError(
+                        "Client handler panicked".into(),
+                    )))
+                })
+            };
             if let Some(res) = internal {
                 res
             } else if request.path.starts_with("/_irondrop/") {
@@ -337,34 +649,76 @@ pub async fn handle_client_async<S>(
                 let base_dir = base_dir.clone();
                 let allowed_extensions = allowed_extensions.clone();
                 let cli_config = cli_config.clone();
-                let response_result = tokio::task::spawn_blocking(move || {
+                let url_prefix = url_prefix.clone();
+                let file_cache = stats.as_ref().map(|stats| stats.file_cache().clone());
+                let job = move || {
                     std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                        crate::handlers::handle_file_request(
-                            &request,
-                            &base_dir,
-                            &allowed_extensions,
-                            chunk_size,
-                            cli_config.as_deref(),
-                        )
+                        crate::templates::with_url_prefix(url_prefix.as_deref(), || {
+                            crate::handlers::handle_file_request(
+                                &request,
+                                &base_dir,
+                                &allowed_extensions,
+                                chunk_size,
+                                cli_config.as_deref(),
+                                file_cache.as_deref(),
+                            )
+                        })
                     }))
                     .unwrap_or_else(|_| {
                         Err(AppError::InternalServerError(
                             "Client handler panicked".into(),
                         ))
                     })
-                })
-                .await;
+                };
+                let response_result = match stats.as_ref() {
+                    Some(stats) => stats.workers().run(job).await,
+                    None => Some(tokio::task::spawn_blocking(job).await),
+                };
 
                 match response_result {
-                    Ok(result) => result,
-                    Err(_) => Err(AppError::InternalServerError("Join error".into())),
+                    Some(Ok(result)) => result,
+                    Some(Err(_)) => Err(AppError::InternalServerError("Join error".into())),
+                    None => Ok(service_busy_response()),
                 }
             }
         }
     };
 
+    let cors = cors.filter(|_| cors_covered);
     match response_result {
         Ok(response) => {
+            // Large downloads need a slot, held until the body has been sent
+            let mut _download_slot = None;
+            let mut slots_busy = false;
+            let head_only = request_method == "HEAD";
+            let file_size = match &response.body {
+                ResponseBody::Stream(body) => body.size,
+                ResponseBody::Mapped(body) => body.size as u64,
+                ResponseBody::Cached(body) => body.size as u64,
+                _ => 0,
+            };
+            let response = match stats.as_ref() {
+                Some(stats)
+                    if !head_only && file_size >= crate::download_slots::LARGE_DOWNLOAD_BYTES =>
+                {
+                    match stats
+                        .download_slots()
+                        .acquire(crate::download_slots::QUEUE_WAIT)
+                        .await
+                    {
+                        Some(slot) => {
+                            _download_slot = Some(slot);
+                            response
+                        }
+                        None => {
+                            slots_busy = true;
+                            service_busy_response()
+                        }
+                    }
+                }
+                _ => response,
+            };
+
             // Check if this is a Finder noise 404 before logging and stats
             let is_finder_noise = response.status_code == 404
                 && crate::utils::is_macos_finder_noise_path(&request_path);
@@ -373,15 +727,43 @@ pub async fn handle_client_async<S>(
                 &log_prefix,
                 &request_method,
                 &request_path,
+                request_query.as_deref(),
                 response.status_code,
             );
-            match send_response_async(&mut stream, response, &log_prefix).await {
-                Ok(body_bytes) =>
-                {
+            let mut response = response;
+            if let Some(cors) = &cors {
+                cors.apply(origin.as_deref(), &mut response.headers);
+            }
+            if let Some(security_headers) = &security_headers {
+                security_headers.apply(&mut response.headers);
+            }
+            let response = crate::compression::maybe_compress(
+                response,
+                accept_encoding.as_deref(),
+                compression,
+            );
+            let status_code = response.status_code;
+            match send_response_async(
+                &mut stream,
+                response,
+                &log_prefix,
+                timeouts.write,
+                throttle.as_mut(),
+                head_only,
+            )
+            .await
+            {
+                Ok(body_bytes) => {
+                    router.response_sent(
+                        &request_method,
+                        &request_path,
+                        request_query.as_deref(),
+                        status_code,
+                    );
                     #[allow(clippy::collapsible_if)]
                     if !is_finder_noise {
                         if let Some(stats) = stats {
-                            stats.record_request(true, body_bytes);
+                            stats.record_request(!slots_busy, body_bytes);
                         }
                     }
                 }
@@ -396,7 +778,20 @@ pub async fn handle_client_async<S>(
             let is_finder_noise = matches!(e, AppError::NotFound)
                 && request_method == "PROPFIND"
                 && crate::utils::is_macos_finder_noise_path(&request_path);
-            send_error_response_async(&mut stream, e, &log_prefix).await;
+            if let Some(cors) = &cors {
+                cors.apply(origin.as_deref(), &mut extra_headers);
+            }
+            send_error_response_async(
+                &mut stream,
+                e,
+                &request_path,
+                url_prefix.as_deref(),
+                &log_prefix,
+                timeouts.write,
+                extra_headers,
+                request_method == "HEAD",
+            )
+            .await;
             #[allow(clippy::collapsible_if)]
             if !is_finder_noise {
                 if let Some(stats) = stats {
@@ -411,15 +806,24 @@ pub async fn handle_client_async<S>(
     }
 }
 
-fn log_request_line(log_prefix: &str, method: &str, path: &str, status_code: u16) {
-    let path_only = path.split('?').next().unwrap_or(path);
-    if is_monitor_path(path_only) {
+fn log_request_line(
+    log_prefix: &str,
+    method: &str,
+    path: &str,
+    query: Option<&str>,
+    status_code: u16,
+) {
+    if is_monitor_path(path) {
         monitor_request_log_rate_limited(method, status_code);
         return;
     }
+    let path = match query {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
     // Suppress macOS Finder noise (._files, .Spotlight, .AU.*, etc.) to TRACE
     // level to keep logs clean. These are harmless 404s from Finder probing.
-    if status_code == 404 && crate::utils::is_macos_finder_noise_path(path_only) {
+    if status_code == 404 && crate::utils::is_macos_finder_noise_path(&path) {
         trace!("{log_prefix} {method} {path} -> {status_code} (finder noise)");
         return;
     }
@@ -427,7 +831,7 @@ fn log_request_line(log_prefix: &str, method: &str, path: &str, status_code: u16
 }
 
 fn is_monitor_path(path: &str) -> bool {
-    let bp = crate::templates::base_path();
+    let bp = crate::templates::configured_base_path();
     if bp.is_empty() {
         path == "/monitor" || path.starts_with("/_irondrop/monitor")
     } else {
@@ -458,31 +862,91 @@ fn monitor_request_log_rate_limited(method: &str, status_code: u16) {
     }
 }
 
-async fn send_error_response_async<S>(stream: &mut S, error: AppError, log_prefix: &str)
-where
-    S: tokio::io::AsyncWrite + Unpin,
+#[allow(clippy::too_many_arguments)]
+async fn send_error_response_async<S>(
+    stream: &mut S,
+    error: AppError,
+    request_path: &str,
+    url_prefix: Option<&str>,
+    log_prefix: &str,
+    write_timeout: Duration,
+    extra_headers: HashMap<String, String>,
+    head_only: bool,
+) where
+    S: tokio::io::AsyncWrite + Unpin + 'static,
 {
+    let realm = match &error {
+        AppError::UnauthorizedRealm(realm) => Some(realm.clone()),
+        _ => None,
+    };
+    let allow = match &error {
+        AppError::MethodNotAllowedFor(allow) => Some(allow.clone()),
+        _ => None,
+    };
+    let retry_after = match &error {
+        AppError::TooManyRequests(secs) => Some(*secs),
+        _ => None,
+    };
+    if let AppError::LoginRequired(next) = &error {
+        info!("{log_prefix} 303 See Other (login)");
+        let mut headers = extra_headers;
+        let location =
+            crate::templates::with_url_prefix(url_prefix, || crate::session::login_location(next));
+        headers.insert("Location".to_string(), location);
+        headers.insert("Cache-Control".to_string(), "no-store".to_string());
+        let response = Response {
+            status_code: 303,
+            status_text: "See Other".to_string(),
+            headers,
+            body: ResponseBody::Text(String::new()),
+        };
+        let _ =
+            send_response_async(stream, response, log_prefix, write_timeout, None, head_only).await;
+        return;
+    }
     let (status_code, status_text) = match error {
         AppError::NotFound => (404, "Not Found"),
         AppError::Forbidden => (403, "Forbidden"),
         AppError::BadRequest => (400, "Bad Request"),
-        AppError::Unauthorized => (401, "Unauthorized"),
-        AppError::MethodNotAllowed => (405, "Method Not Allowed"),
+        AppError::Unauthorized | AppError::UnauthorizedRealm(_) => (401, "Unauthorized"),
+        AppError::MethodNotAllowed | AppError::MethodNotAllowedFor(_) => {
+            (405, "Method Not Allowed")
+        }
         AppError::PayloadTooLarge(_) => (413, "Payload Too Large"),
         AppError::InvalidFilename(_) => (400, "Bad Request"),
         AppError::UploadDiskFull(_) => (507, "Insufficient Storage"),
         AppError::UnsupportedMediaType(_) => (415, "Unsupported Media Type"),
         AppError::UploadDisabled => (403, "Forbidden"),
+        AppError::UploadRejected(_) => (422, "Unprocessable Entity"),
+        AppError::UploadConflict(_) => (409, "Conflict"),
+        AppError::RequestTimeout => (408, "Request Timeout"),
+        AppError::UriTooLong => (414, "URI Too Long"),
+        AppError::HeaderFieldsTooLarge => (431, "Request Header Fields Too Large"),
+        AppError::TooManyRequests(_) => (429, "Too Many Requests"),
         _ => (500, "Internal Server Error"),
     };
 
     info!("{log_prefix} {status_code} {status_text}");
 
-    let http_response = create_error_response(status_code, status_text);
-    let mut headers = HashMap::new();
+    let http_response = crate::templates::with_url_prefix(url_prefix, || {
+        create_error_response_for_path(status_code, status_text, request_path)
+    });
+    let mut headers = extra_headers;
     for (k, v) in http_response.headers {
         headers.insert(k, v);
     }
+    if let Some(realm) = realm {
+        headers.insert(
+            "WWW-Authenticate".to_string(),
+            format!("Basic realm=\"{}\"", realm.replace('"', "'")),
+        );
+    }
+    if let Some(allow) = allow {
+        headers.insert("Allow".to_string(), allow);
+    }
+    if let Some(secs) = retry_after {
+        headers.insert("Retry-After".to_string(), secs.to_string());
+    }
 
     let response = Response {
         status_code: http_response.status_code,
@@ -491,16 +955,35 @@ where
         body: ResponseBody::Binary(http_response.body),
     };
 
-    let _ = send_response_async(stream, response, log_prefix).await;
+    let _ = send_response_async(stream, response, log_prefix, write_timeout, None, head_only).await;
+}
+
+/// 503 sent when every download slot or worker is taken and its queue is full
+fn service_busy_response() -> Response {
+    let http_response = create_error_response(503, "Service Unavailable");
+    let mut headers: HashMap<String, String> = http_response.headers.into_iter().collect();
+    headers.insert(
+        "Retry-After".to_string(),
+        crate::download_slots::RETRY_AFTER_SECS.to_string(),
+    );
+    Response {
+        status_code: http_response.status_code,
+        status_text: http_response.status_text,
+        headers,
+        body: ResponseBody::Binary(http_response.body),
+    }
 }
 
 async fn send_response_async<S>(
     stream: &mut S,
     response: Response,
     log_prefix: &str,
+    write_timeout: Duration,
+    mut throttle: Option<&mut crate::bandwidth::ConnectionThrottle>,
+    head_only: bool,
 ) -> Result<u64, std::io::Error>
 where
-    S: tokio::io::AsyncWrite + Unpin,
+    S: tokio::io::AsyncWrite + Unpin + 'static,
 {
     let mut response_str = format!(
         "HTTP/1.1 {} {}
@@ -536,6 +1019,8 @@ where
             ResponseBody::Binary(bytes) => Some(bytes.len()),
             ResponseBody::StaticBinary(bytes) => Some(bytes.len()),
             ResponseBody::Stream(stream_body) => Some(stream_body.size as usize),
+            ResponseBody::Mapped(mapped) => Some(mapped.size),
+            ResponseBody::Cached(cached) => Some(cached.size),
             ResponseBody::AsyncStream(_) => None,
         };
         if let Some(length) = length_opt {
@@ -546,38 +1031,84 @@ where
     }
 
     response_str.push_str("\r\n");
-    stream.write_all(response_str.as_bytes()).await?;
+    write_all_with_timeout(stream, response_str.as_bytes(), write_timeout).await?;
 
     let mut body_sent: u64 = 0;
+    if head_only {
+        // HEAD: the headers above describe the body, which is never sent
+        tokio::time::timeout(write_timeout, stream.flush())
+            .await
+            .map_err(|_| write_timed_out())??;
+        trace!("{log_prefix} answered HEAD without a body");
+        return Ok(body_sent);
+    }
     match response.body {
         ResponseBody::Text(text) => {
             let bytes = text.as_bytes();
-            stream.write_all(bytes).await?;
+            write_body(stream, bytes, write_timeout, throttle.as_deref_mut()).await?;
             body_sent += bytes.len() as u64;
         }
         ResponseBody::StaticText(text) => {
             let bytes = text.as_bytes();
-            stream.write_all(bytes).await?;
+            write_body(stream, bytes, write_timeout, throttle.as_deref_mut()).await?;
             body_sent += bytes.len() as u64;
         }
         ResponseBody::Binary(bytes) => {
-            stream.write_all(&bytes).await?;
+            write_body(stream, &bytes, write_timeout, throttle.as_deref_mut()).await?;
             body_sent += bytes.len() as u64;
         }
         ResponseBody::StaticBinary(bytes) => {
-            stream.write_all(bytes).await?;
+            write_body(stream, bytes, write_timeout, throttle.as_deref_mut()).await?;
+            body_sent += bytes.len() as u64;
+        }
+        ResponseBody::Mapped(mapped) => {
+            let bytes = mapped.bytes();
+            write_body(stream, bytes, write_timeout, throttle.as_deref_mut()).await?;
+            body_sent += bytes.len() as u64;
+        }
+        ResponseBody::Cached(cached) => {
+            let bytes = cached.bytes();
+            write_body(stream, bytes, write_timeout, throttle.as_deref_mut()).await?;
             body_sent += bytes.len() as u64;
         }
         ResponseBody::Stream(stream_body) => {
             let mut file = tokio::fs::File::open(&stream_body.path).await?;
-            let mut buffer = vec![0; stream_body.chunk_size];
-            loop {
-                let bytes_read = file.read(&mut buffer).await?;
+            // Plain TCP without throttling can hand the copy to the kernel
+            if throttle.is_none()
+                && let Some(socket) = (stream as &mut dyn Any).downcast_mut::<TcpStream>()
+                && let ZeroCopy::Sent(sent) = crate::zero_copy::send_file(
+                    socket,
+                    &file,
+                    stream_body.offset,
+                    stream_body.size,
+                    write_timeout,
+                )
+                .await?
+            {
+                trace!("{log_prefix} sent {sent} bytes with sendfile");
+                return Ok(sent);
+            }
+            if stream_body.offset > 0 {
+                file.seek(std::io::SeekFrom::Start(stream_body.offset))
+                    .await?;
+            }
+            let mut buffer = vec![0; stream_body.chunk_size.max(1)];
+            let mut remaining = stream_body.size;
+            while remaining > 0 {
+                let want = buffer.len().min(remaining as usize);
+                let bytes_read = file.read(&mut buffer[..want]).await?;
                 if bytes_read == 0 {
                     break;
                 }
-                stream.write_all(&buffer[..bytes_read]).await?;
+                write_body(
+                    stream,
+                    &buffer[..bytes_read],
+                    write_timeout,
+                    throttle.as_deref_mut(),
+                )
+                .await?;
                 body_sent += bytes_read as u64;
+                remaining -= bytes_read as u64;
             }
         }
         ResponseBody::AsyncStream(mut receiver) => {
@@ -586,34 +1117,77 @@ where
                     continue;
                 }
                 let hex_size = format!("{:X}\r\n", chunk.len());
-                stream.write_all(hex_size.as_bytes()).await?;
-                stream.write_all(&chunk).await?;
-                stream.write_all(b"\r\n").await?;
+                write_all_with_timeout(stream, hex_size.as_bytes(), write_timeout).await?;
+                write_body(stream, &chunk, write_timeout, throttle.as_deref_mut()).await?;
+                write_all_with_timeout(stream, b"\r\n", write_timeout).await?;
                 body_sent += chunk.len() as u64;
             }
-            stream.write_all(b"0\r\n\r\n").await?;
+            write_all_with_timeout(stream, b"0\r\n\r\n", write_timeout).await?;
         }
     }
 
-    stream.flush().await?;
+    tokio::time::timeout(write_timeout, stream.flush())
+        .await
+        .map_err(|_| write_timed_out())??;
     trace!("{log_prefix} sent {body_sent} bytes");
     Ok(body_sent)
 }
 
-async fn read_with_timeout<S>(stream: &mut S, buf: &mut [u8]) -> Result<usize, AppError>
+fn write_timed_out() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::TimedOut, "write timeout")
+}
+
+/// Write part of a response body, pacing it through the bandwidth throttle
+async fn write_body<S>(
+    stream: &mut S,
+    data: &[u8],
+    timeout: Duration,
+    throttle: Option<&mut crate::bandwidth::ConnectionThrottle>,
+) -> Result<(), std::io::Error>
+where
+    S: tokio::io::AsyncWrite + Unpin,
+{
+    let Some(throttle) = throttle else {
+        return write_all_with_timeout(stream, data, timeout).await;
+    };
+    for slice in data.chunks(throttle.slice_size()) {
+        throttle.throttle(slice.len()).await;
+        write_all_with_timeout(stream, slice, timeout).await?;
+    }
+    Ok(())
+}
+
+async fn write_all_with_timeout<S>(
+    stream: &mut S,
+    data: &[u8],
+    timeout: Duration,
+) -> Result<(), std::io::Error>
+where
+    S: tokio::io::AsyncWrite + Unpin,
+{
+    tokio::time::timeout(timeout, stream.write_all(data))
+        .await
+        .map_err(|_| write_timed_out())?
+}
+
+async fn read_with_timeout<S>(
+    stream: &mut S,
+    buf: &mut [u8],
+    timeout: Duration,
+) -> Result<usize, AppError>
 where
     S: tokio::io::AsyncRead + Unpin,
 {
-    match tokio::time::timeout(Duration::from_secs(30), stream.read(buf)).await {
+    match tokio::time::timeout(timeout, stream.read(buf)).await {
         Ok(result) => result.map_err(AppError::Io),
-        Err(_) => Err(AppError::Io(std::io::Error::new(
-            std::io::ErrorKind::WouldBlock,
-            "read timeout",
-        ))),
+        Err(_) => Err(AppError::RequestTimeout),
     }
 }
 
-async fn read_headers_with_remaining_async<S>(stream: &mut S) -> Result<(String, Vec<u8>), AppError>
+async fn read_headers_with_remaining_async<S>(
+    stream: &mut S,
+    read_timeout: Duration,
+) -> Result<(String, Vec<u8>), AppError>
 where
     S: tokio::io::AsyncRead + Unpin,
 {
@@ -621,7 +1195,7 @@ where
     let mut total_read = 0;
 
     loop {
-        let bytes_read = read_with_timeout(stream, &mut buffer[total_read..]).await?;
+        let bytes_read = read_with_timeout(stream, &mut buffer[total_read..], read_timeout).await?;
         if bytes_read == 0 {
             if total_read == 0 {
                 return Err(AppError::BadRequest);
@@ -658,7 +1232,12 @@ where
         }
 
         if total_read >= buffer.len() {
-            return Err(AppError::BadRequest);
+            // Without a complete request line the target is what is too long
+            return Err(if buffer.contains(&b'\n') {
+                AppError::HeaderFieldsTooLarge
+            } else {
+                AppError::UriTooLong
+            });
         }
     }
 
@@ -666,10 +1245,48 @@ where
     Ok((data.to_string(), Vec::new()))
 }
 
+/// Read the body while publishing received bytes for `GET /_irondrop/upload/progress/<id>`
+async fn read_tracked_body_async<S>(
+    stream: &mut S,
+    read_timeout: Duration,
+    headers: &HashMap<String, String>,
+    remaining_bytes: Vec<u8>,
+    id: String,
+    max_body: usize,
+) -> Result<Option<RequestBody>, AppError>
+where
+    S: tokio::io::AsyncRead + Unpin,
+{
+    let total = headers
+        .get("content-length")
+        .and_then(|length| length.parse::<u64>().ok())
+        .unwrap_or(0);
+    let progress = crate::upload_progress::tracker();
+    progress.begin(&id, total);
+
+    let already_received = remaining_bytes.len() as u64;
+    let mut reader = crate::upload_progress::ProgressReader::new(stream, id, already_received);
+    let body = read_request_body_async(
+        &mut reader,
+        read_timeout,
+        headers,
+        remaining_bytes,
+        max_body,
+    )
+    .await;
+    reader.flush_progress();
+    if body.is_err() {
+        progress.finish(reader.id(), false);
+    }
+    body
+}
+
 async fn read_request_body_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     headers: &HashMap<String, String>,
     remaining_bytes: Vec<u8>,
+    max_body: usize,
 ) -> Result<Option<RequestBody>, AppError>
 where
     S: tokio::io::AsyncRead + Unpin,
@@ -682,7 +1299,7 @@ where
     }
 
     if has_chunked_transfer {
-        let body = read_chunked_body_async(stream, remaining_bytes).await?;
+        let body = read_chunked_body_async(stream, read_timeout, remaining_bytes, max_body).await?;
         return Ok(Some(body));
     }
 
@@ -697,22 +1314,26 @@ where
         return Ok(Some(RequestBody::Memory(Vec::new())));
     }
 
-    if content_length > MAX_REQUEST_BODY_SIZE {
-        return Err(AppError::PayloadTooLarge(MAX_REQUEST_BODY_SIZE as u64));
+    if content_length > max_body {
+        return Err(AppError::PayloadTooLarge(max_body as u64));
     }
 
     if content_length <= STREAM_TO_DISK_THRESHOLD {
-        let body = read_body_to_memory_async(stream, content_length, remaining_bytes).await?;
+        let body = read_body_to_memory_async(stream, read_timeout, content_length, remaining_bytes)
+            .await?;
         Ok(Some(RequestBody::Memory(body)))
     } else {
-        let (path, size) = read_body_to_disk_async(stream, content_length, remaining_bytes).await?;
+        let (path, size) =
+            read_body_to_disk_async(stream, read_timeout, content_length, remaining_bytes).await?;
         Ok(Some(RequestBody::File { path, size }))
     }
 }
 
 async fn read_chunked_body_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     remaining_bytes: Vec<u8>,
+    max_body: usize,
 ) -> Result<RequestBody, AppError>
 where
     S: tokio::io::AsyncRead + Unpin,
@@ -723,9 +1344,11 @@ where
     let mut total_size: usize = 0;
     let mut memory_body: Vec<u8> = Vec::new();
     let mut file_sink: Option<(PathBuf, tokio::fs::File)> = None;
+    let mut flow = crate::bandwidth::UploadFlow::new();
 
     loop {
-        let line = read_crlf_line_async(stream, &mut pending, CHUNK_LINE_LIMIT).await?;
+        let line =
+            read_crlf_line_async(stream, read_timeout, &mut pending, CHUNK_LINE_LIMIT).await?;
         let line_str = std::str::from_utf8(&line).map_err(|_| AppError::BadRequest)?;
         let size_token = line_str
             .split(';')
@@ -738,41 +1361,58 @@ where
 
         let chunk_size = usize::from_str_radix(size_token, 16).map_err(|_| AppError::BadRequest)?;
         if chunk_size == 0 {
-            consume_chunked_trailers_async(stream, &mut pending).await?;
+            consume_chunked_trailers_async(stream, read_timeout, &mut pending).await?;
             break;
         }
 
         let next_total = total_size
             .checked_add(chunk_size)
-            .ok_or(AppError::PayloadTooLarge(MAX_REQUEST_BODY_SIZE as u64))?;
-        if next_total > MAX_REQUEST_BODY_SIZE {
-            return Err(AppError::PayloadTooLarge(MAX_REQUEST_BODY_SIZE as u64));
+            .ok_or(AppError::PayloadTooLarge(max_body as u64))?;
+        if next_total > max_body {
+            return Err(AppError::PayloadTooLarge(max_body as u64));
         }
 
-        let chunk_data = read_exact_from_buffer_async(stream, &mut pending, chunk_size).await?;
-        consume_expected_crlf_async(stream, &mut pending).await?;
-
         if file_sink.is_none() && next_total <= STREAM_TO_DISK_THRESHOLD {
+            let chunk_data =
+                read_exact_from_buffer_async(stream, read_timeout, &mut pending, chunk_size)
+                    .await?;
+            flow.received(chunk_size).await;
             memory_body.extend_from_slice(&chunk_data);
         } else {
             if file_sink.is_none() {
                 let (temp_path, mut temp_file) = create_temp_body_file_async().await?;
                 if !memory_body.is_empty() {
-                    temp_file.write_all(&memory_body).await.map_err(|e| {
-                        let _ = std::fs::remove_file(&temp_path);
-                        AppError::from(e)
-                    })?;
+                    flow.write(&mut temp_file, &memory_body)
+                        .await
+                        .map_err(|e| {
+                            let _ = std::fs::remove_file(&temp_path);
+                            AppError::from(e)
+                        })?;
                     memory_body.clear();
                 }
                 file_sink = Some((temp_path, temp_file));
             }
             if let Some((temp_path, temp_file)) = file_sink.as_mut() {
-                temp_file.write_all(&chunk_data).await.map_err(|e| {
-                    let _ = std::fs::remove_file(temp_path);
-                    AppError::from(e)
-                })?;
+                // Copy large chunks piece by piece instead of buffering them whole
+                let mut left = chunk_size;
+                while left > 0 {
+                    let want = flow.read_size(left.min(DISK_BODY_CHUNK_SIZE));
+                    let piece =
+                        read_exact_from_buffer_async(stream, read_timeout, &mut pending, want)
+                            .await
+                            .inspect_err(|_| {
+                                let _ = std::fs::remove_file(&*temp_path);
+                            })?;
+                    flow.received(piece.len()).await;
+                    flow.write(temp_file, &piece).await.map_err(|e| {
+                        let _ = std::fs::remove_file(&*temp_path);
+                        AppError::from(e)
+                    })?;
+                    left -= piece.len();
+                }
             }
         }
+        consume_expected_crlf_async(stream, read_timeout, &mut pending).await?;
 
         total_size = next_total;
     }
@@ -796,7 +1436,8 @@ where
 
 async fn create_temp_body_file_async() -> Result<(PathBuf, tokio::fs::File), AppError> {
     let temp_filename = format!(
-        "irondrop_request_{}_{:x}_{}.tmp",
+        "{}{}_{:x}_{}.tmp",
+        crate::upload::REQUEST_BODY_PREFIX,
         std::process::id(),
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -819,6 +1460,7 @@ async fn create_temp_body_file_async() -> Result<(PathBuf, tokio::fs::File), App
 
 async fn read_crlf_line_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     pending: &mut PendingBuffer,
     max_line_len: usize,
 ) -> Result<Vec<u8>, AppError>
@@ -837,7 +1479,7 @@ where
         }
 
         let mut buffer = [0u8; 8192];
-        let n = read_with_timeout(stream, &mut buffer).await?;
+        let n = read_with_timeout(stream, &mut buffer, read_timeout).await?;
         if n == 0 {
             return Err(AppError::BadRequest);
         }
@@ -847,6 +1489,7 @@ where
 
 async fn read_exact_from_buffer_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     pending: &mut PendingBuffer,
     count: usize,
 ) -> Result<Vec<u8>, AppError>
@@ -855,7 +1498,7 @@ where
 {
     while pending.len() < count {
         let mut buffer = [0u8; 8192];
-        let n = read_with_timeout(stream, &mut buffer).await?;
+        let n = read_with_timeout(stream, &mut buffer, read_timeout).await?;
         if n == 0 {
             return Err(AppError::BadRequest);
         }
@@ -866,12 +1509,13 @@ where
 
 async fn consume_expected_crlf_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     pending: &mut PendingBuffer,
 ) -> Result<(), AppError>
 where
     S: tokio::io::AsyncRead + Unpin,
 {
-    let crlf = read_exact_from_buffer_async(stream, pending, 2).await?;
+    let crlf = read_exact_from_buffer_async(stream, read_timeout, pending, 2).await?;
     if crlf != b"\r\n" {
         return Err(AppError::BadRequest);
     }
@@ -880,6 +1524,7 @@ where
 
 async fn consume_chunked_trailers_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     pending: &mut PendingBuffer,
 ) -> Result<(), AppError>
 where
@@ -887,7 +1532,7 @@ where
 {
     let mut total_trailer_size = 0usize;
     loop {
-        let line = read_crlf_line_async(stream, pending, MAX_HEADERS_SIZE).await?;
+        let line = read_crlf_line_async(stream, read_timeout, pending, MAX_HEADERS_SIZE).await?;
         total_trailer_size += line.len() + 2;
         if total_trailer_size > MAX_HEADERS_SIZE {
             return Err(AppError::BadRequest);
@@ -901,6 +1546,7 @@ where
 
 async fn read_body_to_memory_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     content_length: usize,
     remaining_bytes: Vec<u8>,
 ) -> Result<Vec<u8>, AppError>
@@ -914,14 +1560,16 @@ where
 
     if bytes_needed > 0 {
         let mut bytes_read = 0;
-        let chunk_size = 8192;
+        let mut flow = crate::bandwidth::UploadFlow::new();
+        let chunk_size = flow.read_size(8192);
         let mut buffer = vec![0; chunk_size];
         while bytes_read < bytes_needed {
             let to_read = (bytes_needed - bytes_read).min(chunk_size);
-            let n = read_with_timeout(stream, &mut buffer[..to_read]).await?;
+            let n = read_with_timeout(stream, &mut buffer[..to_read], read_timeout).await?;
             if n == 0 {
                 return Err(AppError::BadRequest);
             }
+            flow.received(n).await;
             body.extend_from_slice(&buffer[..n]);
             bytes_read += n;
         }
@@ -935,6 +1583,7 @@ where
 
 async fn read_body_to_disk_async<S>(
     stream: &mut S,
+    read_timeout: Duration,
     content_length: usize,
     remaining_bytes: Vec<u8>,
 ) -> Result<(PathBuf, u64), AppError>
@@ -942,7 +1591,8 @@ where
     S: tokio::io::AsyncRead + Unpin,
 {
     let temp_filename = format!(
-        "irondrop_request_{}_{:x}_{}.tmp",
+        "{}{}_{:x}_{}.tmp",
+        crate::upload::REQUEST_BODY_PREFIX,
         std::process::id(),
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -962,11 +1612,11 @@ where
             AppError::from(e)
         })?;
 
+    let mut flow = crate::bandwidth::UploadFlow::new();
     let mut total_written: usize = 0;
     if !remaining_bytes.is_empty() {
         let bytes_to_write = remaining_bytes.len().min(content_length);
-        temp_file
-            .write_all(&remaining_bytes[..bytes_to_write])
+        flow.write(&mut temp_file, &remaining_bytes[..bytes_to_write])
             .await
             .map_err(|e| {
                 let _ = std::fs::remove_file(&temp_path);
@@ -978,19 +1628,26 @@ where
     let bytes_needed = content_length - total_written;
     if bytes_needed > 0 {
         let mut bytes_read = 0usize;
-        let chunk_size = 64 * 1024;
+        let chunk_size = flow.read_size(DISK_BODY_CHUNK_SIZE);
         let mut buffer = vec![0; chunk_size];
         while bytes_read < bytes_needed {
             let to_read = (bytes_needed - bytes_read).min(chunk_size);
-            let n = read_with_timeout(stream, &mut buffer[..to_read]).await?;
+            let n = read_with_timeout(stream, &mut buffer[..to_read], read_timeout)
+                .await
+                .inspect_err(|_| {
+                    let _ = std::fs::remove_file(&temp_path);
+                })?;
             if n == 0 {
                 let _ = std::fs::remove_file(&temp_path);
                 return Err(AppError::BadRequest);
             }
-            temp_file.write_all(&buffer[..n]).await.map_err(|e| {
-                let _ = std::fs::remove_file(&temp_path);
-                AppError::from(e)
-            })?;
+            flow.received(n).await;
+            flow.write(&mut temp_file, &buffer[..n])
+                .await
+                .map_err(|e| {
+                    let _ = std::fs::remove_file(&temp_path);
+                    AppError::from(e)
+                })?;
             bytes_read += n;
             total_written += n;
         }
@@ -1031,9 +1688,14 @@ mod perf_tests {
         drop(writer);
 
         let start = Instant::now();
-        let body = read_chunked_body_async(&mut reader, Vec::new())
-            .await
-            .unwrap();
+        let body = read_chunked_body_async(
+            &mut reader,
+            Duration::from_secs(30),
+            Vec::new(),
+            MAX_REQUEST_BODY_SIZE,
+        )
+        .await
+        .unwrap();
         let elapsed_ms = start.elapsed().as_millis();
 
         match body {
@@ -0,0 +1,406 @@
+// SPDX-License-Identifier: MIT
+
+//! HTTP/2 front end (`http2` feature)
+//!
+//! TLS clients that pick `h2` via ALPN are served here. Every HTTP/2 stream
+//! is replayed as a single HTTP/1.1 exchange over an in-memory pipe into the
+//! regular connection handler, so routing, authentication, limits, timeouts
+//! and streamed bodies behave exactly as they do for HTTP/1.1. The response
+//! the handler writes back is parsed and sent on the stream, honoring the
+//! peer's flow control.
+
+use crate::http::ConnectionTimeouts;
+use bytes::Bytes;
+use h2::server::SendResponse;
+use h2::{RecvStream, SendStream};
+use log::{debug, trace};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::io::{DuplexStream, WriteHalf};
+
+/// ALPN protocol id for HTTP/2 over TLS
+pub const ALPN_H2: &[u8] = b"h2";
+
+/// ALPN protocol id for HTTP/1.1
+pub const ALPN_HTTP11: &[u8] = b"http/1.1";
+
+/// Stream limit advertised when rate limiting does not cap it
+pub const DEFAULT_MAX_CONCURRENT_STREAMS: u32 = 100;
+
+/// Buffer size of the pipe between a stream and its HTTP/1.1 handler
+const PIPE_BUFFER: usize = 64 * 1024;
+
+/// Largest response head accepted back from the handler
+const MAX_RESPONSE_HEAD: usize = 64 * 1024;
+
+/// Headers that are connection-specific in HTTP/1.1 and forbidden in HTTP/2
+const HOP_BY_HOP: [&str; 5] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Serve an HTTP/2 connection until the client closes it or stays idle for
+/// longer than the read timeout.
+///
+/// `serve` is called once per stream with the handler side of the pipe and
+/// returns the HTTP/1.1 handler future, or `None` to refuse the stream with
+/// `429 Too Many Requests`.
+pub async fn serve_connection<S, F, Fut>(
+    io: S,
+    peer_addr: SocketAddr,
+    timeouts: ConnectionTimeouts,
+    max_concurrent_streams: u32,
+    serve: F,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    F: Fn(DuplexStream) -> Option<Fut>,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let handshake = h2::server::Builder::new()
+        .max_concurrent_streams(max_concurrent_streams.max(1))
+        .handshake::<_, Bytes>(io);
+    let mut connection = match tokio::time::timeout(timeouts.header, handshake).await {
+        Ok(Ok(connection)) => connection,
+        Ok(Err(e)) => {
+            debug!("[{peer_addr}] HTTP/2 handshake failed: {e}");
+            return;
+        }
+        Err(_) => {
+            debug!("[{peer_addr}] HTTP/2 handshake timed out");
+            return;
+        }
+    };
+    trace!("[{peer_addr}] HTTP/2 connection established");
+
+    let active = Arc::new(AtomicUsize::new(0));
+    loop {
+        let accepted = match tokio::time::timeout(timeouts.read, connection.accept()).await {
+            Ok(accepted) => accepted,
+            Err(_) if active.load(Ordering::Relaxed) == 0 => {
+                debug!("[{peer_addr}] closing idle HTTP/2 connection");
+                connection.graceful_shutdown();
+                let _ = std::future::poll_fn(|cx| connection.poll_closed(cx)).await;
+                break;
+            }
+            Err(_) => continue,
+        };
+        let (request, respond) = match accepted {
+            Some(Ok(stream)) => stream,
+            Some(Err(e)) => {
+                debug!("[{peer_addr}] HTTP/2 connection error: {e}");
+                break;
+            }
+            None => break,
+        };
+
+        let (pipe, handler_side) = tokio::io::duplex(PIPE_BUFFER);
+        let Some(handler) = serve(handler_side) else {
+            refuse_stream(respond);
+            continue;
+        };
+        tokio::spawn(handler);
+        let active = active.clone();
+        active.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(async move {
+            if let Err(e) = bridge_stream(request, respond, pipe, timeouts).await {
+                trace!("[{peer_addr}] HTTP/2 stream ended early: {e}");
+            }
+            active.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+}
+
+/// Answer a stream the rate limiter turned away
+fn refuse_stream(mut respond: SendResponse<Bytes>) {
+    let response = http::Response::builder()
+        .status(429)
+        .header("retry-after", "60")
+        .body(())
+        .expect("static response is valid");
+    let _ = respond.send_response(response, true);
+}
+
+/// Carry one stream through the pipe: request in, response out
+async fn bridge_stream(
+    request: http::Request<RecvStream>,
+    mut respond: SendResponse<Bytes>,
+    pipe: DuplexStream,
+    timeouts: ConnectionTimeouts,
+) -> std::io::Result<()> {
+    let (parts, body) = request.into_parts();
+    let head_only = parts.method == http::Method::HEAD;
+    let (reader, writer) = tokio::io::split(pipe);
+
+    let (head, chunked) = request_head(&parts);
+    let upload = tokio::spawn(forward_request_body(writer, head, body, chunked));
+
+    let mut reader = BufReader::new(reader);
+    let result = async {
+        let (response, body_chunked) = read_response_head(&mut reader).await?;
+        let no_body = head_only || matches!(response.status().as_u16(), 204 | 304);
+        let mut send = respond.send_response(response, no_body).map_err(h2_io)?;
+        if !no_body {
+            forward_response_body(&mut reader, &mut send, body_chunked, timeouts).await?;
+        }
+        Ok(())
+    }
+    .await;
+    if result.is_err() {
+        respond.send_reset(h2::Reason::INTERNAL_ERROR);
+    }
+    upload.abort();
+    result
+}
+
+/// Rebuild the HTTP/1.1 request head; returns it with whether the body has
+/// to be sent chunked (HTTP/2 bodies need no `Content-Length`)
+fn request_head(parts: &http::request::Parts) -> (String, bool) {
+    let target = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let mut head = format!("{} {target} HTTP/1.1\r\n", parts.method);
+
+    if !parts.headers.contains_key(http::header::HOST)
+        && let Some(authority) = parts.uri.authority()
+    {
+        head.push_str(&format!("host: {authority}\r\n"));
+    }
+    for name in parts.headers.keys() {
+        if HOP_BY_HOP.contains(&name.as_str()) || name == http::header::TE {
+            continue;
+        }
+        // Repeated fields are folded; cookies use their own separator
+        let separator = if name == http::header::COOKIE {
+            "; "
+        } else {
+            ", "
+        };
+        let values: Vec<&str> = parts
+            .headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .collect();
+        if !values.is_empty() {
+            head.push_str(&format!("{name}: {}\r\n", values.join(separator)));
+        }
+    }
+
+    let has_length = parts.headers.contains_key(http::header::CONTENT_LENGTH);
+    let may_have_body = !matches!(
+        parts.method,
+        http::Method::GET | http::Method::HEAD | http::Method::OPTIONS
+    );
+    let chunked = !has_length && may_have_body;
+    if chunked {
+        head.push_str("transfer-encoding: chunked\r\n");
+    }
+    head.push_str("\r\n");
+    (head, chunked)
+}
+
+async fn forward_request_body(
+    mut writer: WriteHalf<DuplexStream>,
+    head: String,
+    mut body: RecvStream,
+    chunked: bool,
+) -> std::io::Result<()> {
+    writer.write_all(head.as_bytes()).await?;
+    while let Some(data) = body.data().await {
+        let data = data.map_err(h2_io)?;
+        let _ = body.flow_control().release_capacity(data.len());
+        if data.is_empty() {
+            continue;
+        }
+        if chunked {
+            writer
+                .write_all(format!("{:X}\r\n", data.len()).as_bytes())
+                .await?;
+            writer.write_all(&data).await?;
+            writer.write_all(b"\r\n").await?;
+        } else {
+            writer.write_all(&data).await?;
+        }
+    }
+    if chunked {
+        writer.write_all(b"0\r\n\r\n").await?;
+    }
+    writer.flush().await
+}
+
+/// Parse the status line and headers the handler wrote; returns the
+/// response with whether its body is chunked
+async fn read_response_head<R>(reader: &mut R) -> std::io::Result<(http::Response<()>, bool)>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    let mut line = String::new();
+    let mut head_len = 0;
+    let mut read_line = async |line: &mut String| -> std::io::Result<()> {
+        line.clear();
+        let n = reader.read_line(line).await?;
+        head_len += n;
+        if n == 0 || head_len > MAX_RESPONSE_HEAD {
+            return Err(invalid("truncated response head"));
+        }
+        Ok(())
+    };
+
+    read_line(&mut line).await?;
+    let status: u16 = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| invalid("malformed status line"))?;
+    let mut builder = http::Response::builder().status(status);
+    let mut chunked = false;
+    loop {
+        read_line(&mut line).await?;
+        let field = line.trim_end_matches(['\r', '\n']);
+        if field.is_empty() {
+            break;
+        }
+        let Some((name, value)) = field.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let value = value.trim();
+        if name == "transfer-encoding" {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        }
+        if HOP_BY_HOP.contains(&name.as_str()) {
+            continue;
+        }
+        builder = builder.header(name, value);
+    }
+    let response = builder.body(()).map_err(|e| invalid(&e.to_string()))?;
+    Ok((response, chunked))
+}
+
+async fn forward_response_body<R>(
+    reader: &mut R,
+    send: &mut SendStream<Bytes>,
+    chunked: bool,
+    timeouts: ConnectionTimeouts,
+) -> std::io::Result<()>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    let mut buffer = vec![0u8; PIPE_BUFFER];
+    if chunked {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await? == 0 {
+                return Err(invalid("truncated chunked body"));
+            }
+            let size = usize::from_str_radix(line.trim().split(';').next().unwrap_or(""), 16)
+                .map_err(|_| invalid("bad chunk size"))?;
+            if size == 0 {
+                break;
+            }
+            let mut remaining = size;
+            while remaining > 0 {
+                let want = remaining.min(buffer.len());
+                reader.read_exact(&mut buffer[..want]).await?;
+                send_data(send, &buffer[..want], timeouts).await?;
+                remaining -= want;
+            }
+            let mut crlf = [0u8; 2];
+            reader.read_exact(&mut crlf).await?;
+        }
+    } else {
+        // The handler closes the connection after each response
+        loop {
+            let n = reader.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            send_data(send, &buffer[..n], timeouts).await?;
+        }
+    }
+    send.send_data(Bytes::new(), true).map_err(h2_io)
+}
+
+/// Send body bytes as the peer's flow-control window allows
+async fn send_data(
+    send: &mut SendStream<Bytes>,
+    data: &[u8],
+    timeouts: ConnectionTimeouts,
+) -> std::io::Result<()> {
+    let mut data = Bytes::copy_from_slice(data);
+    while !data.is_empty() {
+        send.reserve_capacity(data.len());
+        let capacity = tokio::time::timeout(
+            timeouts.write,
+            std::future::poll_fn(|cx| send.poll_capacity(cx)),
+        )
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "write timeout"))?
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "stream closed"))?
+        .map_err(h2_io)?;
+        if capacity == 0 {
+            continue;
+        }
+        let part = data.split_to(capacity.min(data.len()));
+        send.send_data(part, false).map_err(h2_io)?;
+    }
+    Ok(())
+}
+
+fn h2_io(e: h2::Error) -> std::io::Error {
+    std::io::Error::other(e)
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_head_translation() {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri("https://localhost:8443/_irondrop/upload?upload_to=/a")
+            .header("cookie", "a=1")
+            .header("cookie", "b=2")
+            .header("accept", "text/html")
+            .header("te", "trailers")
+            .body(())
+            .unwrap();
+        let (parts, ()) = request.into_parts();
+        let (head, chunked) = request_head(&parts);
+
+        assert!(chunked);
+        assert!(head.starts_with("POST /_irondrop/upload?upload_to=/a HTTP/1.1\r\n"));
+        assert!(head.contains("host: localhost:8443\r\n"));
+        assert!(head.contains("cookie: a=1; b=2\r\n"));
+        assert!(head.contains("accept: text/html\r\n"));
+        assert!(!head.contains("te:"));
+        assert!(head.ends_with("transfer-encoding: chunked\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_read_response_head_drops_hop_by_hop_headers() {
+        let raw: &[u8] = b"HTTP/1.1 200 OK\r\nServer: irondrop\r\nConnection: close\r\nTransfer-Encoding: chunked\r\nContent-Type: text/plain\r\n\r\n";
+        let mut reader = BufReader::new(raw);
+        let (response, chunked) = read_response_head(&mut reader).await.unwrap();
+
+        assert!(chunked);
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/plain");
+        assert!(response.headers().get("connection").is_none());
+        assert!(response.headers().get("transfer-encoding").is_none());
+    }
+}
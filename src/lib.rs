@@ -22,27 +22,67 @@
 /// The version of `IronDrop`, automatically derived from Cargo.toml
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub mod archive;
+pub mod audit;
+pub mod bandwidth;
+pub mod checksum;
 pub mod cli;
+pub mod compression;
 pub mod config;
+pub mod disk_space;
+pub mod download_slots;
+pub mod edit;
 pub mod error;
+pub mod file_cache;
+pub mod file_ops;
 pub mod fs;
 pub mod handlers;
+pub mod health;
+pub mod hooks;
 pub mod http;
+#[cfg(feature = "http2")]
+pub mod http2;
+pub mod listener;
+pub mod log_rotation;
+pub mod mdns;
 pub mod middleware;
+pub mod mmap_cache;
+pub mod partial_put;
+pub mod password;
+pub mod preview;
+pub mod qr;
+pub mod quick_share;
+pub mod quota;
+pub mod read_only;
+pub mod redirects;
 pub mod response;
 pub mod router;
+pub mod saved_search;
 pub mod search;
+pub mod send;
 pub mod server;
+pub mod service;
+pub mod session;
+pub mod share;
+pub mod systemd;
 pub mod templates;
 pub mod ultra_compact_search;
 #[cfg(test)]
 pub mod ultra_memory_test;
 pub mod upload;
+pub mod upload_meta;
+pub mod upload_progress;
+pub mod upload_session;
+pub mod users;
 pub mod utils;
+pub mod watcher;
 pub mod webdav;
+pub mod worker_pool;
+pub mod zero_copy;
 
 use crate::cli::Cli;
 use crate::config::Config;
+use crate::log_rotation::{RotatingLogFile, RotationPolicy};
 use clap::Parser;
 use log::error;
 use std::fs::OpenOptions;
@@ -55,7 +95,24 @@ use std::time::{SystemTime, UNIX_EPOCH};
 /// framework and then calls the `run_server` function to start the server.
 /// If the server returns an error, it is logged and the process exits.
 pub fn run() {
+    // Subcommands are picked out before the regular arguments (which
+    // require `--directory`) are parsed
+    match std::env::args_os().nth(1) {
+        Some(arg) if arg == "hash-password" => {
+            std::process::exit(password::hash_password_command());
+        }
+        Some(arg) if arg == "send" => {
+            std::process::exit(send::send_command(std::env::args_os().skip(1)));
+        }
+        Some(arg) if arg == "serve" => {
+            std::process::exit(quick_share::serve_command(std::env::args_os().skip(1)));
+        }
+        _ => {}
+    }
     let cli = Cli::parse();
+    if cli.check_config {
+        std::process::exit(check_config(&cli));
+    }
 
     // Load configuration with precedence: CLI > ENV > INI > Defaults
     let config = match Config::load(&cli) {
@@ -66,6 +123,33 @@ pub fn run() {
         }
     };
 
+    let service_action = cli
+        .service
+        .as_deref()
+        .and_then(service::ServiceAction::parse);
+    let service_result = match service_action {
+        Some(service::ServiceAction::Install) => {
+            let args: Vec<String> = std::env::args_os()
+                .skip(1)
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            Some(service::install(&args, &cli))
+        }
+        Some(service::ServiceAction::Uninstall) => Some(service::uninstall()),
+        _ => None,
+    };
+    match service_result {
+        Some(Ok(message)) => {
+            println!("{message}");
+            std::process::exit(0);
+        }
+        Some(Err(e)) => {
+            eprintln!("Service error: {e}");
+            std::process::exit(1);
+        }
+        None => {}
+    }
+
     let log_level = if config.verbose {
         "debug"
     } else if config.detailed_logging {
@@ -84,7 +168,7 @@ pub fn run() {
 
     // Initialize logging with optional file output
     if let Some(ref log_dir_path) = config.log_dir {
-        init_file_logger(log_dir_path).unwrap_or_else(|e| {
+        init_file_logger(log_dir_path, config.log_rotation_policy()).unwrap_or_else(|e| {
             eprintln!("Failed to initialize file logger: {e}");
             std::process::exit(1);
         });
@@ -105,14 +189,76 @@ pub fn run() {
         std::process::exit(1);
     }
 
-    if let Err(e) = server::run_server_with_config(config) {
+    config::reload::install_sighup_handler();
+    let reloader = config::reload::ConfigReloader::new(cli);
+    #[cfg(windows)]
+    if service_action == Some(service::ServiceAction::Run) {
+        let result = service::run_windows_service(move |shutdown| {
+            server::run_server_with_reload(config, Some(reloader), Some(shutdown), None)
+                .map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            error!("Server error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Err(e) = server::run_server_with_reload(config, Some(reloader), None, None) {
         error!("Server error: {e}");
         std::process::exit(1);
     }
 }
 
+/// `--check-config`: print the effective configuration and any problems,
+/// returning the exit code
+fn check_config(cli: &Cli) -> i32 {
+    // Config loading reports values it ignores as warnings
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+        .format_timestamp(None)
+        .init();
+
+    let source = match Config::find_config_file(cli) {
+        Ok(Some(path)) => format!("{} and the command line", path.display()),
+        Ok(None) => "defaults and the command line".to_string(),
+        Err(e) => {
+            eprintln!("error: {e}");
+            return 1;
+        }
+    };
+    let config = match Config::load(cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return 1;
+        }
+    };
+    println!("Effective configuration (from {source}):");
+    println!("{:#?}", config.masked());
+
+    let check = server::check_config(&config);
+    for warning in &check.warnings {
+        eprintln!("warning: {warning}");
+    }
+    for error in &check.errors {
+        eprintln!("error: {error}");
+    }
+    if check.is_ok() {
+        println!("Configuration OK");
+        0
+    } else {
+        eprintln!("Configuration has {} error(s)", check.errors.len());
+        1
+    }
+}
+
 /// Initialize file-based logging with timestamped log files
-fn init_file_logger(log_dir_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// When a rotation size or age is configured the file is rotated in place
+/// (`irondrop_<timestamp>.log.1`, `.2`, ...); otherwise it grows without limit.
+fn init_file_logger(
+    log_dir_path: &Path,
+    rotation: RotationPolicy,
+) -> Result<(), Box<dyn std::error::Error>> {
     use env_logger::Builder;
 
     // Generate timestamp without chrono
@@ -124,13 +270,19 @@ fn init_file_logger(log_dir_path: &Path) -> Result<(), Box<dyn std::error::Error
     let log_filename = format!("irondrop_{}.log", timestamp);
     let log_file_path = log_dir_path.join(log_filename);
 
-    let log_file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_file_path)?;
+    let target: Box<dyn std::io::Write + Send> = if rotation.is_enabled() {
+        Box::new(RotatingLogFile::open(log_file_path, rotation)?)
+    } else {
+        Box::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_file_path)?,
+        )
+    };
 
     Builder::from_default_env()
-        .target(env_logger::Target::Pipe(Box::new(log_file)))
+        .target(env_logger::Target::Pipe(target))
         .init();
 
     Ok(())
@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MIT
+
+//! Listening socket and accepted-connection tuning (`[tcp]`)
+//!
+//! The listener is created with an explicit accept backlog and optional
+//! `SO_REUSEADDR` / `SO_REUSEPORT`. Every accepted connection can get
+//! `TCP_NODELAY` and TCP keepalive probes. After each wake-up the accept loop
+//! takes up to `accept_batch` connections that are already waiting, which
+//! helps when many LAN clients connect at once.
+//!
+//! The defaults match the previous behavior: a backlog of 1024, address reuse
+//! on Unix, one connection per wake-up and no `TCP_NODELAY` or keepalive.
+
+use crate::cli::Cli;
+use log::debug;
+use std::net::SocketAddr;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+/// Default accept backlog (what `TcpListener::bind` uses)
+pub const DEFAULT_BACKLOG: u32 = 1024;
+
+/// Default connections accepted per wake-up
+pub const DEFAULT_ACCEPT_BATCH: usize = 1;
+
+/// TCP keepalive probe timing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    /// Idle time before the first probe
+    pub idle: Duration,
+    /// Time between probes (system default when unset)
+    pub interval: Option<Duration>,
+    /// Unanswered probes before the connection is dropped (system default when unset)
+    pub retries: Option<u32>,
+}
+
+/// Socket options applied to the listener and accepted connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerOptions {
+    pub backlog: u32,
+    pub nodelay: bool,
+    pub reuse_address: bool,
+    pub reuse_port: bool,
+    pub keepalive: Option<Keepalive>,
+    pub accept_batch: usize,
+}
+
+impl Default for ListenerOptions {
+    fn default() -> Self {
+        Self {
+            backlog: DEFAULT_BACKLOG,
+            nodelay: false,
+            reuse_address: cfg!(unix),
+            reuse_port: false,
+            keepalive: None,
+            accept_batch: DEFAULT_ACCEPT_BATCH,
+        }
+    }
+}
+
+impl ListenerOptions {
+    pub fn from_cli(cli: &Cli) -> Self {
+        let defaults = Self::default();
+        Self {
+            backlog: cli.tcp_backlog.unwrap_or(defaults.backlog),
+            nodelay: cli.tcp_nodelay.unwrap_or(defaults.nodelay),
+            reuse_address: cli.tcp_reuse_address.unwrap_or(defaults.reuse_address),
+            reuse_port: cli.tcp_reuse_port.unwrap_or(defaults.reuse_port),
+            keepalive: cli
+                .tcp_keepalive
+                .filter(|&secs| secs > 0)
+                .map(|idle| Keepalive {
+                    idle: Duration::from_secs(idle),
+                    interval: cli.tcp_keepalive_interval.map(Duration::from_secs),
+                    retries: cli.tcp_keepalive_retries,
+                }),
+            accept_batch: cli.accept_batch.unwrap_or(defaults.accept_batch).max(1),
+        }
+    }
+
+    /// Create the listening socket for `addr`
+    pub fn bind(&self, addr: SocketAddr) -> std::io::Result<TcpListener> {
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        socket.set_reuseaddr(self.reuse_address)?;
+        if self.reuse_port {
+            #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+            socket.set_reuseport(true)?;
+            #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+            log::warn!("SO_REUSEPORT is not supported on this platform; ignoring tcp reuse_port");
+        }
+        socket.bind(addr)?;
+        debug!("Listening on {addr} with backlog {}", self.backlog);
+        socket.listen(self.backlog)
+    }
+
+    /// Apply per-connection options; failures are logged and ignored
+    pub fn configure_stream(&self, stream: &TcpStream) {
+        if self.nodelay
+            && let Err(e) = stream.set_nodelay(true)
+        {
+            debug!("Failed to set TCP_NODELAY: {e}");
+        }
+        if let Some(keepalive) = self.keepalive
+            && let Err(e) = set_keepalive(stream, keepalive)
+        {
+            debug!("Failed to enable TCP keepalive: {e}");
+        }
+    }
+}
+
+/// Move connections that are already waiting into `batch`, up to `max` in
+/// total, without waiting for new ones
+pub fn drain_ready(listener: &TcpListener, max: usize, batch: &mut Vec<(TcpStream, SocketAddr)>) {
+    let mut cx = Context::from_waker(Waker::noop());
+    while batch.len() < max {
+        match listener.poll_accept(&mut cx) {
+            Poll::Ready(Ok(connection)) => batch.push(connection),
+            Poll::Ready(Err(e)) => {
+                debug!("Accept failed while draining the backlog: {e}");
+                break;
+            }
+            Poll::Pending => break,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn set_keepalive(stream: &TcpStream, keepalive: Keepalive) -> std::io::Result<()> {
+    use std::ffi::c_int;
+    use std::os::fd::AsRawFd;
+
+    // Same declaration as in `mdns`
+    unsafe extern "C" {
+        fn setsockopt(fd: i32, level: i32, name: i32, value: *const i32, len: u32) -> i32;
+    }
+
+    #[cfg(target_os = "linux")]
+    mod opt {
+        pub const SOL_SOCKET: i32 = 1;
+        pub const SO_KEEPALIVE: i32 = 9;
+        pub const TCP_KEEPIDLE: i32 = 4;
+        pub const TCP_KEEPINTVL: i32 = 5;
+        pub const TCP_KEEPCNT: i32 = 6;
+    }
+    #[cfg(target_os = "macos")]
+    mod opt {
+        pub const SOL_SOCKET: i32 = 0xffff;
+        pub const SO_KEEPALIVE: i32 = 0x0008;
+        pub const TCP_KEEPIDLE: i32 = 0x10; // TCP_KEEPALIVE
+        pub const TCP_KEEPINTVL: i32 = 0x101;
+        pub const TCP_KEEPCNT: i32 = 0x102;
+    }
+    const IPPROTO_TCP: c_int = 6;
+
+    let fd = stream.as_raw_fd();
+    let set = |level: c_int, name: c_int, value: c_int| {
+        // SAFETY: `value` lives across the call and `fd` is an open socket
+        let rc =
+            unsafe { setsockopt(fd, level, name, &value, std::mem::size_of::<c_int>() as u32) };
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    };
+    let secs = |d: Duration| d.as_secs().clamp(1, c_int::MAX as u64) as c_int;
+
+    set(opt::SOL_SOCKET, opt::SO_KEEPALIVE, 1)?;
+    set(IPPROTO_TCP, opt::TCP_KEEPIDLE, secs(keepalive.idle))?;
+    if let Some(interval) = keepalive.interval {
+        set(IPPROTO_TCP, opt::TCP_KEEPINTVL, secs(interval))?;
+    }
+    if let Some(retries) = keepalive.retries {
+        set(
+            IPPROTO_TCP,
+            opt::TCP_KEEPCNT,
+            retries.min(c_int::MAX as u32) as c_int,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn set_keepalive(_stream: &TcpStream, _keepalive: Keepalive) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "TCP keepalive tuning is not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_drain_and_configure() {
+        let options = ListenerOptions {
+            backlog: 16,
+            nodelay: true,
+            reuse_port: cfg!(target_os = "linux"),
+            accept_batch: 3,
+            keepalive: Some(Keepalive {
+                idle: Duration::from_secs(30),
+                interval: Some(Duration::from_secs(5)),
+                retries: Some(3),
+            }),
+            ..ListenerOptions::default()
+        };
+        let listener = options.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut clients = Vec::new();
+        for _ in 0..4 {
+            clients.push(TcpStream::connect(addr).await.unwrap());
+        }
+        let first = listener.accept().await.unwrap();
+        let mut batch = vec![first];
+        // Give the kernel a moment to finish the remaining handshakes
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drain_ready(&listener, options.accept_batch, &mut batch);
+        assert_eq!(batch.len(), 3);
+
+        let (stream, _) = &batch[0];
+        options.configure_stream(stream);
+        assert!(stream.nodelay().unwrap());
+    }
+}
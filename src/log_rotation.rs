@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: MIT
+
+//! Size- and age-based rotation for `--log-dir` log files
+//!
+//! [`RotatingLogFile`] is the writer handed to `env_logger`. When the active
+//! file passes `--log-rotate-size` bytes or `--log-rotate-age`, it is renamed
+//! to `<name>.1` (older files shift to `.2`, `.3`, ...), anything beyond
+//! `--log-rotate-keep` is deleted, and a fresh file is opened under the
+//! original name. With `--log-rotate-gzip` rotated files are compressed to
+//! `<name>.1.gz` using the built-in gzip encoder.
+//!
+//! Every step is a rename within the log directory, so readers never see a
+//! half-written file: the compressed copy is written to a temporary name and
+//! renamed into place before the plain file is removed.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Rotated files kept when `--log-rotate-keep` is not given
+pub const DEFAULT_ROTATE_KEEP: usize = 5;
+
+/// When to rotate and what to keep
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Rotate once the active file reaches this many bytes
+    pub max_size: Option<u64>,
+    /// Rotate once the active file has been open this long
+    pub max_age: Option<Duration>,
+    /// Number of rotated files to keep
+    pub keep: usize,
+    /// Compress rotated files with gzip
+    pub gzip: bool,
+}
+
+impl RotationPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_size.is_some() || self.max_age.is_some()
+    }
+}
+
+/// Parse a rotation age such as "12h", "7d", "30m" or "3600" (seconds)
+pub fn parse_rotate_age(value: &str) -> Option<Duration> {
+    let value = value.trim().to_ascii_lowercase();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value.as_str(), "s"),
+    };
+    let multiplier = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    let secs = number.parse::<u64>().ok()?.checked_mul(multiplier)?;
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Log file writer that rotates itself according to a [`RotationPolicy`]
+#[derive(Debug)]
+pub struct RotatingLogFile {
+    path: PathBuf,
+    policy: RotationPolicy,
+    file: File,
+    written: u64,
+    opened_at: Instant,
+}
+
+impl RotatingLogFile {
+    /// Open (or append to) `path`
+    pub fn open(path: impl Into<PathBuf>, policy: RotationPolicy) -> io::Result<Self> {
+        let path = path.into();
+        let file = open_append(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            policy,
+            file,
+            written,
+            opened_at: Instant::now(),
+        })
+    }
+
+    fn needs_rotation(&self) -> bool {
+        if self.written == 0 {
+            return false;
+        }
+        self.policy
+            .max_size
+            .is_some_and(|max_size| self.written >= max_size)
+            || self
+                .policy
+                .max_age
+                .is_some_and(|max_age| self.opened_at.elapsed() >= max_age)
+    }
+
+    /// Path of the `index`-th rotated file (1 = most recent)
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let suffix = if self.policy.gzip { ".gz" } else { "" };
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{index}{suffix}"));
+        PathBuf::from(name)
+    }
+
+    /// Move the active file aside and start a new one
+    pub fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.policy.keep == 0 {
+            // Nothing to keep: start over in place
+            self.file = File::create(&self.path)?;
+            self.written = 0;
+            self.opened_at = Instant::now();
+            return Ok(());
+        }
+
+        remove_if_exists(&self.rotated_path(self.policy.keep))?;
+        for index in (1..self.policy.keep).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+
+        let newest = self.rotated_path(1);
+        if self.policy.gzip {
+            let mut plain = self.path.as_os_str().to_os_string();
+            plain.push(".1");
+            let plain = PathBuf::from(plain);
+            fs::rename(&self.path, &plain)?;
+            self.file = open_append(&self.path)?;
+            compress_into(&plain, &newest)?;
+        } else {
+            fs::rename(&self.path, &newest)?;
+            self.file = open_append(&self.path)?;
+        }
+        self.written = 0;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingLogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.needs_rotation() {
+            // A failed rotation must not lose log lines; keep appending instead
+            if let Err(e) = self.rotate() {
+                eprintln!("Log rotation of {} failed: {e}", self.path.display());
+                self.opened_at = Instant::now();
+            }
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// gzip `source` into `target` via a temporary file, then delete `source`
+fn compress_into(source: &Path, target: &Path) -> io::Result<()> {
+    let data = fs::read(source)?;
+    let mut temp = target.as_os_str().to_os_string();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    fs::write(&temp, crate::compression::gzip(&data))?;
+    fs::rename(&temp, target)?;
+    fs::remove_file(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn policy(max_size: u64, keep: usize, gzip: bool) -> RotationPolicy {
+        RotationPolicy {
+            max_size: Some(max_size),
+            max_age: None,
+            keep,
+            gzip,
+        }
+    }
+
+    #[test]
+    fn test_parse_rotate_age() {
+        assert_eq!(parse_rotate_age("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_rotate_age("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_rotate_age("12h"), Some(Duration::from_secs(43200)));
+        assert_eq!(parse_rotate_age("7d"), Some(Duration::from_secs(604800)));
+        assert_eq!(parse_rotate_age("0h"), None);
+        assert_eq!(parse_rotate_age("1w"), None);
+        assert_eq!(parse_rotate_age("h"), None);
+    }
+
+    #[test]
+    fn test_size_rotation_keeps_newest_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("irondrop.log");
+        let mut log = RotatingLogFile::open(&path, policy(10, 2, false)).unwrap();
+
+        for line in ["first line\n", "second line\n", "third line\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("irondrop.log.1")).unwrap(),
+            "third line\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("irondrop.log.2")).unwrap(),
+            "second line\n"
+        );
+        assert!(!dir.path().join("irondrop.log.3").exists());
+    }
+
+    #[test]
+    fn test_gzip_rotation_writes_compressed_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("irondrop.log");
+        let mut log = RotatingLogFile::open(&path, policy(4, 3, true)).unwrap();
+
+        log.write_all(b"rotated away\n").unwrap();
+        log.write_all(b"current\n").unwrap();
+
+        let compressed = fs::read(dir.path().join("irondrop.log.1.gz")).unwrap();
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+        assert!(!dir.path().join("irondrop.log.1").exists());
+        assert!(!dir.path().join("irondrop.log.1.gz.tmp").exists());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "current\n");
+    }
+}
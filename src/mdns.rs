@@ -0,0 +1,587 @@
+// SPDX-License-Identifier: MIT
+
+//! mDNS / DNS-SD advertisement for LAN discovery (`--mdns`)
+//!
+//! A small responder that makes the server show up as
+//! "IronDrop on <hostname>" under `_http._tcp` (or `_https._tcp` with TLS) in
+//! Bonjour/Avahi browsers, following RFC 6762 and RFC 6763:
+//!
+//! - on start the records are announced twice, one second apart
+//! - queries for the service type, the instance, the host name or
+//!   `_services._dns-sd._udp.local` are answered with the full record set
+//! - [`MdnsAdvertiser::goodbye`] re-sends the records with TTL 0 on shutdown
+//!
+//! The socket is bound with `SO_REUSEADDR`/`SO_REUSEPORT` where available, so
+//! it can share port 5353 with a system responder such as Avahi.
+
+use log::{debug, info, warn};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+
+const CLASS_IN: u16 = 1;
+/// Set on records this host owns exclusively (RFC 6762 §10.2)
+const CACHE_FLUSH: u16 = 0x8000;
+
+/// TTL for host-bound records (SRV, A)
+const HOST_TTL: u32 = 120;
+/// TTL for the remaining records (PTR, TXT)
+const OTHER_TTL: u32 = 4500;
+
+const SERVICES_META: &str = "_services._dns-sd._udp.local";
+
+/// What to advertise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInfo {
+    /// Instance label, e.g. "IronDrop on nas"
+    pub instance: String,
+    /// Service type, `_http._tcp.local` or `_https._tcp.local`
+    pub service_type: String,
+    /// Host name, e.g. "nas.local"
+    pub host: String,
+    pub address: Ipv4Addr,
+    pub port: u16,
+    /// Path announced in the TXT record, "/" unless a base path is set
+    pub path: String,
+}
+
+impl ServiceInfo {
+    pub fn new(hostname: &str, address: Ipv4Addr, port: u16, https: bool, path: &str) -> Self {
+        let label: String = hostname
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let label = if label.trim_matches('-').is_empty() {
+            "irondrop".to_string()
+        } else {
+            label
+        };
+        Self {
+            instance: format!("IronDrop on {label}"),
+            service_type: if https {
+                "_https._tcp.local"
+            } else {
+                "_http._tcp.local"
+            }
+            .to_string(),
+            host: format!("{label}.local"),
+            address,
+            port,
+            path: if path.is_empty() {
+                "/".to_string()
+            } else {
+                path.to_string()
+            },
+        }
+    }
+
+    fn instance_name(&self) -> String {
+        format!("{}.{}", self.instance, self.service_type)
+    }
+
+    /// Whether a question for `name`/`qtype` is about this service
+    fn answers(&self, name: &str, qtype: u16) -> bool {
+        let wants = |t: u16| qtype == t || qtype == TYPE_ANY;
+        (name.eq_ignore_ascii_case(&self.service_type) && wants(TYPE_PTR))
+            || (name.eq_ignore_ascii_case(SERVICES_META) && wants(TYPE_PTR))
+            || (name.eq_ignore_ascii_case(&self.instance_name())
+                && (wants(TYPE_SRV) || wants(TYPE_TXT)))
+            || (name.eq_ignore_ascii_case(&self.host) && wants(TYPE_A))
+    }
+
+    /// Response packet carrying every record; `goodbye` sends them with TTL 0
+    pub fn response(&self, id: u16, goodbye: bool) -> Vec<u8> {
+        let ttl = |ttl: u32| if goodbye { 0 } else { ttl };
+        let instance = self.instance_name();
+
+        let mut packet = Vec::with_capacity(256);
+        packet.extend_from_slice(&id.to_be_bytes());
+        packet.extend_from_slice(&0x8400u16.to_be_bytes()); // response, authoritative
+        packet.extend_from_slice(&0u16.to_be_bytes()); // questions
+        packet.extend_from_slice(&5u16.to_be_bytes()); // answers
+        packet.extend_from_slice(&0u32.to_be_bytes()); // authority + additional
+
+        let mut rdata = Vec::new();
+        encode_name(&mut rdata, &self.service_type);
+        write_record(
+            &mut packet,
+            SERVICES_META,
+            TYPE_PTR,
+            CLASS_IN,
+            ttl(OTHER_TTL),
+            &rdata,
+        );
+
+        rdata.clear();
+        encode_name(&mut rdata, &instance);
+        write_record(
+            &mut packet,
+            &self.service_type,
+            TYPE_PTR,
+            CLASS_IN,
+            ttl(OTHER_TTL),
+            &rdata,
+        );
+
+        rdata.clear();
+        rdata.extend_from_slice(&[0, 0, 0, 0]); // priority, weight
+        rdata.extend_from_slice(&self.port.to_be_bytes());
+        encode_name(&mut rdata, &self.host);
+        let unique = CLASS_IN | CACHE_FLUSH;
+        write_record(
+            &mut packet,
+            &instance,
+            TYPE_SRV,
+            unique,
+            ttl(HOST_TTL),
+            &rdata,
+        );
+
+        rdata.clear();
+        let entry = format!("path={}", self.path);
+        rdata.push(entry.len().min(255) as u8);
+        rdata.extend_from_slice(&entry.as_bytes()[..entry.len().min(255)]);
+        write_record(
+            &mut packet,
+            &instance,
+            TYPE_TXT,
+            unique,
+            ttl(OTHER_TTL),
+            &rdata,
+        );
+
+        write_record(
+            &mut packet,
+            &self.host,
+            TYPE_A,
+            unique,
+            ttl(HOST_TTL),
+            &self.address.octets(),
+        );
+        packet
+    }
+}
+
+fn encode_name(out: &mut Vec<u8>, name: &str) {
+    // The instance label may contain dots only in its service suffix
+    for label in split_name(name) {
+        let bytes = &label.as_bytes()[..label.len().min(63)];
+        out.push(bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+    out.push(0);
+}
+
+/// Split a name into labels, keeping "IronDrop on host" as one label
+fn split_name(name: &str) -> Vec<&str> {
+    match name.find("._") {
+        Some(index) if !name[..index].starts_with('_') => {
+            let mut labels = vec![&name[..index]];
+            labels.extend(name[index + 1..].split('.'));
+            labels
+        }
+        _ => name.split('.').collect(),
+    }
+}
+
+fn write_record(out: &mut Vec<u8>, name: &str, rtype: u16, class: u16, ttl: u32, rdata: &[u8]) {
+    encode_name(out, name);
+    out.extend_from_slice(&rtype.to_be_bytes());
+    out.extend_from_slice(&class.to_be_bytes());
+    out.extend_from_slice(&ttl.to_be_bytes());
+    out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    out.extend_from_slice(rdata);
+}
+
+/// Read a possibly compressed name starting at `pos`; returns the name and
+/// the offset just past it
+fn read_name(packet: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    for _ in 0..64 {
+        let len = *packet.get(pos)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(pos + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            let pointer = ((len & 0x3F) << 8) | *packet.get(pos + 1)? as usize;
+            end.get_or_insert(pos + 2);
+            pos = pointer;
+            continue;
+        }
+        let label = packet.get(pos + 1..pos + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        pos += 1 + len;
+    }
+    None
+}
+
+/// Questions in a query packet as (name, type); empty for responses
+pub fn parse_questions(packet: &[u8]) -> Vec<(String, u16)> {
+    if packet.len() < 12 || packet[2] & 0x80 != 0 {
+        return Vec::new();
+    }
+    let count = u16::from_be_bytes([packet[4], packet[5]]);
+    let mut questions = Vec::new();
+    let mut pos = 12;
+    for _ in 0..count {
+        let Some((name, next)) = read_name(packet, pos) else {
+            break;
+        };
+        let Some(qtype) = packet.get(next..next + 2) else {
+            break;
+        };
+        questions.push((name, u16::from_be_bytes([qtype[0], qtype[1]])));
+        pos = next + 4;
+    }
+    questions
+}
+
+/// Running advertisement; the responder thread stops when this is dropped
+pub struct MdnsAdvertiser {
+    socket: Arc<UdpSocket>,
+    service: ServiceInfo,
+    running: Arc<AtomicBool>,
+}
+
+impl MdnsAdvertiser {
+    /// Join the mDNS group and start announcing/answering in the background
+    pub fn start(service: ServiceInfo) -> io::Result<Self> {
+        let socket = bind_shared(MDNS_PORT)?;
+        socket.join_multicast_v4(&MDNS_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+        socket.set_multicast_ttl_v4(255)?;
+        socket.set_multicast_loop_v4(true)?;
+        socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+        let socket = Arc::new(socket);
+        let running = Arc::new(AtomicBool::new(true));
+
+        info!(
+            "📡 Advertising '{}' as {} on {}:{} via mDNS",
+            service.instance, service.service_type, service.address, service.port
+        );
+
+        let responder = Arc::clone(&socket);
+        let info = service.clone();
+        let still_running = Arc::clone(&running);
+        std::thread::Builder::new()
+            .name("irondrop-mdns".to_string())
+            .spawn(move || run_responder(&responder, &info, &still_running))?;
+
+        Ok(Self {
+            socket,
+            service,
+            running,
+        })
+    }
+
+    /// Tell browsers the service is going away
+    pub fn goodbye(&self) {
+        self.running.store(false, Ordering::Release);
+        let packet = self.service.response(0, true);
+        let _ = self
+            .socket
+            .send_to(&packet, SocketAddrV4::new(MDNS_GROUP, MDNS_PORT));
+    }
+}
+
+impl Drop for MdnsAdvertiser {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+    }
+}
+
+fn run_responder(socket: &UdpSocket, service: &ServiceInfo, running: &AtomicBool) {
+    let group = SocketAddr::V4(SocketAddrV4::new(MDNS_GROUP, MDNS_PORT));
+    let announcement = service.response(0, false);
+    for attempt in 0..2 {
+        if attempt > 0 {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+        if let Err(e) = socket.send_to(&announcement, group) {
+            warn!("mDNS announcement failed: {e}");
+        }
+    }
+
+    let mut buf = [0u8; 1500];
+    while running.load(Ordering::Acquire) {
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(e) => {
+                warn!("mDNS responder stopped: {e}");
+                return;
+            }
+        };
+        let packet = &buf[..len];
+        if !parse_questions(packet)
+            .iter()
+            .any(|(name, qtype)| service.answers(name, *qtype))
+        {
+            continue;
+        }
+        debug!("mDNS query from {from}");
+        // Legacy unicast queries (RFC 6762 §6.7) get a direct reply with their ID
+        let result = if from.port() == MDNS_PORT {
+            socket.send_to(&announcement, group)
+        } else {
+            let id = u16::from_be_bytes([packet[0], packet[1]]);
+            socket.send_to(&service.response(id, false), from)
+        };
+        if let Err(e) = result {
+            debug!("mDNS reply failed: {e}");
+        }
+    }
+}
+
+/// IPv4 address other LAN hosts can reach us on, chosen by the routing table
+pub fn lan_address() -> Option<Ipv4Addr> {
+    // Connecting a UDP socket sends nothing; it only selects a source address
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((MDNS_GROUP, MDNS_PORT)).ok()?;
+    match socket.local_addr().ok()? {
+        SocketAddr::V4(addr) if !addr.ip().is_unspecified() => Some(*addr.ip()),
+        _ => None,
+    }
+}
+
+/// This machine's host name, or "irondrop" if it cannot be read
+pub fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        unsafe extern "C" {
+            fn gethostname(name: *mut std::ffi::c_char, len: usize) -> i32;
+        }
+        let mut buf = [0u8; 256];
+        // SAFETY: the buffer is valid for `len` bytes and gethostname NUL-terminates within it
+        let rc = unsafe { gethostname(buf.as_mut_ptr().cast(), buf.len() - 1) };
+        if rc == 0 {
+            let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+            if end > 0 {
+                return String::from_utf8_lossy(&buf[..end]).into_owned();
+            }
+        }
+    }
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "irondrop".to_string())
+}
+
+/// Bind UDP `port` on all interfaces, sharing it with other mDNS responders
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn bind_shared(port: u16) -> io::Result<UdpSocket> {
+    use std::os::fd::FromRawFd;
+
+    #[cfg(target_os = "linux")]
+    mod sys {
+        pub const SOCK_DGRAM: i32 = 2 | 0o2000000; // SOCK_CLOEXEC
+        pub const SOL_SOCKET: i32 = 1;
+        pub const SO_REUSEADDR: i32 = 2;
+        pub const SO_REUSEPORT: i32 = 15;
+
+        #[repr(C)]
+        pub struct SockAddrIn {
+            pub family: u16,
+            pub port: u16,
+            pub addr: u32,
+            pub zero: [u8; 8],
+        }
+
+        pub fn any_addr(port: u16) -> SockAddrIn {
+            SockAddrIn {
+                family: 2,
+                port: port.to_be(),
+                addr: 0,
+                zero: [0; 8],
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    mod sys {
+        pub const SOCK_DGRAM: i32 = 2;
+        pub const SOL_SOCKET: i32 = 0xffff;
+        pub const SO_REUSEADDR: i32 = 0x4;
+        pub const SO_REUSEPORT: i32 = 0x200;
+
+        #[repr(C)]
+        pub struct SockAddrIn {
+            pub len: u8,
+            pub family: u8,
+            pub port: u16,
+            pub addr: u32,
+            pub zero: [u8; 8],
+        }
+
+        pub fn any_addr(port: u16) -> SockAddrIn {
+            SockAddrIn {
+                len: std::mem::size_of::<SockAddrIn>() as u8,
+                family: 2,
+                port: port.to_be(),
+                addr: 0,
+                zero: [0; 8],
+            }
+        }
+    }
+
+    unsafe extern "C" {
+        fn socket(domain: i32, kind: i32, protocol: i32) -> i32;
+        fn setsockopt(fd: i32, level: i32, name: i32, value: *const i32, len: u32) -> i32;
+        fn bind(fd: i32, addr: *const sys::SockAddrIn, len: u32) -> i32;
+    }
+
+    const AF_INET: i32 = 2;
+
+    // SAFETY: plain socket syscalls on a descriptor we own; on success it is
+    // handed to UdpSocket, which closes it on drop (and on every error path)
+    unsafe {
+        let fd = socket(AF_INET, sys::SOCK_DGRAM, 0);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = UdpSocket::from_raw_fd(fd);
+        let one: i32 = 1;
+        let len = std::mem::size_of::<i32>() as u32;
+        for option in [sys::SO_REUSEADDR, sys::SO_REUSEPORT] {
+            if setsockopt(fd, sys::SOL_SOCKET, option, &one, len) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        let addr = sys::any_addr(port);
+        let addr_len = std::mem::size_of::<sys::SockAddrIn>() as u32;
+        if bind(fd, &addr, addr_len) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(socket)
+    }
+}
+
+/// Bind UDP `port` on all interfaces
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn bind_shared(port: u16) -> io::Result<UdpSocket> {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> ServiceInfo {
+        ServiceInfo::new(
+            "My_NAS.lan",
+            Ipv4Addr::new(192, 168, 1, 20),
+            8080,
+            false,
+            "",
+        )
+    }
+
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut packet = vec![0x12, 0x34, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+        encode_name(&mut packet, name);
+        packet.extend_from_slice(&qtype.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn test_service_names() {
+        let service = service();
+        assert_eq!(service.instance, "IronDrop on my-nas");
+        assert_eq!(service.host, "my-nas.local");
+        assert_eq!(service.service_type, "_http._tcp.local");
+        assert_eq!(service.path, "/");
+        assert_eq!(
+            split_name(&service.instance_name()),
+            vec!["IronDrop on my-nas", "_http", "_tcp", "local"]
+        );
+    }
+
+    #[test]
+    fn test_queries_are_matched() {
+        let service = service();
+        let answered = |name: &str, qtype: u16| {
+            parse_questions(&query(name, qtype))
+                .iter()
+                .any(|(name, qtype)| service.answers(name, *qtype))
+        };
+        assert!(answered("_http._tcp.local", TYPE_PTR));
+        assert!(answered("_HTTP._tcp.local", TYPE_ANY));
+        assert!(answered("_services._dns-sd._udp.local", TYPE_PTR));
+        assert!(answered("IronDrop on my-nas._http._tcp.local", TYPE_SRV));
+        assert!(answered("my-nas.local", TYPE_A));
+        assert!(!answered("_ipp._tcp.local", TYPE_PTR));
+        assert!(!answered("my-nas.local", TYPE_PTR));
+    }
+
+    #[test]
+    fn test_response_records() {
+        let packet = service().response(7, false);
+        assert_eq!(&packet[..2], &7u16.to_be_bytes());
+        assert_eq!(u16::from_be_bytes([packet[6], packet[7]]), 5);
+        // Responses are never treated as queries
+        assert!(parse_questions(&packet).is_empty());
+
+        // First answer: the DNS-SD meta PTR pointing at our service type
+        let (name, next) = read_name(&packet, 12).unwrap();
+        assert_eq!(name, SERVICES_META);
+        assert_eq!(
+            u16::from_be_bytes([packet[next], packet[next + 1]]),
+            TYPE_PTR
+        );
+        let (target, _) = read_name(&packet, next + 10).unwrap();
+        assert_eq!(target, "_http._tcp.local");
+
+        // The A record carries the address and goodbyes zero every TTL
+        assert!(packet.ends_with(&[192, 168, 1, 20]));
+        let goodbye = service().response(0, true);
+        let ttl_offset = goodbye.len() - 4 - 2 - 4;
+        assert_eq!(&goodbye[ttl_offset..ttl_offset + 4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_compressed_names() {
+        // "local" at offset 12, then "a" + pointer back to it
+        let mut packet = vec![0u8; 12];
+        encode_name(&mut packet, "local");
+        let start = packet.len();
+        packet.extend_from_slice(&[1, b'a', 0xC0, 12]);
+        assert_eq!(
+            read_name(&packet, start),
+            Some(("a.local".to_string(), start + 4))
+        );
+        // Pointer loops are rejected
+        let looped = [0u8; 12]
+            .iter()
+            .copied()
+            .chain([0xC0, 12])
+            .collect::<Vec<u8>>();
+        assert_eq!(read_name(&looped, 12), None);
+    }
+}
@@ -2,15 +2,43 @@
 
 //! Middleware system for request preprocessing (e.g. authentication).
 //!
-//! Provides a Basic Auth middleware that validates the `Authorization` header
-//! when username & password are configured. If credentials are not configured
-//! the middleware is a no-op.
+//! Provides an auth middleware that validates the `Authorization` header
+//! when username & password and/or API tokens are configured. Tokens are
+//! accepted as `Authorization: Bearer <token>` or `X-API-Key: <token>`.
+//! If no credentials are configured the middleware is a no-op.
+//!
+//! The Basic Auth password can be given as an Argon2 or bcrypt hash
+//! ([`crate::password`]). Browsers resend the same header on every request,
+//! so a header that verified is remembered for a few minutes instead of
+//! being hashed again. With a [`RateLimiter`] attached, an IP that fails to
+//! log in `--auth-max-failures` times is answered with `429` until its
+//! lockout ends.
+//!
+//! [`PathAuthRule`]s (`[auth.paths]`) put their own Basic Auth realm on a
+//! directory prefix. Requests that touch a protected path, through the URL,
+//! a `path`/`to` parameter or a WebDAV `Destination`, need that directory's
+//! credentials (or the global ones); verified credentials are remembered per
+//! connection.
+//!
+//! [`CorsPolicy`] adds CORS headers to the JSON APIs (`/_irondrop/...` and
+//! `/_api/...`) and answers their preflight `OPTIONS` requests before
+//! authentication runs, since browsers send preflights without credentials.
+//!
+//! [`SecurityHeaders`] adds `Content-Security-Policy`, `X-Content-Type-Options`,
+//! `X-Frame-Options`, `Referrer-Policy` and, with TLS, `Strict-Transport-Security`
+//! to every response.
 
+use crate::audit::{self, AuditEvent, AuditRecord};
 use crate::error::AppError;
-use crate::http::Request;
+use crate::http::{Request, Response, ResponseBody};
+use crate::password::PasswordHash;
+use crate::server::RateLimiter;
+use crate::session::SessionSigner;
+use crate::users::{Role, UserAccount};
 use base64::Engine;
-use log::{trace, warn};
-use std::sync::{Mutex, OnceLock};
+use log::{debug, info, trace, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
 /// Middleware trait – middlewares can inspect a request before it reaches a handler.
@@ -19,11 +47,267 @@ pub trait Middleware: Send + Sync + 'static {
     fn handle(&self, request: &Request) -> Result<(), AppError>;
 }
 
-/// Basic authentication middleware.
+/// Lets a middleware be shared with handlers that need it, such as the login form
+impl<M: Middleware> Middleware for Arc<M> {
+    fn handle(&self, request: &Request) -> Result<(), AppError> {
+        self.as_ref().handle(request)
+    }
+}
+
+/// A configured API token and the label used to identify it in logs.
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    pub label: String,
+    token: Vec<u8>,
+}
+
+impl ApiToken {
+    pub fn new(label: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            token: token.into().into_bytes(),
+        }
+    }
+
+    /// Parse `label:token` entries (as configured via `[auth] tokens`).
+    /// Entries without a label are named `token-<n>`.
+    pub fn parse_list(entries: &[String]) -> Vec<Self> {
+        entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                let entry = entry.trim();
+                let (label, token) = match entry.split_once(':') {
+                    Some((label, token)) => (label.trim().to_string(), token.trim()),
+                    None => (format!("token-{}", i + 1), entry),
+                };
+                (!token.is_empty()).then(|| Self::new(label, token))
+            })
+            .collect()
+    }
+}
+
+/// Credentials guarding one directory prefix.
+#[derive(Debug, Clone)]
+pub struct PathAuthRule {
+    /// Normalized prefix such as `/private/reports`
+    pub prefix: String,
+    /// Realm sent in the challenge for this prefix
+    pub realm: String,
+    /// `prefix` as compared by [`Self::covers`]
+    key: String,
+    expected: Vec<Vec<u8>>,
+}
+
+impl PathAuthRule {
+    /// Parse `/prefix=user:password` entries (as configured via `[auth.paths]`).
+    /// Entries for the same prefix are merged, so a directory can have several users.
+    /// Rules come back longest prefix first; malformed entries are skipped with a warning.
+    pub fn parse_list(entries: &[String]) -> Vec<Self> {
+        let mut rules: Vec<Self> = Vec::new();
+        for entry in entries {
+            let parsed = entry.split_once('=').and_then(|(prefix, credentials)| {
+                let (user, pass) = credentials.trim().split_once(':')?;
+                let prefix = normalize_auth_path(prefix.trim());
+                (!user.is_empty() && prefix != "/").then_some((prefix, user, pass))
+            });
+            let Some((prefix, user, pass)) = parsed else {
+                warn!(
+                    "Ignoring path auth entry for '{}': expected /prefix=user:password",
+                    entry.split('=').next().unwrap_or_default()
+                );
+                continue;
+            };
+            let expected = basic_authorization(user, pass);
+            let key = auth_path_key(&prefix);
+            match rules.iter_mut().find(|rule| rule.key == key) {
+                Some(rule) => rule.expected.push(expected),
+                None => rules.push(Self {
+                    realm: format!("IronDrop {prefix}"),
+                    key,
+                    prefix,
+                    expected: vec![expected],
+                }),
+            }
+        }
+        rules.sort_by(|a, b| b.prefix.len().cmp(&a.prefix.len()));
+        rules
+    }
+
+    /// Whether `path` (already normalized) lies at or below this prefix.
+    /// Compared case-insensitively and across Unicode normalization forms,
+    /// the way case-insensitive filesystems and the server's lookup of
+    /// differently normalized names resolve paths.
+    pub fn covers(&self, path: &str) -> bool {
+        let path = auth_path_key(path);
+        path.strip_prefix(&self.key)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+/// Decomposed, lowercase form of `path` for matching rules
+fn auth_path_key(path: &str) -> String {
+    crate::utils::unicode_path_key(path).to_lowercase()
+}
+
+/// Protected directories as seen by one request, see
+/// [`AuthMiddleware::locked_paths`]
+#[derive(Debug, Clone, Default)]
+pub struct LockedPaths {
+    rules: Vec<(PathAuthRule, bool)>,
+}
+
+impl LockedPaths {
+    /// Whether `path` lies in a protected directory the request cannot open;
+    /// the most specific rule decides.
+    pub fn hides(&self, path: &str) -> bool {
+        let path = normalize_auth_path(path);
+        self.rules
+            .iter()
+            .find(|(rule, _)| rule.covers(&path))
+            .is_some_and(|(_, opened)| !opened)
+    }
+}
+
+fn basic_authorization(user: &str, pass: &str) -> Vec<u8> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(format!("{user}:{pass}"));
+    format!("Basic {encoded}").into_bytes()
+}
+
+/// Collapse `.`/`..` segments and repeated slashes so `/a/../private` is
+/// matched as `/private`.
+fn normalize_auth_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// Routes that act on the file or folder named by their `path` query parameter
+const PATH_PARAM_ROUTES: [&str; 6] = [
+    crate::checksum::CHECKSUM_ROUTE,
+    crate::checksum::CHECKSUM_SHORT_ROUTE,
+    crate::share::SHARE_ROUTE,
+    crate::share::SHARE_SHORT_ROUTE,
+    crate::file_ops::FILES_ROUTE,
+    "/_irondrop/search",
+];
+
+/// Every filesystem path a request can read or change, besides its URL:
+/// preview and edit routes, the `path` query parameter of the routes that
+/// read it, upload targets, the WebDAV `Destination` header and the file
+/// management JSON body. Upload session chunks go where the session was
+/// opened, which was checked when it was created.
+fn request_target_paths(request: &Request) -> Vec<String> {
+    let url_path = request.path.as_str();
+    let mut paths = vec![url_path.to_string()];
+    for prefix in [
+        crate::preview::PREVIEW_ROUTE,
+        crate::preview::PREVIEW_SHORT_ROUTE,
+        crate::edit::EDIT_ROUTE,
+    ] {
+        if let Some(rest) = url_path.strip_prefix(prefix) {
+            paths.push(format!("/{rest}"));
+        }
+    }
+    let params = request.query_params();
+    if PATH_PARAM_ROUTES.contains(&url_path)
+        && let Some(path) = params.get("path")
+    {
+        paths.push(path.clone());
+    }
+    if url_path == "/_irondrop/upload" || url_path.starts_with(crate::upload_session::SESSION_ROUTE)
+    {
+        // Uploads without a target go to the root, which the URL already covers
+        paths.extend(params.get("upload_to").cloned());
+        paths.extend(request.headers.get("x-upload-to").cloned());
+    }
+    if let Some(destination) = request.headers.get("destination") {
+        // Absolute URI or path; only the path part matters
+        let path = destination
+            .split_once("://")
+            .map_or(destination.as_str(), |(_, rest)| {
+                rest.find('/').map_or("/", |idx| &rest[idx..])
+            });
+        paths.push(path.to_string());
+    }
+    if [crate::file_ops::FILES_ROUTE, crate::file_ops::MKDIR_ROUTE].contains(&url_path)
+        && let Some(crate::http::RequestBody::Memory(body)) = &request.body
+        && let Some(fields) = std::str::from_utf8(body)
+            .ok()
+            .and_then(crate::utils::parse_json_string_map)
+    {
+        paths.extend(
+            ["path", "to"]
+                .iter()
+                .filter_map(|k| fields.get(*k).cloned()),
+        );
+    }
+    paths
+}
+
+/// Connections remembered as authenticated before the cache is reset
+const MAX_VERIFIED_CONNECTIONS: usize = 1024;
+
+/// Failed logins from one IP before it is locked out, unless configured otherwise
+pub const DEFAULT_AUTH_MAX_FAILURES: u32 = 10;
+
+/// Seconds a lockout lasts, unless configured otherwise
+pub const DEFAULT_AUTH_LOCKOUT_SECS: u64 = 300;
+
+/// How long a header that matched the password hash is trusted without hashing again
+const HASH_VERIFIED_TTL: Duration = Duration::from_secs(300);
+
+/// Who sent a request, as verified by [`AuthMiddleware::identify`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// Account name, or the label of an API token
+    pub name: String,
+    pub role: Role,
+    pub via_token: bool,
+}
+
+impl Identity {
+    fn user(name: impl Into<String>, role: Role) -> Self {
+        Self {
+            name: name.into(),
+            role,
+            via_token: false,
+        }
+    }
+
+    /// Stable key for per-client accounting, such as upload quotas
+    pub fn key(&self) -> String {
+        if self.via_token {
+            format!("token:{}", self.name)
+        } else {
+            format!("user:{}", self.name)
+        }
+    }
+}
+
+/// Basic authentication and API token middleware.
 pub struct AuthMiddleware {
     pub username: Option<String>,
     pub password: Option<String>,
     expected_authorization: Option<Vec<u8>>,
+    password_hash: Option<PasswordHash>,
+    tokens: Vec<ApiToken>,
+    users: Vec<UserAccount>,
+    path_rules: Vec<PathAuthRule>,
+    lockout: Option<Arc<RateLimiter>>,
+    sessions: Option<SessionSigner>,
+    /// Authorization header last accepted per (connection, protected prefix)
+    verified: Mutex<HashMap<(std::net::SocketAddr, String), String>>,
+    /// SHA-256 of headers that matched `password_hash`, and when
+    hash_verified: Mutex<HashMap<[u8; 32], Instant>>,
 }
 
 impl AuthMiddleware {
@@ -40,41 +324,792 @@ impl AuthMiddleware {
             username,
             password,
             expected_authorization,
+            password_hash: None,
+            tokens: Vec::new(),
+            users: Vec::new(),
+            path_rules: Vec::new(),
+            lockout: None,
+            sessions: None,
+            verified: Mutex::new(HashMap::new()),
+            hash_verified: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check the Basic Auth password against `hash` instead of a plain password.
+    pub fn with_password_hash(mut self, hash: Option<PasswordHash>) -> Self {
+        if hash.is_some() {
+            self.expected_authorization = None;
         }
+        self.password_hash = hash;
+        self
+    }
+
+    /// Count failed logins per IP in `limiter` and refuse locked-out IPs.
+    pub fn with_lockout(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.lockout = Some(limiter);
+        self
+    }
+
+    /// Additionally accept the given API tokens.
+    pub fn with_tokens(mut self, tokens: Vec<ApiToken>) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
+    /// Additionally accept the given `[users]` accounts, limited to their roles.
+    pub fn with_users(mut self, users: Vec<UserAccount>) -> Self {
+        self.users = users;
+        self
+    }
+
+    /// Also accept session cookies signed by `signer`, and send browsers
+    /// without credentials to the login page.
+    pub fn with_sessions(mut self, signer: Option<SessionSigner>) -> Self {
+        self.sessions = signer;
+        self
+    }
+
+    /// Additionally protect directory prefixes with their own credentials.
+    pub fn with_path_rules(mut self, rules: Vec<PathAuthRule>) -> Self {
+        self.path_rules = rules;
+        self
     }
 
-    fn is_authenticated(&self, auth_header: Option<&String>) -> bool {
-        let Some(expected) = &self.expected_authorization else {
-            trace!("Authentication disabled - allowing request");
-            return true; // auth disabled
+    /// Whether any credentials are configured at all.
+    pub fn is_enabled(&self) -> bool {
+        self.basic_enabled() || !self.tokens.is_empty() || !self.path_rules.is_empty()
+    }
+
+    /// The most specific rule covering any path the request touches.
+    pub fn protecting_rule(&self, request: &Request) -> Option<&PathAuthRule> {
+        self.target_rules(request).into_iter().flatten().next()
+    }
+
+    /// For each path the request touches, the most specific rule covering
+    /// it, or `None` where no rule does.
+    fn target_rules(&self, request: &Request) -> Vec<Option<&PathAuthRule>> {
+        if self.path_rules.is_empty() {
+            return Vec::new();
+        }
+        request_target_paths(request)
+            .iter()
+            .map(|path| {
+                let path = normalize_auth_path(path);
+                self.path_rules.iter().find(|rule| rule.covers(&path))
+            })
+            .collect()
+    }
+
+    /// Whether the request only touches protected directories and carries
+    /// the credentials of each of them.
+    fn path_rules_grant(&self, request: &Request) -> bool {
+        let Some(header) = request.headers.get("authorization") else {
+            return false;
         };
+        let rules = self.target_rules(request);
+        !rules.is_empty()
+            && rules.iter().all(|rule| {
+                rule.is_some_and(|rule| {
+                    let mut matched = false;
+                    for expected in &rule.expected {
+                        matched |= constant_time_eq_bytes(header.as_bytes(), expected);
+                    }
+                    matched
+                })
+            })
+    }
 
-        let Some(header) = auth_header else {
-            auth_failure_rate_limited("missing authorization header");
+    /// Directory credentials, or the global ones, for a protected path.
+    fn check_path_rule(&self, rule: &PathAuthRule, request: &Request) -> Result<(), AppError> {
+        let header = request.headers.get("authorization");
+        let cache_key = request.peer_addr.map(|addr| (addr, rule.prefix.clone()));
+        if let (Some(key), Some(header)) = (&cache_key, header)
+            && let Ok(verified) = self.verified.lock()
+            && verified.get(key) == Some(header)
+        {
+            trace!(
+                "Reusing verified credentials for {} on {}",
+                rule.prefix, key.0
+            );
+            return Ok(());
+        }
+
+        if !self.opens(rule, request) {
+            if header.is_some() {
+                auth_failure_rate_limited("invalid credentials for protected path");
+                self.record_failure(request, "invalid credentials for protected path");
+            }
+            return Err(AppError::UnauthorizedRealm(rule.realm.clone()));
+        }
+        self.clear_failures(request);
+        if let (Some(key), Some(header)) = (cache_key, header)
+            && let Ok(mut verified) = self.verified.lock()
+        {
+            if verified.len() >= MAX_VERIFIED_CONNECTIONS {
+                verified.clear();
+            }
+            verified.insert(key, header.clone());
+        }
+        Ok(())
+    }
+
+    /// Whether the request carries credentials that open `rule`'s directory.
+    fn opens(&self, rule: &PathAuthRule, request: &Request) -> bool {
+        let header = request.headers.get("authorization");
+        header.is_some_and(|header| {
+            // Compare against every entry so timing does not reveal which one matched
+            let mut matched = false;
+            for expected in &rule.expected {
+                matched |= constant_time_eq_bytes(header.as_bytes(), expected);
+            }
+            // Among `[users]` accounts only admins are let into protected directories
+            matched | self.matches_global(header) | (self.match_user(header) == Some(Role::Admin))
+        }) || self.match_token(request).is_some()
+            || self.session_role(request) == Some(Role::Admin)
+    }
+
+    /// The protected directories, and which of them the request's
+    /// credentials open. For responses naming paths the middleware never
+    /// saw, such as search results; counts no failures.
+    pub fn locked_paths(&self, request: &Request) -> LockedPaths {
+        LockedPaths {
+            rules: self
+                .path_rules
+                .iter()
+                .map(|rule| (rule.clone(), self.opens(rule, request)))
+                .collect(),
+        }
+    }
+
+    /// Return the label of the token presented by the request, if any matches.
+    fn match_token(&self, request: &Request) -> Option<&str> {
+        let presented = request
+            .headers
+            .get("authorization")
+            .and_then(|h| {
+                h.strip_prefix("Bearer ")
+                    .or_else(|| h.strip_prefix("bearer "))
+            })
+            .or_else(|| request.headers.get("x-api-key").map(String::as_str))?
+            .trim();
+        // Compare against every token so timing does not reveal which one matched
+        let mut matched = None;
+        for api_token in &self.tokens {
+            if constant_time_eq_bytes(presented.as_bytes(), &api_token.token) && matched.is_none() {
+                matched = Some(api_token.label.as_str());
+            }
+        }
+        matched
+    }
+
+    /// Whether a global username and password (or password hash) is configured.
+    fn basic_enabled(&self) -> bool {
+        self.expected_authorization.is_some()
+            || (self.username.is_some() && self.password_hash.is_some())
+            || !self.users.is_empty()
+    }
+
+    /// Whether `header` carries the global Basic Auth credentials.
+    fn matches_global(&self, header: &str) -> bool {
+        if let Some(expected) = &self.expected_authorization {
+            return constant_time_eq_bytes(header.as_bytes(), expected);
+        }
+        let (Some(username), Some(hash)) = (&self.username, &self.password_hash) else {
             return false;
         };
+        let key = header_key(header);
+        if self.recently_verified(&key) {
+            return true;
+        }
+        let decoded = decode_basic(header);
+        let (user, pass) = split_credentials(&decoded);
+        // Hash even when the username is wrong, so timing does not reveal it
+        let user_matches = constant_time_eq_bytes(user, username.as_bytes());
+        let password_matches = hash.verify(pass);
+        if !(user_matches && password_matches) {
+            return false;
+        }
+        self.remember_verified(key);
+        true
+    }
 
-        if constant_time_eq_bytes(header.as_bytes(), expected) {
-            true
-        } else {
+    /// Role of the `[users]` account whose credentials `header` carries.
+    fn match_user(&self, header: &str) -> Option<Role> {
+        let first = self.users.first()?;
+        let decoded = decode_basic(header);
+        let (user, pass) = split_credentials(&decoded);
+        // Compare against every name so timing does not reveal which one exists
+        let mut found = None;
+        for account in &self.users {
+            if constant_time_eq_bytes(user, account.name.as_bytes()) && found.is_none() {
+                found = Some(account);
+            }
+        }
+        let key = header_key(header);
+        if let Some(account) = found
+            && account.is_hashed()
+            && self.recently_verified(&key)
+        {
+            return Some(account.role);
+        }
+        // Unknown names are checked against another account for the same reason
+        let verified = found.unwrap_or(first).verify(pass);
+        let account = found.filter(|_| verified)?;
+        if account.is_hashed() {
+            self.remember_verified(key);
+        }
+        Some(account.role)
+    }
+
+    fn recently_verified(&self, key: &[u8; 32]) -> bool {
+        self.hash_verified.lock().is_ok_and(|cache| {
+            cache
+                .get(key)
+                .is_some_and(|at| at.elapsed() < HASH_VERIFIED_TTL)
+        })
+    }
+
+    fn remember_verified(&self, key: [u8; 32]) {
+        if let Ok(mut cache) = self.hash_verified.lock() {
+            if cache.len() >= MAX_VERIFIED_CONNECTIONS {
+                cache.retain(|_, at| at.elapsed() < HASH_VERIFIED_TTL);
+                if cache.len() >= MAX_VERIFIED_CONNECTIONS {
+                    cache.clear();
+                }
+            }
+            cache.insert(key, Instant::now());
+        }
+    }
+
+    /// Role granted by a Basic `Authorization` header, `None` when it is not valid.
+    /// The global user is an admin.
+    fn authenticate_basic(&self, auth_header: Option<&String>) -> Option<Role> {
+        let Some(header) = auth_header else {
+            auth_failure_rate_limited("missing authorization header");
+            return None;
+        };
+        if self.matches_global(header) {
+            return Some(Role::Admin);
+        }
+        let role = self.match_user(header);
+        if role.is_none() {
             auth_failure_rate_limited("invalid credentials");
-            false
+        }
+        role
+    }
+
+    /// Role of the account named by a valid session cookie. Sessions of
+    /// accounts that no longer exist are not accepted.
+    fn session_role(&self, request: &Request) -> Option<Role> {
+        self.session_identity(request).map(|identity| identity.role)
+    }
+
+    fn session_identity(&self, request: &Request) -> Option<Identity> {
+        let signer = self.sessions.as_ref()?;
+        let cookie = crate::session::session_cookie(request)?;
+        let name = signer.verify(cookie, crate::session::unix_now())?;
+        let global = self.expected_authorization.is_some() || self.password_hash.is_some();
+        if global && self.username.as_deref() == Some(name.as_str()) {
+            return Some(Identity::user(name, Role::Admin));
+        }
+        let role = self
+            .users
+            .iter()
+            .find(|account| account.name == name)
+            .map(|account| account.role)?;
+        Some(Identity::user(name, role))
+    }
+
+    /// The account, token or session the request's credentials verify as,
+    /// `None` when there are none or they do not verify. Unlike
+    /// [`Middleware::handle`] this counts no failures.
+    pub fn identify(&self, request: &Request) -> Option<Identity> {
+        if let Some(identity) = self.session_identity(request) {
+            return Some(identity);
+        }
+        if let Some(label) = self.match_token(request) {
+            return Some(Identity {
+                name: label.to_string(),
+                role: Role::Admin,
+                via_token: true,
+            });
+        }
+        let header = request.headers.get("authorization")?;
+        let decoded = decode_basic(header);
+        let name = String::from_utf8_lossy(split_credentials(&decoded).0).into_owned();
+        if self.matches_global(header) {
+            return Some(Identity::user(name, Role::Admin));
+        }
+        if let Some(role) = self.match_user(header) {
+            return Some(Identity::user(name, role));
+        }
+        // `[auth.paths]` users have no role outside their directory, see
+        // [`Self::role_of`]
+        let mut matched = false;
+        for rule in &self.path_rules {
+            for expected in &rule.expected {
+                matched |= constant_time_eq_bytes(header.as_bytes(), expected);
+            }
+        }
+        matched.then(|| Identity::user(name, Role::ReadOnly))
+    }
+
+    /// Role a request that got past [`Middleware::handle`] acts with: that of
+    /// its verified account, or read-only without one. `[auth.paths]` users
+    /// have full access while the request stays inside their directories.
+    /// Without accounts to check roles against, every request has full access.
+    pub fn role_of(&self, request: &Request) -> Role {
+        if !self.basic_enabled() || self.path_rules_grant(request) {
+            return Role::Admin;
+        }
+        self.identify(request)
+            .map_or(Role::ReadOnly, |identity| identity.role)
+    }
+
+    /// Check credentials from the login form like a Basic Auth header,
+    /// counting failures towards the client's lockout.
+    pub fn check_login(
+        &self,
+        request: &Request,
+        username: &str,
+        password: &str,
+    ) -> Result<Role, AppError> {
+        if let Some(retry_after) = self.locked_out(request) {
+            return Err(AppError::TooManyRequests(retry_after));
+        }
+        let encoded =
+            base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+        match self.authenticate_basic(Some(&format!("Basic {encoded}"))) {
+            Some(role) => {
+                self.clear_failures(request);
+                audit::record(
+                    AuditEvent::Login,
+                    AuditRecord::for_request(request, "success")
+                        .with_user(Some(username.to_string()))
+                        .with("role", role.as_str()),
+                );
+                Ok(role)
+            }
+            None => {
+                self.record_failure_as(request, Some(username), "invalid credentials");
+                Err(AppError::Unauthorized)
+            }
+        }
+    }
+
+    /// Seconds left on the lockout of the request's client, if it has one.
+    fn locked_out(&self, request: &Request) -> Option<u64> {
+        let (limiter, peer) = (self.lockout.as_ref()?, request.peer_addr?);
+        limiter.auth_lockout(peer.ip())
+    }
+
+    /// Report a failed login and count it towards the client's lockout.
+    fn record_failure(&self, request: &Request, reason: &str) {
+        let user = crate::users::basic_username(request);
+        self.record_failure_as(request, user.as_deref(), reason);
+    }
+
+    /// [`Self::record_failure`] for a login attempt as `user`
+    fn record_failure_as(&self, request: &Request, user: Option<&str>, reason: &str) {
+        emit_auth_failure(request, reason);
+        audit::record(
+            AuditEvent::AuthFailure,
+            AuditRecord::for_request(request, "failure")
+                .with_user(user.map(str::to_string))
+                .with("reason", reason),
+        );
+        let (Some(limiter), Some(peer)) = (&self.lockout, request.peer_addr) else {
+            return;
+        };
+        if let Some(secs) = limiter.record_auth_failure(peer.ip()) {
+            warn!(
+                "Locked out {} for {secs}s after too many failed logins",
+                peer.ip()
+            );
+            audit::record(
+                AuditEvent::RateLimitBan,
+                AuditRecord::for_request(request, "denied")
+                    .with_user(user.map(str::to_string))
+                    .with("route", "auth")
+                    .with("seconds", secs),
+            );
+            crate::hooks::emit(
+                crate::hooks::HookEvent::RateLimited,
+                &[
+                    ("client", peer.ip().to_string().into()),
+                    ("route", "auth".into()),
+                ],
+            );
+        }
+    }
+
+    /// Forget the failures of a client that logged in.
+    fn clear_failures(&self, request: &Request) {
+        if let (Some(limiter), Some(peer)) = (&self.lockout, request.peer_addr) {
+            limiter.clear_auth_failures(peer.ip());
         }
     }
 }
 
 impl Middleware for AuthMiddleware {
     fn handle(&self, request: &Request) -> Result<(), AppError> {
-        if self.username.is_some()
-            && self.password.is_some()
-            && !self.is_authenticated(request.headers.get("authorization"))
-        {
-            return Err(AppError::Unauthorized);
+        if let Some(retry_after) = self.locked_out(request) {
+            debug!(
+                "Refusing {} {} from a locked-out client",
+                request.method, request.path
+            );
+            return Err(AppError::TooManyRequests(retry_after));
+        }
+        if self.sessions.is_some() && crate::session::is_public(request) {
+            return Ok(());
+        }
+        // Each protected directory needs its own credentials, and anything
+        // else the request touches the global ones
+        let rules = self.target_rules(request);
+        let mut checked: Vec<&str> = Vec::new();
+        for rule in rules.iter().flatten() {
+            if !checked.contains(&rule.prefix.as_str()) {
+                self.check_path_rule(rule, request)?;
+                checked.push(&rule.prefix);
+            }
+        }
+        if !rules.is_empty() && rules.iter().all(Option::is_some) {
+            return Ok(());
+        }
+        if !self.tokens.is_empty() {
+            if let Some(label) = self.match_token(request) {
+                info!(
+                    "Authenticated API token '{label}' for {} {}",
+                    request.method, request.path
+                );
+                self.clear_failures(request);
+                return Ok(());
+            }
+            if !self.basic_enabled() {
+                auth_failure_rate_limited("missing or invalid API token");
+                if request.headers.contains_key("authorization")
+                    || request.headers.contains_key("x-api-key")
+                {
+                    self.record_failure(request, "invalid API token");
+                }
+                return Err(AppError::Unauthorized);
+            }
+        }
+        if self.basic_enabled() {
+            let role = match self.session_role(request) {
+                Some(role) => role,
+                None => {
+                    let header = request.headers.get("authorization");
+                    let Some(role) = self.authenticate_basic(header) else {
+                        // A missing header is the normal browser challenge, not a failure
+                        if header.is_some() {
+                            self.record_failure(request, "invalid credentials");
+                        } else if self.sessions.is_some()
+                            && crate::session::wants_login_page(request)
+                        {
+                            return Err(AppError::LoginRequired(request.target()));
+                        }
+                        return Err(AppError::Unauthorized);
+                    };
+                    self.clear_failures(request);
+                    role
+                }
+            };
+            let required = crate::users::required_role(request);
+            if role < required {
+                info!(
+                    "Refused {} {}: needs the {} role, the account has {}",
+                    request.method,
+                    request.path,
+                    required.as_str(),
+                    role.as_str()
+                );
+                return Err(AppError::Forbidden);
+            }
         }
         Ok(())
     }
 }
 
+/// Methods allowed for cross-origin requests unless configured otherwise
+pub const DEFAULT_CORS_METHODS: &str = "GET, POST, PUT, DELETE, OPTIONS";
+
+/// Request headers allowed for cross-origin requests unless configured otherwise
+pub const DEFAULT_CORS_HEADERS: &str =
+    "Authorization, Content-Type, X-API-Key, X-Filename, X-Upload-Length";
+
+/// Seconds browsers may cache a preflight result unless configured otherwise
+pub const DEFAULT_CORS_MAX_AGE: u64 = 600;
+
+/// Cross-origin access rules for the JSON APIs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    origins: Vec<String>,
+    methods: String,
+    headers: String,
+    max_age: u64,
+    credentials: bool,
+}
+
+impl CorsPolicy {
+    /// `origins` may contain `*` to allow any origin
+    pub fn new(
+        origins: &[String],
+        methods: &str,
+        headers: &str,
+        max_age: u64,
+        credentials: bool,
+    ) -> Self {
+        Self {
+            origins: origins
+                .iter()
+                .map(|origin| normalize_origin(origin))
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+            methods: methods.to_string(),
+            headers: headers.to_string(),
+            max_age,
+            credentials,
+        }
+    }
+
+    /// The configured policy, or `None` when no origins are allowed
+    pub fn from_cli(cli: Option<&crate::cli::Cli>) -> Option<Self> {
+        let cli = cli?;
+        let origins: Vec<String> = cli
+            .cors_origins
+            .as_deref()?
+            .split(',')
+            .map(str::to_string)
+            .collect();
+        let policy = Self::new(
+            &origins,
+            cli.cors_methods.as_deref().unwrap_or(DEFAULT_CORS_METHODS),
+            cli.cors_headers.as_deref().unwrap_or(DEFAULT_CORS_HEADERS),
+            cli.cors_max_age.unwrap_or(DEFAULT_CORS_MAX_AGE),
+            cli.cors_credentials.unwrap_or(false),
+        );
+        (!policy.origins.is_empty()).then_some(policy)
+    }
+
+    /// Whether `path` (after base path stripping) is one of the APIs CORS covers
+    pub fn covers(path: &str) -> bool {
+        path.starts_with("/_irondrop/") || path.starts_with("/_api/")
+    }
+
+    /// Value for `Access-Control-Allow-Origin`, if `origin` is allowed
+    fn allow_origin(&self, origin: &str) -> Option<String> {
+        let origin = normalize_origin(origin);
+        if self.origins.iter().any(|allowed| allowed == "*") {
+            return Some("*".to_string());
+        }
+        self.origins
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(&origin))
+            .then_some(origin)
+    }
+
+    /// Add the CORS headers for a request from `origin` to a response
+    pub fn apply(&self, origin: Option<&str>, headers: &mut HashMap<String, String>) {
+        let Some(allowed) = origin.and_then(|origin| self.allow_origin(origin)) else {
+            return;
+        };
+        if allowed != "*" {
+            let vary = match headers.get("Vary") {
+                Some(existing) => format!("{existing}, Origin"),
+                None => "Origin".to_string(),
+            };
+            headers.insert("Vary".to_string(), vary);
+        }
+        let wildcard = allowed == "*";
+        headers.insert("Access-Control-Allow-Origin".to_string(), allowed);
+        // Config loading rejects credentials with `*`; never pair them anyway
+        if self.credentials && !wildcard {
+            headers.insert(
+                "Access-Control-Allow-Credentials".to_string(),
+                "true".to_string(),
+            );
+        }
+    }
+
+    /// Answer a preflight request; `None` if `request` is not a preflight
+    ///
+    /// The origin headers themselves are added by [`Self::apply`], so a
+    /// disallowed origin gets a plain `403`.
+    pub fn preflight(&self, request: &Request) -> Option<Response> {
+        if !request.method.eq_ignore_ascii_case("OPTIONS") {
+            return None;
+        }
+        let origin = request.headers.get("origin")?;
+        let requested_method = request.headers.get("access-control-request-method")?;
+
+        let mut headers = HashMap::new();
+        if self.allow_origin(origin).is_none() {
+            debug!("Rejecting CORS preflight from origin {origin}");
+            return Some(Response {
+                status_code: 403,
+                status_text: "Forbidden".to_string(),
+                headers,
+                body: ResponseBody::Text(String::new()),
+            });
+        }
+
+        let allowed_headers = if self.headers.trim() == "*" {
+            // Echo the request so the wildcard also works with credentials
+            request
+                .headers
+                .get("access-control-request-headers")
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            self.headers.clone()
+        };
+        debug!("Answering CORS preflight from {origin} for {requested_method}");
+        headers.insert(
+            "Access-Control-Allow-Methods".to_string(),
+            self.methods.clone(),
+        );
+        if !allowed_headers.is_empty() {
+            headers.insert("Access-Control-Allow-Headers".to_string(), allowed_headers);
+        }
+        headers.insert(
+            "Access-Control-Max-Age".to_string(),
+            self.max_age.to_string(),
+        );
+        Some(Response {
+            status_code: 204,
+            status_text: "No Content".to_string(),
+            headers,
+            body: ResponseBody::Text(String::new()),
+        })
+    }
+}
+
+/// Content-Security-Policy that fits the built-in templates: inline scripts
+/// and styles, Google Fonts, Chart.js from jsDelivr on the monitor page and
+/// external images in Markdown previews
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+    script-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net; \
+    style-src 'self' 'unsafe-inline' https://fonts.googleapis.com; \
+    font-src 'self' https://fonts.gstatic.com; img-src 'self' data: blob: https:; \
+    media-src 'self' blob:; connect-src 'self'; object-src 'none'; base-uri 'self'; \
+    form-action 'self'; frame-ancestors 'self'";
+
+/// `X-Frame-Options` unless configured otherwise
+pub const DEFAULT_FRAME_OPTIONS: &str = "SAMEORIGIN";
+
+/// `Referrer-Policy` unless configured otherwise; keeps share paths off other sites
+pub const DEFAULT_REFERRER_POLICY: &str = "same-origin";
+
+/// `Strict-Transport-Security` max-age in seconds when TLS is enabled (one year)
+pub const DEFAULT_HSTS_MAX_AGE: u64 = 31_536_000;
+
+/// Header values that disable the header instead of sending it
+const HEADER_OFF: &str = "off";
+
+/// Security headers added to every response
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SecurityHeaders {
+    headers: Vec<(&'static str, String)>,
+}
+
+impl SecurityHeaders {
+    /// Headers with the given overrides; `None` means the default, `off` drops
+    /// the header, and HSTS is only sent when `https`
+    pub fn new(
+        https: bool,
+        content_security_policy: Option<&str>,
+        frame_options: Option<&str>,
+        referrer_policy: Option<&str>,
+        hsts_max_age: Option<u64>,
+    ) -> Self {
+        let configured = [
+            (
+                "Content-Security-Policy",
+                content_security_policy.unwrap_or(DEFAULT_CONTENT_SECURITY_POLICY),
+            ),
+            ("X-Content-Type-Options", "nosniff"),
+            (
+                "X-Frame-Options",
+                frame_options.unwrap_or(DEFAULT_FRAME_OPTIONS),
+            ),
+            (
+                "Referrer-Policy",
+                referrer_policy.unwrap_or(DEFAULT_REFERRER_POLICY),
+            ),
+        ];
+        let mut headers: Vec<(&'static str, String)> = configured
+            .into_iter()
+            .map(|(name, value)| (name, value.trim()))
+            .filter(|(_, value)| !value.is_empty() && !value.eq_ignore_ascii_case(HEADER_OFF))
+            .map(|(name, value)| (name, value.to_string()))
+            .collect();
+        let hsts_max_age = hsts_max_age.unwrap_or(DEFAULT_HSTS_MAX_AGE);
+        if https && hsts_max_age > 0 {
+            headers.push((
+                "Strict-Transport-Security",
+                format!("max-age={hsts_max_age}"),
+            ));
+        }
+        Self { headers }
+    }
+
+    /// Headers as configured; `None` when disabled with `--security-headers false`
+    pub fn from_cli(cli: Option<&crate::cli::Cli>) -> Option<Self> {
+        match cli {
+            Some(cli) if !cli.security_headers.unwrap_or(true) => None,
+            Some(cli) => Some(Self::new(
+                cli.ssl_cert.is_some() && cli.ssl_key.is_some(),
+                cli.content_security_policy.as_deref(),
+                cli.frame_options.as_deref(),
+                cli.referrer_policy.as_deref(),
+                cli.hsts_max_age,
+            )),
+            None => Some(Self::new(false, None, None, None, None)),
+        }
+    }
+
+    /// Add the headers a handler has not already set itself
+    pub fn apply(&self, headers: &mut HashMap<String, String>) {
+        for (name, value) in &self.headers {
+            if !headers.keys().any(|key| key.eq_ignore_ascii_case(name)) {
+                headers.insert((*name).to_string(), value.clone());
+            }
+        }
+    }
+}
+
+/// Origins compare without a trailing slash
+fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_string()
+}
+
+/// Decoded `user:password` of a Basic header, empty when it is not one
+fn decode_basic(header: &str) -> Vec<u8> {
+    header
+        .strip_prefix("Basic ")
+        .and_then(|encoded| {
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+fn split_credentials(decoded: &[u8]) -> (&[u8], &[u8]) {
+    match decoded.iter().position(|&b| b == b':') {
+        Some(colon) => (&decoded[..colon], &decoded[colon + 1..]),
+        None => (decoded, &[]),
+    }
+}
+
+/// Cache key for a verified header, so the header itself is not kept
+fn header_key(header: &str) -> [u8; 32] {
+    let mut hasher = crate::checksum::Sha256::new();
+    hasher.update(header.as_bytes());
+    hasher.finalize()
+}
+
 fn constant_time_eq_bytes(a: &[u8], b: &[u8]) -> bool {
     if a.len() != b.len() {
         return false;
@@ -86,6 +1121,18 @@ fn constant_time_eq_bytes(a: &[u8], b: &[u8]) -> bool {
     diff == 0
 }
 
+fn emit_auth_failure(request: &Request, reason: &str) {
+    crate::hooks::emit(
+        crate::hooks::HookEvent::AuthFailure,
+        &[
+            ("reason", reason.into()),
+            ("method", request.method.as_str().into()),
+            ("path", request.path.as_str().into()),
+            ("client", crate::hooks::client_of(request).into()),
+        ],
+    );
+}
+
 fn auth_failure_rate_limited(reason: &'static str) {
     static STATE: OnceLock<Mutex<(Instant, u64)>> = OnceLock::new();
     let state = STATE.get_or_init(|| {
@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: MIT
+
+//! Memory-mapped serving of hot files
+//!
+//! With `--mmap-cache-size` set, files of at most `--mmap-max-file` bytes are
+//! mapped read-only on first download and kept mapped for later requests, so
+//! repeated downloads skip the open/read syscalls entirely. Mappings are
+//! evicted least recently used first once their total size would exceed the
+//! cache size, and are dropped as soon as a file's size or modification time
+//! changes.
+//!
+//! Only available on 64-bit Unix targets; elsewhere the option is ignored with
+//! a warning. A file that is truncated in place while mapped can crash the
+//! process on access, so only enable this for content that is replaced
+//! atomically (uploads always are) or not modified at all.
+
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Default `--mmap-max-file`
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+
+static CACHE: OnceLock<MmapCache> = OnceLock::new();
+
+/// Enable the process-wide cache; a zero size leaves it disabled
+pub fn init(max_total: u64, max_file: u64) {
+    if max_total == 0 {
+        return;
+    }
+    if !cfg!(all(unix, target_pointer_width = "64")) {
+        warn!("Memory-mapped file serving needs a 64-bit Unix target; ignoring --mmap-cache-size");
+        return;
+    }
+    let _ = CACHE.set(MmapCache::new(max_total, max_file));
+}
+
+/// The process-wide cache, if enabled
+pub fn global() -> Option<&'static MmapCache> {
+    CACHE.get()
+}
+
+/// A read-only mapping of a whole file
+pub struct MappedFile {
+    ptr: *const u8,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only and owned by this value until drop
+unsafe impl Send for MappedFile {}
+unsafe impl Sync for MappedFile {}
+
+impl MappedFile {
+    /// Map `path`, which must not be empty
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        use std::ffi::{c_int, c_void};
+        use std::os::fd::AsRawFd;
+
+        unsafe extern "C" {
+            fn mmap(
+                addr: *mut c_void,
+                len: usize,
+                prot: c_int,
+                flags: c_int,
+                fd: c_int,
+                offset: i64,
+            ) -> *mut c_void;
+        }
+        const PROT_READ: c_int = 1;
+        const MAP_PRIVATE: c_int = 2;
+
+        let file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot map an empty file",
+            ));
+        }
+        // SAFETY: a fresh private read-only mapping of an open descriptor; the
+        // mapping stays valid after the descriptor is closed
+        let ptr = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                len,
+                PROT_READ,
+                MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr as isize == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: ptr as *const u8,
+            len,
+        })
+    }
+
+    /// Map `path`, which must not be empty
+    #[cfg(not(all(unix, target_pointer_width = "64")))]
+    pub fn open(_path: &Path) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "memory mapping is not supported on this target",
+        ))
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: `ptr` points to `len` mapped, readable bytes until drop
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        #[cfg(all(unix, target_pointer_width = "64"))]
+        {
+            unsafe extern "C" {
+                fn munmap(addr: *mut std::ffi::c_void, len: usize) -> std::ffi::c_int;
+            }
+            // SAFETY: unmaps exactly the region created in `open`
+            unsafe {
+                munmap(self.ptr as *mut std::ffi::c_void, self.len);
+            }
+        }
+    }
+}
+
+struct Entry {
+    file: Arc<MappedFile>,
+    modified: Option<SystemTime>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<PathBuf, Entry>,
+    total: u64,
+    tick: u64,
+}
+
+/// Mapped files bounded by total size, evicted least recently used first
+pub struct MmapCache {
+    max_total: u64,
+    max_file: u64,
+    state: Mutex<CacheState>,
+}
+
+impl MmapCache {
+    pub fn new(max_total: u64, max_file: u64) -> Self {
+        Self {
+            max_total,
+            max_file: max_file.min(max_total),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Mapping of `path` as described by `metadata`, creating it if needed
+    ///
+    /// Returns `None` for empty files, files over the per-file limit and
+    /// files that cannot be mapped; callers then stream the file as usual.
+    pub fn get(&self, path: &Path, metadata: &Metadata) -> Option<Arc<MappedFile>> {
+        let len = metadata.len();
+        if len == 0 || len > self.max_file {
+            return None;
+        }
+        let modified = metadata.modified().ok();
+        {
+            let mut state = self.state.lock().ok()?;
+            state.tick += 1;
+            let tick = state.tick;
+            match state.entries.get_mut(path) {
+                Some(entry) if entry.file.len() as u64 == len && entry.modified == modified => {
+                    entry.last_used = tick;
+                    return Some(entry.file.clone());
+                }
+                Some(_) => {
+                    if let Some(stale) = state.entries.remove(path) {
+                        state.total -= stale.file.len() as u64;
+                    }
+                }
+                None => {}
+            }
+        }
+
+        // Map outside the lock; a concurrent miss for the same file just
+        // replaces the other mapping
+        let file = match MappedFile::open(path) {
+            Ok(file) if file.len() as u64 == len => Arc::new(file),
+            Ok(_) => return None,
+            Err(e) => {
+                debug!("Not mapping {}: {e}", path.display());
+                return None;
+            }
+        };
+
+        let mut state = self.state.lock().ok()?;
+        if let Some(previous) = state.entries.remove(path) {
+            state.total -= previous.file.len() as u64;
+        }
+        while state.total + len > self.max_total {
+            let Some(victim) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            if let Some(evicted) = state.entries.remove(&victim) {
+                state.total -= evicted.file.len() as u64;
+                debug!("Unmapped {} from the mmap cache", victim.display());
+            }
+        }
+        state.tick += 1;
+        let last_used = state.tick;
+        state.total += len;
+        state.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                file: file.clone(),
+                modified,
+                last_used,
+            },
+        );
+        debug!("Mapped {} ({len} bytes)", path.display());
+        Some(file)
+    }
+
+    /// Number of mapped files and their total size in bytes
+    pub fn usage(&self) -> (usize, u64) {
+        self.state
+            .lock()
+            .map(|state| (state.entries.len(), state.total))
+            .unwrap_or((0, 0))
+    }
+}
+
+#[cfg(all(test, unix, target_pointer_width = "64"))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_lru_eviction_and_invalidation() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, len: usize| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![name.as_bytes()[0]; len]).unwrap();
+            path
+        };
+        let a = write("a", 400);
+        let b = write("b", 400);
+        let c = write("c", 400);
+        let big = write("big", 2000);
+        let cache = MmapCache::new(1000, 1000);
+        let get = |path: &Path| cache.get(path, &fs::metadata(path).unwrap());
+
+        assert_eq!(get(&a).unwrap().as_slice(), &[b'a'; 400][..]);
+        assert!(get(&b).is_some());
+        assert!(Arc::ptr_eq(&get(&a).unwrap(), &get(&a).unwrap()));
+        // `b` is the least recently used and makes room for `c`
+        assert!(get(&c).is_some());
+        assert_eq!(cache.usage(), (2, 800));
+        assert!(cache.state.lock().unwrap().entries.contains_key(&a));
+        assert!(!cache.state.lock().unwrap().entries.contains_key(&b));
+
+        assert!(get(&big).is_none());
+        assert_eq!(cache.usage(), (2, 800));
+
+        // A changed file is mapped again
+        fs::write(&a, [b'z'; 100]).unwrap();
+        assert_eq!(get(&a).unwrap().as_slice(), &[b'z'; 100][..]);
+        assert_eq!(cache.usage(), (2, 500));
+    }
+}
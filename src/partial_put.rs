@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: MIT
+
+//! Ranged WebDAV `PUT` uploads
+//!
+//! Tools such as rclone or `curl --range` can upload a file in pieces by
+//! sending each piece as `PUT /path/to/file` with
+//! `Content-Range: bytes start-end/total` (`total` may be `*`). Pieces may
+//! arrive in any order and may overlap; they are written at their offsets
+//! into a sparse hidden file next to the target, and each answer is
+//! `202 Accepted` with `Upload-Offset` set to the number of contiguous bytes
+//! received from the start.
+//!
+//! A zero-length `PUT` with `Content-Range: bytes */total` commits the file:
+//! when every byte of `0..total` has arrived the hidden file is renamed over
+//! the target (`201`/`204`), otherwise the answer is `409 Conflict` with the
+//! current `Upload-Offset`. A plain `PUT` to the same path discards pending
+//! pieces, and uploads idle for an hour are dropped.
+
+use crate::error::AppError;
+use crate::http::{Request, Response};
+use crate::upload::TEMP_FILE_PREFIX;
+use crate::upload_session::{append_body, parse_content_range};
+use log::{debug, info, trace};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Idle time after which a ranged upload is discarded
+pub const PARTIAL_PUT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Largest file that can be assembled from ranges (matches the request body limit)
+const MAX_PARTIAL_PUT_SIZE: u64 = 10 * 1024 * 1024 * 1024;
+
+static STAGING_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A ranged upload waiting for its finalize request
+#[derive(Debug)]
+struct PartialPut {
+    staging_path: PathBuf,
+    /// Total size once any request announced it
+    total: Option<u64>,
+    /// Received byte ranges (inclusive), sorted and merged
+    ranges: Vec<(u64, u64)>,
+    last_activity: Instant,
+}
+
+impl PartialPut {
+    fn add_range(&mut self, start: u64, end: u64) {
+        self.ranges.push((start, end));
+        self.ranges.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(self.ranges.len());
+        for &(start, end) in &self.ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.ranges = merged;
+    }
+
+    /// Bytes received without a gap from offset 0
+    fn contiguous(&self) -> u64 {
+        match self.ranges.first() {
+            Some(&(0, end)) => end + 1,
+            _ => 0,
+        }
+    }
+
+    fn highest_byte(&self) -> Option<u64> {
+        self.ranges.last().map(|&(_, end)| end)
+    }
+}
+
+fn partial_puts() -> &'static Mutex<HashMap<PathBuf, PartialPut>> {
+    static PARTIAL_PUTS: OnceLock<Mutex<HashMap<PathBuf, PartialPut>>> = OnceLock::new();
+    PARTIAL_PUTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lock_partial_puts()
+-> Result<std::sync::MutexGuard<'static, HashMap<PathBuf, PartialPut>>, AppError> {
+    partial_puts()
+        .lock()
+        .map_err(|_| AppError::InternalServerError("partial PUT registry poisoned".to_string()))
+}
+
+/// Drop ranged uploads that have been idle longer than [`PARTIAL_PUT_TTL`]
+fn cleanup_expired(puts: &mut HashMap<PathBuf, PartialPut>) {
+    puts.retain(|target, put| {
+        let keep = put.last_activity.elapsed() < PARTIAL_PUT_TTL;
+        if !keep {
+            info!("Ranged PUT for {} expired", target.display());
+            let _ = fs::remove_file(&put.staging_path);
+        }
+        keep
+    });
+}
+
+/// Forget pending ranges for `target` (it is being replaced by a plain `PUT`)
+pub(crate) fn discard(target: &Path) {
+    if let Ok(mut puts) = partial_puts().lock()
+        && let Some(put) = puts.remove(target)
+    {
+        debug!("Discarding pending ranges for {}", target.display());
+        let _ = fs::remove_file(&put.staging_path);
+    }
+}
+
+/// Handle a `PUT` carrying `Content-Range` for `target` (already resolved and checked)
+pub(crate) fn handle_range_put(
+    request: &Request,
+    target: &Path,
+    content_range: &str,
+    existed: bool,
+) -> Result<Response, AppError> {
+    let body_len = request.body.as_ref().map_or(0, |b| b.len() as u64);
+    if let Some(total) = parse_finalize_range(content_range) {
+        if body_len != 0 {
+            return Err(AppError::BadRequest);
+        }
+        return finalize(target, total, existed);
+    }
+
+    let (start, end, total) = parse_content_range(content_range).ok_or(AppError::BadRequest)?;
+    if body_len != end - start + 1 {
+        debug!(
+            "Ranged PUT body is {} bytes but Content-Range covers {}",
+            body_len,
+            end - start + 1
+        );
+        return Err(AppError::BadRequest);
+    }
+    if end >= MAX_PARTIAL_PUT_SIZE || total.is_some_and(|t| t > MAX_PARTIAL_PUT_SIZE) {
+        return Err(AppError::PayloadTooLarge(MAX_PARTIAL_PUT_SIZE));
+    }
+    if total.is_some_and(|t| end >= t) {
+        return Err(AppError::BadRequest);
+    }
+
+    let mut puts = lock_partial_puts()?;
+    cleanup_expired(&mut puts);
+    if let Some(put) = puts.get(target) {
+        let conflicting_total = match (put.total, total) {
+            (Some(known), Some(new)) => known != new,
+            (None, Some(new)) => put.highest_byte().is_some_and(|high| high >= new),
+            _ => false,
+        };
+        if conflicting_total {
+            debug!("Ranged PUT for {} changed its total size", target.display());
+            return Err(AppError::BadRequest);
+        }
+    }
+    if !puts.contains_key(target) {
+        let staging_path = staging_path_for(target)?;
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&staging_path)?;
+        debug!(
+            "Started ranged PUT for {} in {}",
+            target.display(),
+            staging_path.display()
+        );
+        puts.insert(
+            target.to_path_buf(),
+            PartialPut {
+                staging_path,
+                total: None,
+                ranges: Vec::new(),
+                last_activity: Instant::now(),
+            },
+        );
+    }
+    let put = puts.get_mut(target).ok_or(AppError::NotFound)?;
+
+    append_body(&put.staging_path, start, request.body.as_ref())?;
+    put.add_range(start, end);
+    put.total = put.total.or(total);
+    put.last_activity = Instant::now();
+    trace!(
+        "Ranged PUT for {} stored bytes {}-{} ({} contiguous)",
+        target.display(),
+        start,
+        end,
+        put.contiguous()
+    );
+
+    Ok(offset_response(202, "Accepted", put.contiguous()))
+}
+
+/// Commit the assembled file once every byte of `0..total` has arrived
+fn finalize(target: &Path, total: u64, existed: bool) -> Result<Response, AppError> {
+    let mut puts = lock_partial_puts()?;
+    cleanup_expired(&mut puts);
+    let Some(put) = puts.get(target) else {
+        if total == 0 {
+            crate::webdav::replace_file(target, |_| Ok(()))?;
+            return Ok(created_response(existed));
+        }
+        debug!("Finalize for {} without pending ranges", target.display());
+        return Ok(offset_response(409, "Conflict", 0));
+    };
+    if put.total.is_some_and(|known| known != total) {
+        return Err(AppError::BadRequest);
+    }
+    let complete = total > 0 && put.ranges == [(0, total - 1)];
+    if !complete {
+        debug!(
+            "Finalize for {} is missing data ({} of {} bytes contiguous)",
+            target.display(),
+            put.contiguous(),
+            total
+        );
+        return Ok(offset_response(409, "Conflict", put.contiguous()));
+    }
+
+    let put = puts.remove(target).ok_or(AppError::NotFound)?;
+    let file = OpenOptions::new().write(true).open(&put.staging_path)?;
+    file.set_len(total)?;
+    file.sync_all()?;
+    drop(file);
+    if let Err(e) = fs::rename(&put.staging_path, target) {
+        let _ = fs::remove_file(&put.staging_path);
+        return Err(AppError::from(e));
+    }
+    info!(
+        "Ranged PUT completed: {} ({} bytes)",
+        target.display(),
+        total
+    );
+    Ok(created_response(existed))
+}
+
+/// `bytes */total`, the finalize form of `Content-Range`
+fn parse_finalize_range(value: &str) -> Option<u64> {
+    let spec = value.trim().strip_prefix("bytes")?.trim_start();
+    let total = spec.strip_prefix('*')?.trim_start().strip_prefix('/')?;
+    total.trim().parse().ok()
+}
+
+fn staging_path_for(target: &Path) -> Result<PathBuf, AppError> {
+    let parent = target.parent().ok_or(AppError::InvalidPath)?;
+    Ok(parent.join(format!(
+        "{}range_{}_{:x}_{}.part",
+        TEMP_FILE_PREFIX,
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+        STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+fn offset_response(status_code: u16, status_text: &str, offset: u64) -> Response {
+    let mut response = crate::webdav::status_response(status_code, status_text);
+    response
+        .headers
+        .insert("Upload-Offset".to_string(), offset.to_string());
+    response
+}
+
+fn created_response(existed: bool) -> Response {
+    if existed {
+        crate::webdav::status_response(204, "No Content")
+    } else {
+        crate::webdav::status_response(201, "Created")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranges_merge_and_contiguous_prefix() {
+        let mut put = PartialPut {
+            staging_path: PathBuf::new(),
+            total: None,
+            ranges: Vec::new(),
+            last_activity: Instant::now(),
+        };
+        put.add_range(10, 19);
+        assert_eq!(put.contiguous(), 0);
+        put.add_range(0, 4);
+        assert_eq!(put.ranges, vec![(0, 4), (10, 19)]);
+        assert_eq!(put.contiguous(), 5);
+        put.add_range(5, 9);
+        assert_eq!(put.ranges, vec![(0, 19)]);
+        put.add_range(15, 24);
+        assert_eq!(put.ranges, vec![(0, 24)]);
+        assert_eq!(put.highest_byte(), Some(24));
+    }
+
+    #[test]
+    fn test_parse_finalize_range() {
+        assert_eq!(parse_finalize_range("bytes */1024"), Some(1024));
+        assert_eq!(parse_finalize_range("bytes */0"), Some(0));
+        assert_eq!(parse_finalize_range("bytes 0-1/2"), None);
+        assert_eq!(parse_finalize_range("bytes */*"), None);
+    }
+}
@@ -0,0 +1,407 @@
+// SPDX-License-Identifier: MIT
+
+//! Password hashes for Basic authentication
+//!
+//! `[auth] password_hash` (or `--password-hash`) replaces the plain-text
+//! `password` with an Argon2 string in PHC format such as
+//! `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>` or a bcrypt one
+//! (`$2a$`, `$2b$`, `$2y$`). `irondrop hash-password` reads a password from
+//! standard input and prints an argon2id hash for it.
+//!
+//! Both algorithms are implemented here to avoid pulling in a crypto crate.
+//! Digests are compared in constant time; the middleware in
+//! [`crate::middleware`] only pays for a verification once per distinct
+//! `Authorization` header.
+
+mod argon2;
+mod bcrypt;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use std::fs::File;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+
+/// Memory of generated hashes in KiB (the OWASP recommendation for argon2id)
+pub const DEFAULT_MEMORY_KIB: u32 = 19 * 1024;
+
+/// Passes over memory of generated hashes
+pub const DEFAULT_ITERATIONS: u32 = 2;
+
+/// Largest accepted Argon2 memory cost, 4 GiB
+const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+
+const SALT_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+
+/// A parsed password hash
+#[derive(Clone, PartialEq, Eq)]
+pub enum PasswordHash {
+    Argon2 {
+        params: argon2::Params,
+        salt: Vec<u8>,
+        hash: Vec<u8>,
+    },
+    Bcrypt {
+        cost: u32,
+        salt: [u8; 16],
+        hash: [u8; 23],
+    },
+}
+
+impl std::fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PasswordHash({})", self.algorithm())
+    }
+}
+
+impl PasswordHash {
+    /// Parse an Argon2 PHC string or a bcrypt hash
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.starts_with("$argon2") {
+            Self::parse_argon2(text)
+        } else if text.starts_with("$2") {
+            Self::parse_bcrypt(text)
+        } else {
+            Err("expected an argon2 ($argon2id$...) or bcrypt ($2b$...) hash".to_string())
+        }
+    }
+
+    fn parse_argon2(text: &str) -> Result<Self, String> {
+        let mut fields = text.split('$').skip(1);
+        let variant = match fields.next() {
+            Some("argon2id") => argon2::Variant::Id,
+            Some("argon2i") => argon2::Variant::I,
+            Some("argon2d") => argon2::Variant::D,
+            _ => return Err("unknown argon2 variant".to_string()),
+        };
+        let mut next = fields.next();
+        let version = match next.and_then(|field| field.strip_prefix("v=")) {
+            Some(version) => {
+                next = fields.next();
+                match version {
+                    "19" => 0x13,
+                    "16" => 0x10,
+                    _ => return Err(format!("unsupported argon2 version {version}")),
+                }
+            }
+            None => 0x10,
+        };
+        let (mut memory, mut iterations, mut lanes) = (None, None, None);
+        for param in next.unwrap_or_default().split(',') {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| format!("invalid argon2 parameter '{param}'"))?;
+            let value: u32 = value
+                .parse()
+                .map_err(|_| format!("invalid argon2 parameter '{param}'"))?;
+            match key {
+                "m" => memory = Some(value),
+                "t" => iterations = Some(value),
+                "p" => lanes = Some(value),
+                _ => return Err(format!("unknown argon2 parameter '{key}'")),
+            }
+        }
+        let (Some(memory), Some(iterations), Some(lanes)) = (memory, iterations, lanes) else {
+            return Err("argon2 hash needs m, t and p parameters".to_string());
+        };
+        if !(1..=255).contains(&lanes) || iterations == 0 {
+            return Err("argon2 needs t >= 1 and p between 1 and 255".to_string());
+        }
+        if memory < 8 * lanes || memory > MAX_MEMORY_KIB {
+            return Err(format!(
+                "argon2 memory must be between 8*p and {MAX_MEMORY_KIB} KiB"
+            ));
+        }
+        let decode = |field: Option<&str>, what: &str| {
+            field
+                .and_then(|field| STANDARD_NO_PAD.decode(field.trim_end_matches('=')).ok())
+                .ok_or_else(|| format!("invalid argon2 {what}"))
+        };
+        let salt = decode(fields.next(), "salt")?;
+        let hash = decode(fields.next(), "hash")?;
+        if salt.len() < 8 || hash.len() < 4 || fields.next().is_some() {
+            return Err("argon2 salt or hash has the wrong length".to_string());
+        }
+        Ok(Self::Argon2 {
+            params: argon2::Params {
+                variant,
+                version,
+                memory,
+                iterations,
+                lanes,
+            },
+            salt,
+            hash,
+        })
+    }
+
+    fn parse_bcrypt(text: &str) -> Result<Self, String> {
+        let mut fields = text.split('$').skip(1);
+        if !matches!(fields.next(), Some("2a" | "2b" | "2y")) {
+            return Err("unsupported bcrypt version".to_string());
+        }
+        let cost: u32 = fields
+            .next()
+            .filter(|cost| cost.len() == 2)
+            .and_then(|cost| cost.parse().ok())
+            .filter(|cost| (bcrypt::MIN_COST..=bcrypt::MAX_COST).contains(cost))
+            .ok_or_else(|| "invalid bcrypt cost".to_string())?;
+        let rest = fields.next().unwrap_or_default();
+        if rest.len() != 53 || !rest.is_ascii() || fields.next().is_some() {
+            return Err("bcrypt hash has the wrong length".to_string());
+        }
+        let invalid = || "invalid bcrypt encoding".to_string();
+        let salt = bcrypt::decode(&rest[..22], 16).ok_or_else(invalid)?;
+        let hash = bcrypt::decode(&rest[22..], 23).ok_or_else(invalid)?;
+        Ok(Self::Bcrypt {
+            cost,
+            salt: salt.try_into().map_err(|_| invalid())?,
+            hash: hash.try_into().map_err(|_| invalid())?,
+        })
+    }
+
+    /// Name of the algorithm, e.g. `argon2id`
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Self::Argon2 { params, .. } => match params.variant {
+                argon2::Variant::Id => "argon2id",
+                argon2::Variant::I => "argon2i",
+                argon2::Variant::D => "argon2d",
+            },
+            Self::Bcrypt { .. } => "bcrypt",
+        }
+    }
+
+    /// Whether `password` matches, comparing the digests in constant time
+    pub fn verify(&self, password: &[u8]) -> bool {
+        match self {
+            Self::Argon2 { params, salt, hash } => {
+                let computed = argon2::hash(params, password, salt, &[], &[], hash.len());
+                constant_time_eq(&computed, hash)
+            }
+            Self::Bcrypt { cost, salt, hash } => {
+                constant_time_eq(&bcrypt::hash(password, salt, *cost), hash)
+            }
+        }
+    }
+}
+
+/// argon2id hash of `password` with the default cost, as a PHC string
+pub fn hash_password(password: &str) -> io::Result<String> {
+    hash_password_with(password, DEFAULT_MEMORY_KIB, DEFAULT_ITERATIONS)
+}
+
+/// argon2id hash with `memory_kib` KiB and `iterations` passes, one lane
+pub fn hash_password_with(password: &str, memory_kib: u32, iterations: u32) -> io::Result<String> {
+    let params = argon2::Params {
+        variant: argon2::Variant::Id,
+        version: 0x13,
+        memory: memory_kib.clamp(8, MAX_MEMORY_KIB),
+        iterations: iterations.max(1),
+        lanes: 1,
+    };
+    let mut salt = [0u8; SALT_LEN];
+    random_bytes(&mut salt)?;
+    let hash = argon2::hash(&params, password.as_bytes(), &salt, &[], &[], TAG_LEN);
+    Ok(format!(
+        "$argon2id$v=19$m={},t={},p=1${}${}",
+        params.memory,
+        params.iterations,
+        STANDARD_NO_PAD.encode(salt),
+        STANDARD_NO_PAD.encode(hash)
+    ))
+}
+
+/// Compare without exiting early, so timing does not reveal the mismatch position
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff: u8 = 0;
+    for (x, y) in a.iter().zip(b) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+/// Fill `buf` from the operating system's random source. There is no weaker
+/// fallback: salts and secrets are not generated at all without it.
+pub(crate) fn random_bytes(buf: &mut [u8]) -> io::Result<()> {
+    File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(buf))
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot read /dev/urandom: {e}")))
+}
+
+/// `irondrop hash-password`: read a password from standard input and print
+/// its hash, returning the exit code
+pub fn hash_password_command() -> i32 {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let read = |prompt: &str| -> io::Result<String> {
+        if interactive {
+            eprint!("{prompt}");
+            io::stderr().flush()?;
+        }
+        let _echo = interactive.then(EchoOff::new);
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line)?;
+        if interactive {
+            eprintln!();
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    let password = match read("Password: ") {
+        Ok(password) if !password.is_empty() => password,
+        Ok(_) => {
+            eprintln!("error: the password is empty");
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("error: failed to read the password: {e}");
+            return 1;
+        }
+    };
+    if interactive && read("Repeat password: ").ok().as_deref() != Some(password.as_str()) {
+        eprintln!("error: the passwords do not match");
+        return 1;
+    }
+    match hash_password(&password) {
+        Ok(hash) => {
+            println!("{hash}");
+            0
+        }
+        Err(e) => {
+            eprintln!("error: failed to generate a salt: {e}");
+            1
+        }
+    }
+}
+
+/// Turns terminal echo off while alive (Unix only)
+struct EchoOff;
+
+impl EchoOff {
+    fn new() -> Self {
+        Self::stty("-echo");
+        Self
+    }
+
+    fn stty(mode: &str) {
+        #[cfg(unix)]
+        if let Ok(tty) = File::open("/dev/tty") {
+            let _ = std::process::Command::new("stty")
+                .arg(mode)
+                .stdin(tty)
+                .status();
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+    }
+}
+
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        Self::stty("echo");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_blake2b_vectors() {
+        let digest = |data: &[u8], len: usize| {
+            let mut hasher = argon2::Blake2b::new(len);
+            hasher.update(data);
+            hex(&hasher.finalize())
+        };
+        assert_eq!(
+            digest(b"abc", 64),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            digest(&[0u8; 128], 32),
+            "378d0caaaa3855f1b38693c1d6ef004fd118691c95c959d4efa950d6d6fcf7c1"
+        );
+    }
+
+    #[test]
+    fn test_argon2_rfc_vectors() {
+        let run = |variant| {
+            let params = argon2::Params {
+                variant,
+                version: 0x13,
+                memory: 32,
+                iterations: 3,
+                lanes: 4,
+            };
+            hex(&argon2::hash(
+                &params, &[1; 32], &[2; 16], &[3; 8], &[4; 12], 32,
+            ))
+        };
+        assert_eq!(
+            run(argon2::Variant::D),
+            "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb"
+        );
+        assert_eq!(
+            run(argon2::Variant::I),
+            "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8"
+        );
+        assert_eq!(
+            run(argon2::Variant::Id),
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+        );
+    }
+
+    #[test]
+    fn test_bcrypt_vectors() {
+        for (password, hash) in [
+            (
+                "",
+                "$2a$05$CCCCCCCCCCCCCCCCCCCCC.7uG0VCzI2bS7j6ymqJi9CdcdxiRTWNy",
+            ),
+            (
+                "U*U",
+                "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW",
+            ),
+            (
+                "U*U*",
+                "$2a$05$CCCCCCCCCCCCCCCCCCCCC.VGOzA784oUp/Z0DY336zx7pLYAy0lwK",
+            ),
+        ] {
+            let parsed = PasswordHash::parse(hash).unwrap();
+            assert_eq!(parsed.algorithm(), "bcrypt");
+            assert!(parsed.verify(password.as_bytes()), "{password:?}");
+            assert!(!parsed.verify(b"wrong"));
+        }
+    }
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hash = hash_password_with("correct horse", 64, 1).unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+        let parsed = PasswordHash::parse(&hash).unwrap();
+        assert!(parsed.verify(b"correct horse"));
+        assert!(!parsed.verify(b"correct horse "));
+        assert_ne!(hash, hash_password_with("correct horse", 64, 1).unwrap());
+
+        for bad in [
+            "plaintext",
+            "$argon2id$v=19$m=64,t=1$c2FsdHNhbHQ$aGFzaGhhc2g",
+            "$argon2id$v=19$m=4,t=1,p=1$c2FsdHNhbHQ$aGFzaGhhc2g",
+            "$argon2x$v=19$m=64,t=1,p=1$c2FsdHNhbHQ$aGFzaGhhc2g",
+            "$2b$99$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW",
+            "$2b$05$short",
+        ] {
+            assert!(PasswordHash::parse(bad).is_err(), "{bad}");
+        }
+    }
+}
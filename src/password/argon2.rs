@@ -0,0 +1,402 @@
+// SPDX-License-Identifier: MIT
+
+//! Argon2 (RFC 9106) and the BLAKE2b hash it is built on
+//!
+//! A straightforward single-threaded implementation: lanes are filled one
+//! after another, which gives the same result as the parallel reference.
+
+/// Argon2 variant, numbered as in the RFC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    D = 0,
+    I = 1,
+    Id = 2,
+}
+
+/// Parameters of one Argon2 run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    pub variant: Variant,
+    /// `0x10` or `0x13`
+    pub version: u32,
+    /// Memory in KiB
+    pub memory: u32,
+    pub iterations: u32,
+    pub lanes: u32,
+}
+
+const BLOCK_WORDS: usize = 128;
+const SYNC_POINTS: u32 = 4;
+
+type Block = [u64; BLOCK_WORDS];
+
+/// Argon2 tag of `tag_len` bytes
+pub fn hash(
+    params: &Params,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    data: &[u8],
+    tag_len: usize,
+) -> Vec<u8> {
+    let lanes = params.lanes.max(1);
+    let mut h0 = Blake2b::new(64);
+    for value in [
+        lanes,
+        tag_len as u32,
+        params.memory,
+        params.iterations,
+        params.version,
+        params.variant as u32,
+    ] {
+        h0.update(&value.to_le_bytes());
+    }
+    for input in [password, salt, secret, data] {
+        h0.update(&(input.len() as u32).to_le_bytes());
+        h0.update(input);
+    }
+    let h0 = h0.finalize();
+
+    let memory = (params.memory.max(8 * lanes) / (SYNC_POINTS * lanes)) * SYNC_POINTS * lanes;
+    let lane_length = (memory / lanes) as usize;
+    let segment_length = lane_length / SYNC_POINTS as usize;
+    let mut blocks: Vec<Block> = vec![[0; BLOCK_WORDS]; memory as usize];
+
+    for lane in 0..lanes as usize {
+        for column in 0..2u32 {
+            let mut seed = h0.clone();
+            seed.extend_from_slice(&column.to_le_bytes());
+            seed.extend_from_slice(&(lane as u32).to_le_bytes());
+            blocks[lane * lane_length + column as usize] = block_from_bytes(&h_prime(&seed, 1024));
+        }
+    }
+
+    for pass in 0..params.iterations.max(1) {
+        for slice in 0..SYNC_POINTS {
+            for lane in 0..lanes {
+                fill_segment(
+                    &mut blocks,
+                    params,
+                    memory,
+                    lane_length,
+                    segment_length,
+                    pass,
+                    slice,
+                    lane,
+                );
+            }
+        }
+    }
+
+    let mut last = blocks[lane_length - 1];
+    for lane in 1..lanes as usize {
+        xor_into(&mut last, &blocks[lane * lane_length + lane_length - 1]);
+    }
+    let bytes: Vec<u8> = last.iter().flat_map(|word| word.to_le_bytes()).collect();
+    h_prime(&bytes, tag_len)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fill_segment(
+    blocks: &mut [Block],
+    params: &Params,
+    memory: u32,
+    lane_length: usize,
+    segment_length: usize,
+    pass: u32,
+    slice: u32,
+    lane: u32,
+) {
+    let data_independent = params.variant == Variant::I
+        || (params.variant == Variant::Id && pass == 0 && slice < SYNC_POINTS / 2);
+    let zero = [0u64; BLOCK_WORDS];
+    let mut input = [0u64; BLOCK_WORDS];
+    let mut addresses = [0u64; BLOCK_WORDS];
+    if data_independent {
+        input[0] = pass as u64;
+        input[1] = lane as u64;
+        input[2] = slice as u64;
+        input[3] = memory as u64;
+        input[4] = params.iterations as u64;
+        input[5] = params.variant as u64;
+    }
+    let next_addresses = |input: &mut Block, addresses: &mut Block| {
+        input[6] += 1;
+        let mut first = [0u64; BLOCK_WORDS];
+        compress(&zero, input, &mut first, false);
+        compress(&zero, &first, addresses, false);
+    };
+
+    let mut start = 0;
+    if pass == 0 && slice == 0 {
+        start = 2;
+        if data_independent {
+            next_addresses(&mut input, &mut addresses);
+        }
+    }
+
+    let lane_start = lane as usize * lane_length;
+    for index in start..segment_length {
+        let column = slice as usize * segment_length + index;
+        let current = lane_start + column;
+        let previous = if column == 0 {
+            lane_start + lane_length - 1
+        } else {
+            current - 1
+        };
+
+        let pseudo_random = if data_independent {
+            if index % BLOCK_WORDS == 0 {
+                next_addresses(&mut input, &mut addresses);
+            }
+            addresses[index % BLOCK_WORDS]
+        } else {
+            blocks[previous][0]
+        };
+
+        let ref_lane = if pass == 0 && slice == 0 {
+            lane as u64
+        } else {
+            (pseudo_random >> 32) % params.lanes.max(1) as u64
+        };
+        let same_lane = ref_lane == lane as u64;
+        let area = if pass == 0 {
+            if slice == 0 {
+                index - 1
+            } else if same_lane {
+                slice as usize * segment_length + index - 1
+            } else {
+                slice as usize * segment_length - usize::from(index == 0)
+            }
+        } else if same_lane {
+            lane_length - segment_length + index - 1
+        } else {
+            lane_length - segment_length - usize::from(index == 0)
+        } as u64;
+        let low = pseudo_random & 0xffff_ffff;
+        let relative = (low * low) >> 32;
+        let relative = area - 1 - ((area * relative) >> 32);
+        let start_position = if pass == 0 || slice == SYNC_POINTS - 1 {
+            0
+        } else {
+            (slice as usize + 1) * segment_length
+        };
+        let reference =
+            ref_lane as usize * lane_length + (start_position + relative as usize) % lane_length;
+
+        let with_xor = pass > 0 && params.version == 0x13;
+        let (prev_block, ref_block) = (blocks[previous], blocks[reference]);
+        compress(&prev_block, &ref_block, &mut blocks[current], with_xor);
+    }
+}
+
+/// Compression function G; XORs into `out` instead of replacing it when
+/// `with_xor` is set (passes after the first, version 0x13)
+fn compress(x: &Block, y: &Block, out: &mut Block, with_xor: bool) {
+    let mut r = *x;
+    xor_into(&mut r, y);
+    let mut tmp = r;
+    if with_xor {
+        xor_into(&mut tmp, out);
+    }
+    for row in 0..8 {
+        let mut v = [0usize; 16];
+        for (i, slot) in v.iter_mut().enumerate() {
+            *slot = 16 * row + i;
+        }
+        permute(&mut r, v);
+    }
+    for column in 0..8 {
+        let mut v = [0usize; 16];
+        for (i, slot) in v.iter_mut().enumerate() {
+            *slot = 2 * column + (i / 2) * 16 + i % 2;
+        }
+        permute(&mut r, v);
+    }
+    xor_into(&mut tmp, &r);
+    *out = tmp;
+}
+
+fn permute(block: &mut Block, v: [usize; 16]) {
+    const LANES: [[usize; 4]; 8] = [
+        [0, 4, 8, 12],
+        [1, 5, 9, 13],
+        [2, 6, 10, 14],
+        [3, 7, 11, 15],
+        [0, 5, 10, 15],
+        [1, 6, 11, 12],
+        [2, 7, 8, 13],
+        [3, 4, 9, 14],
+    ];
+    for [a, b, c, d] in LANES {
+        let (a, b, c, d) = (v[a], v[b], v[c], v[d]);
+        let blamka = |x: u64, y: u64| {
+            x.wrapping_add(y).wrapping_add(
+                2u64.wrapping_mul(x & 0xffff_ffff)
+                    .wrapping_mul(y & 0xffff_ffff),
+            )
+        };
+        block[a] = blamka(block[a], block[b]);
+        block[d] = (block[d] ^ block[a]).rotate_right(32);
+        block[c] = blamka(block[c], block[d]);
+        block[b] = (block[b] ^ block[c]).rotate_right(24);
+        block[a] = blamka(block[a], block[b]);
+        block[d] = (block[d] ^ block[a]).rotate_right(16);
+        block[c] = blamka(block[c], block[d]);
+        block[b] = (block[b] ^ block[c]).rotate_right(63);
+    }
+}
+
+fn xor_into(block: &mut Block, other: &Block) {
+    for (word, other) in block.iter_mut().zip(other) {
+        *word ^= other;
+    }
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+    let mut block = [0u64; BLOCK_WORDS];
+    for (word, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+    }
+    block
+}
+
+/// Variable-length hash H' built from BLAKE2b
+fn h_prime(input: &[u8], len: usize) -> Vec<u8> {
+    let prefix = (len as u32).to_le_bytes();
+    if len <= 64 {
+        let mut hasher = Blake2b::new(len);
+        hasher.update(&prefix);
+        hasher.update(input);
+        return hasher.finalize();
+    }
+    let mut out = Vec::with_capacity(len);
+    let mut hasher = Blake2b::new(64);
+    hasher.update(&prefix);
+    hasher.update(input);
+    let mut v = hasher.finalize();
+    while len - out.len() > 64 {
+        out.extend_from_slice(&v[..32]);
+        let mut hasher = Blake2b::new((len - out.len()).min(64));
+        hasher.update(&v);
+        v = hasher.finalize();
+    }
+    out.extend_from_slice(&v);
+    out
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Unkeyed BLAKE2b (RFC 7693) with 1 to 64 bytes of output
+pub struct Blake2b {
+    h: [u64; 8],
+    counter: u128,
+    buffer: [u8; 128],
+    buffered: usize,
+    out_len: usize,
+}
+
+impl Blake2b {
+    pub fn new(out_len: usize) -> Self {
+        let out_len = out_len.clamp(1, 64);
+        let mut h = BLAKE2B_IV;
+        h[0] ^= 0x0101_0000 ^ out_len as u64;
+        Self {
+            h,
+            counter: 0,
+            buffer: [0; 128],
+            buffered: 0,
+            out_len,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // The last block is compressed by `finalize`, so a full buffer
+            // is only flushed once more input arrives
+            if self.buffered == 128 {
+                self.counter += 128;
+                let block = self.buffer;
+                self.compress(&block, false);
+                self.buffered = 0;
+            }
+            let take = (128 - self.buffered).min(data.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+            self.buffered += take;
+            data = &data[take..];
+        }
+    }
+
+    pub fn finalize(mut self) -> Vec<u8> {
+        self.counter += self.buffered as u128;
+        self.buffer[self.buffered..].fill(0);
+        let block = self.buffer;
+        self.compress(&block, true);
+        self.h
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .take(self.out_len)
+            .collect()
+    }
+
+    fn compress(&mut self, block: &[u8; 128], last: bool) {
+        let mut m = [0u64; 16];
+        for (word, chunk) in m.iter_mut().zip(block.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+        }
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(&self.h);
+        v[8..].copy_from_slice(&BLAKE2B_IV);
+        v[12] ^= self.counter as u64;
+        v[13] ^= (self.counter >> 64) as u64;
+        if last {
+            v[14] = !v[14];
+        }
+        for round in 0..12 {
+            let s = &SIGMA[round % 10];
+            let mut mix = |a: usize, b: usize, c: usize, d: usize, x: u64, y: u64| {
+                v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+                v[d] = (v[d] ^ v[a]).rotate_right(32);
+                v[c] = v[c].wrapping_add(v[d]);
+                v[b] = (v[b] ^ v[c]).rotate_right(24);
+                v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+                v[d] = (v[d] ^ v[a]).rotate_right(16);
+                v[c] = v[c].wrapping_add(v[d]);
+                v[b] = (v[b] ^ v[c]).rotate_right(63);
+            };
+            mix(0, 4, 8, 12, m[s[0]], m[s[1]]);
+            mix(1, 5, 9, 13, m[s[2]], m[s[3]]);
+            mix(2, 6, 10, 14, m[s[4]], m[s[5]]);
+            mix(3, 7, 11, 15, m[s[6]], m[s[7]]);
+            mix(0, 5, 10, 15, m[s[8]], m[s[9]]);
+            mix(1, 6, 11, 12, m[s[10]], m[s[11]]);
+            mix(2, 7, 8, 13, m[s[12]], m[s[13]]);
+            mix(3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+        for i in 0..8 {
+            self.h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+}
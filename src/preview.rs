@@ -0,0 +1,913 @@
+// SPDX-License-Identifier: MIT
+
+//! In-browser previews for Markdown and text files
+//!
+//! `GET /_preview/<path>` (also reachable as `/_irondrop/preview/<path>`)
+//! renders Markdown files to HTML and shows any other text file with line
+//! numbers, wrapped in the regular page template. Files that are not text are
+//! redirected to their normal download URL, so relative images and links
+//! inside a rendered README keep working. Audio and video files get an HTML5
+//! player that streams the file itself, with seeking through range requests.
+//!
+//! The Markdown renderer is intentionally small: headings, paragraphs,
+//! emphasis, code spans and fences, lists (including task lists), block
+//! quotes, tables, rules, links and images. Raw HTML in the source is escaped,
+//! never passed through, and links with script-capable schemes are dropped.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Request, Response, ResponseBody};
+use crate::templates::TemplateEngine;
+use log::debug;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+/// Namespaced preview route
+pub const PREVIEW_ROUTE: &str = "/_irondrop/preview/";
+
+/// Short preview route linked from the UI
+pub const PREVIEW_SHORT_ROUTE: &str = "/_preview/";
+
+/// Only the first part of very large files is rendered
+const MAX_PREVIEW_BYTES: u64 = 1024 * 1024;
+
+/// Serve a preview page for a file inside `base_dir`
+pub fn handle_preview_request(
+    request: &Request,
+    cli: &Cli,
+    base_dir: &Path,
+) -> Result<Response, AppError> {
+    let path_only = request.path.as_str();
+    let relative = path_only
+        .strip_prefix(PREVIEW_ROUTE)
+        .or_else(|| path_only.strip_prefix(PREVIEW_SHORT_ROUTE))
+        .unwrap_or("")
+        .trim_start_matches('/');
+
+    let file_path = crate::webdav::resolve_request_path(base_dir, relative)?;
+    if !file_path.is_file()
+        || crate::fs::ignore_rules()
+            .excludes(file_path.strip_prefix(base_dir).unwrap_or(&file_path))
+    {
+        return Err(AppError::NotFound);
+    }
+    if !cli
+        .allowed_extension_patterns()?
+        .iter()
+        .any(|p| p.matches_path(&file_path))
+    {
+        debug!(
+            "Preview rejected, extension not allowed: {}",
+            file_path.display()
+        );
+        return Err(AppError::Forbidden);
+    }
+
+    let raw_href = crate::templates::prefixed(&format!(
+        "/{}",
+        crate::utils::percent_encode_path(Path::new(relative))
+    ));
+    // Audio and video get a player streaming the file with range requests
+    let content_type = crate::response::get_file_content_type(&file_path, Some(cli));
+    if crate::response::is_media_type(&content_type.content_type) && !content_type.attachment {
+        let html = TemplateEngine::global().render_media_page(
+            &format!("/{relative}"),
+            &raw_href,
+            &content_type.content_type,
+        )?;
+        return Ok(html_response(html));
+    }
+
+    let Some((text, truncated)) = read_text(&file_path)? else {
+        debug!(
+            "Preview of binary file {}, redirecting",
+            file_path.display()
+        );
+        let mut headers = HashMap::new();
+        headers.insert("Location".to_string(), raw_href);
+        return Ok(Response {
+            status_code: 302,
+            status_text: "Found".to_string(),
+            headers,
+            body: ResponseBody::Text(String::new()),
+        });
+    };
+
+    let markdown = is_markdown(&file_path);
+    let body = if markdown {
+        render_markdown(&text)
+    } else {
+        render_text(&text)
+    };
+    // Accounts that may write get a link to the editor
+    let edit_href = (crate::edit::is_enabled()
+        && !truncated
+        && !crate::read_only::is_enabled()
+        && crate::users::request_role(request) >= crate::users::Role::Upload)
+        .then(|| {
+            crate::templates::prefixed(&format!(
+                "{}{}",
+                crate::edit::EDIT_ROUTE,
+                crate::utils::percent_encode_path(Path::new(relative))
+            ))
+        });
+    let html = TemplateEngine::global().render_preview_page(
+        &format!("/{relative}"),
+        &raw_href,
+        edit_href.as_deref(),
+        &body,
+        markdown,
+        truncated,
+    )?;
+    Ok(html_response(html))
+}
+
+fn html_response(html: String) -> Response {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "text/html; charset=utf-8".to_string(),
+    );
+    Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(html),
+    }
+}
+
+/// Read up to [`MAX_PREVIEW_BYTES`]; `None` when the file looks binary
+fn read_text(path: &Path) -> Result<Option<(String, bool)>, AppError> {
+    let file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut bytes = Vec::with_capacity(size.min(MAX_PREVIEW_BYTES) as usize);
+    file.take(MAX_PREVIEW_BYTES).read_to_end(&mut bytes)?;
+
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return Ok(None);
+    }
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+    Ok(Some((text, size > MAX_PREVIEW_BYTES)))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "md" | "markdown" | "mdown" | "mkd"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Render plain text as a `<pre>` block with anchored line numbers
+pub fn render_text(input: &str) -> String {
+    let mut out = String::from(r#"<pre class="preview-text"><code>"#);
+    for (index, line) in input.lines().enumerate() {
+        let n = index + 1;
+        out.push_str(&format!(
+            r##"<span class="line" id="L{n}"><a class="line-number" href="#L{n}">{n}</a>{}</span>"##,
+            html_escape(line)
+        ));
+        out.push('\n');
+    }
+    out.push_str("</code></pre>");
+    out
+}
+
+/// Render Markdown to HTML; all source text is escaped
+pub fn render_markdown(input: &str) -> String {
+    // NUL is used internally to mark hard line breaks
+    let text = input
+        .replace('\0', "\u{FFFD}")
+        .replace("\r\n", "\n")
+        .replace('\t', "    ");
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out = String::new();
+    render_blocks(&lines, &mut out);
+    out
+}
+
+fn render_blocks(lines: &[&str], out: &mut String) {
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if indent_of(line) >= 4 {
+            i = render_indented_code(lines, i, out);
+        } else if let Some(fence) = fence_marker(trimmed) {
+            i = render_fenced_code(lines, i, fence, out);
+        } else if let Some((level, content)) = heading(trimmed) {
+            out.push_str(&format!(
+                "<h{level} id=\"{}\">{}</h{level}>\n",
+                slugify(content),
+                render_inline(content)
+            ));
+            i += 1;
+        } else if is_rule(trimmed) {
+            out.push_str("<hr>\n");
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let mut inner = Vec::new();
+            while i < lines.len() {
+                let Some(rest) = lines[i].trim_start().strip_prefix('>') else {
+                    break;
+                };
+                inner.push(rest.strip_prefix(' ').unwrap_or(rest));
+                i += 1;
+            }
+            out.push_str("<blockquote>\n");
+            render_blocks(&inner, out);
+            out.push_str("</blockquote>\n");
+        } else if list_marker(line).is_some() {
+            i = render_list(lines, i, out);
+        } else if is_table_start(lines, i) {
+            i = render_table(lines, i, out);
+        } else {
+            let start = i;
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && !starts_block(lines, i) {
+                i += 1;
+            }
+            out.push_str("<p>");
+            out.push_str(&render_inline(&join_paragraph(&lines[start..i])));
+            out.push_str("</p>\n");
+        }
+    }
+}
+
+/// Whether line `i` starts a block that interrupts a paragraph
+fn starts_block(lines: &[&str], i: usize) -> bool {
+    let trimmed = lines[i].trim_start();
+    fence_marker(trimmed).is_some()
+        || heading(trimmed).is_some()
+        || is_rule(trimmed)
+        || trimmed.starts_with('>')
+        || list_marker(lines[i]).is_some()
+        || is_table_start(lines, i)
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Join paragraph lines, marking hard breaks (two trailing spaces or `\`)
+fn join_paragraph(lines: &[&str]) -> String {
+    let mut joined = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let line = line.trim_start();
+        let last = index + 1 == lines.len();
+        if !last && line.ends_with("  ") {
+            joined.push_str(line.trim_end());
+            joined.push('\0');
+        } else if !last && line.ends_with('\\') && !line.ends_with("\\\\") {
+            joined.push_str(&line[..line.len() - 1]);
+            joined.push('\0');
+        } else {
+            joined.push_str(line.trim_end());
+            if !last {
+                joined.push('\n');
+            }
+        }
+    }
+    joined
+}
+
+fn fence_marker(trimmed: &str) -> Option<&'static str> {
+    if trimmed.starts_with("```") {
+        Some("```")
+    } else if trimmed.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+fn render_fenced_code(lines: &[&str], start: usize, fence: &str, out: &mut String) -> usize {
+    let info = lines[start].trim_start()[fence.len()..]
+        .trim_start_matches(&fence[..1])
+        .trim();
+    let language = info.split_whitespace().next().unwrap_or("");
+    let mut code = String::new();
+    let mut i = start + 1;
+    while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+        code.push_str(lines[i]);
+        code.push('\n');
+        i += 1;
+    }
+    let class = if language.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"language-{}\"", html_escape(language))
+    };
+    out.push_str(&format!(
+        "<pre><code{class}>{}</code></pre>\n",
+        html_escape(&code)
+    ));
+    // Skip the closing fence (an unclosed fence runs to the end)
+    i + 1
+}
+
+fn render_indented_code(lines: &[&str], start: usize, out: &mut String) -> usize {
+    let mut end = start;
+    let mut i = start;
+    while i < lines.len() && (lines[i].trim().is_empty() || indent_of(lines[i]) >= 4) {
+        if !lines[i].trim().is_empty() {
+            end = i + 1;
+        }
+        i += 1;
+    }
+    let code: String = lines[start..end]
+        .iter()
+        .map(|line| format!("{}\n", line.get(4..).unwrap_or("")))
+        .collect();
+    out.push_str(&format!("<pre><code>{}</code></pre>\n", html_escape(&code)));
+    end
+}
+
+fn heading(trimmed: &str) -> Option<(usize, &str)> {
+    let level = trimmed.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    // Optional closing sequence: "## Title ##"
+    let content = rest.trim();
+    let content = match content.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with(' ') => stripped.trim_end(),
+        _ => content,
+    };
+    Some((level, content))
+}
+
+fn is_rule(trimmed: &str) -> bool {
+    let compact: Vec<char> = trimmed.chars().filter(|c| *c != ' ').collect();
+    compact.len() >= 3
+        && matches!(compact[0], '-' | '*' | '_')
+        && compact.iter().all(|c| *c == compact[0])
+}
+
+/// GitHub-style anchor id for a heading
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            slug.push(c);
+        } else if c == ' ' {
+            slug.push('-');
+        }
+    }
+    slug
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ListMarker {
+    ordered: bool,
+    start: u64,
+    /// Column where the item's content begins
+    content_offset: usize,
+}
+
+fn list_marker(line: &str) -> Option<ListMarker> {
+    let indent = indent_of(line);
+    if indent >= 4 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let bytes = rest.as_bytes();
+    let (ordered, start, marker_len) = match bytes.first()? {
+        b'-' | b'*' | b'+' => (false, 1, 1),
+        b'0'..=b'9' => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            if digits > 9 || !matches!(bytes.get(digits), Some(b'.' | b')')) {
+                return None;
+            }
+            (true, rest[..digits].parse().ok()?, digits + 1)
+        }
+        _ => return None,
+    };
+    match bytes.get(marker_len) {
+        None => {}
+        Some(b' ') => {}
+        Some(_) => return None,
+    }
+    if is_rule(rest) {
+        return None;
+    }
+    Some(ListMarker {
+        ordered,
+        start,
+        content_offset: indent + marker_len + 1,
+    })
+}
+
+fn render_list(lines: &[&str], start: usize, out: &mut String) -> usize {
+    let first = list_marker(lines[start]).expect("caller checked for a list marker");
+    if !first.ordered {
+        out.push_str("<ul>\n");
+    } else if first.start == 1 {
+        out.push_str("<ol>\n");
+    } else {
+        out.push_str(&format!("<ol start=\"{}\">\n", first.start));
+    }
+
+    let mut i = start;
+    while i < lines.len() {
+        let Some(marker) = list_marker(lines[i]) else {
+            break;
+        };
+        if marker.ordered != first.ordered {
+            break;
+        }
+
+        let mut item: Vec<&str> = vec![lines[i].get(marker.content_offset..).unwrap_or("")];
+        let mut loose = false;
+        i += 1;
+        while i < lines.len() {
+            let line = lines[i];
+            if line.trim().is_empty() {
+                // A blank line continues the item only if indented content follows
+                let next = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+                match next {
+                    Some(j) if indent_of(lines[j]) >= marker.content_offset => {
+                        item.extend(std::iter::repeat_n("", j - i));
+                        loose = true;
+                        i = j;
+                    }
+                    _ => break,
+                }
+            } else if indent_of(line) >= marker.content_offset {
+                item.push(&line[marker.content_offset..]);
+                i += 1;
+            } else if starts_block(lines, i) {
+                break;
+            } else {
+                // Lazy paragraph continuation
+                item.push(line.trim_start());
+                i += 1;
+            }
+        }
+
+        out.push_str("<li>");
+        let (checkbox, first_line) = task_checkbox(item[0]);
+        item[0] = first_line;
+        out.push_str(checkbox);
+        if loose {
+            out.push('\n');
+            render_blocks(&item, out);
+        } else {
+            // Tight item: leading paragraph inline, nested blocks after it
+            let split = (1..item.len())
+                .find(|&j| starts_block(&item, j))
+                .unwrap_or(item.len());
+            out.push_str(&render_inline(&join_paragraph(&item[..split])));
+            if split < item.len() {
+                out.push('\n');
+                render_blocks(&item[split..], out);
+            }
+        }
+        out.push_str("</li>\n");
+
+        // Blank lines between sibling items
+        let next = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+        match next {
+            Some(j)
+                if j > i && list_marker(lines[j]).is_some_and(|m| m.ordered == first.ordered) =>
+            {
+                i = j;
+            }
+            _ => {}
+        }
+    }
+
+    out.push_str(if first.ordered { "</ol>\n" } else { "</ul>\n" });
+    i
+}
+
+fn task_checkbox(line: &str) -> (&'static str, &str) {
+    if let Some(rest) = line.strip_prefix("[ ] ") {
+        (r#"<input type="checkbox" disabled> "#, rest)
+    } else if let Some(rest) = line
+        .strip_prefix("[x] ")
+        .or_else(|| line.strip_prefix("[X] "))
+    {
+        (r#"<input type="checkbox" checked disabled> "#, rest)
+    } else {
+        ("", line)
+    }
+}
+
+fn split_table_row(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let trimmed = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let trimmed = match trimmed.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => trimmed,
+    };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = trimmed.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn table_alignments(line: &str) -> Option<Vec<Option<&'static str>>> {
+    if !line.contains('-') {
+        return None;
+    }
+    split_table_row(line)
+        .iter()
+        .map(|cell| {
+            let left = cell.starts_with(':');
+            let right = cell.ends_with(':');
+            let dashes = cell.trim_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (left, right) {
+                (true, true) => Some("center"),
+                (false, true) => Some("right"),
+                (true, false) => Some("left"),
+                (false, false) => None,
+            })
+        })
+        .collect()
+}
+
+fn is_table_start(lines: &[&str], i: usize) -> bool {
+    i + 1 < lines.len()
+        && lines[i].contains('|')
+        && lines[i + 1].contains('|')
+        && table_alignments(lines[i + 1])
+            .is_some_and(|align| align.len() == split_table_row(lines[i]).len())
+}
+
+fn render_table(lines: &[&str], start: usize, out: &mut String) -> usize {
+    let header = split_table_row(lines[start]);
+    let alignments = table_alignments(lines[start + 1]).unwrap_or_default();
+    let cell = |tag: &str, index: usize, content: &str| {
+        let style = alignments
+            .get(index)
+            .copied()
+            .flatten()
+            .map(|a| format!(" style=\"text-align: {a}\""))
+            .unwrap_or_default();
+        format!("<{tag}{style}>{}</{tag}>", render_inline(content))
+    };
+
+    out.push_str("<table>\n<thead>\n<tr>");
+    for (index, content) in header.iter().enumerate() {
+        out.push_str(&cell("th", index, content));
+    }
+    out.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    let mut i = start + 2;
+    while i < lines.len() && !lines[i].trim().is_empty() && lines[i].contains('|') {
+        let row = split_table_row(lines[i]);
+        out.push_str("<tr>");
+        for index in 0..header.len() {
+            out.push_str(&cell(
+                "td",
+                index,
+                row.get(index).map_or("", String::as_str),
+            ));
+        }
+        out.push_str("</tr>\n");
+        i += 1;
+    }
+    out.push_str("</tbody>\n</table>\n");
+    i
+}
+
+/// Render inline Markdown (emphasis, code, links, images) with escaping
+fn render_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if chars.get(i + 1).is_some_and(|n| n.is_ascii_punctuation()) => {
+                push_escaped(&mut out, chars[i + 1]);
+                i += 2;
+            }
+            '\0' => {
+                out.push_str("<br>\n");
+                i += 1;
+            }
+            '`' => {
+                let run = run_length(&chars, i, '`');
+                match find_run(&chars, i + run, '`', run) {
+                    Some(close) => {
+                        let code: String = chars[i + run..close].iter().collect();
+                        let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                            Some(inner) if !inner.trim().is_empty() => inner.to_string(),
+                            _ => code,
+                        };
+                        out.push_str(&format!("<code>{}</code>", html_escape(&code)));
+                        i = close + run;
+                    }
+                    None => {
+                        out.push_str(&"`".repeat(run));
+                        i += run;
+                    }
+                }
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => match parse_link(&chars, i + 1) {
+                Some((label, url, end)) => {
+                    out.push_str(&format!(
+                        "<img src=\"{}\" alt=\"{}\">",
+                        html_escape(&safe_url(&url)),
+                        html_escape(&label)
+                    ));
+                    i = end;
+                }
+                None => {
+                    out.push('!');
+                    i += 1;
+                }
+            },
+            '[' => match parse_link(&chars, i) {
+                Some((label, url, end)) => {
+                    out.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        html_escape(&safe_url(&url)),
+                        render_inline(&label)
+                    ));
+                    i = end;
+                }
+                None => {
+                    out.push('[');
+                    i += 1;
+                }
+            },
+            '<' => {
+                let close = chars[i + 1..].iter().position(|&c| c == '>');
+                let target: Option<String> =
+                    close.map(|len| chars[i + 1..i + 1 + len].iter().collect());
+                match target {
+                    Some(url)
+                        if !url.contains(char::is_whitespace)
+                            && ["http://", "https://", "mailto:"]
+                                .iter()
+                                .any(|scheme| url.starts_with(scheme)) =>
+                    {
+                        let shown = url.strip_prefix("mailto:").unwrap_or(&url);
+                        out.push_str(&format!(
+                            "<a href=\"{}\">{}</a>",
+                            html_escape(&url),
+                            html_escape(shown)
+                        ));
+                        i += url.chars().count() + 2;
+                    }
+                    _ => {
+                        out.push_str("&lt;");
+                        i += 1;
+                    }
+                }
+            }
+            '*' | '_' | '~' => {
+                let run = run_length(&chars, i, c);
+                let word_before = i > 0 && chars[i - 1].is_alphanumeric();
+                let usable = match c {
+                    '~' => run == 2,
+                    '_' => run <= 3 && !word_before,
+                    _ => run <= 3,
+                };
+                let opens = chars.get(i + run).is_some_and(|n| !n.is_whitespace());
+                let close = if usable && opens {
+                    find_emphasis_close(&chars, i + run, c, run)
+                } else {
+                    None
+                };
+                match close {
+                    Some(close) => {
+                        let inner =
+                            render_inline(&chars[i + run..close].iter().collect::<String>());
+                        let (open_tags, close_tags) = match (c, run) {
+                            ('~', _) => ("<del>", "</del>"),
+                            (_, 1) => ("<em>", "</em>"),
+                            (_, 2) => ("<strong>", "</strong>"),
+                            _ => ("<strong><em>", "</em></strong>"),
+                        };
+                        out.push_str(open_tags);
+                        out.push_str(&inner);
+                        out.push_str(close_tags);
+                        i = close + run;
+                    }
+                    None => {
+                        for _ in 0..run {
+                            out.push(c);
+                        }
+                        i += run;
+                    }
+                }
+            }
+            _ => {
+                push_escaped(&mut out, c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while(|&&x| x == c).count()
+}
+
+/// Find the next run of exactly `len` copies of `c` at or after `from`
+fn find_run(chars: &[char], from: usize, c: char, len: usize) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        if chars[j] == c {
+            let run = run_length(chars, j, c);
+            if run == len {
+                return Some(j);
+            }
+            j += run;
+        } else {
+            j += 1;
+        }
+    }
+    None
+}
+
+fn find_emphasis_close(chars: &[char], from: usize, c: char, len: usize) -> Option<usize> {
+    let mut j = from;
+    while let Some(close) = find_run(chars, j, c, len) {
+        let after_word = chars.get(close + len).is_some_and(|n| n.is_alphanumeric());
+        if close > from && !chars[close - 1].is_whitespace() && !(c == '_' && after_word) {
+            return Some(close);
+        }
+        j = close + len;
+    }
+    None
+}
+
+/// Parse `[label](url "title")` starting at the `[`; returns label, url and end index
+fn parse_link(chars: &[char], open: usize) -> Option<(String, String, usize)> {
+    let mut depth = 0;
+    let mut close = None;
+    for (j, &c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let mut depth = 0;
+    let mut end = None;
+    for (j, &c) in chars.iter().enumerate().skip(close + 1) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+    let label: String = chars[open + 1..close].iter().collect();
+    let target: String = chars[close + 2..end].iter().collect();
+    // Drop an optional title: (url "title")
+    let url = target.split_whitespace().next().unwrap_or("");
+    let url = url
+        .strip_prefix('<')
+        .and_then(|u| u.strip_suffix('>'))
+        .unwrap_or(url);
+    Some((label, url.to_string(), end + 1))
+}
+
+/// Neutralise URLs with schemes that can run script
+fn safe_url(url: &str) -> String {
+    let lower = url.trim().to_ascii_lowercase();
+    let scheme_end = lower.find(':');
+    let path_start = lower.find(['/', '?', '#']);
+    match scheme_end {
+        Some(colon) if path_start.is_none_or(|p| colon < p) => {
+            let scheme = &lower[..colon];
+            if matches!(scheme, "http" | "https" | "mailto" | "ftp") {
+                url.trim().to_string()
+            } else {
+                "#".to_string()
+            }
+        }
+        _ => url.trim().to_string(),
+    }
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#x27;"),
+        _ => out.push(c),
+    }
+}
+
+fn html_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        push_escaped(&mut out, c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_blocks() {
+        let html = render_markdown(
+            "# Title\n\nSome *text* and **bold**.\n\n- one\n- two\n  - nested\n\n1. first\n2. second\n\n> quoted\n\n---\n```rust\nfn main() {}\n```\n",
+        );
+        assert!(html.contains(r#"<h1 id="title">Title</h1>"#));
+        assert!(html.contains("<p>Some <em>text</em> and <strong>bold</strong>.</p>"));
+        assert!(
+            html.contains(
+                "<ul>\n<li>one</li>\n<li>two\n<ul>\n<li>nested</li>\n</ul>\n</li>\n</ul>"
+            )
+        );
+        assert!(html.contains("<ol>\n<li>first</li>\n<li>second</li>\n</ol>"));
+        assert!(html.contains("<blockquote>\n<p>quoted</p>\n</blockquote>"));
+        assert!(html.contains("<hr>"));
+        assert!(html.contains(r#"<pre><code class="language-rust">fn main() {}"#));
+    }
+
+    #[test]
+    fn test_markdown_inline_and_tables() {
+        let html = render_markdown(
+            "Use `a < b`, snake_case_name, ~~old~~ and [docs](guide.md).\n\n| Name | Size |\n|:-----|-----:|\n| a.txt | 1 KB |\n\n- [x] done\n- [ ] todo\n",
+        );
+        assert!(html.contains("<code>a &lt; b</code>"));
+        assert!(html.contains("snake_case_name"));
+        assert!(html.contains("<del>old</del>"));
+        assert!(html.contains(r#"<a href="guide.md">docs</a>"#));
+        assert!(html.contains(
+            r#"<th style="text-align: left">Name</th><th style="text-align: right">Size</th>"#
+        ));
+        assert!(html.contains(r#"<td style="text-align: left">a.txt</td>"#));
+        assert!(html.contains(r#"<li><input type="checkbox" checked disabled> done</li>"#));
+    }
+
+    #[test]
+    fn test_markdown_escapes_html_and_scripts() {
+        let html = render_markdown(
+            "<script>alert(1)</script>\n\n[x](javascript:alert(1)) ![i](data:text/html,hi) [ok](https://example.com/?a=1&b=2)",
+        );
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains(r##"<a href="#">x</a>"##));
+        assert!(html.contains(r##"<img src="#" alt="i">"##));
+        assert!(html.contains(r#"<a href="https://example.com/?a=1&amp;b=2">ok</a>"#));
+    }
+
+    #[test]
+    fn test_text_line_numbers() {
+        let html = render_text("first\n<second>");
+        assert!(html.contains(
+            r##"<span class="line" id="L1"><a class="line-number" href="#L1">1</a>first</span>"##
+        ));
+        assert!(html.contains(r##"href="#L2">2</a>&lt;second&gt;</span>"##));
+    }
+}
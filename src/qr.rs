@@ -0,0 +1,681 @@
+// SPDX-License-Identifier: MIT
+
+//! QR code generation for the share URL
+//!
+//! A compact encoder for byte-mode QR codes (ISO/IEC 18004) at error
+//! correction level M, versions 1-40. It is only meant for URLs, so it skips
+//! the numeric/alphanumeric modes and always picks the smallest version that
+//! fits. [`QrCode::to_svg`] renders the code for the `/_qr` page and
+//! [`QrCode::to_terminal`] for the startup banner.
+//!
+//! `GET /_qr` (also `/_irondrop/qr`) shows the code for the server's own URL
+//! so a phone can open the share by scanning the screen; `?format=svg` returns
+//! just the image. Loopback hosts are swapped for the LAN address, since a
+//! phone cannot reach `localhost` on the machine running IronDrop.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Request, Response, ResponseBody};
+use crate::templates::TemplateEngine;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// Namespaced QR code route
+pub const QR_ROUTE: &str = "/_irondrop/qr";
+
+/// Short QR code route, easy to type on the machine running the server
+pub const QR_SHORT_ROUTE: &str = "/_qr";
+
+/// Error correction codewords per block at level M, indexed by version
+const ECC_CODEWORDS_PER_BLOCK: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+
+/// Error correction blocks at level M, indexed by version
+const NUM_ERROR_CORRECTION_BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+
+/// Format information bits for level M
+const ECL_M_FORMAT_BITS: u32 = 0;
+
+/// Quiet zone around the symbol, in modules
+const QUIET_ZONE: usize = 4;
+
+/// A finished QR symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode `data` in byte mode; `None` if it exceeds version 40
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=40).find(|&version| {
+            let bits = 4 + char_count_bits(version) + data.len() * 8;
+            bits <= num_data_codewords(version) * 8
+        })?;
+
+        // Segment bits: byte mode indicator, length, payload
+        let mut bits = BitBuffer::default();
+        bits.append(0b0100, 4);
+        bits.append(data.len() as u32, char_count_bits(version));
+        for &byte in data {
+            bits.append(u32::from(byte), 8);
+        }
+
+        // Terminator, byte alignment and alternating pad bytes
+        let capacity = num_data_codewords(version) * 8;
+        bits.append(0, (capacity - bits.len()).min(4));
+        bits.append(0, (8 - bits.len() % 8) % 8);
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if bits.len() >= capacity {
+                break;
+            }
+            bits.append(pad, 8);
+        }
+        let codewords = add_ecc_and_interleave(&bits.into_bytes(), version);
+
+        let size = version * 4 + 17;
+        let mut qr = Self {
+            version,
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        };
+        qr.draw_function_patterns();
+        qr.draw_codewords(&codewords);
+
+        // Pick the mask with the lowest penalty
+        let mut best = (0, i32::MAX);
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(mask);
+            let penalty = qr.penalty_score();
+            if penalty < best.1 {
+                best = (mask, penalty);
+            }
+            qr.apply_mask(mask); // XOR again to undo
+        }
+        qr.apply_mask(best.0);
+        qr.draw_format_bits(best.0);
+        Some(qr)
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Modules per side, without the quiet zone
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at column `x`, row `y` is dark
+    pub fn module(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// Standalone SVG image, one user unit per module
+    pub fn to_svg(&self) -> String {
+        let full = self.size + QUIET_ZONE * 2;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.module(x, y) {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+                }
+            }
+        }
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {full} {full}" shape-rendering="crispEdges"><rect width="100%" height="100%" fill="#ffffff"/><path d="{path}" fill="#000000"/></svg>"##
+        )
+    }
+
+    /// Text rendering using half-block characters, two module rows per line
+    pub fn to_terminal(&self) -> String {
+        let dark = |x: isize, y: isize| x >= 0 && y >= 0 && self.module(x as usize, y as usize);
+        let margin = QUIET_ZONE as isize;
+        let end = self.size as isize + margin;
+        let mut out = String::new();
+        let mut y = -margin;
+        while y < end {
+            for x in -margin..end {
+                // Light terminal cells are drawn as blocks so dark-on-light
+                // scanning works on dark terminal themes too
+                out.push(match (dark(x, y), dark(x, y + 1)) {
+                    (false, false) => '█',
+                    (false, true) => '▀',
+                    (true, false) => '▄',
+                    (true, true) => ' ',
+                });
+            }
+            out.push('\n');
+            y += 2;
+        }
+        out
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        let index = y * self.size + x;
+        self.modules[index] = dark;
+        self.is_function[index] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        self.draw_finder_pattern(3, 3);
+        self.draw_finder_pattern(size - 4, 3);
+        self.draw_finder_pattern(3, size - 4);
+
+        let positions = alignment_pattern_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Skip the three corners occupied by finder patterns
+                let corner = (i == 0 || i == last) && (j == 0 || j == last);
+                if corner && !(i == last && j == last) {
+                    continue;
+                }
+                self.draw_alignment_pattern(x, y);
+            }
+        }
+
+        // Reserve the format areas; real bits are drawn once the mask is known
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    fn draw_finder_pattern(&mut self, x: usize, y: usize) {
+        for dy in -4isize..=4 {
+            for dx in -4isize..=4 {
+                let (xx, yy) = (x as isize + dx, y as isize + dy);
+                if (0..self.size as isize).contains(&xx) && (0..self.size as isize).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment_pattern(&mut self, x: usize, y: usize) {
+        for dy in -2isize..=2 {
+            for dx in -2isize..=2 {
+                let distance = dx.abs().max(dy.abs());
+                self.set_function(
+                    (x as isize + dx) as usize,
+                    (y as isize + dy) as usize,
+                    distance != 1,
+                );
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        let data = (ECL_M_FORMAT_BITS << 3) | mask;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | rem) ^ 0x5412;
+        let bit = |i: u32| (bits >> i) & 1 != 0;
+
+        // Copy next to the top-left finder
+        for i in 0..=5 {
+            self.set_function(8, i as usize, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i as usize, 8, bit(i));
+        }
+
+        // Copy split between the other two finders
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function(size - 1 - i as usize, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i as usize, bit(i));
+        }
+        self.set_function(8, size - 8, true); // always-dark module
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let version = self.version as u32;
+        let mut rem = version;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = (version << 12) | rem;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    /// Place data in the zigzag order, skipping function modules
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let total_bits = codewords.len() * 8;
+        let mut i = 0;
+        let mut right = size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    let index = y * size + x;
+                    if !self.is_function[index] && i < total_bits {
+                        self.modules[index] = (codewords[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.is_function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    /// Mask penalty following the four rules of ISO/IEC 18004 §7.8.3
+    fn penalty_score(&self) -> i32 {
+        let size = self.size;
+        let mut penalty = 0;
+
+        for horizontal in [true, false] {
+            let at = |line: usize, i: usize| {
+                if horizontal {
+                    self.module(i, line)
+                } else {
+                    self.module(line, i)
+                }
+            };
+            for line in 0..size {
+                // Rule 1: runs of five or more modules of one colour
+                let mut run = 1;
+                for i in 1..size {
+                    if at(line, i) == at(line, i - 1) {
+                        run += 1;
+                        if run == 5 {
+                            penalty += 3;
+                        } else if run > 5 {
+                            penalty += 1;
+                        }
+                    } else {
+                        run = 1;
+                    }
+                }
+                // Rule 3: finder-like 1:1:3:1:1 patterns next to four light modules
+                const PATTERN: [bool; 7] = [true, false, true, true, true, false, true];
+                for i in 0..size.saturating_sub(6) {
+                    if (0..7).all(|k| at(line, i + k) == PATTERN[k]) {
+                        let light_before = (i.saturating_sub(4)..i).all(|k| !at(line, k)) && i >= 4;
+                        let light_after = i + 11 <= size && (i + 7..i + 11).all(|k| !at(line, k));
+                        if light_before || light_after {
+                            penalty += 40;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Rule 2: 2x2 blocks of one colour
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.module(x, y);
+                if color == self.module(x + 1, y)
+                    && color == self.module(x, y + 1)
+                    && color == self.module(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        // Rule 4: balance of dark and light modules
+        let dark = self.modules.iter().filter(|&&dark| dark).count() as i32;
+        let total = (size * size) as i32;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        penalty + k * 10
+    }
+}
+
+/// Serve the QR code page, or the bare SVG with `?format=svg`
+pub fn handle_qr_request(request: &Request, cli: Option<&Cli>) -> Result<Response, AppError> {
+    let https = cli.is_some_and(|cli| cli.ssl_cert.is_some() && cli.ssl_key.is_some())
+        || request
+            .headers
+            .get("x-forwarded-proto")
+            .is_some_and(|proto| proto.eq_ignore_ascii_case("https"));
+    let host = request
+        .headers
+        .get("host")
+        .map(|host| reachable_host(host))
+        .unwrap_or_else(|| "localhost".to_string());
+    let url = share_url(
+        if https { "https" } else { "http" },
+        &host,
+        &crate::templates::base_path(),
+    );
+    let qr = QrCode::encode(url.as_bytes()).ok_or_else(|| {
+        AppError::InternalServerError("Server URL is too long for a QR code".to_string())
+    })?;
+
+    let (body, content_type) = if request
+        .query_params()
+        .get("format")
+        .is_some_and(|format| format == "svg")
+    {
+        (qr.to_svg(), "image/svg+xml")
+    } else {
+        (
+            TemplateEngine::global().render_qr_page(&url, &qr.to_svg())?,
+            "text/html; charset=utf-8",
+        )
+    };
+
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), content_type.to_string());
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(body),
+    })
+}
+
+/// URL of the share root for `host` (which may carry a port)
+pub fn share_url(scheme: &str, host: &str, base_path: &str) -> String {
+    format!("{scheme}://{host}{}/", base_path.trim_end_matches('/'))
+}
+
+/// Host to advertise for a server bound to `addr`, preferring the LAN address
+pub fn advertised_host(addr: SocketAddr) -> String {
+    let ip = match addr.ip() {
+        ip if ip.is_unspecified() || ip.is_loopback() => {
+            crate::mdns::lan_address().map(IpAddr::V4).unwrap_or(ip)
+        }
+        ip => ip,
+    };
+    SocketAddr::new(ip, addr.port()).to_string()
+}
+
+/// Replace a loopback `Host` header value with the LAN address, keeping the port
+fn reachable_host(host: &str) -> String {
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') && !name.ends_with(':') => (name, Some(port)),
+        _ => (host, None),
+    };
+    let trimmed = name.trim_start_matches('[').trim_end_matches(']');
+    let loopback = trimmed.eq_ignore_ascii_case("localhost")
+        || trimmed.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback());
+    match (loopback, crate::mdns::lan_address()) {
+        (true, Some(lan)) => match port {
+            Some(port) => format!("{lan}:{port}"),
+            None => lan.to_string(),
+        },
+        _ => host.to_string(),
+    }
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version <= 9 { 8 } else { 16 }
+}
+
+/// Modules available for data and error correction in a version
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let num_align = version / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize) -> usize {
+    num_raw_data_modules(version) / 8
+        - ECC_CODEWORDS_PER_BLOCK[version] * NUM_ERROR_CORRECTION_BLOCKS[version]
+}
+
+fn alignment_pattern_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let num_align = version / 7 + 2;
+    let size = version * 4 + 17;
+    let step = (version * 8 + num_align * 3 + 5) / (num_align * 4 - 4) * 2;
+    let mut positions: Vec<usize> = (0..num_align - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// Split data into blocks, append Reed-Solomon ECC and interleave
+fn add_ecc_and_interleave(data: &[u8], version: usize) -> Vec<u8> {
+    let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut blocks: Vec<Vec<u8>> = Vec::with_capacity(num_blocks);
+    let mut offset = 0;
+    for i in 0..num_blocks {
+        let len = short_block_len - ecc_len + usize::from(i >= num_short_blocks);
+        let mut block = data[offset..offset + len].to_vec();
+        offset += len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < num_short_blocks {
+            block.push(0); // placeholder so every block has the same length
+        }
+        block.extend_from_slice(&ecc);
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            // Skip the placeholders in short blocks
+            if i != short_block_len - ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((u16::from(y) >> i) & 1) * u16::from(x);
+    }
+    z as u8
+}
+
+#[derive(Default)]
+struct BitBuffer {
+    bits: Vec<bool>,
+}
+
+impl BitBuffer {
+    fn append(&mut self, value: u32, len: usize) {
+        for i in (0..len).rev() {
+            self.bits.push((value >> i) & 1 != 0);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bits
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, &bit)| byte | (u8::from(bit) << (7 - i)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_selection() {
+        // Version 1-M holds 14 bytes, version 2-M holds 26
+        assert_eq!(QrCode::encode(&[b'a'; 14]).unwrap().version(), 1);
+        assert_eq!(QrCode::encode(&[b'a'; 15]).unwrap().version(), 2);
+        let url = QrCode::encode(b"http://192.168.100.200:8080/").unwrap();
+        assert_eq!(url.version(), 3);
+        assert_eq!(url.size(), 29);
+        assert!(QrCode::encode(&vec![0u8; 3000]).is_none());
+    }
+
+    #[test]
+    fn test_capacity_tables() {
+        assert_eq!(num_data_codewords(1), 16);
+        assert_eq!(num_data_codewords(10), 216);
+        assert_eq!(num_data_codewords(40), 2334);
+        assert_eq!(alignment_pattern_positions(7), vec![6, 22, 38]);
+        assert_eq!(
+            alignment_pattern_positions(32),
+            vec![6, 34, 60, 86, 112, 138]
+        );
+    }
+
+    #[test]
+    fn test_reed_solomon_known_vector() {
+        // "HELLO WORLD" 1-M data codewords and their ECC from ISO/IEC 18004 Annex I
+        let data = [
+            0x20, 0x5B, 0x0B, 0x78, 0xD1, 0x72, 0xDC, 0x4D, 0x43, 0x40, 0xEC, 0x11, 0xEC, 0x11,
+            0xEC, 0x11,
+        ];
+        let ecc = reed_solomon_remainder(&data, &reed_solomon_divisor(10));
+        assert_eq!(
+            ecc,
+            vec![0xC4, 0x23, 0x27, 0x77, 0xEB, 0xD7, 0xE7, 0xE2, 0x5D, 0x17]
+        );
+    }
+
+    #[test]
+    fn test_finder_patterns_and_svg() {
+        let qr = QrCode::encode(b"https://example.com/").unwrap();
+        let size = qr.size();
+        for (x, y) in [(0, 0), (size - 7, 0), (0, size - 7)] {
+            assert!(qr.module(x, y) && qr.module(x + 6, y + 6) && qr.module(x + 3, y + 3));
+            assert!(!qr.module(x + 1, y + 1));
+        }
+        // The timing pattern alternates between the finders
+        assert!((8..size - 8).all(|i| qr.module(i, 6) == (i % 2 == 0)));
+
+        let svg = qr.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(&format!("viewBox=\"0 0 {0} {0}\"", size + 8)));
+        assert_eq!(qr.to_terminal().lines().count(), (size + 8).div_ceil(2));
+    }
+
+    #[test]
+    fn test_share_url_and_hosts() {
+        assert_eq!(
+            share_url("http", "10.0.0.5:8080", ""),
+            "http://10.0.0.5:8080/"
+        );
+        assert_eq!(
+            share_url("https", "files.example.com", "/webstorage/"),
+            "https://files.example.com/webstorage/"
+        );
+        assert_eq!(
+            reachable_host("files.example.com:8080"),
+            "files.example.com:8080"
+        );
+        assert_eq!(reachable_host("[fe80::1]:8080"), "[fe80::1]:8080");
+        if let Some(lan) = crate::mdns::lan_address() {
+            assert_eq!(reachable_host("localhost:8080"), format!("{lan}:8080"));
+            assert_eq!(reachable_host("127.0.0.1"), lan.to_string());
+        }
+    }
+}
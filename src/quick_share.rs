@@ -0,0 +1,344 @@
+// SPDX-License-Identifier: MIT
+
+//! `irondrop serve --once`: share one file or folder, then shut down
+//!
+//! ```text
+//! irondrop serve --once report.pdf --downloads 2 --timeout 30m
+//! ```
+//!
+//! The server answers nothing but the URL of the shared item, which is
+//! printed with a QR code: a file is served at `/<name>`, a folder as an
+//! archive at `/?download=tar.gz`. Every other request gets a 404. The
+//! server stops once the item has been downloaded `--downloads` times, or
+//! when `--timeout` expires. Only a `200` response sent to the end counts as
+//! a download; `HEAD` and ranged requests (a resumed download) do not.
+
+use crate::archive::ArchiveFormat;
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::Request;
+use crate::middleware::Middleware;
+use clap::Parser;
+use std::io::IsTerminal;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the wait loop checks that the server is still running
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Arguments of `irondrop serve`
+#[derive(Parser, Debug, Clone)]
+#[command(
+    name = "irondrop serve",
+    bin_name = "irondrop serve",
+    version,
+    about = "Share one file or folder and shut down once it has been downloaded"
+)]
+pub struct ServeArgs {
+    /// Serve the path until it has been downloaded, then shut down
+    #[arg(long, required = true)]
+    pub once: bool,
+
+    /// File or folder to share
+    pub path: PathBuf,
+
+    /// Shut down after this many completed downloads
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub downloads: u32,
+
+    /// Shut down after this long even if nobody downloaded, e.g. "90s", "10m" or "1h"
+    #[arg(long, value_parser = parse_timeout)]
+    pub timeout: Option<Duration>,
+
+    /// Host address to listen on
+    #[arg(short, long, default_value = "0.0.0.0")]
+    pub listen: String,
+
+    /// Port number to listen on (0 picks a free one)
+    #[arg(short, long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Archive format a shared folder is sent as: "tar" or "tar.gz"
+    #[arg(long, default_value = "tar.gz", value_parser = parse_format)]
+    pub format: ArchiveFormat,
+}
+
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (digits, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, "s"),
+    };
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => 0,
+    };
+    match digits.parse::<u64>() {
+        Ok(value) if value > 0 && scale > 0 => Ok(Duration::from_secs(value * scale)),
+        _ => Err(format!("Invalid timeout: '{s}' (use e.g. 90s, 10m or 1h)")),
+    }
+}
+
+fn parse_format(s: &str) -> Result<ArchiveFormat, String> {
+    ArchiveFormat::parse(s).ok_or_else(|| format!("Unknown archive format: '{s}' (tar or tar.gz)"))
+}
+
+fn describe_duration(duration: Duration) -> String {
+    match duration.as_secs() {
+        secs if secs % 3600 == 0 => format!("{}h", secs / 3600),
+        secs if secs % 60 == 0 => format!("{}m", secs / 60),
+        secs => format!("{secs}s"),
+    }
+}
+
+/// How a quick share ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareOutcome {
+    /// Downloaded as often as requested
+    Downloaded(u32),
+    /// The timeout expired first, after this many downloads
+    TimedOut(u32),
+}
+
+/// The one item a quick share serves, and how often it was downloaded
+pub struct QuickShare {
+    /// Decoded path of the shared URL, without query
+    path: String,
+    /// Set when a folder is shared
+    archive: Option<ArchiveFormat>,
+    limit: u32,
+    completed: AtomicU32,
+    downloaded_tx: mpsc::Sender<()>,
+}
+
+impl QuickShare {
+    /// Share the item at `path`; the receiver gets one message per download
+    pub fn new(
+        path: String,
+        archive: Option<ArchiveFormat>,
+        limit: u32,
+    ) -> (Self, mpsc::Receiver<()>) {
+        let (downloaded_tx, downloaded_rx) = mpsc::channel();
+        let share = Self {
+            path,
+            archive,
+            limit,
+            completed: AtomicU32::new(0),
+            downloaded_tx,
+        };
+        (share, downloaded_rx)
+    }
+
+    /// Shared URL path, percent-encoded and with the archive query for folders
+    pub fn url_path(&self) -> String {
+        let path = crate::utils::percent_encode_path(Path::new(&self.path));
+        match self.archive {
+            Some(format) => format!("{path}?download={}", format.extension()),
+            None => path,
+        }
+    }
+
+    /// Completed downloads so far
+    pub fn downloads(&self) -> u32 {
+        self.completed.load(Ordering::SeqCst)
+    }
+
+    fn is_shared(&self, path: &str, query: Option<&str>) -> bool {
+        if path != self.path {
+            return false;
+        }
+        match self.archive {
+            Some(format) => {
+                crate::utils::parse_query_string(query.unwrap_or(""))
+                    .get("download")
+                    .and_then(|value| ArchiveFormat::parse(value))
+                    == Some(format)
+            }
+            None => true,
+        }
+    }
+
+    /// Count a response sent in full if it delivered the shared item
+    pub fn record_sent(&self, method: &str, path: &str, query: Option<&str>, status_code: u16) {
+        if method == "GET" && status_code == 200 && self.is_shared(path, query) {
+            self.completed.fetch_add(1, Ordering::SeqCst);
+            let _ = self.downloaded_tx.send(());
+        }
+    }
+}
+
+impl Middleware for QuickShare {
+    fn handle(&self, request: &Request) -> Result<(), AppError> {
+        // Nothing is served once used up, even in the moment before shutdown
+        if !self.is_shared(&request.path, request.query.as_deref())
+            || self.downloads() >= self.limit
+        {
+            return Err(AppError::NotFound);
+        }
+        if request.method != "GET" && request.method != "HEAD" {
+            return Err(AppError::MethodNotAllowedFor("GET, HEAD".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Entry point of `irondrop serve`; returns the process exit code
+///
+/// `0` once the item was downloaded, `1` when the timeout expired first and
+/// `2` for bad arguments or a server that failed to start.
+pub fn serve_command<I, T>(args: I) -> i32
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let args = match ServeArgs::try_parse_from(args) {
+        Ok(args) => args,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() { 2 } else { 0 };
+        }
+    };
+    match serve_once(&args, None) {
+        Ok(ShareOutcome::Downloaded(count)) => {
+            println!("Downloaded {count} time(s), shutting down.");
+            0
+        }
+        Ok(ShareOutcome::TimedOut(count)) => {
+            println!(
+                "Timed out with {count} of {} download(s), shutting down.",
+                args.downloads
+            );
+            1
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            2
+        }
+    }
+}
+
+/// Serve `args.path` until it has been downloaded or the timeout expires
+///
+/// The listening address is sent through `addr_tx` once the server is up.
+pub fn serve_once(
+    args: &ServeArgs,
+    addr_tx: Option<mpsc::Sender<SocketAddr>>,
+) -> Result<ShareOutcome, AppError> {
+    let path = args.path.canonicalize().map_err(|e| {
+        AppError::InvalidConfiguration(format!("Cannot share {}: {e}", args.path.display()))
+    })?;
+    let (directory, share_path, archive) = if path.is_dir() {
+        (path.clone(), "/".to_string(), Some(args.format))
+    } else {
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => (
+                parent.to_path_buf(),
+                format!("/{}", name.to_string_lossy()),
+                None,
+            ),
+            _ => {
+                return Err(AppError::InvalidConfiguration(format!(
+                    "Cannot share {}",
+                    path.display()
+                )));
+            }
+        }
+    };
+    let (share, downloaded_rx) = QuickShare::new(share_path, archive, args.downloads);
+    let share = Arc::new(share);
+
+    let mut cli = Cli::parse_from([
+        "irondrop".into(),
+        "--directory".into(),
+        directory.into_os_string(),
+        "--listen".into(),
+        args.listen.clone().into(),
+        "--port".into(),
+        args.port.to_string().into(),
+        // The shared file itself may be a dotfile
+        "--hide-dotfiles".into(),
+        "false".into(),
+    ]);
+    cli.quick_share = Some(share.clone());
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (local_tx, local_rx) = mpsc::channel();
+    let server =
+        thread::spawn(move || crate::server::run_server(cli, Some(shutdown_rx), Some(local_tx)));
+    let join = |server: thread::JoinHandle<Result<(), AppError>>| {
+        server.join().unwrap_or_else(|_| {
+            Err(AppError::InternalServerError(
+                "Server thread panicked".into(),
+            ))
+        })
+    };
+    let Ok(local_addr) = local_rx.recv() else {
+        // The server gave up before binding; its error says why
+        return join(server).and(Err(AppError::InternalServerError(
+            "Server did not start".into(),
+        )));
+    };
+    print_share(&path, &share, local_addr, args);
+    if let Some(tx) = addr_tx {
+        let _ = tx.send(local_addr);
+    }
+
+    let deadline = args.timeout.map(|timeout| Instant::now() + timeout);
+    let outcome = loop {
+        match downloaded_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(()) => {
+                let count = share.downloads();
+                println!("Download {count} of {} complete.", args.downloads);
+                if count >= args.downloads {
+                    break ShareOutcome::Downloaded(count);
+                }
+            }
+            Err(_) if server.is_finished() => {
+                join(server)?;
+                return Err(AppError::InternalServerError(
+                    "Server stopped unexpectedly".into(),
+                ));
+            }
+            Err(_) if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                break ShareOutcome::TimedOut(share.downloads());
+            }
+            Err(_) => {}
+        }
+    };
+    let _ = shutdown_tx.send(());
+    join(server)?;
+    Ok(outcome)
+}
+
+fn print_share(path: &Path, share: &QuickShare, local_addr: SocketAddr, args: &ServeArgs) {
+    let url = format!(
+        "http://{}{}",
+        crate::qr::advertised_host(local_addr),
+        share.url_path()
+    );
+    let name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    println!("Sharing {name} at:\n\n    {url}\n");
+    if std::io::stdout().is_terminal()
+        && let Some(qr) = crate::qr::QrCode::encode(url.as_bytes())
+    {
+        println!("{}", qr.to_terminal());
+    }
+    match args.timeout {
+        Some(timeout) => println!(
+            "Stops after {} download(s) or in {}.",
+            args.downloads,
+            describe_duration(timeout)
+        ),
+        None => println!("Stops after {} download(s).", args.downloads),
+    }
+}
@@ -0,0 +1,455 @@
+// SPDX-License-Identifier: MIT
+
+//! Upload quotas per client
+//!
+//! Tracks cumulative uploaded bytes per client and enforces optional daily and
+//! lifetime limits on top of the per-request `max_upload_size`. A client is the
+//! user, session or API token that authentication verified, otherwise the
+//! remote IP address. At most [`MAX_TRACKED_CLIENTS`] clients are remembered;
+//! the least recently active are forgotten first.
+//!
+//! - daily limit exceeded: `429 Too Many Requests` (resets at 00:00 UTC)
+//! - total limit exceeded: `507 Insufficient Storage`
+//!
+//! An upload [`reserve`](UploadQuota::reserve)s its size before the body is
+//! stored, so concurrent uploads cannot each fit into the same remaining
+//! quota; the charge is given back when the upload fails.
+//!
+//! Usage is kept in memory and resets when the server restarts; a config
+//! reload only changes the limits.
+
+use crate::http::Request;
+use crate::response::HttpResponse;
+use crate::utils::json_escape;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Clients whose usage is remembered at once
+pub const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Configured quota limits in bytes; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaLimits {
+    pub daily_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+}
+
+impl QuotaLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.daily_bytes.is_none() && self.total_bytes.is_none()
+    }
+
+    fn check(&self, usage: &QuotaUsage, incoming: u64) -> Result<(), QuotaExceeded> {
+        if let Some(limit) = self.total_bytes
+            && usage.total.saturating_add(incoming) > limit
+        {
+            return Err(QuotaExceeded::Total {
+                used: usage.total,
+                limit,
+            });
+        }
+        if let Some(limit) = self.daily_bytes
+            && usage.today.saturating_add(incoming) > limit
+        {
+            return Err(QuotaExceeded::Daily {
+                used: usage.today,
+                limit,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct QuotaUsage {
+    total: u64,
+    today: u64,
+    day: u64,
+}
+
+impl QuotaUsage {
+    fn roll_over(&mut self, day: u64) {
+        if self.day != day {
+            self.day = day;
+            self.today = 0;
+        }
+    }
+}
+
+/// Which limit rejected an upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    Daily { used: u64, limit: u64 },
+    Total { used: u64, limit: u64 },
+}
+
+/// Thread-safe per-client upload accounting
+#[derive(Debug)]
+pub struct UploadQuota {
+    limits: Mutex<QuotaLimits>,
+    usage: Mutex<HashMap<String, QuotaUsage>>,
+    max_clients: usize,
+}
+
+impl Default for UploadQuota {
+    fn default() -> Self {
+        Self::new(QuotaLimits::default())
+    }
+}
+
+impl UploadQuota {
+    pub fn new(limits: QuotaLimits) -> Self {
+        Self {
+            limits: Mutex::new(limits),
+            usage: Mutex::new(HashMap::new()),
+            max_clients: MAX_TRACKED_CLIENTS,
+        }
+    }
+
+    pub fn from_cli(cli: &crate::cli::Cli) -> Self {
+        Self::new(QuotaLimits {
+            daily_bytes: cli.upload_quota_daily,
+            total_bytes: cli.upload_quota_total,
+        })
+    }
+
+    pub fn limits(&self) -> QuotaLimits {
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replace the limits, keeping usage recorded so far
+    pub fn set_limits(&self, limits: QuotaLimits) {
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+    }
+
+    /// Identify the client an upload is charged to: the user or token that
+    /// authentication verified, otherwise the remote IP. Unverified
+    /// credentials are ignored so made-up user names cannot reset a quota.
+    pub fn client_key(request: &Request) -> String {
+        match (crate::users::request_identity(request), request.peer_addr) {
+            (Some(identity), _) => identity.key(),
+            (None, Some(addr)) => format!("ip:{}", addr.ip()),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+
+    /// Check whether `incoming` more bytes fit within the client's quota
+    pub fn check(&self, client: &str, incoming: u64) -> Result<(), QuotaExceeded> {
+        let limits = self.limits();
+        if limits.is_unlimited() {
+            return Ok(());
+        }
+        let Ok(usage) = self.usage.lock() else {
+            return Ok(());
+        };
+        // Only charged uploads add clients, so refused attempts take no room
+        let mut entry = usage.get(client).copied().unwrap_or_default();
+        entry.roll_over(current_day());
+        limits.check(&entry, incoming)
+    }
+
+    /// Charge `incoming` bytes to the client if they fit within its quota.
+    ///
+    /// The check and the charge happen under one lock, so concurrent uploads
+    /// cannot both pass on the same remaining bytes. Dropping the returned
+    /// reservation gives the bytes back unless it was
+    /// [`commit`](QuotaReservation::commit)ted.
+    pub fn reserve<'a>(
+        &'a self,
+        client: &str,
+        incoming: u64,
+    ) -> Result<QuotaReservation<'a>, QuotaExceeded> {
+        let limits = self.limits();
+        let day = current_day();
+        let mut reservation = QuotaReservation {
+            quota: self,
+            client: client.to_string(),
+            bytes: 0,
+            day,
+        };
+        if limits.is_unlimited() || incoming == 0 {
+            return Ok(reservation);
+        }
+        let Ok(mut usage) = self.usage.lock() else {
+            return Ok(reservation);
+        };
+        let mut entry = usage.get(client).copied().unwrap_or_default();
+        entry.roll_over(day);
+        limits.check(&entry, incoming)?;
+        charge(&mut usage, self.max_clients, client, incoming);
+        reservation.bytes = incoming;
+        Ok(reservation)
+    }
+
+    /// Charge `bytes` of completed upload traffic to the client
+    pub fn record(&self, client: &str, bytes: u64) {
+        if self.limits().is_unlimited() || bytes == 0 {
+            return;
+        }
+        if let Ok(mut usage) = self.usage.lock() {
+            charge(&mut usage, self.max_clients, client, bytes);
+        }
+    }
+
+    /// Give back bytes charged on `day` by a reservation that did not complete
+    fn refund(&self, client: &str, bytes: u64, day: u64) {
+        let Ok(mut usage) = self.usage.lock() else {
+            return;
+        };
+        let Some(entry) = usage.get_mut(client) else {
+            return;
+        };
+        entry.total = entry.total.saturating_sub(bytes);
+        if entry.day == day {
+            entry.today = entry.today.saturating_sub(bytes);
+        }
+        debug!("Refunded {bytes} bytes of upload quota to {client}");
+        if entry.total == 0 {
+            usage.remove(client);
+        }
+    }
+
+    /// Quota section for the monitoring JSON
+    pub fn usage_json(&self) -> String {
+        let limit = |v: Option<u64>| v.map_or("null".to_string(), |b| b.to_string());
+        let limits = self.limits();
+        let day = current_day();
+        let mut clients: Vec<(String, QuotaUsage)> = self
+            .usage
+            .lock()
+            .map(|usage| usage.iter().map(|(k, v)| (k.clone(), *v)).collect())
+            .unwrap_or_default();
+        clients.sort_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(&b.0)));
+        let entries: Vec<String> = clients
+            .iter()
+            .map(|(client, usage)| {
+                let today = if usage.day == day { usage.today } else { 0 };
+                format!(
+                    r#"{{"client":"{}","today_bytes":{},"total_bytes":{}}}"#,
+                    json_escape(client),
+                    today,
+                    usage.total
+                )
+            })
+            .collect();
+        format!(
+            r#""quota":{{"enabled":{},"daily_limit_bytes":{},"total_limit_bytes":{},"clients":[{}]}}"#,
+            !limits.is_unlimited(),
+            limit(limits.daily_bytes),
+            limit(limits.total_bytes),
+            entries.join(",")
+        )
+    }
+}
+
+impl QuotaExceeded {
+    /// JSON error response explaining which limit was hit and when it resets
+    pub fn to_response(self, client: &str) -> HttpResponse {
+        warn!("Upload quota exceeded for {client}: {self:?}");
+        let (status, text, kind, used, limit, reset) = match self {
+            QuotaExceeded::Daily { used, limit } => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                let reset = SECONDS_PER_DAY - now % SECONDS_PER_DAY;
+                (429, "Too Many Requests", "daily", used, limit, Some(reset))
+            }
+            QuotaExceeded::Total { used, limit } => {
+                (507, "Insufficient Storage", "total", used, limit, None)
+            }
+        };
+        let body = format!(
+            r#"{{"error":"upload_quota_exceeded","message":"Upload quota exceeded ({kind} limit of {limit} bytes).","quota":"{kind}","used_bytes":{used},"limit_bytes":{limit},"remaining_bytes":{},"resets_in_secs":{}}}"#,
+            limit.saturating_sub(used),
+            reset.map_or("null".to_string(), |s| s.to_string())
+        );
+        let mut response = HttpResponse::new(status, text)
+            .with_file_body(body.into_bytes(), "application/json; charset=utf-8");
+        if let Some(reset) = reset {
+            response = response.add_header("Retry-After".to_string(), reset.to_string());
+        }
+        response
+    }
+}
+
+/// Bytes charged to a client by [`UploadQuota::reserve`], refunded on drop
+/// unless committed
+#[derive(Debug)]
+pub struct QuotaReservation<'a> {
+    quota: &'a UploadQuota,
+    client: String,
+    bytes: u64,
+    day: u64,
+}
+
+impl QuotaReservation<'_> {
+    /// Keep the charge: the upload went through
+    pub fn commit(mut self) {
+        self.bytes = 0;
+    }
+}
+
+impl Drop for QuotaReservation<'_> {
+    fn drop(&mut self) {
+        if self.bytes > 0 {
+            self.quota.refund(&self.client, self.bytes, self.day);
+        }
+    }
+}
+
+fn charge(usage: &mut HashMap<String, QuotaUsage>, max_clients: usize, client: &str, bytes: u64) {
+    if !usage.contains_key(client) && usage.len() >= max_clients {
+        forget_least_active(usage);
+    }
+    let entry = usage.entry(client.to_string()).or_default();
+    entry.roll_over(current_day());
+    entry.total = entry.total.saturating_add(bytes);
+    entry.today = entry.today.saturating_add(bytes);
+    debug!(
+        "Upload quota for {client}: {} bytes today, {} bytes total",
+        entry.today, entry.total
+    );
+}
+
+/// Drop the client that was active longest ago, the lightest one on a tie
+fn forget_least_active(usage: &mut HashMap<String, QuotaUsage>) {
+    let victim = usage
+        .iter()
+        .min_by_key(|(_, usage)| (usage.day, usage.today, usage.total))
+        .map(|(client, _)| client.clone());
+    if let Some(victim) = victim {
+        debug!("Forgetting upload quota usage of {victim}");
+        usage.remove(&victim);
+    }
+}
+
+fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_daily_and_total_limits() {
+        let quota = UploadQuota::new(QuotaLimits {
+            daily_bytes: Some(100),
+            total_bytes: Some(150),
+        });
+        assert!(quota.check("ip:1.2.3.4", 80).is_ok());
+        quota.record("ip:1.2.3.4", 80);
+        assert_eq!(
+            quota.check("ip:1.2.3.4", 30),
+            Err(QuotaExceeded::Daily {
+                used: 80,
+                limit: 100
+            })
+        );
+        // Other clients are unaffected
+        assert!(quota.check("ip:5.6.7.8", 100).is_ok());
+
+        // Simulate a new day: the daily counter resets, the total does not
+        quota
+            .usage
+            .lock()
+            .unwrap()
+            .get_mut("ip:1.2.3.4")
+            .unwrap()
+            .day -= 1;
+        assert!(quota.check("ip:1.2.3.4", 60).is_ok());
+        quota.record("ip:1.2.3.4", 60);
+        assert_eq!(
+            quota.check("ip:1.2.3.4", 20),
+            Err(QuotaExceeded::Total {
+                used: 140,
+                limit: 150
+            })
+        );
+    }
+
+    #[test]
+    fn test_quota_reservations_hold_bytes_until_dropped() {
+        let quota = UploadQuota::new(QuotaLimits {
+            daily_bytes: None,
+            total_bytes: Some(100),
+        });
+        // A second upload in flight cannot take the bytes held by the first
+        let first = quota.reserve("ip:1.2.3.4", 60).unwrap();
+        assert_eq!(
+            quota.reserve("ip:1.2.3.4", 60).unwrap_err(),
+            QuotaExceeded::Total {
+                used: 60,
+                limit: 100
+            }
+        );
+        drop(first);
+        assert!(quota.usage.lock().unwrap().is_empty());
+
+        quota.reserve("ip:1.2.3.4", 60).unwrap().commit();
+        let failed = quota.reserve("ip:1.2.3.4", 40).unwrap();
+        assert!(quota.check("ip:1.2.3.4", 1).is_err());
+        drop(failed);
+        assert!(quota.check("ip:1.2.3.4", 40).is_ok());
+        assert!(quota.check("ip:1.2.3.4", 41).is_err());
+    }
+
+    #[test]
+    fn test_quota_unlimited_and_json() {
+        let quota = UploadQuota::default();
+        assert!(quota.check("ip:1.2.3.4", u64::MAX).is_ok());
+        quota.record("ip:1.2.3.4", 10);
+        assert!(quota.usage_json().contains(r#""enabled":false"#));
+
+        let quota = UploadQuota::new(QuotaLimits {
+            daily_bytes: None,
+            total_bytes: Some(10),
+        });
+        quota.record("user:alice", 4);
+        let json = quota.usage_json();
+        assert!(json.contains(r#""total_limit_bytes":10"#));
+        assert!(json.contains(r#"{"client":"user:alice","today_bytes":4,"total_bytes":4}"#));
+
+        let response = QuotaExceeded::Total { used: 4, limit: 10 }.to_response("user:alice");
+        assert_eq!(response.status_code, 507);
+        assert!(String::from_utf8_lossy(&response.body).contains(r#""remaining_bytes":6"#));
+    }
+
+    #[test]
+    fn test_quota_forgets_least_active_clients() {
+        let quota = UploadQuota {
+            max_clients: 3,
+            ..UploadQuota::new(QuotaLimits {
+                daily_bytes: Some(100),
+                total_bytes: None,
+            })
+        };
+        // Checks alone do not add clients
+        for n in 0..10 {
+            assert!(quota.check(&format!("ip:10.0.0.{n}"), 1).is_ok());
+        }
+        assert!(quota.usage.lock().unwrap().is_empty());
+
+        for (client, bytes) in [("ip:a", 50), ("ip:b", 10), ("ip:c", 30)] {
+            quota.record(client, bytes);
+        }
+        quota.usage.lock().unwrap().get_mut("ip:a").unwrap().day -= 1;
+        // `a` was active longest ago, then `d` has uploaded the least today
+        quota.record("ip:d", 5);
+        quota.record("ip:e", 20);
+        let mut clients: Vec<String> = quota.usage.lock().unwrap().keys().cloned().collect();
+        clients.sort();
+        assert_eq!(clients, ["ip:b", "ip:c", "ip:e"]);
+        // Known clients never push anyone out
+        quota.record("ip:b", 1);
+        assert_eq!(quota.usage.lock().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MIT
+
+//! Server-wide read-only mode
+//!
+//! With `--read-only` (or after switching it on at runtime) every request that
+//! could change the served directory is refused with `403 Forbidden` before
+//! it reaches a handler: uploads, upload sessions, file management and WebDAV
+//! writes alike. Listings stop offering the upload and management controls.
+//!
+//! When global credentials or API tokens are configured, the mode can be
+//! switched without a restart:
+//!
+//! - `GET  /_irondrop/read-only` - `{"read_only":true}`
+//! - `POST /_irondrop/read-only?enabled=false` - switch and return the new state
+//!
+//! A config reload only changes the mode when the configured value changed,
+//! so a runtime switch survives unrelated edits to the config file.
+
+use crate::error::AppError;
+use crate::http::{Request, Response, ResponseBody};
+use crate::middleware::Middleware;
+use log::{debug, info};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Route for reading and switching the mode
+pub const READ_ONLY_ROUTE: &str = "/_irondrop/read-only";
+
+/// Methods that never change the served directory
+const SAFE_METHODS: [&str; 4] = ["GET", "HEAD", "OPTIONS", "PROPFIND"];
+
+/// Non-safe routes that stay available while read-only
+const EXEMPT_ROUTES: [&str; 4] = [
+    READ_ONLY_ROUTE,
+    "/_irondrop/cleanup-memory",
+    crate::session::LOGIN_ROUTE,
+    crate::archive::ARCHIVE_ROUTE,
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether mutating requests are currently refused
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Switch the mode, logging actual changes
+pub fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::SeqCst) != enabled {
+        if enabled {
+            info!("🔒 Read-only mode enabled; uploads and changes are refused");
+        } else {
+            info!("🔓 Read-only mode disabled");
+        }
+    }
+}
+
+/// Apply the configured value at startup, or on reload when it changed
+pub fn configure(configured: bool, previous: Option<bool>) {
+    match previous {
+        None if configured => set_enabled(true),
+        Some(previous) if previous != configured => set_enabled(configured),
+        _ => {}
+    }
+}
+
+/// Whether `request` could change the served directory
+pub fn is_mutating(request: &Request) -> bool {
+    if SAFE_METHODS.contains(&request.method.as_str()) {
+        return false;
+    }
+    let path = request.path.as_str();
+    !EXEMPT_ROUTES.contains(&path)
+}
+
+/// Refuses mutating requests while read-only mode is on
+pub struct ReadOnlyMiddleware;
+
+impl Middleware for ReadOnlyMiddleware {
+    fn handle(&self, request: &Request) -> Result<(), AppError> {
+        if is_enabled() && is_mutating(request) {
+            debug!(
+                "Read-only mode: refused {} {}",
+                request.method, request.path
+            );
+            return Err(AppError::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+/// `GET` or `POST` on [`READ_ONLY_ROUTE`]
+pub fn handle_read_only_request(request: &Request) -> Result<Response, AppError> {
+    if request.method == "POST" {
+        let params = request.query_params();
+        let enabled = match params.get("enabled").map(String::as_str) {
+            Some("true" | "1" | "on") => true,
+            Some("false" | "0" | "off") => false,
+            _ => {
+                debug!("Read-only toggle without a valid 'enabled' parameter");
+                return Err(AppError::BadRequest);
+            }
+        };
+        set_enabled(enabled);
+    }
+
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(format!(r#"{{"read_only":{}}}"#, is_enabled())),
+    })
+}
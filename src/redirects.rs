@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: MIT
+
+//! Redirects for missing paths, for static site hosting
+//!
+//! Rules use the `_redirects` file format, `from to [status]`:
+//!
+//! ```text
+//! # old blog URLs
+//! /blog/*        /posts/:splat
+//! /docs          https://docs.example.com/ 302
+//! ```
+//!
+//! They come from `[redirects]` (`/docs = https://docs.example.com/ 302`),
+//! `--redirects` (which replaces the section) and a `--redirects-file`. A
+//! `from` ending in `/*` matches everything below it, and `:splat` in the
+//! target is replaced by the matched rest. An exact rule wins over `/*`
+//! rules, a longer prefix over a shorter one, and of two rules for the same
+//! `from` the first one counts. The status defaults to 301; 302, 303, 307
+//! and 308 are accepted too. Rules are only consulted when nothing exists
+//! at the requested path, so they never hide content. The query string is
+//! passed on unless the target has its own.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Response, ResponseBody};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+static RULES: RwLock<Option<Arc<RedirectRules>>> = RwLock::new(None);
+
+/// One `from to [status]` rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// Exact path, or the prefix before `/*` for splat rules
+    from: String,
+    splat: bool,
+    to: String,
+    status: u16,
+}
+
+impl Redirect {
+    /// Parse a `from to [status]` rule
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = rule.split_whitespace().collect();
+        let (from, to, status) = match fields.as_slice() {
+            [from, to] => (*from, *to, 301),
+            [from, to, status] => match status.parse::<u16>() {
+                Ok(status) if matches!(status, 301 | 302 | 303 | 307 | 308) => (*from, *to, status),
+                _ => {
+                    return Err(format!(
+                        "invalid status '{status}' in '{rule}' (use 301, 302, 303, 307 or 308)"
+                    ));
+                }
+            },
+            _ => return Err(format!("expected 'from to [status]', got '{rule}'")),
+        };
+        if !from.starts_with('/') {
+            return Err(format!("'{from}' must start with '/'"));
+        }
+        let is_url = to.starts_with("http://") || to.starts_with("https://");
+        if !to.starts_with('/') && !is_url {
+            return Err(format!("'{to}' must be a path or an http(s) URL"));
+        }
+        let (from, splat) = match from.strip_suffix("/*") {
+            Some(prefix) => (format!("{prefix}/"), true),
+            None => (from.to_string(), false),
+        };
+        Ok(Self {
+            from,
+            splat,
+            to: to.to_string(),
+            status,
+        })
+    }
+
+    /// Target for `path` (decoded, without query), if the rule matches it
+    fn target(&self, path: &str) -> Option<String> {
+        if !self.splat {
+            return (path == self.from).then(|| self.to.clone());
+        }
+        let rest = path
+            .strip_prefix(self.from.as_str())
+            .or_else(|| (path == self.from.trim_end_matches('/')).then_some(""))?;
+        Some(self.to.replace(
+            ":splat",
+            &crate::utils::percent_encode_path(Path::new(rest)),
+        ))
+    }
+}
+
+/// Ordered redirect rules of a server
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RedirectRules {
+    rules: Vec<Redirect>,
+}
+
+impl RedirectRules {
+    /// Rules from `--redirects` followed by those in `--redirects-file`
+    pub fn from_cli(cli: &Cli) -> Result<Self, AppError> {
+        let invalid = |e: String| AppError::InvalidConfiguration(format!("Invalid redirect: {e}"));
+        let mut rules = Vec::new();
+        for rule in cli
+            .redirects
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter(|rule| !rule.trim().is_empty())
+        {
+            rules.push(Redirect::parse(rule).map_err(invalid)?);
+        }
+        if let Some(path) = &cli.redirects_file {
+            let contents = std::fs::read_to_string(path).map_err(|e| {
+                AppError::InvalidConfiguration(format!(
+                    "Cannot read redirects file {}: {e}",
+                    path.display()
+                ))
+            })?;
+            rules.extend(
+                Self::parse_file(&contents).map_err(|e| {
+                    AppError::InvalidConfiguration(format!("{}: {e}", path.display()))
+                })?,
+            );
+        }
+        Ok(Self { rules })
+    }
+
+    /// Parse a `_redirects` file: one rule per line, `#` lines are comments
+    pub fn parse_file(contents: &str) -> Result<Vec<Redirect>, String> {
+        contents
+            .lines()
+            .enumerate()
+            .map(|(number, line)| (number, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, line)| {
+                Redirect::parse(line).map_err(|e| format!("line {}: {e}", number + 1))
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Status and `Location` for a decoded `path` and its raw `query`
+    pub fn find(&self, path_only: &str, query: Option<&str>) -> Option<(u16, String)> {
+        let exact = self
+            .rules
+            .iter()
+            .find(|rule| !rule.splat && rule.from == path_only);
+        let rule = exact.or_else(|| {
+            self.rules
+                .iter()
+                .filter(|rule| rule.splat)
+                .filter(|rule| rule.target(path_only).is_some())
+                .reduce(|best, rule| {
+                    if rule.from.len() > best.from.len() {
+                        rule
+                    } else {
+                        best
+                    }
+                })
+        })?;
+        let status = rule.status;
+        let mut location = rule.target(path_only)?;
+        if !location.starts_with("http") {
+            location = crate::templates::prefixed(&location);
+        }
+        if let Some(query) = query.filter(|_| !location.contains('?')) {
+            location = format!("{location}?{query}");
+        }
+        Some((status, location))
+    }
+}
+
+/// Install the rules used by [`lookup`]; called on startup and every reload
+pub fn configure(rules: RedirectRules) {
+    if !rules.is_empty() {
+        info!("↪️ {} redirect rule(s) for missing paths", rules.len());
+    }
+    if let Ok(mut slot) = RULES.write() {
+        *slot = (!rules.is_empty()).then(|| Arc::new(rules));
+    }
+}
+
+/// The redirect response for a missing `path`, if a rule matches
+pub fn lookup(path: &str, query: Option<&str>) -> Option<Response> {
+    let rules = RULES.read().ok()?.clone()?;
+    let (status_code, location) = rules.find(path, query)?;
+    debug!("Redirecting {path} to {location} ({status_code})");
+    let status_text = match status_code {
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        307 => "Temporary Redirect",
+        _ => "Permanent Redirect",
+    };
+    let mut headers = HashMap::new();
+    headers.insert("Location".to_string(), location);
+    Some(Response {
+        status_code,
+        status_text: status_text.to_string(),
+        headers,
+        body: ResponseBody::Text(String::new()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_match_rules() {
+        let rules = RedirectRules {
+            rules: RedirectRules::parse_file(
+                "# comment\n\n/old /new\n/blog/* /posts/:splat 302\n/blog/drafts/* /\n/away https://example.com/?from=away#top 308\n/old /ignored\n",
+            )
+            .unwrap(),
+        };
+        assert_eq!(rules.len(), 5);
+        assert_eq!(rules.find("/old", None), Some((301, "/new".to_string())));
+        assert_eq!(rules.find("/old/", None), None);
+        assert_eq!(
+            rules.find("/blog/2024/my post", Some("ref=x")),
+            Some((302, "/posts/2024/my%20post?ref=x".to_string()))
+        );
+        assert_eq!(
+            rules.find("/blog", None),
+            Some((302, "/posts/".to_string()))
+        );
+        assert_eq!(
+            rules.find("/blog/drafts/x", None),
+            Some((301, "/".to_string()))
+        );
+        assert_eq!(rules.find("/blogger", None), None);
+        assert_eq!(
+            rules.find("/away", Some("x=1")),
+            Some((308, "https://example.com/?from=away#top".to_string()))
+        );
+
+        assert!(Redirect::parse("/a").is_err());
+        assert!(Redirect::parse("a /b").is_err());
+        assert!(Redirect::parse("/a b").is_err());
+        assert!(Redirect::parse("/a /b 200").is_err());
+        assert!(Redirect::parse("/a /b 301 extra").is_err());
+        assert_eq!(
+            RedirectRules::parse_file("/ok /fine\n/bad\n").unwrap_err(),
+            "line 2: expected 'from to [status]', got '/bad'"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MIT
 
+use crate::cli::Cli;
 use crate::error::AppError;
 use crate::templates::{TemplateEngine, get_error_description};
 use log::{debug, error, trace};
@@ -26,16 +27,258 @@ pub fn get_mime_type(path: &Path) -> &'static str {
         Some("tar") => "application/x-tar",
         Some("gz") => "application/gzip",
         Some("mp4") => "video/mp4",
+        Some("m4v") => "video/x-m4v",
+        Some("webm") => "video/webm",
+        Some("ogv") => "video/ogg",
+        Some("mkv") => "video/x-matroska",
+        Some("mov") => "video/quicktime",
+        Some("avi") => "video/x-msvideo",
         Some("mp3") => "audio/mpeg",
         Some("wav") => "audio/wav",
+        Some("ogg" | "oga" | "opus") => "audio/ogg",
+        Some("flac") => "audio/flac",
+        Some("m4a") => "audio/mp4",
+        Some("aac") => "audio/aac",
         _ => "application/octet-stream",
     }
 }
 
+/// Charset added to text types unless `--default-charset` says otherwise
+pub const DEFAULT_CHARSET: &str = "utf-8";
+
+/// Extensions served as attachments when uploads are enabled and
+/// `--force-download` is not set, since uploaded HTML or SVG could run
+/// scripts on the server's origin
+pub const DEFAULT_FORCE_DOWNLOAD: &str = "html,htm,xhtml,svg";
+
+/// How a downloaded file is labelled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileContentType {
+    /// `Content-Type` header value
+    pub content_type: String,
+    /// Send `Content-Disposition: attachment` so browsers do not render it
+    pub attachment: bool,
+}
+
+/// Content type for serving `path`, applying the `--mime-types`,
+/// `--default-charset` and `--force-download` settings
+///
+/// `--mime-types` entries look like `md=text/markdown; charset=utf-8` and take
+/// precedence over [`get_mime_type`]. Text types without an explicit charset
+/// get the default one; an empty value or `none` turns that off.
+pub fn get_file_content_type(path: &Path, cli: Option<&Cli>) -> FileContentType {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    let configured = cli
+        .and_then(|cli| cli.mime_types.as_deref())
+        .and_then(|list| {
+            list.split(',').find_map(|entry| {
+                let (ext, mime) = entry.split_once('=')?;
+                let ext = ext.trim().trim_start_matches('.');
+                (!ext.is_empty() && ext.eq_ignore_ascii_case(&extension))
+                    .then(|| mime.trim().to_string())
+            })
+        })
+        .filter(|mime| !mime.is_empty());
+    let mut content_type = configured
+        .unwrap_or_else(|| get_mime_type(Path::new(&format!("file.{extension}"))).to_string());
+
+    let charset = cli
+        .and_then(|cli| cli.default_charset.as_deref())
+        .unwrap_or(DEFAULT_CHARSET)
+        .trim();
+    let has_charset = content_type.to_ascii_lowercase().contains("charset=");
+    if !charset.is_empty()
+        && !charset.eq_ignore_ascii_case("none")
+        && !has_charset
+        && is_text_type(&content_type)
+    {
+        content_type = format!("{content_type}; charset={charset}");
+    }
+
+    let force_download = match cli {
+        Some(cli) => match cli.force_download.as_deref() {
+            Some(list) => list,
+            None if cli.enable_upload.unwrap_or(false) => DEFAULT_FORCE_DOWNLOAD,
+            None => "",
+        },
+        None => "",
+    };
+    let attachment = !extension.is_empty()
+        && force_download.split(',').any(|ext| {
+            ext.trim()
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(&extension)
+        });
+
+    FileContentType {
+        content_type,
+        attachment,
+    }
+}
+
+/// Whether a MIME type is audio or video, which browsers play inline and seek
+/// through with range requests
+pub fn is_media_type(content_type: &str) -> bool {
+    let mime = content_type.trim_start().to_ascii_lowercase();
+    mime.starts_with("video/") || mime.starts_with("audio/")
+}
+
+/// Whether a MIME type carries text that a charset applies to
+fn is_text_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("+xml")
+        || mime.ends_with("+json")
+        || matches!(
+            mime.as_str(),
+            "application/javascript" | "application/json" | "application/xml"
+        )
+}
+
+/// Broad kind of a file, used to pick its icon in directory listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Image,
+    Video,
+    Audio,
+    Archive,
+    Code,
+    Document,
+    Other,
+}
+
+impl FileKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Video => "video",
+            Self::Audio => "audio",
+            Self::Archive => "archive",
+            Self::Code => "code",
+            Self::Document => "document",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Classify `path` by its MIME type from [`get_mime_type`], falling back to
+/// extensions that mapping serves as `application/octet-stream`
+pub fn get_file_kind(path: &Path) -> FileKind {
+    let Some(extension) = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+    else {
+        return FileKind::Other;
+    };
+    let mime = get_mime_type(Path::new(&format!("file.{extension}")));
+    match mime {
+        "application/zip" | "application/x-tar" | "application/gzip" => return FileKind::Archive,
+        "text/html"
+        | "text/css"
+        | "application/javascript"
+        | "application/json"
+        | "application/xml" => return FileKind::Code,
+        "text/plain" | "text/markdown" | "application/pdf" => return FileKind::Document,
+        _ if mime.starts_with("image/") => return FileKind::Image,
+        _ if mime.starts_with("video/") => return FileKind::Video,
+        _ if mime.starts_with("audio/") => return FileKind::Audio,
+        _ => {}
+    }
+    match extension.as_str() {
+        "bmp" | "webp" | "tif" | "tiff" | "avif" | "heic" => FileKind::Image,
+        "avi" | "mkv" | "mov" | "wmv" | "flv" | "webm" | "m4v" => FileKind::Video,
+        "flac" | "ogg" | "oga" | "opus" | "m4a" | "aac" | "wma" => FileKind::Audio,
+        "rar" | "7z" | "bz2" | "xz" | "zst" | "tgz" | "iso" => FileKind::Archive,
+        "rs" | "py" | "go" | "c" | "h" | "cpp" | "hpp" | "cc" | "java" | "kt" | "swift" | "ts"
+        | "tsx" | "jsx" | "sh" | "bash" | "rb" | "php" | "sql" | "toml" | "yaml" | "yml"
+        | "ini" | "lua" | "cs" => FileKind::Code,
+        "doc" | "docx" | "odt" | "rtf" | "xls" | "xlsx" | "ods" | "csv" | "ppt" | "pptx"
+        | "odp" | "epub" | "log" => FileKind::Document,
+        _ => FileKind::Other,
+    }
+}
+
+/// Outcome of evaluating a `Range` request header against a file size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// No usable range; the full body should be served with 200
+    Full,
+    /// A single satisfiable range with inclusive start and end offsets
+    Partial { start: u64, end: u64 },
+    /// The range is well-formed but lies entirely outside the file (416)
+    Unsatisfiable,
+}
+
+/// Parse an RFC 7233 `Range: bytes=...` header for a representation of `size` bytes.
+///
+/// Only single ranges are served as 206; multi-range requests fall back to the
+/// full body, which RFC 7233 permits. Malformed headers are ignored.
+pub fn parse_range_header(value: &str, size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (first, last) = (first.trim(), last.trim());
+
+    if first.is_empty() {
+        // Suffix range: the final N bytes
+        let Ok(suffix) = last.parse::<u64>() else {
+            return ByteRange::Full;
+        };
+        if suffix == 0 || size == 0 {
+            return ByteRange::Unsatisfiable;
+        }
+        return ByteRange::Partial {
+            start: size.saturating_sub(suffix),
+            end: size - 1,
+        };
+    }
+
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = if last.is_empty() {
+        u64::MAX
+    } else {
+        match last.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial {
+        start,
+        end: end.min(size - 1),
+    }
+}
+
 /// Generate error pages using embedded templates - dark mode only
-fn generate_error_page(status_code: u16, status_text: &str) -> String {
+fn generate_error_page(status_code: u16, status_text: &str, request_path: &str) -> String {
     let engine = TemplateEngine::global();
     let description = get_error_description(status_code);
+    if let Some(page) =
+        engine.render_custom_error_page(status_code, status_text, description, request_path)
+    {
+        return page;
+    }
 
     engine.render_error_page(status_code, status_text, description)
         .unwrap_or_else(|_| {
@@ -158,8 +401,17 @@ impl HttpResponse {
 
 /// Create error response with beautiful HTML error page
 pub fn create_error_response(status_code: u16, status_text: &str) -> HttpResponse {
+    create_error_response_for_path(status_code, status_text, "")
+}
+
+/// Like [`create_error_response`], with the request path available to custom error pages
+pub fn create_error_response_for_path(
+    status_code: u16,
+    status_text: &str,
+    request_path: &str,
+) -> HttpResponse {
     debug!("Creating error response: {} {}", status_code, status_text);
-    let error_page = generate_error_page(status_code, status_text);
+    let error_page = generate_error_page(status_code, status_text, request_path);
     let mut response = HttpResponse::new(status_code, status_text).with_html_body(error_page);
 
     if status_code == 401 {
@@ -6,6 +6,10 @@
 //! - Exact path matching (e.g. "/_health")
 //! - Prefix path matching (useful for static asset directories)
 //! - Method filtering (GET/POST/etc.)
+//! - `HEAD` served by the matching `GET` route, `OPTIONS` answered with an
+//!   `Allow` header and `405` (with `Allow`) for paths owned by other methods
+//! - Per-method fallbacks for paths no route covers (e.g. `PUT` uploads)
+//! - Hooks told about every response once it has been sent
 //!
 //! Handlers are stored as boxed closures capturing any required state.
 //! The router is lightweight and intended to be constructed either once
@@ -15,20 +19,24 @@
 //! Future enhancements that would be beneficial:
 //! - Path parameters (e.g. /files/:id)
 //! - Glob or regex based matching
-//! - Middleware (before/after hooks)
-//! - A fallback / not-found handler override
+//! - Middleware that can rewrite responses
 //! - Caching / static router built once and shared via Arc
 //!
 //! For current use cases we keep it intentionally small and dependency free.
 
 use crate::error::AppError;
-use crate::http::{Request, Response};
+use crate::http::{Request, Response, ResponseBody};
 use crate::middleware::Middleware;
 use log::{debug, trace};
+use std::collections::HashMap;
 
 /// Type alias for a request handler closure.
 pub type Handler = Box<dyn Fn(&Request) -> Result<Response, AppError> + Send + Sync + 'static>;
 
+/// Hook called with the method, path, query and status of a response that
+/// was sent in full
+pub type SentHook = Box<dyn Fn(&str, &str, Option<&str>, u16) + Send + Sync + 'static>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum MatchKind {
     Exact,
@@ -46,7 +54,9 @@ struct RouteEntry {
 #[derive(Default)]
 pub struct Router {
     routes: Vec<RouteEntry>,
+    fallbacks: Vec<(String, Handler)>,
     middleware: Vec<Box<dyn Middleware>>, // global middleware executed in order
+    sent_hooks: Vec<SentHook>,
 }
 
 impl Router {
@@ -54,7 +64,9 @@ impl Router {
     pub fn new() -> Self {
         Self {
             routes: Vec::new(),
+            fallbacks: Vec::new(),
             middleware: Vec::new(),
+            sent_hooks: Vec::new(),
         }
     }
 
@@ -87,11 +99,59 @@ impl Router {
         });
     }
 
+    /// Register a handler for `method` requests to paths no route covers.
+    ///
+    /// Fallbacks are not listed in `Allow` headers, and paths owned by other
+    /// methods still answer `405`, so `OPTIONS` on a file is unaffected.
+    pub fn register_fallback<M>(&mut self, method: M, handler: Handler)
+    where
+        M: Into<String>,
+    {
+        self.fallbacks.push((method.into().to_uppercase(), handler));
+    }
+
     /// Add a global middleware executed before any handler.
     pub fn add_middleware(&mut self, mw: Box<dyn Middleware>) {
         self.middleware.push(mw);
     }
 
+    /// Add a hook run after a response body has been written to the client.
+    ///
+    /// Responses cut short by the client or a write error are not reported.
+    pub fn add_sent_hook(&mut self, hook: SentHook) {
+        self.sent_hooks.push(hook);
+    }
+
+    /// Report a response sent in full to the registered hooks
+    pub fn response_sent(&self, method: &str, path: &str, query: Option<&str>, status_code: u16) {
+        for hook in &self.sent_hooks {
+            hook(method, path, query, status_code);
+        }
+    }
+
+    /// Methods registered for `path`, in `Allow` header form
+    ///
+    /// `HEAD` is listed next to `GET` and `OPTIONS` comes first. Returns `None`
+    /// when no route covers the path.
+    pub fn allowed_methods(&self, path_only: &str) -> Option<String> {
+        let mut methods: Vec<&str> = Vec::new();
+        for entry in self.routes.iter().filter(|e| e.matches(path_only)) {
+            let method = entry.method.as_str();
+            if !methods.contains(&method) {
+                methods.push(method);
+            }
+            if method == "GET" && !methods.contains(&"HEAD") {
+                methods.push("HEAD");
+            }
+        }
+        if methods.is_empty() {
+            return None;
+        }
+        methods.retain(|&m| m != "OPTIONS");
+        methods.insert(0, "OPTIONS");
+        Some(methods.join(", "))
+    }
+
     /// Attempt to resolve a request to a registered route.
     /// Returns Some(Result<..>) if a route matched, or None if no route matched.
     pub fn route(&self, request: &Request) -> Option<Result<Response, AppError>> {
@@ -108,32 +168,62 @@ impl Router {
         trace!("Middleware chain passed for request");
 
         let method = request.method.to_uppercase();
-        // Match against the path without query string so routes like "/_irondrop/upload?x=y" work
-        let path_only = if let Some(pos) = request.path.find('?') {
-            &request.path[..pos]
-        } else {
-            request.path.as_str()
-        };
-        for entry in &self.routes {
-            if entry.method != method {
-                continue;
-            }
-            let is_match = match entry.kind {
-                MatchKind::Exact => path_only == entry.path,
-                MatchKind::Prefix => path_only.starts_with(&entry.path),
-            };
-            if is_match {
-                debug!(
-                    "Route matched: {} {} ({:?})",
-                    entry.method, entry.path, entry.kind
-                );
-                return Some((entry.handler)(request));
+        let path_only = request.path.as_str();
+        let matched = self.find(&method, path_only).or_else(|| {
+            (method == "HEAD")
+                .then(|| self.find("GET", path_only))
+                .flatten()
+        });
+        if let Some(entry) = matched {
+            debug!(
+                "Route matched: {} {} ({:?})",
+                entry.method, entry.path, entry.kind
+            );
+            return Some((entry.handler)(request));
+        }
+
+        if let Some(allow) = self.allowed_methods(path_only) {
+            if method == "OPTIONS" {
+                debug!("Answering OPTIONS for {} with Allow: {}", path_only, allow);
+                let mut headers = HashMap::new();
+                headers.insert("Allow".to_string(), allow);
+                return Some(Ok(Response {
+                    status_code: 204,
+                    status_text: "No Content".to_string(),
+                    headers,
+                    body: ResponseBody::Text(String::new()),
+                }));
             }
+            debug!(
+                "Method {} not registered for {} (allowed: {})",
+                method, path_only, allow
+            );
+            return Some(Err(AppError::MethodNotAllowedFor(allow)));
+        }
+
+        if let Some((_, handler)) = self.fallbacks.iter().find(|(m, _)| *m == method) {
+            debug!("Fallback handler for: {} {}", method, path_only);
+            return Some(handler(request));
         }
 
         debug!("No route matched for: {} {}", request.method, request.path);
         None
     }
+
+    fn find(&self, method: &str, path_only: &str) -> Option<&RouteEntry> {
+        self.routes
+            .iter()
+            .find(|entry| entry.method == method && entry.matches(path_only))
+    }
+}
+
+impl RouteEntry {
+    fn matches(&self, path_only: &str) -> bool {
+        match self.kind {
+            MatchKind::Exact => path_only == self.path,
+            MatchKind::Prefix => path_only.starts_with(&self.path),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -142,12 +232,19 @@ mod tests {
     use crate::http::ResponseBody;
     use std::collections::HashMap;
 
-    fn dummy_request(method: &str, path: &str) -> Request {
+    fn dummy_request(method: &str, target: &str) -> Request {
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
         Request {
             method: method.to_string(),
             path: path.to_string(),
+            query,
             headers: HashMap::new(),
             body: None,
+            peer_addr: None,
+            forwarded_by: None,
         }
     }
 
@@ -211,7 +308,111 @@ mod tests {
         );
 
         let req = dummy_request("POST", "/onlyget");
-        assert!(router.route(&req).is_none());
+        match router.route(&req) {
+            Some(Err(AppError::MethodNotAllowedFor(allow))) => {
+                assert_eq!(allow, "OPTIONS, GET, HEAD")
+            }
+            other => panic!(
+                "expected 405, got {:?}",
+                other.map(|r| r.map(|r| r.status_code))
+            ),
+        }
+        assert!(router.route(&dummy_request("POST", "/other")).is_none());
+    }
+
+    #[test]
+    fn test_fallback_only_for_uncovered_paths() {
+        let mut router = Router::new();
+        let ok = |text: &'static str| -> Handler {
+            Box::new(move |_| {
+                Ok(Response {
+                    status_code: 200,
+                    status_text: text.into(),
+                    headers: HashMap::new(),
+                    body: ResponseBody::Text(String::new()),
+                })
+            })
+        };
+        router.register_exact("GET", "/_health", ok("health"));
+        router.register_fallback("put", ok("fallback"));
+
+        let resp = router
+            .route(&dummy_request("PUT", "/docs/new.txt"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(resp.status_text, "fallback");
+        assert!(matches!(
+            router.route(&dummy_request("PUT", "/_health")),
+            Some(Err(AppError::MethodNotAllowedFor(_)))
+        ));
+        assert_eq!(
+            router.allowed_methods("/_health").unwrap(),
+            "OPTIONS, GET, HEAD"
+        );
+        assert!(
+            router
+                .route(&dummy_request("DELETE", "/docs/new.txt"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_sent_hooks_see_every_response() {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut router = Router::new();
+        let hook_seen = seen.clone();
+        router.add_sent_hook(Box::new(move |method, path, query, status_code| {
+            hook_seen
+                .lock()
+                .unwrap()
+                .push(format!("{method} {path} {query:?} {status_code}"));
+        }));
+        router.response_sent("GET", "/a.txt", Some("download=1"), 200);
+        router.response_sent("HEAD", "/b.txt", None, 404);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                r#"GET /a.txt Some("download=1") 200"#,
+                "HEAD /b.txt None 404"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_head_and_options_use_registered_routes() {
+        let mut router = Router::new();
+        for method in ["GET", "POST"] {
+            router.register_exact(
+                method,
+                "/_irondrop/upload",
+                Box::new(|r| {
+                    Ok(Response {
+                        status_code: 200,
+                        status_text: r.method.clone(),
+                        headers: HashMap::new(),
+                        body: ResponseBody::Text("ok".into()),
+                    })
+                }),
+            );
+        }
+
+        let head = router
+            .route(&dummy_request("HEAD", "/_irondrop/upload"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(head.status_code, 200);
+
+        let options = router
+            .route(&dummy_request("OPTIONS", "/_irondrop/upload?upload_to=x"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.status_code, 204);
+        assert_eq!(
+            options.headers.get("Allow").map(String::as_str),
+            Some("OPTIONS, GET, HEAD, POST")
+        );
+        assert_eq!(router.allowed_methods("/_irondrop/missing"), None);
     }
 
     #[test]
@@ -0,0 +1,505 @@
+// SPDX-License-Identifier: MIT
+
+//! Recent-query suggestions and saved searches
+//!
+//! Every successful `/_irondrop/search` is remembered for the browser that
+//! sent it: the first search hands out an `irondrop_search` session cookie,
+//! and the last 20 distinct queries of that session are kept in memory.
+//!
+//! - `GET /_irondrop/search/suggest?q=re` - recent queries and saved searches
+//!   containing the text, newest first:
+//!   `{"recent":["report"],"saved":[{"name":"Q3 PDFs","query":"q=report&ext=pdf"}]}`
+//! - `GET /_irondrop/search/saved` - every saved search
+//! - `POST /_irondrop/search/saved?name=Q3%20PDFs&q=report&ext=pdf` - save (or
+//!   replace) a named search with its mode, scope, sort and filter parameters
+//! - `DELETE /_irondrop/search/saved?name=Q3%20PDFs` - remove one
+//!
+//! Saved searches are shared by everyone who can reach the server and are
+//! stored per served directory in `~/.config/irondrop/saved-searches/`, as a
+//! flat JSON object mapping each name to its query string.
+
+use crate::error::AppError;
+use crate::http::{Request, Response, ResponseBody};
+use crate::search::{SearchFilters, SearchMode, SearchScope, SearchSort, SortOrder, fold_key};
+use crate::utils::{json_escape, parse_json_string_map, percent_encode_query};
+use log::{debug, info, warn};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Route for listing, saving and deleting named searches
+pub const SAVED_SEARCHES_ROUTE: &str = "/_irondrop/search/saved";
+
+/// Route for autocomplete suggestions
+pub const SUGGEST_ROUTE: &str = "/_irondrop/search/suggest";
+
+/// Cookie identifying a browser's recent queries
+pub const SESSION_COOKIE: &str = "irondrop_search";
+
+/// Recent queries kept per session
+pub const MAX_RECENT_QUERIES: usize = 20;
+
+/// Sessions remembered at once; the least recently used is dropped first
+const MAX_SESSIONS: usize = 1000;
+
+/// Most saved searches per served directory
+pub const MAX_SAVED_SEARCHES: usize = 100;
+
+/// Longest accepted name of a saved search, in characters
+pub const MAX_SAVED_NAME_CHARS: usize = 64;
+
+/// Suggestions returned per kind
+const MAX_SUGGESTIONS: usize = 10;
+
+/// Search parameters stored with a saved search, in this order
+const SAVED_PARAMS: [&str; 13] = [
+    "q",
+    "path",
+    "mount",
+    "mode",
+    "scope",
+    "sort",
+    "order",
+    "type",
+    "ext",
+    "min_size",
+    "max_size",
+    "modified_after",
+    "modified_before",
+];
+
+struct RecentSession {
+    last_used: u64,
+    /// Newest first
+    queries: VecDeque<String>,
+}
+
+#[derive(Default)]
+struct RecentQueries {
+    sessions: HashMap<String, RecentSession>,
+    clock: u64,
+}
+
+static RECENT_QUERIES: Mutex<Option<RecentQueries>> = Mutex::new(None);
+
+/// Remember `query` as the newest search of `session`
+pub fn record_recent_query(session: &str, query: &str) {
+    let query = query.trim();
+    if query.is_empty() {
+        return;
+    }
+    let Ok(mut recent) = RECENT_QUERIES.lock() else {
+        return;
+    };
+    let recent = recent.get_or_insert_with(RecentQueries::default);
+    recent.clock += 1;
+    let now = recent.clock;
+
+    if !recent.sessions.contains_key(session)
+        && recent.sessions.len() >= MAX_SESSIONS
+        && let Some(oldest) = recent
+            .sessions
+            .iter()
+            .min_by_key(|(_, s)| s.last_used)
+            .map(|(id, _)| id.clone())
+    {
+        recent.sessions.remove(&oldest);
+    }
+    let entry = recent
+        .sessions
+        .entry(session.to_string())
+        .or_insert_with(|| RecentSession {
+            last_used: now,
+            queries: VecDeque::new(),
+        });
+    entry.last_used = now;
+    entry.queries.retain(|existing| existing != query);
+    entry.queries.push_front(query.to_string());
+    entry.queries.truncate(MAX_RECENT_QUERIES);
+}
+
+/// Recent queries of `session` containing `text` (ignoring case and
+/// accents), newest first
+pub fn recent_queries(session: &str, text: &str) -> Vec<String> {
+    let needle = fold_key(text.trim());
+    let Ok(recent) = RECENT_QUERIES.lock() else {
+        return Vec::new();
+    };
+    recent
+        .as_ref()
+        .and_then(|recent| recent.sessions.get(session))
+        .map(|s| {
+            s.queries
+                .iter()
+                .filter(|query| fold_key(query).contains(&needle))
+                .take(MAX_SUGGESTIONS)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Session named by the request's cookie, if it carries a well-formed one
+pub fn session_from_request(request: &Request) -> Option<String> {
+    request
+        .headers
+        .get("cookie")?
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| value.len() == 32 && value.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Record the query of a successful search in the caller's session, handing
+/// out a session cookie on `response` when the request had none
+pub fn remember_search(request: &Request, response: &mut Response) {
+    if response.status_code != 200 {
+        return;
+    }
+    let Some(query) = request.query_params().remove("q") else {
+        return;
+    };
+    let session = session_from_request(request).unwrap_or_else(|| {
+        let session = crate::upload_session::generate_session_id();
+        response.headers.insert(
+            "Set-Cookie".to_string(),
+            format!("{SESSION_COOKIE}={session}; Path=/; HttpOnly; SameSite=Lax"),
+        );
+        session
+    });
+    record_recent_query(&session, &query);
+}
+
+/// Location of the saved searches for `base_dir`
+pub fn default_saved_searches_path(base_dir: &Path) -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    let index = crate::search::default_index_path(base_dir)?;
+    Some(
+        Path::new(&home)
+            .join(".config")
+            .join("irondrop")
+            .join("saved-searches")
+            .join(format!("{}.json", index.file_stem()?.to_string_lossy())),
+    )
+}
+
+/// Named searches persisted for one served directory
+pub struct SavedSearches {
+    /// `None` keeps them in memory only
+    path: Option<PathBuf>,
+    searches: Mutex<BTreeMap<String, String>>,
+}
+
+impl SavedSearches {
+    /// Saved searches stored at `path`; a missing or unreadable file starts
+    /// out empty
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let searches = path
+            .as_deref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| {
+                let parsed = parse_json_string_map(&content);
+                if parsed.is_none() {
+                    warn!("Ignoring malformed saved searches file");
+                }
+                parsed
+            })
+            .map(|map| map.into_iter().collect())
+            .unwrap_or_default();
+        Self {
+            path,
+            searches: Mutex::new(searches),
+        }
+    }
+
+    /// (name, query string) of every saved search, by name
+    pub fn list(&self) -> Vec<(String, String)> {
+        self.searches
+            .lock()
+            .map(|searches| {
+                searches
+                    .iter()
+                    .map(|(name, query)| (name.clone(), query.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Save the search in `params` as `name`, replacing one of the same name.
+    /// Returns the stored query string.
+    pub fn save(&self, name: &str, params: &HashMap<String, String>) -> Result<String, AppError> {
+        let name = name.trim();
+        if name.is_empty()
+            || name.chars().count() > MAX_SAVED_NAME_CHARS
+            || name.chars().any(char::is_control)
+        {
+            debug!("Rejected saved search name '{name}'");
+            return Err(AppError::BadRequest);
+        }
+        let query = canonical_query(params)?;
+
+        let mut searches = self
+            .searches
+            .lock()
+            .map_err(|_| AppError::InternalServerError("Saved searches unavailable".into()))?;
+        if !searches.contains_key(name) && searches.len() >= MAX_SAVED_SEARCHES {
+            debug!("Refusing more than {MAX_SAVED_SEARCHES} saved searches");
+            return Err(AppError::BadRequest);
+        }
+        let mut updated = searches.clone();
+        updated.insert(name.to_string(), query.clone());
+        self.write(&updated)?;
+        *searches = updated;
+        info!("Saved search '{name}': {query}");
+        Ok(query)
+    }
+
+    /// Remove the search named `name`; `false` when there was none
+    pub fn remove(&self, name: &str) -> Result<bool, AppError> {
+        let mut searches = self
+            .searches
+            .lock()
+            .map_err(|_| AppError::InternalServerError("Saved searches unavailable".into()))?;
+        if !searches.contains_key(name.trim()) {
+            return Ok(false);
+        }
+        let mut updated = searches.clone();
+        updated.remove(name.trim());
+        self.write(&updated)?;
+        *searches = updated;
+        Ok(true)
+    }
+
+    fn write(&self, searches: &BTreeMap<String, String>) -> Result<(), AppError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if searches.is_empty() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(AppError::from(e)),
+                _ => Ok(()),
+            };
+        }
+        let body = searches
+            .iter()
+            .map(|(name, query)| format!(r#""{}":"{}""#, json_escape(name), json_escape(query)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let temp = path.with_extension("json.tmp");
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&temp, format!("{{{body}}}\n")))
+            .and_then(|()| fs::rename(&temp, path));
+        result.map_err(|e| {
+            warn!("Failed to store saved searches in {}: {e}", path.display());
+            AppError::from(e)
+        })
+    }
+}
+
+/// Query string of the search parameters in `params`, after checking them as
+/// the search API would
+fn canonical_query(params: &HashMap<String, String>) -> Result<String, AppError> {
+    let query_len = params.get("q").map_or(0, |q| q.len());
+    if !(2..=100).contains(&query_len) {
+        debug!("Saved search needs a query of 2 to 100 bytes");
+        return Err(AppError::BadRequest);
+    }
+    let valid = params
+        .get("mode")
+        .is_none_or(|v| SearchMode::parse(v).is_some())
+        && params
+            .get("scope")
+            .is_none_or(|v| SearchScope::parse(v).is_some())
+        && params
+            .get("sort")
+            .is_none_or(|v| SearchSort::parse(v).is_some())
+        && params
+            .get("order")
+            .is_none_or(|v| SortOrder::parse(v).is_some())
+        && params
+            .get("mount")
+            .is_none_or(|v| crate::search::is_valid_mount_name(v));
+    if !valid {
+        debug!("Saved search has an unknown mode, scope, sort, order or mount");
+        return Err(AppError::BadRequest);
+    }
+    SearchFilters::from_query(params)?;
+
+    Ok(SAVED_PARAMS
+        .iter()
+        .filter_map(|&key| {
+            params
+                .get(key)
+                .map(|value| format!("{key}={}", percent_encode_query(value)))
+        })
+        .collect::<Vec<_>>()
+        .join("&"))
+}
+
+fn saved_json(name: &str, query: &str) -> String {
+    format!(
+        r#"{{"name":"{}","query":"{}"}}"#,
+        json_escape(name),
+        json_escape(query)
+    )
+}
+
+fn json_response(status_code: u16, status_text: &str, body: String) -> Response {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Response {
+        status_code,
+        status_text: status_text.to_string(),
+        headers,
+        body: ResponseBody::Text(body),
+    }
+}
+
+/// `GET`, `POST` or `DELETE` on [`SAVED_SEARCHES_ROUTE`]
+pub fn handle_saved_searches_request(
+    request: &Request,
+    saved: &SavedSearches,
+) -> Result<Response, AppError> {
+    let params = request.query_params();
+    match request.method.as_str() {
+        "POST" => {
+            let name = params.get("name").ok_or(AppError::BadRequest)?;
+            let query = saved.save(name, &params)?;
+            Ok(json_response(
+                201,
+                "Created",
+                saved_json(name.trim(), &query),
+            ))
+        }
+        "DELETE" => {
+            let name = params.get("name").ok_or(AppError::BadRequest)?;
+            if saved.remove(name)? {
+                Ok(json_response(200, "OK", r#"{"deleted":true}"#.to_string()))
+            } else {
+                Err(AppError::NotFound)
+            }
+        }
+        _ => {
+            let items: Vec<String> = saved
+                .list()
+                .iter()
+                .map(|(name, query)| saved_json(name, query))
+                .collect();
+            Ok(json_response(
+                200,
+                "OK",
+                format!(r#"{{"saved":[{}]}}"#, items.join(",")),
+            ))
+        }
+    }
+}
+
+/// `GET` on [`SUGGEST_ROUTE`]: the caller's recent queries and the saved
+/// searches whose name or query contains `q`
+pub fn handle_suggest_request(
+    request: &Request,
+    saved: &SavedSearches,
+) -> Result<Response, AppError> {
+    let text = request.query_params().remove("q").unwrap_or_default();
+    let recent = session_from_request(request)
+        .map(|session| recent_queries(&session, &text))
+        .unwrap_or_default();
+    let needle = fold_key(text.trim());
+    let matching: Vec<String> = saved
+        .list()
+        .iter()
+        .filter(|(name, query)| {
+            fold_key(name).contains(&needle) || fold_key(query).contains(&needle)
+        })
+        .take(MAX_SUGGESTIONS)
+        .map(|(name, query)| saved_json(name, query))
+        .collect();
+    Ok(json_response(
+        200,
+        "OK",
+        format!(
+            r#"{{"recent":[{}],"saved":[{}]}}"#,
+            recent
+                .iter()
+                .map(|query| format!(r#""{}""#, json_escape(query)))
+                .collect::<Vec<_>>()
+                .join(","),
+            matching.join(",")
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_recent_queries_per_session() {
+        let (a, b) = ("a".repeat(32), "b".repeat(32));
+        for query in ["report", "résumé", "notes", "report"] {
+            record_recent_query(&a, query);
+        }
+        record_recent_query(&b, "secret");
+        assert_eq!(recent_queries(&a, ""), ["report", "notes", "résumé"]);
+        assert_eq!(recent_queries(&a, "RESU"), ["résumé"]);
+        assert!(recent_queries(&a, "secret").is_empty());
+
+        for i in 0..MAX_RECENT_QUERIES + 5 {
+            record_recent_query(&b, &format!("query {i}"));
+        }
+        assert_eq!(recent_queries(&b, "query").len(), MAX_SUGGESTIONS);
+        assert!(recent_queries(&b, "secret").is_empty());
+    }
+
+    #[test]
+    fn test_saved_searches_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("saved.json");
+        let saved = SavedSearches::load(Some(path.clone()));
+
+        let query = saved
+            .save(
+                " Q3 PDFs ",
+                &params(&[
+                    ("name", "Q3 PDFs"),
+                    ("ext", "pdf"),
+                    ("q", "q3 report"),
+                    ("token", "ignored"),
+                ]),
+            )
+            .unwrap();
+        assert_eq!(query, "q=q3%20report&ext=pdf");
+        for bad in [
+            params(&[("q", "x")]),
+            params(&[("q", "report"), ("mode", "fuzzy")]),
+            params(&[("q", "report"), ("min_size", "lots")]),
+        ] {
+            assert!(saved.save("bad", &bad).is_err());
+        }
+        assert!(saved.save("", &params(&[("q", "report")])).is_err());
+
+        let reloaded = SavedSearches::load(Some(path.clone()));
+        assert_eq!(
+            reloaded.list(),
+            [("Q3 PDFs".to_string(), "q=q3%20report&ext=pdf".to_string())]
+        );
+        assert!(reloaded.remove("Q3 PDFs").unwrap());
+        assert!(!reloaded.remove("Q3 PDFs").unwrap());
+        assert!(!path.exists());
+    }
+}
@@ -9,9 +9,25 @@
 //! - Radix-accelerated index: Sorted arrays instead of HashMap/BTreeMap
 //! - Bit-packed data: Every bit counts for memory efficiency
 //! - Cache-aligned structures: Optimize for CPU cache lines
+//! - Incremental updates: filesystem notifications patch changed entries in
+//!   place; periodic full rebuilds remain the fallback, built beside the live
+//!   index and swapped in whole (memory briefly doubles while they run)
+//! - Persistent snapshots: large indexes are saved to disk and reloaded at
+//!   startup when still current (see `persist`)
+//! - Parallel builds: a few threads read directories while one inserts their
+//!   entries, so IDs and parent links stay consistent
+//!
+//! Optional content index (`--search-content`):
+//! - Small text files are tokenized into a sorted inverted index
+//! - Queries with `scope=content` or `scope=both` match words inside files
+
+mod persist;
+
+pub use persist::{INDEX_FORMAT_VERSION, PERSIST_MIN_ENTRIES, default_index_path};
 
 use crate::error::AppError;
-use crate::utils::is_hidden_file;
+use crate::fs::{IgnoreRules, ignore_rules};
+use crate::watcher::{FsEvent, FsWatcher};
 use log::{debug, error, info, trace, warn};
 use std::collections::{HashMap, VecDeque};
 use std::fs;
@@ -31,6 +47,552 @@ pub struct SearchResult {
     pub file_type: String,
     pub score: f32,
     pub last_modified: Option<u64>,
+    /// Size in bytes (0 for directories), used for sorting
+    pub size_bytes: u64,
+    /// Byte ranges of `name` the query matched, in order and not
+    /// overlapping; empty for content matches
+    pub matched_ranges: Vec<(usize, usize)>,
+    /// Mount the result belongs to; `path` is relative to it
+    pub mount: String,
+}
+
+impl SearchResult {
+    /// `name` escaped for HTML with the matched ranges wrapped in `<mark>`
+    pub fn highlighted_name(&self) -> String {
+        let mut highlighted = String::with_capacity(self.name.len() + 13);
+        let mut pos = 0;
+        for &(start, end) in &self.matched_ranges {
+            let (Some(before), Some(matched)) =
+                (self.name.get(pos..start), self.name.get(start..end))
+            else {
+                continue;
+            };
+            highlighted.push_str(&crate::templates::html_escape(before));
+            highlighted.push_str("<mark>");
+            highlighted.push_str(&crate::templates::html_escape(matched));
+            highlighted.push_str("</mark>");
+            pos = end;
+        }
+        highlighted.push_str(&crate::templates::html_escape(
+            self.name.get(pos..).unwrap_or_default(),
+        ));
+        highlighted
+    }
+}
+
+/// Field search results are ordered by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchSort {
+    #[default]
+    Score,
+    Name,
+    Size,
+    Modified,
+}
+
+impl SearchSort {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "score" | "relevance" => Some(Self::Score),
+            "name" => Some(Self::Name),
+            "size" => Some(Self::Size),
+            "modified" | "mtime" => Some(Self::Modified),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Score => "score",
+            Self::Name => "name",
+            Self::Size => "size",
+            Self::Modified => "modified",
+        }
+    }
+
+    /// Natural direction when no `order` is given: best score first, names A-Z
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            Self::Score => SortOrder::Desc,
+            Self::Name | Self::Size | Self::Modified => SortOrder::Asc,
+        }
+    }
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "asc" => Some(Self::Asc),
+            "desc" => Some(Self::Desc),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+}
+
+/// What a query is matched against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    #[default]
+    Name,
+    Content,
+    Both,
+}
+
+impl SearchScope {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(Self::Name),
+            "content" => Some(Self::Content),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Content => "content",
+            Self::Both => "both",
+        }
+    }
+
+    fn includes_names(self) -> bool {
+        matches!(self, Self::Name | Self::Both)
+    }
+
+    fn includes_content(self) -> bool {
+        matches!(self, Self::Content | Self::Both)
+    }
+}
+
+/// How a query is matched against names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Case-insensitive substring with fuzzy relevance scoring
+    #[default]
+    Substring,
+    /// Shell-style wildcards (`*`, `?`, `[...]`) matched against the whole name
+    Glob,
+    /// Regular expression found anywhere in the name
+    Regex,
+}
+
+impl SearchMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "substring" | "text" => Some(Self::Substring),
+            "glob" => Some(Self::Glob),
+            "regex" => Some(Self::Regex),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Substring => "substring",
+            Self::Glob => "glob",
+            Self::Regex => "regex",
+        }
+    }
+}
+
+/// Most `*` wildcards in a glob; each one adds a level of backtracking
+pub const MAX_GLOB_WILDCARDS: usize = 8;
+
+/// Compiled size limit for regex programs and their lazy DFA
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Deepest group or repetition nesting accepted in a regex
+const REGEX_NEST_LIMIT: u32 = 16;
+
+/// Compiled regexes kept for repeated queries
+const REGEX_CACHE_CAPACITY: usize = 64;
+
+/// Score given to glob and regex matches, which have no notion of relevance
+const PATTERN_MATCH_SCORE: f32 = 50.0;
+
+static REGEX_CACHE: Mutex<Option<HashMap<String, Arc<regex::Regex>>>> = Mutex::new(None);
+
+/// A query compiled for its [`SearchMode`]; names are matched
+/// case-insensitively in every mode, and substrings also ignore accents
+#[derive(Debug, Clone)]
+pub enum NameMatcher {
+    /// `folded` is the query after [`fold_key`]
+    Substring {
+        folded: String,
+    },
+    Glob(glob::Pattern),
+    Regex(Arc<regex::Regex>),
+}
+
+impl NameMatcher {
+    /// Compile `query`; an invalid or too expensive pattern is a `400`
+    pub fn new(query: &str, mode: SearchMode) -> Result<Self, AppError> {
+        match mode {
+            SearchMode::Substring => Ok(Self::substring(query)),
+            SearchMode::Glob => {
+                // Names have no separators, so `**` means the same as `*`
+                let mut collapsed = query.to_string();
+                while collapsed.contains("**") {
+                    collapsed = collapsed.replace("**", "*");
+                }
+                if collapsed.matches('*').count() > MAX_GLOB_WILDCARDS {
+                    debug!("Glob has more than {MAX_GLOB_WILDCARDS} wildcards: '{query}'");
+                    return Err(AppError::BadRequest);
+                }
+                glob::Pattern::new(&collapsed).map(Self::Glob).map_err(|e| {
+                    debug!("Invalid glob '{query}': {e}");
+                    AppError::BadRequest
+                })
+            }
+            SearchMode::Regex => cached_regex(query).map(Self::Regex),
+        }
+    }
+
+    pub fn substring(query: &str) -> Self {
+        Self::Substring {
+            folded: fold_key(query),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Substring { folded } if name.is_ascii() => {
+                contains_case_insensitive_ascii(name, folded)
+            }
+            Self::Substring { folded } => fold_key(name).contains(folded.as_str()),
+            Self::Glob(pattern) => pattern.matches_with(
+                name,
+                glob::MatchOptions {
+                    case_sensitive: false,
+                    require_literal_separator: false,
+                    require_literal_leading_dot: false,
+                },
+            ),
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+
+    /// Byte ranges of `name` that match, for highlighting: every occurrence
+    /// of a substring query, each regex match, or the whole name for a glob
+    pub fn match_ranges(&self, name: &str) -> Vec<(usize, usize)> {
+        match self {
+            Self::Substring { folded } if folded.is_empty() => Vec::new(),
+            Self::Substring { folded } if name.is_ascii() => {
+                let (haystack, needle) = (name.as_bytes(), folded.as_bytes());
+                let mut ranges = Vec::new();
+                let mut pos = 0;
+                while pos + needle.len() <= haystack.len() {
+                    if haystack[pos..pos + needle.len()].eq_ignore_ascii_case(needle) {
+                        ranges.push((pos, pos + needle.len()));
+                        pos += needle.len();
+                    } else {
+                        pos += 1;
+                    }
+                }
+                ranges
+            }
+            Self::Substring { folded } => {
+                // Fold one character at a time, remembering which span of
+                // `name` each folded byte came from
+                let mut folded_name = String::with_capacity(name.len());
+                let mut spans: Vec<(usize, usize)> = Vec::new();
+                let mut span_of_byte = Vec::with_capacity(name.len());
+                let mut buf = [0u8; 4];
+                for (start, c) in name.char_indices() {
+                    let end = start + c.len_utf8();
+                    let piece = fold_key(c.encode_utf8(&mut buf));
+                    if piece.is_empty() {
+                        // Combining marks belong to the character before them
+                        if let Some(last) = spans.last_mut() {
+                            last.1 = end;
+                        }
+                        continue;
+                    }
+                    spans.push((start, end));
+                    span_of_byte.extend(std::iter::repeat_n(spans.len() - 1, piece.len()));
+                    folded_name.push_str(&piece);
+                }
+
+                let mut ranges: Vec<(usize, usize)> = Vec::new();
+                for (start, matched) in folded_name.match_indices(folded.as_str()) {
+                    let first = spans[span_of_byte[start]];
+                    let last = spans[span_of_byte[start + matched.len() - 1]];
+                    match ranges.last_mut() {
+                        // Both halves of an expansion such as "ß" -> "ss"
+                        Some(previous) if first.0 < previous.1 => previous.1 = last.1,
+                        _ => ranges.push((first.0, last.1)),
+                    }
+                }
+                ranges
+            }
+            Self::Glob(_) if self.matches(name) => vec![(0, name.len())],
+            Self::Glob(_) => Vec::new(),
+            Self::Regex(regex) => regex
+                .find_iter(name)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+        }
+    }
+
+    /// Relevance of a matching `name`
+    fn score(&self, name: &str) -> f32 {
+        match self {
+            Self::Substring { folded } => {
+                calculate_relevance_score_with_query_lower(&fold_key(name), folded)
+            }
+            Self::Glob(_) | Self::Regex(_) => PATTERN_MATCH_SCORE,
+        }
+    }
+
+    /// Radix bucket most likely to hold matches, searched first
+    fn first_byte(&self) -> Option<u8> {
+        match self {
+            Self::Substring { folded } => folded.as_bytes().first().copied(),
+            Self::Glob(_) | Self::Regex(_) => None,
+        }
+    }
+}
+
+/// Compile `pattern` within the size and nesting limits, reusing earlier
+/// compilations of the same pattern
+fn cached_regex(pattern: &str) -> Result<Arc<regex::Regex>, AppError> {
+    if let Ok(cache) = REGEX_CACHE.lock()
+        && let Some(regex) = cache.as_ref().and_then(|cache| cache.get(pattern))
+    {
+        return Ok(regex.clone());
+    }
+
+    let regex = regex::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .dfa_size_limit(REGEX_SIZE_LIMIT)
+        .nest_limit(REGEX_NEST_LIMIT)
+        .build()
+        .map(Arc::new)
+        .map_err(|e| {
+            debug!("Rejected search regex '{pattern}': {e}");
+            AppError::BadRequest
+        })?;
+
+    if let Ok(mut cache) = REGEX_CACHE.lock() {
+        let cache = cache.get_or_insert_with(HashMap::new);
+        if cache.len() >= REGEX_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), regex.clone());
+    }
+    Ok(regex)
+}
+
+/// Kind of entry a search is restricted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+}
+
+impl EntryKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "file" => Some(Self::File),
+            "dir" | "directory" => Some(Self::Dir),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Dir => "dir",
+        }
+    }
+}
+
+/// Restrictions applied to matches before they become results
+///
+/// Sizes are in bytes, with directories counting as 0; timestamps are Unix
+/// seconds, `modified_after` inclusive and `modified_before` exclusive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilters {
+    pub kind: Option<EntryKind>,
+    /// Lowercase extensions without the dot; any of them matches
+    pub extensions: Vec<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub modified_after: Option<u64>,
+    pub modified_before: Option<u64>,
+}
+
+impl SearchFilters {
+    /// Filters from the `type`, `ext`, `min_size`, `max_size`,
+    /// `modified_after` and `modified_before` query parameters
+    pub fn from_query(params: &HashMap<String, String>) -> Result<Self, AppError> {
+        let invalid = |name: &str, value: &str| {
+            debug!("Invalid search filter {name}='{value}'");
+            AppError::BadRequest
+        };
+        let size = |name: &str| {
+            params
+                .get(name)
+                .map(|value| {
+                    crate::config::ini_parser::parse_file_size(value)
+                        .ok_or_else(|| invalid(name, value))
+                })
+                .transpose()
+        };
+        let time = |name: &str| {
+            params
+                .get(name)
+                .map(|value| parse_filter_time(value).ok_or_else(|| invalid(name, value)))
+                .transpose()
+        };
+
+        let kind = params
+            .get("type")
+            .map(|value| EntryKind::parse(value).ok_or_else(|| invalid("type", value)))
+            .transpose()?;
+        let mut extensions: Vec<String> = params
+            .get("ext")
+            .map(|list| {
+                list.split(',')
+                    .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|ext| !ext.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        extensions.sort();
+        extensions.dedup();
+
+        Ok(Self {
+            kind,
+            extensions,
+            min_size: size("min_size")?,
+            max_size: size("max_size")?,
+            modified_after: time("modified_after")?,
+            modified_before: time("modified_before")?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Distinguishes cached results of differently filtered queries
+    fn cache_key(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        format!(
+            "{}|{}|{:?}|{:?}|{:?}|{:?}",
+            self.kind.map_or("", EntryKind::as_str),
+            self.extensions.join(","),
+            self.min_size,
+            self.max_size,
+            self.modified_after,
+            self.modified_before
+        )
+    }
+
+    /// Check an entry whose size and modification time are only known to lie
+    /// within inclusive bounds. `None` means the bounds straddle a limit and
+    /// the exact values are needed.
+    fn check(
+        &self,
+        is_dir: bool,
+        name: &str,
+        size: (u64, u64),
+        modified: (u64, u64),
+    ) -> Option<bool> {
+        if let Some(kind) = self.kind
+            && (kind == EntryKind::Dir) != is_dir
+        {
+            return Some(false);
+        }
+        if !self.extensions.is_empty() {
+            let extension = Path::new(name)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase());
+            if is_dir || extension.is_none_or(|ext| self.extensions.binary_search(&ext).is_err()) {
+                return Some(false);
+            }
+        }
+
+        let mut decided = true;
+        // `min`/`max` are inclusive limits on a value within `bounds`
+        let mut limit = |bounds: (u64, u64), min: Option<u64>, max: Option<u64>| {
+            let min = min.unwrap_or(0);
+            let max = max.unwrap_or(u64::MAX);
+            if bounds.1 < min || bounds.0 > max {
+                return false;
+            }
+            if bounds.0 < min || bounds.1 > max {
+                decided = false;
+            }
+            true
+        };
+        let size = if is_dir { (0, 0) } else { size };
+        let in_range = limit(size, self.min_size, self.max_size)
+            && limit(
+                modified,
+                self.modified_after,
+                self.modified_before.map(|before| before.saturating_sub(1)),
+            );
+        if !in_range {
+            Some(false)
+        } else if decided {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a result with exact size and timestamp passes
+    pub fn matches(&self, result: &SearchResult) -> bool {
+        let has_dates = self.modified_after.is_some() || self.modified_before.is_some();
+        let modified = match result.last_modified {
+            Some(secs) => (secs, secs),
+            None if has_dates => return false,
+            None => (0, 0),
+        };
+        self.check(
+            result.file_type == "directory",
+            &result.name,
+            (result.size_bytes, result.size_bytes),
+            modified,
+        ) == Some(true)
+    }
+}
+
+/// Unix seconds, or a `YYYY-MM-DD` date taken as midnight UTC
+fn parse_filter_time(value: &str) -> Option<u64> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    let mut parts = value.splitn(3, '-');
+    let year: i32 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = crate::utils::days_from_civil(year, month, day);
+    u64::try_from(days).ok().map(|days| days * 86_400)
 }
 
 /// Search parameters
@@ -41,8 +603,32 @@ pub struct SearchParams {
     pub limit: usize,
     pub offset: usize,
     pub case_sensitive: bool,
+    pub sort: SearchSort,
+    pub order: SortOrder,
+    pub scope: SearchScope,
+    pub mode: SearchMode,
+    pub filters: SearchFilters,
+    /// Only search this mount (`?mount=`), `None` for all of them
+    pub mount: Option<String>,
+}
+
+/// One page of sorted search results
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    /// Matches found, at most [`SEARCH_RESULT_WINDOW`]
+    pub total: usize,
+    /// `true` when more matches exist than the window holds
+    pub total_capped: bool,
+    /// Offset of the next page, `None` on the last page
+    pub next_offset: Option<usize>,
+    /// State of the name index when the search ran
+    pub index_state: IndexState,
 }
 
+/// Most matches considered for sorting and counting per query
+pub const SEARCH_RESULT_WINDOW: usize = 1000;
+
 /// LRU Cache for search results
 pub struct SearchCache {
     cache: HashMap<String, CachedSearchResult>,
@@ -213,6 +799,9 @@ mod search_cache_tests {
             file_type: "file".to_string(),
             score: 1.0,
             last_modified: None,
+            size_bytes: 0,
+            matched_ranges: Vec::new(),
+            mount: DEFAULT_MOUNT.to_string(),
         }]);
 
         cache.put("k".to_string(), results.clone());
@@ -244,6 +833,8 @@ pub struct UltraLowMemoryIndex {
 
     /// Metadata and tracking
     last_update: Instant,
+    /// Filesystem changes up to this moment are reflected in the index
+    synced_at: SystemTime,
     base_dir: PathBuf,
     entry_count: AtomicUsize,
     memory_usage: AtomicU64,
@@ -253,6 +844,28 @@ pub struct UltraLowMemoryIndex {
 
     /// Update tracking for incremental updates
     is_updating: AtomicBool,
+
+    /// Entries tombstoned by incremental updates since the last rebuild
+    removed_count: usize,
+
+    /// Exact size of every entry, by entry ID, when `--search-exact-sizes` is
+    /// on; otherwise sizes come from the log2 field of the entry
+    exact_sizes: Option<Vec<u64>>,
+
+    /// (entry ID, string pool offset) of the [`fold_key`] of every non-ASCII
+    /// name, sorted by entry ID; ASCII names are compared as they are
+    folded_names: Vec<(u32, u32)>,
+
+    /// Posting lists of the trigrams in every folded name, built when they
+    /// fit in `trigram_budget` bytes
+    trigrams: Option<TrigramIndex>,
+    trigram_budget: u64,
+
+    /// Build progress, shared with [`ConcurrentUltraLowMemoryIndex`]
+    progress: Arc<IndexProgress>,
+
+    /// Exclusions and depth limits applied while walking
+    scope: Arc<IndexScope>,
 }
 
 /// Ultra-compact entry structure - exactly 11 bytes per entry
@@ -279,6 +892,165 @@ struct StringPoolEntry {
     offset: u32, // 4 bytes - supports 4GB string pool
 }
 
+/// Trigram posting lists for substring queries of three bytes or more
+///
+/// Every entry is listed under each distinct 3-byte window of its folded
+/// name, so only entries holding all trigrams of the query need comparing.
+struct TrigramIndex {
+    /// Sorted (trigram, start in `postings`) pairs
+    keys: Vec<(u32, u32)>,
+    /// Entry IDs per trigram, ascending
+    postings: Vec<u32>,
+    /// Entries from this ID on were added by incremental updates and are
+    /// not in the posting lists
+    indexed_until: u32,
+}
+
+/// Default memory allowed for the trigram index
+pub const DEFAULT_TRIGRAM_BUDGET: u64 = 32 * 1024 * 1024;
+
+/// Folder levels indexed below the served directory unless configured
+pub const DEFAULT_MAX_DEPTH: usize = 25;
+
+/// Part of the tree the search indexes cover
+///
+/// `exclude` globs follow the `ignore_patterns` rules of [`IgnoreRules`] but
+/// only keep entries out of the indexes, so they can still be browsed and
+/// downloaded. Folders up to `max_depth` levels below the served directory
+/// are read; a depth limit such as `media:2` caps the levels read inside that
+/// folder instead, the most specific limit winning.
+#[derive(Debug, Clone)]
+pub struct IndexScope {
+    patterns: Vec<String>,
+    exclude: IgnoreRules,
+    max_depth: usize,
+    /// (folder relative to the served directory, levels), longest first
+    depth_limits: Vec<(PathBuf, usize)>,
+}
+
+impl Default for IndexScope {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            exclude: IgnoreRules::new(Path::new(""), false, &[]).unwrap_or_default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth_limits: Vec::new(),
+        }
+    }
+}
+
+impl PartialEq for IndexScope {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns == other.patterns
+            && self.max_depth == other.max_depth
+            && self.depth_limits == other.depth_limits
+    }
+}
+
+impl Eq for IndexScope {}
+
+impl IndexScope {
+    pub fn new(
+        base_dir: &Path,
+        exclude: &[String],
+        max_depth: usize,
+        mut depth_limits: Vec<(PathBuf, usize)>,
+    ) -> Result<Self, AppError> {
+        let patterns: Vec<String> = exclude
+            .iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        depth_limits.sort_by(|a, b| {
+            b.0.components()
+                .count()
+                .cmp(&a.0.components().count())
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        depth_limits.dedup_by(|a, b| a.0 == b.0);
+        Ok(Self {
+            exclude: IgnoreRules::new(base_dir, false, &patterns)?,
+            patterns,
+            max_depth,
+            depth_limits,
+        })
+    }
+
+    /// Scope from `--search-exclude`, `--search-max-depth` and
+    /// `--search-depth-limits`
+    pub fn from_cli(cli: &crate::cli::Cli, base_dir: &Path) -> Result<Self, AppError> {
+        let exclude: Vec<String> = cli
+            .search_exclude
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .map(str::to_string)
+            .collect();
+        let depth_limits = parse_depth_limits(cli.search_depth_limits.as_deref().unwrap_or(""))
+            .map_err(AppError::InvalidConfiguration)?;
+        Self::new(
+            base_dir,
+            &exclude,
+            cli.search_max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+            depth_limits,
+        )
+    }
+
+    /// Whether the entries of `dir` (relative to the served directory) are
+    /// within the depth limits
+    pub fn indexes_children_of(&self, dir: &Path) -> bool {
+        let (below, levels) = self
+            .depth_limits
+            .iter()
+            .find(|(limited, _)| dir.starts_with(limited))
+            .map_or(
+                (dir.components().count(), self.max_depth),
+                |(limited, levels)| {
+                    (
+                        dir.components().count() - limited.components().count(),
+                        *levels,
+                    )
+                },
+            );
+        below < levels
+    }
+
+    /// Whether `relative` or a folder above it is excluded
+    pub fn excludes(&self, relative: &Path) -> bool {
+        !self.patterns.is_empty() && self.exclude.excludes(relative)
+    }
+
+    /// Whether the entry `name` at `path` is excluded, given that its parent
+    /// was not
+    pub fn excludes_entry(&self, path: &Path, name: &str) -> bool {
+        !self.patterns.is_empty() && self.exclude.excludes_entry(path, name)
+    }
+}
+
+/// Parse depth limits such as `"media:2,archive/old:1"`
+pub fn parse_depth_limits(list: &str) -> Result<Vec<(PathBuf, usize)>, String> {
+    let mut limits = Vec::new();
+    for item in list
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let invalid = || format!("Invalid depth limit '{item}' (use e.g. media:2)");
+        let (dir, levels) = item.rsplit_once(':').ok_or_else(invalid)?;
+        let dir = Path::new(dir.trim().trim_matches('/'));
+        if dir.as_os_str().is_empty()
+            || !dir
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            return Err(invalid());
+        }
+        let levels = levels.trim().parse().map_err(|_| invalid())?;
+        limits.push((dir.to_path_buf(), levels));
+    }
+    Ok(limits)
+}
+
 /// Radix index bucket for first-byte acceleration
 #[derive(Default)]
 struct RadixBucket {
@@ -294,6 +1066,83 @@ struct IndexedDirEntry {
     modified: SystemTime,
 }
 
+/// Output of a directory reader thread
+enum WalkBatch {
+    /// Entries read from the directory with this entry ID
+    Entries(u32, Vec<IndexedDirEntry>),
+    /// One queued directory is finished
+    Done,
+}
+
+/// Most threads reading directories during an index build
+const MAX_WALK_WORKERS: usize = 4;
+
+fn walk_worker_count() -> usize {
+    thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .clamp(1, MAX_WALK_WORKERS)
+}
+
+/// Read `dir` and send its entries in batches of up to 1000
+fn read_directory_batches(
+    dir: &Path,
+    parent_id: u32,
+    base_dir: &Path,
+    scope: &IndexScope,
+    progress: &IndexProgress,
+    tx: &mpsc::SyncSender<WalkBatch>,
+) {
+    progress.directory_started();
+
+    // Depth limits also prevent excessive recursion
+    let relative = dir.strip_prefix(base_dir).unwrap_or(dir);
+    if !scope.indexes_children_of(relative) {
+        return;
+    }
+
+    let dir_entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("Failed to read directory {dir:?}: {e}");
+            return;
+        }
+    };
+
+    let mut batch = Vec::with_capacity(1000);
+    for entry in dir_entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let file_path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        // Skip dotfiles, ignore patterns and search exclusions
+        if ignore_rules().excludes_entry(&file_path, &file_name)
+            || scope.excludes_entry(&file_path, &file_name)
+        {
+            continue;
+        }
+
+        batch.push(IndexedDirEntry {
+            path: file_path,
+            name: file_name,
+            size: metadata.len(),
+            is_dir: metadata.is_dir(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+        if batch.len() >= 1000
+            && tx
+                .send(WalkBatch::Entries(parent_id, std::mem::take(&mut batch)))
+                .is_err()
+        {
+            return;
+        }
+    }
+    if !batch.is_empty() {
+        let _ = tx.send(WalkBatch::Entries(parent_id, batch));
+    }
+}
+
 /// Cache-aligned memory pool for ultra-efficient string storage
 /// Single continuous buffer eliminates pointer chasing and fragmentation
 struct UnifiedStringPool {
@@ -308,6 +1157,7 @@ struct UnifiedStringPool {
 
 // Constants for ultra-compact bit packing
 const FLAG_IS_DIR: u32 = 1 << 31; // Top bit for directory flag
+const FLAG_REMOVED: u32 = 1 << 30; // Tombstone left by incremental updates
 
 const TIMESTAMP_MASK: u32 = 0x3FFF_FFFF; // 30 bits for timestamp (34 years from 2024)
 const PARENT_NULL: u32 = 0xFF_FF_FF; // Special value for root entries
@@ -382,17 +1232,46 @@ impl UltraCompactEntry {
         }
     }
 
+    /// Smallest and largest size the log2 encoding may stand for
+    fn size_bounds(&self) -> (u64, u64) {
+        match self.size_log2 {
+            0 => (0, 0),
+            bits if bits >= 64 => (1u64 << 63, u64::MAX),
+            bits => (1u64 << (bits - 1), (1u64 << bits) - 1),
+        }
+    }
+
     /// Check if entry is directory
     fn is_dir(&self) -> bool {
         (self.packed_data & FLAG_IS_DIR) != 0
     }
 
+    /// Check if entry was removed since the last rebuild
+    fn is_removed(&self) -> bool {
+        (self.packed_data & FLAG_REMOVED) != 0
+    }
+
+    fn mark_removed(&mut self) {
+        self.packed_data |= FLAG_REMOVED;
+    }
+
     /// Extract modification time
     fn modified_time(&self) -> SystemTime {
         let base_epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200); // 2024-01-01
         let timestamp_secs = self.packed_data & TIMESTAMP_MASK;
         base_epoch + Duration::from_secs(timestamp_secs as u64)
     }
+
+    /// Earliest and latest modification time in Unix seconds; times outside
+    /// the packed range were clamped to its ends
+    fn modified_bounds(&self) -> (u64, u64) {
+        const BASE_EPOCH: u64 = 1_704_067_200;
+        match self.packed_data & TIMESTAMP_MASK {
+            0 => (0, BASE_EPOCH),
+            TIMESTAMP_MASK => (BASE_EPOCH + TIMESTAMP_MASK as u64, u64::MAX),
+            secs => (BASE_EPOCH + secs as u64, BASE_EPOCH + secs as u64),
+        }
+    }
 }
 
 impl UnifiedStringPool {
@@ -602,6 +1481,54 @@ fn murmur3_hash(data: &[u8]) -> u32 {
     hash
 }
 
+/// Case- and accent-insensitive form of `text` used to compare names:
+/// lowercase, with combining marks dropped and accented Latin letters and
+/// ligatures spelled without them ("Résumé" and "RESUME" both fold to
+/// "resume", "Straße" to "strasse")
+pub fn fold_key(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_ascii_lowercase();
+    }
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let replacement = match c {
+            // Combining diacritical marks, as left by decomposed names
+            '\u{0300}'..='\u{036f}' => "",
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+            'ď' | 'đ' | 'ð' => "d",
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+            'ĥ' | 'ħ' => "h",
+            'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+            'ĵ' => "j",
+            'ķ' => "k",
+            'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+            'ñ' | 'ń' | 'ņ' | 'ň' | 'ŉ' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+            'ŕ' | 'ŗ' | 'ř' => "r",
+            'ś' | 'ŝ' | 'ş' | 'š' | 'ſ' => "s",
+            'ţ' | 'ť' | 'ŧ' => "t",
+            'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+            'ŵ' => "w",
+            'ý' | 'ÿ' | 'ŷ' => "y",
+            'ź' | 'ż' | 'ž' => "z",
+            'æ' => "ae",
+            'œ' => "oe",
+            'ß' => "ss",
+            'þ' => "th",
+            'ĳ' => "ij",
+            'ς' => "σ",
+            _ => {
+                folded.push(c);
+                continue;
+            }
+        };
+        folded.push_str(replacement);
+    }
+    folded
+}
+
 fn contains_case_insensitive_ascii(haystack: &str, needle_lower: &str) -> bool {
     let needle = needle_lower.as_bytes();
     if needle.is_empty() {
@@ -615,13 +1542,71 @@ fn contains_case_insensitive_ascii(haystack: &str, needle_lower: &str) -> bool {
     })
 }
 
-impl RadixBucket {
-    /// Add entry to bucket during bulk index construction.
-    fn add_entry(&mut self, entry_id: u32) {
-        self.entries.push(entry_id);
-    }
+/// Distinct trigrams of `text` into `out`, sorted
+fn collect_trigrams(text: &str, out: &mut Vec<u32>) {
+    out.clear();
+    out.extend(
+        text.as_bytes()
+            .windows(3)
+            .map(|w| u32::from_be_bytes([0, w[0], w[1], w[2]])),
+    );
+    out.sort_unstable();
+    out.dedup();
+}
 
-    /// Search bucket for entries matching criteria
+impl TrigramIndex {
+    /// Entries that may contain `folded_query`, ascending; `None` when the
+    /// query is too short to narrow anything down
+    fn candidates(&self, folded_query: &str) -> Option<Vec<u32>> {
+        let mut trigrams = Vec::new();
+        collect_trigrams(folded_query, &mut trigrams);
+        if trigrams.is_empty() {
+            return None;
+        }
+        let mut lists = Vec::with_capacity(trigrams.len());
+        for trigram in trigrams {
+            let Some(list) = self.postings_of(trigram) else {
+                return Some(Vec::new());
+            };
+            lists.push(list);
+        }
+        lists.sort_unstable_by_key(|list| list.len());
+        let mut ids = lists[0].to_vec();
+        for list in &lists[1..] {
+            ids.retain(|id| list.binary_search(id).is_ok());
+            if ids.is_empty() {
+                break;
+            }
+        }
+        Some(ids)
+    }
+
+    fn postings_of(&self, trigram: u32) -> Option<&[u32]> {
+        let idx = self
+            .keys
+            .binary_search_by_key(&trigram, |&(key, _)| key)
+            .ok()?;
+        let start = self.keys[idx].1 as usize;
+        let end = self
+            .keys
+            .get(idx + 1)
+            .map_or(self.postings.len(), |&(_, next)| next as usize);
+        Some(&self.postings[start..end])
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.keys.capacity() * std::mem::size_of::<(u32, u32)>()
+            + self.postings.capacity() * std::mem::size_of::<u32>()
+    }
+}
+
+impl RadixBucket {
+    /// Add entry to bucket during bulk index construction.
+    fn add_entry(&mut self, entry_id: u32) {
+        self.entries.push(entry_id);
+    }
+
+    /// Search bucket for entries matching criteria
     fn search(&self) -> &[u32] {
         &self.entries
     }
@@ -647,14 +1632,128 @@ impl UltraLowMemoryIndex {
             entries: Vec::with_capacity(estimated_entries),
             directory_children: Vec::with_capacity(estimated_entries / 10), // ~10% directories
             last_update: Instant::now(),
+            synced_at: SystemTime::UNIX_EPOCH,
             base_dir,
             entry_count: AtomicUsize::new(0),
             memory_usage: AtomicU64::new(0),
             root_entry_id: u32::MAX, // Will be set during first build
             is_updating: AtomicBool::new(false),
+            removed_count: 0,
+            exact_sizes: None,
+            folded_names: Vec::new(),
+            trigrams: None,
+            trigram_budget: DEFAULT_TRIGRAM_BUDGET,
+            progress: Arc::default(),
+            scope: Arc::default(),
+        }
+    }
+
+    /// Empty index with the same directory, settings and progress tracker,
+    /// for building a replacement
+    fn empty_like(&self) -> Self {
+        let mut fresh = Self::new(self.base_dir.clone());
+        fresh.set_exact_sizes(self.has_exact_sizes());
+        fresh.trigram_budget = self.trigram_budget;
+        fresh.scope = self.scope.clone();
+        fresh.progress = self.progress.clone();
+        fresh
+    }
+
+    /// Limit indexing to `scope`; applies from the next build
+    pub fn set_scope(&mut self, scope: IndexScope) {
+        self.scope = Arc::new(scope);
+    }
+
+    /// Build a trigram index for substring queries when it needs at most
+    /// `bytes` (0 disables it); applies from the next build
+    pub fn set_trigram_budget(&mut self, bytes: u64) {
+        self.trigram_budget = bytes;
+    }
+
+    pub fn has_trigram_index(&self) -> bool {
+        self.trigrams.is_some()
+    }
+
+    /// Keep exact sizes next to the compact entries (8 more bytes per entry)
+    /// instead of rounding them to a power of two; applies from the next build
+    pub fn set_exact_sizes(&mut self, enabled: bool) {
+        if enabled != self.exact_sizes.is_some() {
+            self.exact_sizes = enabled.then(Vec::new);
+        }
+    }
+
+    pub fn has_exact_sizes(&self) -> bool {
+        self.exact_sizes.is_some()
+    }
+
+    /// Append an entry named `name`, recording its exact size when enabled
+    /// and the folded form of a non-ASCII name
+    fn push_entry(&mut self, entry: UltraCompactEntry, name: &str, size: u64) {
+        let entry_id = self.entries.len() as u32;
+        if !name.is_ascii() {
+            let offset = self.add_string(&fold_key(name));
+            self.folded_names.push((entry_id, offset));
+        }
+        self.entries.push(entry);
+        // Stays aligned with the entries even when enabled on a built index
+        if let Some(sizes) = self.exact_sizes.as_mut()
+            && sizes.len() + 1 == self.entries.len()
+        {
+            sizes.push(size);
+        }
+    }
+
+    /// [`fold_key`] of the entry's `name`, from the pool when recorded
+    fn folded_name<'a>(&'a self, entry_id: u32, name: &'a str) -> std::borrow::Cow<'a, str> {
+        if name.is_ascii() {
+            return std::borrow::Cow::Owned(name.to_ascii_lowercase());
+        }
+        self.folded_names
+            .binary_search_by_key(&entry_id, |&(id, _)| id)
+            .ok()
+            .and_then(|idx| self.get_string(self.folded_names[idx].1))
+            .map_or_else(
+                || std::borrow::Cow::Owned(fold_key(name)),
+                std::borrow::Cow::Borrowed,
+            )
+    }
+
+    /// Whether the entry's `name` matches, using the stored folded key for
+    /// substring queries instead of folding every candidate
+    fn name_matches(&self, matcher: &NameMatcher, entry_id: u32, name: &str) -> bool {
+        match matcher {
+            NameMatcher::Substring { folded } if !name.is_ascii() => {
+                self.folded_name(entry_id, name).contains(folded.as_str())
+            }
+            _ => matcher.matches(name),
         }
     }
 
+    /// Size of an entry: exact when recorded, otherwise rounded down to a
+    /// power of two
+    fn entry_size(&self, entry_id: u32) -> u64 {
+        let (low, high) = self.entry_size_bounds(entry_id);
+        if low == high {
+            return low;
+        }
+        self.entries
+            .get(entry_id as usize)
+            .map_or(0, UltraCompactEntry::get_size)
+    }
+
+    fn entry_size_bounds(&self, entry_id: u32) -> (u64, u64) {
+        if let Some(&size) = self
+            .exact_sizes
+            .as_ref()
+            .and_then(|sizes| sizes.get(entry_id as usize))
+        {
+            return (size, size);
+        }
+        self.entries
+            .get(entry_id as usize)
+            .map_or((0, 0), UltraCompactEntry::size_bounds)
+    }
+
     /// Add string to unified pool and return offset
     fn add_string(&mut self, s: &str) -> u32 {
         let offset = self.string_pool.add_string(s);
@@ -679,11 +1778,20 @@ impl UltraLowMemoryIndex {
         let radix_size: usize = self.radix_index.iter().map(|b| b.memory_usage()).sum();
         let directory_children_size =
             self.directory_children.capacity() * std::mem::size_of::<Vec<u32>>();
+        let exact_sizes_size = self
+            .exact_sizes
+            .as_ref()
+            .map_or(0, |sizes| sizes.capacity() * std::mem::size_of::<u64>());
+        let folded_names_size = self.folded_names.capacity() * std::mem::size_of::<(u32, u32)>();
+        let trigrams_size = self.trigrams.as_ref().map_or(0, TrigramIndex::memory_usage);
 
         (entries_size
             + string_pool_size as usize
             + radix_size
             + directory_children_size
+            + exact_sizes_size
+            + folded_names_size
+            + trigrams_size
             + std::mem::size_of::<Self>()) as u64
     }
 
@@ -717,6 +1825,10 @@ impl UltraLowMemoryIndex {
 
         if entries_capacity_ratio > 2.0 {
             self.entries.shrink_to_fit();
+            if let Some(sizes) = self.exact_sizes.as_mut() {
+                sizes.shrink_to_fit();
+            }
+            self.folded_names.shrink_to_fit();
             debug!(
                 "Shrunk entries vector capacity (ratio was {:.1}x)",
                 entries_capacity_ratio
@@ -771,7 +1883,9 @@ impl UltraLowMemoryIndex {
         );
 
         // Always perform full rebuild for ultra-low memory efficiency
+        self.progress.begin();
         let result = self.rebuild_index_ultra_optimized();
+        self.progress.finish(result.is_ok());
 
         self.last_update = Instant::now();
         self.is_updating.store(false, Ordering::Release);
@@ -806,6 +1920,13 @@ impl UltraLowMemoryIndex {
         self.entries.clear();
         self.entries.shrink_to_fit();
         self.entries.reserve(100_000); // Reserve reasonable initial capacity
+        if let Some(sizes) = self.exact_sizes.as_mut() {
+            sizes.clear();
+            sizes.shrink_to_fit();
+        }
+        self.folded_names.clear();
+        self.folded_names.shrink_to_fit();
+        self.trigrams = None;
 
         self.directory_children.clear();
         self.directory_children.shrink_to_fit();
@@ -814,98 +1935,108 @@ impl UltraLowMemoryIndex {
         self.entry_count.store(0, Ordering::Relaxed);
         self.memory_usage.store(0, Ordering::Relaxed);
         self.root_entry_id = u32::MAX;
+        self.removed_count = 0;
 
         info!("Index cleared with memory shrinking for long-run efficiency");
     }
 
-    /// Ultra-efficient directory walking with hierarchical parent tracking
+    /// Walk `dir` below `parent_entry_id` with a small pool of reader threads
+    ///
+    /// Workers only read directories. Every batch is inserted on this thread,
+    /// so entry IDs stay sequential and each subdirectory is queued with the
+    /// ID it was just given, keeping the parent-ID hierarchy intact.
     fn walk_directory_hierarchical(
         &mut self,
         dir: &Path,
         parent_entry_id: u32,
-        depth: usize,
     ) -> Result<(), AppError> {
-        // Prevent excessive recursion
-        if depth > 25 {
-            return Ok(());
-        }
-
-        // Check memory and entry limits for ultra-low memory target
-        let current_entries = self.entry_count.load(Ordering::Relaxed);
-        if current_entries >= 10_000_000 {
-            warn!("Directory index limit reached (10M entries)");
-            return Ok(());
-        }
-
-        // Check memory usage (limit for large directories)
-        let memory_usage = self.get_memory_usage();
-        if memory_usage > 1_073_741_824 {
-            // 1GB safety margin
-            warn!("Memory usage limit reached (1GB), stopping indexing");
-            return Ok(());
-        }
-
-        let dir_entries = match fs::read_dir(dir) {
-            Ok(entries) => entries,
-            Err(e) => {
-                debug!("Failed to read directory {dir:?}: {e}");
-                return Ok(());
-            }
-        };
-
-        let mut batch_entries = Vec::with_capacity(1000);
-        let mut subdirs = Vec::with_capacity(100);
-
-        // Collect entries in this directory
-        for entry_result in dir_entries {
-            let entry = match entry_result {
-                Ok(e) => e,
-                Err(_) => continue,
-            };
-
-            let metadata = match entry.metadata() {
-                Ok(m) => m,
-                Err(_) => continue,
-            };
-
-            let file_path = entry.path();
-            let file_name = entry.file_name().to_string_lossy().to_string();
-            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
-
-            // Skip hidden files (starting with '._' or '.DS_Store')
-            if is_hidden_file(&file_name) {
-                continue;
+        let (job_tx, job_rx) = mpsc::channel::<(PathBuf, u32)>();
+        let job_rx = Mutex::new(job_rx);
+        let workers = walk_worker_count();
+        // Bounded so readers cannot run far ahead of the inserts
+        let (batch_tx, batch_rx) = mpsc::sync_channel::<WalkBatch>(workers * 4);
+        let stop = AtomicBool::new(false);
+        let base_dir = self.base_dir.clone();
+        let scope = self.scope.clone();
+        let progress = self.progress.clone();
+
+        thread::scope(|s| {
+            for _ in 0..workers {
+                let batch_tx = batch_tx.clone();
+                let (job_rx, stop) = (&job_rx, &stop);
+                let (base_dir, scope, progress) = (&base_dir, &scope, &progress);
+                s.spawn(move || {
+                    loop {
+                        let job = match job_rx.lock() {
+                            Ok(job_rx) => job_rx.recv(),
+                            Err(_) => break,
+                        };
+                        let Ok((dir, parent_id)) = job else {
+                            break;
+                        };
+                        if !stop.load(Ordering::Relaxed) {
+                            read_directory_batches(
+                                &dir, parent_id, base_dir, scope, progress, &batch_tx,
+                            );
+                        }
+                        if batch_tx.send(WalkBatch::Done).is_err() {
+                            break;
+                        }
+                    }
+                });
             }
+            drop(batch_tx);
 
-            batch_entries.push(IndexedDirEntry {
-                path: file_path,
-                name: file_name,
-                size: metadata.len(),
-                is_dir: metadata.is_dir(),
-                modified,
-            });
-
-            // Process batch when it's full
-            if batch_entries.len() >= 1000 {
-                subdirs.extend(
-                    self.process_entry_batch_hierarchical(&mut batch_entries, parent_entry_id)?,
-                );
+            let mut result = Ok(());
+            let mut pending = 1usize;
+            let _ = job_tx.send((dir.to_path_buf(), parent_entry_id));
+            while pending > 0 {
+                let Ok(batch) = batch_rx.recv() else {
+                    break;
+                };
+                let (parent_id, mut entries) = match batch {
+                    WalkBatch::Done => {
+                        pending -= 1;
+                        continue;
+                    }
+                    WalkBatch::Entries(parent_id, entries) => (parent_id, entries),
+                };
+                if stop.load(Ordering::Relaxed) {
+                    continue;
+                }
+                match self.process_entry_batch_hierarchical(&mut entries, parent_id) {
+                    Ok(subdirs) if !self.walk_limit_reached() => {
+                        pending += subdirs.len();
+                        for subdir in subdirs {
+                            let _ = job_tx.send(subdir);
+                        }
+                    }
+                    Ok(_) => stop.store(true, Ordering::Relaxed),
+                    Err(e) => {
+                        stop.store(true, Ordering::Relaxed);
+                        result = Err(e);
+                    }
+                }
             }
-        }
+            // Workers leave once the queue is closed and drained
+            drop(job_tx);
+            result
+        })
+    }
 
-        // Process remaining entries
-        if !batch_entries.is_empty() {
-            subdirs.extend(
-                self.process_entry_batch_hierarchical(&mut batch_entries, parent_entry_id)?,
-            );
+    /// Whether the index is too large to keep walking
+    fn walk_limit_reached(&self) -> bool {
+        // Check memory and entry limits for ultra-low memory target
+        if self.entry_count.load(Ordering::Relaxed) >= 10_000_000 {
+            warn!("Directory index limit reached (10M entries)");
+            return true;
         }
-
-        // Recursively process subdirectories with the entry IDs generated during batching.
-        for (subdir, subdir_entry_id) in subdirs {
-            self.walk_directory_hierarchical(&subdir, subdir_entry_id, depth + 1)?;
+        // 1GB safety margin for large directories
+        if self.get_memory_usage() > 1_073_741_824 {
+            warn!("Memory usage limit reached (1GB), stopping indexing");
+            return true;
         }
-
-        Ok(())
+        false
     }
 
     /// Process batch of entries with hierarchical parent references (ultra-memory efficient)
@@ -915,6 +2046,7 @@ impl UltraLowMemoryIndex {
         parent_entry_id: u32,
     ) -> Result<Vec<(PathBuf, u32)>, AppError> {
         let mut subdirs = Vec::new();
+        let batch_len = batch.len();
 
         for entry in batch.drain(..) {
             let entry_id = self.entries.len() as u32;
@@ -931,7 +2063,11 @@ impl UltraLowMemoryIndex {
                 entry.is_dir,
             );
 
-            self.entries.push(ultra_compact_entry);
+            self.push_entry(
+                ultra_compact_entry,
+                &entry.name,
+                if entry.is_dir { 0 } else { entry.size },
+            );
 
             // Update directory children mapping if parent exists
             if parent_entry_id != u32::MAX {
@@ -956,11 +2092,15 @@ impl UltraLowMemoryIndex {
             }
         }
 
+        self.progress.found(batch_len, subdirs.len());
         Ok(subdirs)
     }
 
     /// Rebuild the entire index from scratch (ultra-low memory optimized)
     fn rebuild_index_ultra_optimized(&mut self) -> Result<(), AppError> {
+        // Changes made while walking show up as newer directory mtimes
+        let started_at = SystemTime::now();
+
         // Clear existing data
         self.clear_index();
 
@@ -981,7 +2121,7 @@ impl UltraLowMemoryIndex {
             true, // Is directory
         );
 
-        self.entries.push(root_entry);
+        self.push_entry(root_entry, &root_name, 0);
         self.root_entry_id = 0;
         self.entry_count.store(1, Ordering::Relaxed);
 
@@ -990,15 +2130,172 @@ impl UltraLowMemoryIndex {
 
         // Walk directory hierarchy starting from root
         let base_dir = self.base_dir.clone();
-        self.walk_directory_hierarchical(&base_dir, self.root_entry_id, 0)?;
+        self.walk_directory_hierarchical(&base_dir, self.root_entry_id)?;
 
         // Build radix index for fast searching
         self.build_radix_index();
+        self.build_trigram_index();
         self.string_pool.finalize();
+        self.synced_at = started_at;
 
         Ok(())
     }
 
+    /// Find the live entry for an absolute path inside `base_dir`
+    fn find_entry(&self, path: &Path) -> Option<u32> {
+        if self.root_entry_id == u32::MAX {
+            return None;
+        }
+        let relative = path.strip_prefix(&self.base_dir).ok()?;
+        let mut current = self.root_entry_id;
+        for component in relative.components() {
+            let name = component.as_os_str().to_string_lossy();
+            current = self
+                .directory_children
+                .get(current as usize)?
+                .iter()
+                .copied()
+                .find(|&id| {
+                    self.entries.get(id as usize).is_some_and(|entry| {
+                        !entry.is_removed()
+                            && self.get_string(entry.get_name_offset()) == Some(&name)
+                    })
+                })?;
+        }
+        Some(current)
+    }
+
+    /// Tombstone an entry and everything below it
+    fn remove_subtree(&mut self, entry_id: u32) {
+        let parent_id = self.entries[entry_id as usize].get_parent_id();
+        if let Some(siblings) = self.directory_children.get_mut(parent_id as usize) {
+            siblings.retain(|&id| id != entry_id);
+        }
+
+        let mut pending = vec![entry_id];
+        while let Some(id) = pending.pop() {
+            if let Some(entry) = self.entries.get_mut(id as usize)
+                && !entry.is_removed()
+            {
+                entry.mark_removed();
+                self.removed_count += 1;
+                self.entry_count.fetch_sub(1, Ordering::Relaxed);
+            }
+            if let Some(children) = self.directory_children.get_mut(id as usize) {
+                pending.append(children);
+            }
+        }
+    }
+
+    /// Patch the index for watcher events instead of rebuilding it.
+    /// Returns `false` when only a full rebuild can bring it up to date.
+    pub fn apply_changes(&mut self, events: &[FsEvent]) -> bool {
+        if self.root_entry_id == u32::MAX {
+            return false;
+        }
+        for event in events {
+            let patched = match event {
+                FsEvent::Overflow => false,
+                FsEvent::Removed(path) => {
+                    if let Some(id) = self.find_entry(path)
+                        && id != self.root_entry_id
+                    {
+                        self.remove_subtree(id);
+                    }
+                    true
+                }
+                FsEvent::Changed(path) => self.apply_changed_path(path),
+            };
+            if !patched {
+                return false;
+            }
+        }
+
+        // Compact with a rebuild once tombstones make up a quarter of the index
+        if self.removed_count > 1000 && self.removed_count > self.entries.len() / 4 {
+            return false;
+        }
+        // New entries are scanned one by one until the trigrams are rebuilt
+        if let Some(trigrams) = &self.trigrams {
+            let unindexed = self.entries.len() - trigrams.indexed_until as usize;
+            if unindexed > 1000 && unindexed > self.entries.len() / 4 {
+                self.build_trigram_index();
+            }
+        }
+        self.string_pool.finalize();
+        self.last_update = Instant::now();
+        true
+    }
+
+    fn apply_changed_path(&mut self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.base_dir) else {
+            return true;
+        };
+        if relative.as_os_str().is_empty()
+            || ignore_rules().excludes(relative)
+            || self.scope.excludes(relative)
+            || !relative
+                .parent()
+                .is_some_and(|parent| self.scope.indexes_children_of(parent))
+        {
+            return true;
+        }
+
+        let existing = self.find_entry(path);
+        let Ok(metadata) = fs::metadata(path) else {
+            // Removed again before the event was handled
+            if let Some(id) = existing {
+                self.remove_subtree(id);
+            }
+            return true;
+        };
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+
+        if let Some(id) = existing {
+            let entry = self.entries[id as usize];
+            if entry.is_dir() == metadata.is_dir() {
+                self.entries[id as usize] = UltraCompactEntry::new(
+                    entry.get_name_offset(),
+                    entry.get_parent_id(),
+                    metadata.len(),
+                    modified,
+                    metadata.is_dir(),
+                );
+                if let Some(size) = self
+                    .exact_sizes
+                    .as_mut()
+                    .and_then(|sizes| sizes.get_mut(id as usize))
+                {
+                    *size = if metadata.is_dir() { 0 } else { metadata.len() };
+                }
+                return true;
+            }
+            self.remove_subtree(id);
+        }
+
+        let Some(parent_id) = path.parent().and_then(|parent| self.find_entry(parent)) else {
+            debug!("Parent of {} is not indexed", path.display());
+            return false;
+        };
+        let mut batch = vec![IndexedDirEntry {
+            path: path.to_path_buf(),
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            size: metadata.len(),
+            is_dir: metadata.is_dir(),
+            modified,
+        }];
+        match self.process_entry_batch_hierarchical(&mut batch, parent_id) {
+            Ok(subdirs) => subdirs
+                .into_iter()
+                .all(|(dir, id)| self.walk_directory_hierarchical(&dir, id).is_ok()),
+            Err(_) => false,
+        }
+    }
+
     /// Get comprehensive statistics about ultra-low memory usage
     pub fn get_ultra_memory_stats(&self) -> String {
         let entry_count = self.entry_count.load(Ordering::Relaxed);
@@ -1012,6 +2309,21 @@ impl UltraLowMemoryIndex {
         let entries_size = self.entries.len() * std::mem::size_of::<UltraCompactEntry>();
         let string_pool_size = self.string_pool.memory_usage();
         let radix_size: usize = self.radix_index.iter().map(|b| b.memory_usage()).sum();
+        let trigram_stats = match &self.trigrams {
+            Some(trigrams) => {
+                let size = trigrams.memory_usage();
+                format!(
+                    "{:.1} MB ({:.1}%)",
+                    size as f64 / 1_048_576.0,
+                    size as f64 / total_memory as f64 * 100.0
+                )
+            }
+            None if self.trigram_budget == 0 => "disabled".to_string(),
+            None => format!(
+                "not built (over the {:.1} MB budget)",
+                self.trigram_budget as f64 / 1_048_576.0
+            ),
+        };
 
         format!(
             "Ultra-Low Memory Index Stats:\n\
@@ -1020,6 +2332,8 @@ impl UltraLowMemoryIndex {
              - Entries: {:.1} MB ({:.1}%)\n\
              - String Pool: {:.1} MB ({:.1}%)\n\
              - Radix Index: {:.1} MB ({:.1}%)\n\
+             - Trigram Index: {}\n\
+             Sizes: {}\n\
              Target: <100MB for 10M entries (currently {:.1}% of target)",
             entry_count,
             memory_per_entry,
@@ -1030,6 +2344,12 @@ impl UltraLowMemoryIndex {
             string_pool_size as f64 / total_memory as f64 * 100.0,
             radix_size as f64 / 1_048_576.0,
             radix_size as f64 / total_memory as f64 * 100.0,
+            trigram_stats,
+            if self.has_exact_sizes() {
+                "exact"
+            } else {
+                "log2 (rounded to a power of two)"
+            },
             total_memory as f64 / 100_000_000.0 * 100.0
         )
     }
@@ -1062,11 +2382,102 @@ impl UltraLowMemoryIndex {
         info!("Radix index built in {:.2}s", start.elapsed().as_secs_f32());
     }
 
+    /// Build the trigram posting lists, unless they would take more than
+    /// `trigram_budget` bytes
+    fn build_trigram_index(&mut self) {
+        self.trigrams = None;
+        if self.trigram_budget == 0 {
+            return;
+        }
+        let start = Instant::now();
+
+        // First pass: size every posting list so the budget is checked
+        // before anything is allocated
+        let mut counts: HashMap<u32, u32> = HashMap::new();
+        let mut trigrams = Vec::new();
+        for entry_id in 0..self.entries.len() as u32 {
+            self.entry_trigrams(entry_id, &mut trigrams);
+            for &trigram in &trigrams {
+                *counts.entry(trigram).or_default() += 1;
+            }
+        }
+        let total: u64 = counts.values().map(|&count| u64::from(count)).sum();
+        let needed = total * std::mem::size_of::<u32>() as u64
+            + counts.len() as u64 * std::mem::size_of::<(u32, u32)>() as u64;
+        if needed > self.trigram_budget || total > u64::from(u32::MAX) {
+            info!(
+                "Skipping the trigram index: it needs {:.1} MB, the budget is {:.1} MB",
+                needed as f64 / 1_048_576.0,
+                self.trigram_budget as f64 / 1_048_576.0
+            );
+            return;
+        }
+
+        let mut keys: Vec<(u32, u32)> = counts.into_iter().collect();
+        keys.sort_unstable();
+        let mut next = 0;
+        for key in &mut keys {
+            let count = key.1;
+            key.1 = next;
+            next += count;
+        }
+        let mut fill: Vec<u32> = keys.iter().map(|&(_, start)| start).collect();
+        let mut postings = vec![0u32; total as usize];
+        for entry_id in 0..self.entries.len() as u32 {
+            self.entry_trigrams(entry_id, &mut trigrams);
+            for &trigram in &trigrams {
+                if let Ok(idx) = keys.binary_search_by_key(&trigram, |&(key, _)| key) {
+                    postings[fill[idx] as usize] = entry_id;
+                    fill[idx] += 1;
+                }
+            }
+        }
+
+        let trigrams = TrigramIndex {
+            keys,
+            postings,
+            indexed_until: self.entries.len() as u32,
+        };
+        info!(
+            "Trigram index built in {:.2}s ({} trigrams, {:.1} MB)",
+            start.elapsed().as_secs_f32(),
+            trigrams.keys.len(),
+            trigrams.memory_usage() as f64 / 1_048_576.0
+        );
+        self.trigrams = Some(trigrams);
+    }
+
+    /// Trigrams of a live entry's folded name into `out`
+    fn entry_trigrams(&self, entry_id: u32, out: &mut Vec<u32>) {
+        out.clear();
+        if let Some(entry) = self.entries.get(entry_id as usize)
+            && !entry.is_removed()
+            && let Some(name) = self.get_string(entry.get_name_offset())
+        {
+            collect_trigrams(&self.folded_name(entry_id, name), out);
+        }
+    }
+
     /// Ultra-fast search using radix acceleration and binary search
     pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        self.search_filtered(
+            &NameMatcher::substring(query),
+            limit,
+            &SearchFilters::default(),
+        )
+    }
+
+    /// [`Self::search`] for any [`SearchMode`], keeping only entries that
+    /// pass `filters`
+    pub fn search_filtered(
+        &self,
+        matcher: &NameMatcher,
+        limit: usize,
+        filters: &SearchFilters,
+    ) -> Vec<SearchResult> {
         debug!(
-            "UltraLowMemoryIndex search: query='{}', limit={}",
-            query, limit
+            "UltraLowMemoryIndex search: matcher={:?}, limit={}",
+            matcher, limit
         );
         trace!(
             "Index stats: {} entries, {} bytes memory",
@@ -1074,13 +2485,43 @@ impl UltraLowMemoryIndex {
             self.get_memory_usage()
         );
         let start = Instant::now();
-        let query_lower = query.to_lowercase();
-        let query_is_ascii = query.is_ascii();
         let mut candidate_ids = Vec::with_capacity(limit.saturating_mul(2));
+        let mut exhaustive = false;
 
+        // Strategy 0: only entries holding every trigram of the query, plus
+        // those added since the trigram index was built
+        if let NameMatcher::Substring { folded } = matcher
+            && let Some(trigrams) = &self.trigrams
+            && let Some(ids) = trigrams.candidates(folded)
+        {
+            let added = trigrams.indexed_until..self.entries.len() as u32;
+            // Names starting like the query go first, as with the radix buckets
+            let mut others = Vec::new();
+            for entry_id in ids.into_iter().chain(added) {
+                if candidate_ids.len() >= limit * 3 {
+                    break;
+                }
+                if let Some(entry) = self.entries.get(entry_id as usize)
+                    && !entry.is_removed()
+                    && let Some(name) = self.get_string(entry.get_name_offset())
+                    && self.name_matches(matcher, entry_id, name)
+                    && self.passes_filters(entry, entry_id, name, filters)
+                {
+                    if name.as_bytes().first().copied() == matcher.first_byte() {
+                        candidate_ids.push(entry_id);
+                    } else if others.len() < limit * 2 {
+                        others.push(entry_id);
+                    }
+                }
+            }
+            if candidate_ids.len() < limit {
+                let room = (limit * 2).saturating_sub(candidate_ids.len());
+                candidate_ids.extend(others.into_iter().take(room));
+            }
+            exhaustive = true;
+        }
         // Strategy 1: Radix-accelerated search using first character
-        if !query_lower.is_empty() {
-            let first_byte = query_lower.as_bytes()[0];
+        else if let Some(first_byte) = matcher.first_byte() {
             let bucket = &self.radix_index[first_byte as usize];
 
             // Search within the radix bucket for matching entries
@@ -1090,24 +2531,20 @@ impl UltraLowMemoryIndex {
                 }
 
                 if let Some(entry) = self.entries.get(entry_id as usize)
+                    && !entry.is_removed()
                     && let Some(name) = self.get_string(entry.get_name_offset())
+                    && self.name_matches(matcher, entry_id, name)
+                    && self.passes_filters(entry, entry_id, name, filters)
                 {
-                    let matches = if query_is_ascii {
-                        contains_case_insensitive_ascii(name, &query_lower)
-                    } else {
-                        name.to_lowercase().contains(&query_lower)
-                    };
-                    if matches {
-                        candidate_ids.push(entry_id);
-                    }
+                    candidate_ids.push(entry_id);
                 }
             }
         }
 
         // Strategy 2: If radix search is insufficient, search other buckets
-        if candidate_ids.len() < limit {
+        if !exhaustive && candidate_ids.len() < limit {
             for (bucket_idx, bucket) in self.radix_index.iter().enumerate() {
-                if bucket_idx == query_lower.as_bytes().first().copied().unwrap_or(0) as usize {
+                if matcher.first_byte().map(usize::from) == Some(bucket_idx) {
                     continue; // Already searched
                 }
 
@@ -1117,16 +2554,12 @@ impl UltraLowMemoryIndex {
                     }
 
                     if let Some(entry) = self.entries.get(entry_id as usize)
+                        && !entry.is_removed()
                         && let Some(name) = self.get_string(entry.get_name_offset())
+                        && self.name_matches(matcher, entry_id, name)
+                        && self.passes_filters(entry, entry_id, name, filters)
                     {
-                        let matches = if query_is_ascii {
-                            contains_case_insensitive_ascii(name, &query_lower)
-                        } else {
-                            name.to_lowercase().contains(&query_lower)
-                        };
-                        if matches {
-                            candidate_ids.push(entry_id);
-                        }
+                        candidate_ids.push(entry_id);
                     }
                 }
             }
@@ -1140,7 +2573,7 @@ impl UltraLowMemoryIndex {
                 break;
             }
 
-            if let Some(search_result) = self.create_search_result(entry_id, query) {
+            if let Some(search_result) = self.create_search_result(entry_id, matcher) {
                 results.push(search_result);
             }
         }
@@ -1162,8 +2595,48 @@ impl UltraLowMemoryIndex {
         results
     }
 
+    /// Check `filters` against the packed size and timestamp; only entries
+    /// too close to a limit for the compact encoding need their path rebuilt
+    /// and the file examined
+    fn passes_filters(
+        &self,
+        entry: &UltraCompactEntry,
+        entry_id: u32,
+        name: &str,
+        filters: &SearchFilters,
+    ) -> bool {
+        if filters.is_empty() {
+            return true;
+        }
+        let is_dir = entry.is_dir();
+        if let Some(decided) = filters.check(
+            is_dir,
+            name,
+            self.entry_size_bounds(entry_id),
+            entry.modified_bounds(),
+        ) {
+            return decided;
+        }
+        let Some(metadata) = self
+            .reconstruct_path(entry_id)
+            .and_then(|path| fs::metadata(path).ok())
+        else {
+            return false;
+        };
+        let size = metadata.len();
+        let Some(modified) = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+        else {
+            return false;
+        };
+        filters.check(is_dir, name, (size, size), (modified, modified)) == Some(true)
+    }
+
     /// Create SearchResult with on-demand path reconstruction from parent chain
-    fn create_search_result(&self, entry_id: u32, query: &str) -> Option<SearchResult> {
+    fn create_search_result(&self, entry_id: u32, matcher: &NameMatcher) -> Option<SearchResult> {
         let entry = self.entries.get(entry_id as usize)?;
         let name = self.get_string(entry.get_name_offset())?;
 
@@ -1191,7 +2664,13 @@ impl UltraLowMemoryIndex {
             clean_path.push('/');
         }
 
-        let score = self.calculate_optimized_relevance_score(name, query);
+        let score = match matcher {
+            NameMatcher::Substring { folded } => {
+                let name = self.folded_name(entry_id, name);
+                self.calculate_optimized_relevance_score(&name, folded)
+            }
+            _ => matcher.score(name),
+        };
 
         let modified_time = entry
             .modified_time()
@@ -1199,13 +2678,18 @@ impl UltraLowMemoryIndex {
             .ok()
             .map(|d| d.as_secs());
 
+        let size = if entry.is_dir() {
+            0
+        } else {
+            self.entry_size(entry_id)
+        };
         Some(SearchResult {
             name: name.to_string(),
             path: clean_path,
             size: if entry.is_dir() {
                 "-".to_string()
             } else {
-                format_file_size(entry.get_size())
+                format_file_size(size)
             },
             file_type: if entry.is_dir() {
                 "directory".to_string()
@@ -1214,6 +2698,9 @@ impl UltraLowMemoryIndex {
             },
             score,
             last_modified: modified_time,
+            size_bytes: size,
+            matched_ranges: matcher.match_ranges(name),
+            mount: DEFAULT_MOUNT.to_string(),
         })
     }
 
@@ -1365,37 +2852,442 @@ impl UltraLowMemoryIndex {
     }
 }
 
-/// Concurrent wrapper for ultra-low memory index
-pub struct ConcurrentUltraLowMemoryIndex {
-    index: Arc<RwLock<UltraLowMemoryIndex>>,
-    search_cache: Arc<Mutex<SearchCache>>,
-    update_in_progress: Arc<AtomicBool>,
+/// Whether the name index can answer searches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexState {
+    /// No complete index yet; searches walk the filesystem meanwhile
+    Building,
+    /// A rebuild replaces a complete index
+    Updating,
+    Ready,
 }
 
-impl ConcurrentUltraLowMemoryIndex {
-    pub fn new(base_dir: PathBuf) -> Self {
-        Self {
-            index: Arc::new(RwLock::new(UltraLowMemoryIndex::new(base_dir))),
-            search_cache: Arc::new(Mutex::new(SearchCache::new(1000))),
-            update_in_progress: Arc::new(AtomicBool::new(false)),
+impl IndexState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Building => "building",
+            Self::Updating => "updating",
+            Self::Ready => "ready",
         }
     }
+}
 
-    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>, AppError> {
-        debug!(
-            "ConcurrentUltraLowMemoryIndex search: query='{}', limit={}",
-            query, limit
-        );
-        let _start_time = Instant::now();
-        let cache_key = format!("{query}:{limit}");
-        debug!(
-            "Starting concurrent search for query: '{}' with limit: {}",
-            query, limit
-        );
-        trace!("Cache key generated: {}", cache_key);
+/// Progress of index builds, readable without taking the index lock
+#[derive(Default)]
+pub struct IndexProgress {
+    building: AtomicBool,
+    /// Set once a build finished or a snapshot was loaded
+    ready: AtomicBool,
+    started: Mutex<Option<Instant>>,
+    last_duration_ms: AtomicU64,
+    entries_scanned: AtomicUsize,
+    directories_scanned: AtomicUsize,
+    directories_remaining: AtomicUsize,
+    /// Entries found by the last complete build, to estimate the next one
+    last_total: AtomicUsize,
+}
 
-        // Try cache first (quick lock)
-        {
+/// Point-in-time view of [`IndexProgress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexStatus {
+    pub state: IndexState,
+    pub entries_scanned: usize,
+    pub directories_scanned: usize,
+    pub directories_remaining: usize,
+    /// Time spent on the running build, or on the last one when idle
+    pub elapsed: Duration,
+    /// Estimated time until the running build completes
+    pub eta: Option<Duration>,
+}
+
+impl IndexProgress {
+    fn begin(&self) {
+        self.entries_scanned.store(0, Ordering::Relaxed);
+        self.directories_scanned.store(0, Ordering::Relaxed);
+        self.directories_remaining.store(1, Ordering::Relaxed);
+        if let Ok(mut started) = self.started.lock() {
+            *started = Some(Instant::now());
+        }
+        self.building.store(true, Ordering::Release);
+    }
+
+    fn directory_started(&self) {
+        if self.building.load(Ordering::Acquire) {
+            self.directories_scanned.fetch_add(1, Ordering::Relaxed);
+            let _ = self.directories_remaining.fetch_update(
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+                |remaining| remaining.checked_sub(1),
+            );
+        }
+    }
+
+    fn found(&self, entries: usize, directories: usize) {
+        if self.building.load(Ordering::Acquire) {
+            self.entries_scanned.fetch_add(entries, Ordering::Relaxed);
+            self.directories_remaining
+                .fetch_add(directories, Ordering::Relaxed);
+        }
+    }
+
+    fn finish(&self, completed: bool) {
+        if let Ok(started) = self.started.lock()
+            && let Some(started) = *started
+        {
+            self.last_duration_ms
+                .store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+        }
+        if completed {
+            self.last_total.store(
+                self.entries_scanned.load(Ordering::Relaxed),
+                Ordering::Relaxed,
+            );
+            self.ready.store(true, Ordering::Release);
+        }
+        self.directories_remaining.store(0, Ordering::Relaxed);
+        self.building.store(false, Ordering::Release);
+    }
+
+    /// Loaded from a snapshot holding `entries`
+    fn mark_ready(&self, entries: usize) {
+        self.entries_scanned.store(entries, Ordering::Relaxed);
+        self.last_total.store(entries, Ordering::Relaxed);
+        self.ready.store(true, Ordering::Release);
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        let building = self.building.load(Ordering::Acquire);
+        let state = match (self.ready.load(Ordering::Acquire), building) {
+            (false, _) => IndexState::Building,
+            (true, true) => IndexState::Updating,
+            (true, false) => IndexState::Ready,
+        };
+        let scanned = self.entries_scanned.load(Ordering::Relaxed);
+        let directories_scanned = self.directories_scanned.load(Ordering::Relaxed);
+        let directories_remaining = self.directories_remaining.load(Ordering::Relaxed);
+        let elapsed = match self.started.lock().ok().and_then(|started| *started) {
+            Some(started) if building => started.elapsed(),
+            _ => Duration::from_millis(self.last_duration_ms.load(Ordering::Relaxed)),
+        };
+
+        // Scale the time so far by what the last build found, or else by the
+        // directories still queued
+        let last_total = self.last_total.load(Ordering::Relaxed);
+        let eta = if !building || scanned == 0 {
+            None
+        } else if last_total > scanned {
+            Some(elapsed.mul_f64((last_total - scanned) as f64 / scanned as f64))
+        } else if directories_scanned > 0 {
+            Some(elapsed.mul_f64(directories_remaining as f64 / directories_scanned as f64))
+        } else {
+            None
+        };
+
+        IndexStatus {
+            state,
+            entries_scanned: scanned,
+            directories_scanned,
+            directories_remaining,
+            elapsed,
+            eta,
+        }
+    }
+}
+
+/// Concurrent wrapper for ultra-low memory index
+pub struct ConcurrentUltraLowMemoryIndex {
+    index: Arc<RwLock<UltraLowMemoryIndex>>,
+    /// Shared with the index so builds can be followed while it is locked
+    progress: Arc<IndexProgress>,
+    search_cache: Arc<Mutex<SearchCache>>,
+    update_in_progress: Arc<AtomicBool>,
+    /// Watcher events that arrived while an update was running, replayed on
+    /// the index it produces
+    queued_changes: Mutex<Vec<FsEvent>>,
+    /// Set while a filesystem watcher keeps the index current
+    watching: Arc<AtomicBool>,
+    /// On-disk snapshot location, `None` when there is nowhere to store it
+    snapshot_path: Option<PathBuf>,
+    snapshot_state: Mutex<SnapshotState>,
+}
+
+#[derive(Default)]
+struct SnapshotState {
+    /// `synced_at` of the snapshot on disk
+    saved_synced_at: Option<SystemTime>,
+    last_saved: Option<Instant>,
+}
+
+/// Minimum time between snapshot writes outside of startup
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(600);
+
+impl ConcurrentUltraLowMemoryIndex {
+    pub fn new(base_dir: PathBuf) -> Self {
+        let index = UltraLowMemoryIndex::new(base_dir.clone());
+        Self {
+            snapshot_path: default_index_path(&base_dir),
+            snapshot_state: Mutex::new(SnapshotState::default()),
+            progress: index.progress.clone(),
+            index: Arc::new(RwLock::new(index)),
+            search_cache: Arc::new(Mutex::new(SearchCache::new(1000))),
+            update_in_progress: Arc::new(AtomicBool::new(false)),
+            queued_changes: Mutex::new(Vec::new()),
+            watching: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Record exact sizes; see [`UltraLowMemoryIndex::set_exact_sizes`]
+    pub fn with_exact_sizes(self, enabled: bool) -> Self {
+        if let Ok(mut index_guard) = self.index.write() {
+            index_guard.set_exact_sizes(enabled);
+        }
+        self
+    }
+
+    /// Memory allowed for the trigram index; see
+    /// [`UltraLowMemoryIndex::set_trigram_budget`]
+    pub fn with_trigram_budget(self, bytes: u64) -> Self {
+        if let Ok(mut index_guard) = self.index.write() {
+            index_guard.set_trigram_budget(bytes);
+        }
+        self
+    }
+
+    /// Exclusions and depth limits; see [`IndexScope`]
+    pub fn with_scope(self, scope: IndexScope) -> Self {
+        if let Ok(mut index_guard) = self.index.write() {
+            index_guard.set_scope(scope);
+        }
+        self
+    }
+
+    /// Progress of the running or last index build
+    pub fn index_status(&self) -> IndexStatus {
+        self.progress.status()
+    }
+
+    /// Whether a filesystem watcher replaces the periodic rebuild
+    pub fn is_watching(&self) -> bool {
+        self.watching.load(Ordering::Relaxed)
+    }
+
+    /// Load the on-disk snapshot when it is still current, otherwise rebuild
+    /// the index (and snapshot it when large enough)
+    pub fn load_or_build(&self) -> Result<(), AppError> {
+        if let Some(path) = self.snapshot_path.as_ref().filter(|p| p.exists())
+            && self
+                .update_in_progress
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        {
+            let loaded = self.load_snapshot(path);
+            self.update_in_progress.store(false, Ordering::Release);
+            if loaded {
+                return Ok(());
+            }
+        }
+        self.update_if_needed(true)?;
+        self.persist_if_due(true);
+        Ok(())
+    }
+
+    fn load_snapshot(&self, path: &Path) -> bool {
+        let start = Instant::now();
+        let (base_dir, exact_sizes, trigram_budget, scope) = match self.index.read() {
+            Ok(index_guard) => (
+                index_guard.base_dir.clone(),
+                index_guard.has_exact_sizes(),
+                index_guard.trigram_budget,
+                index_guard.scope.clone(),
+            ),
+            Err(_) => return false,
+        };
+        let mut loaded = match UltraLowMemoryIndex::load_from(path, &base_dir, scope) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                info!("Ignoring search index snapshot {}: {e}", path.display());
+                return false;
+            }
+        };
+        if loaded.has_exact_sizes() != exact_sizes {
+            info!("Search index snapshot stores sizes differently, rebuilding");
+            return false;
+        }
+        if let Some(dir) = loaded.find_stale_directory() {
+            info!(
+                "Search index snapshot is out of date ({} changed), rebuilding",
+                dir.display()
+            );
+            return false;
+        }
+        // Not part of the snapshot; rebuilt from the loaded names
+        loaded.set_trigram_budget(trigram_budget);
+        loaded.build_trigram_index();
+
+        let entries = loaded.get_entry_count();
+        let synced_at = loaded.synced_at;
+        loaded.progress = self.progress.clone();
+        self.replay_queued(&mut loaded);
+        match self.index.write() {
+            Ok(mut index_guard) => *index_guard = loaded,
+            Err(_) => return false,
+        }
+        self.progress.mark_ready(entries);
+        index_changed();
+        if let Ok(mut state) = self.snapshot_state.lock() {
+            state.saved_synced_at = Some(synced_at);
+            state.last_saved = Some(Instant::now());
+        }
+        if let Ok(mut cache) = self.search_cache.try_lock() {
+            cache.clear();
+        }
+        info!(
+            "Loaded search index snapshot with {} entries in {:.2}s",
+            entries,
+            start.elapsed().as_secs_f32()
+        );
+        true
+    }
+
+    /// Snapshot the index to disk when it changed since the last snapshot.
+    /// Without `force`, writes are spaced at least `SNAPSHOT_INTERVAL` apart.
+    pub fn persist_if_due(&self, force: bool) {
+        let Some(path) = &self.snapshot_path else {
+            return;
+        };
+        let Ok(mut state) = self.snapshot_state.lock() else {
+            return;
+        };
+        if !force
+            && state
+                .last_saved
+                .is_some_and(|t| t.elapsed() < SNAPSHOT_INTERVAL)
+        {
+            return;
+        }
+        let Ok(index_guard) = self.index.read() else {
+            return;
+        };
+        if index_guard.root_entry_id == u32::MAX
+            || index_guard.get_entry_count() < PERSIST_MIN_ENTRIES
+            || state.saved_synced_at == Some(index_guard.synced_at)
+        {
+            return;
+        }
+
+        let start = Instant::now();
+        match index_guard.save_to(path) {
+            Ok(()) => info!(
+                "Saved search index snapshot ({} entries) to {} in {:.2}s",
+                index_guard.get_entry_count(),
+                path.display(),
+                start.elapsed().as_secs_f32()
+            ),
+            Err(e) => warn!(
+                "Failed to save search index snapshot to {}: {e}",
+                path.display()
+            ),
+        }
+        state.saved_synced_at = Some(index_guard.synced_at);
+        state.last_saved = Some(Instant::now());
+    }
+
+    /// Patch the index from watcher events, rebuilding when patching is not
+    /// possible. `polled_at` is when the events were read; the index counts as
+    /// current up to then. While another update runs the events are queued and
+    /// replayed on the index it produces.
+    pub fn apply_changes(&self, events: &[FsEvent], polled_at: SystemTime) -> Result<(), AppError> {
+        let events = {
+            let mut queued = self
+                .queued_changes
+                .lock()
+                .map_err(|_| AppError::InternalServerError("Index lock poisoned".to_string()))?;
+            if events.is_empty() && queued.is_empty() {
+                return Ok(());
+            }
+            if self
+                .update_in_progress
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                queued.extend_from_slice(events);
+                if queued.len() > MAX_PENDING_EVENTS {
+                    *queued = vec![FsEvent::Overflow];
+                }
+                return Ok(());
+            }
+            // Left over when an update ended just after queueing them
+            let mut pending = std::mem::take(&mut *queued);
+            pending.extend_from_slice(events);
+            pending
+        };
+
+        let patched = match self.index.write() {
+            Ok(mut index_guard) => {
+                let patched = index_guard.apply_changes(&events);
+                if patched {
+                    index_guard.synced_at = polled_at;
+                }
+                patched
+            }
+            Err(_) => {
+                self.update_in_progress.store(false, Ordering::Release);
+                return Err(AppError::InternalServerError(
+                    "Index lock poisoned".to_string(),
+                ));
+            }
+        };
+        self.update_in_progress.store(false, Ordering::Release);
+
+        if patched {
+            debug!("Applied {} filesystem changes to the index", events.len());
+            index_changed();
+            if let Ok(mut cache) = self.search_cache.try_lock() {
+                cache.clear();
+            }
+        } else {
+            info!("Incremental index update not possible, rebuilding");
+            self.update_if_needed(true)?;
+        }
+        Ok(())
+    }
+
+    /// Patch the watcher events queued during an update into the index it
+    /// built, before that index replaces the current one. Events that cannot
+    /// be patched in leave a rebuild queued instead.
+    fn replay_queued(&self, index: &mut UltraLowMemoryIndex) {
+        let Ok(mut queued) = self.queued_changes.lock() else {
+            return;
+        };
+        if queued.is_empty() {
+            return;
+        }
+        let events = std::mem::take(&mut *queued);
+        if index.apply_changes(&events) {
+            debug!(
+                "Replayed {} filesystem changes made during the index update",
+                events.len()
+            );
+        } else {
+            queued.push(FsEvent::Overflow);
+        }
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>, AppError> {
+        debug!(
+            "ConcurrentUltraLowMemoryIndex search: query='{}', limit={}",
+            query, limit
+        );
+        let _start_time = Instant::now();
+        let cache_key = format!("{query}:{limit}");
+        debug!(
+            "Starting concurrent search for query: '{}' with limit: {}",
+            query, limit
+        );
+        trace!("Cache key generated: {}", cache_key);
+
+        // Try cache first (quick lock)
+        {
             if let Ok(mut cache) = self.search_cache.try_lock() {
                 trace!("Acquired cache lock for search query");
                 if let Some(cached_results) = cache.get(&cache_key) {
@@ -1458,9 +3350,11 @@ impl ConcurrentUltraLowMemoryIndex {
     pub fn search_shared(
         &self,
         query: &str,
+        mode: SearchMode,
         limit: usize,
+        filters: &SearchFilters,
     ) -> Result<Arc<Vec<SearchResult>>, AppError> {
-        let cache_key = format!("{query}:{limit}");
+        let cache_key = format!("{query}:{}:{limit}:{}", mode.as_str(), filters.cache_key());
 
         {
             if let Ok(mut cache) = self.search_cache.try_lock()
@@ -1475,7 +3369,7 @@ impl ConcurrentUltraLowMemoryIndex {
                 .index
                 .read()
                 .map_err(|_| AppError::InternalServerError("Index lock poisoned".to_string()))?;
-            index_guard.search(query, limit)
+            index_guard.search_filtered(&NameMatcher::new(query, mode)?, limit, filters)
         };
 
         let shared = Arc::new(results);
@@ -1507,32 +3401,30 @@ impl ConcurrentUltraLowMemoryIndex {
             return Ok(()); // Already updating
         }
 
-        let result = {
+        // Build the new index off to the side so searches keep seeing the
+        // complete current one, then swap it in under a brief write lock
+        let result = match self.index.read() {
+            Ok(index_guard) => Ok(index_guard.empty_like()),
+            Err(_) => Err(AppError::InternalServerError(
+                "Index lock poisoned".to_string(),
+            )),
+        }
+        .and_then(|mut fresh| {
+            fresh.update_if_needed(true)?;
+            // Changes seen while building may postdate the part already walked
+            self.replay_queued(&mut fresh);
             let mut index_guard = self
                 .index
                 .write()
                 .map_err(|_| AppError::InternalServerError("Index lock poisoned".to_string()))?;
-
-            // Check memory usage before update
-            let memory_usage = index_guard.get_memory_usage();
-            let memory_mb = memory_usage / 1_048_576;
-
-            // Perform memory cleanup if memory usage is high or if it's been a while
-            let should_cleanup = memory_mb > 200 || // Over 200MB
-                index_guard.last_update.elapsed().as_secs() > 3600; // Over 1 hour since last update
-
-            if should_cleanup {
-                info!(
-                    "Performing memory cleanup due to high usage ({}MB) or time threshold",
-                    memory_mb
-                );
-                index_guard.perform_memory_cleanup();
-            }
-
-            index_guard.update_if_needed(force)
-        };
+            *index_guard = fresh;
+            Ok(())
+        });
 
         self.update_in_progress.store(false, Ordering::Release);
+        if result.is_ok() {
+            index_changed();
+        }
 
         // Clear cache after update and force shrink if needed
         if result.is_ok()
@@ -1554,7 +3446,7 @@ impl ConcurrentUltraLowMemoryIndex {
         Ok((
             index_guard.get_entry_count(),
             index_guard.get_memory_usage(),
-            index_guard.is_updating(),
+            self.update_in_progress.load(Ordering::Relaxed),
         ))
     }
 
@@ -1572,22 +3464,186 @@ impl ConcurrentUltraLowMemoryIndex {
 static ULTRA_LOW_MEMORY_INDEX: RwLock<Option<Arc<ConcurrentUltraLowMemoryIndex>>> =
     RwLock::new(None);
 
+/// Bumped whenever an index is replaced or patched, see [`index_generation`]
+static INDEX_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Counter that changes whenever search results may have changed; conditional
+/// requests for search responses are validated against it
+pub fn index_generation() -> u64 {
+    INDEX_GENERATION.load(Ordering::Acquire)
+}
+
+fn index_changed() {
+    INDEX_GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Mount name of the served directory in search results
+pub const DEFAULT_MOUNT: &str = "root";
+
+/// Directory searched next to the served one, see [`add_search_mount`]
+struct SearchMount {
+    name: String,
+    base_dir: PathBuf,
+    index: Arc<ConcurrentUltraLowMemoryIndex>,
+}
+
+/// Mounts in the order they were added
+static SEARCH_MOUNTS: RwLock<Vec<SearchMount>> = RwLock::new(Vec::new());
+
+/// Whether `name` can be used as a mount name: 1 to 32 ASCII letters,
+/// digits, `-` or `_`
+pub fn is_valid_mount_name(name: &str) -> bool {
+    (1..=32).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Also search `base_dir`, attributing its results to the mount `name`
+///
+/// Results from a mount have paths relative to its directory, and `mount=`
+/// restricts a query to one mount. The index is built in the background with
+/// the same settings as [`initialize_search`], which drops the mounts added
+/// before it.
+pub fn add_search_mount(
+    name: &str,
+    base_dir: PathBuf,
+    exact_sizes: bool,
+    trigram_budget: u64,
+    scope: IndexScope,
+) -> Result<(), AppError> {
+    if !is_valid_mount_name(name) || name == DEFAULT_MOUNT {
+        return Err(AppError::InvalidConfiguration(format!(
+            "Invalid search mount name '{name}'"
+        )));
+    }
+    if !base_dir.is_dir() {
+        return Err(AppError::DirectoryNotFound(
+            base_dir.to_string_lossy().into_owned(),
+        ));
+    }
+    let index = Arc::new(
+        ConcurrentUltraLowMemoryIndex::new(base_dir.clone())
+            .with_exact_sizes(exact_sizes)
+            .with_trigram_budget(trigram_budget)
+            .with_scope(scope),
+    );
+    {
+        let Ok(mut mounts) = SEARCH_MOUNTS.write() else {
+            return Err(AppError::InternalServerError(
+                "Search mounts unavailable".to_string(),
+            ));
+        };
+        if mounts.iter().any(|mount| mount.name == name) {
+            return Err(AppError::InvalidConfiguration(format!(
+                "Search mount '{name}' already exists"
+            )));
+        }
+        mounts.push(SearchMount {
+            name: name.to_string(),
+            base_dir: base_dir.clone(),
+            index: index.clone(),
+        });
+        index_changed();
+    }
+    info!("Search mount '{name}' added for {}", base_dir.display());
+    start_index_maintenance(index, base_dir);
+    Ok(())
+}
+
+/// Names of the mounts added with [`add_search_mount`]
+pub fn search_mounts() -> Vec<String> {
+    SEARCH_MOUNTS
+        .read()
+        .map(|mounts| mounts.iter().map(|mount| mount.name.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// (name, directory, index) of the mounts `mount` selects, the served
+/// directory first
+fn selected_mounts(
+    base_dir: &Path,
+    mount: Option<&str>,
+) -> Result<Vec<(String, PathBuf, Arc<ConcurrentUltraLowMemoryIndex>)>, AppError> {
+    let mut selected = Vec::new();
+    if mount.is_none_or(|name| name == DEFAULT_MOUNT) {
+        let index_guard = ULTRA_LOW_MEMORY_INDEX.read().unwrap();
+        match &*index_guard {
+            Some(index) => selected.push((
+                DEFAULT_MOUNT.to_string(),
+                base_dir.to_path_buf(),
+                index.clone(),
+            )),
+            None => {
+                error!("Ultra-low memory search index not initialized");
+                return Err(AppError::InternalServerError(
+                    "Ultra-low memory search index not initialized".to_string(),
+                ));
+            }
+        }
+    }
+    if let Ok(mounts) = SEARCH_MOUNTS.read() {
+        selected.extend(
+            mounts
+                .iter()
+                .filter(|m| mount.is_none_or(|name| name == m.name))
+                .map(|m| (m.name.clone(), m.base_dir.clone(), m.index.clone())),
+        );
+    }
+    if selected.is_empty() {
+        debug!("Unknown search mount: {mount:?}");
+        return Err(AppError::BadRequest);
+    }
+    Ok(selected)
+}
+
 /// Initialize the ultra-low memory search subsystem (<100MB for 10M entries)
-pub fn initialize_search(base_dir: PathBuf) {
+///
+/// `exact_sizes` keeps exact file sizes in the index at 8 bytes per entry;
+/// without it sizes are rounded to a power of two. Substring queries use a
+/// trigram index when it fits in `trigram_budget` bytes (0 disables it).
+/// Only the part of the tree in `scope` is indexed.
+pub fn initialize_search(
+    base_dir: PathBuf,
+    exact_sizes: bool,
+    trigram_budget: u64,
+    scope: IndexScope,
+) {
     // Initialize ultra-low memory concurrent index
-    let concurrent_index = Arc::new(ConcurrentUltraLowMemoryIndex::new(base_dir.clone()));
+    let concurrent_index = Arc::new(
+        ConcurrentUltraLowMemoryIndex::new(base_dir.clone())
+            .with_exact_sizes(exact_sizes)
+            .with_trigram_budget(trigram_budget)
+            .with_scope(scope),
+    );
 
     {
         let mut global_index = ULTRA_LOW_MEMORY_INDEX.write().unwrap();
         *global_index = Some(concurrent_index.clone());
     }
+    if let Ok(mut mounts) = SEARCH_MOUNTS.write() {
+        mounts.clear();
+    }
+    index_changed();
+
+    start_index_maintenance(concurrent_index, base_dir);
+
+    info!("Ultra-low memory search subsystem initialized - targeting <100MB for 10M entries");
+}
+
+/// Build `concurrent_index` in the background, then keep it current and
+/// compact for as long as the process runs
+fn start_index_maintenance(
+    concurrent_index: Arc<ConcurrentUltraLowMemoryIndex>,
+    base_dir: PathBuf,
+) {
+    start_index_watcher(concurrent_index.clone(), base_dir);
 
     if let Ok(handle) = tokio::runtime::Handle::try_current() {
         handle.spawn({
             let init_index = concurrent_index.clone();
             async move {
-                let res =
-                    tokio::task::spawn_blocking(move || init_index.update_if_needed(true)).await;
+                let res = tokio::task::spawn_blocking(move || init_index.load_or_build()).await;
                 match res {
                     Ok(Ok(())) => {}
                     Ok(Err(e)) => warn!("Failed to build initial ultra-low memory index: {e:?}"),
@@ -1604,13 +3660,18 @@ pub fn initialize_search(base_dir: PathBuf) {
                 loop {
                     interval.tick().await;
 
-                    let idx = concurrent_index.clone();
-                    let res =
-                        tokio::task::spawn_blocking(move || idx.update_if_needed(false)).await;
-                    match res {
-                        Ok(Ok(())) => {}
-                        Ok(Err(e)) => warn!("Failed to update ultra-low memory index: {e:?}"),
-                        Err(e) => warn!("Failed to update ultra-low memory index: {e:?}"),
+                    if !concurrent_index.is_watching() {
+                        let idx = concurrent_index.clone();
+                        let res =
+                            tokio::task::spawn_blocking(move || idx.update_if_needed(false)).await;
+                        match res {
+                            Ok(Ok(())) => {}
+                            Ok(Err(e)) => warn!("Failed to update ultra-low memory index: {e:?}"),
+                            Err(e) => warn!("Failed to update ultra-low memory index: {e:?}"),
+                        }
+                        let idx = concurrent_index.clone();
+                        let _ =
+                            tokio::task::spawn_blocking(move || idx.persist_if_due(false)).await;
                     }
 
                     cleanup_counter += 1;
@@ -1625,47 +3686,449 @@ pub fn initialize_search(base_dir: PathBuf) {
                             cache.shrink_if_needed(true);
                         }
 
-                        debug!("Completed hourly memory cleanup cycle");
-                    }
-                }
-            }
-        });
-    } else {
-        let init_index = concurrent_index.clone();
-        thread::spawn(move || {
-            if let Err(e) = init_index.update_if_needed(true) {
-                warn!("Failed to build initial ultra-low memory index: {e:?}");
+                        debug!("Completed hourly memory cleanup cycle");
+                    }
+                }
+            }
+        });
+    } else {
+        let init_index = concurrent_index.clone();
+        thread::spawn(move || {
+            if let Err(e) = init_index.load_or_build() {
+                warn!("Failed to build initial ultra-low memory index: {e:?}");
+            }
+        });
+
+        thread::spawn(move || {
+            let mut cleanup_counter = 0;
+            loop {
+                thread::sleep(Duration::from_secs(60));
+
+                if !concurrent_index.is_watching() {
+                    if let Err(e) = concurrent_index.update_if_needed(false) {
+                        warn!("Failed to update ultra-low memory index: {e:?}");
+                    }
+                    concurrent_index.persist_if_due(false);
+                }
+
+                cleanup_counter += 1;
+                if cleanup_counter >= 60 {
+                    cleanup_counter = 0;
+
+                    if let Ok(mut index_guard) = concurrent_index.index.write() {
+                        index_guard.perform_memory_cleanup();
+                    }
+
+                    if let Ok(mut cache) = concurrent_index.search_cache.try_lock() {
+                        cache.shrink_if_needed(true);
+                    }
+
+                    debug!("Completed hourly memory cleanup cycle");
+                }
+            }
+        });
+    }
+}
+
+/// How often watcher events are drained and applied to the index
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pending events beyond this are replaced by a full rebuild
+const MAX_PENDING_EVENTS: usize = 10_000;
+
+/// Keep the index current from filesystem notifications. When no watcher is
+/// available the periodic rebuild in [`start_index_maintenance`] stays in
+/// charge.
+fn start_index_watcher(index: Arc<ConcurrentUltraLowMemoryIndex>, base_dir: PathBuf) {
+    thread::spawn(move || {
+        let mut watcher = match FsWatcher::new(&base_dir) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                info!(
+                    "Filesystem watcher unavailable ({e}), search index will be rebuilt periodically"
+                );
+                return;
+            }
+        };
+        info!(
+            "Watching {} directories for incremental search index updates",
+            watcher.watched_directories()
+        );
+        index.watching.store(true, Ordering::Relaxed);
+
+        loop {
+            thread::sleep(WATCH_POLL_INTERVAL);
+            // Stop once a newer index has replaced this one
+            if !is_current_index(&index) {
+                break;
+            }
+            let polled_at = SystemTime::now();
+            let mut events = match watcher.poll() {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!("Filesystem watcher failed ({e}), falling back to periodic rebuilds");
+                    break;
+                }
+            };
+            if events.len() > MAX_PENDING_EVENTS {
+                events = vec![FsEvent::Overflow];
+            }
+            // Also replays what was queued while an update ran
+            if let Err(e) = index.apply_changes(&events, polled_at) {
+                warn!("Failed to apply filesystem changes to the index: {e:?}");
+            }
+            index.persist_if_due(false);
+        }
+        index.watching.store(false, Ordering::Relaxed);
+    });
+}
+
+fn is_current_index(index: &Arc<ConcurrentUltraLowMemoryIndex>) -> bool {
+    ULTRA_LOW_MEMORY_INDEX
+        .read()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|current| Arc::ptr_eq(current, index)))
+        .unwrap_or(false)
+        || SEARCH_MOUNTS
+            .read()
+            .is_ok_and(|mounts| mounts.iter().any(|mount| Arc::ptr_eq(&mount.index, index)))
+}
+
+/// Extensions indexed by `--search-content` when none are configured
+pub const DEFAULT_CONTENT_EXTENSIONS: &str = "txt,md,markdown,rst,log,csv,tsv,json,xml,yaml,yml,toml,ini,conf,cfg,html,htm,css,js,ts,rs,py,go,java,c,h,cpp,hpp,sh";
+
+/// Largest file indexed by `--search-content` unless configured otherwise
+pub const DEFAULT_CONTENT_MAX_SIZE: u64 = 256 * 1024;
+
+/// How often the content index is rebuilt from disk
+const CONTENT_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Tokens outside this length range are not indexed
+const MIN_TOKEN_LEN: usize = 2;
+const MAX_TOKEN_LEN: usize = 64;
+
+/// Normalize extension lists such as `.MD, *.txt` to `md`, `txt`
+pub fn normalize_content_extensions(list: &[String]) -> Vec<String> {
+    let mut extensions = Vec::new();
+    for ext in list {
+        let ext = ext
+            .trim()
+            .trim_start_matches('*')
+            .trim_start_matches('.')
+            .to_lowercase();
+        if !ext.is_empty() && !extensions.contains(&ext) {
+            extensions.push(ext);
+        }
+    }
+    extensions
+}
+
+/// Which files the content index reads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentIndexSettings {
+    /// Lowercase extensions without the leading dot
+    pub extensions: Vec<String>,
+    pub max_file_size: u64,
+    /// Same exclusions and depth limits as the name index
+    pub scope: IndexScope,
+}
+
+impl Default for ContentIndexSettings {
+    fn default() -> Self {
+        let defaults: Vec<String> = DEFAULT_CONTENT_EXTENSIONS
+            .split(',')
+            .map(str::to_string)
+            .collect();
+        Self {
+            extensions: normalize_content_extensions(&defaults),
+            max_file_size: DEFAULT_CONTENT_MAX_SIZE,
+            scope: IndexScope::default(),
+        }
+    }
+}
+
+impl ContentIndexSettings {
+    /// Settings from the CLI, or `None` when content indexing is disabled
+    pub fn from_cli(cli: &crate::cli::Cli) -> Option<Self> {
+        if !cli.search_content.unwrap_or(false) {
+            return None;
+        }
+        let mut settings = Self::default();
+        if let Some(list) = &cli.search_content_extensions {
+            let list: Vec<String> = list.split(',').map(str::to_string).collect();
+            let extensions = normalize_content_extensions(&list);
+            if !extensions.is_empty() {
+                settings.extensions = extensions;
+            }
+        }
+        if let Some(max_size) = cli.search_content_max_size {
+            settings.max_file_size = max_size;
+        }
+        Some(settings)
+    }
+
+    fn wants(&self, path: &Path, size: u64) -> bool {
+        size <= self.max_file_size
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| self.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+}
+
+/// Split text into lowercase alphanumeric words
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| (MIN_TOKEN_LEN..=MAX_TOKEN_LEN).contains(&word.chars().count()))
+        .map(fold_key)
+}
+
+#[derive(Debug)]
+struct ContentDocument {
+    name: String,
+    path: String,
+    size: u64,
+    last_modified: Option<u64>,
+}
+
+/// Inverted index over the words of small text files
+#[derive(Debug)]
+pub struct ContentIndex {
+    base_dir: PathBuf,
+    documents: Vec<ContentDocument>,
+    /// Sorted by token so exact and prefix lookups are binary searches
+    postings: Vec<(Box<str>, Vec<u32>)>,
+}
+
+impl ContentIndex {
+    fn empty(base_dir: PathBuf) -> Self {
+        Self {
+            base_dir,
+            documents: Vec::new(),
+            postings: Vec::new(),
+        }
+    }
+
+    /// Walk `base_dir` and index every file matching `settings`
+    pub fn build(base_dir: &Path, settings: &ContentIndexSettings) -> Self {
+        let mut documents = Vec::new();
+        let mut tokens: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut pending = vec![base_dir.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let relative = dir.strip_prefix(base_dir).unwrap_or(&dir);
+            if !settings.scope.indexes_children_of(relative) {
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if ignore_rules().excludes_entry(&entry.path(), &name)
+                    || settings.scope.excludes_entry(&entry.path(), &name)
+                {
+                    continue;
+                }
+                // Symlinks are not followed, matching the name index
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                if !file_type.is_file() {
+                    continue;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if !settings.wants(&path, metadata.len()) {
+                    continue;
+                }
+                let Ok(bytes) = fs::read(&path) else {
+                    continue;
+                };
+                if bytes.contains(&0) {
+                    trace!("Skipping binary file for content index: {}", path.display());
+                    continue;
+                }
+
+                let doc_id = documents.len() as u32;
+                for token in tokenize(&String::from_utf8_lossy(&bytes)) {
+                    let docs = tokens.entry(token).or_default();
+                    if docs.last() != Some(&doc_id) {
+                        docs.push(doc_id);
+                    }
+                }
+
+                let relative = path
+                    .strip_prefix(base_dir)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                documents.push(ContentDocument {
+                    name,
+                    path: format!("/{}", relative.trim_start_matches('/')),
+                    size: metadata.len(),
+                    last_modified: metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs()),
+                });
+            }
+        }
+
+        let mut postings: Vec<(Box<str>, Vec<u32>)> = tokens
+            .into_iter()
+            .map(|(token, docs)| (token.into_boxed_str(), docs))
+            .collect();
+        postings.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        Self {
+            base_dir: base_dir.to_path_buf(),
+            documents,
+            postings,
+        }
+    }
+
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn token_count(&self) -> usize {
+        self.postings.len()
+    }
+
+    /// Documents with a word starting with `prefix`, and those containing it exactly
+    fn documents_with_prefix(&self, prefix: &str) -> (Vec<u32>, Vec<u32>) {
+        let start = self
+            .postings
+            .partition_point(|(token, _)| &**token < prefix);
+        let mut all = Vec::new();
+        let mut exact = Vec::new();
+        for (token, docs) in self.postings[start..]
+            .iter()
+            .take_while(|(token, _)| token.starts_with(prefix))
+        {
+            if &**token == prefix {
+                exact.extend_from_slice(docs);
             }
-        });
+            all.extend_from_slice(docs);
+        }
+        all.sort_unstable();
+        all.dedup();
+        (all, exact)
+    }
 
-        thread::spawn(move || {
-            let mut cleanup_counter = 0;
-            loop {
-                thread::sleep(Duration::from_secs(60));
+    /// Files containing every word of `query` (the last word may be partial),
+    /// restricted to those under `path`
+    pub fn search(&self, query: &str, path: &str, limit: usize) -> Vec<SearchResult> {
+        let mut words: Vec<String> = tokenize(query).collect();
+        words.sort();
+        words.dedup();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut candidates: Option<Vec<u32>> = None;
+        let mut exact_hits: HashMap<u32, u32> = HashMap::new();
+        for word in &words {
+            let (docs, exact) = self.documents_with_prefix(word);
+            for doc in exact {
+                *exact_hits.entry(doc).or_default() += 1;
+            }
+            candidates = Some(match candidates {
+                None => docs,
+                Some(current) => current
+                    .into_iter()
+                    .filter(|doc| docs.binary_search(doc).is_ok())
+                    .collect(),
+            });
+        }
 
-                if let Err(e) = concurrent_index.update_if_needed(false) {
-                    warn!("Failed to update ultra-low memory index: {e:?}");
+        let scope = if path.ends_with('/') {
+            path.to_string()
+        } else {
+            format!("{path}/")
+        };
+        candidates
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|doc_id| {
+                let doc = &self.documents[doc_id as usize];
+                if !doc.path.starts_with(&scope) {
+                    return None;
                 }
+                let exact = exact_hits.get(&doc_id).copied().unwrap_or_default();
+                Some(SearchResult {
+                    name: doc.name.clone(),
+                    path: doc.path.clone(),
+                    size: format_file_size(doc.size),
+                    file_type: "file".to_string(),
+                    score: 20.0 + 10.0 * exact as f32 / words.len() as f32,
+                    last_modified: doc.last_modified,
+                    size_bytes: doc.size,
+                    matched_ranges: Vec::new(),
+                    mount: DEFAULT_MOUNT.to_string(),
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+}
 
-                cleanup_counter += 1;
-                if cleanup_counter >= 60 {
-                    cleanup_counter = 0;
+/// Global content index, present only when `--search-content` is enabled
+static CONTENT_INDEX: RwLock<Option<Arc<ContentIndex>>> = RwLock::new(None);
 
-                    if let Ok(mut index_guard) = concurrent_index.index.write() {
-                        index_guard.perform_memory_cleanup();
-                    }
+fn content_index_for(base_dir: &Path) -> Option<Arc<ContentIndex>> {
+    let guard = CONTENT_INDEX.read().ok()?;
+    guard
+        .as_ref()
+        .filter(|index| index.base_dir == base_dir)
+        .cloned()
+}
 
-                    if let Ok(mut cache) = concurrent_index.search_cache.try_lock() {
-                        cache.shrink_if_needed(true);
-                    }
+/// Build the content index in the background and refresh it periodically
+pub fn initialize_content_search(base_dir: PathBuf, settings: ContentIndexSettings) {
+    // Publish an empty index right away so `scope=content` is accepted while
+    // the first build runs
+    if let Ok(mut guard) = CONTENT_INDEX.write() {
+        *guard = Some(Arc::new(ContentIndex::empty(base_dir.clone())));
+    }
+    index_changed();
 
-                    debug!("Completed hourly memory cleanup cycle");
+    thread::spawn(move || {
+        loop {
+            let start = Instant::now();
+            let index = ContentIndex::build(&base_dir, &settings);
+            info!(
+                "Content index built: {} files, {} distinct words in {}ms",
+                index.document_count(),
+                index.token_count(),
+                start.elapsed().as_millis()
+            );
+
+            match CONTENT_INDEX.write() {
+                // Stop refreshing once another directory has taken over
+                Ok(mut guard) if content_index_owner(&guard) == Some(&base_dir) => {
+                    *guard = Some(Arc::new(index));
+                    index_changed();
                 }
+                _ => break,
             }
-        });
-    }
 
-    info!("Ultra-low memory search subsystem initialized - targeting <100MB for 10M entries");
+            thread::sleep(CONTENT_REFRESH_INTERVAL);
+        }
+        debug!("Content index refresh stopped for {}", base_dir.display());
+    });
+}
+
+fn content_index_owner(guard: &Option<Arc<ContentIndex>>) -> Option<&PathBuf> {
+    guard.as_ref().map(|index| &index.base_dir)
 }
 
 /// Perform ultra-fast search using ultra-low memory concurrent index
@@ -1673,98 +4136,229 @@ pub fn perform_search(
     base_dir: &Path,
     params: &SearchParams,
 ) -> Result<Vec<SearchResult>, AppError> {
+    perform_search_page(base_dir, params).map(|page| page.results)
+}
+
+/// Search, sort by `params.sort`/`params.order` and return the requested page
+/// together with the total match count
+pub fn perform_search_page(base_dir: &Path, params: &SearchParams) -> Result<SearchPage, AppError> {
+    perform_search_page_filtered(base_dir, params, |_| true)
+}
+
+/// [`perform_search_page`] counting and returning only the matches
+/// `visible` accepts
+pub fn perform_search_page_filtered(
+    base_dir: &Path,
+    params: &SearchParams,
+    visible: impl Fn(&SearchResult) -> bool,
+) -> Result<SearchPage, AppError> {
     debug!(
-        "Starting search: query='{}', path='{}', limit={}, offset={}",
-        params.query, params.path, params.limit, params.offset
+        "Starting search: query='{}', path='{}', limit={}, offset={}, sort={}, order={}",
+        params.query,
+        params.path,
+        params.limit,
+        params.offset,
+        params.sort.as_str(),
+        params.order.as_str()
     );
     trace!(
         "Search parameters: case_sensitive={}",
         params.case_sensitive
     );
     let start = Instant::now();
-    debug!(
-        "Performing search with query: '{}', limit: {}, offset: {}",
-        params.query, params.limit, params.offset
-    );
-    trace!(
-        "Search parameters - path: '{}', case_sensitive: {}",
-        params.path, params.case_sensitive
-    );
+    let mounts = selected_mounts(base_dir, params.mount.as_deref())?;
+    // The least ready of the searched indexes
+    let index_state = mounts
+        .iter()
+        .map(|(_, _, index)| index.index_status().state)
+        .max_by_key(|state| match state {
+            IndexState::Ready => 0,
+            IndexState::Updating => 1,
+            IndexState::Building => 2,
+        })
+        .unwrap_or(IndexState::Building);
+
+    // Resolve the content index first so an unavailable scope fails fast;
+    // it only covers the served directory
+    let content_index = if params.scope.includes_content() {
+        if params.mode != SearchMode::Substring {
+            debug!("Content search supports only the substring mode");
+            return Err(AppError::BadRequest);
+        }
+        if mounts[0].0 != DEFAULT_MOUNT {
+            debug!("Content search covers only the served directory");
+            return Err(AppError::BadRequest);
+        }
+        let index = content_index_for(base_dir).ok_or_else(|| {
+            debug!("Content search requested but content indexing is not enabled");
+            AppError::BadRequest
+        })?;
+        Some(index)
+    } else {
+        None
+    };
 
-    // Get ultra-low memory concurrent index
-    let concurrent_index = {
-        let index_guard = ULTRA_LOW_MEMORY_INDEX.read().unwrap();
-        match &*index_guard {
-            Some(index) => {
-                debug!("Using ultra-low memory index for search");
-                let stats = index.get_stats().unwrap_or((0, 0, false));
-                trace!(
-                    "Index stats - entries: {}, memory: {} bytes, updating: {}",
-                    stats.0, stats.1, stats.2
-                );
-                index.clone()
-            }
-            None => {
-                error!("Ultra-low memory search index not initialized");
-                return Err(AppError::InternalServerError(
-                    "Ultra-low memory search index not initialized".to_string(),
-                ));
+    let mut matches = Vec::new();
+    if params.scope.includes_names() {
+        for (name, mount_dir, index) in &mounts {
+            let mut found = search_names(index, mount_dir, params)?;
+            if name != DEFAULT_MOUNT {
+                for result in &mut found {
+                    result.mount.clone_from(name);
+                }
             }
+            matches.extend(found);
         }
-    };
+    }
+    if let Some(index) = content_index {
+        let mut content_matches = index.search(&params.query, &params.path, SEARCH_RESULT_WINDOW);
+        content_matches.retain(|result| params.filters.matches(result));
+        debug!("Content index returned {} results", content_matches.len());
+        merge_results(&mut matches, content_matches);
+    }
+
+    let total_capped = matches.len() >= SEARCH_RESULT_WINDOW;
+    matches.retain(|result| visible(result));
+    sort_results(&mut matches, params.sort, params.order);
+    matches.truncate(SEARCH_RESULT_WINDOW);
+
+    let total = matches.len();
+    let start_idx = params.offset.min(total);
+    let end_idx = params.offset.saturating_add(params.limit).min(total);
+    let results = matches[start_idx..end_idx].to_vec();
+    let next_offset = (end_idx < total).then_some(end_idx);
+
+    let search_time = start.elapsed();
+    info!(
+        "Ultra-fast search completed for '{}': {} of {} results in {:.2}ms (ultra-low memory)",
+        params.query,
+        results.len(),
+        total,
+        search_time.as_millis()
+    );
+
+    Ok(SearchPage {
+        results,
+        total,
+        total_capped,
+        next_offset,
+        index_state,
+    })
+}
+
+/// Match file and directory names in the index of `base_dir`, falling back
+/// to a filesystem walk
+fn search_names(
+    concurrent_index: &ConcurrentUltraLowMemoryIndex,
+    base_dir: &Path,
+    params: &SearchParams,
+) -> Result<Vec<SearchResult>, AppError> {
+    debug!("Using ultra-low memory index for search");
+    let stats = concurrent_index.get_stats().unwrap_or((0, 0, false));
+    trace!(
+        "Index stats - entries: {}, memory: {} bytes, updating: {}",
+        stats.0, stats.1, stats.2
+    );
 
-    // Perform ultra-fast radix-accelerated search
-    let expanded_limit = params
-        .offset
-        .saturating_add(params.limit)
-        .saturating_mul(2)
-        .max(params.limit.max(1));
+    // Gather the whole result window so it can be counted and sorted
     trace!(
-        "Starting radix-accelerated search with expanded limit: {}",
-        expanded_limit
+        "Starting radix-accelerated search with result window: {}",
+        SEARCH_RESULT_WINDOW
     );
-    let shared_results = concurrent_index.search_shared(&params.query, expanded_limit)?;
+    // The first build holds the index lock; walk the filesystem instead of
+    // waiting for it
+    let shared_results = if concurrent_index.index_status().state == IndexState::Building {
+        debug!("Search index is still being built");
+        Arc::new(Vec::new())
+    } else {
+        concurrent_index.search_shared(
+            &params.query,
+            params.mode,
+            SEARCH_RESULT_WINDOW,
+            &params.filters,
+        )?
+    };
     debug!(
         "Index search returned {} initial results",
         shared_results.len()
     );
 
     // If index search returns no results, fall back to filesystem search
-    if shared_results.is_empty() {
+    let matches = if shared_results.is_empty() {
         info!(
             "Ultra-low memory index search returned no results, falling back to filesystem search"
         );
         debug!("Initiating parallel filesystem search as fallback");
-        let mut results = perform_parallel_search(base_dir, params)?;
+        let window_params = SearchParams {
+            query: params.query.clone(),
+            path: params.path.clone(),
+            limit: SEARCH_RESULT_WINDOW / 2,
+            offset: 0,
+            case_sensitive: params.case_sensitive,
+            sort: params.sort,
+            order: params.order,
+            scope: SearchScope::Name,
+            mode: params.mode,
+            filters: params.filters.clone(),
+            mount: params.mount.clone(),
+        };
+        let mut results = perform_parallel_search(base_dir, &window_params)?;
+        results.retain(|result| params.filters.matches(result));
         trace!(
             "Filesystem search fallback returned {} results",
             results.len()
         );
-        let start_idx = params.offset.min(results.len());
-        let end_idx = (params.offset + params.limit).min(results.len());
-        results = results[start_idx..end_idx].to_vec();
-        let search_time = start.elapsed();
-        info!(
-            "Ultra-fast search completed for '{}': {} results in {:.2}ms (ultra-low memory)",
-            params.query,
-            results.len(),
-            search_time.as_millis()
-        );
-        return Ok(results);
-    }
-    let start_idx = params.offset.min(shared_results.len());
-    let end_idx = (params.offset + params.limit).min(shared_results.len());
-    let results = shared_results[start_idx..end_idx].to_vec();
+        results
+    } else {
+        shared_results.to_vec()
+    };
 
-    let search_time = start.elapsed();
-    info!(
-        "Ultra-fast search completed for '{}': {} results in {:.2}ms (ultra-low memory)",
-        params.query,
-        results.len(),
-        search_time.as_millis()
-    );
+    Ok(matches)
+}
 
-    Ok(results)
+/// Add content matches, keeping the better score for paths found by both
+fn merge_results(results: &mut Vec<SearchResult>, extra: Vec<SearchResult>) {
+    let mut positions: HashMap<(String, String), usize> = results
+        .iter()
+        .enumerate()
+        .map(|(i, r)| ((r.mount.clone(), r.path.clone()), i))
+        .collect();
+    for result in extra {
+        let key = (result.mount.clone(), result.path.clone());
+        match positions.get(&key) {
+            Some(&i) => {
+                if result.score > results[i].score {
+                    results[i].score = result.score;
+                }
+            }
+            None => {
+                positions.insert(key, results.len());
+                results.push(result);
+            }
+        }
+    }
+}
+
+/// Order results in place; ties fall back to the path so pages are stable
+pub fn sort_results(results: &mut [SearchResult], sort: SearchSort, order: SortOrder) {
+    use std::cmp::Ordering as CmpOrdering;
+    let key = |a: &SearchResult, b: &SearchResult| -> CmpOrdering {
+        match sort {
+            SearchSort::Score => a.score.partial_cmp(&b.score).unwrap_or(CmpOrdering::Equal),
+            SearchSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SearchSort::Size => a.size_bytes.cmp(&b.size_bytes),
+            SearchSort::Modified => a.last_modified.cmp(&b.last_modified),
+        }
+    };
+    results.sort_by(|a, b| {
+        let primary = match order {
+            SortOrder::Asc => key(a, b),
+            SortOrder::Desc => key(b, a),
+        };
+        primary
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.mount.cmp(&b.mount))
+    });
 }
 
 /// Perform a parallel filesystem search using multiple threads
@@ -1773,7 +4367,7 @@ fn perform_parallel_search(
     params: &SearchParams,
 ) -> Result<Vec<SearchResult>, AppError> {
     let (tx, rx) = mpsc::channel();
-    let query_lower = Arc::new(params.query.to_lowercase());
+    let matcher = Arc::new(NameMatcher::new(&params.query, params.mode)?);
     let base_dir = Arc::new(base_dir.to_path_buf());
     let num_threads = 4; // Use 4 worker threads for parallel searching
 
@@ -1793,7 +4387,7 @@ fn perform_parallel_search(
     if let Ok(entries) = fs::read_dir(&dirs_to_search[0]) {
         for entry in entries.flatten() {
             let file_name = entry.file_name().to_string_lossy().to_string();
-            if is_hidden_file(&file_name) {
+            if ignore_rules().excludes_entry(&entry.path(), &file_name) {
                 continue;
             }
             if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
@@ -1817,12 +4411,12 @@ fn perform_parallel_search(
         .into_iter()
         .map(|chunk| {
             let tx = tx.clone();
-            let query_lower = Arc::clone(&query_lower);
+            let matcher = Arc::clone(&matcher);
             let base_dir = Arc::clone(&base_dir);
 
             thread::spawn(move || {
                 for dir in chunk {
-                    search_directory_recursive(&dir, &query_lower, &base_dir, &tx, 0);
+                    search_directory_recursive(&dir, &matcher, &base_dir, &tx, 0);
                 }
             })
         })
@@ -1856,7 +4450,7 @@ fn perform_parallel_search(
 /// Recursively search a directory
 fn search_directory_recursive(
     dir: &Path,
-    query_lower: &str,
+    matcher: &NameMatcher,
     base_dir: &Path,
     tx: &mpsc::Sender<SearchResult>,
     depth: usize,
@@ -1868,12 +4462,10 @@ fn search_directory_recursive(
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
             let file_name = entry.file_name().to_string_lossy().to_string();
-            if is_hidden_file(&file_name) {
+            if ignore_rules().excludes_entry(&entry.path(), &file_name) {
                 continue;
             }
-            let file_name_lower = file_name.to_lowercase();
-
-            if file_name_lower.contains(query_lower)
+            if matcher.matches(&file_name)
                 && let Ok(metadata) = entry.metadata()
             {
                 let relative_path = entry
@@ -1911,12 +4503,15 @@ fn search_directory_recursive(
                     } else {
                         "file".to_string()
                     },
-                    score: calculate_relevance_score_with_query_lower(&file_name, query_lower),
+                    score: matcher.score(&file_name),
                     last_modified: metadata
                         .modified()
                         .ok()
                         .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
                         .map(|d| d.as_secs()),
+                    size_bytes: if metadata.is_dir() { 0 } else { metadata.len() },
+                    matched_ranges: matcher.match_ranges(&file_name),
+                    mount: DEFAULT_MOUNT.to_string(),
                 };
 
                 let _ = tx.send(result);
@@ -1924,7 +4519,7 @@ fn search_directory_recursive(
 
             // Recursively search subdirectories
             if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
-                search_directory_recursive(&entry.path(), query_lower, base_dir, tx, depth + 1);
+                search_directory_recursive(&entry.path(), matcher, base_dir, tx, depth + 1);
             }
         }
     }
@@ -2059,6 +4654,24 @@ pub fn get_search_stats() -> String {
         if let Some(ref concurrent_index) = *index_guard {
             let cache_stats = concurrent_index.get_cache_stats();
 
+            // Reading the index itself would wait for the build to finish
+            let status = concurrent_index.index_status();
+            if status.state != IndexState::Ready {
+                return format!(
+                    "Ultra-Low Memory Index: {} ({} entries scanned, {} directories scanned, {} remaining, {:.0}s elapsed, ETA {})\n{}",
+                    status.state.as_str(),
+                    status.entries_scanned,
+                    status.directories_scanned,
+                    status.directories_remaining,
+                    status.elapsed.as_secs_f64(),
+                    status.eta.map_or("unknown".to_string(), |eta| format!(
+                        "{:.0}s",
+                        eta.as_secs_f64()
+                    )),
+                    cache_stats
+                );
+            }
+
             match concurrent_index.get_stats() {
                 Ok((entry_count, memory_usage, is_updating)) => {
                     let memory_per_entry = if entry_count > 0 {
@@ -2091,6 +4704,25 @@ pub fn get_search_stats() -> String {
     }
 }
 
+/// Route reporting index build progress
+pub const INDEX_STATUS_ROUTE: &str = "/_irondrop/index/status";
+
+/// Short index status route
+pub const INDEX_STATUS_SHORT_ROUTE: &str = "/_api/index/status";
+
+/// Progress of the name index, `None` while search is not initialized
+pub fn index_status() -> Option<IndexStatus> {
+    let index_guard = ULTRA_LOW_MEMORY_INDEX.read().ok()?;
+    Some(index_guard.as_ref()?.index_status())
+}
+
+/// Entry count, estimated memory in bytes and whether a rebuild is running,
+/// or `None` while the search index is not initialized
+pub fn get_search_index_stats() -> Option<(usize, u64, bool)> {
+    let index_guard = ULTRA_LOW_MEMORY_INDEX.read().ok()?;
+    index_guard.as_ref()?.get_stats().ok()
+}
+
 /// Get cache statistics (legacy function for backward compatibility)
 pub fn get_cache_stats() -> String {
     if let Ok(index_guard) = ULTRA_LOW_MEMORY_INDEX.read() {
@@ -2196,11 +4828,25 @@ mod perf_tests {
         let build_ms = build_start.elapsed().as_millis();
 
         let search_start = Instant::now();
-        let first = concurrent.search_shared("document_12_", 64).unwrap();
+        let first = concurrent
+            .search_shared(
+                "document_12_",
+                SearchMode::Substring,
+                64,
+                &SearchFilters::default(),
+            )
+            .unwrap();
         let first_search_us = search_start.elapsed().as_micros();
 
         let cache_start = Instant::now();
-        let second = concurrent.search_shared("document_12_", 64).unwrap();
+        let second = concurrent
+            .search_shared(
+                "document_12_",
+                SearchMode::Substring,
+                64,
+                &SearchFilters::default(),
+            )
+            .unwrap();
         let cache_hit_us = cache_start.elapsed().as_micros();
 
         let stats = concurrent.get_stats().unwrap();
@@ -2213,3 +4859,542 @@ mod perf_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod incremental_update_tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn names(index: &ConcurrentUltraLowMemoryIndex, query: &str) -> Vec<String> {
+        let mut names: Vec<String> = index
+            .search(query, 50)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn apply_changes_patches_entries_without_rebuild() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs").join("report.txt"), "a").unwrap();
+
+        let index = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        index.update_if_needed(true).unwrap();
+        assert_eq!(names(&index, "report"), ["/docs/report.txt"]);
+
+        // New file and a new directory with contents
+        std::fs::write(root.join("docs").join("report_2.txt"), "b").unwrap();
+        std::fs::create_dir(root.join("archive")).unwrap();
+        std::fs::write(root.join("archive").join("report_old.txt"), "c").unwrap();
+        index
+            .apply_changes(
+                &[
+                    FsEvent::Changed(root.join("docs").join("report_2.txt")),
+                    FsEvent::Changed(root.join("archive")),
+                ],
+                SystemTime::now(),
+            )
+            .unwrap();
+        assert_eq!(
+            names(&index, "report"),
+            [
+                "/archive/report_old.txt",
+                "/docs/report.txt",
+                "/docs/report_2.txt"
+            ]
+        );
+
+        // Removing a directory drops everything below it
+        std::fs::remove_dir_all(root.join("docs")).unwrap();
+        index
+            .apply_changes(&[FsEvent::Removed(root.join("docs"))], SystemTime::now())
+            .unwrap();
+        assert_eq!(names(&index, "report"), ["/archive/report_old.txt"]);
+        let guard = index.index.read().unwrap();
+        assert_eq!(guard.removed_count, 3);
+        assert_eq!(guard.get_entry_count(), 3);
+    }
+
+    #[test]
+    fn build_progress_is_reported() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("a").join("b")).unwrap();
+        std::fs::write(root.join("a").join("b").join("deep.txt"), "a").unwrap();
+
+        let index = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        assert_eq!(index.index_status().state, IndexState::Building);
+        index.update_if_needed(true).unwrap();
+        let status = index.index_status();
+        assert_eq!(status.state, IndexState::Ready);
+        assert_eq!(status.entries_scanned, 3);
+        assert_eq!(status.directories_scanned, 3);
+        assert_eq!(status.directories_remaining, 0);
+        assert_eq!(status.eta, None);
+
+        // A rebuild estimates its remaining time from the last one
+        let progress = IndexProgress::default();
+        progress.begin();
+        progress.directory_started();
+        progress.found(10, 4);
+        assert_eq!(progress.status().state, IndexState::Building);
+        assert_eq!(progress.status().directories_remaining, 4);
+        progress.finish(true);
+        progress.begin();
+        progress.found(5, 0);
+        let status = progress.status();
+        assert_eq!(status.state, IndexState::Updating);
+        assert!(status.eta.is_some());
+    }
+
+    #[test]
+    fn trigram_index_finds_mid_name_matches() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        for name in [
+            "q3_report.pdf",
+            "Annual-REPORT.txt",
+            "notes.txt",
+            "Über_Rëport.md",
+        ] {
+            std::fs::write(root.join("docs").join(name), "a").unwrap();
+        }
+
+        let index = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        index.update_if_needed(true).unwrap();
+        assert!(index.index.read().unwrap().has_trigram_index());
+        let expected = [
+            "/docs/Annual-REPORT.txt",
+            "/docs/q3_report.pdf",
+            "/docs/Über_Rëport.md",
+        ];
+        assert_eq!(names(&index, "report"), expected);
+        assert!(names(&index, "reportx").is_empty());
+        // Too short for a trigram: falls back to the radix scan
+        assert_eq!(names(&index, "q3"), ["/docs/q3_report.pdf"]);
+
+        // Entries added incrementally are found before the next build
+        std::fs::write(root.join("docs").join("old_report.txt"), "b").unwrap();
+        index
+            .apply_changes(
+                &[FsEvent::Changed(root.join("docs").join("old_report.txt"))],
+                SystemTime::now(),
+            )
+            .unwrap();
+        assert!(names(&index, "report").contains(&"/docs/old_report.txt".to_string()));
+
+        // Over budget (or disabled) it is skipped and results stay the same
+        let small = ConcurrentUltraLowMemoryIndex::new(root.clone()).with_trigram_budget(16);
+        small.update_if_needed(true).unwrap();
+        let small_index = small.index.read().unwrap();
+        assert!(!small_index.has_trigram_index());
+        assert!(
+            small_index
+                .get_ultra_memory_stats()
+                .contains("Trigram Index: not built")
+        );
+        drop(small_index);
+        assert_eq!(names(&small, "report"), names(&index, "report"));
+    }
+
+    #[test]
+    fn names_match_without_case_or_accents() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::write(root.join("Résumé.pdf"), "a").unwrap();
+        // Decomposed: "e" followed by a combining acute accent
+        std::fs::write(root.join("cafe\u{301} menu.txt"), "a").unwrap();
+        std::fs::write(root.join("STRASSE.txt"), "a").unwrap();
+
+        let index = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        index.update_if_needed(true).unwrap();
+        assert_eq!(names(&index, "resume"), ["/Résumé.pdf"]);
+        assert_eq!(names(&index, "RÉSUMÉ"), ["/Résumé.pdf"]);
+        assert_eq!(names(&index, "café"), ["/cafe\u{301} menu.txt"]);
+        assert_eq!(names(&index, "straße"), ["/STRASSE.txt"]);
+    }
+
+    #[test]
+    fn exact_sizes_replace_rounded_ones() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::write(root.join("sized_1500.bin"), vec![0u8; 1500]).unwrap();
+
+        let mut rounded = UltraLowMemoryIndex::new(root.clone());
+        rounded.rebuild_index_ultra_optimized().unwrap();
+        assert_eq!(rounded.search("sized", 5)[0].size_bytes, 1024);
+
+        let mut exact = UltraLowMemoryIndex::new(root.clone());
+        exact.set_exact_sizes(true);
+        exact.rebuild_index_ultra_optimized().unwrap();
+        assert_eq!(exact.search("sized", 5)[0].size_bytes, 1500);
+        assert!(exact.get_memory_usage() > rounded.get_memory_usage());
+
+        // Incremental updates keep the side table current
+        std::fs::write(root.join("sized_1500.bin"), vec![0u8; 1700]).unwrap();
+        std::fs::write(root.join("sized_new.bin"), vec![0u8; 3]).unwrap();
+        assert!(exact.apply_changes(&[
+            FsEvent::Changed(root.join("sized_1500.bin")),
+            FsEvent::Changed(root.join("sized_new.bin")),
+        ]));
+        let mut sizes: Vec<u64> = exact
+            .search("sized", 5)
+            .iter()
+            .map(|r| r.size_bytes)
+            .collect();
+        sizes.sort();
+        assert_eq!(sizes, [3, 1700]);
+    }
+
+    #[test]
+    fn rebuilds_are_swapped_in_whole() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        for d in 0..10 {
+            let dir = root.join(format!("dir_{d}"));
+            std::fs::create_dir(&dir).unwrap();
+            for f in 0..100 {
+                std::fs::write(dir.join(format!("item_{d}_{f}.txt")), "x").unwrap();
+            }
+        }
+
+        let index = Arc::new(ConcurrentUltraLowMemoryIndex::new(root.clone()));
+        index.update_if_needed(true).unwrap();
+        let rebuilder = {
+            let index = index.clone();
+            thread::spawn(move || {
+                for _ in 0..5 {
+                    index.update_if_needed(true).unwrap();
+                }
+            })
+        };
+        // Searches during a rebuild see the previous index, never a partial one
+        while !rebuilder.is_finished() {
+            let count = index.index.read().unwrap().search("item_", 5000).len();
+            assert_eq!(count, 1000);
+        }
+        rebuilder.join().unwrap();
+        assert_eq!(index.index_status().state, IndexState::Ready);
+        assert_eq!(index.get_stats().unwrap().0, 1011);
+    }
+
+    #[test]
+    fn changes_during_a_rebuild_are_replayed() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        std::fs::write(root.join("keep.txt"), "a").unwrap();
+        std::fs::write(root.join("gone.txt"), "b").unwrap();
+
+        let index = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        index.update_if_needed(true).unwrap();
+
+        // Arrives while another update holds the index, as if the file went
+        // away after that rebuild had walked past it
+        index.update_in_progress.store(true, Ordering::Release);
+        index
+            .apply_changes(
+                &[FsEvent::Removed(root.join("gone.txt"))],
+                SystemTime::now(),
+            )
+            .unwrap();
+        assert_eq!(names(&index, ".txt"), ["/gone.txt", "/keep.txt"]);
+        index.update_in_progress.store(false, Ordering::Release);
+
+        index.update_if_needed(true).unwrap();
+        assert_eq!(names(&index, ".txt"), ["/keep.txt"]);
+        assert!(index.queued_changes.lock().unwrap().is_empty());
+
+        // Changes that need a rebuild leave one queued for the next tick
+        index.update_in_progress.store(true, Ordering::Release);
+        index
+            .apply_changes(&[FsEvent::Overflow], SystemTime::now())
+            .unwrap();
+        index.update_in_progress.store(false, Ordering::Release);
+        index.update_if_needed(true).unwrap();
+        assert_eq!(
+            *index.queued_changes.lock().unwrap(),
+            vec![FsEvent::Overflow]
+        );
+        index.apply_changes(&[], SystemTime::now()).unwrap();
+        assert!(index.queued_changes.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parallel_build_keeps_parent_links() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let mut expected = 0;
+        for a in 0..6 {
+            for b in 0..4 {
+                let dir = root.join(format!("dir_{a}")).join(format!("sub_{b}"));
+                std::fs::create_dir_all(&dir).unwrap();
+                for c in 0..30 {
+                    std::fs::write(dir.join(format!("file_{a}_{b}_{c}.txt")), "x").unwrap();
+                }
+                expected += 31;
+            }
+            expected += 1;
+        }
+
+        let mut index = UltraLowMemoryIndex::new(root.clone());
+        index.rebuild_index_ultra_optimized().unwrap();
+        // Plus the root entry
+        assert_eq!(index.get_entry_count(), expected + 1);
+        for id in 1..index.entries.len() as u32 {
+            let path = index.reconstruct_path(id).unwrap();
+            assert!(path.exists(), "{} is not on disk", path.display());
+        }
+        let results = index.search("file_5_3_29", 5);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "/dir_5/sub_3/file_5_3_29.txt");
+    }
+
+    #[test]
+    fn scope_excludes_paths_and_limits_depth() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        for dir in [
+            "app/node_modules/lib",
+            "app/src/deep/deeper",
+            "media/2024/june",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "app/node_modules/lib/todo_lib.js",
+            "app/src/todo_main.rs",
+            "app/src/deep/deeper/todo_deep.rs",
+            "app/todo.iso",
+            "media/todo_cover.jpg",
+            "media/2024/todo_index.txt",
+            "media/2024/june/todo_photo.jpg",
+        ] {
+            std::fs::write(root.join(file), "a").unwrap();
+        }
+
+        let scope = IndexScope::new(
+            &root,
+            &["node_modules".to_string(), "*.iso".to_string()],
+            3,
+            parse_depth_limits("media:1").unwrap(),
+        )
+        .unwrap();
+        assert!(scope.indexes_children_of(Path::new("app/src")));
+        assert!(!scope.indexes_children_of(Path::new("app/src/deep")));
+        assert!(scope.indexes_children_of(Path::new("media")));
+        assert!(!scope.indexes_children_of(Path::new("media/2024")));
+
+        let index = ConcurrentUltraLowMemoryIndex::new(root.clone()).with_scope(scope);
+        index.update_if_needed(true).unwrap();
+        assert_eq!(
+            names(&index, "todo"),
+            ["/app/src/todo_main.rs", "/media/todo_cover.jpg"]
+        );
+
+        // Watcher events outside the scope are ignored as well
+        std::fs::write(root.join("app/node_modules/todo_new.js"), "b").unwrap();
+        std::fs::write(root.join("media/2024/todo_new.txt"), "b").unwrap();
+        index
+            .apply_changes(
+                &[
+                    FsEvent::Changed(root.join("app/node_modules/todo_new.js")),
+                    FsEvent::Changed(root.join("media/2024/todo_new.txt")),
+                ],
+                SystemTime::now(),
+            )
+            .unwrap();
+        assert_eq!(names(&index, "todo").len(), 2);
+
+        assert!(parse_depth_limits("media").is_err());
+        assert!(parse_depth_limits("../up:1").is_err());
+        assert!(parse_depth_limits("/:1").is_err());
+    }
+
+    #[test]
+    fn apply_changes_reports_when_rebuild_is_needed() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let mut index = UltraLowMemoryIndex::new(root.clone());
+        // Not built yet
+        assert!(!index.apply_changes(&[FsEvent::Changed(root.join("a.txt"))]));
+
+        index.rebuild_index_ultra_optimized().unwrap();
+        assert!(!index.apply_changes(&[FsEvent::Overflow]));
+
+        // Parent directory was never indexed
+        std::fs::create_dir(root.join("late")).unwrap();
+        std::fs::write(root.join("late").join("b.txt"), "b").unwrap();
+        assert!(!index.apply_changes(&[FsEvent::Changed(root.join("late").join("b.txt"))]));
+
+        // Hidden paths and paths outside the tree are ignored
+        std::fs::write(root.join(".secret"), "s").unwrap();
+        assert!(index.apply_changes(&[
+            FsEvent::Changed(root.join(".secret")),
+            FsEvent::Removed(PathBuf::from("/elsewhere/file.txt")),
+        ]));
+    }
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use super::*;
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn filters_decide_from_compact_bounds_when_possible() {
+        let filters = SearchFilters::from_query(&query(&[
+            ("type", "file"),
+            ("ext", "PDF, .docx"),
+            ("min_size", "1KB"),
+            ("modified_before", "2025-01-01"),
+        ]))
+        .unwrap();
+        assert_eq!(filters.extensions, ["docx", "pdf"]);
+        assert_eq!(filters.min_size, Some(1024));
+        assert_eq!(filters.modified_before, Some(1_735_689_600));
+
+        let recent = (1_720_000_000, 1_720_000_000);
+        assert_eq!(
+            filters.check(false, "a.pdf", (2048, 4095), recent),
+            Some(true)
+        );
+        assert_eq!(filters.check(true, "a.pdf", (0, 0), recent), Some(false));
+        assert_eq!(
+            filters.check(false, "a.txt", (2048, 4095), recent),
+            Some(false)
+        );
+        assert_eq!(
+            filters.check(false, "a.pdf", (256, 511), recent),
+            Some(false)
+        );
+        // Straddles the size limit or a clamped timestamp: needs the file
+        assert_eq!(filters.check(false, "a.pdf", (512, 2047), recent), None);
+        assert_eq!(
+            filters.check(false, "a.pdf", (2048, 4095), (0, 1_704_067_200)),
+            Some(true)
+        );
+        assert_eq!(
+            filters.check(false, "a.pdf", (2048, 4095), (1_735_689_600, 1_735_689_600)),
+            Some(false)
+        );
+
+        let entry = UltraCompactEntry::new(0, 0, 1500, UNIX_EPOCH, false);
+        assert_eq!(entry.size_bounds(), (1024, 2047));
+        assert_eq!(entry.modified_bounds(), (0, 1_704_067_200));
+
+        assert!(SearchFilters::from_query(&query(&[])).unwrap().is_empty());
+        for bad in [
+            ("type", "link"),
+            ("max_size", "lots"),
+            ("modified_after", "2024-13-01"),
+        ] {
+            assert!(SearchFilters::from_query(&query(&[bad])).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod mode_tests {
+    use super::*;
+
+    #[test]
+    fn fold_key_drops_case_and_accents() {
+        assert_eq!(fold_key("Résumé"), "resume");
+        assert_eq!(fold_key("re\u{301}sume\u{301}"), "resume");
+        assert_eq!(fold_key("Ærø Straße"), "aero strasse");
+        assert_eq!(fold_key("ŁÓDŹ"), "lodz");
+        assert_eq!(fold_key("ΟΔΟΣ"), "οδοσ");
+        assert_eq!(fold_key("Plain.TXT"), "plain.txt");
+
+        let matcher = NameMatcher::new("resume", SearchMode::Substring).unwrap();
+        assert!(matcher.matches("Mon_RÉSUMÉ.pdf"));
+        assert!(!matcher.matches("rézumé.pdf"));
+    }
+
+    #[test]
+    fn match_ranges_and_highlighting() {
+        let substring = NameMatcher::substring("re");
+        assert_eq!(substring.match_ranges("Report_re.txt"), [(0, 2), (7, 9)]);
+        assert_eq!(
+            NameMatcher::substring("resume").match_ranges("Mon Résumé.pdf"),
+            [(4, 12)]
+        );
+        // The combining accent stays inside the range
+        assert_eq!(
+            NameMatcher::substring("cafe").match_ranges("cafe\u{301}!"),
+            [(0, 6)]
+        );
+        assert_eq!(NameMatcher::substring("s").match_ranges("ßa"), [(0, 2)]);
+        let regex = NameMatcher::new(r"\d+", SearchMode::Regex).unwrap();
+        assert_eq!(regex.match_ranges("v12_and_3"), [(1, 3), (8, 9)]);
+        let glob = NameMatcher::new("*.log", SearchMode::Glob).unwrap();
+        assert_eq!(glob.match_ranges("app.log"), [(0, 7)]);
+        assert!(glob.match_ranges("app.txt").is_empty());
+
+        let result = SearchResult {
+            name: "<b>&report.txt".to_string(),
+            path: "/<b>&report.txt".to_string(),
+            size: "1 B".to_string(),
+            file_type: "file".to_string(),
+            score: 1.0,
+            last_modified: None,
+            size_bytes: 1,
+            matched_ranges: NameMatcher::substring("REPORT").match_ranges("<b>&report.txt"),
+            mount: DEFAULT_MOUNT.to_string(),
+        };
+        assert_eq!(
+            result.highlighted_name(),
+            "&lt;b&gt;&amp;<mark>report</mark>.txt"
+        );
+    }
+
+    #[test]
+    fn glob_and_regex_matchers() {
+        let glob = NameMatcher::new("*.LOG", SearchMode::Glob).unwrap();
+        assert!(glob.matches("server.log"));
+        assert!(!glob.matches("server.log.gz"));
+        assert!(
+            NameMatcher::new("a**b", SearchMode::Glob)
+                .unwrap()
+                .matches("aXYb")
+        );
+        assert!(NameMatcher::new(&"a*".repeat(9), SearchMode::Glob).is_err());
+        assert!(NameMatcher::new("[abc", SearchMode::Glob).is_err());
+
+        let regex = NameMatcher::new(r"^report_\d{4}\.", SearchMode::Regex).unwrap();
+        assert!(regex.matches("Report_2024.pdf"));
+        assert!(!regex.matches("old_report_2024.pdf"));
+        assert!(NameMatcher::new("(unclosed", SearchMode::Regex).is_err());
+        // Too deeply nested, or too large once repetitions are expanded
+        assert!(
+            NameMatcher::new(
+                &format!("{}a{}", "(".repeat(20), ")".repeat(20)),
+                SearchMode::Regex
+            )
+            .is_err()
+        );
+        assert!(NameMatcher::new(r"(\w{100}){100}", SearchMode::Regex).is_err());
+
+        let NameMatcher::Regex(first) = NameMatcher::new("cached", SearchMode::Regex).unwrap()
+        else {
+            unreachable!()
+        };
+        let NameMatcher::Regex(second) = NameMatcher::new("cached", SearchMode::Regex).unwrap()
+        else {
+            unreachable!()
+        };
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
@@ -0,0 +1,528 @@
+// SPDX-License-Identifier: MIT
+
+//! On-disk snapshots of the ultra-low memory index
+//!
+//! Large trees take minutes to walk, so the index is saved under
+//! `~/.config/irondrop/search-index/` and loaded at the next start instead of
+//! rebuilding. The file is streamed in and out in this little-endian layout:
+//!
+//! ```text
+//! magic "IRDXIDX\0" | version u32
+//! synced_at_nanos u64 | base_dir (len u32 + bytes) | scope fingerprint u64
+//! root_entry_id u32 | entry_count u64 | removed_count u64
+//! string buffer (len u64 + bytes) | string index (len u64 + [hash u32, offset u32])
+//! entries (len u64 + 11 bytes each) | exact sizes (len u64 + u64 each, 0 when off)
+//! folded names (len u64 + [entry id u32, offset u32])
+//! directory children (len u64 + [len u32 + ids])
+//! radix buckets (256 x [len u32 + ids]) | FNV-1a 64 checksum of everything above
+//! ```
+//!
+//! A snapshot is only used when the version, checksum, base directory and
+//! [`IndexScope`] match and no indexed directory was modified after `synced_at`; anything else
+//! falls back to a rebuild.
+
+use super::{
+    IndexScope, RadixBucket, StringPoolEntry, UltraCompactEntry, UltraLowMemoryIndex,
+    UnifiedStringPool,
+};
+use log::debug;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+const MAGIC: &[u8; 8] = b"IRDXIDX\0";
+
+/// Bumped whenever the layout changes; older snapshots are rebuilt
+pub const INDEX_FORMAT_VERSION: u32 = 4;
+
+/// Smaller trees rebuild in well under a second and are not persisted
+pub const PERSIST_MIN_ENTRIES: usize = 10_000;
+
+/// Where the snapshot for `base_dir` lives, `None` without a home directory
+pub fn default_index_path(base_dir: &Path) -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    let hash = fnv1a(FNV_OFFSET, base_dir.as_os_str().as_encoded_bytes());
+    Some(
+        Path::new(&home)
+            .join(".config")
+            .join("irondrop")
+            .join("search-index")
+            .join(format!("{hash:016x}.idx")),
+    )
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl IndexScope {
+    /// Hash of the settings that decide which entries get indexed
+    fn fingerprint(&self) -> u64 {
+        let mut hash = fnv1a(FNV_OFFSET, &(self.max_depth as u64).to_le_bytes());
+        for pattern in &self.patterns {
+            hash = fnv1a(hash, pattern.as_bytes());
+            hash = fnv1a(hash, b"\0");
+        }
+        for (dir, levels) in &self.depth_limits {
+            hash = fnv1a(hash, dir.as_os_str().as_encoded_bytes());
+            hash = fnv1a(hash, &(*levels as u64).to_le_bytes());
+        }
+        hash
+    }
+}
+
+fn fnv1a(mut hash: u64, data: &[u8]) -> u64 {
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// Writer that checksums everything passing through it
+struct ChecksumWriter<W: Write> {
+    inner: W,
+    hash: u64,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn put(&mut self, data: &[u8]) -> io::Result<()> {
+        self.hash = fnv1a(self.hash, data);
+        self.inner.write_all(data)
+    }
+
+    fn put_u32(&mut self, value: u32) -> io::Result<()> {
+        self.put(&value.to_le_bytes())
+    }
+
+    fn put_u64(&mut self, value: u64) -> io::Result<()> {
+        self.put(&value.to_le_bytes())
+    }
+
+    fn put_ids(&mut self, ids: &[u32]) -> io::Result<()> {
+        self.put_u32(ids.len() as u32)?;
+        for &id in ids {
+            self.put_u32(id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reader that checksums and bounds everything read through it
+struct ChecksumReader<R: Read> {
+    inner: R,
+    hash: u64,
+    remaining: u64,
+}
+
+impl<R: Read> ChecksumReader<R> {
+    fn take(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.remaining = self.remaining.saturating_sub(buf.len() as u64);
+        self.hash = fnv1a(self.hash, buf);
+        Ok(())
+    }
+
+    fn take_u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0u8; 4];
+        self.take(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn take_u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        self.take(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Read a length prefix, rejecting lengths the rest of the file cannot hold
+    fn take_len(&mut self, wide: bool, item_size: u64) -> io::Result<usize> {
+        let len = if wide {
+            self.take_u64()?
+        } else {
+            self.take_u32()? as u64
+        };
+        if len.saturating_mul(item_size) > self.remaining {
+            return Err(invalid("length exceeds file size"));
+        }
+        Ok(len as usize)
+    }
+
+    fn take_ids(&mut self, limit: usize) -> io::Result<Vec<u32>> {
+        let len = self.take_len(false, 4)?;
+        let mut ids = Vec::with_capacity(len);
+        for _ in 0..len {
+            let id = self.take_u32()?;
+            if id as usize >= limit {
+                return Err(invalid("entry id out of range"));
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+}
+
+impl UltraLowMemoryIndex {
+    /// Write a snapshot atomically (temporary file + rename)
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = path.with_extension("idx.tmp");
+        let result = self.write_snapshot(&temp_path);
+        match result {
+            Ok(()) => fs::rename(&temp_path, path),
+            Err(e) => {
+                let _ = fs::remove_file(&temp_path);
+                Err(e)
+            }
+        }
+    }
+
+    fn write_snapshot(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&INDEX_FORMAT_VERSION.to_le_bytes())?;
+
+        let mut w = ChecksumWriter {
+            inner: &mut out,
+            hash: FNV_OFFSET,
+        };
+        let synced_at = self
+            .synced_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        w.put_u64(synced_at)?;
+        let base_dir = self.base_dir.as_os_str().as_encoded_bytes();
+        w.put_u32(base_dir.len() as u32)?;
+        w.put(base_dir)?;
+        w.put_u64(self.scope.fingerprint())?;
+        w.put_u32(self.root_entry_id)?;
+        w.put_u64(self.entry_count.load(Ordering::Relaxed) as u64)?;
+        w.put_u64(self.removed_count as u64)?;
+
+        let pool = &self.string_pool;
+        w.put_u64(pool.buffer.len() as u64)?;
+        w.put(&pool.buffer)?;
+        w.put_u64(pool.index.len() as u64)?;
+        for entry in &pool.index {
+            w.put_u32(entry.hash)?;
+            w.put_u32(entry.offset)?;
+        }
+
+        w.put_u64(self.entries.len() as u64)?;
+        for entry in &self.entries {
+            let packed = entry.packed_data;
+            w.put(&entry.name_offset)?;
+            w.put(&entry.parent_id)?;
+            w.put(&[entry.size_log2])?;
+            w.put_u32(packed)?;
+        }
+        let exact_sizes = self.exact_sizes.as_deref().unwrap_or_default();
+        w.put_u64(exact_sizes.len() as u64)?;
+        for &size in exact_sizes {
+            w.put_u64(size)?;
+        }
+        w.put_u64(self.folded_names.len() as u64)?;
+        for &(entry_id, offset) in &self.folded_names {
+            w.put_u32(entry_id)?;
+            w.put_u32(offset)?;
+        }
+
+        w.put_u64(self.directory_children.len() as u64)?;
+        for children in &self.directory_children {
+            w.put_ids(children)?;
+        }
+        for bucket in &self.radix_index {
+            w.put_ids(&bucket.entries)?;
+        }
+
+        let checksum = w.hash;
+        out.write_all(&checksum.to_le_bytes())?;
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()
+    }
+
+    /// Load a snapshot written for `base_dir` and `scope`, rejecting corrupt
+    /// or foreign files
+    pub fn load_from(path: &Path, base_dir: &Path, scope: Arc<IndexScope>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut input = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not an IronDrop index"));
+        }
+        let mut version = [0u8; 4];
+        input.read_exact(&mut version)?;
+        if u32::from_le_bytes(version) != INDEX_FORMAT_VERSION {
+            return Err(invalid("unsupported index version"));
+        }
+
+        let mut r = ChecksumReader {
+            inner: &mut input,
+            hash: FNV_OFFSET,
+            remaining: file_len.saturating_sub(12),
+        };
+        let synced_at = UNIX_EPOCH + Duration::from_nanos(r.take_u64()?);
+        let dir_len = r.take_len(false, 1)?;
+        let mut dir = vec![0u8; dir_len];
+        r.take(&mut dir)?;
+        if dir != base_dir.as_os_str().as_encoded_bytes() {
+            return Err(invalid("index belongs to another directory"));
+        }
+        if r.take_u64()? != scope.fingerprint() {
+            return Err(invalid(
+                "index was built with other exclusions or depth limits",
+            ));
+        }
+        let root_entry_id = r.take_u32()?;
+        let entry_count = r.take_u64()? as usize;
+        let removed_count = r.take_u64()? as usize;
+
+        let buffer_len = r.take_len(true, 1)?;
+        let mut buffer = vec![0u8; buffer_len];
+        r.take(&mut buffer)?;
+        let pool_len = r.take_len(true, 8)?;
+        let mut index = Vec::with_capacity(pool_len);
+        for _ in 0..pool_len {
+            let hash = r.take_u32()?;
+            let offset = r.take_u32()?;
+            index.push(StringPoolEntry { hash, offset });
+        }
+
+        let entry_len = r.take_len(true, 11)?;
+        let mut entries = Vec::with_capacity(entry_len);
+        let mut raw = [0u8; 11];
+        for _ in 0..entry_len {
+            r.take(&mut raw)?;
+            entries.push(UltraCompactEntry {
+                name_offset: [raw[0], raw[1], raw[2]],
+                parent_id: [raw[3], raw[4], raw[5]],
+                size_log2: raw[6],
+                packed_data: u32::from_le_bytes([raw[7], raw[8], raw[9], raw[10]]),
+            });
+        }
+        if root_entry_id as usize >= entries.len() {
+            return Err(invalid("missing root entry"));
+        }
+        let sizes_len = r.take_len(true, 8)?;
+        if sizes_len != 0 && sizes_len != entries.len() {
+            return Err(invalid("exact sizes do not match entries"));
+        }
+        let mut sizes = Vec::with_capacity(sizes_len);
+        for _ in 0..sizes_len {
+            sizes.push(r.take_u64()?);
+        }
+        let exact_sizes = (sizes_len != 0).then_some(sizes);
+        let folded_len = r.take_len(true, 8)?;
+        let mut folded_names = Vec::with_capacity(folded_len);
+        for _ in 0..folded_len {
+            let entry_id = r.take_u32()?;
+            let offset = r.take_u32()?;
+            if entry_id as usize >= entries.len() || offset as usize >= buffer.len() {
+                return Err(invalid("folded name out of range"));
+            }
+            if folded_names
+                .last()
+                .is_some_and(|&(last, _)| last >= entry_id)
+            {
+                return Err(invalid("folded names out of order"));
+            }
+            folded_names.push((entry_id, offset));
+        }
+
+        let children_len = r.take_len(true, 4)?;
+        let mut directory_children = Vec::with_capacity(children_len);
+        for _ in 0..children_len {
+            directory_children.push(r.take_ids(entries.len())?);
+        }
+        let mut radix_index: [RadixBucket; 256] = std::array::from_fn(|_| RadixBucket::default());
+        for bucket in &mut radix_index {
+            bucket.entries = r.take_ids(entries.len())?;
+        }
+
+        let expected = r.hash;
+        let mut trailer = [0u8; 8];
+        input.read_exact(&mut trailer)?;
+        if u64::from_le_bytes(trailer) != expected {
+            return Err(invalid("checksum mismatch"));
+        }
+        if input.read(&mut [0u8; 1])? != 0 {
+            return Err(invalid("trailing data after checksum"));
+        }
+
+        let write_pos = buffer.len() as u32;
+        let index = Self {
+            string_pool: UnifiedStringPool {
+                buffer,
+                index,
+                write_pos,
+                lookup: None,
+            },
+            radix_index,
+            entries,
+            directory_children,
+            last_update: Instant::now(),
+            synced_at,
+            base_dir: base_dir.to_path_buf(),
+            entry_count: AtomicUsize::new(entry_count),
+            memory_usage: AtomicU64::new(0),
+            root_entry_id,
+            is_updating: AtomicBool::new(false),
+            removed_count,
+            exact_sizes,
+            folded_names,
+            trigrams: None,
+            trigram_budget: super::DEFAULT_TRIGRAM_BUDGET,
+            progress: Arc::default(),
+            scope,
+        };
+        index
+            .memory_usage
+            .store(index.get_memory_usage(), Ordering::Relaxed);
+        Ok(index)
+    }
+
+    /// First indexed directory modified after the snapshot was taken, if any
+    pub fn find_stale_directory(&self) -> Option<PathBuf> {
+        for (id, entry) in self.entries.iter().enumerate() {
+            if !entry.is_dir() || entry.is_removed() {
+                continue;
+            }
+            let path = self.reconstruct_path(id as u32)?;
+            match fs::metadata(&path).and_then(|m| m.modified()) {
+                Ok(modified) if modified <= self.synced_at => {}
+                _ => {
+                    debug!("Index snapshot is stale: {} changed", path.display());
+                    return Some(path);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn snapshot_round_trip_and_validation() {
+        let data = tempdir().unwrap();
+        let root = data.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs").join("annual_report.pdf"), "pdf").unwrap();
+        std::fs::write(root.join("notes.txt"), "notes").unwrap();
+        std::fs::write(root.join("Über_Plan.txt"), "plan").unwrap();
+
+        let mut index = UltraLowMemoryIndex::new(root.clone());
+        index.rebuild_index_ultra_optimized().unwrap();
+
+        let store = tempdir().unwrap();
+        let path = store.path().join("nested").join("index.idx");
+        index.save_to(&path).unwrap();
+
+        let loaded = UltraLowMemoryIndex::load_from(&path, &root, Arc::default()).unwrap();
+        assert_eq!(loaded.get_entry_count(), index.get_entry_count());
+        let results = loaded.search("annual", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "/docs/annual_report.pdf");
+        assert_eq!(loaded.search("uber", 10)[0].path, "/Über_Plan.txt");
+        assert!(loaded.find_stale_directory().is_none());
+
+        // Any directory modified after the snapshot makes it stale
+        std::thread::sleep(Duration::from_millis(20));
+        std::fs::write(root.join("docs").join("new.txt"), "new").unwrap();
+        assert_eq!(loaded.find_stale_directory(), Some(root.join("docs")));
+
+        // Foreign directories, other scopes and corrupted files are rejected
+        assert!(UltraLowMemoryIndex::load_from(&path, store.path(), Arc::default()).is_err());
+        let scope = IndexScope::new(&root, &["*.pdf".to_string()], 25, Vec::new()).unwrap();
+        assert!(UltraLowMemoryIndex::load_from(&path, &root, Arc::new(scope)).is_err());
+        let mut bytes = std::fs::read(&path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(UltraLowMemoryIndex::load_from(&path, &root, Arc::default()).is_err());
+
+        // So are snapshots from another format version
+        index.save_to(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[8] = bytes[8].wrapping_add(1);
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(UltraLowMemoryIndex::load_from(&path, &root, Arc::default()).is_err());
+    }
+
+    #[test]
+    fn load_or_build_prefers_current_snapshot() {
+        use super::super::ConcurrentUltraLowMemoryIndex;
+
+        let data = tempdir().unwrap();
+        let root = data.path().canonicalize().unwrap();
+        std::fs::write(root.join("ledger_2024.csv"), "a").unwrap();
+        let store = tempdir().unwrap();
+        let path = store.path().join("index.idx");
+
+        let mut index = UltraLowMemoryIndex::new(root.clone());
+        index.rebuild_index_ultra_optimized().unwrap();
+        index.save_to(&path).unwrap();
+
+        let mut concurrent = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        concurrent.snapshot_path = Some(path.clone());
+        concurrent.load_or_build().unwrap();
+        assert_eq!(concurrent.search("ledger", 10).unwrap().len(), 1);
+
+        // A newer directory mtime rejects the snapshot and forces a rebuild
+        std::thread::sleep(Duration::from_millis(20));
+        std::fs::write(root.join("ledger_2025.csv"), "b").unwrap();
+        let mut concurrent = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        concurrent.snapshot_path = Some(path);
+        concurrent.load_or_build().unwrap();
+        assert_eq!(concurrent.search("ledger", 10).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn snapshot_keeps_exact_sizes() {
+        use super::super::ConcurrentUltraLowMemoryIndex;
+
+        let data = tempdir().unwrap();
+        let root = data.path().canonicalize().unwrap();
+        std::fs::write(root.join("ledger.csv"), vec![b'a'; 1500]).unwrap();
+        let store = tempdir().unwrap();
+        let path = store.path().join("index.idx");
+
+        let mut index = UltraLowMemoryIndex::new(root.clone());
+        index.set_exact_sizes(true);
+        index.rebuild_index_ultra_optimized().unwrap();
+        index.save_to(&path).unwrap();
+        let loaded = UltraLowMemoryIndex::load_from(&path, &root, Arc::default()).unwrap();
+        assert!(loaded.has_exact_sizes());
+        assert_eq!(loaded.search("ledger", 1)[0].size_bytes, 1500);
+
+        // A snapshot with the other size mode is not used
+        let concurrent = ConcurrentUltraLowMemoryIndex::new(root.clone());
+        assert!(!concurrent.load_snapshot(&path));
+        let concurrent = concurrent.with_exact_sizes(true);
+        assert!(concurrent.load_snapshot(&path));
+    }
+
+    #[test]
+    fn default_path_is_per_directory() {
+        let a = default_index_path(Path::new("/srv/a"));
+        let b = default_index_path(Path::new("/srv/b"));
+        if let (Some(a), Some(b)) = (a, b) {
+            assert_ne!(a, b);
+            assert!(a.to_string_lossy().contains("search-index"));
+        }
+    }
+}
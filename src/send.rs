@@ -0,0 +1,602 @@
+// SPDX-License-Identifier: MIT
+
+//! `irondrop send`: upload files to a running IronDrop server
+//!
+//! ```text
+//! irondrop send report.pdf photos.zip --to http://192.168.1.20:8080/incoming
+//! ```
+//!
+//! The path of `--to` names the folder the files go into (`upload_to`).
+//! Files up to `--chunk-size` are sent in one `POST /_irondrop/upload`;
+//! larger ones through a resumable upload session, so a dropped connection
+//! only costs the chunk in flight: after a failure the client asks the
+//! session for its committed offset and carries on from there. Failed
+//! requests are retried with a doubling delay; client errors such as a
+//! rejected extension or an exhausted quota are not.
+//!
+//! Like webhooks, only plain `http://` servers can be reached.
+
+use crate::error::AppError;
+use crate::upload_session::SESSION_ROUTE;
+use base64::Engine;
+use clap::Parser;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Default size of one resumable chunk, and of the largest single request
+pub const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Arguments of `irondrop send`
+#[derive(Parser, Debug, Clone)]
+#[command(
+    name = "irondrop send",
+    bin_name = "irondrop send",
+    version,
+    about = "Upload files to a running IronDrop server (started with --enable-upload true)"
+)]
+pub struct SendArgs {
+    /// Files to upload
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+
+    /// Server URL; its path is the target folder, e.g. http://host:8080/incoming
+    #[arg(long)]
+    pub to: String,
+
+    /// Basic Auth credentials as NAME:PASSWORD; with only NAME the password is read from IRONDROP_PASSWORD
+    #[arg(short, long)]
+    pub user: Option<String>,
+
+    /// API token, sent as `Authorization: Bearer <token>`
+    #[arg(long)]
+    pub token: Option<String>,
+
+    /// How often a failed request is retried before the file is given up
+    #[arg(long, default_value_t = 3)]
+    pub retries: u32,
+
+    /// Files larger than this are sent in resumable chunks of this size, e.g. "8MB"
+    #[arg(long, default_value = "8MB", value_parser = validate_chunk_size)]
+    pub chunk_size: u64,
+
+    /// Do not draw progress bars
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+fn validate_chunk_size(s: &str) -> Result<u64, String> {
+    match crate::config::ini_parser::parse_file_size(s) {
+        Some(0) | None => Err(format!("Invalid chunk size: '{s}' (use e.g. 512KB or 8MB)")),
+        Some(bytes) => Ok(bytes),
+    }
+}
+
+/// `irondrop send ...` (`args` without the program name), returning the exit code
+pub fn send_command<I, T>(args: I) -> i32
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let args = match SendArgs::try_parse_from(args) {
+        Ok(args) => args,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() { 2 } else { 0 };
+        }
+    };
+    let client = match SendClient::new(&args) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return 2;
+        }
+    };
+
+    let mut failed = 0;
+    for file in &args.files {
+        if let Err(e) = client.send_file(file) {
+            eprintln!("error: {}: {e}", file.display());
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        eprintln!("{failed} of {} files failed", args.files.len());
+        1
+    } else {
+        0
+    }
+}
+
+/// IronDrop server reachable at `http://host:port`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendTarget {
+    host: String,
+    port: u16,
+    /// Folder inside the served tree, always starting with `/`
+    pub folder: String,
+}
+
+impl SendTarget {
+    /// Parse `http://host[:port][/folder]`
+    pub fn parse(url: &str) -> Result<Self, AppError> {
+        let url = url.trim();
+        let invalid =
+            |reason: &str| AppError::InvalidConfiguration(format!("Invalid URL '{url}': {reason}"));
+        let rest = if let Some(rest) = url.strip_prefix("http://") {
+            rest
+        } else if url.starts_with("https://") {
+            return Err(invalid("only http:// servers are supported"));
+        } else {
+            return Err(invalid("expected an http:// URL"));
+        };
+
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        if authority.is_empty() || authority.contains('@') {
+            return Err(invalid("missing or unsupported host"));
+        }
+        // Bracketed IPv6 literals keep their colons
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, after) = v6.split_once(']').ok_or_else(|| invalid("unclosed '['"))?;
+            let port = match after.strip_prefix(':') {
+                Some(port) => port.parse().map_err(|_| invalid("bad port"))?,
+                None if after.is_empty() => 80,
+                None => return Err(invalid("bad port")),
+            };
+            (host.to_string(), port)
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (
+                    host.to_string(),
+                    port.parse().map_err(|_| invalid("bad port"))?,
+                ),
+                None => (authority.to_string(), 80),
+            }
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+
+        let path = crate::utils::percent_decode(path.split('?').next().unwrap_or(""));
+        Ok(Self {
+            host,
+            port,
+            folder: format!("/{}", path.trim_matches('/')),
+        })
+    }
+
+    fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == 80 {
+            host
+        } else {
+            format!("{host}:{}", self.port)
+        }
+    }
+}
+
+/// Why a request did not succeed; only `Retry` is worth another attempt
+#[derive(Debug)]
+enum Failure {
+    Retry(String),
+    Fatal(String),
+}
+
+impl Failure {
+    fn message(self) -> String {
+        match self {
+            Failure::Retry(message) | Failure::Fatal(message) => message,
+        }
+    }
+
+    /// Classify an error status; server trouble is retried, refusals are not
+    fn from_reply(reply: &Reply) -> Self {
+        let message = format!("server answered {} {}", reply.status, reply.error_text());
+        match reply.status {
+            408 | 500..=504 => Failure::Retry(message),
+            _ => Failure::Fatal(message),
+        }
+    }
+}
+
+/// Body part of a file sent with a request
+struct FileRange<'a> {
+    file: &'a mut File,
+    offset: u64,
+    len: u64,
+}
+
+#[derive(Debug)]
+struct Reply {
+    status: u16,
+    /// Header names in lowercase
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl Reply {
+    /// The `error` or `message` of a JSON error body, or nothing
+    fn error_text(&self) -> String {
+        crate::utils::parse_json_string_map(&self.body)
+            .and_then(|mut map| map.remove("error").or_else(|| map.remove("message")))
+            .map(|text| format!("({text})"))
+            .unwrap_or_default()
+    }
+
+    fn upload_offset(&self) -> Option<u64> {
+        self.headers.get("upload-offset")?.trim().parse().ok()
+    }
+}
+
+struct SendClient {
+    target: SendTarget,
+    authorization: Option<String>,
+    retries: u32,
+    chunk_size: u64,
+    progress: bool,
+}
+
+impl SendClient {
+    fn new(args: &SendArgs) -> Result<Self, AppError> {
+        let target = SendTarget::parse(&args.to)?;
+        let authorization = match (&args.user, &args.token) {
+            (Some(_), Some(_)) => {
+                return Err(AppError::InvalidConfiguration(
+                    "--user and --token cannot be combined".to_string(),
+                ));
+            }
+            (Some(user), None) => {
+                let credentials = if user.contains(':') {
+                    user.clone()
+                } else {
+                    let password = std::env::var("IRONDROP_PASSWORD").map_err(|_| {
+                        AppError::InvalidConfiguration(
+                            "--user without a password needs IRONDROP_PASSWORD".to_string(),
+                        )
+                    })?;
+                    format!("{user}:{password}")
+                };
+                Some(format!(
+                    "Basic {}",
+                    base64::engine::general_purpose::STANDARD.encode(credentials)
+                ))
+            }
+            (None, Some(token)) => Some(format!("Bearer {token}")),
+            (None, None) => None,
+        };
+        Ok(Self {
+            target,
+            authorization,
+            retries: args.retries,
+            chunk_size: args.chunk_size,
+            progress: !args.quiet && io::stderr().is_terminal(),
+        })
+    }
+
+    fn send_file(&self, path: &Path) -> Result<(), String> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| "not a file".to_string())?;
+        let mut file = File::open(path).map_err(|e| e.to_string())?;
+        let size = file.metadata().map_err(|e| e.to_string())?.len();
+        let mut progress = Progress::new(&name, size, self.progress);
+
+        let result = if size <= self.chunk_size {
+            self.with_retries(|| self.send_whole(&mut file, &name, size, &mut progress))
+        } else {
+            self.send_in_chunks(&mut file, &name, size, &mut progress)
+        };
+        progress.finish();
+        let reply = result?;
+        println!(
+            "Sent {} ({}) to {}",
+            path.display(),
+            crate::fs::format_file_size(size),
+            self.target.folder
+        );
+        log::debug!("Upload of {name} answered: {}", reply.body);
+        Ok(())
+    }
+
+    /// One `POST /_irondrop/upload` with the whole file
+    fn send_whole(
+        &self,
+        file: &mut File,
+        name: &str,
+        size: u64,
+        progress: &mut Progress,
+    ) -> Result<Reply, Failure> {
+        progress.set(0);
+        let reply = self.exchange(
+            "POST",
+            &format!("/_irondrop/upload?upload_to={}", self.upload_to()),
+            &[
+                ("X-Filename", name.to_string()),
+                ("Content-Type", "application/octet-stream".to_string()),
+            ],
+            Some(FileRange {
+                file,
+                offset: 0,
+                len: size,
+            }),
+            &mut |sent| progress.set(sent),
+        )?;
+        match reply.status {
+            200 | 201 => Ok(reply),
+            _ => Err(Failure::from_reply(&reply)),
+        }
+    }
+
+    /// Send `file` through a resumable upload session
+    fn send_in_chunks(
+        &self,
+        file: &mut File,
+        name: &str,
+        size: u64,
+        progress: &mut Progress,
+    ) -> Result<Reply, String> {
+        let session = self.with_retries(|| {
+            let reply = self.exchange(
+                "POST",
+                &format!("{SESSION_ROUTE}?upload_to={}", self.upload_to()),
+                &[
+                    ("X-Filename", name.to_string()),
+                    ("X-Upload-Length", size.to_string()),
+                ],
+                None,
+                &mut |_| {},
+            )?;
+            match (reply.status, reply.headers.get("location")) {
+                (201, Some(location)) => Ok(location.clone()),
+                (201, None) => Err(Failure::Fatal("session has no Location".to_string())),
+                _ => Err(Failure::from_reply(&reply)),
+            }
+        })?;
+
+        let mut offset = 0;
+        let mut attempt = 0;
+        loop {
+            let len = self.chunk_size.min(size - offset);
+            let result = self
+                .exchange(
+                    "PUT",
+                    &session,
+                    &[(
+                        "Content-Range",
+                        format!("bytes {offset}-{}/{size}", offset + len - 1),
+                    )],
+                    Some(FileRange {
+                        file: &mut *file,
+                        offset,
+                        len,
+                    }),
+                    &mut |sent| progress.set(offset + sent),
+                )
+                .and_then(|reply| match reply.status {
+                    200 | 201 | 409 => Ok(reply),
+                    _ => Err(Failure::from_reply(&reply)),
+                });
+            match result {
+                Ok(reply) if reply.status == 201 => return Ok(reply),
+                Ok(reply) => {
+                    // 409 means the server holds a different offset than we
+                    // assumed; it says where to continue either way
+                    offset = reply.upload_offset().unwrap_or(offset + len);
+                    attempt = 0;
+                }
+                Err(Failure::Retry(message)) if attempt < self.retries => {
+                    attempt += 1;
+                    progress.note(&format!("{message}, retrying ({attempt}/{})", self.retries));
+                    std::thread::sleep(retry_delay(attempt));
+                    if let Ok(reply) = self.exchange("GET", &session, &[], None, &mut |_| {})
+                        && let Some(committed) = reply.upload_offset()
+                    {
+                        offset = committed;
+                    }
+                }
+                Err(failure) => {
+                    // Free the partial file on the server
+                    let _ = self.exchange("DELETE", &session, &[], None, &mut |_| {});
+                    return Err(failure.message());
+                }
+            }
+            progress.set(offset);
+        }
+    }
+
+    fn upload_to(&self) -> String {
+        crate::utils::percent_encode_query(&self.target.folder)
+    }
+
+    /// Run `attempt` until it succeeds, fails for good or runs out of retries
+    fn with_retries<T>(
+        &self,
+        mut attempt: impl FnMut() -> Result<T, Failure>,
+    ) -> Result<T, String> {
+        let mut tries = 0;
+        loop {
+            match attempt() {
+                Ok(value) => return Ok(value),
+                Err(Failure::Retry(message)) if tries < self.retries => {
+                    tries += 1;
+                    eprintln!("{message}, retrying ({tries}/{})", self.retries);
+                    std::thread::sleep(retry_delay(tries));
+                }
+                Err(failure) => return Err(failure.message()),
+            }
+        }
+    }
+
+    /// One request on its own connection; `sent` is told how many body
+    /// bytes have been written so far
+    fn exchange(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, String)],
+        body: Option<FileRange<'_>>,
+        sent: &mut dyn FnMut(u64),
+    ) -> Result<Reply, Failure> {
+        let retry = |e: io::Error| Failure::Retry(e.to_string());
+        let addr = (self.target.host.as_str(), self.target.port)
+            .to_socket_addrs()
+            .map_err(|e| Failure::Retry(format!("cannot resolve {}: {e}", self.target.host)))?
+            .next()
+            .ok_or_else(|| Failure::Fatal(format!("no address for {}", self.target.host)))?;
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(retry)?;
+        stream
+            .set_read_timeout(Some(IO_TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(IO_TIMEOUT)))
+            .map_err(retry)?;
+
+        let mut head = format!(
+            "{method} {path} HTTP/1.1\r\nHost: {}\r\nUser-Agent: IronDrop/{}\r\nAccept: application/json\r\nConnection: close\r\nContent-Length: {}\r\n",
+            self.target.host_header(),
+            crate::VERSION,
+            body.as_ref().map_or(0, |b| b.len)
+        );
+        if let Some(authorization) = &self.authorization {
+            head.push_str(&format!("Authorization: {authorization}\r\n"));
+        }
+        for (name, value) in headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+
+        let written = stream.write_all(head.as_bytes()).and_then(|()| match body {
+            Some(range) => copy_range(range, &mut stream, sent),
+            None => Ok(()),
+        });
+        // A server refusing the upload may answer before reading the body;
+        // its reply explains the failure better than the broken pipe
+        let mut response = Vec::new();
+        let read = stream.read_to_end(&mut response);
+        match (parse_reply(&response), written, read) {
+            (Some(reply), _, _) => Ok(reply),
+            (None, Err(e), _) | (None, Ok(()), Err(e)) => Err(retry(e)),
+            (None, Ok(()), Ok(_)) => Err(Failure::Retry("malformed response".to_string())),
+        }
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY.saturating_mul(1 << attempt.saturating_sub(1).min(5))
+}
+
+fn copy_range(
+    range: FileRange<'_>,
+    out: &mut impl Write,
+    sent: &mut dyn FnMut(u64),
+) -> io::Result<()> {
+    range.file.seek(SeekFrom::Start(range.offset))?;
+    let mut remaining = range.len;
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    while remaining > 0 {
+        let want = buffer.len().min(remaining as usize);
+        let n = range.file.read(&mut buffer[..want])?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file shrank while it was being sent",
+            ));
+        }
+        out.write_all(&buffer[..n])?;
+        remaining -= n as u64;
+        sent(range.len - remaining);
+    }
+    out.flush()
+}
+
+fn parse_reply(response: &[u8]) -> Option<Reply> {
+    let end = response.windows(4).position(|w| w == b"\r\n\r\n")?;
+    let head = String::from_utf8_lossy(&response[..end]);
+    let mut lines = head.lines();
+    let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    Some(Reply {
+        status,
+        headers,
+        body: String::from_utf8_lossy(&response[end + 4..]).into_owned(),
+    })
+}
+
+/// Progress bar for one file on standard error
+struct Progress {
+    name: String,
+    total: u64,
+    enabled: bool,
+    drawn: Option<Instant>,
+}
+
+impl Progress {
+    fn new(name: &str, total: u64, enabled: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            total,
+            enabled,
+            drawn: None,
+        }
+    }
+
+    fn set(&mut self, done: u64) {
+        if !self.enabled
+            || self
+                .drawn
+                .is_some_and(|at| at.elapsed() < PROGRESS_INTERVAL && done < self.total)
+        {
+            return;
+        }
+        self.drawn = Some(Instant::now());
+        self.draw(done);
+    }
+
+    fn draw(&self, done: u64) {
+        const WIDTH: u64 = 30;
+        let filled = (done * WIDTH).checked_div(self.total).unwrap_or(WIDTH) as usize;
+        let percent = (done * 100).checked_div(self.total).unwrap_or(100);
+        eprint!(
+            "\r{} [{}{}] {percent:>3}% {} / {}\x1b[K",
+            self.name,
+            "#".repeat(filled),
+            "-".repeat(WIDTH as usize - filled),
+            crate::fs::format_file_size(done),
+            crate::fs::format_file_size(self.total),
+        );
+        let _ = io::stderr().flush();
+    }
+
+    /// Print a message on its own line, keeping the bar below it
+    fn note(&self, message: &str) {
+        if self.enabled {
+            eprintln!("\r{message}\x1b[K");
+        } else {
+            eprintln!("{}: {message}", self.name);
+        }
+    }
+
+    fn finish(&self) {
+        if self.enabled && self.drawn.is_some() {
+            eprintln!();
+        }
+    }
+}
@@ -1,11 +1,24 @@
 // SPDX-License-Identifier: MIT
 
+use crate::audit::{AuditEvent, AuditRecord};
+use crate::bandwidth::BandwidthLimiter;
 use crate::cli::Cli;
 use crate::config::Config;
+use crate::config::reload::{ConfigReloader, RELOAD_POLL_INTERVAL};
+use crate::download_slots::DownloadSlots;
 use crate::error::AppError;
-use crate::handlers::register_internal_routes;
-use crate::middleware::AuthMiddleware;
+use crate::file_cache::FileCache;
+use crate::handlers::{InternalState, register_internal_routes_with_state};
+use crate::hooks::{HookEvent, HookSettings};
+use crate::middleware::{ApiToken, AuthMiddleware, PathAuthRule};
+use crate::password::PasswordHash;
+use crate::quota::{QuotaLimits, UploadQuota};
+use crate::read_only::{READ_ONLY_ROUTE, ReadOnlyMiddleware};
 use crate::router::Router;
+use crate::session::SessionSigner;
+use crate::share::{ShareLinkMiddleware, ShareSigner};
+use crate::users::UserAccount;
+use crate::worker_pool::WorkerPool;
 use glob::Pattern;
 use log::{debug, info, trace, warn};
 use std::collections::HashMap;
@@ -25,12 +38,83 @@ use std::fs;
 use std::mem;
 
 /// Rate limiter for basic DoS protection
+///
+/// New connections are limited per IP and minute, plus concurrently open
+/// connections per IP. Requests of a [`RouteClass`] can carry a stricter
+/// per-minute limit of their own, checked per request by
+/// [`RouteRateLimitMiddleware`].
 #[derive(Clone)]
 pub struct RateLimiter {
     connections: Arc<Vec<Mutex<HashMap<IpAddr, ConnectionInfo>>>>,
     max_requests_per_minute: u32,
     max_concurrent_per_ip: u32,
     max_connections_per_ip: u32,
+    route_limits: HashMap<RouteClass, u32>,
+    route_windows: Arc<Vec<Mutex<RouteWindows>>>,
+    /// Failed logins before a lockout and its length (`None` = no lockout)
+    auth_lockout: Option<(u32, Duration)>,
+    auth_failures: Arc<Vec<Mutex<HashMap<IpAddr, AuthFailures>>>>,
+}
+
+/// Requests that can be limited separately from plain downloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// Upload form posts, upload sessions and WebDAV `PUT`
+    Upload,
+    /// The search API
+    Search,
+}
+
+impl RouteClass {
+    pub const ALL: [RouteClass; 2] = [RouteClass::Upload, RouteClass::Search];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteClass::Upload => "upload",
+            RouteClass::Search => "search",
+        }
+    }
+
+    /// Class of a request, or `None` when only the connection limits apply
+    pub fn for_request(method: &str, path: &str) -> Option<Self> {
+        if path == "/_irondrop/search" {
+            return Some(RouteClass::Search);
+        }
+        let writes = !matches!(method, "GET" | "HEAD" | "OPTIONS");
+        if writes && path.starts_with("/_irondrop/upload") {
+            return Some(RouteClass::Upload);
+        }
+        if method == "PUT" && !path.starts_with("/_irondrop/") {
+            return Some(RouteClass::Upload);
+        }
+        None
+    }
+}
+
+type RouteWindows = HashMap<(IpAddr, RouteClass), RouteWindow>;
+
+/// Requests of one class from one IP in the current minute
+#[derive(Debug)]
+struct RouteWindow {
+    count: u32,
+    started: Instant,
+}
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Longest lockout the backoff grows to (unless the base lockout is longer)
+const MAX_AUTH_LOCKOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Failed logins from one IP; failures are counted from `started` for one
+/// lockout period. `strikes` counts the lockouts in a row: each one lasts
+/// twice as long as the last, and they are forgotten once the IP stays quiet
+/// for as long as its last lockout lasted.
+#[derive(Debug)]
+struct AuthFailures {
+    count: u32,
+    started: Instant,
+    locked_until: Option<Instant>,
+    strikes: u32,
 }
 
 #[derive(Debug)]
@@ -53,12 +137,181 @@ impl RateLimiter {
         for _ in 0..RATE_LIMITER_SHARDS {
             shards.push(Mutex::new(HashMap::new()));
         }
+        let route_windows = (0..RATE_LIMITER_SHARDS)
+            .map(|_| Mutex::new(HashMap::new()))
+            .collect();
+        let auth_failures = (0..RATE_LIMITER_SHARDS)
+            .map(|_| Mutex::new(HashMap::new()))
+            .collect();
         Self {
             connections: Arc::new(shards),
             max_requests_per_minute,
             max_concurrent_per_ip,
             max_connections_per_ip: 1000, // Limit stored connections per IP
+            route_limits: HashMap::new(),
+            route_windows: Arc::new(route_windows),
+            auth_lockout: None,
+            auth_failures: Arc::new(auth_failures),
+        }
+    }
+
+    /// Lock an IP out for `lockout` after `max_failures` failed logins within
+    /// that time (`max_failures` 0 = never)
+    pub fn with_auth_lockout(mut self, max_failures: u32, lockout: Duration) -> Self {
+        self.auth_lockout = (max_failures > 0).then_some((max_failures, lockout));
+        self
+    }
+
+    /// Failed logins before a lockout and its length, if lockouts are on
+    pub fn auth_lockout_limits(&self) -> Option<(u32, Duration)> {
+        self.auth_lockout
+    }
+
+    /// Seconds left on the lockout of `ip`, if it is locked out
+    pub fn auth_lockout(&self, ip: IpAddr) -> Option<u64> {
+        self.auth_lockout?;
+        let failures = self.auth_failures[Self::shard_index(ip)].lock().ok()?;
+        let remaining = failures
+            .get(&ip)?
+            .locked_until?
+            .checked_duration_since(Instant::now())?;
+        Some(remaining.as_secs().max(1))
+    }
+
+    /// Count a failed login from `ip`; returns the lockout in seconds when
+    /// this failure starts one. Repeated lockouts double, up to a day.
+    pub fn record_auth_failure(&self, ip: IpAddr) -> Option<u64> {
+        let (max_failures, lockout) = self.auth_lockout?;
+        let mut failures = self.auth_failures[Self::shard_index(ip)].lock().ok()?;
+        let now = Instant::now();
+        if !failures.contains_key(&ip) && failures.len() >= MAX_RATE_LIMITER_ENTRIES_PER_SHARD {
+            failures.retain(|_, entry| Self::auth_entry_live(entry, lockout, now));
+        }
+        let fresh = || AuthFailures {
+            count: 0,
+            started: now,
+            locked_until: None,
+            strikes: 0,
+        };
+        let entry = failures.entry(ip).or_insert_with(fresh);
+        if !Self::auth_entry_live(entry, lockout, now) {
+            *entry = fresh();
+        }
+        let locked = entry.locked_until.is_some_and(|until| now < until);
+        // A finished lockout or an expired window starts a new count, keeping the strikes
+        if !locked && (entry.count >= max_failures || now.duration_since(entry.started) >= lockout)
+        {
+            entry.count = 0;
+            entry.started = now;
+        }
+        entry.count += 1;
+        trace!("Failed login {}/{} for {}", entry.count, max_failures, ip);
+        if entry.count >= max_failures && !locked {
+            entry.strikes += 1;
+            let duration = Self::backoff(lockout, entry.strikes);
+            entry.locked_until = Some(now + duration);
+            return Some(duration.as_secs().max(1));
+        }
+        None
+    }
+
+    /// Length of the `strikes`-th lockout in a row
+    fn backoff(lockout: Duration, strikes: u32) -> Duration {
+        let factor = 1u32 << strikes.saturating_sub(1).min(16);
+        lockout
+            .saturating_mul(factor)
+            .min(MAX_AUTH_LOCKOUT.max(lockout))
+    }
+
+    /// Forget the failed logins of `ip` after it authenticated
+    pub fn clear_auth_failures(&self, ip: IpAddr) {
+        if self.auth_lockout.is_some()
+            && let Ok(mut failures) = self.auth_failures[Self::shard_index(ip)].lock()
+        {
+            failures.remove(&ip);
+        }
+    }
+
+    /// Whether an entry still counts: failures within the window, a running
+    /// lockout, or one that ended less than its own length ago
+    fn auth_entry_live(entry: &AuthFailures, lockout: Duration, now: Instant) -> bool {
+        match entry.locked_until {
+            Some(until) => now < until + Self::backoff(lockout, entry.strikes),
+            None => now.duration_since(entry.started) < lockout,
+        }
+    }
+
+    /// Also limit requests of `class` to `limit` per IP and minute (`None` = no extra limit)
+    pub fn with_route_limit(mut self, class: RouteClass, limit: Option<u32>) -> Self {
+        match limit {
+            Some(limit) => self.route_limits.insert(class, limit),
+            None => self.route_limits.remove(&class),
+        };
+        self
+    }
+
+    pub fn max_requests_per_minute(&self) -> u32 {
+        self.max_requests_per_minute
+    }
+
+    pub fn max_concurrent_per_ip(&self) -> u32 {
+        self.max_concurrent_per_ip
+    }
+
+    /// Per-minute limit for `class`, if one is configured
+    pub fn route_limit(&self, class: RouteClass) -> Option<u32> {
+        self.route_limits.get(&class).copied()
+    }
+
+    /// Whether both limiters enforce the same limits (their counters may differ)
+    fn same_limits(&self, other: &RateLimiter) -> bool {
+        self.max_requests_per_minute == other.max_requests_per_minute
+            && self.max_concurrent_per_ip == other.max_concurrent_per_ip
+            && self.route_limits == other.route_limits
+            && self.auth_lockout == other.auth_lockout
+    }
+
+    /// Count a request of `class` from `ip`; `Err` carries the seconds until
+    /// the window resets when the class limit is exhausted
+    pub fn check_route_limit(&self, ip: IpAddr, class: RouteClass) -> Result<(), u64> {
+        let Some(limit) = self.route_limit(class) else {
+            return Ok(());
+        };
+        let shard_index = Self::shard_index(ip);
+        let Ok(mut windows) = self.route_windows[shard_index].lock() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if !windows.contains_key(&(ip, class))
+            && windows.len() >= MAX_RATE_LIMITER_ENTRIES_PER_SHARD
+        {
+            windows.retain(|_, window| now.duration_since(window.started) < RATE_LIMIT_WINDOW);
+        }
+        let window = windows.entry((ip, class)).or_insert(RouteWindow {
+            count: 0,
+            started: now,
+        });
+        let elapsed = now.duration_since(window.started);
+        if elapsed >= RATE_LIMIT_WINDOW {
+            window.count = 0;
+            window.started = now;
         }
+        if window.count >= limit {
+            warn!(
+                "Rate limit exceeded for {ip}: more than {limit} {} requests per minute",
+                class.as_str()
+            );
+            return Err(RATE_LIMIT_WINDOW.saturating_sub(elapsed).as_secs().max(1));
+        }
+        window.count += 1;
+        trace!(
+            "{} request {}/{} this minute for {}",
+            class.as_str(),
+            window.count,
+            limit,
+            ip
+        );
+        Ok(())
     }
 
     fn shard_index(ip: IpAddr) -> usize {
@@ -197,6 +450,22 @@ impl RateLimiter {
                 connections.shrink_to_fit();
             }
         }
+        for shard in self.route_windows.iter() {
+            if let Ok(mut windows) = shard.lock() {
+                let initial_count = windows.len();
+                windows.retain(|_, window| now.duration_since(window.started) < RATE_LIMIT_WINDOW);
+                cleaned_count += initial_count - windows.len();
+            }
+        }
+        if let Some((_, lockout)) = self.auth_lockout {
+            for shard in self.auth_failures.iter() {
+                if let Ok(mut failures) = shard.lock() {
+                    let initial_count = failures.len();
+                    failures.retain(|_, entry| Self::auth_entry_live(entry, lockout, now));
+                    cleaned_count += initial_count - failures.len();
+                }
+            }
+        }
 
         if cleaned_count == 0 {
             trace!("No old entries to clean up");
@@ -269,6 +538,253 @@ impl RateLimiter {
     }
 }
 
+/// Enforces the per-route limits of a [`RateLimiter`] (and the per-minute
+/// limit for clients behind a [`TrustedProxies`] entry), answering `429` with
+/// `Retry-After` once an IP used up its share for the minute
+pub struct RouteRateLimitMiddleware {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RouteRateLimitMiddleware {
+    pub fn new(limiter: Arc<RateLimiter>) -> Self {
+        Self { limiter }
+    }
+}
+
+impl crate::middleware::Middleware for RouteRateLimitMiddleware {
+    fn handle(&self, request: &crate::http::Request) -> Result<(), AppError> {
+        let Some(peer) = request.peer_addr else {
+            return Ok(());
+        };
+        // Connections from trusted proxies skip the connection limit, so the
+        // per-minute budget is charged to the forwarded client per request
+        if request.forwarded_by.is_some() {
+            if !self.limiter.check_rate_limit(peer.ip()) {
+                crate::hooks::emit(
+                    HookEvent::RateLimited,
+                    &[("client", peer.ip().to_string().into())],
+                );
+                crate::audit::record(
+                    AuditEvent::RateLimitBan,
+                    AuditRecord::for_request(request, "denied").with("route", "requests"),
+                );
+                return Err(AppError::TooManyRequests(RATE_LIMIT_WINDOW.as_secs()));
+            }
+            self.limiter.release_connection(peer.ip());
+        }
+        let method = request.method.to_uppercase();
+        let Some(class) = RouteClass::for_request(&method, &request.path) else {
+            return Ok(());
+        };
+        self.limiter
+            .check_route_limit(peer.ip(), class)
+            .map_err(|retry_after| {
+                crate::hooks::emit(
+                    HookEvent::RateLimited,
+                    &[
+                        ("client", peer.ip().to_string().into()),
+                        ("route", class.as_str().into()),
+                    ],
+                );
+                crate::audit::record(
+                    AuditEvent::RateLimitBan,
+                    AuditRecord::for_request(request, "denied").with("route", class.as_str()),
+                );
+                AppError::TooManyRequests(retry_after)
+            })
+    }
+}
+
+/// A parsed CIDR block such as `10.0.0.0/8` or `2001:db8::/32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CidrBlock {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl CidrBlock {
+    /// Parse `addr/prefix`; a bare address is treated as a single host
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        let value = value.trim();
+        let invalid =
+            || AppError::InvalidConfiguration(format!("Invalid IP or CIDR range: '{value}'"));
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let network: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.trim().parse::<u8>().map_err(|_| invalid())?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            return Err(invalid());
+        }
+        Ok(Self { network, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // Treat IPv4-mapped IPv6 peers (dual-stack listeners) as IPv4
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Connection-level allow/deny filtering applied right after `accept()`.
+///
+/// Deny rules win over allow rules; a non-empty allow list rejects everything
+/// it does not match.
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+    allow: Vec<CidrBlock>,
+    deny: Vec<CidrBlock>,
+    log_rejected: bool,
+}
+
+impl IpFilter {
+    pub fn new(allow: Vec<CidrBlock>, deny: Vec<CidrBlock>, log_rejected: bool) -> Self {
+        Self {
+            allow,
+            deny,
+            log_rejected,
+        }
+    }
+
+    /// Build the filter from comma-separated `--allow-ips` / `--deny-ips` values
+    pub fn from_cli(cli: &Cli) -> Result<Self, AppError> {
+        let parse = |list: &Option<String>| -> Result<Vec<CidrBlock>, AppError> {
+            list.as_deref()
+                .unwrap_or("")
+                .split(',')
+                .filter(|entry| !entry.trim().is_empty())
+                .map(CidrBlock::parse)
+                .collect()
+        };
+        Ok(Self::new(
+            parse(&cli.allow_ips)?,
+            parse(&cli.deny_ips)?,
+            cli.log_rejected_ips.unwrap_or(false),
+        ))
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        if self.deny.iter().any(|block| block.contains(ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|block| block.contains(ip))
+    }
+
+    /// Check a freshly accepted peer, logging the rejection if configured
+    fn admit(&self, peer_addr: SocketAddr) -> bool {
+        let allowed = self.is_allowed(peer_addr.ip());
+        if !allowed && self.log_rejected {
+            warn!("Rejected connection from {} by IP filter", peer_addr.ip());
+        }
+        allowed
+    }
+}
+
+/// Reverse proxies whose forwarding headers are believed
+///
+/// For a connection from a trusted proxy the client is taken from
+/// `Forwarded: for=...` (or `X-Forwarded-For` when there is no `Forwarded`
+/// header). The hops are read right to left, skipping further trusted
+/// proxies, so a client cannot pose as someone else by sending the header
+/// itself. Headers from any other peer are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    blocks: Vec<CidrBlock>,
+}
+
+impl TrustedProxies {
+    pub fn new(blocks: Vec<CidrBlock>) -> Self {
+        Self { blocks }
+    }
+
+    /// Build the list from the comma-separated `--trusted-proxies` value
+    pub fn from_cli(cli: &Cli) -> Result<Self, AppError> {
+        let blocks = cli
+            .trusted_proxies
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(CidrBlock::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(blocks))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.blocks.iter().any(|block| block.contains(ip))
+    }
+
+    /// The client behind `peer`, or `None` when `peer` is not a trusted proxy
+    /// (lowercase header names, as in [`crate::http::Request`])
+    pub fn client_ip(&self, peer: IpAddr, headers: &HashMap<String, String>) -> Option<IpAddr> {
+        if !self.is_trusted(peer) {
+            return None;
+        }
+        let hops: Vec<Option<IpAddr>> = if let Some(forwarded) = headers.get("forwarded") {
+            forwarded
+                .split(',')
+                .map(|element| {
+                    element
+                        .split(';')
+                        .filter_map(|pair| pair.split_once('='))
+                        .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                        .and_then(|(_, value)| parse_forwarded_node(value))
+                })
+                .collect()
+        } else if let Some(forwarded_for) = headers.get("x-forwarded-for") {
+            forwarded_for.split(',').map(parse_forwarded_node).collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut client = peer;
+        for hop in hops.iter().rev() {
+            // Obfuscated or garbled hops end the chain at the last known address
+            let Some(ip) = *hop else { break };
+            client = ip;
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+        Some(client)
+    }
+}
+
+/// An address from `X-Forwarded-For` or a `Forwarded` `for=` value:
+/// `203.0.113.7`, `203.0.113.7:4711`, `"[2001:db8::1]:4711"` or `2001:db8::1`
+fn parse_forwarded_node(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+    if let Some(rest) = value.strip_prefix('[') {
+        return rest.split(']').next()?.parse().ok();
+    }
+    if let Ok(ip) = value.parse() {
+        return Some(ip);
+    }
+    value.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
+
 /// Comprehensive server statistics and monitoring
 ///
 /// Tracks both HTTP request statistics and file upload metrics with thread-safe
@@ -293,6 +809,7 @@ pub struct ServerStats {
     successful_requests: AtomicU64,
     error_requests: AtomicU64,
     bytes_served: AtomicU64,
+    timed_out_requests: AtomicU64,
     start_time: Instant,
 
     // Upload statistics
@@ -310,6 +827,15 @@ pub struct ServerStats {
     peak_memory_bytes: Mutex<Option<u64>>,
     last_memory_check: Mutex<Option<Instant>>,
     memory_available: AtomicBool,
+
+    // Large download slots
+    download_slots: Arc<DownloadSlots>,
+
+    // Blocking handler workers
+    workers: Arc<WorkerPool>,
+
+    // In-memory cache of small files
+    file_cache: Arc<FileCache>,
 }
 
 impl ServerStats {
@@ -320,6 +846,7 @@ impl ServerStats {
             successful_requests: AtomicU64::new(0),
             error_requests: AtomicU64::new(0),
             bytes_served: AtomicU64::new(0),
+            timed_out_requests: AtomicU64::new(0),
             start_time: Instant::now(),
 
             // Upload statistics
@@ -337,6 +864,12 @@ impl ServerStats {
             peak_memory_bytes: Mutex::new(None),
             last_memory_check: Mutex::new(None),
             memory_available: AtomicBool::new(true),
+
+            download_slots: Arc::new(DownloadSlots::default()),
+
+            workers: Arc::new(WorkerPool::default()),
+
+            file_cache: Arc::new(FileCache::default()),
         }
     }
 
@@ -350,6 +883,36 @@ impl ServerStats {
         self.bytes_served.fetch_add(bytes, Ordering::Relaxed);
     }
 
+    /// Record a connection closed because the client was too slow
+    pub fn record_timeout(&self) {
+        self.timed_out_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of connections closed with 408 Request Timeout
+    pub fn get_timeout_count(&self) -> u64 {
+        self.timed_out_requests.load(Ordering::Relaxed)
+    }
+
+    /// Slots limiting concurrent large downloads
+    pub fn download_slots(&self) -> &Arc<DownloadSlots> {
+        &self.download_slots
+    }
+
+    /// Workers running blocking file-request handlers
+    pub fn workers(&self) -> &Arc<WorkerPool> {
+        &self.workers
+    }
+
+    /// Cache of small files served from memory
+    pub fn file_cache(&self) -> &Arc<FileCache> {
+        &self.file_cache
+    }
+
+    /// File cache hits and misses since startup
+    pub fn get_file_cache_stats(&self) -> (u64, u64) {
+        (self.file_cache.hits(), self.file_cache.misses())
+    }
+
     pub fn get_stats(&self) -> (u64, u64, u64, u64, Duration) {
         let total = self.total_requests.load(Ordering::Relaxed);
         let successful = self.successful_requests.load(Ordering::Relaxed);
@@ -1115,26 +1678,695 @@ fn load_tls_key(
 fn build_tls_config(
     cert_path: &std::path::Path,
     key_path: &std::path::Path,
+    http2: bool,
 ) -> Result<Arc<ServerConfig>, AppError> {
     let certs = load_tls_certs(cert_path)?;
     let key = load_tls_key(key_path)?;
 
-    let config = ServerConfig::builder()
+    #[allow(unused_mut)]
+    let mut config = ServerConfig::builder()
         .with_no_client_auth()
         .with_single_cert(certs, key)
         .map_err(|e| {
             AppError::InvalidConfiguration(format!("Failed to build TLS configuration: {}", e))
         })?;
+    #[cfg(feature = "http2")]
+    if http2 {
+        config.alpn_protocols = vec![
+            crate::http2::ALPN_H2.to_vec(),
+            crate::http2::ALPN_HTTP11.to_vec(),
+        ];
+        info!("HTTP/2 offered via ALPN");
+    }
+    #[cfg(not(feature = "http2"))]
+    let _ = http2;
 
     info!("TLS configuration built successfully");
     Ok(Arc::new(config))
 }
 
+/// Problems found by [`check_config`]
+#[derive(Debug, Default)]
+pub struct ConfigCheck {
+    /// Settings the server would refuse to start with
+    pub errors: Vec<String>,
+    /// Settings that work but are probably not what was meant
+    pub warnings: Vec<String>,
+}
+
+impl ConfigCheck {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Validate `config` the way startup would, without binding or changing
+/// any server state (`--check-config`)
+pub fn check_config(config: &Config) -> ConfigCheck {
+    let mut check = ConfigCheck::default();
+    let cli = cli_from_config(config.clone());
+    let mut error = |result: Result<(), AppError>| {
+        if let Err(e) = result {
+            check.errors.push(e.to_string());
+        }
+    };
+
+    error(cli.validate());
+    let base_dir = cli.directory.canonicalize().ok();
+    if let Some(dir) = &base_dir
+        && let Err(e) = fs::read_dir(dir)
+    {
+        error(Err(AppError::InvalidConfiguration(format!(
+            "Cannot read directory {}: {e}",
+            dir.display()
+        ))));
+    }
+
+    error(cli.allowed_extension_patterns().map(drop));
+    error(IpFilter::from_cli(&cli).map(drop));
+    error(TrustedProxies::from_cli(&cli).map(drop));
+    error(HookSettings::from_cli(&cli).map(drop));
+    error(crate::redirects::RedirectRules::from_cli(&cli).map(drop));
+    if let Some(dir) = &base_dir {
+        error(
+            crate::fs::IgnoreRules::new(dir, config.hide_dotfiles, &config.ignore_patterns)
+                .map(drop),
+        );
+        error(crate::search::IndexScope::from_cli(&cli, dir).map(drop));
+    }
+    if let (Some(cert), Some(key)) = (&cli.ssl_cert, &cli.ssl_key) {
+        error(build_tls_config(cert, key, config.http2).map(drop));
+    }
+
+    let has_password = config.password.is_some() || config.password_hash.is_some();
+    if config.username.is_some() != has_password {
+        error(Err(AppError::InvalidConfiguration(
+            "username and password must be set together for basic authentication".to_string(),
+        )));
+    }
+    for entry in &config.users {
+        if let Err(e) = UserAccount::parse(entry) {
+            error(Err(AppError::InvalidConfiguration(format!(
+                "Invalid [users] entry: {e}"
+            ))));
+        }
+    }
+    for entry in &config.auth_paths {
+        if PathAuthRule::parse_list(std::slice::from_ref(entry)).is_empty() {
+            error(Err(AppError::InvalidConfiguration(format!(
+                "Invalid auth path entry for '{}' (expected /prefix=user:password)",
+                entry.split('=').next().unwrap_or_default()
+            ))));
+        }
+    }
+    for (path, what) in [
+        (&config.template_dir, "template directory"),
+        (&config.custom_css, "custom CSS"),
+    ] {
+        if let Some(path) = path
+            && !path.exists()
+        {
+            error(Err(AppError::InvalidConfiguration(format!(
+                "The {what} {} does not exist",
+                path.display()
+            ))));
+        }
+    }
+    if let Some(log_dir) = &config.log_dir
+        && log_dir.exists()
+        && !log_dir.is_dir()
+    {
+        error(Err(AppError::InvalidConfiguration(format!(
+            "The log directory {} is not a directory",
+            log_dir.display()
+        ))));
+    }
+
+    let bind_address = format!("{}:{}", config.listen, config.port);
+    match std::net::ToSocketAddrs::to_socket_addrs(&bind_address) {
+        Err(e) => check
+            .errors
+            .push(format!("Invalid listen address '{}': {e}", config.listen)),
+        Ok(_) if config.port != 0 => {
+            if let Err(e) = std::net::TcpListener::bind(&bind_address) {
+                check
+                    .warnings
+                    .push(format!("Cannot bind {bind_address} right now: {e}"));
+            }
+        }
+        Ok(_) => {}
+    }
+    let authenticated = config.username.is_some() || !config.auth_tokens.is_empty();
+    if config.enable_file_management && !authenticated {
+        check.warnings.push(
+            "File management is enabled without authentication; anyone who can reach the server can delete or move files".to_string(),
+        );
+    }
+    if config.enable_edit && !authenticated && config.users.is_empty() {
+        check
+            .warnings
+            .push("Editing only takes effect when authentication is enabled".to_string());
+    }
+    if config.share_secret.is_some() && !authenticated {
+        check
+            .warnings
+            .push("Share links only take effect when authentication is enabled".to_string());
+    }
+    check
+}
+
+/// Settings derived from the configuration that each connection needs
+///
+/// Built once at startup and rebuilt on every config reload; connections keep
+/// the snapshot they were accepted with.
+struct ServerState {
+    cli: Arc<Cli>,
+    allowed_extensions: Arc<Vec<Pattern>>,
+    username: Arc<Option<String>>,
+    password: Arc<Option<String>>,
+    chunk_size: usize,
+    rate_limiter: Arc<RateLimiter>,
+    rate_limit_disabled: bool,
+    ip_filter: IpFilter,
+    trusted_proxies: Arc<TrustedProxies>,
+    router: Arc<Router>,
+    bandwidth: Arc<BandwidthLimiter>,
+}
+
+impl ServerState {
+    fn build(
+        cli: Cli,
+        stats: &Arc<ServerStats>,
+        base_dir: &Arc<std::path::PathBuf>,
+        internal: &InternalState,
+        previous: Option<&ServerState>,
+    ) -> Result<Self, AppError> {
+        // Everything that can fail is parsed before any process-wide setting
+        // changes, so a rejected reload leaves the running configuration alone
+        let allowed_extensions = Arc::new(
+            cli.allowed_extensions
+                .as_ref()
+                .unwrap_or(&"*".to_string())
+                .split(',')
+                .map(|ext| Pattern::new(ext.trim()))
+                .collect::<Result<Vec<Pattern>, _>>()?,
+        );
+        let ip_filter = IpFilter::from_cli(&cli)?;
+        let trusted_proxies = Arc::new(TrustedProxies::from_cli(&cli)?);
+        let hooks = HookSettings::from_cli(&cli)?;
+        let redirects = crate::redirects::RedirectRules::from_cli(&cli)?;
+        let password_hash = cli
+            .password_hash
+            .as_deref()
+            .map(PasswordHash::parse)
+            .transpose()
+            .map_err(|e| AppError::InvalidConfiguration(format!("Invalid password hash: {e}")))?;
+
+        let webdav_enabled = cli.enable_webdav.unwrap_or(false);
+        let disable_rate_limit_requested = cli.disable_rate_limit.unwrap_or(false);
+        let rate_limit_disabled = webdav_enabled && disable_rate_limit_requested;
+        if disable_rate_limit_requested && !webdav_enabled {
+            warn!(
+                "Ignoring --disable-rate-limit because WebDAV is disabled. Enable WebDAV for it to take effect."
+            );
+        }
+        if rate_limit_disabled {
+            info!("WebDAV rate limiting is disabled by configuration.");
+        }
+        let (default_per_minute, default_concurrent) = if webdav_enabled {
+            (3500, 128)
+        } else {
+            (120, 10)
+        };
+        let limiter = RateLimiter::new(
+            cli.rate_limit.unwrap_or(default_per_minute),
+            cli.rate_limit_concurrent.unwrap_or(default_concurrent),
+        )
+        .with_route_limit(RouteClass::Upload, cli.rate_limit_upload)
+        .with_route_limit(RouteClass::Search, cli.rate_limit_search)
+        .with_auth_lockout(
+            cli.auth_max_failures
+                .unwrap_or(crate::middleware::DEFAULT_AUTH_MAX_FAILURES),
+            Duration::from_secs(
+                cli.auth_lockout_secs
+                    .unwrap_or(crate::middleware::DEFAULT_AUTH_LOCKOUT_SECS),
+            ),
+        );
+        // Keep per-IP counters across reloads unless the limits changed
+        let rate_limiter = match previous {
+            Some(previous) if previous.rate_limiter.same_limits(&limiter) => {
+                previous.rate_limiter.clone()
+            }
+            _ => Arc::new(limiter),
+        };
+        if !rate_limit_disabled {
+            info!(
+                "Rate limit: {} connections/min and {} concurrent per IP",
+                rate_limiter.max_requests_per_minute(),
+                rate_limiter.max_concurrent_per_ip()
+            );
+            for class in RouteClass::ALL {
+                if let Some(limit) = rate_limiter.route_limit(class) {
+                    info!("Rate limit: {limit} {} requests/min per IP", class.as_str());
+                }
+            }
+        }
+
+        stats.download_slots().configure(
+            cli.max_concurrent_downloads,
+            cli.download_queue.unwrap_or(0),
+        );
+        if let Some(limit) = cli.max_concurrent_downloads {
+            info!(
+                "📦 Concurrent large downloads limited to {limit} (queue: {})",
+                cli.download_queue.unwrap_or(0)
+            );
+        }
+        crate::disk_space::configure(base_dir, cli.upload_disk_reserve.unwrap_or(0));
+        let file_cache_size = cli.file_cache_size.unwrap_or(0);
+        let file_cache_max_file = cli
+            .file_cache_max_file
+            .unwrap_or(crate::file_cache::DEFAULT_MAX_FILE_BYTES);
+        stats
+            .file_cache()
+            .configure(file_cache_size, file_cache_max_file);
+        if file_cache_size > 0 {
+            info!(
+                "🗃️ Caching files up to {file_cache_max_file} bytes in memory ({file_cache_size} bytes total)"
+            );
+        }
+        stats.workers().configure(
+            cli.max_workers,
+            cli.worker_queue
+                .unwrap_or(crate::worker_pool::DEFAULT_QUEUE),
+        );
+        if let Some(limit) = cli.max_workers {
+            info!(
+                "🧵 File request workers limited to {limit} (queue: {})",
+                cli.worker_queue
+                    .unwrap_or(crate::worker_pool::DEFAULT_QUEUE)
+            );
+        }
+        if !trusted_proxies.is_empty() {
+            info!(
+                "Trusting forwarded client addresses from {} proxy range(s)",
+                trusted_proxies.blocks.len()
+            );
+        }
+        if ip_filter.is_active() {
+            info!(
+                "IP filtering enabled ({} allow, {} deny rules)",
+                ip_filter.allow.len(),
+                ip_filter.deny.len()
+            );
+        }
+        internal.quota.set_limits(QuotaLimits {
+            daily_bytes: cli.upload_quota_daily,
+            total_bytes: cli.upload_quota_total,
+        });
+        crate::hooks::configure(hooks);
+        crate::redirects::configure(redirects);
+        crate::audit::configure(
+            cli.audit.unwrap_or(false).then(|| {
+                crate::audit::audit_path(cli.audit_file.as_deref(), cli.log_dir.as_deref())
+            }),
+        );
+
+        let username = Arc::new(cli.username.clone());
+        let password = Arc::new(cli.password.clone());
+        let chunk_size = cli.chunk_size.unwrap_or(1024);
+        let bandwidth = Arc::new(BandwidthLimiter::from_cli(&cli));
+        if !bandwidth.is_unlimited() {
+            let describe = |rate: Option<u64>| {
+                rate.map_or("unlimited".to_string(), crate::bandwidth::format_bandwidth)
+            };
+            info!(
+                "⏱️ Bandwidth limit: {} total, {} per connection",
+                describe(cli.max_bandwidth),
+                describe(cli.per_conn_bandwidth)
+            );
+        }
+        crate::bandwidth::configure_uploads(cli.max_upload_bandwidth, cli.per_upload_bandwidth);
+        if cli.max_upload_bandwidth.is_some() || cli.per_upload_bandwidth.is_some() {
+            let describe = |rate: Option<u64>| {
+                rate.map_or("unlimited".to_string(), crate::bandwidth::format_bandwidth)
+            };
+            info!(
+                "⏱️ Upload bandwidth limit: {} total, {} per upload",
+                describe(cli.max_upload_bandwidth),
+                describe(cli.per_upload_bandwidth)
+            );
+        }
+        let cli_arc = Arc::new(cli);
+
+        let mut router = Router::new();
+        // A quick share answers nothing but its one URL, before any other check
+        if let Some(share) = &cli_arc.quick_share {
+            router.add_middleware(Box::new(share.clone()));
+            let share = share.clone();
+            router.add_sent_hook(Box::new(move |method, path, query, status_code| {
+                share.record_sent(method, path, query, status_code)
+            }));
+        }
+        if !rate_limit_disabled {
+            router.add_middleware(Box::new(RouteRateLimitMiddleware::new(
+                rate_limiter.clone(),
+            )));
+        }
+        let api_tokens = cli_arc
+            .auth_tokens
+            .as_deref()
+            .map(|list| {
+                let entries: Vec<String> = list.split(',').map(str::to_string).collect();
+                ApiToken::parse_list(&entries)
+            })
+            .unwrap_or_default();
+        let mut accounts = cli_arc
+            .users
+            .as_deref()
+            .map(|list| UserAccount::parse_list(&crate::users::split_entries(list)))
+            .unwrap_or_default();
+        accounts.retain(|account| {
+            let clashes = cli_arc.username.as_deref() == Some(account.name.as_str());
+            if clashes {
+                warn!(
+                    "Ignoring [users] entry '{}': it has the name of the [auth] user",
+                    account.name
+                );
+            }
+            !clashes
+        });
+        for account in &accounts {
+            debug!(
+                "User account '{}' with role {}",
+                account.name,
+                account.role.as_str()
+            );
+        }
+        let has_logins = (cli_arc.username.is_some()
+            && (cli_arc.password.is_some() || cli_arc.password_hash.is_some()))
+            || !accounts.is_empty();
+        // Switching read-only mode at runtime needs credentials covering every path
+        let global_auth = (cli_arc.username.is_some()
+            && (cli_arc.password.is_some() || cli_arc.password_hash.is_some()))
+            || !api_tokens.is_empty()
+            || !accounts.is_empty();
+        let path_rules = cli_arc
+            .auth_paths
+            .as_deref()
+            .map(|list| {
+                let entries: Vec<String> = list.split(',').map(str::to_string).collect();
+                PathAuthRule::parse_list(&entries)
+            })
+            .unwrap_or_default();
+        for rule in &path_rules {
+            info!("🔒 Password-protected directory: {}", rule.prefix);
+        }
+        if let Some(hash) = &password_hash {
+            info!(
+                "🔒 Basic authentication uses a {} password hash",
+                hash.algorithm()
+            );
+        }
+        let mut auth = AuthMiddleware::new(cli_arc.username.clone(), cli_arc.password.clone())
+            .with_password_hash(password_hash)
+            .with_tokens(api_tokens)
+            .with_users(accounts)
+            .with_path_rules(path_rules);
+        if let Some((failures, lockout)) = rate_limiter.auth_lockout_limits() {
+            debug!(
+                "Locking out IPs for {}s after {failures} failed logins",
+                lockout.as_secs()
+            );
+            auth = auth.with_lockout(rate_limiter.clone());
+        }
+        let sessions = SessionSigner::from_cli(&cli_arc).filter(|_| {
+            if !has_logins {
+                warn!(
+                    "Login sessions need a username and password or [users] accounts; ignoring them"
+                );
+            }
+            has_logins
+        });
+        if let Some(signer) = &sessions {
+            info!(
+                "🔒 Browsers log in through {} (sessions last {}s)",
+                crate::session::LOGIN_ROUTE,
+                signer.lifetime()
+            );
+        }
+        crate::session::configure(sessions.is_some(), cli_arc.ssl_cert.is_some());
+        let auth = Arc::new(auth.with_sessions(sessions.clone()));
+        crate::users::configure(auth.is_enabled().then(|| auth.clone()));
+        if let Some(signer) = sessions {
+            let signer = Arc::new(signer);
+            for method in ["GET", "POST"] {
+                let auth_for_login = auth.clone();
+                let signer_for_login = signer.clone();
+                router.register_exact(
+                    method,
+                    crate::session::LOGIN_ROUTE,
+                    Box::new(move |req: &crate::http::Request| {
+                        crate::session::handle_login_request(
+                            req,
+                            &auth_for_login,
+                            &signer_for_login,
+                        )
+                    }),
+                );
+            }
+        }
+        if cli_arc.enable_file_management.unwrap_or(false) && !auth.is_enabled() {
+            warn!(
+                "File management is enabled without authentication; anyone who can reach the server can delete or move files."
+            );
+        }
+        if auth.is_enabled() || previous.is_some() {
+            crate::templates::AUTH_ENABLED
+                .store(auth.is_enabled(), std::sync::atomic::Ordering::SeqCst);
+        }
+        if auth.is_enabled() {
+            match ShareSigner::from_cli(&cli_arc) {
+                Some(signer) => {
+                    router.add_middleware(Box::new(ShareLinkMiddleware::new(signer, auth)))
+                }
+                None => router.add_middleware(Box::new(auth)),
+            }
+        }
+        // After authentication, so a 401 still comes first
+        router.add_middleware(Box::new(ReadOnlyMiddleware));
+        crate::read_only::configure(
+            cli_arc.read_only.unwrap_or(false),
+            previous.map(|previous| previous.cli.read_only.unwrap_or(false)),
+        );
+        let edit_enabled = cli_arc.enable_edit.unwrap_or(false)
+            && if global_auth {
+                true
+            } else {
+                warn!(
+                    "Editing needs a username and password, tokens or [users] accounts; ignoring it"
+                );
+                false
+            };
+        crate::edit::set_enabled(edit_enabled);
+        if edit_enabled {
+            info!(
+                "✏️  Text files can be edited at {}<path>",
+                crate::edit::EDIT_ROUTE
+            );
+            for method in ["GET", "POST"] {
+                let cli_for_edit = cli_arc.clone();
+                let base_for_edit = base_dir.clone();
+                router.register_prefix(
+                    method,
+                    crate::edit::EDIT_ROUTE,
+                    Box::new(move |req: &crate::http::Request| {
+                        crate::edit::handle_edit_request(
+                            req,
+                            cli_for_edit.as_ref(),
+                            base_for_edit.as_ref(),
+                        )
+                    }),
+                );
+            }
+        }
+        if global_auth {
+            router.register_exact(
+                "GET",
+                READ_ONLY_ROUTE,
+                Box::new(crate::read_only::handle_read_only_request),
+            );
+            router.register_exact(
+                "POST",
+                READ_ONLY_ROUTE,
+                Box::new(crate::read_only::handle_read_only_request),
+            );
+        }
+        register_internal_routes_with_state(
+            &mut router,
+            Some(cli_arc.clone()),
+            Some(stats.clone()),
+            Some(base_dir.clone()),
+            internal,
+            (!rate_limit_disabled).then(|| rate_limiter.clone()),
+        );
+
+        Ok(Self {
+            cli: cli_arc,
+            allowed_extensions,
+            username,
+            password,
+            chunk_size,
+            rate_limiter,
+            rate_limit_disabled,
+            ip_filter,
+            trusted_proxies,
+            router: Arc::new(router),
+            bandwidth,
+        })
+    }
+}
+
+/// Load the configuration again and build the state new connections will use
+fn reload_server_state(
+    reloader: &ConfigReloader,
+    current: &ServerState,
+    stats: &Arc<ServerStats>,
+    base_dir: &Arc<std::path::PathBuf>,
+    internal: &InternalState,
+) -> Result<ServerState, String> {
+    let mut cli = cli_from_config(reloader.load()?);
+    cli.validate().map_err(|e| e.to_string())?;
+
+    let old = current.cli.as_ref();
+    let restart_only = [
+        ("listen", old.listen != cli.listen),
+        ("port", old.port != cli.port),
+        ("directory", old.directory != cli.directory),
+        ("threads", old.threads != cli.threads),
+        (
+            "ssl",
+            old.ssl_cert != cli.ssl_cert || old.ssl_key != cli.ssl_key || old.http2 != cli.http2,
+        ),
+        ("base_path", old.base_path != cli.base_path),
+        ("template_dir", old.template_dir != cli.template_dir),
+        (
+            "theme",
+            old.theme != cli.theme || old.custom_css != cli.custom_css,
+        ),
+        ("log_dir", old.log_dir != cli.log_dir),
+        ("mdns", old.mdns != cli.mdns),
+        (
+            "ignore_patterns",
+            old.hide_dotfiles != cli.hide_dotfiles || old.ignore_patterns != cli.ignore_patterns,
+        ),
+        (
+            "tcp",
+            old.tcp_backlog != cli.tcp_backlog
+                || old.tcp_nodelay != cli.tcp_nodelay
+                || old.tcp_reuse_address != cli.tcp_reuse_address
+                || old.tcp_reuse_port != cli.tcp_reuse_port
+                || old.tcp_keepalive != cli.tcp_keepalive
+                || old.tcp_keepalive_interval != cli.tcp_keepalive_interval
+                || old.tcp_keepalive_retries != cli.tcp_keepalive_retries
+                || old.accept_batch != cli.accept_batch,
+        ),
+        (
+            "mmap_cache",
+            old.mmap_cache_size != cli.mmap_cache_size || old.mmap_max_file != cli.mmap_max_file,
+        ),
+        (
+            "search_content",
+            old.search_content != cli.search_content
+                || old.search_content_extensions != cli.search_content_extensions
+                || old.search_content_max_size != cli.search_content_max_size
+                || old.search_exact_sizes != cli.search_exact_sizes
+                || old.search_trigram_budget != cli.search_trigram_budget,
+        ),
+        (
+            "search_scope",
+            old.search_exclude != cli.search_exclude
+                || old.search_max_depth != cli.search_max_depth
+                || old.search_depth_limits != cli.search_depth_limits,
+        ),
+    ];
+    for (setting, changed) in restart_only {
+        if changed {
+            warn!("Changing '{setting}' takes effect after a restart; keeping the current value");
+        }
+    }
+    cli.listen.clone_from(&old.listen);
+    cli.port = old.port;
+    cli.directory.clone_from(&old.directory);
+    cli.threads = old.threads;
+    cli.ssl_cert.clone_from(&old.ssl_cert);
+    cli.ssl_key.clone_from(&old.ssl_key);
+    cli.http2 = old.http2;
+    cli.hide_dotfiles = old.hide_dotfiles;
+    cli.ignore_patterns.clone_from(&old.ignore_patterns);
+    cli.tcp_backlog = old.tcp_backlog;
+    cli.tcp_nodelay = old.tcp_nodelay;
+    cli.tcp_reuse_address = old.tcp_reuse_address;
+    cli.tcp_reuse_port = old.tcp_reuse_port;
+    cli.tcp_keepalive = old.tcp_keepalive;
+    cli.tcp_keepalive_interval = old.tcp_keepalive_interval;
+    cli.tcp_keepalive_retries = old.tcp_keepalive_retries;
+    cli.accept_batch = old.accept_batch;
+    cli.mmap_cache_size = old.mmap_cache_size;
+    cli.mmap_max_file = old.mmap_max_file;
+    cli.base_path.clone_from(&old.base_path);
+    cli.template_dir.clone_from(&old.template_dir);
+    cli.theme.clone_from(&old.theme);
+    cli.custom_css.clone_from(&old.custom_css);
+    cli.log_dir.clone_from(&old.log_dir);
+    cli.mdns = old.mdns;
+    cli.search_content = old.search_content;
+    cli.search_content_extensions
+        .clone_from(&old.search_content_extensions);
+    cli.search_content_max_size = old.search_content_max_size;
+    cli.search_exact_sizes = old.search_exact_sizes;
+    cli.search_trigram_budget = old.search_trigram_budget;
+    cli.search_exclude.clone_from(&old.search_exclude);
+    cli.search_max_depth = old.search_max_depth;
+    cli.search_depth_limits.clone_from(&old.search_depth_limits);
+
+    ServerState::build(cli, stats, base_dir, internal, Some(current)).map_err(|e| e.to_string())
+}
+
 /// Run server with new configuration system
 pub fn run_server_with_config(config: Config) -> Result<(), AppError> {
-    // Convert Config back to Cli for compatibility with existing code
+    run_server_with_reload(config, None, None, None)
+}
+
+/// Run server from a loaded [`Config`], reloading it through `reloader` when
+/// the INI file changes or `SIGHUP` arrives
+pub fn run_server_with_reload(
+    config: Config,
+    reloader: Option<ConfigReloader>,
+    shutdown_rx: Option<mpsc::Receiver<()>>,
+    addr_tx: Option<mpsc::Sender<SocketAddr>>,
+) -> Result<(), AppError> {
+    let worker_threads = config.threads;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_io()
+        .enable_time()
+        .worker_threads(worker_threads)
+        .max_blocking_threads(worker_threads.saturating_mul(8).max(64))
+        .build()
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+
+    runtime.block_on(run_server_async(
+        cli_from_config(config),
+        reloader,
+        shutdown_rx,
+        addr_tx,
+    ))
+}
+
+/// Convert Config back to Cli for compatibility with existing code
+fn cli_from_config(config: Config) -> Cli {
     // This is a transitional approach - eventually we could refactor to use Config throughout
-    let cli = Cli {
+    Cli {
         directory: config.directory,
         listen: Some(config.listen),
         port: Some(config.port),
@@ -1158,9 +2390,156 @@ pub fn run_server_with_config(config: Config) -> Result<(), AppError> {
         } else {
             Some(config.base_path)
         },
-    };
-
-    run_server(cli, None, None)
+        compression: Some(config.compression),
+        compression_min_size: Some(config.compression_min_size),
+        auth_tokens: if config.auth_tokens.is_empty() {
+            None
+        } else {
+            Some(config.auth_tokens.join(","))
+        },
+        upload_quota_daily: config.upload_quota_daily,
+        upload_quota_total: config.upload_quota_total,
+        allow_ips: if config.allowed_ips.is_empty() {
+            None
+        } else {
+            Some(config.allowed_ips.join(","))
+        },
+        deny_ips: if config.denied_ips.is_empty() {
+            None
+        } else {
+            Some(config.denied_ips.join(","))
+        },
+        log_rejected_ips: Some(config.log_rejected_ips),
+        enable_file_management: Some(config.enable_file_management),
+        search_content: Some(config.search_content),
+        search_content_extensions: Some(config.search_content_extensions.join(",")),
+        search_content_max_size: Some(config.search_content_max_size),
+        read_timeout: Some(config.read_timeout),
+        write_timeout: Some(config.write_timeout),
+        header_timeout: Some(config.header_timeout),
+        max_bandwidth: config.max_bandwidth,
+        per_conn_bandwidth: config.per_conn_bandwidth,
+        max_concurrent_downloads: config.max_concurrent_downloads,
+        download_queue: Some(config.download_queue),
+        log_rotate_size: config.log_rotate_size,
+        log_rotate_age: config.log_rotate_age,
+        log_rotate_keep: Some(config.log_rotate_keep),
+        log_rotate_gzip: Some(config.log_rotate_gzip),
+        audit: Some(config.audit),
+        audit_file: config.audit_file,
+        mdns: Some(config.mdns),
+        cors_origins: if config.cors_origins.is_empty() {
+            None
+        } else {
+            Some(config.cors_origins.join(","))
+        },
+        cors_methods: Some(config.cors_methods.join(", ")),
+        cors_headers: Some(config.cors_headers.join(", ")),
+        cors_max_age: Some(config.cors_max_age),
+        cors_credentials: Some(config.cors_credentials),
+        security_headers: Some(config.security_headers),
+        content_security_policy: Some(config.content_security_policy),
+        frame_options: Some(config.frame_options),
+        referrer_policy: Some(config.referrer_policy),
+        hsts_max_age: Some(config.hsts_max_age),
+        upload_scan_command: config.upload_scan_command,
+        webhook_urls: if config.webhook_urls.is_empty() {
+            None
+        } else {
+            Some(config.webhook_urls.join(","))
+        },
+        webhook_events: if config.webhook_events.is_empty() {
+            None
+        } else {
+            Some(config.webhook_events.join(","))
+        },
+        webhook_retries: Some(config.webhook_retries),
+        webhook_queue_size: Some(config.webhook_queue_size),
+        share_secret: config.share_secret,
+        share_max_ttl: Some(config.share_max_ttl),
+        auth_paths: if config.auth_paths.is_empty() {
+            None
+        } else {
+            Some(config.auth_paths.join(","))
+        },
+        upload_organize: Some(config.upload_organize),
+        upload_dedup: Some(config.upload_dedup),
+        template_dir: config.template_dir,
+        theme: Some(config.theme),
+        custom_css: config.custom_css,
+        rate_limit: config.rate_limit,
+        rate_limit_concurrent: config.rate_limit_concurrent,
+        rate_limit_upload: config.rate_limit_upload,
+        rate_limit_search: config.rate_limit_search,
+        trusted_proxies: if config.trusted_proxies.is_empty() {
+            None
+        } else {
+            Some(config.trusted_proxies.join(","))
+        },
+        http2: Some(config.http2),
+        mmap_cache_size: Some(config.mmap_cache_size),
+        mmap_max_file: Some(config.mmap_max_file),
+        tcp_backlog: Some(config.tcp_backlog),
+        tcp_nodelay: Some(config.tcp_nodelay),
+        tcp_reuse_address: Some(config.tcp_reuse_address),
+        tcp_reuse_port: Some(config.tcp_reuse_port),
+        tcp_keepalive: config.tcp_keepalive,
+        tcp_keepalive_interval: config.tcp_keepalive_interval,
+        tcp_keepalive_retries: config.tcp_keepalive_retries,
+        accept_batch: Some(config.accept_batch),
+        max_workers: config.max_workers,
+        worker_queue: Some(config.worker_queue),
+        follow_symlinks: Some(config.follow_symlinks),
+        hide_dotfiles: Some(config.hide_dotfiles),
+        ignore_patterns: if config.ignore_patterns.is_empty() {
+            None
+        } else {
+            Some(config.ignore_patterns.join(","))
+        },
+        read_only: Some(config.read_only),
+        mime_types: (!config.mime_types.is_empty()).then(|| config.mime_types.join(",")),
+        default_charset: Some(config.default_charset.clone()),
+        force_download: config.force_download.as_ref().map(|list| list.join(",")),
+        upload_conflict: Some(config.upload_conflict),
+        upload_disk_reserve: Some(config.upload_disk_reserve),
+        search_exact_sizes: Some(config.search_exact_sizes),
+        search_trigram_budget: Some(config.search_trigram_budget),
+        search_exclude: Some(config.search_exclude.join(",")),
+        search_max_depth: Some(config.search_max_depth),
+        search_depth_limits: Some(
+            config
+                .search_depth_limits
+                .iter()
+                .map(|(dir, levels)| format!("{}:{levels}", dir.display()))
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        max_upload_bandwidth: config.max_upload_bandwidth,
+        per_upload_bandwidth: config.per_upload_bandwidth,
+        service: None,
+        check_config: false,
+        password_hash: config.password_hash,
+        auth_max_failures: Some(config.auth_max_failures),
+        auth_lockout_secs: Some(config.auth_lockout_secs),
+        users: if config.users.is_empty() {
+            None
+        } else {
+            Some(config.users.join(","))
+        },
+        auth_sessions: Some(config.auth_sessions),
+        session_lifetime: Some(config.session_lifetime),
+        session_secret: config.session_secret,
+        enable_edit: Some(config.enable_edit),
+        quick_share: None,
+        index: Some(config.index),
+        spa: Some(config.spa),
+        redirects: (!config.redirects.is_empty()).then(|| config.redirects.join(",")),
+        redirects_file: config.redirects_file,
+        not_found_page: config.not_found_page,
+        file_cache_size: Some(config.file_cache_size),
+        file_cache_max_file: Some(config.file_cache_max_file),
+        max_request_body: Some(config.max_request_body),
+    }
 }
 
 pub fn run_server(
@@ -1177,11 +2556,12 @@ pub fn run_server(
         .build()
         .map_err(|e| AppError::InternalServerError(e.to_string()))?;
 
-    runtime.block_on(run_server_async(cli, shutdown_rx, addr_tx))
+    runtime.block_on(run_server_async(cli, None, shutdown_rx, addr_tx))
 }
 
 async fn run_server_async(
     cli: Cli,
+    reloader: Option<ConfigReloader>,
     shutdown_rx: Option<mpsc::Receiver<()>>,
     addr_tx: Option<mpsc::Sender<SocketAddr>>,
 ) -> Result<(), AppError> {
@@ -1199,16 +2579,28 @@ async fn run_server_async(
         ));
     }
 
-    crate::search::initialize_search(base_dir.as_ref().clone());
-
-    let allowed_extensions = Arc::new(
-        cli.allowed_extensions
-            .as_ref()
-            .unwrap_or(&"*".to_string())
+    crate::fs::init_ignore_rules(crate::fs::IgnoreRules::new(
+        &base_dir,
+        cli.hide_dotfiles.unwrap_or(true),
+        &cli.ignore_patterns
+            .as_deref()
+            .unwrap_or("")
             .split(',')
-            .map(|ext| Pattern::new(ext.trim()))
-            .collect::<Result<Vec<Pattern>, _>>()?,
+            .map(str::to_string)
+            .collect::<Vec<_>>(),
+    )?);
+    let index_scope = crate::search::IndexScope::from_cli(&cli, &base_dir)?;
+    crate::search::initialize_search(
+        base_dir.as_ref().clone(),
+        cli.search_exact_sizes.unwrap_or(false),
+        cli.search_trigram_budget
+            .unwrap_or(crate::search::DEFAULT_TRIGRAM_BUDGET),
+        index_scope.clone(),
     );
+    if let Some(mut settings) = crate::search::ContentIndexSettings::from_cli(&cli) {
+        settings.scope = index_scope;
+        crate::search::initialize_content_search(base_dir.as_ref().clone(), settings);
+    }
 
     let bind_address = format!(
         "{}:{}",
@@ -1216,12 +2608,24 @@ async fn run_server_async(
         cli.port.unwrap_or(8080)
     );
 
-    let listener = tokio::net::TcpListener::bind(&bind_address).await?;
+    // Backlog and bind options do not apply to a socket passed by systemd
+    let listener_options = crate::listener::ListenerOptions::from_cli(&cli);
+    let listener = match crate::systemd::take_activated_listener()? {
+        Some(listener) => {
+            info!("Using the listening socket passed by systemd");
+            tokio::net::TcpListener::from_std(listener)?
+        }
+        None => bind_listener(&bind_address, &listener_options).await?,
+    };
     let local_addr = listener.local_addr()?;
 
     let tls_config: Option<Arc<ServerConfig>> =
         if let (Some(cert_path), Some(key_path)) = (&cli.ssl_cert, &cli.ssl_key) {
-            Some(build_tls_config(cert_path, key_path)?)
+            Some(build_tls_config(
+                cert_path,
+                key_path,
+                cli.http2.unwrap_or(false),
+            )?)
         } else {
             None
         };
@@ -1230,24 +2634,51 @@ async fn run_server_async(
         .map(|cfg| tokio_rustls::TlsAcceptor::from(cfg.clone()));
     let is_https = tls_acceptor.is_some();
 
-    let webdav_enabled = cli.enable_webdav.unwrap_or(false);
-    let disable_rate_limit_requested = cli.disable_rate_limit.unwrap_or(false);
-    let rate_limit_disabled = webdav_enabled && disable_rate_limit_requested;
-    if disable_rate_limit_requested && !webdav_enabled {
-        warn!(
-            "Ignoring --disable-rate-limit because WebDAV is disabled. Enable WebDAV for it to take effect."
+    // Initialize the global base path for reverse proxy sub-path support
+    crate::templates::init_base_path(cli.base_path.clone().unwrap_or_default());
+    crate::mmap_cache::init(
+        cli.mmap_cache_size.unwrap_or(0),
+        cli.mmap_max_file
+            .unwrap_or(crate::mmap_cache::DEFAULT_MAX_FILE_BYTES),
+    );
+    let custom_css = match &cli.custom_css {
+        Some(path) => Some(fs::read_to_string(path).map_err(|e| {
+            AppError::InvalidConfiguration(format!(
+                "Cannot read custom CSS {}: {e}",
+                path.display()
+            ))
+        })?),
+        None => None,
+    };
+    crate::templates::init_theme(
+        cli.theme
+            .as_deref()
+            .and_then(crate::templates::Theme::parse)
+            .unwrap_or_default(),
+        custom_css,
+    );
+    if let Some(dir) = &cli.template_dir {
+        let count = crate::templates::TemplateEngine::init_error_templates(dir)?;
+        info!(
+            "Loaded {} custom error page(s) from {}",
+            count,
+            dir.display()
         );
     }
-    if rate_limit_disabled {
-        info!("WebDAV rate limiting is disabled by configuration.");
-    }
-    let (rate_limit_per_minute, concurrent_per_ip) = if webdav_enabled {
-        (3500, 128)
-    } else {
-        (120, 10)
-    };
-    let rate_limiter = Arc::new(RateLimiter::new(rate_limit_per_minute, concurrent_per_ip));
+
     let stats = Arc::new(ServerStats::new());
+    if cli.enable_upload.unwrap_or(false) || cli.enable_webdav.unwrap_or(false) {
+        // Leftovers of uploads interrupted by a crash; may walk a big tree
+        let dir = base_dir.to_path_buf();
+        std::thread::spawn(move || {
+            crate::upload::remove_orphaned_temp_files(&dir, crate::upload::ORPHANED_TEMP_MAX_AGE);
+        });
+    }
+    let internal = InternalState::new(
+        Arc::new(UploadQuota::from_cli(&cli)),
+        Some(base_dir.as_path()),
+    );
+    let state = ServerState::build(cli, &stats, &base_dir, &internal, None)?;
 
     if let Some(tx) = addr_tx
         && tx.send(local_addr).is_err()
@@ -1263,39 +2694,30 @@ async fn run_server_async(
         protocol,
         local_addr,
         base_dir.display(),
-        allowed_extensions
+        state.allowed_extensions
     );
 
-    let username = Arc::new(cli.username.clone());
-    let password = Arc::new(cli.password.clone());
-    let chunk_size = cli.chunk_size.unwrap_or(1024);
-    let cli_arc = Arc::new(cli);
-
-    // Initialize the global base path for reverse proxy sub-path support
-    crate::templates::init_base_path(cli_arc.base_path.clone().unwrap_or_default());
-
-    let mut router = Router::new();
-    if cli_arc.username.is_some() && cli_arc.password.is_some() {
-        crate::templates::AUTH_ENABLED.store(true, std::sync::atomic::Ordering::SeqCst);
-        router.add_middleware(Box::new(AuthMiddleware::new(
-            cli_arc.username.clone(),
-            cli_arc.password.clone(),
-        )));
+    // `irondrop serve --once` prints the URL of the shared item itself
+    if state.cli.quick_share.is_none() {
+        print_share_qr(local_addr, is_https, state.cli.base_path.as_deref());
     }
-    register_internal_routes(
-        &mut router,
-        Some(cli_arc.clone()),
-        Some(stats.clone()),
-        Some(base_dir.clone()),
-    );
-    let shared_router = Arc::new(router);
+
+    let mdns = if state.cli.mdns.unwrap_or(false) {
+        start_mdns(local_addr, is_https, state.cli.base_path.as_deref())
+    } else {
+        None
+    };
+
+    // Connections take the current state when accepted; reloads swap it
+    let (state_tx, state_rx) = tokio::sync::watch::channel(Arc::new(state));
 
     tokio::spawn({
-        let rate_limiter = rate_limiter.clone();
+        let state_rx = state_rx.clone();
         async move {
             let mut interval = tokio::time::interval(Duration::from_secs(60));
             loop {
                 interval.tick().await;
+                let rate_limiter = state_rx.borrow().rate_limiter.clone();
                 rate_limiter.cleanup_old_entries();
             }
         }
@@ -1303,11 +2725,12 @@ async fn run_server_async(
 
     tokio::spawn({
         let stats_reporter = stats.clone();
-        let rate_limiter_monitor = rate_limiter.clone();
+        let state_rx = state_rx.clone();
         async move {
             let mut interval = tokio::time::interval(Duration::from_secs(300));
             loop {
                 interval.tick().await;
+                let rate_limiter_monitor = state_rx.borrow().rate_limiter.clone();
                 let (total, successful, errors, bytes, uptime) = stats_reporter.get_stats();
                 let upload_stats = stats_reporter.get_upload_stats();
                 let (current_memory, peak_memory, memory_available) =
@@ -1337,6 +2760,15 @@ async fn run_server_async(
                     );
                 }
 
+                if stats_reporter.file_cache().is_enabled() {
+                    let (hits, misses) = stats_reporter.get_file_cache_stats();
+                    let (entries, cached) = stats_reporter.file_cache().usage();
+                    info!(
+                        "🗃️ File Cache: {hits} hits, {misses} misses, {entries} files ({:.2} MB)",
+                        cached as f64 / 1024.0 / 1024.0
+                    );
+                }
+
                 if upload_stats.total_uploads > 0 {
                     info!(
                         "📤 Upload Stats: {} uploads ({:.1}% success), {} files, {:.2} MB uploaded, avg: {:.2} MB/file, {:.0}ms/upload, {} concurrent",
@@ -1353,61 +2785,180 @@ async fn run_server_async(
         }
     });
 
+    if let Some(mut reloader) = reloader {
+        if let Some(path) = reloader.config_path() {
+            info!("🔄 Watching {} for configuration changes", path.display());
+        }
+        let stats = stats.clone();
+        let base_dir = base_dir.clone();
+        let internal = internal.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RELOAD_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                if !reloader.poll() {
+                    continue;
+                }
+                let current = state_tx.borrow().clone();
+                let audit_record = |result| AuditRecord {
+                    path: reloader
+                        .config_path()
+                        .map(|path| path.display().to_string()),
+                    result,
+                    ..AuditRecord::default()
+                };
+                match reload_server_state(&reloader, &current, &stats, &base_dir, &internal) {
+                    Ok(state) => {
+                        state_tx.send_replace(Arc::new(state));
+                        info!("🔄 Configuration reloaded");
+                        crate::audit::record(AuditEvent::ConfigReload, audit_record("success"));
+                    }
+                    Err(e) => {
+                        warn!("Configuration reload failed, keeping current settings: {e}");
+                        crate::audit::record(
+                            AuditEvent::ConfigReload,
+                            audit_record("failure").with("error", e),
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    crate::systemd::notify("READY=1");
+
     let mut shutdown_task = shutdown_rx.map(|rx| {
         tokio::task::spawn_blocking(move || {
             let _ = rx.recv();
         })
     });
 
+    let mut accepted = Vec::with_capacity(listener_options.accept_batch);
     loop {
-        if let Some(ref mut shutdown_task) = shutdown_task {
+        let first = if let Some(ref mut shutdown_task) = shutdown_task {
             tokio::select! {
                 _ = shutdown_task => {
                     break;
                 }
-                res = listener.accept() => {
-                    let (stream, peer_addr) = res?;
-                    handle_connection(
-                        stream,
-                        peer_addr,
-                        base_dir.clone(),
-                        allowed_extensions.clone(),
-                        username.clone(),
-                        password.clone(),
-                        chunk_size,
-                        rate_limiter.clone(),
-                        rate_limit_disabled,
-                        stats.clone(),
-                        cli_arc.clone(),
-                        shared_router.clone(),
-                        tls_acceptor.clone(),
-                    );
-                }
+                res = listener.accept() => res?,
             }
         } else {
-            let (stream, peer_addr) = listener.accept().await?;
+            listener.accept().await?
+        };
+        accepted.push(first);
+        crate::listener::drain_ready(&listener, listener_options.accept_batch, &mut accepted);
+
+        let state = state_rx.borrow().clone();
+        for (stream, peer_addr) in accepted.drain(..) {
+            if !state.ip_filter.admit(peer_addr) {
+                drop(stream);
+                continue;
+            }
+            listener_options.configure_stream(&stream);
             handle_connection(
                 stream,
                 peer_addr,
                 base_dir.clone(),
-                allowed_extensions.clone(),
-                username.clone(),
-                password.clone(),
-                chunk_size,
-                rate_limiter.clone(),
-                rate_limit_disabled,
+                state.allowed_extensions.clone(),
+                state.username.clone(),
+                state.password.clone(),
+                state.chunk_size,
+                state.rate_limiter.clone(),
+                state.rate_limit_disabled,
                 stats.clone(),
-                cli_arc.clone(),
-                shared_router.clone(),
+                state.cli.clone(),
+                state.router.clone(),
                 tls_acceptor.clone(),
+                state.bandwidth.clone(),
+                state.trusted_proxies.clone(),
             );
         }
     }
 
+    crate::systemd::notify("STOPPING=1");
+    if let Some(mdns) = mdns {
+        mdns.goodbye();
+    }
     info!("✅ Server shut down gracefully.");
     Ok(())
 }
 
+/// Bind the first address `bind_address` resolves to that accepts the socket
+async fn bind_listener(
+    bind_address: &str,
+    options: &crate::listener::ListenerOptions,
+) -> Result<tokio::net::TcpListener, AppError> {
+    let mut last_error = None;
+    for addr in tokio::net::lookup_host(bind_address).await? {
+        match options.bind(addr) {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::AddrNotAvailable,
+                format!("{bind_address} did not resolve to any address"),
+            )
+        })
+        .into())
+}
+
+/// Print the share URL and its QR code when running in a terminal
+fn print_share_qr(local_addr: SocketAddr, is_https: bool, base_path: Option<&str>) {
+    use std::io::IsTerminal;
+
+    if !std::io::stdout().is_terminal() {
+        return;
+    }
+    let url = crate::qr::share_url(
+        if is_https { "https" } else { "http" },
+        &crate::qr::advertised_host(local_addr),
+        base_path.unwrap_or(""),
+    );
+    if let Some(qr) = crate::qr::QrCode::encode(url.as_bytes()) {
+        println!(
+            "\nScan to open {url} on your phone:\n\n{}",
+            qr.to_terminal()
+        );
+    }
+}
+
+/// Advertise the listening address via mDNS; failures only disable discovery
+fn start_mdns(
+    local_addr: SocketAddr,
+    is_https: bool,
+    base_path: Option<&str>,
+) -> Option<crate::mdns::MdnsAdvertiser> {
+    let address = match local_addr.ip() {
+        IpAddr::V4(ip) if ip.is_loopback() => {
+            warn!("Not advertising via mDNS: the server only listens on {ip}");
+            return None;
+        }
+        IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+        _ => crate::mdns::lan_address(),
+    };
+    let Some(address) = address else {
+        warn!("Not advertising via mDNS: no LAN IPv4 address found");
+        return None;
+    };
+    let service = crate::mdns::ServiceInfo::new(
+        &crate::mdns::hostname(),
+        address,
+        local_addr.port(),
+        is_https,
+        base_path.unwrap_or("/"),
+    );
+    match crate::mdns::MdnsAdvertiser::start(service) {
+        Ok(advertiser) => Some(advertiser),
+        Err(e) => {
+            warn!("mDNS advertisement failed to start: {e}");
+            None
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn handle_connection(
     stream: TokioTcpStream,
@@ -1423,15 +2974,80 @@ fn handle_connection(
     cli_config: Arc<Cli>,
     router: Arc<Router>,
     tls_acceptor: Option<tokio_rustls::TlsAcceptor>,
+    bandwidth: Arc<BandwidthLimiter>,
+    trusted_proxies: Arc<TrustedProxies>,
 ) {
     let client_ip = peer_addr.ip();
-    if !rate_limit_disabled && !rate_limiter.check_rate_limit(client_ip) {
+    // Proxied requests are limited per forwarded client by RouteRateLimitMiddleware
+    let limit_connection = !rate_limit_disabled && !trusted_proxies.is_trusted(client_ip);
+    if limit_connection && !rate_limiter.check_rate_limit(client_ip) {
+        crate::hooks::emit(
+            HookEvent::RateLimited,
+            &[("client", client_ip.to_string().into())],
+        );
+        crate::audit::record(
+            AuditEvent::RateLimitBan,
+            AuditRecord {
+                ip: Some(client_ip.to_string()),
+                result: "denied",
+                ..AuditRecord::default()
+            }
+            .with("route", "connections"),
+        );
         return;
     }
 
     tokio::spawn(async move {
         let result = if let Some(acceptor) = tls_acceptor {
             match acceptor.accept(stream).await {
+                #[cfg(feature = "http2")]
+                Ok(tls_stream)
+                    if tls_stream.get_ref().1.alpn_protocol() == Some(crate::http2::ALPN_H2) =>
+                {
+                    let timeouts = crate::http::ConnectionTimeouts::from_cli(Some(&cli_config));
+                    // The connection holds one slot; each stream takes another
+                    let max_streams = if limit_connection {
+                        rate_limiter.max_concurrent_per_ip().saturating_sub(1)
+                    } else {
+                        crate::http2::DEFAULT_MAX_CONCURRENT_STREAMS
+                    };
+                    let stream_limiter = rate_limiter.clone();
+                    let serve = move |pipe| {
+                        if limit_connection && !stream_limiter.check_rate_limit(client_ip) {
+                            return None;
+                        }
+                        let stream_limiter = stream_limiter.clone();
+                        let request = crate::http::handle_client_async(
+                            pipe,
+                            peer_addr,
+                            base_dir.clone(),
+                            allowed_extensions.clone(),
+                            username.clone(),
+                            password.clone(),
+                            chunk_size,
+                            Some(cli_config.clone()),
+                            Some(stats.clone()),
+                            router.clone(),
+                            Some(bandwidth.clone()),
+                            Some(trusted_proxies.clone()),
+                        );
+                        Some(async move {
+                            request.await;
+                            if limit_connection {
+                                stream_limiter.release_connection(client_ip);
+                            }
+                        })
+                    };
+                    crate::http2::serve_connection(
+                        tls_stream,
+                        peer_addr,
+                        timeouts,
+                        max_streams,
+                        serve,
+                    )
+                    .await;
+                    Ok(())
+                }
                 Ok(tls_stream) => {
                     crate::http::handle_client_async(
                         tls_stream,
@@ -1444,6 +3060,8 @@ fn handle_connection(
                         Some(cli_config),
                         Some(stats.clone()),
                         router,
+                        Some(bandwidth),
+                        Some(trusted_proxies),
                     )
                     .await;
                     Ok(())
@@ -1462,12 +3080,14 @@ fn handle_connection(
                 Some(cli_config),
                 Some(stats.clone()),
                 router,
+                Some(bandwidth),
+                Some(trusted_proxies),
             )
             .await;
             Ok(())
         };
 
-        if !rate_limit_disabled {
+        if limit_connection {
             rate_limiter.release_connection(client_ip);
         }
         let _ = result;
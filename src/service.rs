@@ -0,0 +1,653 @@
+// SPDX-License-Identifier: MIT
+
+//! Running IronDrop as a background service
+//!
+//! `--service install` registers the server with the platform's service
+//! manager so it starts at boot (or login) and is restarted when it exits:
+//!
+//! - Linux: a systemd unit, `/etc/systemd/system/irondrop.service`, enabled
+//!   and started with `systemctl` (needs root)
+//! - macOS: a launchd agent, `~/Library/LaunchAgents/com.irondrop.server.plist`,
+//!   loaded with `launchctl` for the current user
+//! - Windows: a service named `irondrop` created with `sc.exe` (needs an
+//!   administrator prompt)
+//!
+//! The other options on the command line are baked into the service, with the
+//! served directory and config file made absolute. `--service uninstall` stops
+//! and removes it again. `--service run` is what the Windows service manager
+//! starts: it reports to the service control dispatcher and shuts the server
+//! down cleanly on stop. Elsewhere it runs the server in the foreground, as
+//! launchd and systemd expect.
+
+use crate::cli::Cli;
+use crate::config::Config;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Name of the systemd unit and the Windows service
+pub const SERVICE_NAME: &str = "irondrop";
+
+/// Label of the launchd agent
+pub const LAUNCHD_LABEL: &str = "com.irondrop.server";
+
+/// Name shown by service managers
+const DISPLAY_NAME: &str = "IronDrop File Server";
+
+/// What `--service` was asked to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    Install,
+    Uninstall,
+    Run,
+}
+
+impl ServiceAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "install" => Some(Self::Install),
+            "uninstall" | "remove" => Some(Self::Uninstall),
+            "run" => Some(Self::Run),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Install => "install",
+            Self::Uninstall => "uninstall",
+            Self::Run => "run",
+        }
+    }
+}
+
+/// Arguments the service runs with: `args` (without the program name) minus
+/// `--service`, with the served directory and config file made absolute
+pub fn service_args(args: &[String], cli: &Cli) -> Vec<String> {
+    // Options whose value is replaced below and must be dropped with it
+    const REPLACED: [&str; 4] = ["--service", "--directory", "-d", "--config-file"];
+
+    let mut baked = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if REPLACED.contains(&arg.as_str()) {
+            iter.next();
+            continue;
+        }
+        let attached = ["--service=", "--directory=", "--config-file="]
+            .iter()
+            .any(|prefix| arg.starts_with(prefix));
+        if attached || (arg.starts_with("-d") && !arg.starts_with("--")) {
+            continue;
+        }
+        baked.push(arg.clone());
+    }
+
+    baked.push("--directory".to_string());
+    baked.push(absolute(&cli.directory).to_string_lossy().into_owned());
+    if let Ok(Some(config_file)) = Config::find_config_file(cli) {
+        baked.push("--config-file".to_string());
+        baked.push(absolute(&config_file).to_string_lossy().into_owned());
+    }
+    baked
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// systemd unit starting `program` with `args`
+pub fn systemd_unit(program: &Path, args: &[String], workdir: &Path, user: Option<&str>) -> String {
+    let exec = std::iter::once(program.to_string_lossy().into_owned())
+        .chain(args.iter().cloned())
+        .map(|arg| systemd_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let user = user.map_or(String::new(), |user| format!("User={user}\n"));
+    format!(
+        "[Unit]\n\
+         Description={DISPLAY_NAME}\n\
+         After=network-online.target\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=notify\n\
+         {user}\
+         WorkingDirectory={}\n\
+         ExecStart={exec}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n",
+        workdir.display()
+    )
+}
+
+/// Quote `arg` for `ExecStart=`, escaping systemd's `%` and `$` expansions
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+        escaped
+    } else {
+        format!("\"{escaped}\"")
+    }
+}
+
+/// launchd agent starting `program` with `args`, logging to `log_file`
+pub fn launchd_plist(program: &Path, args: &[String], workdir: &Path, log_file: &Path) -> String {
+    let arguments = std::iter::once(program.to_string_lossy().into_owned())
+        .chain(args.iter().cloned())
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+        .collect::<String>();
+    let log_file = xml_escape(&log_file.to_string_lossy());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>WorkingDirectory</key>
+    <string>{}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log_file}</string>
+    <key>StandardErrorPath</key>
+    <string>{log_file}</string>
+</dict>
+</plist>
+"#,
+        xml_escape(&workdir.to_string_lossy())
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Command line for a Windows service: `program args --service run`, quoted
+/// the way `CommandLineToArgvW` splits it
+pub fn windows_command_line(program: &Path, args: &[String]) -> String {
+    std::iter::once(program.to_string_lossy().into_owned())
+        .chain(args.iter().cloned())
+        .chain(["--service".to_string(), "run".to_string()])
+        .map(|arg| windows_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Run `program` with `args`, failing when it cannot start or exits non-zero
+fn run_command(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to run {program}: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{program} {} failed ({status})", args.join(" ")))
+    }
+}
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Cannot locate the irondrop executable: {e}"))
+}
+
+/// Register and start the service with the options in `args`
+pub fn install(args: &[String], cli: &Cli) -> Result<String, String> {
+    let program = current_exe()?;
+    let baked = service_args(args, cli);
+    platform::install(&program, &baked)
+}
+
+/// Stop and remove the service
+pub fn uninstall() -> Result<String, String> {
+    platform::uninstall()
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    const UNIT_PATH: &str = "/etc/systemd/system/irondrop.service";
+
+    pub fn install(program: &Path, args: &[String]) -> Result<String, String> {
+        let workdir = std::env::current_dir().map_err(|e| e.to_string())?;
+        // Under sudo, run as the user who asked rather than as root
+        let user = std::env::var("SUDO_USER")
+            .ok()
+            .filter(|user| !user.is_empty() && user != "root");
+        let unit = systemd_unit(program, args, &workdir, user.as_deref());
+        std::fs::write(UNIT_PATH, unit)
+            .map_err(|e| format!("Failed to write {UNIT_PATH}: {e} (run with sudo)"))?;
+        run_command("systemctl", &["daemon-reload"])?;
+        run_command("systemctl", &["enable", SERVICE_NAME])?;
+        // Restart so a reinstall picks up the new options
+        run_command("systemctl", &["restart", SERVICE_NAME])?;
+        Ok(format!(
+            "Installed {UNIT_PATH} and started it; check it with `systemctl status {SERVICE_NAME}`"
+        ))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        if !Path::new(UNIT_PATH).exists() {
+            return Err(format!("{UNIT_PATH} does not exist; nothing to uninstall"));
+        }
+        // Stopping fails when it is not running, which is fine
+        let _ = run_command("systemctl", &["disable", "--now", SERVICE_NAME]);
+        std::fs::remove_file(UNIT_PATH)
+            .map_err(|e| format!("Failed to remove {UNIT_PATH}: {e} (run with sudo)"))?;
+        run_command("systemctl", &["daemon-reload"])?;
+        Ok(format!("Stopped and removed {UNIT_PATH}"))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    fn home() -> Result<PathBuf, String> {
+        std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .ok_or_else(|| "HOME is not set".to_string())
+    }
+
+    fn plist_path() -> Result<PathBuf, String> {
+        Ok(home()?
+            .join("Library/LaunchAgents")
+            .join(format!("{LAUNCHD_LABEL}.plist")))
+    }
+
+    pub fn install(program: &Path, args: &[String]) -> Result<String, String> {
+        let plist = plist_path()?;
+        let workdir = std::env::current_dir().map_err(|e| e.to_string())?;
+        let log_file = home()?.join("Library/Logs/irondrop.log");
+        if let Some(parent) = plist.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&plist, launchd_plist(program, args, &workdir, &log_file))
+            .map_err(|e| format!("Failed to write {}: {e}", plist.display()))?;
+        let plist_arg = plist.to_string_lossy();
+        // Reinstalling replaces a loaded agent
+        let _ = run_command("launchctl", &["unload", &plist_arg]);
+        run_command("launchctl", &["load", "-w", &plist_arg])?;
+        Ok(format!(
+            "Installed {} and started it; output goes to {}",
+            plist.display(),
+            log_file.display()
+        ))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        let plist = plist_path()?;
+        if !plist.exists() {
+            return Err(format!(
+                "{} does not exist; nothing to uninstall",
+                plist.display()
+            ));
+        }
+        let _ = run_command("launchctl", &["unload", "-w", &plist.to_string_lossy()]);
+        std::fs::remove_file(&plist)
+            .map_err(|e| format!("Failed to remove {}: {e}", plist.display()))?;
+        Ok(format!("Stopped and removed {}", plist.display()))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    pub fn install(program: &Path, args: &[String]) -> Result<String, String> {
+        let command_line = windows_command_line(program, args);
+        run_command(
+            "sc.exe",
+            &[
+                "create",
+                SERVICE_NAME,
+                "binPath=",
+                &command_line,
+                "start=",
+                "auto",
+                "DisplayName=",
+                DISPLAY_NAME,
+            ],
+        )
+        .map_err(|e| format!("{e} (run from an administrator prompt)"))?;
+        let _ = run_command(
+            "sc.exe",
+            &["description", SERVICE_NAME, "Serves files over HTTP"],
+        );
+        run_command("sc.exe", &["start", SERVICE_NAME])?;
+        Ok(format!(
+            "Installed the {SERVICE_NAME} service and started it; manage it in services.msc"
+        ))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        // Stopping fails when it is not running, which is fine
+        let _ = run_command("sc.exe", &["stop", SERVICE_NAME]);
+        run_command("sc.exe", &["delete", SERVICE_NAME])
+            .map_err(|e| format!("{e} (run from an administrator prompt)"))?;
+        Ok(format!("Stopped and removed the {SERVICE_NAME} service"))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::*;
+
+    pub fn install(_program: &Path, _args: &[String]) -> Result<String, String> {
+        Err("--service install is supported on Linux, macOS and Windows only".to_string())
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        Err("--service uninstall is supported on Linux, macOS and Windows only".to_string())
+    }
+}
+
+/// Run `server` under the Windows service control dispatcher.
+///
+/// `server` gets a channel that receives a message when the service is asked
+/// to stop. Started from a console instead of by the service manager, the
+/// server simply runs in the foreground.
+#[cfg(windows)]
+pub fn run_windows_service<F>(server: F) -> Result<(), String>
+where
+    F: FnOnce(std::sync::mpsc::Receiver<()>) -> Result<(), String> + Send + 'static,
+{
+    windows::run(Box::new(server))
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+    use std::sync::mpsc;
+    use std::sync::{Mutex, OnceLock};
+
+    type ServerFn = Box<dyn FnOnce(mpsc::Receiver<()>) -> Result<(), String> + Send>;
+
+    const SERVICE_WIN32_OWN_PROCESS: u32 = 0x10;
+    const SERVICE_STOPPED: u32 = 1;
+    const SERVICE_START_PENDING: u32 = 2;
+    const SERVICE_STOP_PENDING: u32 = 3;
+    const SERVICE_RUNNING: u32 = 4;
+    const SERVICE_ACCEPT_STOP: u32 = 0x1;
+    const SERVICE_ACCEPT_SHUTDOWN: u32 = 0x4;
+    const SERVICE_CONTROL_STOP: u32 = 1;
+    const SERVICE_CONTROL_INTERROGATE: u32 = 4;
+    const SERVICE_CONTROL_SHUTDOWN: u32 = 5;
+    const NO_ERROR: u32 = 0;
+    const ERROR_CALL_NOT_IMPLEMENTED: u32 = 120;
+    const ERROR_SERVICE_SPECIFIC_ERROR: u32 = 1066;
+    const ERROR_FAILED_SERVICE_CONTROLLER_CONNECT: i32 = 1063;
+
+    #[repr(C)]
+    struct ServiceTableEntry {
+        name: *mut u16,
+        main: Option<unsafe extern "system" fn(u32, *mut *mut u16)>,
+    }
+
+    #[repr(C)]
+    struct ServiceStatus {
+        service_type: u32,
+        current_state: u32,
+        controls_accepted: u32,
+        win32_exit_code: u32,
+        service_specific_exit_code: u32,
+        check_point: u32,
+        wait_hint: u32,
+    }
+
+    #[link(name = "advapi32")]
+    unsafe extern "system" {
+        fn StartServiceCtrlDispatcherW(table: *const ServiceTableEntry) -> i32;
+        fn RegisterServiceCtrlHandlerExW(
+            name: *const u16,
+            handler: unsafe extern "system" fn(u32, u32, *mut c_void, *mut c_void) -> u32,
+            context: *mut c_void,
+        ) -> *mut c_void;
+        fn SetServiceStatus(handle: *mut c_void, status: *const ServiceStatus) -> i32;
+    }
+
+    /// The server to start once the dispatcher calls `service_main`
+    static SERVER: Mutex<Option<ServerFn>> = Mutex::new(None);
+    /// Sends the stop request to the running server
+    static STOP: Mutex<Option<mpsc::Sender<()>>> = Mutex::new(None);
+    /// Status handle of the running service, stored as an address
+    static STATUS_HANDLE: OnceLock<usize> = OnceLock::new();
+    static RESULT: Mutex<Option<Result<(), String>>> = Mutex::new(None);
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn report(state: u32, exit_code: u32) {
+        let Some(&handle) = STATUS_HANDLE.get() else {
+            return;
+        };
+        let status = ServiceStatus {
+            service_type: SERVICE_WIN32_OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == SERVICE_RUNNING {
+                SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN
+            } else {
+                0
+            },
+            win32_exit_code: exit_code,
+            service_specific_exit_code: 0,
+            check_point: 0,
+            wait_hint: if state == SERVICE_RUNNING { 0 } else { 10_000 },
+        };
+        // SAFETY: `handle` came from RegisterServiceCtrlHandlerExW and
+        // `status` is a valid SERVICE_STATUS
+        unsafe {
+            SetServiceStatus(handle as *mut c_void, &status);
+        }
+    }
+
+    unsafe extern "system" fn control_handler(
+        control: u32,
+        _event_type: u32,
+        _event_data: *mut c_void,
+        _context: *mut c_void,
+    ) -> u32 {
+        match control {
+            SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+                report(SERVICE_STOP_PENDING, NO_ERROR);
+                if let Some(stop) = STOP.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                    let _ = stop.send(());
+                }
+                NO_ERROR
+            }
+            SERVICE_CONTROL_INTERROGATE => NO_ERROR,
+            _ => ERROR_CALL_NOT_IMPLEMENTED,
+        }
+    }
+
+    unsafe extern "system" fn service_main(_argc: u32, _argv: *mut *mut u16) {
+        let name = wide(super::SERVICE_NAME);
+        // SAFETY: `name` is NUL-terminated and outlives the call
+        let handle = unsafe {
+            RegisterServiceCtrlHandlerExW(name.as_ptr(), control_handler, std::ptr::null_mut())
+        };
+        if handle.is_null() {
+            return;
+        }
+        let _ = STATUS_HANDLE.set(handle as usize);
+        report(SERVICE_START_PENDING, NO_ERROR);
+
+        let (stop_tx, stop_rx) = mpsc::channel();
+        *STOP.lock().unwrap_or_else(|e| e.into_inner()) = Some(stop_tx);
+        let server = SERVER.lock().unwrap_or_else(|e| e.into_inner()).take();
+        report(SERVICE_RUNNING, NO_ERROR);
+        let result = server.map_or(Ok(()), |server| server(stop_rx));
+        let exit_code = if result.is_ok() {
+            NO_ERROR
+        } else {
+            ERROR_SERVICE_SPECIFIC_ERROR
+        };
+        *RESULT.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+        report(SERVICE_STOPPED, exit_code);
+    }
+
+    pub fn run(server: ServerFn) -> Result<(), String> {
+        *SERVER.lock().unwrap_or_else(|e| e.into_inner()) = Some(server);
+        let mut name = wide(super::SERVICE_NAME);
+        let table = [
+            ServiceTableEntry {
+                name: name.as_mut_ptr(),
+                main: Some(service_main),
+            },
+            ServiceTableEntry {
+                name: std::ptr::null_mut(),
+                main: None,
+            },
+        ];
+        // SAFETY: `table` is terminated by a null entry and outlives the call,
+        // which blocks until the service has stopped
+        if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(ERROR_FAILED_SERVICE_CONTROLLER_CONNECT) {
+                return Err(format!("Failed to start the service dispatcher: {error}"));
+            }
+            // Not started by the service manager: run in the foreground
+            let server = SERVER.lock().unwrap_or_else(|e| e.into_inner()).take();
+            let (_stop_tx, stop_rx) = mpsc::channel();
+            return server.map_or(Ok(()), |server| server(stop_rx));
+        }
+        RESULT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .unwrap_or(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_service_args_are_absolute_and_drop_service() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_arg = dir.path().to_string_lossy().into_owned();
+        let args = strings(&[
+            "-d",
+            &dir_arg,
+            "--port",
+            "9000",
+            "--service",
+            "install",
+            "--enable-upload",
+            "true",
+        ]);
+        let cli = Cli::parse_from(std::iter::once("irondrop".to_string()).chain(args.clone()));
+        assert_eq!(cli.service.as_deref(), Some("install"));
+
+        let baked = service_args(&args, &cli);
+        assert_eq!(
+            baked[..4],
+            strings(&["--port", "9000", "--enable-upload", "true"])
+        );
+        assert_eq!(baked[4], "--directory");
+        assert!(Path::new(&baked[5]).is_absolute());
+        assert!(!baked.iter().any(|arg| arg.contains("service")));
+
+        let attached = strings(&["--service=install", "-dfiles", "--directory=x", "-v"]);
+        assert_eq!(service_args(&attached, &cli)[..1], strings(&["-v"]));
+    }
+
+    #[test]
+    fn test_unit_plist_and_windows_command_line() {
+        let program = Path::new("/usr/local/bin/irondrop");
+        let args = strings(&["--directory", "/srv/my files", "--password", "50%$x"]);
+
+        let unit = systemd_unit(program, &args, Path::new("/srv"), Some("alice"));
+        assert!(unit.contains(
+            "ExecStart=/usr/local/bin/irondrop --directory \"/srv/my files\" --password 50%%$$x\n"
+        ));
+        assert!(unit.contains("User=alice\n"));
+        assert!(unit.contains("Type=notify\n"));
+        assert!(!systemd_unit(program, &args, Path::new("/srv"), None).contains("User="));
+
+        let plist = launchd_plist(
+            program,
+            &strings(&["--password", "a<b&c"]),
+            Path::new("/Users/me"),
+            Path::new("/Users/me/Library/Logs/irondrop.log"),
+        );
+        assert!(plist.contains("<string>a&lt;b&amp;c</string>"));
+        assert!(plist.contains(&format!("<string>{LAUNCHD_LABEL}</string>")));
+
+        let command_line = windows_command_line(
+            Path::new(r"C:\Program Files\IronDrop\irondrop.exe"),
+            &strings(&["-v", r"C:\My Files\", r#"say "hi""#]),
+        );
+        assert_eq!(
+            command_line,
+            r#""C:\Program Files\IronDrop\irondrop.exe" -v "C:\My Files\\" "say \"hi\"" --service run"#
+        );
+    }
+
+    #[test]
+    fn test_service_action_parse() {
+        assert_eq!(
+            ServiceAction::parse("Install"),
+            Some(ServiceAction::Install)
+        );
+        assert_eq!(
+            ServiceAction::parse("remove"),
+            Some(ServiceAction::Uninstall)
+        );
+        assert_eq!(ServiceAction::parse("run").map(|a| a.as_str()), Some("run"));
+        assert_eq!(ServiceAction::parse("start"), None);
+    }
+}
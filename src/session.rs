@@ -0,0 +1,364 @@
+// SPDX-License-Identifier: MIT
+
+//! Cookie sessions with a login page
+//!
+//! Basic Auth makes browsers show their own login dialog, which is awkward on
+//! phones and cannot be logged out of reliably. With `[auth] sessions = true`
+//! (or `--auth-sessions true`) browsers get a login form instead:
+//!
+//! - a page load without credentials is redirected (`303`) to
+//!   [`LOGIN_ROUTE`]`?next=<path>`
+//! - `POST` [`LOGIN_ROUTE`] with `username`, `password` and `next` form fields
+//!   checks the same accounts as Basic Auth (the `[auth]` user and `[users]`),
+//!   sets a signed [`SESSION_COOKIE`] and redirects back to `next`
+//! - `GET` [`LOGOUT_ROUTE`] clears the cookie
+//!
+//! The cookie holds the user name and an expiry time, signed with
+//! HMAC-SHA256 under `[auth] session_secret`. Without a secret a random one is
+//! made at startup, so sessions end when the server restarts; changing the
+//! secret ends them all. The account's role is looked up on every request, so
+//! removing an account or changing its role applies to open sessions at once.
+//!
+//! Basic Auth and API tokens keep working for scripts, and other requests
+//! without credentials still get a `401` challenge. The cookie is `HttpOnly`
+//! and `SameSite=Lax` (so cross-site form posts do not carry it), and `Secure`
+//! when IronDrop serves HTTPS itself.
+
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Request, RequestBody, Response, ResponseBody};
+use crate::middleware::AuthMiddleware;
+use crate::share::hmac_sha256;
+use crate::utils::{parse_query_string, percent_encode_query};
+use base64::Engine;
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Login form (`GET`) and credential check (`POST`)
+pub const LOGIN_ROUTE: &str = "/_irondrop/login";
+
+/// Clears the session cookie
+pub const LOGOUT_ROUTE: &str = "/_irondrop/logout";
+
+/// Name of the session cookie
+pub const SESSION_COOKIE: &str = "irondrop_session";
+
+/// Session lifetime in seconds unless configured otherwise (1 day)
+pub const DEFAULT_SESSION_LIFETIME: u64 = 24 * 3600;
+
+/// Largest login form accepted
+const MAX_LOGIN_BODY: usize = 8 * 1024;
+
+/// Secrets shorter than this are accepted with a warning
+const MIN_SECRET_LEN: usize = 16;
+
+/// Whether session mode is on, for the templates and the logout route
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether cookies are sent with `Secure`
+static SECURE: AtomicBool = AtomicBool::new(false);
+
+/// Key used when no `session_secret` is configured, kept across config reloads
+static RANDOM_SECRET: OnceLock<[u8; 32]> = OnceLock::new();
+
+/// Signs and verifies session cookies
+#[derive(Clone)]
+pub struct SessionSigner {
+    key: Vec<u8>,
+    lifetime: u64,
+}
+
+impl SessionSigner {
+    pub fn new(secret: &[u8], lifetime: u64) -> Self {
+        Self {
+            key: secret.to_vec(),
+            lifetime: lifetime.max(1),
+        }
+    }
+
+    /// Signer for the configured sessions, or `None` when session mode is off
+    pub fn from_cli(cli: &Cli) -> Option<Self> {
+        if !cli.auth_sessions.unwrap_or(false) {
+            return None;
+        }
+        let lifetime = cli.session_lifetime.unwrap_or(DEFAULT_SESSION_LIFETIME);
+        match cli.session_secret.as_deref().map(str::trim) {
+            Some(secret) if !secret.is_empty() => {
+                if secret.len() < MIN_SECRET_LEN {
+                    warn!(
+                        "Session secret is shorter than {MIN_SECRET_LEN} characters; use a longer one"
+                    );
+                }
+                Some(Self::new(secret.as_bytes(), lifetime))
+            }
+            _ => {
+                if RANDOM_SECRET.get().is_none() {
+                    let mut secret = [0u8; 32];
+                    if let Err(e) = crate::password::random_bytes(&mut secret) {
+                        warn!(
+                            "Cannot generate a session secret ({e}); set session_secret to use login sessions"
+                        );
+                        return None;
+                    }
+                    let _ = RANDOM_SECRET.set(secret);
+                }
+                RANDOM_SECRET
+                    .get()
+                    .map(|secret| Self::new(secret, lifetime))
+            }
+        }
+    }
+
+    pub fn lifetime(&self) -> u64 {
+        self.lifetime
+    }
+
+    /// Cookie value for `user`, valid until `now` plus the lifetime
+    pub fn issue(&self, user: &str, now: u64) -> String {
+        let name = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(user.as_bytes());
+        let expires = now.saturating_add(self.lifetime);
+        format!("{name}.{expires}.{}", self.sign(&name, expires))
+    }
+
+    /// User name of a cookie value that is signed and not expired at `now`
+    pub fn verify(&self, value: &str, now: u64) -> Option<String> {
+        let mut parts = value.trim().splitn(3, '.');
+        let (name, expires, signature) = (parts.next()?, parts.next()?, parts.next()?);
+        let expires: u64 = expires.parse().ok()?;
+        let expected = self.sign(name, expires);
+        if !crate::password::constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            debug!("Session cookie with an invalid signature");
+            return None;
+        }
+        if now >= expires {
+            debug!("Expired session cookie");
+            return None;
+        }
+        let name = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(name)
+            .ok()?;
+        String::from_utf8(name).ok()
+    }
+
+    fn sign(&self, name: &str, expires: u64) -> String {
+        let mac = hmac_sha256(&self.key, format!("session\n{name}\n{expires}").as_bytes());
+        mac.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// `Set-Cookie` value starting a session for `user`
+    pub fn set_cookie(&self, user: &str, now: u64) -> String {
+        cookie(&self.issue(user, now), self.lifetime)
+    }
+}
+
+/// Turn session mode on or off for the templates and the logout route
+pub fn configure(enabled: bool, secure: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    SECURE.store(secure, Ordering::SeqCst);
+}
+
+/// Whether browsers log in through [`LOGIN_ROUTE`]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// `Set-Cookie` value that removes the session cookie
+pub fn clear_cookie() -> String {
+    cookie("", 0)
+}
+
+fn cookie(value: &str, max_age: u64) -> String {
+    let base = crate::templates::base_path();
+    let path = if base.is_empty() { "/" } else { &base };
+    let secure = if SECURE.load(Ordering::SeqCst) {
+        "; Secure"
+    } else {
+        ""
+    };
+    format!(
+        "{SESSION_COOKIE}={value}; Max-Age={max_age}; Path={path}; HttpOnly; SameSite=Lax{secure}"
+    )
+}
+
+/// Value of the session cookie sent with `request`
+pub fn session_cookie(request: &Request) -> Option<&str> {
+    request
+        .headers
+        .get("cookie")?
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// Account named by the session cookie, not verified. For logging requests
+/// the auth middleware has already let through.
+pub fn cookie_user(request: &Request) -> Option<String> {
+    let name = session_cookie(request)?.split('.').next()?;
+    let name = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(name)
+        .ok()?;
+    String::from_utf8(name).ok()
+}
+
+/// Routes the login page needs before anyone is logged in
+pub fn is_public(request: &Request) -> bool {
+    let path = request.path.as_str();
+    path == LOGIN_ROUTE
+        || path == LOGOUT_ROUTE
+        || path == "/_irondrop/logo"
+        || path == "/_irondrop/static/custom.css"
+        || path.starts_with("/_irondrop/static/common/")
+        || ["/favicon.ico", "/favicon-16x16.png", "/favicon-32x32.png"].contains(&path)
+}
+
+/// Whether an unauthenticated request is a browser opening a page, which is
+/// sent to the login form instead of getting a `401`
+pub fn wants_login_page(request: &Request) -> bool {
+    matches!(request.method.as_str(), "GET" | "HEAD")
+        && !request.path.starts_with("/_api/")
+        && request
+            .headers
+            .get("accept")
+            .is_some_and(|accept| accept.contains("text/html"))
+}
+
+/// Where to send a browser that needs to log in before seeing `next`
+pub fn login_location(next: &str) -> String {
+    format!(
+        "{}?next={}",
+        crate::templates::prefixed(LOGIN_ROUTE),
+        percent_encode_query(next)
+    )
+}
+
+/// `next` when it is a path on this server, else the root. Keeps the login
+/// form from redirecting to another site.
+fn safe_next(next: &str) -> &str {
+    let local =
+        next.starts_with('/') && !next.starts_with("//") && !next.contains(['\\', '\r', '\n']);
+    if local { next } else { "/" }
+}
+
+/// `GET` and `POST` on [`LOGIN_ROUTE`]
+pub fn handle_login_request(
+    request: &Request,
+    auth: &AuthMiddleware,
+    signer: &SessionSigner,
+) -> Result<Response, AppError> {
+    if request.method != "POST" {
+        let params = request.query_params();
+        let next = safe_next(params.get("next").map_or("/", String::as_str));
+        return login_page(200, next, "", None);
+    }
+
+    let body = match &request.body {
+        Some(RequestBody::Memory(data)) if data.len() <= MAX_LOGIN_BODY => data.as_slice(),
+        Some(RequestBody::Memory(_)) | Some(RequestBody::File { .. }) => {
+            return Err(AppError::PayloadTooLarge(MAX_LOGIN_BODY as u64));
+        }
+        None => &[],
+    };
+    let form = parse_query_string(&String::from_utf8_lossy(body));
+    let field = |name: &str| form.get(name).map_or("", String::as_str);
+    let (username, next) = (field("username"), safe_next(field("next")));
+
+    match auth.check_login(request, username, field("password")) {
+        Ok(role) => {
+            info!("Session started for '{username}' ({})", role.as_str());
+            let mut headers = HashMap::new();
+            headers.insert("Location".to_string(), crate::templates::prefixed(next));
+            headers.insert(
+                "Set-Cookie".to_string(),
+                signer.set_cookie(username, unix_now()),
+            );
+            headers.insert("Cache-Control".to_string(), "no-store".to_string());
+            Ok(Response {
+                status_code: 303,
+                status_text: "See Other".to_string(),
+                headers,
+                body: ResponseBody::Text(String::new()),
+            })
+        }
+        Err(AppError::Unauthorized) => {
+            login_page(401, next, username, Some("Wrong username or password."))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn login_page(
+    status_code: u16,
+    next: &str,
+    username: &str,
+    error: Option<&str>,
+) -> Result<Response, AppError> {
+    let html =
+        crate::templates::TemplateEngine::global().render_login_page(next, username, error)?;
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "text/html; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-store".to_string());
+    Ok(Response {
+        status_code,
+        status_text: if status_code == 200 {
+            "OK"
+        } else {
+            "Unauthorized"
+        }
+        .to_string(),
+        headers,
+        body: ResponseBody::Text(html),
+    })
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_cookie_round_trip() {
+        let signer = SessionSigner::new(b"0123456789abcdef", 60);
+        let value = signer.issue("alice:ops", 1000);
+        assert_eq!(signer.verify(&value, 1059).as_deref(), Some("alice:ops"));
+        assert_eq!(signer.verify(&value, 1060), None);
+
+        let other = SessionSigner::new(b"another secret!!", 60);
+        assert_eq!(other.verify(&value, 1001), None);
+        let (name, rest) = value.split_once('.').unwrap();
+        let forged = format!(
+            "{}.{rest}",
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("root")
+        );
+        assert_ne!(name, forged.split('.').next().unwrap());
+        assert_eq!(signer.verify(&forged, 1001), None);
+        assert_eq!(signer.verify("garbage", 1001), None);
+    }
+
+    #[test]
+    fn test_safe_next() {
+        assert_eq!(safe_next("/docs/?sort=name"), "/docs/?sort=name");
+        for unsafe_next in [
+            "",
+            "https://evil.example",
+            "//evil.example",
+            "/\\evil",
+            "docs",
+        ] {
+            assert_eq!(safe_next(unsafe_next), "/", "{unsafe_next}");
+        }
+    }
+}
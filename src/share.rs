@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: MIT
+
+//! Expiring share links
+//!
+//! With a share secret configured (`[share] secret` or `--share-secret`), an
+//! authenticated user can mint a temporary download link:
+//!
+//! `GET /_api/share?path=/docs/report.pdf&ttl=3600` (also reachable as
+//! `/_irondrop/share`) answers with a URL such as
+//! `http://host/docs/report.pdf?expires=1735689600&signature=<hex>`.
+//!
+//! The signature is an HMAC-SHA256 over the path and the expiry time, keyed
+//! with the share secret. [`ShareLinkMiddleware`] wraps the authentication
+//! middleware: a `GET`/`HEAD` carrying a valid, unexpired signature for exactly
+//! the requested path skips authentication, while a wrong or expired signature
+//! is refused with 403. Links only cover single files, changing the secret
+//! revokes every outstanding link, and directories with their own
+//! `[auth.paths]` credentials always ask for them.
+
+use crate::checksum::Sha256;
+use crate::cli::Cli;
+use crate::error::AppError;
+use crate::http::{Request, Response, ResponseBody};
+use crate::middleware::{AuthMiddleware, Middleware};
+use crate::utils::{json_escape, percent_encode_path};
+use log::{debug, trace, warn};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Namespaced share route
+pub const SHARE_ROUTE: &str = "/_irondrop/share";
+
+/// Short share route
+pub const SHARE_SHORT_ROUTE: &str = "/_api/share";
+
+/// Link lifetime in seconds when the request does not ask for one
+pub const DEFAULT_SHARE_TTL: u64 = 3600;
+
+/// Longest link lifetime in seconds unless configured otherwise (7 days)
+pub const DEFAULT_SHARE_MAX_TTL: u64 = 7 * 24 * 3600;
+
+/// Query parameter carrying the expiry time (Unix seconds)
+const EXPIRES_PARAM: &str = "expires";
+
+/// Query parameter carrying the hex HMAC
+const SIGNATURE_PARAM: &str = "signature";
+
+/// Secrets shorter than this are accepted with a warning
+const MIN_SECRET_LEN: usize = 16;
+
+/// Outcome of checking a signed request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareCheck {
+    Valid,
+    Expired,
+    Invalid,
+}
+
+/// Signs and verifies share links
+#[derive(Clone)]
+pub struct ShareSigner {
+    key: Vec<u8>,
+    max_ttl: u64,
+}
+
+impl ShareSigner {
+    pub fn new(secret: &str, max_ttl: u64) -> Self {
+        Self {
+            key: secret.as_bytes().to_vec(),
+            max_ttl: max_ttl.max(1),
+        }
+    }
+
+    /// Signer for the configured secret, or `None` when share links are off
+    pub fn from_cli(cli: &Cli) -> Option<Self> {
+        let secret = cli.share_secret.as_deref()?.trim();
+        if secret.is_empty() {
+            return None;
+        }
+        if secret.len() < MIN_SECRET_LEN {
+            warn!("Share secret is shorter than {MIN_SECRET_LEN} characters; use a longer one");
+        }
+        Some(Self::new(
+            secret,
+            cli.share_max_ttl.unwrap_or(DEFAULT_SHARE_MAX_TTL),
+        ))
+    }
+
+    pub fn max_ttl(&self) -> u64 {
+        self.max_ttl
+    }
+
+    /// Hex signature for `path` (as the server sees it, decoded) valid until `expires`
+    pub fn sign(&self, path: &str, expires: u64) -> String {
+        let mac = hmac_sha256(&self.key, format!("{path}\n{expires}").as_bytes());
+        mac.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Check a signature presented for `path` at time `now`
+    pub fn verify(&self, path: &str, expires: u64, signature: &str, now: u64) -> ShareCheck {
+        let expected = self.sign(path, expires);
+        if !constant_time_eq(
+            expected.as_bytes(),
+            signature.to_ascii_lowercase().as_bytes(),
+        ) {
+            return ShareCheck::Invalid;
+        }
+        if now >= expires {
+            return ShareCheck::Expired;
+        }
+        ShareCheck::Valid
+    }
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        let mut hasher = Sha256::new();
+        hasher.update(key);
+        block[..32].copy_from_slice(&hasher.finalize());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(&block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let inner = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(&block.map(|b| b ^ 0x5c));
+    outer.update(&inner);
+    outer.finalize()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Lets requests with a valid share signature past the wrapped middleware
+pub struct ShareLinkMiddleware {
+    signer: ShareSigner,
+    inner: Arc<AuthMiddleware>,
+}
+
+impl ShareLinkMiddleware {
+    pub fn new(signer: ShareSigner, inner: Arc<AuthMiddleware>) -> Self {
+        Self { signer, inner }
+    }
+}
+
+impl Middleware for ShareLinkMiddleware {
+    fn handle(&self, request: &Request) -> Result<(), AppError> {
+        if !matches!(request.method.as_str(), "GET" | "HEAD")
+            || self.inner.protecting_rule(request).is_some()
+        {
+            return self.inner.handle(request);
+        }
+        let params = request.query_params();
+        let Some(signature) = params.get(SIGNATURE_PARAM) else {
+            return self.inner.handle(request);
+        };
+        let path = request.path.as_str();
+        let expires = params
+            .get(EXPIRES_PARAM)
+            .and_then(|value| value.parse::<u64>().ok())
+            .ok_or(AppError::Forbidden)?;
+
+        match self.signer.verify(path, expires, signature, unix_now()) {
+            ShareCheck::Valid => {
+                trace!("Share link accepted for {path}");
+                Ok(())
+            }
+            ShareCheck::Expired => {
+                debug!("Expired share link for {path}");
+                Err(AppError::Forbidden)
+            }
+            ShareCheck::Invalid => {
+                debug!("Invalid share link signature for {path}");
+                Err(AppError::Forbidden)
+            }
+        }
+    }
+}
+
+/// `GET` on [`SHARE_ROUTE`]: mint a signed link for a file
+pub fn handle_share_request(
+    request: &Request,
+    signer: &ShareSigner,
+    cli: &Cli,
+    base_dir: &Path,
+) -> Result<Response, AppError> {
+    let params = request.query_params();
+    let Some(relative) = params.get("path").filter(|p| !p.is_empty()) else {
+        debug!("Share request without a path");
+        return Err(AppError::BadRequest);
+    };
+    let ttl = match params.get("ttl") {
+        Some(value) => value
+            .parse::<u64>()
+            .ok()
+            .filter(|&ttl| ttl > 0)
+            .ok_or(AppError::BadRequest)?,
+        None => DEFAULT_SHARE_TTL,
+    }
+    .min(signer.max_ttl());
+
+    let file_path = crate::webdav::resolve_request_path(base_dir, relative)?;
+    if !file_path.is_file()
+        || crate::fs::ignore_rules()
+            .excludes(file_path.strip_prefix(base_dir).unwrap_or(&file_path))
+    {
+        return Err(AppError::NotFound);
+    }
+
+    let display_path = format!("/{}", relative.trim_start_matches('/'));
+    let encoded_path = percent_encode_path(Path::new(&display_path));
+    // Sign what the HTTP layer will hand the middleware after decoding the link
+    let signed_path = crate::utils::decode_path(&encoded_path)?;
+    let expires = unix_now() + ttl;
+    let signature = signer.sign(&signed_path, expires);
+
+    let https = cli.ssl_cert.is_some() && cli.ssl_key.is_some()
+        || request
+            .headers
+            .get("x-forwarded-proto")
+            .is_some_and(|proto| proto.eq_ignore_ascii_case("https"));
+    let host = request
+        .headers
+        .get("host")
+        .map_or("localhost", String::as_str);
+    let url = format!(
+        "{}://{host}{}{encoded_path}?{EXPIRES_PARAM}={expires}&{SIGNATURE_PARAM}={signature}",
+        if https { "https" } else { "http" },
+        crate::templates::base_path().trim_end_matches('/'),
+    );
+    debug!("Issued share link for {display_path} valid for {ttl}s");
+    crate::audit::record(
+        crate::audit::AuditEvent::ShareCreated,
+        crate::audit::AuditRecord::for_request(request, "success")
+            .with_path(display_path.as_str())
+            .with("expires", expires),
+    );
+
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers.insert("Cache-Control".to_string(), "no-store".to_string());
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(format!(
+            r#"{{"url":"{}","path":"{}","expires":{},"ttl":{}}}"#,
+            json_escape(&url),
+            json_escape(&display_path),
+            expires,
+            ttl
+        )),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231_vectors() {
+        // Test case 1
+        assert_eq!(
+            hex(&hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        // Test case 2
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Test case 6: key longer than the block size
+        assert_eq!(
+            hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = ShareSigner::new("a long enough share secret", 600);
+        let signature = signer.sign("/docs/report.pdf", 1_000);
+
+        assert_eq!(
+            signer.verify("/docs/report.pdf", 1_000, &signature, 999),
+            ShareCheck::Valid
+        );
+        assert_eq!(
+            signer.verify(
+                "/docs/report.pdf",
+                1_000,
+                &signature.to_ascii_uppercase(),
+                999
+            ),
+            ShareCheck::Valid
+        );
+        assert_eq!(
+            signer.verify("/docs/report.pdf", 1_000, &signature, 1_000),
+            ShareCheck::Expired
+        );
+        assert_eq!(
+            signer.verify("/docs/other.pdf", 1_000, &signature, 999),
+            ShareCheck::Invalid
+        );
+        assert_eq!(
+            signer.verify("/docs/report.pdf", 2_000, &signature, 999),
+            ShareCheck::Invalid
+        );
+        let other = ShareSigner::new("a different share secret", 600);
+        assert_eq!(
+            other.verify("/docs/report.pdf", 1_000, &signature, 999),
+            ShareCheck::Invalid
+        );
+    }
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MIT
+
+//! systemd integration: socket activation and readiness notification
+//!
+//! - Socket activation: when systemd starts IronDrop from a `.socket` unit it
+//!   passes the bound listener as file descriptor 3 and sets `LISTEN_PID` and
+//!   `LISTEN_FDS`. [`take_activated_listener`] adopts that socket instead of
+//!   binding `--listen`/`--port`.
+//! - Notification: with `Type=notify`, [`notify`] sends `READY=1` once the
+//!   server accepts connections and `STOPPING=1` on shutdown to the datagram
+//!   socket named by `NOTIFY_SOCKET`.
+//!
+//! Both are no-ops when the variables are absent, so nothing changes for
+//! servers started any other way.
+
+use log::{debug, warn};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// First file descriptor systemd passes (`SD_LISTEN_FDS_START`)
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// The passed socket can only be adopted once per process
+static LISTENER_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Number of sockets passed to this process by systemd
+///
+/// `LISTEN_PID` must name this process, otherwise the variables were
+/// inherited from a parent and do not apply.
+pub fn listen_fd_count(listen_pid: Option<&str>, listen_fds: Option<&str>, own_pid: u32) -> usize {
+    let pid_matches = listen_pid
+        .and_then(|pid| pid.trim().parse::<u32>().ok())
+        .is_some_and(|pid| pid == own_pid);
+    if !pid_matches {
+        return 0;
+    }
+    listen_fds
+        .and_then(|fds| fds.trim().parse::<usize>().ok())
+        .unwrap_or(0)
+}
+
+/// Adopt the listening socket passed by systemd, if there is one
+#[cfg(unix)]
+pub fn take_activated_listener() -> io::Result<Option<std::net::TcpListener>> {
+    use std::os::fd::FromRawFd;
+
+    let count = listen_fd_count(
+        std::env::var("LISTEN_PID").ok().as_deref(),
+        std::env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    );
+    if count == 0 || LISTENER_TAKEN.swap(true, Ordering::AcqRel) {
+        return Ok(None);
+    }
+    if count > 1 {
+        warn!("systemd passed {count} sockets; only the first one is used");
+    }
+
+    // SAFETY: systemd guarantees descriptors from LISTEN_FDS_START onwards are
+    // open and owned by this process, and LISTENER_TAKEN ensures we wrap it once
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(LISTEN_FDS_START) };
+    listener.set_nonblocking(true)?;
+    Ok(Some(listener))
+}
+
+/// Adopt the listening socket passed by systemd, if there is one
+#[cfg(not(unix))]
+pub fn take_activated_listener() -> io::Result<Option<std::net::TcpListener>> {
+    let _ = &LISTENER_TAKEN;
+    Ok(None)
+}
+
+/// Send a state change such as `READY=1` to systemd, if it asked for them
+pub fn notify(state: &str) {
+    let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    match notify_socket(&socket.to_string_lossy(), state) {
+        Ok(()) => debug!("sd_notify: {state}"),
+        Err(e) => warn!("sd_notify {state} failed: {e}"),
+    }
+}
+
+/// Send `state` to the notification socket at `path`
+///
+/// A leading `@` names a Linux abstract socket.
+#[cfg(unix)]
+pub fn notify_socket(path: &str, state: &str) -> io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let socket = UnixDatagram::unbound()?;
+    if let Some(name) = path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)?;
+            return Ok(());
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = name;
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "abstract sockets are Linux-only",
+            ));
+        }
+    }
+    socket.send_to(state.as_bytes(), path)?;
+    Ok(())
+}
+
+/// Send `state` to the notification socket at `path`
+#[cfg(not(unix))]
+pub fn notify_socket(_path: &str, _state: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "sd_notify needs Unix sockets",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listen_fd_count_requires_matching_pid() {
+        assert_eq!(listen_fd_count(Some("42"), Some("1"), 42), 1);
+        assert_eq!(listen_fd_count(Some("42"), Some("2"), 42), 2);
+        assert_eq!(listen_fd_count(Some("41"), Some("1"), 42), 0);
+        assert_eq!(listen_fd_count(None, Some("1"), 42), 0);
+        assert_eq!(listen_fd_count(Some("42"), None, 42), 0);
+        assert_eq!(listen_fd_count(Some("42"), Some("x"), 42), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_notify_socket_delivers_state() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("notify.sock");
+        let receiver = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        notify_socket(&path.to_string_lossy(), "READY=1").unwrap();
+
+        let mut buf = [0u8; 64];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+    }
+}
@@ -3,8 +3,16 @@
 //! Template loading and rendering system for modular HTML
 
 use crate::error::AppError;
-use log::{debug, trace};
+use crate::fs::{ListingOptions, ListingSort, ListingView};
+use crate::response::{FileKind, get_file_kind};
+use crate::search::SortOrder;
+use crate::upload_meta::UploadMetadata;
+use crate::utils::percent_encode_path;
+use log::{debug, trace, warn};
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::OnceLock;
 use std::sync::atomic::AtomicBool;
 
@@ -19,11 +27,58 @@ pub fn init_base_path(path: String) {
     let _ = BASE_PATH.set(path);
 }
 
+thread_local! {
+    /// `X-Forwarded-Prefix` of the request being handled on this thread
+    static URL_PREFIX: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
 /// Get the configured base path prefix (empty string if not set).
-pub fn base_path() -> &'static str {
+/// This is what incoming request paths are stripped of.
+pub fn configured_base_path() -> &'static str {
     BASE_PATH.get().map(|s| s.as_str()).unwrap_or("")
 }
 
+/// Prefix for generated URLs: the current request's forwarded prefix when a
+/// trusted proxy sent one (see [`with_url_prefix`]), else the base path.
+pub fn base_path() -> Cow<'static, str> {
+    match URL_PREFIX.with(|prefix| prefix.borrow().clone()) {
+        Some(prefix) => Cow::Owned(prefix),
+        None => Cow::Borrowed(configured_base_path()),
+    }
+}
+
+/// Run `f` with generated URLs using `prefix` instead of the base path.
+/// The previous value is restored afterwards, also when `f` panics.
+pub fn with_url_prefix<R>(prefix: Option<&str>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<String>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            URL_PREFIX.with(|prefix| *prefix.borrow_mut() = previous);
+        }
+    }
+
+    let Some(prefix) = prefix else {
+        return f();
+    };
+    let _restore = Restore(URL_PREFIX.with(|p| p.replace(Some(prefix.to_string()))));
+    f()
+}
+
+/// Normalize an `X-Forwarded-Prefix` value: `/files/` becomes `/files` and
+/// `/` the empty prefix. Values that are not plain absolute paths are refused.
+pub fn parse_forwarded_prefix(value: &str) -> Option<String> {
+    let value = value.split(',').next()?.trim();
+    if !value.starts_with('/') || value.starts_with("//") {
+        return None;
+    }
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "-._~/%!$&'()*+;=:@".contains(c);
+    if !value.chars().all(allowed) || value.split('/').any(|segment| segment == "..") {
+        return None;
+    }
+    Some(value.trim_end_matches('/').to_string())
+}
+
 /// Prepend the base path to an absolute path (e.g. "/_irondrop/foo" -> "/webstorage/_irondrop/foo").
 pub fn prefixed(path: &str) -> String {
     let bp = base_path();
@@ -34,6 +89,53 @@ pub fn prefixed(path: &str) -> String {
     }
 }
 
+/// Default web UI theme (`[ui] theme`); the header toggle overrides it per browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// Follow the browser's `prefers-color-scheme`
+    Auto,
+}
+
+impl Theme {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "dark" | "" => Some(Self::Dark),
+            "light" => Some(Self::Light),
+            "auto" | "system" => Some(Self::Auto),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Stylesheet from `--custom-css`, served as `/_irondrop/static/custom.css`
+static CUSTOM_CSS: OnceLock<String> = OnceLock::new();
+
+/// Initialize the default theme and optional branding stylesheet. Call once during server startup.
+pub fn init_theme(theme: Theme, custom_css: Option<String>) {
+    let _ = THEME.set(theme);
+    if let Some(css) = custom_css {
+        let _ = CUSTOM_CSS.set(css);
+    }
+}
+
+/// The configured default theme
+pub fn theme() -> Theme {
+    THEME.get().copied().unwrap_or_default()
+}
+
 // Embed templates at compile time
 // Base template
 const BASE_HTML: &str = include_str!("../templates/common/base.html");
@@ -44,6 +146,11 @@ const ERROR_CONTENT_HTML: &str = include_str!("../templates/error/content.html")
 const UPLOAD_CONTENT_HTML: &str = include_str!("../templates/upload/content.html");
 const UPLOAD_SUCCESS_HTML: &str = include_str!("../templates/upload/success.html");
 const LOGOUT_CONTENT_HTML: &str = include_str!("../templates/common/logout.html");
+const LOGIN_CONTENT_HTML: &str = include_str!("../templates/common/login.html");
+const PREVIEW_CONTENT_HTML: &str = include_str!("../templates/preview/content.html");
+const PREVIEW_MEDIA_HTML: &str = include_str!("../templates/preview/media.html");
+const QR_CONTENT_HTML: &str = include_str!("../templates/qr/content.html");
+const EDIT_CONTENT_HTML: &str = include_str!("../templates/edit/content.html");
 
 // CSS and JS assets
 const DIRECTORY_STYLES_CSS: &str = include_str!("../templates/directory/styles.css");
@@ -53,14 +160,25 @@ const ERROR_SCRIPT_JS: &str = include_str!("../templates/error/script.js");
 const UPLOAD_STYLES_CSS: &str = include_str!("../templates/upload/styles.css");
 const UPLOAD_SCRIPT_JS: &str = include_str!("../templates/upload/script.js");
 const UPLOAD_FORM_HTML: &str = include_str!("../templates/upload/form.html");
+const PREVIEW_STYLES_CSS: &str = include_str!("../templates/preview/styles.css");
+const QR_STYLES_CSS: &str = include_str!("../templates/qr/styles.css");
+const EDIT_STYLES_CSS: &str = include_str!("../templates/edit/styles.css");
+const EDIT_SCRIPT_JS: &str = include_str!("../templates/edit/script.js");
 
 // Monitor templates
 const MONITOR_CONTENT_HTML: &str = include_str!("../templates/monitor/content.html");
 const MONITOR_STYLES_CSS: &str = include_str!("../templates/monitor/styles.css");
 const MONITOR_SCRIPT_JS: &str = include_str!("../templates/monitor/script.js");
 
+// Status dashboard templates
+const STATUS_CONTENT_HTML: &str = include_str!("../templates/status/content.html");
+const STATUS_STYLES_CSS: &str = include_str!("../templates/status/styles.css");
+const STATUS_SCRIPT_JS: &str = include_str!("../templates/status/script.js");
+
 // Common base styles
 const BASE_CSS: &str = include_str!("../templates/common/base.css");
+const THEMES_CSS: &str = include_str!("../templates/common/themes.css");
+const THEME_SCRIPT_JS: &str = include_str!("../templates/common/theme.js");
 
 // Embed favicon files at compile time
 const FAVICON_ICO: &[u8] = include_bytes!("../favicon.ico");
@@ -76,6 +194,9 @@ const BACK_ICON_SVG: &str = include_str!("../templates/directory/back_icon.svg")
 const ZIP_ICON_SVG: &str = include_str!("../templates/directory/zip_icon.svg");
 const IMAGE_ICON_SVG: &str = include_str!("../templates/directory/image_icon.svg");
 const VIDEO_ICON_SVG: &str = include_str!("../templates/directory/video_icon.svg");
+const AUDIO_ICON_SVG: &str = include_str!("../templates/directory/audio_icon.svg");
+const CODE_ICON_SVG: &str = include_str!("../templates/directory/code_icon.svg");
+const DOCUMENT_ICON_SVG: &str = include_str!("../templates/directory/document_icon.svg");
 
 /// Template loader and renderer for modular HTML templates
 pub struct TemplateEngine {
@@ -84,6 +205,12 @@ pub struct TemplateEngine {
 
 static TEMPLATE_ENGINE: OnceLock<TemplateEngine> = OnceLock::new();
 
+/// Custom error pages from `--template-dir`, keyed by status code
+static CUSTOM_ERROR_PAGES: OnceLock<HashMap<u16, String>> = OnceLock::new();
+
+/// Host name shown as `{{SERVER_NAME}}` on custom error pages
+static SERVER_NAME: OnceLock<String> = OnceLock::new();
+
 impl Default for TemplateEngine {
     fn default() -> Self {
         Self::new()
@@ -105,7 +232,13 @@ impl TemplateEngine {
         templates.insert("upload_success", UPLOAD_SUCCESS_HTML);
         templates.insert("upload_form", UPLOAD_FORM_HTML);
         templates.insert("monitor_content", MONITOR_CONTENT_HTML);
+        templates.insert("status_content", STATUS_CONTENT_HTML);
         templates.insert("logout_content", LOGOUT_CONTENT_HTML);
+        templates.insert("login_content", LOGIN_CONTENT_HTML);
+        templates.insert("preview_content", PREVIEW_CONTENT_HTML);
+        templates.insert("preview_media", PREVIEW_MEDIA_HTML);
+        templates.insert("qr_content", QR_CONTENT_HTML);
+        templates.insert("edit_content", EDIT_CONTENT_HTML);
 
         Self { templates }
     }
@@ -115,20 +248,98 @@ impl TemplateEngine {
         TEMPLATE_ENGINE.get_or_init(Self::new)
     }
 
-    /// Get appropriate icon SVG based on file extension
-    fn get_file_icon(filename: &str) -> &'static str {
-        let extension = filename.split('.').next_back().unwrap_or("").to_lowercase();
-        match extension.as_str() {
-            // Archive formats
-            "zip" | "rar" | "7z" | "tar" | "gz" | "bz2" | "xz" => ZIP_ICON_SVG,
-            // Image formats
-            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tiff" => {
-                IMAGE_ICON_SVG
+    /// Load custom error pages from `dir` for every later error response.
+    /// Call once during server startup.
+    pub fn init_error_templates(dir: &Path) -> Result<usize, AppError> {
+        let pages = Self::load_error_templates(dir)?;
+        let count = pages.len();
+        if CUSTOM_ERROR_PAGES.set(pages).is_err() {
+            debug!("Custom error templates already loaded; keeping the first set");
+        }
+        Ok(count)
+    }
+
+    /// Read `<status>.html` files (400-599) from `dir`; other files are ignored
+    pub fn load_error_templates(dir: &Path) -> Result<HashMap<u16, String>, AppError> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|_| AppError::DirectoryNotFound(dir.display().to_string()))?;
+        let mut pages = HashMap::new();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let is_html = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("html"));
+            let Some(code) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u16>().ok())
+                .filter(|code| (400..=599).contains(code) && is_html)
+            else {
+                continue;
+            };
+            match std::fs::read_to_string(&path) {
+                Ok(template) => {
+                    debug!("Loaded custom {} page from {}", code, path.display());
+                    pages.insert(code, template);
+                }
+                Err(e) => warn!("Skipping error template {}: {}", path.display(), e),
             }
-            // Video formats
-            "mp4" | "avi" | "mkv" | "mov" | "wmv" | "flv" | "webm" | "m4v" => VIDEO_ICON_SVG,
-            // Default file icon
-            _ => FILE_ICON_SVG,
+        }
+        Ok(pages)
+    }
+
+    /// The custom page for `status_code`, if `--template-dir` provided one
+    pub fn render_custom_error_page(
+        &self,
+        status_code: u16,
+        status_text: &str,
+        description: &str,
+        request_path: &str,
+    ) -> Option<String> {
+        let template = CUSTOM_ERROR_PAGES.get()?.get(&status_code)?;
+        Some(self.render_error_template(
+            template,
+            status_code,
+            status_text,
+            description,
+            request_path,
+        ))
+    }
+
+    /// Fill a custom error template. Variables: `{{ERROR_CODE}}`,
+    /// `{{ERROR_MESSAGE}}`, `{{ERROR_DESCRIPTION}}`, `{{REQUEST_PATH}}`,
+    /// `{{SERVER_NAME}}`, `{{BASE_PATH}}` and `{{VERSION}}`.
+    pub fn render_error_template(
+        &self,
+        template: &str,
+        status_code: u16,
+        status_text: &str,
+        description: &str,
+        request_path: &str,
+    ) -> String {
+        let server_name = SERVER_NAME.get_or_init(crate::mdns::hostname);
+        let mut variables = HashMap::new();
+        variables.insert("ERROR_CODE".to_string(), status_code.to_string());
+        variables.insert("ERROR_MESSAGE".to_string(), html_escape(status_text));
+        variables.insert("ERROR_DESCRIPTION".to_string(), html_escape(description));
+        variables.insert("REQUEST_PATH".to_string(), html_escape(request_path));
+        variables.insert("SERVER_NAME".to_string(), html_escape(server_name));
+        variables.insert("BASE_PATH".to_string(), html_escape(&base_path()));
+        variables.insert("VERSION".to_string(), crate::VERSION.to_string());
+
+        let conditional_processed = self.process_conditionals_optimized(template, &variables);
+        Self::substitute_variables_single_pass(&conditional_processed, &variables)
+    }
+
+    /// Icon SVG for a file kind (see [`crate::response::get_file_kind`])
+    fn get_file_icon(kind: FileKind) -> &'static str {
+        match kind {
+            FileKind::Archive => ZIP_ICON_SVG,
+            FileKind::Image => IMAGE_ICON_SVG,
+            FileKind::Video => VIDEO_ICON_SVG,
+            FileKind::Audio => AUDIO_ICON_SVG,
+            FileKind::Code => CODE_ICON_SVG,
+            FileKind::Document => DOCUMENT_ICON_SVG,
+            FileKind::Other => FILE_ICON_SVG,
         }
     }
 
@@ -143,6 +354,9 @@ impl TemplateEngine {
         match path {
             // Common base styles
             "common/base.css" => Some((BASE_CSS, "text/css")),
+            "common/themes.css" => Some((THEMES_CSS, "text/css")),
+            "common/theme.js" => Some((THEME_SCRIPT_JS, "application/javascript")),
+            "custom.css" => CUSTOM_CSS.get().map(|css| (css.as_str(), "text/css")),
             // Directory assets
             "directory/styles.css" => Some((DIRECTORY_STYLES_CSS, "text/css")),
             "directory/script.js" => Some((DIRECTORY_SCRIPT_JS, "application/javascript")),
@@ -155,6 +369,16 @@ impl TemplateEngine {
             // Monitor assets
             "monitor/styles.css" => Some((MONITOR_STYLES_CSS, "text/css")),
             "monitor/script.js" => Some((MONITOR_SCRIPT_JS, "application/javascript")),
+            // Status dashboard assets
+            "status/styles.css" => Some((STATUS_STYLES_CSS, "text/css")),
+            "status/script.js" => Some((STATUS_SCRIPT_JS, "application/javascript")),
+            // Preview assets
+            "preview/styles.css" => Some((PREVIEW_STYLES_CSS, "text/css")),
+            // QR code page assets
+            "qr/styles.css" => Some((QR_STYLES_CSS, "text/css")),
+            // Editor assets
+            "edit/styles.css" => Some((EDIT_STYLES_CSS, "text/css")),
+            "edit/script.js" => Some((EDIT_SCRIPT_JS, "application/javascript")),
             _ => None,
         }
     }
@@ -190,13 +414,23 @@ impl TemplateEngine {
         base_variables.insert("PAGE_TITLE".to_string(), page_title.to_string());
         base_variables.insert("PAGE_STYLES".to_string(), page_styles.to_string());
         base_variables.insert("PAGE_SCRIPTS".to_string(), page_scripts.to_string());
+        base_variables.insert("THEME".to_string(), theme().as_str().to_string());
+        base_variables.insert(
+            "CUSTOM_CSS".to_string(),
+            CUSTOM_CSS.get().is_some().to_string(),
+        );
 
         let auth_enabled = AUTH_ENABLED.load(std::sync::atomic::Ordering::SeqCst);
         let logout_href = prefixed("/_irondrop/logout");
-        let full_header_actions = if auth_enabled && content_template != "logout_content" {
+        let full_header_actions = if !auth_enabled
+            || matches!(content_template, "logout_content" | "login_content")
+        {
+            header_actions.to_string()
+        } else if crate::session::is_enabled() {
+            // The logout route clears the session cookie, no Basic Auth trick needed
             format!(
                 r#"{}
-                <a href="{logout_href}" onclick="event.preventDefault(); var xhr = new XMLHttpRequest(); xhr.open('GET', '{logout_href}', true, 'logout', 'logout'); xhr.send(); xhr.onreadystatechange = function() {{ if (xhr.readyState == 4) window.location.href = '{logout_href}'; }};" class="btn btn-light" id="logoutBtn" style="margin-left: 8px;" title="Logout">
+                <a href="{logout_href}" class="btn btn-light" id="logoutBtn" style="margin-left: 8px;" title="Logout">
                     <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
                         <path d="M9 21H5a2 2 0 0 1-2-2V5a2 2 0 0 1 2-2h4"></path>
                         <polyline points="16 17 21 12 16 7"></polyline>
@@ -207,7 +441,18 @@ impl TemplateEngine {
                 header_actions
             )
         } else {
-            header_actions.to_string()
+            format!(
+                r#"{}
+                <a href="{logout_href}" onclick="event.preventDefault(); var xhr = new XMLHttpRequest(); xhr.open('GET', '{logout_href}', true, 'logout', 'logout'); xhr.send(); xhr.onreadystatechange = function() {{ if (xhr.readyState == 4) window.location.href = '{logout_href}'; }};" class="btn btn-light" id="logoutBtn" style="margin-left: 8px;" title="Logout">
+                    <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
+                        <path d="M9 21H5a2 2 0 0 1-2-2V5a2 2 0 0 1 2-2h4"></path>
+                        <polyline points="16 17 21 12 16 7"></polyline>
+                        <line x1="21" y1="12" x2="9" y2="12"></line>
+                    </svg>
+                    Logout
+                </a>"#,
+                header_actions
+            )
         };
         base_variables.insert("HEADER_ACTIONS".to_string(), full_header_actions);
         base_variables.insert("PAGE_CONTENT".to_string(), content);
@@ -427,6 +672,204 @@ impl TemplateEngine {
         )
     }
 
+    /// Render a Markdown or text preview; `body_html` must already be escaped
+    pub fn render_preview_page(
+        &self,
+        file_path: &str,
+        raw_href: &str,
+        edit_href: Option<&str>,
+        body_html: &str,
+        markdown: bool,
+        truncated: bool,
+    ) -> Result<String, AppError> {
+        debug!("Rendering preview page for {file_path} (markdown={markdown})");
+        let file_name = file_path.rsplit('/').next().unwrap_or(file_path);
+        let parent = &file_path[..file_path.len() - file_name.len()];
+
+        let mut variables = HashMap::new();
+        variables.insert("FILE_NAME".to_string(), html_escape(file_name));
+        variables.insert("FILE_PATH".to_string(), html_escape(file_path));
+        variables.insert("PREVIEW_BODY".to_string(), body_html.to_string());
+        variables.insert(
+            "PREVIEW_CLASS".to_string(),
+            if markdown {
+                "markdown-body"
+            } else {
+                "text-body"
+            }
+            .to_string(),
+        );
+        variables.insert("TRUNCATED".to_string(), truncated.to_string());
+        for (key, algorithm) in [("SHA256_HREF", "sha256"), ("MD5_HREF", "md5")] {
+            let href = prefixed(&format!(
+                "{}?path={}&algo={algorithm}&format=text",
+                crate::checksum::CHECKSUM_SHORT_ROUTE,
+                crate::utils::percent_encode_query(file_path)
+            ));
+            variables.insert(key.to_string(), html_escape(&href));
+        }
+
+        let page_styles = format!(
+            r#"<link rel="stylesheet" href="{}/_irondrop/static/preview/styles.css">"#,
+            base_path()
+        );
+        let edit_link = edit_href.map_or(String::new(), |href| {
+            format!(
+                r#"<a href="{}" class="btn btn-light" id="editFile" style="margin-left: 8px;">Edit</a>"#,
+                html_escape(href)
+            )
+        });
+        let header_actions = Self::preview_actions(raw_href, parent, &edit_link);
+
+        self.render_page(
+            "preview_content",
+            &html_escape(file_name),
+            &page_styles,
+            "",
+            &header_actions,
+            &variables,
+        )
+    }
+
+    /// Render an HTML5 audio or video player for `file_path`, streamed from `raw_href`
+    pub fn render_media_page(
+        &self,
+        file_path: &str,
+        raw_href: &str,
+        mime_type: &str,
+    ) -> Result<String, AppError> {
+        debug!("Rendering media player for {file_path} ({mime_type})");
+        let file_name = file_path.rsplit('/').next().unwrap_or(file_path);
+        let parent = &file_path[..file_path.len() - file_name.len()];
+        let video = mime_type.starts_with("video/");
+
+        let mut variables = HashMap::new();
+        variables.insert("FILE_NAME".to_string(), html_escape(file_name));
+        variables.insert("FILE_PATH".to_string(), html_escape(file_path));
+        variables.insert("RAW_HREF".to_string(), html_escape(raw_href));
+        variables.insert("MIME_TYPE".to_string(), html_escape(mime_type));
+        variables.insert("VIDEO".to_string(), video.to_string());
+        variables.insert("AUDIO".to_string(), (!video).to_string());
+
+        let page_styles = format!(
+            r#"<link rel="stylesheet" href="{}/_irondrop/static/preview/styles.css">"#,
+            base_path()
+        );
+        let header_actions = Self::preview_actions(raw_href, parent, "");
+
+        self.render_page(
+            "preview_media",
+            &html_escape(file_name),
+            &page_styles,
+            "",
+            &header_actions,
+            &variables,
+        )
+    }
+
+    /// Download, `extra` and "Back to Directory" buttons of preview pages
+    fn preview_actions(raw_href: &str, parent: &str, extra: &str) -> String {
+        let back_href = prefixed(&format!(
+            "/{}",
+            percent_encode_path(Path::new(parent.trim_start_matches('/')))
+        ));
+        format!(
+            r#"<a href="{}" class="btn btn-light" id="downloadFile">Download</a>{extra}
+                <a href="{back_href}" class="btn btn-light" id="backToDir" style="margin-left: 8px;">
+                    <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2">
+                        <path d="m12 19-7-7 7-7" />
+                        <path d="m19 12H5" />
+                    </svg>
+                    Back to Directory
+                </a>"#,
+            html_escape(raw_href)
+        )
+    }
+
+    /// Render the text editor for `file_path`, saving to `save_href`
+    pub fn render_edit_page(
+        &self,
+        file_path: &str,
+        raw_href: &str,
+        save_href: &str,
+        text: &str,
+        etag: &str,
+    ) -> Result<String, AppError> {
+        debug!("Rendering editor for {file_path}");
+        let file_name = file_path.rsplit('/').next().unwrap_or(file_path);
+
+        let mut variables = HashMap::new();
+        variables.insert("FILE_NAME".to_string(), html_escape(file_name));
+        variables.insert("FILE_PATH".to_string(), html_escape(file_path));
+        variables.insert("SAVE_HREF".to_string(), html_escape(save_href));
+        variables.insert("ETAG".to_string(), html_escape(etag));
+        variables.insert(
+            "LINE_ENDING".to_string(),
+            if text.contains("\r\n") { "crlf" } else { "lf" }.to_string(),
+        );
+        // A leading newline right after <textarea> would be dropped by the parser
+        let text = text.replace("\r\n", "\n");
+        let leading = if text.starts_with('\n') { "\n" } else { "" };
+        variables.insert(
+            "EDIT_CONTENT".to_string(),
+            format!("{leading}{}", html_escape(&text)),
+        );
+
+        let page_styles = format!(
+            r#"<link rel="stylesheet" href="{}/_irondrop/static/edit/styles.css">"#,
+            base_path()
+        );
+        let page_scripts = format!(
+            r#"<script src="{}/_irondrop/static/edit/script.js"></script>"#,
+            base_path()
+        );
+        let header_actions = format!(
+            r#"<a href="{}" class="btn btn-light" id="downloadFile">Download</a>"#,
+            html_escape(raw_href)
+        );
+
+        self.render_page(
+            "edit_content",
+            &format!("Edit {}", html_escape(file_name)),
+            &page_styles,
+            &page_scripts,
+            &header_actions,
+            &variables,
+        )
+    }
+
+    /// Render the QR code landing page; `qr_svg` is inlined as-is
+    pub fn render_qr_page(&self, share_url: &str, qr_svg: &str) -> Result<String, AppError> {
+        debug!("Rendering QR code page for {share_url}");
+        let mut variables = HashMap::new();
+        variables.insert("SHARE_URL".to_string(), html_escape(share_url));
+        variables.insert("QR_SVG".to_string(), qr_svg.to_string());
+
+        let page_styles = format!(
+            r#"<link rel="stylesheet" href="{}/_irondrop/static/qr/styles.css">"#,
+            base_path()
+        );
+        let header_actions = format!(
+            r#"<a href="{}" class="btn btn-light" id="backToDir">
+                    <svg width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2">
+                        <path d="m12 19-7-7 7-7" />
+                        <path d="m19 12H5" />
+                    </svg>
+                    Back to Files
+                </a>"#,
+            html_escape(&prefixed("/"))
+        );
+
+        self.render_page(
+            "qr_content",
+            "Open on your phone",
+            &page_styles,
+            "",
+            &header_actions,
+            &variables,
+        )
+    }
+
     /// Render a template with variables, supporting conditionals (optimized single-scan passes)
     pub fn render(
         &self,
@@ -537,17 +980,108 @@ impl TemplateEngine {
         current_path: &str,
         page: usize,
         total_pages: usize,
+    ) -> Result<String, AppError> {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|(name, size, date)| (name.clone(), size.clone(), date.clone(), false, None))
+            .collect();
+        self.render_directory_listing_with_file_management(
+            path,
+            &entries,
+            entry_count,
+            upload_enabled,
+            current_path,
+            &ListingOptions::page(page),
+            total_pages,
+            false,
+        )
+    }
+
+    /// Generate directory listing HTML, adding rename/delete controls to each
+    /// row when `file_management` is enabled
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_directory_listing_with_file_management(
+        &self,
+        path: &str,
+        entries: &[(String, String, String, bool, Option<UploadMetadata>)], // (name, size, date, symlink, metadata)
+        entry_count: usize,
+        upload_enabled: bool,
+        current_path: &str,
+        options: &ListingOptions,
+        total_pages: usize,
+        file_management: bool,
     ) -> Result<String, AppError> {
         debug!(
-            "Rendering directory listing: path='{}', entries={}, upload_enabled={}",
-            path, entry_count, upload_enabled
+            "Rendering directory listing: path='{}', entries={}, upload_enabled={}, file_management={}",
+            path, entry_count, upload_enabled, file_management
         );
         trace!("Directory listing current path: {}", current_path);
         let mut variables = HashMap::new();
         variables.insert("PATH".to_string(), path.to_string());
         variables.insert("ENTRY_COUNT".to_string(), entry_count.to_string());
         variables.insert("UPLOAD_ENABLED".to_string(), upload_enabled.to_string());
+        variables.insert("FILE_MANAGEMENT".to_string(), file_management.to_string());
         variables.insert("CURRENT_PATH".to_string(), current_path.to_string());
+        let directory = current_path.trim_matches('/');
+        variables.insert(
+            "DIRECTORY_PATH".to_string(),
+            html_escape(&format!("/{directory}")),
+        );
+        let columns = if file_management { 4 } else { 3 };
+        let page = options.page;
+
+        // Column headers link to the listing re-sorted by that column
+        for (sort, label, key) in [
+            (ListingSort::Name, "Name", "NAME"),
+            (ListingSort::Size, "Size", "SIZE"),
+            (ListingSort::Modified, "Modified", "MODIFIED"),
+        ] {
+            let (aria, arrow) = match (sort == options.sort, options.order) {
+                (false, _) => ("none", ""),
+                (true, SortOrder::Asc) => ("ascending", " ▲"),
+                (true, SortOrder::Desc) => ("descending", " ▼"),
+            };
+            variables.insert(format!("{key}_ARIA_SORT"), aria.to_string());
+            variables.insert(
+                format!("{key}_HEADER"),
+                format!(
+                    r#"<a href="{}" class="sort-link">{label}{arrow}</a>"#,
+                    html_escape(&Self::listing_href(&options.query_for_sort(sort)))
+                ),
+            );
+        }
+        variables.insert(
+            "FILTER".to_string(),
+            html_escape(options.filter.as_deref().unwrap_or("")),
+        );
+        variables.insert(
+            "FILTER_ACTIVE".to_string(),
+            options.filter.is_some().to_string(),
+        );
+        variables.insert(
+            "CLEAR_FILTER_HREF".to_string(),
+            html_escape(&Self::listing_href(
+                &ListingOptions {
+                    filter: None,
+                    ..options.clone()
+                }
+                .query_for_page(1),
+            )),
+        );
+        let mut sort_inputs = String::new();
+        if options.sort != ListingSort::Name {
+            sort_inputs.push_str(&format!(
+                r#"<input type="hidden" name="sort" value="{}">"#,
+                options.sort.as_str()
+            ));
+        }
+        if options.order != SortOrder::Asc {
+            sort_inputs.push_str(&format!(
+                r#"<input type="hidden" name="order" value="{}">"#,
+                options.order.as_str()
+            ));
+        }
+        variables.insert("SORT_INPUTS".to_string(), sort_inputs);
 
         // Build a clean query suffix for the upload link (omit for root)
         let clean = current_path.trim_start_matches('/').trim_end_matches('/');
@@ -555,13 +1089,41 @@ impl TemplateEngine {
             String::new()
         } else {
             // Percent-encode minimal set for URLs
-            let encoded = percent_encode(clean);
+            let encoded = percent_encode_path(Path::new(clean));
             format!("?upload_to={encoded}")
         };
         variables.insert("QUERY_UPLOAD_SUFFIX".to_string(), query_suffix);
 
+        // The gallery is offered when at least half of the files on this
+        // page are pictures; it shows those as thumbnails above the table
+        let (files, images) = entries
+            .iter()
+            .filter(|(name, ..)| !name.ends_with('/'))
+            .fold((0, 0), |(files, images), (name, ..)| {
+                let image = get_file_kind(Path::new(name)) == FileKind::Image;
+                (files + 1, images + usize::from(image))
+            });
+        let gallery_available = images > 0 && images * 2 >= files;
+        let gallery = gallery_available && options.view == ListingView::Gallery;
+        variables.insert(
+            "GALLERY_AVAILABLE".to_string(),
+            gallery_available.to_string(),
+        );
+        variables.insert("GALLERY".to_string(), gallery.to_string());
+        let (toggle_view, toggle_label) = if gallery {
+            (ListingView::List, "List view")
+        } else {
+            (ListingView::Gallery, "Gallery view")
+        };
+        variables.insert(
+            "VIEW_TOGGLE_HREF".to_string(),
+            html_escape(&options.query_for_view(toggle_view)),
+        );
+        variables.insert("VIEW_TOGGLE_LABEL".to_string(), toggle_label.to_string());
+
         // Generate entries HTML
         let mut entries_html = String::new();
+        let mut gallery_html = String::new();
 
         // Add parent directory link if not at root (as table row)
         if path != "/" && !path.is_empty() {
@@ -573,72 +1135,165 @@ impl TemplateEngine {
                             <span class="name">Back</span>
                         </a>
                     </td>
-                    <td class="size" colspan="2"></td>
-                </tr>"#
+                    <td class="size" colspan="{}"></td>
+                </tr>"#,
+                columns - 1
             ));
         }
 
         // Add file/directory entries with template-based icons
-        for (name, size, date) in entries {
+        for (name, size, date, symlink, metadata) in entries {
             let is_directory = name.ends_with('/');
-            let type_class = if is_directory { "directory" } else { "file" };
             let display_name = if is_directory {
                 name.trim_end_matches('/')
             } else {
                 name
             };
 
-            let icon_svg = if is_directory {
-                FOLDER_ICON_SVG
-            } else {
-                Self::get_file_icon(name)
+            let kind = (!is_directory).then(|| get_file_kind(std::path::Path::new(name)));
+            let (type_class, icon_svg) = match kind {
+                None => ("directory".to_string(), FOLDER_ICON_SVG),
+                Some(kind) => (
+                    format!("file kind-{}", kind.as_str()),
+                    Self::get_file_icon(kind),
+                ),
             };
 
             // Build absolute href using CURRENT_PATH, prefixed with base_path
             let bp = base_path();
             let base_clean = current_path.trim_end_matches('/');
             let href = if base_clean.is_empty() {
-                format!("{}/{}", bp, percent_encode(name))
+                format!("{}/{}", bp, percent_encode_path(Path::new(name)))
             } else {
                 format!(
                     "{}/{}/{}",
                     bp,
                     base_clean.trim_start_matches('/'),
-                    percent_encode(name)
+                    percent_encode_path(Path::new(name))
                 )
             };
 
+            let dir = current_path.trim_matches('/');
+            let entry_path = if dir.is_empty() {
+                format!("/{display_name}")
+            } else {
+                format!("/{dir}/{display_name}")
+            };
+            // Checked entries are downloaded together with "Download selected"
+            let select_html = format!(
+                r#"<input type="checkbox" class="entry-select" value="{}" aria-label="Select {}">"#,
+                html_escape(&entry_path),
+                html_escape(display_name)
+            );
+
+            let actions_html = if file_management {
+                format!(
+                    r#"<td class="actions">
+                        <button type="button" class="btn btn-light btn-sm file-action" data-action="rename" data-path="{path}" data-name="{name}" title="Rename or move">Rename</button>
+                        <button type="button" class="btn btn-light btn-sm file-action" data-action="delete" data-path="{path}" data-name="{name}" title="Delete">Delete</button>
+                    </td>"#,
+                    path = html_escape(&entry_path),
+                    name = html_escape(display_name)
+                )
+            } else {
+                String::new()
+            };
+
+            let symlink_badge = if *symlink {
+                r#"<span class="symlink-badge" title="Symbolic link">↪</span>"#
+            } else {
+                ""
+            };
+
+            // Description and tags saved with an upload
+            let (title_attr, metadata_html) = match metadata {
+                Some(meta) => {
+                    let mut html = String::new();
+                    for tag in &meta.tags {
+                        html.push_str(&format!(
+                            r#"<span class="upload-tag">{}</span>"#,
+                            html_escape(tag)
+                        ));
+                    }
+                    if let Some(description) = &meta.description {
+                        html.push_str(&format!(
+                            r#"<div class="upload-description">{}</div>"#,
+                            html_escape(description)
+                        ));
+                    }
+                    (
+                        meta.description
+                            .as_ref()
+                            .map_or(String::new(), |d| format!(r#" title="{}""#, html_escape(d))),
+                        html,
+                    )
+                }
+                None => (String::new(), String::new()),
+            };
+            // Audio and video open in the player page
+            let metadata_html = if matches!(kind, Some(FileKind::Video | FileKind::Audio)) {
+                format!(
+                    r#"<a href="{}{}{}" class="media-play" title="Play" aria-label="Play {}">▶</a>{metadata_html}"#,
+                    bp,
+                    crate::preview::PREVIEW_SHORT_ROUTE.trim_end_matches('/'),
+                    &href[bp.len()..],
+                    html_escape(display_name)
+                )
+            } else {
+                metadata_html
+            };
+
+            if gallery && kind == Some(FileKind::Image) {
+                gallery_html.push_str(&format!(
+                    r#"<figure class="gallery-item">
+                        {select_html}
+                        <a href="{href}" class="gallery-link"{title_attr}>
+                            <img src="{href}" alt="{name}" loading="lazy" decoding="async">
+                        </a>
+                        <figcaption class="gallery-name">{name}</figcaption>
+                    </figure>"#,
+                    name = html_escape(display_name)
+                ));
+                continue;
+            }
+
             entries_html.push_str(&format!(
                 r#"<tr>
                     <td>
-                        <a href="{}" class="file-link">
+                        {}<a href="{}" class="file-link"{}>
                             <span class="file-type {}">{}</span>
-                            <span class="name">{}</span>
-                        </a>
+                            <span class="name">{}</span>{}
+                        </a>{}
                     </td>
                     <td class="size">{}</td>
                     <td class="date">{}</td>
+                    {}
                 </tr>"#,
+                select_html,
                 href,
+                title_attr,
                 type_class,
                 icon_svg,
                 html_escape(display_name),
+                symlink_badge,
+                metadata_html,
                 size,
-                date
+                date,
+                actions_html
             ));
         }
 
         if total_pages > 1 {
-            let mut pagination_html = String::from(
-                "<tr class=\"pagination-row\"><td colspan=\"3\" style=\"text-align: center; padding: 15px;\">",
+            let mut pagination_html = format!(
+                "<tr class=\"pagination-row\"><td colspan=\"{columns}\" style=\"text-align: center; padding: 15px;\">",
             );
 
             if page > 1 {
-                pagination_html.push_str(&format!("<a href=\"?p={}\" class=\"btn btn-light\" style=\"margin-right: 10px;\">← Previous Page</a>", page - 1));
+                pagination_html.push_str(&format!("<a href=\"{}\" class=\"btn btn-light\" style=\"margin-right: 10px;\">← Previous Page</a>", html_escape(&Self::listing_href(&options.query_for_page(page - 1)))));
             }
             pagination_html.push_str(&format!("<span>Page {} of {}</span>", page, total_pages));
             if page < total_pages {
-                pagination_html.push_str(&format!("<a href=\"?p={}\" class=\"btn btn-light\" style=\"margin-left: 10px;\">Next Page →</a>", page + 1));
+                pagination_html.push_str(&format!("<a href=\"{}\" class=\"btn btn-light\" style=\"margin-left: 10px;\">Next Page →</a>", html_escape(&Self::listing_href(&options.query_for_page(page + 1)))));
             }
 
             pagination_html.push_str("</td></tr>");
@@ -646,17 +1301,47 @@ impl TemplateEngine {
         }
 
         variables.insert("ENTRIES".to_string(), entries_html);
+        variables.insert("GALLERY_ITEMS".to_string(), gallery_html);
 
         // Use the new base template system
         self.render_directory_page(&variables)
     }
 
+    /// Link target for a listing query; an empty query points at the bare directory
+    fn listing_href(query: &str) -> String {
+        if query.is_empty() {
+            "./".to_string()
+        } else {
+            query.to_string()
+        }
+    }
+
     /// Generate logout page HTML using base template system
     pub fn render_logout_page(&self) -> Result<String, AppError> {
         debug!("Rendering logout page");
         let variables = HashMap::new();
         self.render_page("logout_content", "Logged Out", "", "", "", &variables)
     }
+    /// Generate the session login form, returning to `next` afterwards
+    pub fn render_login_page(
+        &self,
+        next: &str,
+        username: &str,
+        error: Option<&str>,
+    ) -> Result<String, AppError> {
+        debug!("Rendering login page");
+        let mut variables = HashMap::new();
+        variables.insert("BASE_PATH".to_string(), html_escape(&base_path()));
+        variables.insert("NEXT".to_string(), html_escape(next));
+        variables.insert("USERNAME".to_string(), html_escape(username));
+        variables.insert("LOGIN_FAILED".to_string(), error.is_some().to_string());
+        variables.insert(
+            "LOGIN_ERROR".to_string(),
+            html_escape(error.unwrap_or_default()),
+        );
+        self.render_page("login_content", "Log In", "", "", "", &variables)
+    }
+
     /// Generate error page HTML using base template system
     pub fn render_error_page(
         &self,
@@ -710,6 +1395,31 @@ impl TemplateEngine {
         )
     }
 
+    /// Render the `/_status` dashboard; the numbers are filled in by its script
+    pub fn render_status_page(&self) -> Result<String, AppError> {
+        debug!("Rendering status dashboard");
+        let page_styles = format!(
+            r#"<link rel="stylesheet" href="{}/_irondrop/static/status/styles.css">"#,
+            base_path()
+        );
+        let page_scripts = format!(
+            r#"<script src="{}/_irondrop/static/status/script.js"></script>"#,
+            base_path()
+        );
+        let back_href = prefixed("/");
+        let header_actions =
+            format!(r#"<a href="{back_href}" class="btn btn-light">← Back to Files</a>"#);
+
+        self.render_page(
+            "status_content",
+            "Status",
+            &page_styles,
+            &page_scripts,
+            &header_actions,
+            &HashMap::new(),
+        )
+    }
+
     /// Get upload form component HTML
     pub fn get_upload_form(&self) -> Result<String, AppError> {
         self.render("upload_form", &HashMap::new())
@@ -752,25 +1462,8 @@ impl TemplateEngine {
     }
 }
 
-/// Simple percent encoding for URLs
-fn percent_encode(input: &str) -> String {
-    input
-        .chars()
-        .map(|c| match c {
-            ' ' => "%20".to_string(),
-            '"' => "%22".to_string(),
-            '#' => "%23".to_string(),
-            '%' => "%25".to_string(),
-            '<' => "%3C".to_string(),
-            '>' => "%3E".to_string(),
-            '?' => "%3F".to_string(),
-            _ => c.to_string(),
-        })
-        .collect()
-}
-
 /// Simple HTML entity escaping
-fn html_escape(input: &str) -> String {
+pub(crate) fn html_escape(input: &str) -> String {
     input
         .replace('&', "&amp;")
         .replace('<', "&lt;")
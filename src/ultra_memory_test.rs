@@ -7,7 +7,10 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::search::{SearchParams, get_ultra_memory_stats, initialize_search, perform_search};
+    use crate::search::{
+        DEFAULT_TRIGRAM_BUDGET, SearchParams, SearchScope, SearchSort, SortOrder,
+        get_ultra_memory_stats, initialize_search, perform_search,
+    };
 
     #[test]
     fn test_memory_efficiency_estimate() {
@@ -95,7 +98,12 @@ mod tests {
         std::fs::write(subdir.join("another_document.pdf"), "another pdf").unwrap();
 
         // Initialize the ultra-low memory search system
-        initialize_search(temp_dir.clone());
+        initialize_search(
+            temp_dir.clone(),
+            false,
+            DEFAULT_TRIGRAM_BUDGET,
+            Default::default(),
+        );
 
         // Give the background indexing thread time to complete
         std::thread::sleep(std::time::Duration::from_millis(100));
@@ -107,6 +115,12 @@ mod tests {
             limit: 10,
             offset: 0,
             case_sensitive: false,
+            sort: SearchSort::Score,
+            order: SortOrder::Desc,
+            scope: SearchScope::Name,
+            mode: Default::default(),
+            filters: Default::default(),
+            mount: None,
         };
 
         let results = perform_search(&temp_dir, &search_params).unwrap();
@@ -131,6 +145,12 @@ mod tests {
             limit: 10,
             offset: 0,
             case_sensitive: false,
+            sort: SearchSort::Score,
+            order: SortOrder::Desc,
+            scope: SearchScope::Name,
+            mode: Default::default(),
+            filters: Default::default(),
+            mount: None,
         };
 
         let nested_results = perform_search(&temp_dir, &nested_search_params).unwrap();
@@ -182,7 +202,12 @@ mod tests {
         println!("\\nCreated test directory with 1500 files");
 
         // Initialize search system
-        initialize_search(temp_dir.clone());
+        initialize_search(
+            temp_dir.clone(),
+            false,
+            DEFAULT_TRIGRAM_BUDGET,
+            Default::default(),
+        );
 
         // Give more time for indexing larger directory
         std::thread::sleep(std::time::Duration::from_millis(500));
@@ -196,6 +221,12 @@ mod tests {
             limit: 100,
             offset: 0,
             case_sensitive: false,
+            sort: SearchSort::Score,
+            order: SortOrder::Desc,
+            scope: SearchScope::Name,
+            mode: Default::default(),
+            filters: Default::default(),
+            mount: None,
         };
 
         let results = perform_search(&temp_dir, &search_params).unwrap();
@@ -230,6 +261,12 @@ mod tests {
                 limit: 50,
                 offset: 0,
                 case_sensitive: false,
+                sort: SearchSort::Score,
+                order: SortOrder::Desc,
+                scope: SearchScope::Name,
+                mode: Default::default(),
+                filters: Default::default(),
+                mount: None,
             };
 
             let pattern_results = perform_search(&temp_dir, &params).unwrap();
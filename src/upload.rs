@@ -8,7 +8,12 @@
 //! - Provides comprehensive security validations
 //! - Supports filename extraction from URL path or headers
 //! - Implements atomic file operations with temporary files
-//! - Includes progress tracking capabilities
+//! - Reports progress for uploads sent with an `X-Upload-Id` header
+//!   (see [`crate::upload_progress`])
+//! - Optionally sorts uploads into `YYYY/MM/DD/` or per-extension folders
+//!   (see [`UploadOrganize`])
+//! - Optionally deduplicates uploads: a file whose SHA-256 matches one already
+//!   in the upload directory becomes a hard link to it instead of a new copy
 //!
 //! # Design Philosophy
 //!
@@ -24,10 +29,12 @@
 //! providing constant memory usage regardless of file size.
 
 use crate::cli::Cli;
+use crate::disk_space::Reservation;
 use crate::error::AppError;
 use crate::http::{Request, RequestBody};
 use crate::response::{HttpResponse, get_mime_type};
 use crate::templates::TemplateEngine;
+use crate::upload_meta::UploadMetadata;
 use glob::Pattern;
 use log::{debug, error, info, trace, warn};
 use std::collections::HashMap;
@@ -35,19 +42,131 @@ use std::env;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 /// Memory threshold: files <= 2MB processed in memory, >2MB streamed to disk
 const MEMORY_THRESHOLD: u64 = 2 * 1024 * 1024; // 2MB
 
 /// Temporary file prefix for atomic operations
-const TEMP_FILE_PREFIX: &str = ".irondrop_temp_";
+pub(crate) const TEMP_FILE_PREFIX: &str = ".irondrop_temp_";
+
+/// Prefix of request bodies spooled to the system temp directory
+pub(crate) const REQUEST_BODY_PREFIX: &str = "irondrop_request_";
+
+/// Age after which a leftover temp file is removed at startup
+pub const ORPHANED_TEMP_MAX_AGE: Duration = Duration::from_secs(60 * 60);
 static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 /// Buffer size for streaming operations
 const STREAM_BUFFER_SIZE: usize = 64 * 1024; // 64KB
 
+/// How long the upload scan command may run before the upload is rejected
+const SCAN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Placeholder in the scan command that is replaced by the file path
+const SCAN_PATH_PLACEHOLDER: &str = "%f";
+
+/// Files looked at while searching the upload directory for a duplicate
+const MAX_DEDUP_CANDIDATES: usize = 10_000;
+
+/// Subfolder for uploads without a usable extension under [`UploadOrganize::Type`]
+const UNTYPED_UPLOAD_DIR: &str = "other";
+
+/// Where uploads land below the target directory (`[upload] organize`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UploadOrganize {
+    /// Directly in the target directory
+    #[default]
+    Flat,
+    /// In `YYYY/MM/DD/` folders for the UTC upload date
+    Date,
+    /// In a folder named after the lowercase file extension
+    Type,
+}
+
+impl UploadOrganize {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "flat" | "none" | "" => Some(Self::Flat),
+            "date" => Some(Self::Date),
+            "type" | "extension" => Some(Self::Type),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Flat => "flat",
+            Self::Date => "date",
+            Self::Type => "type",
+        }
+    }
+
+    /// Relative folder for `filename` uploaded at `now`
+    pub fn subdir(&self, filename: &str, now: SystemTime) -> PathBuf {
+        match self {
+            Self::Flat => PathBuf::new(),
+            Self::Date => {
+                let days = now
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+                    / 86_400;
+                let (year, month, day) = crate::utils::civil_from_days(days as i64);
+                PathBuf::from(format!("{year:04}"))
+                    .join(format!("{month:02}"))
+                    .join(format!("{day:02}"))
+            }
+            Self::Type => {
+                let extension = Path::new(filename)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(str::to_ascii_lowercase)
+                    .filter(|ext| !ext.is_empty() && !crate::utils::is_unusable_file_name(ext))
+                    .unwrap_or_else(|| UNTYPED_UPLOAD_DIR.to_string());
+                PathBuf::from(extension)
+            }
+        }
+    }
+}
+
+/// What happens when an upload's name is already taken (`[upload] conflict`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UploadConflict {
+    /// Save as `name_1.ext`, `name_2.ext`, ...
+    #[default]
+    Rename,
+    /// Replace the existing file
+    Overwrite,
+    /// Refuse the upload with `409 Conflict`
+    Reject,
+    /// Keep the existing file and save as `name.v2.ext`, `name.v3.ext`, ...
+    Version,
+}
+
+impl UploadConflict {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "rename" | "" => Some(Self::Rename),
+            "overwrite" | "replace" => Some(Self::Overwrite),
+            "reject" | "fail" => Some(Self::Reject),
+            "version" => Some(Self::Version),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rename => "rename",
+            Self::Overwrite => "overwrite",
+            Self::Reject => "reject",
+            Self::Version => "version",
+        }
+    }
+}
+
 /// Progress tracking information for uploads
 #[derive(Debug, Clone)]
 pub struct UploadProgress {
@@ -72,6 +191,21 @@ pub enum UploadStage {
     Finalizing,
     /// Upload completed
     Completed,
+    /// Upload was rejected or aborted
+    Failed,
+}
+
+impl UploadStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UploadStage::Receiving => "receiving",
+            UploadStage::Validating => "validating",
+            UploadStage::Writing => "writing",
+            UploadStage::Finalizing => "finalizing",
+            UploadStage::Completed => "completed",
+            UploadStage::Failed => "failed",
+        }
+    }
 }
 
 /// Information about a successfully uploaded file
@@ -89,6 +223,10 @@ pub struct UploadedFile {
     pub mime_type: String,
     /// Whether filename was modified to resolve conflicts
     pub renamed: bool,
+    /// Whether an existing file of the same name was replaced
+    pub overwritten: bool,
+    /// Whether the file is a hard link to an identical existing upload
+    pub deduplicated: bool,
 }
 
 /// Upload operation result
@@ -100,6 +238,8 @@ pub struct UploadResult {
     pub processing_time_ms: u64,
     /// Any warnings during processing
     pub warnings: Vec<String>,
+    /// Description and tags saved with the file
+    pub metadata: Option<UploadMetadata>,
 }
 
 /// Direct upload handler with security and configuration
@@ -112,6 +252,24 @@ pub struct DirectUploadHandler {
     allowed_extensions: Vec<Pattern>,
     /// Whether upload functionality is enabled
     upload_enabled: bool,
+    /// Progress id of the upload being handled, if the client asked for one
+    progress_id: Option<String>,
+    /// Command that must approve every upload before it is moved into place
+    scan_command: Option<String>,
+    /// Folder layout applied below `target_dir`
+    organize: UploadOrganize,
+    /// Subfolder chosen for the upload being handled
+    subdir: PathBuf,
+    /// Hard-link uploads to identical files already in `target_dir`
+    dedup: bool,
+    /// Name conflict strategy for the upload being handled
+    conflict: UploadConflict,
+    /// Whether `X-Conflict-Strategy: overwrite` is honoured
+    allow_overwrite: bool,
+    /// Free bytes to keep on the upload filesystem
+    disk_reserve: u64,
+    /// Name from the request URL (`PUT /<dir>/<name>`), used ahead of any header
+    filename: Option<String>,
 }
 
 impl DirectUploadHandler {
@@ -147,15 +305,108 @@ impl DirectUploadHandler {
             .map_err(AppError::from)?;
 
         let max_upload_bytes = cli.max_upload_size_bytes();
+        let conflict = cli
+            .upload_conflict
+            .as_deref()
+            .and_then(UploadConflict::parse)
+            .unwrap_or_default();
 
         Ok(Self {
             target_dir,
             max_upload_size: max_upload_bytes,
             allowed_extensions,
             upload_enabled: true,
+            progress_id: None,
+            scan_command: cli
+                .upload_scan_command
+                .as_deref()
+                .map(str::trim)
+                .filter(|command| !command.is_empty())
+                .map(str::to_string),
+            organize: cli
+                .upload_organize
+                .as_deref()
+                .and_then(UploadOrganize::parse)
+                .unwrap_or_default(),
+            subdir: PathBuf::new(),
+            dedup: cli.upload_dedup.unwrap_or(false),
+            conflict,
+            allow_overwrite: conflict == UploadConflict::Overwrite
+                || cli.enable_file_management.unwrap_or(false),
+            disk_reserve: cli.upload_disk_reserve.unwrap_or(0),
+            filename: None,
         })
     }
 
+    /// Directory uploads are written to
+    pub(crate) fn target_dir(&self) -> &Path {
+        &self.target_dir
+    }
+
+    /// Pick (and create) the organize subfolder for `filename`.
+    /// Later file placement and name conflict checks happen inside it.
+    pub(crate) fn organize_for(&mut self, filename: &str) -> Result<(), AppError> {
+        self.subdir = self.organize.subdir(filename, SystemTime::now());
+        if !self.subdir.as_os_str().is_empty() {
+            let dir = self.destination_dir();
+            fs::create_dir_all(&dir).map_err(|e| {
+                error!("Failed to create upload folder {dir:?}: {e}");
+                AppError::from(e)
+            })?;
+            debug!("Organizing upload into {}", dir.display());
+        }
+        Ok(())
+    }
+
+    /// Conflict strategy for `request`, honouring an `X-Conflict-Strategy` header.
+    /// Unknown values are a bad request; `overwrite` needs the configured
+    /// strategy to be `overwrite` or file management to be enabled.
+    pub(crate) fn conflict_for(&self, request: &Request) -> Result<UploadConflict, AppError> {
+        let Some(value) = request.headers.get("x-conflict-strategy") else {
+            return Ok(self.conflict);
+        };
+        let Some(conflict) = UploadConflict::parse(value) else {
+            debug!("Unknown X-Conflict-Strategy '{value}'");
+            return Err(AppError::BadRequest);
+        };
+        if conflict == UploadConflict::Overwrite && !self.allow_overwrite {
+            warn!("Upload asked to overwrite existing files, which is not allowed");
+            return Err(AppError::Forbidden);
+        }
+        trace!("Conflict strategy for this upload: {}", conflict.as_str());
+        Ok(conflict)
+    }
+
+    /// Use `conflict` for name conflicts of the upload being handled
+    pub(crate) fn set_conflict(&mut self, conflict: UploadConflict) {
+        self.conflict = conflict;
+    }
+
+    /// Save the upload as `filename` whatever the request headers say
+    pub(crate) fn set_filename(&mut self, filename: String) {
+        self.filename = Some(filename);
+    }
+
+    /// Directory the current upload is moved into
+    pub(crate) fn destination_dir(&self) -> PathBuf {
+        self.target_dir.join(&self.subdir)
+    }
+
+    /// Path of `saved_name` relative to `target_dir`, with `/` separators
+    pub(crate) fn relative_upload_path(&self, saved_name: &str) -> String {
+        self.subdir
+            .iter()
+            .map(|part| part.to_string_lossy())
+            .chain(std::iter::once(saved_name.into()))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Maximum accepted upload size in bytes
+    pub(crate) fn max_upload_size(&self) -> u64 {
+        self.max_upload_size
+    }
+
     /// Detect the OS-specific download directory
     pub fn detect_os_download_directory() -> Result<PathBuf, AppError> {
         let download_dir = if cfg!(target_os = "windows") {
@@ -233,7 +484,11 @@ impl DirectUploadHandler {
             request.method, request.path
         );
 
+        self.progress_id = crate::upload_progress::upload_id(&request.headers);
         let result = self.handle_upload(request, stats);
+        if let Some(id) = self.progress_id.take() {
+            crate::upload_progress::tracker().finish(&id, result.is_ok());
+        }
 
         // If there was an error, record failure statistics
         if result.is_err() {
@@ -334,6 +589,8 @@ impl DirectUploadHandler {
         );
         trace!("Upload size validation passed");
 
+        self.report_stage(UploadStage::Validating, 0);
+
         // Extract filename from URL path or Content-Disposition header
         let filename = self.extract_filename(request)?;
         debug!("Extracted filename: '{}'", filename);
@@ -348,11 +605,28 @@ impl DirectUploadHandler {
         debug!("Filename validation passed");
         trace!("File extension validation passed");
 
-        // Check available disk space
-        debug!("Checking disk space for {} bytes", body_size);
-        self.check_disk_space(body_size)?;
+        // Reject bad metadata before anything is written
+        let metadata = UploadMetadata::from_request(request)?;
+        self.conflict = self.conflict_for(request)?;
+
+        // Check available disk space; a spooled body on the same filesystem
+        // only needs to be renamed into place
+        let required = match body {
+            RequestBody::File { path, .. }
+                if crate::disk_space::same_filesystem(path, &self.target_dir) =>
+            {
+                0
+            }
+            _ => body_size,
+        };
+        debug!("Checking disk space for {} bytes", required);
+        let _reservation = self.check_disk_space(required)?;
         debug!("Disk space check passed");
 
+        self.organize_for(&filename)?;
+
+        self.report_stage(UploadStage::Writing, 0);
+
         // Process upload based on body type and size
         let uploaded_file = if body_size <= MEMORY_THRESHOLD {
             debug!(
@@ -377,10 +651,21 @@ impl DirectUploadHandler {
             uploaded_file.saved_path.display()
         );
 
+        let mut warnings = Vec::new();
+        if let Some(meta) = &metadata {
+            if meta.save(&uploaded_file.saved_path).is_err() {
+                warnings.push("Description and tags could not be saved".to_string());
+            }
+        } else if uploaded_file.overwritten {
+            // The replaced file's description no longer applies
+            crate::upload_meta::remove_sidecar(&uploaded_file.saved_path);
+        }
+
         let upload_result = UploadResult {
             uploaded_file,
             processing_time_ms: processing_time,
-            warnings: Vec::new(),
+            warnings,
+            metadata,
         };
 
         // Record successful upload statistics
@@ -395,12 +680,35 @@ impl DirectUploadHandler {
             stats.finish_upload();
         }
 
+        let saved = &upload_result.uploaded_file;
+        crate::hooks::emit(
+            crate::hooks::HookEvent::UploadCompleted,
+            &[
+                ("name", saved.saved_name.as_str().into()),
+                ("size", saved.size.into()),
+                ("mime_type", saved.mime_type.as_str().into()),
+                ("renamed", saved.renamed.into()),
+                ("deduplicated", saved.deduplicated.into()),
+                ("client", crate::hooks::client_of(request).into()),
+            ],
+        );
+        crate::audit::record(
+            crate::audit::AuditEvent::Upload,
+            crate::audit::AuditRecord::for_request(request, "success")
+                .with_path(saved.saved_path.display().to_string())
+                .with("size", saved.size),
+        );
+
         // Generate appropriate response based on Accept header
         self.generate_upload_response(request, upload_result)
     }
 
     /// Extract filename from URL path or headers
-    fn extract_filename(&self, request: &Request) -> Result<String, AppError> {
+    pub(crate) fn extract_filename(&self, request: &Request) -> Result<String, AppError> {
+        if let Some(filename) = &self.filename {
+            return Ok(filename.clone());
+        }
+
         // First, try to get filename from Content-Disposition header
         if let Some(content_disposition) = request.headers.get("content-disposition")
             && let Some(filename) = Self::parse_filename_from_disposition(content_disposition)
@@ -416,7 +724,7 @@ impl DirectUploadHandler {
         }
 
         // Finally, extract from URL path (last segment after /, excluding query params)
-        let path_without_query = request.path.split('?').next().unwrap_or(&request.path);
+        let path_without_query = request.path.as_str();
         let path_segments: Vec<&str> = path_without_query.split('/').collect();
         if let Some(last_segment) = path_segments.last()
             && !last_segment.is_empty()
@@ -466,13 +774,14 @@ impl DirectUploadHandler {
             }
         };
 
-        // Generate unique filename to avoid conflicts
+        // Resolve a name conflict with the configured strategy
         let (final_filename, was_renamed) = self.generate_unique_filename(filename)?;
+        let overwritten = self.destination_dir().join(&final_filename).exists();
         debug!(
-            "Generated filename: '{}' (renamed: {})",
-            final_filename, was_renamed
+            "Generated filename: '{}' (renamed: {}, overwriting: {})",
+            final_filename, was_renamed, overwritten
         );
-        let target_path = self.target_dir.join(&final_filename);
+        let target_path = self.destination_dir().join(&final_filename);
         trace!("Target path: {}", target_path.display());
 
         // Create temporary file for atomic write
@@ -487,7 +796,8 @@ impl DirectUploadHandler {
             data.len(),
             TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
         );
-        let temp_path = self.target_dir.join(&temp_filename);
+        // Next to the destination, so the final rename stays on one filesystem
+        let temp_path = self.destination_dir().join(&temp_filename);
 
         // Write data to temporary file
         debug!(
@@ -518,14 +828,23 @@ impl DirectUploadHandler {
             })?;
         }
 
-        // Atomically rename temporary file to final location
-        debug!("Atomically moving temporary file to final location");
-        fs::rename(&temp_path, &target_path).map_err(|e| {
-            error!("Failed to rename {temp_path:?} to {target_path:?}: {e}");
-            let _ = fs::remove_file(&temp_path); // Cleanup on error
-            AppError::from(e)
-        })?;
-        trace!("File successfully moved to: {}", target_path.display());
+        if let Err(e) = self.scan_file(&temp_path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        let deduplicated = self.link_duplicate(&temp_path, &target_path, data.len() as u64);
+        if !deduplicated {
+            // Atomically rename temporary file to final location
+            debug!("Atomically moving temporary file to final location");
+            fs::rename(&temp_path, &target_path).map_err(|e| {
+                error!("Failed to rename {temp_path:?} to {target_path:?}: {e}");
+                let _ = fs::remove_file(&temp_path); // Cleanup on error
+                AppError::from(e)
+            })?;
+            trace!("File successfully moved to: {}", target_path.display());
+        }
+        sync_parent_dir(&target_path)?;
 
         // Determine MIME type
         let mime_type = get_mime_type(&target_path).to_string();
@@ -545,6 +864,8 @@ impl DirectUploadHandler {
             size: data.len() as u64,
             mime_type,
             renamed: was_renamed,
+            overwritten,
+            deduplicated,
         })
     }
 
@@ -580,18 +901,44 @@ impl DirectUploadHandler {
             filename
         );
 
-        // Generate unique filename to avoid conflicts
+        // Resolve a name conflict with the configured strategy
         let (final_filename, was_renamed) = self.generate_unique_filename(filename)?;
+        let overwritten = self.destination_dir().join(&final_filename).exists();
         debug!(
-            "Generated filename: '{}' (renamed: {})",
-            final_filename, was_renamed
+            "Generated filename: '{}' (renamed: {}, overwriting: {})",
+            final_filename, was_renamed, overwritten
         );
-        let target_path = self.target_dir.join(&final_filename);
+        let target_path = self.destination_dir().join(&final_filename);
         trace!("Target path: {}", target_path.display());
 
+        if let Err(e) = self.scan_file(source_path) {
+            let _ = fs::remove_file(source_path);
+            return Err(e);
+        }
+
+        let source_size = match known_size {
+            Some(size) => size,
+            None => fs::metadata(source_path)?.len(),
+        };
+        if self.link_duplicate(source_path, &target_path, source_size) {
+            sync_parent_dir(&target_path)?;
+            return Ok(UploadedFile {
+                original_name: filename.to_string(),
+                saved_name: final_filename,
+                mime_type: get_mime_type(&target_path).to_string(),
+                saved_path: target_path,
+                size: source_size,
+                renamed: was_renamed,
+                overwritten,
+                deduplicated: true,
+            });
+        }
+
         // Fast path: reuse the request-body temp file when the target lives on the same filesystem.
+        // The request body was synced when it was spooled.
         match fs::rename(source_path, &target_path) {
             Ok(()) => {
+                sync_parent_dir(&target_path)?;
                 let file_size = known_size.unwrap_or_else(|| {
                     fs::metadata(&target_path)
                         .map(|m| m.len())
@@ -611,6 +958,8 @@ impl DirectUploadHandler {
                     size: file_size,
                     mime_type,
                     renamed: was_renamed,
+                    overwritten,
+                    deduplicated: false,
                 });
             }
             Err(err) => {
@@ -638,7 +987,8 @@ impl DirectUploadHandler {
             source_path.to_string_lossy().len(),
             TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
         );
-        let temp_path = self.target_dir.join(&temp_filename);
+        // Next to the destination, so the final rename stays on one filesystem
+        let temp_path = self.destination_dir().join(&temp_filename);
 
         // Stream copy from source to temporary file
         debug!(
@@ -691,6 +1041,9 @@ impl DirectUploadHandler {
                 // Log progress for large files
                 if total_bytes.is_multiple_of(1024 * 1024) || total_bytes < 1024 * 1024 {
                     trace!("Streamed {} bytes so far", total_bytes);
+                    if let Some(id) = &self.progress_id {
+                        crate::upload_progress::tracker().processed(id, total_bytes);
+                    }
                 }
 
                 // Check size limit during streaming
@@ -728,6 +1081,7 @@ impl DirectUploadHandler {
 
             total_bytes
         };
+        self.report_stage(UploadStage::Finalizing, file_size);
 
         // Atomically rename temporary file to final location
         fs::rename(&temp_path, &target_path).map_err(|e| {
@@ -735,6 +1089,7 @@ impl DirectUploadHandler {
             let _ = fs::remove_file(&temp_path); // Cleanup on error
             AppError::from(e)
         })?;
+        sync_parent_dir(&target_path)?;
 
         // Determine MIME type
         let mime_type = get_mime_type(&target_path).to_string();
@@ -753,14 +1108,197 @@ impl DirectUploadHandler {
             size: file_size,
             mime_type,
             renamed: was_renamed,
+            overwritten,
+            deduplicated: false,
         })
     }
 
-    /// Check available disk space
-    fn check_disk_space(&self, required_bytes: u64) -> Result<(), AppError> {
-        // Simple heuristic: Check if we can create a test file
-        // In a production system, you might use platform-specific APIs to get actual disk space
+    /// Run the configured scan command against a finished upload.
+    ///
+    /// The command is split on whitespace and run without a shell; every `%f`
+    /// argument is replaced by `path` (or `path` is appended when there is none).
+    /// Any non-zero exit, a failure to start the command or a timeout rejects
+    /// the upload, so a broken scanner never lets files through.
+    pub(crate) fn scan_file(&self, path: &Path) -> Result<(), AppError> {
+        let Some(command) = &self.scan_command else {
+            return Ok(());
+        };
+        self.report_stage(UploadStage::Validating, 0);
+
+        let mut parts = command.split_whitespace();
+        let Some(program) = parts.next() else {
+            return Ok(());
+        };
+        let mut args: Vec<std::ffi::OsString> = Vec::new();
+        let mut has_placeholder = false;
+        for part in parts {
+            if part == SCAN_PATH_PLACEHOLDER {
+                has_placeholder = true;
+                args.push(path.as_os_str().to_os_string());
+            } else {
+                args.push(part.into());
+            }
+        }
+        if !has_placeholder {
+            args.push(path.as_os_str().to_os_string());
+        }
+
+        debug!("Scanning upload {} with '{program}'", path.display());
+        let mut child = Command::new(program)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                error!("Failed to start upload scan command '{program}': {e}");
+                AppError::upload_rejected(format!("scan command could not be started: {e}"))
+            })?;
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if started.elapsed() >= SCAN_TIMEOUT => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    warn!(
+                        "Upload scan of {} timed out after {}s",
+                        path.display(),
+                        SCAN_TIMEOUT.as_secs()
+                    );
+                    return Err(AppError::upload_rejected("scan timed out"));
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+                Err(e) => {
+                    let _ = child.kill();
+                    error!("Failed to wait for upload scan command '{program}': {e}");
+                    return Err(AppError::upload_rejected(format!(
+                        "scan command failed: {e}"
+                    )));
+                }
+            }
+        };
+
+        if status.success() {
+            trace!("Upload scan passed for {}", path.display());
+            Ok(())
+        } else {
+            warn!("Upload scan rejected {} ({status})", path.display());
+            Err(AppError::upload_rejected(status.to_string()))
+        }
+    }
+
+    /// With dedup enabled, publish `staged` as a hard link to an identical file
+    /// already in the upload directory and remove `staged`.
+    ///
+    /// Candidates are files of the same size (symlinks are skipped); their
+    /// SHA-256 digests come from [`ChecksumCache`](crate::checksum::ChecksumCache).
+    /// Returns `false` when dedup is off, nothing matches or linking fails, in
+    /// which case the caller writes the file as usual.
+    pub(crate) fn link_duplicate(&self, staged: &Path, target: &Path, size: u64) -> bool {
+        use crate::checksum::{ChecksumAlgorithm, ChecksumCache, checksum_reader};
+
+        if !self.dedup || size == 0 {
+            return false;
+        }
+        let candidates = self.same_size_files(size);
+        if candidates.is_empty() {
+            trace!("No dedup candidates of {size} bytes");
+            return false;
+        }
+        let digest = match File::open(staged)
+            .and_then(|file| checksum_reader(file, ChecksumAlgorithm::Sha256))
+        {
+            Ok(digest) => digest,
+            Err(e) => {
+                warn!("Failed to hash upload {} for dedup: {e}", staged.display());
+                return false;
+            }
+        };
+
+        for candidate in candidates {
+            let matches = ChecksumCache::global()
+                .checksum(&candidate, ChecksumAlgorithm::Sha256)
+                .is_ok_and(|(existing, _)| existing == digest);
+            if !matches {
+                continue;
+            }
+            match fs::hard_link(&candidate, target) {
+                Ok(()) => {
+                    let _ = fs::remove_file(staged);
+                    info!(
+                        "Deduplicated upload {} as a link to {}",
+                        target.display(),
+                        candidate.display()
+                    );
+                    return true;
+                }
+                Err(e) => {
+                    debug!(
+                        "Could not link {} to {}: {e}; storing a copy",
+                        target.display(),
+                        candidate.display()
+                    );
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    /// Regular files of exactly `size` bytes below `target_dir`
+    fn same_size_files(&self, size: u64) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        let mut pending = vec![self.target_dir.clone()];
+        let mut visited = 0usize;
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                visited += 1;
+                if visited > MAX_DEDUP_CANDIDATES {
+                    debug!("Dedup search stopped after {MAX_DEDUP_CANDIDATES} entries");
+                    return found;
+                }
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue; // temp files, .space_test and other hidden files
+                }
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    pending.push(entry.path());
+                } else if file_type.is_file()
+                    && entry
+                        .metadata()
+                        .is_ok_and(|metadata| metadata.len() == size)
+                {
+                    found.push(entry.path());
+                }
+            }
+        }
+        found
+    }
+
+    fn report_stage(&self, stage: UploadStage, processed: u64) {
+        if let Some(id) = &self.progress_id {
+            crate::upload_progress::tracker().set_stage(id, stage, processed);
+        }
+    }
 
+    /// Check that `required_bytes` fit on the upload filesystem, keeping the
+    /// configured reserve free, and hold them until the returned reservation
+    /// is dropped
+    pub(crate) fn check_disk_space(&self, required_bytes: u64) -> Result<Reservation, AppError> {
+        if let Some(reservation) =
+            crate::disk_space::reserve(&self.target_dir, self.disk_reserve, required_bytes)?
+        {
+            return Ok(reservation);
+        }
+
+        // Free space is unknown on this platform: check if we can create a test file
         let test_size = std::cmp::min(required_bytes / 100, 1024 * 1024); // Test with 1% or max 1MB
         let test_path = self.target_dir.join(".space_test");
 
@@ -775,7 +1313,7 @@ impl DirectUploadHandler {
                 match file.write_all(&test_data) {
                     Ok(_) => {
                         let _ = fs::remove_file(&test_path); // Cleanup
-                        Ok(())
+                        Ok(Reservation::default())
                     }
                     Err(_) => {
                         let _ = fs::remove_file(&test_path); // Cleanup
@@ -788,53 +1326,12 @@ impl DirectUploadHandler {
     }
 
     /// Validate filename for security
-    fn validate_filename(&self, filename: &str) -> Result<(), AppError> {
-        if filename.is_empty() {
-            return Err(AppError::invalid_filename("Empty filename"));
-        }
-
-        if filename.len() > 255 {
-            return Err(AppError::invalid_filename("Filename too long"));
-        }
-
-        // Check for path traversal attempts
-        if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
-            return Err(AppError::invalid_filename(filename));
-        }
-
-        // Check for dangerous characters
-        let dangerous_chars = ['<', '>', ':', '"', '|', '?', '*'];
-        if filename
-            .chars()
-            .any(|c| dangerous_chars.contains(&c) || c.is_control())
-        {
-            return Err(AppError::invalid_filename(filename));
-        }
-
-        // Check for Windows reserved names (case-insensitive)
-        let base_name = if let Some(dot_pos) = filename.rfind('.') {
-            &filename[..dot_pos]
-        } else {
-            filename
-        };
-
-        let reserved_names = [
-            "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7",
-            "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
-        ];
-
-        if reserved_names
-            .iter()
-            .any(|&reserved| base_name.eq_ignore_ascii_case(reserved))
-        {
-            return Err(AppError::invalid_filename(filename));
-        }
-
-        Ok(())
+    pub(crate) fn validate_filename(&self, filename: &str) -> Result<(), AppError> {
+        validate_filename(filename)
     }
 
     /// Validate file extension against allowed patterns
-    fn validate_file_extension(&self, filename: &str) -> Result<(), AppError> {
+    pub(crate) fn validate_file_extension(&self, filename: &str) -> Result<(), AppError> {
         if self.allowed_extensions.is_empty() {
             return Ok(()); // No restrictions
         }
@@ -859,16 +1356,37 @@ impl DirectUploadHandler {
         Ok(())
     }
 
-    /// Generate a unique filename to avoid conflicts
-    fn generate_unique_filename(&self, original: &str) -> Result<(String, bool), AppError> {
+    /// Pick the name to save `original` under, following the conflict strategy.
+    ///
+    /// Returns the name and whether it differs from `original`. A free name is
+    /// always used as is; `reject` fails with `409 Conflict` when it is taken,
+    /// and `overwrite` keeps it (but never replaces a directory).
+    pub(crate) fn generate_unique_filename(
+        &self,
+        original: &str,
+    ) -> Result<(String, bool), AppError> {
+        let target_dir = self.destination_dir();
+
         // Try the original filename first by checking if it exists
-        let target_path = self.target_dir.join(original);
+        let target_path = target_dir.join(original);
 
         // Check if file exists without creating it
         if !target_path.exists() {
             return Ok((original.to_string(), false));
         }
 
+        match self.conflict {
+            UploadConflict::Overwrite if !target_path.is_dir() => {
+                debug!("Replacing existing file {}", target_path.display());
+                return Ok((original.to_string(), false));
+            }
+            UploadConflict::Reject | UploadConflict::Overwrite => {
+                info!("Upload '{original}' rejected: the name is already taken");
+                return Err(AppError::upload_conflict(original));
+            }
+            UploadConflict::Rename | UploadConflict::Version => {}
+        }
+
         // File exists, generate a unique name
         let path = Path::new(original);
         let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
@@ -878,9 +1396,14 @@ impl DirectUploadHandler {
             .map(|ext| format!(".{ext}"))
             .unwrap_or_default();
 
-        for i in 1..=9999 {
-            let new_filename = format!("{stem}_{i}{extension}");
-            let new_path = self.target_dir.join(&new_filename);
+        // The existing file counts as the first version
+        let (separator, first) = match self.conflict {
+            UploadConflict::Version => (".v", 2),
+            _ => ("_", 1),
+        };
+        for i in first..=9999 {
+            let new_filename = format!("{stem}{separator}{i}{extension}");
+            let new_path = target_dir.join(&new_filename);
 
             // Check if this filename is available
             if !new_path.exists() {
@@ -909,13 +1432,43 @@ impl DirectUploadHandler {
         let wants_json = accept_header.contains("application/json")
             || request.headers.contains_key("x-requested-with");
 
-        if wants_json {
+        // `PUT /<dir>/<name>` (e.g. `curl -T`) answers 201 with a plain text
+        // line unless JSON is asked for
+        if request.method == "PUT" {
+            let mut response = if wants_json {
+                self.generate_json_response(result)?
+            } else {
+                self.generate_text_response(result)
+            };
+            response.status_code = 201;
+            response.status_text = "Created".to_string();
+            Ok(response)
+        } else if wants_json {
             self.generate_json_response(result)
         } else {
             self.generate_html_response(result)
         }
     }
 
+    /// Generate a one-line confirmation for command-line clients
+    fn generate_text_response(&self, result: UploadResult) -> HttpResponse {
+        let file = &result.uploaded_file;
+        let mut line = format!(
+            "Uploaded {} ({} bytes)",
+            self.relative_upload_path(&file.saved_name),
+            file.size
+        );
+        if file.renamed {
+            line.push_str(&format!(", renamed from {}", file.original_name));
+        }
+        for warning in &result.warnings {
+            line.push_str(&format!("\nWarning: {warning}"));
+        }
+        line.push('\n');
+
+        HttpResponse::new(200, "OK").with_file_body(line.into_bytes(), "text/plain; charset=utf-8")
+    }
+
     /// Generate JSON response for API clients
     fn generate_json_response(&self, result: UploadResult) -> Result<HttpResponse, AppError> {
         let file = &result.uploaded_file;
@@ -926,22 +1479,39 @@ impl DirectUploadHandler {
     "message": "Upload completed successfully",
     "file": {{
         "name": "{}",
+        "path": "{}",
         "originalName": "{}",
         "size": {},
         "mimeType": "{}",
-        "renamed": {}
+        "renamed": {},
+        "overwritten": {},
+        "deduplicated": {},
+        "metadata": {}
     }},
     "statistics": {{
         "processingTimeMs": {}
     }},
-    "warnings": []
+    "warnings": [{}]
 }}"#,
             file.saved_name,
+            crate::utils::json_escape(&self.relative_upload_path(&file.saved_name)),
             file.original_name,
             file.size,
             file.mime_type,
             file.renamed,
-            result.processing_time_ms
+            file.overwritten,
+            file.deduplicated,
+            result
+                .metadata
+                .as_ref()
+                .map_or("null".to_string(), UploadMetadata::to_json),
+            result.processing_time_ms,
+            result
+                .warnings
+                .iter()
+                .map(|warning| format!(r#""{}""#, crate::utils::json_escape(warning)))
+                .collect::<Vec<_>>()
+                .join(",")
         );
 
         Ok(HttpResponse::new(200, "OK")
@@ -965,7 +1535,7 @@ impl DirectUploadHandler {
 
         let files_list = format!(
             r"<li><strong>{}</strong>{} - {} bytes</li>",
-            file.saved_name,
+            self.relative_upload_path(&file.saved_name),
             rename_note,
             format_bytes(file.size)
         );
@@ -1010,11 +1580,109 @@ impl DirectUploadHandler {
             "memory_threshold_mb".to_string(),
             (MEMORY_THRESHOLD / 1024 / 1024).to_string(),
         );
+        info.insert("organize".to_string(), self.organize.as_str().to_string());
         info
     }
 }
 
 /// Format bytes into human-readable format
+/// Flush the directory holding `path`, so a file just renamed or linked into
+/// place is still there after a crash. Filesystems that cannot sync
+/// directories are skipped.
+pub(crate) fn sync_parent_dir(path: &Path) -> Result<(), AppError> {
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        if let Err(e) = File::open(parent).and_then(|dir| dir.sync_all()) {
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::InvalidInput | std::io::ErrorKind::Unsupported
+            ) {
+                trace!("Directory sync not supported for {}: {e}", parent.display());
+            } else {
+                error!("Failed to sync directory {parent:?}: {e}");
+                return Err(AppError::from(e));
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Delete `.irondrop_temp_*` files below `dir`, and spooled request bodies in
+/// the system temp directory, last modified more than `max_age` ago.
+///
+/// Uploads stage data in such files and rename them into place, so any left
+/// behind after a crash are orphans; the age limit spares the ones of another
+/// server still working on the same directory. Symlinks are not followed.
+/// Returns how many files were removed.
+pub fn remove_orphaned_temp_files(dir: &Path, max_age: Duration) -> usize {
+    let is_stale = |entry: &fs::DirEntry| {
+        entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age)
+    };
+    let mut removed = 0usize;
+    let mut remove = |entry: &fs::DirEntry| match fs::remove_file(entry.path()) {
+        Ok(()) => {
+            debug!("Removed orphaned temp file {}", entry.path().display());
+            removed += 1;
+        }
+        Err(e) => warn!(
+            "Failed to remove orphaned temp file {}: {e}",
+            entry.path().display()
+        ),
+    };
+
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file()
+                && entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(TEMP_FILE_PREFIX)
+                && is_stale(&entry)
+            {
+                remove(&entry);
+            }
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(env::temp_dir()) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(REQUEST_BODY_PREFIX)
+                && name.ends_with(".tmp")
+                && entry.file_type().is_ok_and(|t| t.is_file())
+                && is_stale(&entry)
+            {
+                remove(&entry);
+            }
+        }
+    }
+
+    if removed > 0 {
+        info!("🧹 Removed {removed} orphaned upload temp file(s)");
+    }
+    removed
+}
+
 fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
@@ -1032,6 +1700,42 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
+/// Reject empty, over-long, traversal, dangerous-character and Windows reserved
+/// filenames, plus on Windows names ending in a dot or space
+pub(crate) fn validate_filename(filename: &str) -> Result<(), AppError> {
+    if filename.is_empty() {
+        return Err(AppError::invalid_filename("Empty filename"));
+    }
+
+    if filename.len() > 255 {
+        return Err(AppError::invalid_filename("Filename too long"));
+    }
+
+    // Check for path traversal attempts
+    if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
+        return Err(AppError::invalid_filename(filename));
+    }
+
+    // Check for dangerous characters
+    let dangerous_chars = ['<', '>', ':', '"', '|', '?', '*'];
+    if filename
+        .chars()
+        .any(|c| dangerous_chars.contains(&c) || c.is_control())
+    {
+        return Err(AppError::invalid_filename(filename));
+    }
+
+    // Windows reserved names are refused everywhere so the folder stays
+    // portable; trailing dots and spaces only break Windows itself
+    if crate::utils::is_windows_reserved_name(filename)
+        || crate::utils::is_unusable_file_name(filename)
+    {
+        return Err(AppError::invalid_filename(filename));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1061,6 +1765,108 @@ mod tests {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         }
     }
 
@@ -1113,6 +1919,33 @@ mod tests {
         );
         assert!(handler.validate_filename("file<with>brackets.txt").is_err());
         assert!(handler.validate_filename("").is_err());
+
+        // Windows devices, whatever the extension
+        for name in [
+            "CON",
+            "prn.txt",
+            "Aux.tar.gz",
+            "nul .txt",
+            "COM1.log",
+            "lpt\u{b9}",
+            "conin$",
+        ] {
+            assert!(handler.validate_filename(name).is_err(), "{name}");
+        }
+        for name in [
+            "CONSOLE.txt",
+            "com10.txt",
+            "auxiliary.md",
+            "prn_report.pdf",
+            "thesis.aux",
+        ] {
+            assert!(handler.validate_filename(name).is_ok(), "{name}");
+        }
+        assert_eq!(
+            handler.validate_filename("report.txt.").is_err(),
+            cfg!(windows)
+        );
+        assert_eq!(handler.validate_filename("notes ").is_err(), cfg!(windows));
     }
 
     #[test]
@@ -1137,6 +1970,93 @@ mod tests {
         assert!(!renamed);
     }
 
+    #[test]
+    fn test_conflict_strategies() {
+        let temp_dir = TempDir::new().unwrap();
+        let cli = create_test_cli(temp_dir.path().to_path_buf());
+        let mut handler = DirectUploadHandler::new(&cli).unwrap();
+        fs::write(temp_dir.path().join("report.pdf"), "v1").unwrap();
+        fs::write(temp_dir.path().join("report.v2.pdf"), "v2").unwrap();
+        fs::create_dir(temp_dir.path().join("folder")).unwrap();
+
+        handler.set_conflict(UploadConflict::Version);
+        assert_eq!(
+            handler.generate_unique_filename("report.pdf").unwrap(),
+            ("report.v3.pdf".to_string(), true)
+        );
+
+        handler.set_conflict(UploadConflict::Overwrite);
+        assert_eq!(
+            handler.generate_unique_filename("report.pdf").unwrap(),
+            ("report.pdf".to_string(), false)
+        );
+        assert!(matches!(
+            handler.generate_unique_filename("folder"),
+            Err(AppError::UploadConflict(_))
+        ));
+
+        handler.set_conflict(UploadConflict::Reject);
+        assert!(matches!(
+            handler.generate_unique_filename("report.pdf"),
+            Err(AppError::UploadConflict(_))
+        ));
+        assert_eq!(
+            handler.generate_unique_filename("new.pdf").unwrap(),
+            ("new.pdf".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn test_upload_organize_subdirs() {
+        // 2024-02-29 12:00:00 UTC
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_208_000);
+        assert_eq!(UploadOrganize::Flat.subdir("a.pdf", now), PathBuf::new());
+        assert_eq!(
+            UploadOrganize::Date.subdir("a.pdf", now),
+            PathBuf::from("2024/02/29")
+        );
+        assert_eq!(
+            UploadOrganize::Type.subdir("Report.PDF", now),
+            PathBuf::from("pdf")
+        );
+        assert_eq!(
+            UploadOrganize::Type.subdir("README", now),
+            PathBuf::from("other")
+        );
+        assert_eq!(UploadOrganize::parse(" DATE "), Some(UploadOrganize::Date));
+        assert_eq!(UploadOrganize::parse("weekly"), None);
+    }
+
+    #[test]
+    fn test_remove_orphaned_temp_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        let stale = |path: &Path| {
+            fs::write(path, "partial").unwrap();
+            File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(an_hour_ago)
+                .unwrap();
+        };
+        stale(&temp_dir.path().join(".irondrop_temp_1_a.txt"));
+        stale(&nested.join(".irondrop_temp_2_b.txt"));
+        stale(&temp_dir.path().join("keep.txt"));
+        fs::write(nested.join(".irondrop_temp_3_c.txt"), "in flight").unwrap();
+
+        // Stale request bodies in the system temp directory may add to the count
+        assert!(remove_orphaned_temp_files(temp_dir.path(), Duration::from_secs(60)) >= 2);
+        assert!(!temp_dir.path().join(".irondrop_temp_1_a.txt").exists());
+        assert!(temp_dir.path().join("keep.txt").exists());
+        assert!(nested.join(".irondrop_temp_3_c.txt").exists());
+        assert!(!nested.join(".irondrop_temp_2_b.txt").exists());
+
+        sync_parent_dir(&temp_dir.path().join("keep.txt")).unwrap();
+    }
+
     #[test]
     fn test_format_bytes() {
         assert_eq!(format_bytes(0), "0 B");
@@ -1189,11 +2109,14 @@ mod tests {
         let request = Request {
             method: "POST".to_string(),
             path: "/perf-upload.bin".to_string(),
+            query: None,
             headers: HashMap::new(),
             body: Some(RequestBody::File {
                 path: request_temp,
                 size: 16 * 1024 * 1024,
             }),
+            peer_addr: None,
+            forwarded_by: None,
         };
 
         let start = std::time::Instant::now();
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: MIT
+
+//! Descriptions and tags attached to direct uploads
+//!
+//! `POST /_irondrop/upload` accepts optional metadata alongside the file:
+//!
+//! - `X-Upload-Description` header or `?description=` - free text
+//! - `X-Upload-Tags` header or `?tags=` - comma-separated tags
+//!
+//! (`X-Upload-To` works like `?upload_to=` for the target folder.) Metadata is
+//! validated before anything is written and saved next to the file as a
+//! hidden sidecar, `.<name>.meta.json`, holding a flat JSON object such as
+//! `{"description":"Q3 numbers","tags":"finance,2024"}`. Listings and the
+//! JSON listing show it; sidecars themselves are never listed, and they
+//! follow their file when it is renamed or deleted through file management.
+
+use crate::error::AppError;
+use crate::http::Request;
+use crate::utils::{json_escape, parse_json_string_map};
+use log::{debug, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest accepted description, in characters
+pub const MAX_DESCRIPTION_CHARS: usize = 1024;
+
+/// Most tags accepted per upload
+pub const MAX_TAGS: usize = 16;
+
+/// Longest accepted tag, in characters
+pub const MAX_TAG_CHARS: usize = 32;
+
+const SIDECAR_SUFFIX: &str = ".meta.json";
+
+/// Metadata supplied with an upload
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadMetadata {
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl UploadMetadata {
+    /// Metadata from the upload headers or query, `None` when there is none
+    pub fn from_request(request: &Request) -> Result<Option<Self>, AppError> {
+        let params = request.query_params();
+        let field = |header: &str, param: &str| {
+            request
+                .headers
+                .get(header)
+                .or_else(|| params.get(param))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let description = field("x-upload-description", "description");
+        if let Some(description) = &description {
+            if description.chars().count() > MAX_DESCRIPTION_CHARS {
+                return Err(invalid("description is too long"));
+            }
+            if description
+                .chars()
+                .any(|c| c.is_control() && c != '\n' && c != '\t')
+            {
+                return Err(invalid("description contains control characters"));
+            }
+        }
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in field("x-upload-tags", "tags")
+            .iter()
+            .flat_map(|list| list.split(','))
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+        {
+            if tag.chars().count() > MAX_TAG_CHARS
+                || !tag
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' '))
+            {
+                return Err(invalid(
+                    "tags may only use letters, digits, '-', '_', '.' and spaces",
+                ));
+            }
+            if !tags
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(tag))
+            {
+                tags.push(tag.to_string());
+            }
+        }
+        if tags.len() > MAX_TAGS {
+            return Err(invalid("too many tags"));
+        }
+
+        if description.is_none() && tags.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { description, tags }))
+    }
+
+    /// JSON object with `description` (or `null`) and a `tags` array
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"description":{},"tags":[{}]}}"#,
+            self.description
+                .as_ref()
+                .map_or("null".to_string(), |d| format!(r#""{}""#, json_escape(d))),
+            self.tags
+                .iter()
+                .map(|tag| format!(r#""{}""#, json_escape(tag)))
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+
+    /// Save as the sidecar of `file`
+    pub fn save(&self, file: &Path) -> Result<(), AppError> {
+        let Some(sidecar) = sidecar_path(file) else {
+            return Err(AppError::InvalidPath);
+        };
+        let mut fields = Vec::new();
+        if let Some(description) = &self.description {
+            fields.push(format!(r#""description":"{}""#, json_escape(description)));
+        }
+        if !self.tags.is_empty() {
+            fields.push(format!(r#""tags":"{}""#, json_escape(&self.tags.join(","))));
+        }
+        fs::write(&sidecar, format!("{{{}}}\n", fields.join(","))).map_err(|e| {
+            warn!("Failed to write upload metadata {}: {e}", sidecar.display());
+            AppError::from(e)
+        })
+    }
+
+    /// Metadata saved for `file`, if any
+    pub fn load(file: &Path) -> Option<Self> {
+        let content = fs::read_to_string(sidecar_path(file)?).ok()?;
+        let fields = parse_json_string_map(&content)?;
+        let meta = Self {
+            description: fields.get("description").cloned(),
+            tags: fields
+                .get("tags")
+                .map(|tags| {
+                    tags.split(',')
+                        .map(|tag| tag.trim().to_string())
+                        .filter(|tag| !tag.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        (meta.description.is_some() || !meta.tags.is_empty()).then_some(meta)
+    }
+}
+
+/// Whether `name` is a metadata sidecar
+pub fn is_sidecar(name: &str) -> bool {
+    name.len() > 1 + SIDECAR_SUFFIX.len() && name.starts_with('.') && name.ends_with(SIDECAR_SUFFIX)
+}
+
+/// Sidecar holding the metadata of `file`
+pub fn sidecar_path(file: &Path) -> Option<PathBuf> {
+    let name = file.file_name()?.to_str()?;
+    Some(file.with_file_name(format!(".{name}{SIDECAR_SUFFIX}")))
+}
+
+/// Move the sidecar of `from` along with it; a missing sidecar is fine
+pub fn move_sidecar(from: &Path, to: &Path) {
+    if let (Some(from), Some(to)) = (sidecar_path(from), sidecar_path(to))
+        && from.exists()
+        && let Err(e) = fs::rename(&from, &to)
+    {
+        warn!("Failed to move upload metadata {}: {e}", from.display());
+    }
+}
+
+/// Delete the sidecar of `file`, if there is one
+pub fn remove_sidecar(file: &Path) {
+    if let Some(sidecar) = sidecar_path(file)
+        && let Err(e) = fs::remove_file(&sidecar)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!(
+            "Failed to delete upload metadata {}: {e}",
+            sidecar.display()
+        );
+    }
+}
+
+fn invalid(reason: &str) -> AppError {
+    debug!("Rejected upload metadata: {reason}");
+    AppError::BadRequest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn request(target: &str, headers: &[(&str, &str)]) -> Request {
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        Request {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            query,
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            body: None,
+            peer_addr: None,
+            forwarded_by: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_save_and_load() {
+        assert_eq!(
+            UploadMetadata::from_request(&request("/_irondrop/upload", &[])).unwrap(),
+            None
+        );
+        let meta = UploadMetadata::from_request(&request(
+            "/_irondrop/upload?description=Q3%20%22final%22&tags=ignored",
+            &[("x-upload-tags", "finance, 2024,Finance,")],
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(meta.description.as_deref(), Some("Q3 \"final\""));
+        assert_eq!(meta.tags, vec!["finance", "2024"]);
+        assert_eq!(
+            meta.to_json(),
+            r#"{"description":"Q3 \"final\"","tags":["finance","2024"]}"#
+        );
+
+        for bad in ["tags=a%2Fb", "description=%07bell"] {
+            let path = format!("/_irondrop/upload?{bad}");
+            assert!(UploadMetadata::from_request(&request(&path, &[])).is_err());
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("report.pdf");
+        meta.save(&file).unwrap();
+        assert!(is_sidecar(".report.pdf.meta.json"));
+        assert!(!is_sidecar("report.pdf"));
+        assert_eq!(UploadMetadata::load(&file), Some(meta));
+
+        let moved = dir.path().join("renamed.pdf");
+        move_sidecar(&file, &moved);
+        assert!(UploadMetadata::load(&file).is_none());
+        assert!(UploadMetadata::load(&moved).is_some());
+        remove_sidecar(&moved);
+        assert!(UploadMetadata::load(&moved).is_none());
+    }
+}
@@ -0,0 +1,409 @@
+// SPDX-License-Identifier: MIT
+
+//! Live progress for in-flight uploads
+//!
+//! A client that wants progress picks an id and sends it with the upload in an
+//! `X-Upload-Id` header. While the body is still arriving it can ask for that id:
+//!
+//! - `GET /_irondrop/upload/progress/<id>` - JSON snapshot (poll it)
+//! - same URL with `Accept: text/event-stream` - Server-Sent Events until the
+//!   upload completes or fails
+//!
+//! The HTTP layer counts body bytes as they are received, the upload handler
+//! reports the validate/write/finalize stages. Finished entries are kept for a
+//! short while so a last poll still sees the outcome.
+
+use crate::error::AppError;
+use crate::http::{Request, Response, ResponseBody};
+use crate::upload::{UploadProgress, UploadStage};
+use crate::utils::json_escape;
+use log::{debug, trace};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Route prefix for progress queries
+pub const PROGRESS_ROUTE: &str = "/_irondrop/upload/progress";
+
+/// Request header carrying the client-chosen upload id
+pub const UPLOAD_ID_HEADER: &str = "x-upload-id";
+
+/// Longest accepted upload id
+const MAX_UPLOAD_ID_LEN: usize = 64;
+
+/// How long completed or failed uploads stay queryable
+const FINISHED_RETENTION: Duration = Duration::from_secs(60);
+
+/// Entries without any update for this long are dropped (abandoned clients)
+const IDLE_RETENTION: Duration = Duration::from_secs(10 * 60);
+
+/// Upper bound on tracked uploads so ids cannot be used to exhaust memory
+const MAX_TRACKED_UPLOADS: usize = 1024;
+
+/// Interval between Server-Sent Events
+const EVENT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Received bytes are published at most this often (in bytes)
+const REPORT_GRANULARITY: u64 = 64 * 1024;
+
+#[derive(Debug, Clone)]
+struct TrackedUpload {
+    progress: UploadProgress,
+    received: u64,
+    started: Instant,
+    updated: Instant,
+}
+
+impl TrackedUpload {
+    fn is_finished(&self) -> bool {
+        matches!(
+            self.progress.stage,
+            UploadStage::Completed | UploadStage::Failed
+        )
+    }
+}
+
+/// Point-in-time view of one upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressSnapshot {
+    pub id: String,
+    pub stage: UploadStage,
+    /// Body bytes received from the client
+    pub bytes_received: u64,
+    /// Bytes handled by the current stage (written bytes while `Writing`)
+    pub bytes_processed: u64,
+    /// Declared body size, `0` when unknown (chunked uploads)
+    pub total_bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl ProgressSnapshot {
+    /// Average receive rate since the upload started
+    pub fn bytes_per_second(&self) -> u64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return 0;
+        }
+        (self.bytes_received as f64 / secs) as u64
+    }
+
+    /// Completion percentage of the body transfer, `None` when the size is unknown
+    pub fn percent(&self) -> Option<f64> {
+        if self.stage == UploadStage::Completed {
+            return Some(100.0);
+        }
+        (self.total_bytes > 0)
+            .then(|| (self.bytes_received as f64 * 100.0 / self.total_bytes as f64).min(100.0))
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"upload_id":"{}","stage":"{}","bytes_received":{},"bytes_processed":{},"total_bytes":{},"percent":{},"bytes_per_second":{},"elapsed_ms":{}}}"#,
+            json_escape(&self.id),
+            self.stage.as_str(),
+            self.bytes_received,
+            self.bytes_processed,
+            if self.total_bytes > 0 {
+                self.total_bytes.to_string()
+            } else {
+                "null".to_string()
+            },
+            self.percent()
+                .map(|p| format!("{p:.1}"))
+                .unwrap_or_else(|| "null".to_string()),
+            self.bytes_per_second(),
+            self.elapsed.as_millis()
+        )
+    }
+}
+
+/// Registry of uploads that asked for progress reporting
+#[derive(Debug, Default)]
+pub struct UploadProgressTracker {
+    uploads: Mutex<HashMap<String, TrackedUpload>>,
+}
+
+/// Process-wide tracker shared by the HTTP layer and the upload handlers
+pub fn tracker() -> &'static UploadProgressTracker {
+    static TRACKER: OnceLock<UploadProgressTracker> = OnceLock::new();
+    TRACKER.get_or_init(UploadProgressTracker::default)
+}
+
+/// Extract a usable upload id from request headers
+pub fn upload_id(headers: &HashMap<String, String>) -> Option<String> {
+    let id = headers.get(UPLOAD_ID_HEADER)?.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_UPLOAD_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.');
+    if !valid {
+        debug!("Ignoring invalid {UPLOAD_ID_HEADER} header");
+        return None;
+    }
+    Some(id.to_string())
+}
+
+impl UploadProgressTracker {
+    /// Start tracking `id`, replacing any earlier upload with the same id
+    pub fn begin(&self, id: &str, total_size: u64) {
+        let Ok(mut uploads) = self.uploads.lock() else {
+            return;
+        };
+        let now = Instant::now();
+        uploads.retain(|_, upload| {
+            let age = now.duration_since(upload.updated);
+            age < IDLE_RETENTION && !(upload.is_finished() && age >= FINISHED_RETENTION)
+        });
+        if uploads.len() >= MAX_TRACKED_UPLOADS && !uploads.contains_key(id) {
+            debug!("Upload progress table full, not tracking {id}");
+            return;
+        }
+        uploads.insert(
+            id.to_string(),
+            TrackedUpload {
+                progress: UploadProgress {
+                    total_size,
+                    processed_size: 0,
+                    stage: UploadStage::Receiving,
+                },
+                received: 0,
+                started: now,
+                updated: now,
+            },
+        );
+    }
+
+    /// Record the body bytes received so far
+    pub fn received(&self, id: &str, bytes: u64) {
+        self.update(id, |upload| {
+            upload.received = bytes;
+            upload.progress.processed_size = bytes;
+        });
+    }
+
+    /// Move to a new stage; `processed` resets the per-stage byte counter
+    pub fn set_stage(&self, id: &str, stage: UploadStage, processed: u64) {
+        trace!("Upload {id} -> {}", stage.as_str());
+        self.update(id, |upload| {
+            upload.progress.stage = stage;
+            upload.progress.processed_size = processed;
+        });
+    }
+
+    /// Record bytes handled by the current stage
+    pub fn processed(&self, id: &str, bytes: u64) {
+        self.update(id, |upload| upload.progress.processed_size = bytes);
+    }
+
+    /// Mark the upload as finished
+    pub fn finish(&self, id: &str, success: bool) {
+        let stage = if success {
+            UploadStage::Completed
+        } else {
+            UploadStage::Failed
+        };
+        self.update(id, |upload| {
+            if success {
+                upload.progress.processed_size = upload.received;
+            }
+            upload.progress.stage = stage;
+        });
+    }
+
+    pub fn snapshot(&self, id: &str) -> Option<ProgressSnapshot> {
+        let uploads = self.uploads.lock().ok()?;
+        let upload = uploads.get(id)?;
+        let elapsed = if upload.is_finished() {
+            upload.updated.duration_since(upload.started)
+        } else {
+            upload.started.elapsed()
+        };
+        Some(ProgressSnapshot {
+            id: id.to_string(),
+            stage: upload.progress.stage.clone(),
+            bytes_received: upload.received,
+            bytes_processed: upload.progress.processed_size,
+            total_bytes: upload.progress.total_size,
+            elapsed,
+        })
+    }
+
+    fn update(&self, id: &str, apply: impl FnOnce(&mut TrackedUpload)) {
+        if let Ok(mut uploads) = self.uploads.lock()
+            && let Some(upload) = uploads.get_mut(id)
+            && !upload.is_finished()
+        {
+            apply(upload);
+            upload.updated = Instant::now();
+        }
+    }
+}
+
+/// Reader adapter that publishes received byte counts for a tracked upload
+pub(crate) struct ProgressReader<'a, S> {
+    inner: &'a mut S,
+    id: String,
+    received: u64,
+    reported: u64,
+}
+
+impl<'a, S> ProgressReader<'a, S> {
+    /// `already_received` covers body bytes that arrived together with the headers
+    pub(crate) fn new(inner: &'a mut S, id: String, already_received: u64) -> Self {
+        tracker().received(&id, already_received);
+        Self {
+            inner,
+            id,
+            received: already_received,
+            reported: already_received,
+        }
+    }
+
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Publish the final count once the body has been read
+    pub(crate) fn flush_progress(&mut self) {
+        if self.reported != self.received {
+            tracker().received(&self.id, self.received);
+            self.reported = self.received;
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ProgressReader<'_, S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let result = Pin::new(&mut *this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            this.received += (buf.filled().len() - before) as u64;
+            if this.received - this.reported >= REPORT_GRANULARITY {
+                tracker().received(&this.id, this.received);
+                this.reported = this.received;
+            }
+        }
+        result
+    }
+}
+
+/// `GET /_irondrop/upload/progress/<id>`
+pub fn handle_progress_request(request: &Request) -> Result<Response, AppError> {
+    let path_only = request.path.as_str();
+    let id = path_only
+        .strip_prefix(PROGRESS_ROUTE)
+        .unwrap_or("")
+        .trim_matches('/')
+        .to_string();
+    if id.is_empty() {
+        return Err(AppError::NotFound);
+    }
+    let snapshot = tracker().snapshot(&id).ok_or(AppError::NotFound)?;
+
+    let wants_events = request
+        .headers
+        .get("accept")
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    if wants_events {
+        return Ok(event_stream_response(id));
+    }
+
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/json; charset=utf-8".to_string(),
+    );
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Ok(Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::Text(snapshot.to_json()),
+    })
+}
+
+/// Stream `progress` events until the upload finishes or disappears
+fn event_stream_response(id: String) -> Response {
+    let (tx, rx) = tokio::sync::mpsc::channel(8);
+    std::thread::spawn(move || {
+        loop {
+            let Some(snapshot) = tracker().snapshot(&id) else {
+                let _ = tx.blocking_send(b"event: gone\ndata: {}\n\n".to_vec());
+                break;
+            };
+            let event = format!("event: progress\ndata: {}\n\n", snapshot.to_json());
+            if tx.blocking_send(event.into_bytes()).is_err() {
+                break; // client went away
+            }
+            if matches!(snapshot.stage, UploadStage::Completed | UploadStage::Failed) {
+                break;
+            }
+            std::thread::sleep(EVENT_INTERVAL);
+        }
+    });
+
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), "text/event-stream".to_string());
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    Response {
+        status_code: 200,
+        status_text: "OK".to_string(),
+        headers,
+        body: ResponseBody::AsyncStream(rx),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upload_id_validation() {
+        let mut headers = HashMap::new();
+        assert_eq!(upload_id(&headers), None);
+        headers.insert(UPLOAD_ID_HEADER.to_string(), " abc-123_x.1 ".to_string());
+        assert_eq!(upload_id(&headers).as_deref(), Some("abc-123_x.1"));
+        headers.insert(UPLOAD_ID_HEADER.to_string(), "../etc".to_string());
+        assert_eq!(upload_id(&headers), None);
+        headers.insert(UPLOAD_ID_HEADER.to_string(), "a".repeat(65));
+        assert_eq!(upload_id(&headers), None);
+    }
+
+    #[test]
+    fn test_tracker_lifecycle() {
+        let tracker = UploadProgressTracker::default();
+        tracker.begin("t1", 1000);
+        tracker.received("t1", 250);
+        let snapshot = tracker.snapshot("t1").unwrap();
+        assert_eq!(snapshot.stage, UploadStage::Receiving);
+        assert_eq!(snapshot.bytes_received, 250);
+        assert_eq!(snapshot.percent(), Some(25.0));
+
+        tracker.received("t1", 1000);
+        tracker.set_stage("t1", UploadStage::Writing, 0);
+        tracker.processed("t1", 400);
+        let snapshot = tracker.snapshot("t1").unwrap();
+        assert_eq!(snapshot.bytes_processed, 400);
+        assert!(snapshot.to_json().contains(r#""stage":"writing""#));
+
+        tracker.finish("t1", true);
+        // Finished uploads ignore further updates
+        tracker.received("t1", 5);
+        let snapshot = tracker.snapshot("t1").unwrap();
+        assert_eq!(snapshot.stage, UploadStage::Completed);
+        assert_eq!(snapshot.bytes_received, 1000);
+
+        tracker.begin("t2", 0);
+        assert_eq!(tracker.snapshot("t2").unwrap().percent(), None);
+        assert!(tracker.snapshot("missing").is_none());
+    }
+}
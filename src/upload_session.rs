@@ -0,0 +1,527 @@
+// SPDX-License-Identifier: MIT
+
+//! Resumable upload sessions for IronDrop
+//!
+//! Large transfers over flaky links should not have to restart from zero.
+//! A client opens a session, then sends the file as a sequence of ranged
+//! `PUT` requests carrying `Content-Range: bytes start-end/total`:
+//!
+//! - `POST   /_irondrop/upload/session`      - open a session (`X-Upload-Length` + filename)
+//! - `PUT    /_irondrop/upload/session/<id>` - append the next chunk
+//! - `GET    /_irondrop/upload/session/<id>` - query the committed offset after an interruption
+//! - `DELETE /_irondrop/upload/session/<id>` - abort and discard the partial file
+//!
+//! Chunks are persisted to a hidden temp file inside the target directory so the
+//! final step is a same-filesystem atomic rename. Idle sessions expire and are
+//! removed by a background cleanup thread.
+
+use crate::cli::Cli;
+use crate::disk_space::Reservation;
+use crate::error::AppError;
+use crate::http::{Request, RequestBody};
+use crate::response::HttpResponse;
+use crate::upload::{DirectUploadHandler, TEMP_FILE_PREFIX, UploadConflict};
+use crate::utils::json_escape;
+use log::{debug, info, trace, warn};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Route prefix for session endpoints
+pub const SESSION_ROUTE: &str = "/_irondrop/upload/session";
+
+/// Idle time after which an unfinished session is discarded
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How often the background thread sweeps expired sessions
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Buffer size for copying chunk data into the partial file
+const CHUNK_BUFFER_SIZE: usize = 64 * 1024;
+
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// State of a single in-progress resumable upload
+#[derive(Debug)]
+pub struct UploadSession {
+    pub id: String,
+    /// Validated filename requested by the client
+    pub filename: String,
+    /// Directory the finished file is moved into
+    pub target_dir: PathBuf,
+    /// Total size announced when the session was opened
+    pub total_size: u64,
+    /// Bytes committed to the partial file so far
+    pub received: u64,
+    temp_path: PathBuf,
+    last_activity: Instant,
+    /// Name conflict strategy chosen when the session was opened
+    conflict: UploadConflict,
+    /// Disk space held for the chunks still to come
+    reservation: Reservation,
+}
+
+impl UploadSession {
+    fn is_expired(&self, ttl: Duration) -> bool {
+        self.last_activity.elapsed() >= ttl
+    }
+
+    fn expires_in(&self, ttl: Duration) -> u64 {
+        ttl.saturating_sub(self.last_activity.elapsed()).as_secs()
+    }
+
+    fn status_json(&self, ttl: Duration) -> String {
+        format!(
+            r#"{{"id":"{}","filename":"{}","size":{},"offset":{},"complete":false,"expires_in":{}}}"#,
+            self.id,
+            json_escape(&self.filename),
+            self.total_size,
+            self.received,
+            self.expires_in(ttl)
+        )
+    }
+}
+
+/// Tracks open upload sessions and their partial files
+pub struct UploadSessionManager {
+    sessions: Mutex<HashMap<String, Arc<Mutex<UploadSession>>>>,
+    ttl: Duration,
+}
+
+impl UploadSessionManager {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Spawn a background thread that periodically drops expired sessions.
+    /// The thread exits once the manager itself has been dropped.
+    pub fn spawn_cleanup_thread(manager: &Arc<Self>) {
+        let weak: Weak<Self> = Arc::downgrade(manager);
+        let spawned = thread::Builder::new()
+            .name("irondrop-upload-sessions".to_string())
+            .spawn(move || {
+                loop {
+                    thread::sleep(CLEANUP_INTERVAL);
+                    match weak.upgrade() {
+                        Some(manager) => {
+                            manager.cleanup_expired();
+                        }
+                        None => break,
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("Failed to spawn upload session cleanup thread: {e}");
+        }
+    }
+
+    /// Number of currently open sessions
+    pub fn session_count(&self) -> usize {
+        self.sessions.lock().map(|s| s.len()).unwrap_or(0)
+    }
+
+    /// Remove expired sessions and their partial files, returning how many were dropped
+    pub fn cleanup_expired(&self) -> usize {
+        let expired: Vec<Arc<Mutex<UploadSession>>> = match self.sessions.lock() {
+            Ok(mut sessions) => {
+                let ids: Vec<String> = sessions
+                    .iter()
+                    .filter(|(_, s)| s.lock().map(|s| s.is_expired(self.ttl)).unwrap_or(false))
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                ids.iter().filter_map(|id| sessions.remove(id)).collect()
+            }
+            Err(_) => return 0,
+        };
+
+        for session in &expired {
+            if let Ok(session) = session.lock() {
+                debug!(
+                    "Upload session {} expired, removing partial file",
+                    session.id
+                );
+                let _ = fs::remove_file(&session.temp_path);
+            }
+        }
+        if !expired.is_empty() {
+            info!("Cleaned up {} expired upload session(s)", expired.len());
+        }
+        expired.len()
+    }
+
+    /// Open a new session for `handler`'s target directory
+    pub fn create_session(
+        &self,
+        request: &Request,
+        handler: &DirectUploadHandler,
+    ) -> Result<HttpResponse, AppError> {
+        let total_size = request
+            .headers
+            .get("x-upload-length")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .ok_or_else(|| {
+                debug!("Upload session request missing a valid X-Upload-Length header");
+                AppError::BadRequest
+            })?;
+
+        if total_size > handler.max_upload_size() {
+            return Err(AppError::payload_too_large(handler.max_upload_size()));
+        }
+
+        let filename = handler.extract_filename(request)?;
+        handler.validate_filename(&filename)?;
+        handler.validate_file_extension(&filename)?;
+        let conflict = handler.conflict_for(request)?;
+        let reservation = handler.check_disk_space(total_size)?;
+
+        let id = generate_session_id();
+        let temp_path = handler
+            .target_dir()
+            .join(format!("{TEMP_FILE_PREFIX}session_{id}.part"));
+        File::create(&temp_path)?;
+
+        let session = UploadSession {
+            id: id.clone(),
+            filename,
+            target_dir: handler.target_dir().to_path_buf(),
+            total_size,
+            received: 0,
+            temp_path,
+            last_activity: Instant::now(),
+            conflict,
+            reservation,
+        };
+        info!(
+            "Opened upload session {} for '{}' ({} bytes)",
+            id, session.filename, total_size
+        );
+        let body = session.status_json(self.ttl);
+
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| AppError::InternalServerError("Upload session lock poisoned".into()))?;
+        sessions.insert(id.clone(), Arc::new(Mutex::new(session)));
+
+        Ok(json_response(201, "Created", body).add_header(
+            "Location".to_string(),
+            crate::templates::prefixed(&format!("{SESSION_ROUTE}/{id}")),
+        ))
+    }
+
+    /// Report the committed offset so an interrupted client knows where to resume
+    pub fn session_status(&self, id: &str) -> Result<HttpResponse, AppError> {
+        let session = self.get(id)?;
+        let session = lock_session(&session)?;
+        Ok(json_response(200, "OK", session.status_json(self.ttl))
+            .add_header("Upload-Offset".to_string(), session.received.to_string()))
+    }
+
+    /// Abort a session and delete its partial file
+    pub fn abort_session(&self, id: &str) -> Result<HttpResponse, AppError> {
+        let removed = self
+            .sessions
+            .lock()
+            .map_err(|_| AppError::InternalServerError("Upload session lock poisoned".into()))?
+            .remove(id)
+            .ok_or(AppError::NotFound)?;
+        let session = lock_session(&removed)?;
+        let _ = fs::remove_file(&session.temp_path);
+        info!("Aborted upload session {}", session.id);
+        Ok(HttpResponse::new(204, "No Content"))
+    }
+
+    /// Append a `Content-Range` chunk, finalizing the upload once the last byte arrives
+    pub fn write_chunk(
+        &self,
+        id: &str,
+        request: &Request,
+        cli: &Cli,
+        stats: Option<&crate::server::ServerStats>,
+    ) -> Result<HttpResponse, AppError> {
+        let started = Instant::now();
+        let session_arc = self.get(id)?;
+        let mut session = lock_session(&session_arc)?;
+
+        let range = request
+            .headers
+            .get("content-range")
+            .ok_or(AppError::BadRequest)?;
+        let (start, end, total) = parse_content_range(range).ok_or(AppError::BadRequest)?;
+        if let Some(total) = total
+            && total != session.total_size
+        {
+            debug!(
+                "Content-Range total {} does not match session size {}",
+                total, session.total_size
+            );
+            return Err(AppError::BadRequest);
+        }
+        if end >= session.total_size {
+            return Err(AppError::BadRequest);
+        }
+
+        let body_len = request.body.as_ref().map_or(0, |b| b.len() as u64);
+        if body_len != end - start + 1 {
+            debug!(
+                "Chunk body is {} bytes but Content-Range covers {}",
+                body_len,
+                end - start + 1
+            );
+            return Err(AppError::BadRequest);
+        }
+
+        // Chunks must arrive in order; tell the client where to resume otherwise
+        if start != session.received {
+            debug!(
+                "Out-of-order chunk for session {}: start={} expected={}",
+                session.id, start, session.received
+            );
+            return Ok(
+                json_response(409, "Conflict", session.status_json(self.ttl))
+                    .add_header("Upload-Offset".to_string(), session.received.to_string()),
+            );
+        }
+
+        append_body(&session.temp_path, start, request.body.as_ref())?;
+        session.reservation.release(body_len);
+        session.received = end + 1;
+        session.last_activity = Instant::now();
+        trace!(
+            "Session {} received {}/{} bytes",
+            session.id, session.received, session.total_size
+        );
+
+        if session.received < session.total_size {
+            return Ok(json_response(200, "OK", session.status_json(self.ttl))
+                .add_header("Upload-Offset".to_string(), session.received.to_string()));
+        }
+
+        // Final chunk: move the partial file into place atomically
+        let mut handler = DirectUploadHandler::new_with_directory(cli, session.target_dir.clone())?;
+        if let Err(e) = handler.scan_file(&session.temp_path) {
+            self.discard(session);
+            return Err(e);
+        }
+        handler.organize_for(&session.filename)?;
+        handler.set_conflict(session.conflict);
+        let (final_name, renamed) = match handler.generate_unique_filename(&session.filename) {
+            Ok(name) => name,
+            Err(e) => {
+                self.discard(session);
+                return Err(e);
+            }
+        };
+        let target_path = handler.destination_dir().join(&final_name);
+        let deduplicated =
+            handler.link_duplicate(&session.temp_path, &target_path, session.total_size);
+        if !deduplicated {
+            fs::rename(&session.temp_path, &target_path)?;
+        }
+        crate::upload::sync_parent_dir(&target_path)?;
+        let total_size = session.total_size;
+        let session_id = session.id.clone();
+        drop(session);
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(&session_id);
+        }
+
+        info!(
+            "Upload session {} completed: {} ({} bytes)",
+            session_id,
+            target_path.display(),
+            total_size
+        );
+        crate::hooks::emit(
+            crate::hooks::HookEvent::UploadCompleted,
+            &[
+                ("name", final_name.as_str().into()),
+                ("size", total_size.into()),
+                ("renamed", renamed.into()),
+                ("deduplicated", deduplicated.into()),
+                ("session", session_id.as_str().into()),
+                ("client", crate::hooks::client_of(request).into()),
+            ],
+        );
+        crate::audit::record(
+            crate::audit::AuditEvent::Upload,
+            crate::audit::AuditRecord::for_request(request, "success")
+                .with_path(target_path.display().to_string())
+                .with("size", total_size)
+                .with("session", session_id.as_str()),
+        );
+        if let Some(stats) = stats {
+            stats.record_upload_request(
+                true,
+                1,
+                total_size,
+                started.elapsed().as_millis() as u64,
+                total_size,
+            );
+        }
+
+        let body = format!(
+            r#"{{"id":"{}","status":"success","complete":true,"file":{{"name":"{}","path":"{}","size":{},"renamed":{},"deduplicated":{}}}}}"#,
+            session_id,
+            json_escape(&final_name),
+            json_escape(&handler.relative_upload_path(&final_name)),
+            total_size,
+            renamed,
+            deduplicated
+        );
+        Ok(json_response(201, "Created", body))
+    }
+
+    /// Drop a session that cannot complete, deleting its partial file
+    fn discard(&self, session: std::sync::MutexGuard<'_, UploadSession>) {
+        let _ = fs::remove_file(&session.temp_path);
+        let session_id = session.id.clone();
+        drop(session);
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(&session_id);
+        }
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<Mutex<UploadSession>>, AppError> {
+        if !is_valid_session_id(id) {
+            return Err(AppError::NotFound);
+        }
+        let sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| AppError::InternalServerError("Upload session lock poisoned".into()))?;
+        let session = sessions.get(id).cloned().ok_or(AppError::NotFound)?;
+        drop(sessions);
+
+        let expired = lock_session(&session)?.is_expired(self.ttl);
+        if expired {
+            self.cleanup_expired();
+            return Err(AppError::NotFound);
+        }
+        Ok(session)
+    }
+}
+
+impl Default for UploadSessionManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION_TTL)
+    }
+}
+
+fn lock_session(
+    session: &Arc<Mutex<UploadSession>>,
+) -> Result<std::sync::MutexGuard<'_, UploadSession>, AppError> {
+    session
+        .lock()
+        .map_err(|_| AppError::InternalServerError("Upload session lock poisoned".into()))
+}
+
+fn json_response(status_code: u16, status_text: &str, body: String) -> HttpResponse {
+    HttpResponse::new(status_code, status_text)
+        .with_file_body(body.into_bytes(), "application/json")
+}
+
+/// Write the request body into the partial file at `offset`
+pub(crate) fn append_body(
+    temp_path: &Path,
+    offset: u64,
+    body: Option<&RequestBody>,
+) -> Result<(), AppError> {
+    let mut file = OpenOptions::new().write(true).open(temp_path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    match body {
+        Some(RequestBody::Memory(data)) => file.write_all(data)?,
+        Some(RequestBody::File { path, .. }) => {
+            let mut source = File::open(path)?;
+            let mut buffer = vec![0u8; CHUNK_BUFFER_SIZE];
+            loop {
+                let n = source.read(&mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                file.write_all(&buffer[..n])?;
+            }
+        }
+        None => {}
+    }
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Parse `bytes start-end/total` (total may be `*`)
+pub fn parse_content_range(value: &str) -> Option<(u64, u64, Option<u64>)> {
+    let spec = value.trim().strip_prefix("bytes")?.trim_start();
+    let (range, total) = spec.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start = start.trim().parse::<u64>().ok()?;
+    let end = end.trim().parse::<u64>().ok()?;
+    if end < start {
+        return None;
+    }
+    let total = match total.trim() {
+        "*" => None,
+        t => Some(t.parse::<u64>().ok()?),
+    };
+    Some((start, end, total))
+}
+
+fn is_valid_session_id(id: &str) -> bool {
+    id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Generate a 128-bit hex session identifier, preferring the OS random source
+pub(crate) fn generate_session_id() -> String {
+    let mut bytes = [0u8; 16];
+    let from_os = File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut bytes))
+        .is_ok();
+    if !from_os {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let counter = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mixed = nanos ^ (u64::from(std::process::id()) << 32) ^ counter.rotate_left(17);
+        bytes[..8].copy_from_slice(&mixed.to_le_bytes());
+        bytes[8..]
+            .copy_from_slice(&(mixed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ counter).to_le_bytes());
+    }
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 0-99/200"),
+            Some((0, 99, Some(200)))
+        );
+        assert_eq!(
+            parse_content_range("bytes 100-199/*"),
+            Some((100, 199, None))
+        );
+        assert_eq!(parse_content_range("bytes 10-5/20"), None);
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+        assert_eq!(parse_content_range("bytes -5/20"), None);
+    }
+
+    #[test]
+    fn test_session_id_format() {
+        let a = generate_session_id();
+        let b = generate_session_id();
+        assert!(is_valid_session_id(&a));
+        assert_ne!(a, b);
+        assert!(!is_valid_session_id("../../etc/passwd"));
+    }
+}
@@ -0,0 +1,353 @@
+// SPDX-License-Identifier: MIT
+
+//! User accounts with roles
+//!
+//! Besides the single `[auth]` user, `[users]` (or `--users`) defines
+//! accounts as `name = role:password`, where the password may also be an
+//! Argon2 or bcrypt hash (see [`crate::password`]):
+//!
+//! ```ini
+//! [users]
+//! alice = admin:$argon2id$v=19$m=19456,t=2,p=1$...
+//! bob = upload:bobs-password
+//! carol = read-only:carols-password
+//! ```
+//!
+//! Roles build on each other:
+//!
+//! - `read-only` - browse, download, search and preview
+//! - `upload` - also upload (forms, sessions, WebDAV `PUT`/`MKCOL`/`COPY`) and
+//!   anything else that changes state through the `/_irondrop/` APIs, including
+//!   minting share links
+//! - `admin` - also delete, rename and move files, create folders through the
+//!   file management API, the monitoring pages, the read-only switch and
+//!   memory cleanup
+//!
+//! [`crate::middleware::AuthMiddleware`] answers `403 Forbidden` when an
+//! account lacks the role a request needs ([`required_role`]). The `[auth]`
+//! user, API tokens and `[auth.paths]` users keep full access. Handlers call
+//! [`request_role`] to leave out controls the account cannot use; requests
+//! that carry no verified credentials get the read-only role.
+
+use crate::http::Request;
+use crate::middleware::{AuthMiddleware, Identity, LockedPaths};
+use crate::password::PasswordHash;
+use base64::Engine;
+use log::warn;
+use std::sync::{Arc, RwLock};
+
+/// What an account may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Role {
+    ReadOnly,
+    Upload,
+    Admin,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "read-only" | "readonly" | "read" => Some(Self::ReadOnly),
+            "upload" => Some(Self::Upload),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read-only",
+            Self::Upload => "upload",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// How an account's password is checked
+#[derive(Clone)]
+enum Credential {
+    Plain(Vec<u8>),
+    Hash(PasswordHash),
+}
+
+/// One `[users]` account
+#[derive(Clone)]
+pub struct UserAccount {
+    pub name: String,
+    pub role: Role,
+    credential: Credential,
+}
+
+impl std::fmt::Debug for UserAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserAccount")
+            .field("name", &self.name)
+            .field("role", &self.role)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UserAccount {
+    /// Parse one `name=role:password` entry
+    pub fn parse(entry: &str) -> Result<Self, String> {
+        let (name, rest) = entry
+            .split_once('=')
+            .ok_or_else(|| "expected name=role:password".to_string())?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(':') {
+            return Err("user names must not be empty or contain ':'".to_string());
+        }
+        let (role, password) = rest
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| format!("user '{name}': expected role:password"))?;
+        let role = Role::parse(role).ok_or_else(|| {
+            format!("user '{name}': unknown role '{role}' (use read-only, upload or admin)")
+        })?;
+        let credential = if password.starts_with('$') {
+            Credential::Hash(
+                PasswordHash::parse(password).map_err(|e| format!("user '{name}': {e}"))?,
+            )
+        } else if password.is_empty() {
+            return Err(format!("user '{name}': the password is empty"));
+        } else {
+            Credential::Plain(password.as_bytes().to_vec())
+        };
+        Ok(Self {
+            name: name.to_string(),
+            role,
+            credential,
+        })
+    }
+
+    /// Parse entries, skipping invalid ones and duplicate names with a warning
+    pub fn parse_list(entries: &[String]) -> Vec<Self> {
+        let mut accounts: Vec<Self> = Vec::new();
+        for entry in entries {
+            match Self::parse(entry) {
+                Ok(account) if accounts.iter().any(|a| a.name == account.name) => {
+                    warn!("Ignoring duplicate [users] entry for '{}'", account.name);
+                }
+                Ok(account) => accounts.push(account),
+                Err(e) => warn!("Ignoring [users] entry: {e}"),
+            }
+        }
+        accounts
+    }
+
+    /// Whether `password` is this account's password, compared in constant time
+    pub fn verify(&self, password: &[u8]) -> bool {
+        match &self.credential {
+            Credential::Plain(expected) => crate::password::constant_time_eq(password, expected),
+            Credential::Hash(hash) => hash.verify(password),
+        }
+    }
+
+    /// Whether checking the password is expensive enough to be worth caching
+    pub fn is_hashed(&self) -> bool {
+        matches!(self.credential, Credential::Hash(_))
+    }
+}
+
+/// Split a comma-separated `--users` list. Argon2 hashes contain commas, so
+/// a piece only starts a new entry when it looks like `name=role:`.
+pub fn split_entries(list: &str) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::new();
+    for piece in list.split(',') {
+        let starts_entry = piece
+            .split_once('=')
+            .and_then(|(_, rest)| rest.split_once(':'))
+            .is_some_and(|(role, _)| Role::parse(role).is_some());
+        match entries.last_mut() {
+            Some(last) if !starts_entry => {
+                last.push(',');
+                last.push_str(piece);
+            }
+            _ => entries.push(piece.trim().to_string()),
+        }
+    }
+    entries.retain(|entry| !entry.is_empty());
+    entries
+}
+
+/// Role a request needs
+pub fn required_role(request: &Request) -> Role {
+    let path = request.path.as_str();
+    let method = request.method.to_ascii_uppercase();
+    let under = |prefix: &str| {
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+
+    if [
+        "/monitor",
+        "/_irondrop/monitor",
+        "/_status",
+        "/_irondrop/status",
+    ]
+    .contains(&path)
+        || under(crate::file_ops::FILES_ROUTE)
+        || path == crate::file_ops::MKDIR_ROUTE
+        || under(crate::read_only::READ_ONLY_ROUTE)
+        || path == "/_irondrop/cleanup-memory"
+    {
+        return Role::Admin;
+    }
+    // Share links are minted with a GET
+    if under("/_irondrop/upload")
+        || path.starts_with(crate::edit::EDIT_ROUTE)
+        || [crate::share::SHARE_ROUTE, crate::share::SHARE_SHORT_ROUTE].contains(&path)
+    {
+        return Role::Upload;
+    }
+    if path.starts_with("/_irondrop/") || path.starts_with("/_api/") {
+        return if crate::read_only::is_mutating(request) {
+            Role::Upload
+        } else {
+            Role::ReadOnly
+        };
+    }
+    match method.as_str() {
+        // Removing or renaming is file management
+        "DELETE" | "MOVE" => Role::Admin,
+        _ if crate::read_only::is_mutating(request) => Role::Upload,
+        _ => Role::ReadOnly,
+    }
+}
+
+/// Authentication in effect, for [`request_role`] and [`request_identity`]
+static AUTH: RwLock<Option<Arc<AuthMiddleware>>> = RwLock::new(None);
+
+/// Remember the authentication in effect; `None` when it is off
+pub fn configure(auth: Option<Arc<AuthMiddleware>>) {
+    if let Ok(mut slot) = AUTH.write() {
+        *slot = auth;
+    }
+}
+
+fn current_auth() -> Option<Arc<AuthMiddleware>> {
+    AUTH.read().ok()?.clone()
+}
+
+/// Role of an already authenticated request, see [`AuthMiddleware::role_of`].
+/// Without authentication everyone is admin.
+pub fn request_role(request: &Request) -> Role {
+    current_auth().map_or(Role::Admin, |auth| auth.role_of(request))
+}
+
+/// Who the request's credentials or session verify as; `None` without
+/// authentication or verified credentials
+pub fn request_identity(request: &Request) -> Option<Identity> {
+    current_auth()?.identify(request)
+}
+
+/// Protected directories the request cannot open, for filtering paths the
+/// auth middleware did not see; none without authentication
+pub fn locked_paths(request: &Request) -> LockedPaths {
+    current_auth().map_or_else(LockedPaths::default, |auth| auth.locked_paths(request))
+}
+
+/// User name of a Basic `Authorization` header, not verified
+pub fn basic_username(request: &Request) -> Option<String> {
+    let encoded = request
+        .headers
+        .get("authorization")?
+        .strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    Some(decoded.split_once(':')?.0.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn request(method: &str, target: &str) -> Request {
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query,
+            headers: HashMap::new(),
+            body: None,
+            peer_addr: None,
+            forwarded_by: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_accounts() {
+        let bcrypt = "$2b$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+        let argon2 = crate::password::hash_password_with("pw", 64, 1).unwrap();
+        let list = format!("alice=admin:{argon2},bob = upload:{bcrypt}, carol=read:c:ol");
+        let entries = split_entries(&list);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0], format!("alice=admin:{argon2}"));
+
+        let accounts = UserAccount::parse_list(&entries);
+        let summary: Vec<(&str, Role, bool)> = accounts
+            .iter()
+            .map(|a| (a.name.as_str(), a.role, a.is_hashed()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("alice", Role::Admin, true),
+                ("bob", Role::Upload, true),
+                ("carol", Role::ReadOnly, false),
+            ]
+        );
+        assert!(accounts[0].verify(b"pw"));
+        assert!(accounts[2].verify(b"c:ol"));
+        assert!(!accounts[2].verify(b"c"));
+
+        for bad in [
+            "nobody",
+            "dave=root:pw",
+            "erin=admin:",
+            "frank=admin:$argon2id$bad",
+        ] {
+            assert!(UserAccount::parse(bad).is_err(), "{bad}");
+        }
+        assert_eq!(
+            UserAccount::parse_list(&["a=admin:x".to_string(), "a=upload:y".to_string()]).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_required_role() {
+        for (method, path, role) in [
+            ("GET", "/docs/report.pdf", Role::ReadOnly),
+            ("PROPFIND", "/docs/", Role::ReadOnly),
+            ("GET", "/_irondrop/search?q=x", Role::ReadOnly),
+            ("GET", "/_irondrop/share?path=/a", Role::Upload),
+            ("GET", "/_api/share?path=/a&ttl=60", Role::Upload),
+            ("GET", "/_irondrop/upload", Role::Upload),
+            ("POST", "/_irondrop/upload/session", Role::Upload),
+            ("PUT", "/docs/new.txt", Role::Upload),
+            ("MKCOL", "/docs/new", Role::Upload),
+            ("DELETE", "/docs/old.txt", Role::Admin),
+            ("MOVE", "/docs/old.txt", Role::Admin),
+            ("POST", "/_irondrop/files/rename", Role::Admin),
+            ("POST", "/_api/mkdir", Role::Admin),
+            ("GET", "/monitor", Role::Admin),
+            ("GET", "/_status?json=1", Role::Admin),
+            ("POST", "/_irondrop/read-only?enabled=true", Role::Admin),
+        ] {
+            assert_eq!(
+                required_role(&request(method, path)),
+                role,
+                "{method} {path}"
+            );
+        }
+    }
+}
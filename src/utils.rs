@@ -2,8 +2,40 @@
 
 use crate::error::AppError;
 use log::{debug, trace};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Percent-encode a query parameter value (everything but unreserved characters)
+pub fn percent_encode_query(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// `Content-Disposition` value with an ASCII `filename` fallback and the
+/// exact name as `filename*`, e.g. `inline; filename="a.mp4"; filename*=UTF-8''a.mp4`
+pub fn content_disposition(disposition: &str, filename: &str) -> String {
+    let ascii_name: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+    format!(
+        "{disposition}; filename=\"{ascii_name}\"; filename*=UTF-8''{}",
+        percent_encode_query(filename)
+    )
+}
 
 // Helper function to percent-encode path segments for URLs. 🌐
 pub fn percent_encode_path(path: &Path) -> String {
@@ -23,6 +55,7 @@ pub fn percent_encode_path(path: &Path) -> String {
     path_str
         .chars()
         .map(|c| match c {
+            c if c.is_ascii_control() => format!("%{:02X}", c as u8),
             ' ' => "%20".to_string(),
             '"' => "%22".to_string(),
             '#' => "%23".to_string(),
@@ -85,58 +118,354 @@ pub fn get_request_path(request_line: &str) -> &str {
     "/" // Default to root path if request line parsing fails - safer fallback. 🗺️
 }
 
-/// Parse query parameters from a URL
-pub fn parse_query_params(url: &str) -> HashMap<String, String> {
-    trace!("Parsing query parameters from URL: {}", url);
-    let mut params = HashMap::new();
-
-    if let Some(query_start) = url.find('?') {
-        let query = &url[query_start + 1..];
+/// Split a request target at its `?` and decode the path part
+///
+/// The split happens before decoding, so an escaped `%3F` stays part of the
+/// path. The query is returned as sent and decoded per parameter by
+/// [`parse_query_string`], so an escaped `&` or `=` in a value keeps its
+/// meaning. See [`decode_path`] for what the path may contain.
+pub fn decode_request_target(target: &str) -> Result<(String, Option<String>), AppError> {
+    match target.split_once('?') {
+        Some((path, query)) => Ok((decode_path(path)?, Some(query.to_string()))),
+        None => Ok((decode_path(target)?, None)),
+    }
+}
 
-        for param in query.split('&') {
-            if let Some((key, value)) = param.split_once('=') {
-                // Simple URL decoding for common characters
-                let decoded_value = url_decode(value);
-                params.insert(key.to_string(), decoded_value);
+/// Decode a percent-encoded URL path
+///
+/// Escapes must spell valid UTF-8 and `+` stays a plus. Malformed escapes,
+/// overlong or otherwise invalid UTF-8, control characters and dot segments
+/// written with escapes (`%2e%2e`, `..%2f`, `..%5c`), which no browser sends
+/// but traversal attempts do, are rejected with [`AppError::BadRequest`].
+/// Plain `..` segments are left to [`normalize_path`].
+pub fn decode_path(path: &str) -> Result<String, AppError> {
+    let mut decoded = String::with_capacity(path.len());
+    for (index, segment) in path.split('/').enumerate() {
+        if index > 0 {
+            decoded.push('/');
+        }
+        if !segment.contains('%') {
+            decoded.push_str(segment);
+            continue;
+        }
+        let mut bytes = Vec::with_capacity(segment.len());
+        let mut rest = segment.as_bytes();
+        while let Some((&byte, tail)) = rest.split_first() {
+            if byte != b'%' {
+                bytes.push(byte);
+                rest = tail;
+                continue;
             }
+            let value = tail
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(AppError::BadRequest)?;
+            bytes.push(value);
+            rest = &tail[2..];
         }
+        let segment = String::from_utf8(bytes).map_err(|_| AppError::BadRequest)?;
+        if segment
+            .split(['/', '\\'])
+            .any(|part| part == "." || part == "..")
+        {
+            debug!("Rejecting encoded dot segment in {path}");
+            return Err(AppError::BadRequest);
+        }
+        decoded.push_str(&segment);
+    }
+    if decoded.chars().any(char::is_control) {
+        return Err(AppError::BadRequest);
     }
+    Ok(decoded)
+}
 
-    params
+/// Decode `%XX` escapes as UTF-8, leaving malformed ones as they are
+pub fn percent_decode(input: &str) -> String {
+    decode_lossy(input, false)
 }
 
-/// Simple URL decoding for common percent-encoded characters
-fn url_decode(input: &str) -> String {
-    let mut result = String::with_capacity(input.len());
-    let mut chars = input.chars().peekable();
+/// [`percent_decode`] for query string keys and values, where `+` is a space
+pub fn decode_query_component(input: &str) -> String {
+    decode_lossy(input, true)
+}
 
-    while let Some(ch) = chars.next() {
-        if ch == '%' {
-            // Try to decode percent-encoded character
-            if let (Some(hex1), Some(hex2)) = (chars.next(), chars.next()) {
-                if let (Some(d1), Some(d2)) = (hex1.to_digit(16), hex2.to_digit(16)) {
-                    let byte_val = ((d1 << 4) | d2) as u8;
-                    if let Some(decoded_char) = char::from_u32(byte_val as u32) {
-                        result.push(decoded_char);
-                        continue;
-                    }
+fn decode_lossy(input: &str, plus_as_space: bool) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                // Keep the original characters when the escape is malformed
+                let hex = input
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                if let Some(byte) = hex {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
                 }
-                // If decoding failed, keep the original characters
-                result.push('%');
-                result.push(hex1);
-                result.push(hex2);
-            } else {
-                result.push(ch);
+                decoded.push(b'%');
             }
-        } else if ch == '+' {
-            // Handle + as space in query parameters
-            result.push(' ');
-        } else {
-            result.push(ch);
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
         }
+        i += 1;
     }
 
-    result
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// (combining mark, precomposed letters, what each letter is without the mark)
+///
+/// The canonical decompositions of Latin letters with one diacritic; letters
+/// with several (`ấ`) decompose through another entry (`â`).
+const LATIN_COMPOSITIONS: &[(char, &str, &str)] = &[
+    (
+        '\u{300}',
+        "ÀÈÌÒÙàèìòùǛǜǸǹḔḕṐṑẀẁẦầẰằ",
+        "AEIOUaeiouÜüNnĒēŌōWwÂâĂă",
+    ),
+    ('\u{300}', "ỀềỒồỜờỪừỲỳ", "ÊêÔôƠơƯưYy"),
+    (
+        '\u{301}',
+        "ÁÉÍÓÚÝáéíóúýĆćĹĺŃńŔŕŚśŹź",
+        "AEIOUYaeiouyCcLlNnRrSsZz",
+    ),
+    (
+        '\u{301}',
+        "ǗǘǴǵǺǻǼǽǾǿḈḉḖḗḮḯḰḱḾḿṌṍṒṓ",
+        "ÜüGgÅåÆæØøÇçĒēÏïKkMmÕõŌō",
+    ),
+    ('\u{301}', "ṔṕṸṹẂẃẤấẮắẾếỐốỚớỨứ", "PpŨũWwÂâĂăÊêÔôƠơƯư"),
+    (
+        '\u{302}',
+        "ÂÊÎÔÛâêîôûĈĉĜĝĤĥĴĵŜŝŴŵŶŷ",
+        "AEIOUaeiouCcGgHhJjSsWwYy",
+    ),
+    ('\u{302}', "ẐẑẬậỆệỘộ", "ZzẠạẸẹỌọ"),
+    (
+        '\u{303}',
+        "ÃÑÕãñõĨĩŨũṼṽẪẫẴẵẼẽỄễỖỗỠỡ",
+        "ANOanoIiUuVvÂâĂăEeÊêÔôƠơ",
+    ),
+    ('\u{303}', "ỮữỸỹ", "ƯưYy"),
+    (
+        '\u{304}',
+        "ĀāĒēĪīŌōŪūǕǖǞǟǠǡǢǣǬǭȪȫȬȭ",
+        "AaEeIiOoUuÜüÄäȦȧÆæǪǫÖöÕõ",
+    ),
+    ('\u{304}', "ȰȱȲȳḠḡḸḹṜṝ", "ȮȯYyGgḶḷṚṛ"),
+    ('\u{306}', "ĂăĔĕĞğĬĭŎŏŬŭḜḝẶặ", "AaEeGgIiOoUuȨȩẠạ"),
+    (
+        '\u{307}',
+        "ĊċĖėĠġİŻżȦȧȮȯḂḃḊḋḞḟḢḣṀṁṄ",
+        "CcEeGgIZzAaOoBbDdFfHhMmN",
+    ),
+    (
+        '\u{307}',
+        "ṅṖṗṘṙṠṡṤṥṦṧṨṩṪṫẆẇẊẋẎẏẛ",
+        "nPpRrSsŚśŠšṢṣTtWwXxYyſ",
+    ),
+    (
+        '\u{308}',
+        "ÄËÏÖÜäëïöüÿŸḦḧṎṏṺṻẄẅẌẍẗ",
+        "AEIOUaeiouyYHhÕõŪūWwXxt",
+    ),
+    (
+        '\u{309}',
+        "ẢảẨẩẲẳẺẻỂểỈỉỎỏỔổỞởỦủỬửỶỷ",
+        "AaÂâĂăEeÊêIiOoÔôƠơUuƯưYy",
+    ),
+    ('\u{30a}', "ÅåŮůẘẙ", "AaUuwy"),
+    ('\u{30b}', "ŐőŰű", "OoUu"),
+    (
+        '\u{30c}',
+        "ČčĎďĚěĽľŇňŘřŠšŤťŽžǍǎǏǐǑǒ",
+        "CcDdEeLlNnRrSsTtZzAaIiOo",
+    ),
+    ('\u{30c}', "ǓǔǙǚǦǧǨǩǮǯǰȞȟ", "UuÜüGgKkƷʒjHh"),
+    ('\u{30f}', "ȀȁȄȅȈȉȌȍȐȑȔȕ", "AaEeIiOoRrUu"),
+    ('\u{311}', "ȂȃȆȇȊȋȎȏȒȓȖȗ", "AaEeIiOoRrUu"),
+    ('\u{31b}', "ƠơƯư", "OoUu"),
+    (
+        '\u{323}',
+        "ḄḅḌḍḤḥḲḳḶḷṂṃṆṇṚṛṢṣṬṭṾṿẈẉ",
+        "BbDdHhKkLlMmNnRrSsTtVvWw",
+    ),
+    ('\u{323}', "ẒẓẠạẸẹỊịỌọỢợỤụỰựỴỵ", "ZzAaEeIiOoƠơUuƯưYy"),
+    ('\u{324}', "Ṳṳ", "Uu"),
+    ('\u{325}', "Ḁḁ", "Aa"),
+    ('\u{326}', "ȘșȚț", "SsTt"),
+    (
+        '\u{327}',
+        "ÇçĢģĶķĻļŅņŖŗŞşŢţȨȩḐḑḨḩ",
+        "CcGgKkLlNnRrSsTtEeDdHh",
+    ),
+    ('\u{328}', "ĄąĘęĮįŲųǪǫ", "AaEeIiUuOo"),
+    ('\u{32d}', "ḒḓḘḙḼḽṊṋṰṱṶṷ", "DdEeLlNnTtUu"),
+    ('\u{32e}', "Ḫḫ", "Hh"),
+    ('\u{330}', "ḚḛḬḭṴṵ", "EeIiUu"),
+    ('\u{331}', "ḆḇḎḏḴḵḺḻṈṉṞṟṮṯẔẕẖ", "BbDdKkLlNnRrTtZzh"),
+];
+
+static LATIN_DECOMPOSITIONS: LazyLock<HashMap<char, (char, char)>> = LazyLock::new(|| {
+    LATIN_COMPOSITIONS
+        .iter()
+        .flat_map(|&(mark, composed, bases)| {
+            composed
+                .chars()
+                .zip(bases.chars())
+                .map(move |(letter, base)| (letter, (base, mark)))
+        })
+        .collect()
+});
+
+fn push_decomposed(c: char, out: &mut String) {
+    // Hangul syllables decompose algorithmically (Unicode 3.12)
+    const S_BASE: u32 = 0xAC00;
+    const V_COUNT: u32 = 21;
+    const T_COUNT: u32 = 28;
+    let index = (c as u32).wrapping_sub(S_BASE);
+    if index < 19 * V_COUNT * T_COUNT {
+        let jamo = |code: u32| char::from_u32(code).unwrap_or(c);
+        out.push(jamo(0x1100 + index / (V_COUNT * T_COUNT)));
+        out.push(jamo(0x1161 + index % (V_COUNT * T_COUNT) / T_COUNT));
+        if !index.is_multiple_of(T_COUNT) {
+            out.push(jamo(0x11A7 + index % T_COUNT));
+        }
+        return;
+    }
+    match LATIN_DECOMPOSITIONS.get(&c) {
+        Some(&(base, mark)) => {
+            push_decomposed(base, out);
+            out.push(mark);
+        }
+        None => out.push(c),
+    }
+}
+
+/// Canonically decomposed (NFD) form of `name`, for comparing names written
+/// in different Unicode normalization forms
+///
+/// Browsers send composed names (`é`), while macOS stores and sends them
+/// decomposed (`e` + U+0301). Covers Latin letters and Hangul.
+pub fn unicode_path_key(name: &str) -> Cow<'_, str> {
+    if name.is_ascii() {
+        return Cow::Borrowed(name);
+    }
+    let mut key = String::with_capacity(name.len() + 8);
+    for c in name.chars() {
+        push_decomposed(c, &mut key);
+    }
+    Cow::Owned(key)
+}
+
+/// On-disk spelling of `relative` below `base` when it exists only in
+/// another Unicode normalization form, e.g. a composed request for a file
+/// created decomposed on a Mac
+pub fn find_unicode_variant(base: &Path, relative: &Path) -> Option<PathBuf> {
+    if relative.to_str().is_none_or(str::is_ascii) {
+        return None;
+    }
+    let mut found = PathBuf::new();
+    for component in relative.components() {
+        let Component::Normal(name) = component else {
+            return None;
+        };
+        let dir = base.join(&found);
+        if dir.join(name).symlink_metadata().is_ok() {
+            found.push(name);
+            continue;
+        }
+        let wanted = unicode_path_key(name.to_str()?).into_owned();
+        let entry = std::fs::read_dir(&dir).ok()?.flatten().find(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|candidate| unicode_path_key(candidate) == wanted)
+        })?;
+        found.push(entry.file_name());
+    }
+    (found.as_path() != relative).then_some(found)
+}
+
+/// Whether Windows treats `name` as a device rather than a file
+///
+/// Covers `CON`, `PRN`, `AUX`, `NUL`, `COM0`-`COM9`, `LPT0`-`LPT9` (also with
+/// superscript digits) and the console handles `CONIN$` and `CONOUT$`, in
+/// any case and with any extension: `aux.txt` and `Con.tar.gz` are devices
+/// too, as is `nul .txt` since trailing spaces are dropped before the check.
+pub fn is_windows_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    if stem.eq_ignore_ascii_case("CONIN$") || stem.eq_ignore_ascii_case("CONOUT$") {
+        return true;
+    }
+    if ["CON", "PRN", "AUX", "NUL"]
+        .iter()
+        .any(|device| stem.eq_ignore_ascii_case(device))
+    {
+        return true;
+    }
+    let Some((prefix, digit)) = stem.char_indices().nth(3).map(|(i, _)| stem.split_at(i)) else {
+        return false;
+    };
+    (prefix.eq_ignore_ascii_case("COM") || prefix.eq_ignore_ascii_case("LPT"))
+        && matches!(
+            digit,
+            "0" | "1"
+                | "2"
+                | "3"
+                | "4"
+                | "5"
+                | "6"
+                | "7"
+                | "8"
+                | "9"
+                | "\u{b9}"
+                | "\u{b2}"
+                | "\u{b3}"
+        )
+}
+
+/// Whether the local file system cannot hold a file named exactly `name`
+///
+/// On Windows that is a reserved device name or one ending in a dot or a
+/// space, which Windows silently drops, so `report.txt.` would open
+/// `report.txt`. Always `false` elsewhere.
+pub fn is_unusable_file_name(name: &str) -> bool {
+    cfg!(windows) && (is_windows_reserved_name(name) || name.ends_with(['.', ' ']))
+}
+
+/// Whether any component of `path` fails [`is_unusable_file_name`]
+pub fn has_unusable_component(path: &Path) -> bool {
+    cfg!(windows)
+        && path.components().any(|component| match component {
+            Component::Normal(name) => name.to_str().is_some_and(is_unusable_file_name),
+            _ => false,
+        })
+}
+
+/// Parse query parameters from a URL
+pub fn parse_query_params(url: &str) -> HashMap<String, String> {
+    trace!("Parsing query parameters from URL: {}", url);
+    url.split_once('?')
+        .map(|(_, query)| parse_query_string(query))
+        .unwrap_or_default()
+}
+
+/// Parse `key=value` pairs of a query string (without the `?`) or form body
+pub fn parse_query_string(query: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    for param in query.split('&') {
+        if let Some((key, value)) = param.split_once('=') {
+            let decoded_value = decode_query_component(value);
+            params.insert(key.to_string(), decoded_value);
+        }
+    }
+    params
 }
 
 /// Resolve upload directory based on base directory and optional upload_to parameter
@@ -153,6 +482,9 @@ pub fn resolve_upload_directory(
             // Parse and validate the upload path
             let requested_path = PathBuf::from(path_str.strip_prefix('/').unwrap_or(path_str));
             let safe_path = normalize_path(&requested_path)?;
+            if has_unusable_component(&safe_path) {
+                return Err(AppError::NotFound);
+            }
             let target_dir = base_dir.join(safe_path);
 
             // Security: Ensure target is within base directory
@@ -179,7 +511,10 @@ pub fn resolve_upload_directory(
 }
 
 /// Safe path normalization to prevent directory traversal
-fn normalize_path(path: &Path) -> Result<PathBuf, AppError> {
+///
+/// Resolves `.` and `..` lexically and drops root and prefix components;
+/// [`AppError::Forbidden`] when `..` would leave the root.
+pub fn normalize_path(path: &Path) -> Result<PathBuf, AppError> {
     let mut components = Vec::new();
     for component in path.components() {
         match component {
@@ -197,6 +532,157 @@ fn normalize_path(path: &Path) -> Result<PathBuf, AppError> {
     Ok(components.iter().collect())
 }
 
+/// Escape a string for embedding inside a JSON string literal
+pub fn json_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn parse_json_string(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+    let mut out = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(out),
+            '\\' => match chars.next()? {
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                '/' => out.push('/'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'b' => out.push('\u{8}'),
+                'f' => out.push('\u{c}'),
+                'u' => {
+                    let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                    let mut code = u32::from_str_radix(&hex, 16).ok()?;
+                    // Combine UTF-16 surrogate pairs
+                    if (0xD800..0xDC00).contains(&code) {
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        let low: String = (0..4).filter_map(|_| chars.next()).collect();
+                        let low = u32::from_str_radix(&low, 16).ok()?;
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low.checked_sub(0xDC00)?);
+                    }
+                    out.push(char::from_u32(code)?);
+                }
+                _ => return None,
+            },
+            c => out.push(c),
+        }
+    }
+}
+fn skip_json_ws(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+/// Parse a flat JSON object whose values are strings, e.g. `{"action":"delete","path":"/a.txt"}`.
+/// Returns `None` for anything else (nested values, numbers, malformed input).
+pub fn parse_json_string_map(input: &str) -> Option<HashMap<String, String>> {
+    let mut chars = input.chars().peekable();
+    let mut map = HashMap::new();
+    skip_json_ws(&mut chars);
+    if chars.next()? != '{' {
+        return None;
+    }
+    skip_json_ws(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_json_ws(&mut chars);
+            let key = parse_json_string(&mut chars)?;
+            skip_json_ws(&mut chars);
+            if chars.next()? != ':' {
+                return None;
+            }
+            skip_json_ws(&mut chars);
+            let value = parse_json_string(&mut chars)?;
+            map.insert(key, value);
+            skip_json_ws(&mut chars);
+            match chars.next()? {
+                ',' => continue,
+                '}' => break,
+                _ => return None,
+            }
+        }
+    }
+    skip_json_ws(&mut chars);
+    chars.next().is_none().then_some(map)
+}
+
+/// Like [`parse_json_string_map`], but values may also be arrays of strings,
+/// e.g. `{"paths":["/a.txt","/b"],"format":"tar"}`. A plain string value
+/// becomes a one-element list.
+pub fn parse_json_string_lists(input: &str) -> Option<HashMap<String, Vec<String>>> {
+    let mut chars = input.chars().peekable();
+    let mut map = HashMap::new();
+    skip_json_ws(&mut chars);
+    if chars.next()? != '{' {
+        return None;
+    }
+    skip_json_ws(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_json_ws(&mut chars);
+            let key = parse_json_string(&mut chars)?;
+            skip_json_ws(&mut chars);
+            if chars.next()? != ':' {
+                return None;
+            }
+            skip_json_ws(&mut chars);
+            let mut values = Vec::new();
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                skip_json_ws(&mut chars);
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                } else {
+                    loop {
+                        skip_json_ws(&mut chars);
+                        values.push(parse_json_string(&mut chars)?);
+                        skip_json_ws(&mut chars);
+                        match chars.next()? {
+                            ',' => continue,
+                            ']' => break,
+                            _ => return None,
+                        }
+                    }
+                }
+            } else {
+                values.push(parse_json_string(&mut chars)?);
+            }
+            map.insert(key, values);
+            skip_json_ws(&mut chars);
+            match chars.next()? {
+                ',' => continue,
+                '}' => break,
+                _ => return None,
+            }
+        }
+    }
+    skip_json_ws(&mut chars);
+    chars.next().is_none().then_some(map)
+}
+
 /// Check if a filename should be hidden from directory listings and search
 /// Returns true for files starting with '._', '.', or named '.DS_Store'
 pub fn is_hidden_file(filename: &str) -> bool {
@@ -204,8 +690,7 @@ pub fn is_hidden_file(filename: &str) -> bool {
 }
 
 pub fn is_macos_finder_noise_path(path: &str) -> bool {
-    let path_only = path.split('?').next().unwrap_or(path);
-    for component in path_only.split('/') {
+    for component in path.split('/') {
         if component.is_empty() {
             continue;
         }
@@ -235,3 +720,95 @@ pub fn is_macos_finder_noise_path(path: &str) -> bool {
     }
     false
 }
+
+/// Format a timestamp as an RFC 7231 IMF-fixdate (e.g. `Sun, 06 Nov 1994 08:49:37 GMT`)
+pub fn format_http_date(time: SystemTime) -> Option<String> {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let total_seconds = time.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    let days = total_seconds.div_euclid(86_400);
+    let secs_of_day = total_seconds.rem_euclid(86_400);
+
+    let hour = (secs_of_day / 3600) as u32;
+    let minute = ((secs_of_day % 3600) / 60) as u32;
+    let second = (secs_of_day % 60) as u32;
+
+    let weekday_idx = (days.rem_euclid(7)) as usize;
+    let weekday = WEEKDAYS[weekday_idx];
+
+    let (year, month, day) = civil_from_days(days);
+    let month_name = MONTHS[(month - 1) as usize];
+
+    Some(format!(
+        "{weekday}, {day:02} {month_name} {year:04} {hour:02}:{minute:02}:{second:02} GMT"
+    ))
+}
+
+/// Parse an RFC 7231 IMF-fixdate back into a timestamp; other legacy formats yield `None`
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    // "Sun, 06 Nov 1994 08:49:37 GMT"
+    let (_, rest) = value.trim().split_once(", ")?;
+    let mut parts = rest.split_whitespace();
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month_name = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month_name)? as u32 + 1;
+    let year: i32 = parts.next()?.parse().ok()?;
+    let mut clock = parts.next()?.split(':');
+    let hour: u64 = clock.next()?.parse().ok()?;
+    let minute: u64 = clock.next()?.parse().ok()?;
+    let second: u64 = clock.next()?.parse().ok()?;
+    if parts.next()? != "GMT" || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let secs = u64::try_from(days).ok()? * 86_400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+/// Weak validator for a file, derived from its size, mtime and path
+pub fn etag_for_resource(path: &Path, metadata: &std::fs::Metadata) -> String {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let seed = path.to_string_lossy();
+    format!("\"{:x}-{:x}-{:x}\"", metadata.len(), modified, seed.len())
+}
+
+/// Convert a (year, month, day) civil date into days since the Unix epoch
+pub(crate) fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year } as i64;
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Convert days since the Unix epoch into a (year, month, day) civil date
+pub(crate) fn civil_from_days(days_since_epoch: i64) -> (i32, u32, u32) {
+    let z = days_since_epoch + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let mut year = (yoe as i32) + era as i32 * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = mp + if mp < 10 { 3 } else { -9 };
+    if month <= 2 {
+        year += 1;
+    }
+    (year, month as u32, day as u32)
+}
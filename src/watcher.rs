@@ -0,0 +1,310 @@
+// SPDX-License-Identifier: MIT
+
+//! Filesystem change notifications for incremental search index updates
+//!
+//! [`FsWatcher`] hides the platform notification API behind two calls: watch a
+//! directory tree recursively, then [`FsWatcher::poll`] for the paths that
+//! changed since the last poll. The search index patches just those entries
+//! instead of re-walking the whole tree.
+//!
+//! - Linux: inotify, one watch per directory (new directories are picked up as
+//!   they appear)
+//! - other platforms: [`FsWatcher::new`] returns `Unsupported` and the index
+//!   keeps its periodic full rebuild
+//!
+//! Hidden directories are not watched, matching what the index skips.
+
+use crate::utils::is_hidden_file;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A change reported by the watcher
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsEvent {
+    /// The path was created or its contents/metadata changed
+    Changed(PathBuf),
+    /// The path was deleted or moved away
+    Removed(PathBuf),
+    /// Events were lost; callers should rebuild from scratch
+    Overflow,
+}
+
+/// Recursive watch over a directory tree
+pub struct FsWatcher {
+    backend: backend::Backend,
+}
+
+impl FsWatcher {
+    /// Start watching `root` and every non-hidden directory below it
+    pub fn new(root: &Path) -> io::Result<Self> {
+        Ok(Self {
+            backend: backend::Backend::new(root)?,
+        })
+    }
+
+    /// Drain pending changes without blocking
+    pub fn poll(&mut self) -> io::Result<Vec<FsEvent>> {
+        self.backend.poll()
+    }
+
+    /// Number of directories currently watched
+    pub fn watched_directories(&self) -> usize {
+        self.backend.watched_directories()
+    }
+}
+
+fn is_hidden_path(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| is_hidden_file(&name.to_string_lossy()))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+mod backend {
+    use super::{FsEvent, is_hidden_path};
+    use log::{debug, warn};
+    use std::collections::HashMap;
+    use std::ffi::{CString, c_char, c_int};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::FromRawFd;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    unsafe extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+    }
+
+    const IN_NONBLOCK: c_int = 0o4000;
+    const IN_CLOEXEC: c_int = 0o2000000;
+
+    const IN_ATTRIB: u32 = 0x0000_0004;
+    const IN_CLOSE_WRITE: u32 = 0x0000_0008;
+    const IN_MOVED_FROM: u32 = 0x0000_0040;
+    const IN_MOVED_TO: u32 = 0x0000_0080;
+    const IN_CREATE: u32 = 0x0000_0100;
+    const IN_DELETE: u32 = 0x0000_0200;
+    const IN_Q_OVERFLOW: u32 = 0x0000_4000;
+    const IN_IGNORED: u32 = 0x0000_8000;
+    const IN_ONLYDIR: u32 = 0x0100_0000;
+    const IN_DONT_FOLLOW: u32 = 0x0200_0000;
+    const IN_ISDIR: u32 = 0x4000_0000;
+
+    const WATCH_MASK: u32 = IN_ATTRIB
+        | IN_CLOSE_WRITE
+        | IN_MOVED_FROM
+        | IN_MOVED_TO
+        | IN_CREATE
+        | IN_DELETE
+        | IN_ONLYDIR
+        | IN_DONT_FOLLOW;
+
+    /// `struct inotify_event` without the trailing name
+    const EVENT_HEADER_LEN: usize = 16;
+
+    pub struct Backend {
+        inotify: File,
+        directories: HashMap<i32, PathBuf>,
+    }
+
+    impl Backend {
+        pub fn new(root: &Path) -> io::Result<Self> {
+            let fd = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // The File owns the descriptor and closes it on drop
+            let inotify = unsafe { File::from_raw_fd(fd) };
+            let mut backend = Self {
+                inotify,
+                directories: HashMap::new(),
+            };
+            backend.watch_tree(root)?;
+            debug!(
+                "Watching {} directories under {}",
+                backend.directories.len(),
+                root.display()
+            );
+            Ok(backend)
+        }
+
+        pub fn watched_directories(&self) -> usize {
+            self.directories.len()
+        }
+
+        fn add_watch(&mut self, dir: &Path) -> io::Result<()> {
+            let path = CString::new(dir.as_os_str().as_bytes())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let fd = std::os::fd::AsRawFd::as_raw_fd(&self.inotify);
+            let wd = unsafe { inotify_add_watch(fd, path.as_ptr(), WATCH_MASK) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            self.directories.insert(wd, dir.to_path_buf());
+            Ok(())
+        }
+
+        /// Watch `root` and its subdirectories; fails when the watch limit is hit
+        fn watch_tree(&mut self, root: &Path) -> io::Result<()> {
+            let mut pending = vec![root.to_path_buf()];
+            while let Some(dir) = pending.pop() {
+                match self.add_watch(&dir) {
+                    Ok(()) => {}
+                    // Vanished or unreadable directories are simply not watched
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                    Err(e) if e.kind() == io::ErrorKind::PermissionDenied => continue,
+                    Err(e) => return Err(e),
+                }
+                let Ok(entries) = std::fs::read_dir(&dir) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if entry.file_type().map(|t| t.is_dir()).unwrap_or(false)
+                        && !is_hidden_path(&path)
+                    {
+                        pending.push(path);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        pub fn poll(&mut self) -> io::Result<Vec<FsEvent>> {
+            let mut events = Vec::new();
+            let mut buffer = [0u8; 64 * 1024];
+            loop {
+                let read = match self.inotify.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                self.parse_events(&buffer[..read], &mut events);
+            }
+            Ok(events)
+        }
+
+        fn parse_events(&mut self, mut data: &[u8], events: &mut Vec<FsEvent>) {
+            while data.len() >= EVENT_HEADER_LEN {
+                let field = |at: usize| {
+                    u32::from_ne_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+                };
+                let wd = field(0) as i32;
+                let mask = field(4);
+                let name_len = field(12) as usize;
+                let end = (EVENT_HEADER_LEN + name_len).min(data.len());
+                let name = &data[EVENT_HEADER_LEN..end];
+                let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+                data = &data[end..];
+
+                if mask & IN_Q_OVERFLOW != 0 {
+                    events.push(FsEvent::Overflow);
+                    continue;
+                }
+                if mask & IN_IGNORED != 0 {
+                    self.directories.remove(&wd);
+                    continue;
+                }
+                let Some(dir) = self.directories.get(&wd) else {
+                    continue;
+                };
+                if name.is_empty() {
+                    continue;
+                }
+                let path = dir.join(std::ffi::OsStr::from_bytes(name));
+                if is_hidden_path(&path) {
+                    continue;
+                }
+
+                if mask & (IN_DELETE | IN_MOVED_FROM) != 0 {
+                    events.push(FsEvent::Removed(path));
+                    continue;
+                }
+                if mask & IN_ISDIR != 0 && mask & (IN_CREATE | IN_MOVED_TO) != 0 {
+                    // Watch the new subtree before reporting it so nothing
+                    // created inside it afterwards is missed
+                    if let Err(e) = self.watch_tree(&path) {
+                        warn!("Cannot watch new directory {}: {e}", path.display());
+                        events.push(FsEvent::Overflow);
+                    }
+                }
+                events.push(FsEvent::Changed(path));
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod backend {
+    use super::FsEvent;
+    use std::io;
+    use std::path::Path;
+
+    pub struct Backend;
+
+    impl Backend {
+        pub fn new(_root: &Path) -> io::Result<Self> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "filesystem notifications are not implemented on this platform",
+            ))
+        }
+
+        pub fn poll(&mut self) -> io::Result<Vec<FsEvent>> {
+            Ok(Vec::new())
+        }
+
+        pub fn watched_directories(&self) -> usize {
+            0
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for(watcher: &mut FsWatcher, expected: &FsEvent) -> Vec<FsEvent> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut seen = Vec::new();
+        while Instant::now() < deadline {
+            seen.extend(watcher.poll().unwrap());
+            if seen.contains(expected) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        seen
+    }
+
+    #[test]
+    fn test_watcher_reports_changes_in_new_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join(".hidden")).unwrap();
+        let mut watcher = FsWatcher::new(&root).unwrap();
+        assert_eq!(watcher.watched_directories(), 1);
+
+        std::fs::create_dir(root.join("sub")).unwrap();
+        let seen = wait_for(&mut watcher, &FsEvent::Changed(root.join("sub")));
+        assert!(seen.contains(&FsEvent::Changed(root.join("sub"))));
+        assert_eq!(watcher.watched_directories(), 2);
+
+        let file = root.join("sub").join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        let seen = wait_for(&mut watcher, &FsEvent::Changed(file.clone()));
+        assert!(seen.contains(&FsEvent::Changed(file.clone())));
+
+        std::fs::remove_file(&file).unwrap();
+        let seen = wait_for(&mut watcher, &FsEvent::Removed(file.clone()));
+        assert!(seen.contains(&FsEvent::Removed(file)));
+
+        std::fs::write(root.join(".hidden").join("b.txt"), "b").unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(watcher.poll().unwrap().is_empty());
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::error::AppError;
 use crate::http::{Request, RequestBody, Response, ResponseBody};
+use crate::utils::{civil_from_days, etag_for_resource, format_http_date};
 use log::{debug, trace};
 use std::collections::HashMap;
 use std::io::Write;
@@ -62,6 +63,7 @@ static DAV_DEAD_PROPERTIES: OnceLock<Mutex<HashMap<String, HashMap<PropName, Str
     OnceLock::new();
 static DAV_OP_GUARD: OnceLock<Mutex<()>> = OnceLock::new();
 static LOCK_COUNTER: AtomicU64 = AtomicU64::new(1);
+static REPLACE_COUNTER: AtomicU64 = AtomicU64::new(0);
 const DAV_NAMESPACE: &str = "DAV:";
 
 pub fn handle_webdav_request(
@@ -452,22 +454,37 @@ fn handle_put(request: &Request, base_dir: &Path) -> Result<Response, AppError>
 
     let existed = target_path.exists();
 
-    if let Some(body) = &request.body {
-        match body {
-            RequestBody::Memory(data) => {
-                let mut file = std::fs::File::create(&target_path)?;
-                file.write_all(data)?;
-                file.sync_all()?;
-            }
-            RequestBody::File { path, .. } => {
-                if std::fs::rename(path, &target_path).is_err() {
-                    std::fs::copy(path, &target_path)?;
-                }
+    // Ranged pieces are assembled and committed by `partial_put`
+    if let Some(content_range) = request.headers.get("content-range") {
+        return crate::partial_put::handle_range_put(request, &target_path, content_range, existed);
+    }
+    crate::partial_put::discard(&target_path);
+
+    match &request.body {
+        Some(RequestBody::Memory(data)) => replace_file(&target_path, |file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })?,
+        Some(RequestBody::File { path, .. }) => {
+            if std::fs::rename(path, &target_path).is_err() {
+                replace_file(&target_path, |file| {
+                    std::io::copy(&mut std::fs::File::open(path)?, file)?;
+                    file.sync_all()
+                })?;
             }
         }
-    } else {
-        std::fs::File::create(&target_path)?;
-    }
+        None => replace_file(&target_path, |_| Ok(()))?,
+    }
+    crate::audit::record(
+        crate::audit::AuditEvent::Upload,
+        crate::audit::AuditRecord::for_request(request, "success")
+            .with_path(target_path.display().to_string())
+            .with(
+                "size",
+                std::fs::metadata(&target_path).map_or(0, |meta| meta.len()),
+            )
+            .with("source", "webdav"),
+    );
 
     if existed {
         Ok(status_response(204, "No Content"))
@@ -515,7 +532,8 @@ fn handle_delete(request: &Request, base_dir: &Path) -> Result<Response, AppErro
         }
     }
 
-    if target_path.is_dir() {
+    let is_dir = target_path.is_dir();
+    if is_dir {
         std::fs::remove_dir_all(&target_path)?;
         remove_locks_for_subtree(&target_path);
         remove_dead_props_for_subtree(&target_path);
@@ -526,6 +544,23 @@ fn handle_delete(request: &Request, base_dir: &Path) -> Result<Response, AppErro
     }
 
     debug!("WebDAV DELETE success target={}", target_path.display());
+    let deleted = request_path_only(&request.path);
+    crate::hooks::emit(
+        crate::hooks::HookEvent::FileDeleted,
+        &[
+            ("path", deleted.as_str().into()),
+            ("directory", is_dir.into()),
+            ("source", "webdav".into()),
+            ("client", crate::hooks::client_of(request).into()),
+        ],
+    );
+    crate::audit::record(
+        crate::audit::AuditEvent::Delete,
+        crate::audit::AuditRecord::for_request(request, "success")
+            .with_path(deleted)
+            .with("directory", is_dir)
+            .with("source", "webdav"),
+    );
     Ok(status_response(204, "No Content"))
 }
 
@@ -870,12 +905,15 @@ fn extract_destination_path(
         {
             return Err(AppError::BadRequest);
         }
-        let raw_path = decode_percent_path(&rest[path_start..])?;
+        // Split off any query before decoding so an escaped `?` stays in the name
+        let target = &rest[path_start..];
+        let target = target.split(['?', '#']).next().unwrap_or(target);
+        let raw_path = crate::utils::decode_path(target)?;
 
         // Strip the base_path prefix if configured
         let bp = crate::templates::base_path();
         if !bp.is_empty() {
-            if let Some(stripped) = raw_path.strip_prefix(bp) {
+            if let Some(stripped) = raw_path.strip_prefix(bp.as_ref()) {
                 let normalized = if stripped.is_empty() || !stripped.starts_with('/') {
                     format!("/{stripped}")
                 } else {
@@ -892,28 +930,6 @@ fn extract_destination_path(
     Err(AppError::BadRequest)
 }
 
-fn decode_percent_path(path: &str) -> Result<String, AppError> {
-    let bytes = path.as_bytes();
-    let mut out = Vec::with_capacity(bytes.len());
-    let mut i = 0usize;
-    while i < bytes.len() {
-        if bytes[i] == b'%' {
-            if i + 2 >= bytes.len() {
-                return Err(AppError::BadRequest);
-            }
-            let hex =
-                std::str::from_utf8(&bytes[i + 1..i + 3]).map_err(|_| AppError::BadRequest)?;
-            let byte = u8::from_str_radix(hex, 16).map_err(|_| AppError::BadRequest)?;
-            out.push(byte);
-            i += 3;
-        } else {
-            out.push(bytes[i]);
-            i += 1;
-        }
-    }
-    String::from_utf8(out).map_err(|_| AppError::BadRequest)
-}
-
 fn copy_path_recursive(source: &Path, destination: &Path) -> Result<(), AppError> {
     if source.is_dir() {
         std::fs::create_dir_all(destination)?;
@@ -1502,8 +1518,7 @@ fn if_header_matches_lock_token(if_header: &str, expected_token: &str, request_p
 }
 
 fn request_path_only(path: &str) -> String {
-    let only_path = path.split('?').next().unwrap_or(path);
-    format!("/{}", only_path.trim_start_matches('/'))
+    format!("/{}", path.trim_start_matches('/'))
 }
 
 fn if_header_resource_tag_matches(prefix: &str, request_path: &str) -> bool {
@@ -1570,6 +1585,33 @@ fn locks_map() -> &'static Mutex<HashMap<String, DavLock>> {
     DAV_LOCKS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
+/// Replace `target` with a file written by `fill` into a temporary file in the
+/// same folder. Renaming it into place leaves other hard links to the old
+/// file, such as deduplicated uploads, untouched.
+pub(crate) fn replace_file(
+    target: &Path,
+    fill: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> Result<(), AppError> {
+    let dir = target.parent().ok_or(AppError::InvalidPath)?;
+    let temp_path = dir.join(format!(
+        "{}put_{}_{}.tmp",
+        crate::upload::TEMP_FILE_PREFIX,
+        std::process::id(),
+        REPLACE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = std::fs::OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(&temp_path)
+        .and_then(|mut file| fill(&mut file))
+        .and_then(|()| std::fs::rename(&temp_path, target));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
 fn op_guard() -> &'static Mutex<()> {
     DAV_OP_GUARD.get_or_init(|| Mutex::new(()))
 }
@@ -1699,7 +1741,7 @@ fn lockdiscovery_xml(
     )
 }
 
-fn status_response(status_code: u16, status_text: &str) -> Response {
+pub(crate) fn status_response(status_code: u16, status_text: &str) -> Response {
     if status_code == 423 {
         let mut headers = HashMap::new();
         headers.insert(
@@ -1756,9 +1798,11 @@ fn resolve_request_path_without_canonicalize(
     base_dir: &Path,
     request_path: &str,
 ) -> Result<PathBuf, AppError> {
-    let path_only = request_path.split('?').next().unwrap_or(request_path);
-    let requested_path = PathBuf::from(path_only.strip_prefix('/').unwrap_or(path_only));
-    let safe_path = normalize_relative_path(&requested_path)?;
+    let requested_path = PathBuf::from(request_path.strip_prefix('/').unwrap_or(request_path));
+    let safe_path = crate::utils::normalize_path(&requested_path)?;
+    if crate::utils::has_unusable_component(&safe_path) {
+        return Err(AppError::NotFound);
+    }
     let full_path = base_dir.join(safe_path);
     if !full_path.starts_with(base_dir) {
         return Err(AppError::Forbidden);
@@ -1766,10 +1810,20 @@ fn resolve_request_path_without_canonicalize(
     Ok(full_path)
 }
 
-fn resolve_request_path(base_dir: &Path, request_path: &str) -> Result<PathBuf, AppError> {
-    let path_only = request_path.split('?').next().unwrap_or(request_path);
-    let requested_path = PathBuf::from(path_only.strip_prefix('/').unwrap_or(path_only));
-    let safe_path = normalize_relative_path(&requested_path)?;
+pub(crate) fn resolve_request_path(
+    base_dir: &Path,
+    request_path: &str,
+) -> Result<PathBuf, AppError> {
+    let requested_path = PathBuf::from(request_path.strip_prefix('/').unwrap_or(request_path));
+    let mut safe_path = crate::utils::normalize_path(&requested_path)?;
+    if !base_dir.join(&safe_path).exists()
+        && let Some(variant) = crate::utils::find_unicode_variant(base_dir, &safe_path)
+    {
+        safe_path = variant;
+    }
+    if crate::utils::has_unusable_component(&safe_path) {
+        return Err(AppError::NotFound);
+    }
     let full_path = base_dir.join(safe_path);
     if !full_path.starts_with(base_dir) {
         return Err(AppError::Forbidden);
@@ -1796,22 +1850,6 @@ fn resolve_request_path(base_dir: &Path, request_path: &str) -> Result<PathBuf,
     Ok(full_path)
 }
 
-fn normalize_relative_path(path: &Path) -> Result<PathBuf, AppError> {
-    let mut components = Vec::new();
-    for component in path.components() {
-        match component {
-            Component::Normal(name) => components.push(name),
-            Component::ParentDir => {
-                if components.pop().is_none() {
-                    return Err(AppError::Forbidden);
-                }
-            }
-            _ => {}
-        }
-    }
-    Ok(components.iter().collect())
-}
-
 fn append_multistatus_response(
     xml: &mut String,
     base_dir: &Path,
@@ -2004,17 +2042,6 @@ fn build_live_props(
     props
 }
 
-fn etag_for_resource(path: &Path, metadata: &std::fs::Metadata) -> String {
-    let modified = metadata
-        .modified()
-        .ok()
-        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-        .map(|d| d.as_secs())
-        .unwrap_or_default();
-    let seed = path.to_string_lossy();
-    format!("\"{:x}-{:x}-{:x}\"", metadata.len(), modified, seed.len())
-}
-
 fn build_href(base_dir: &Path, resource: &Path, is_dir: bool) -> String {
     let bp = crate::templates::base_path();
 
@@ -2062,31 +2089,6 @@ fn xml_escape(value: &str) -> String {
         .replace('\'', "&apos;")
 }
 
-fn format_http_date(time: SystemTime) -> Option<String> {
-    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
-    const MONTHS: [&str; 12] = [
-        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
-    ];
-
-    let total_seconds = time.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
-    let days = total_seconds.div_euclid(86_400);
-    let secs_of_day = total_seconds.rem_euclid(86_400);
-
-    let hour = (secs_of_day / 3600) as u32;
-    let minute = ((secs_of_day % 3600) / 60) as u32;
-    let second = (secs_of_day % 60) as u32;
-
-    let weekday_idx = (days.rem_euclid(7)) as usize;
-    let weekday = WEEKDAYS[weekday_idx];
-
-    let (year, month, day) = civil_from_days(days);
-    let month_name = MONTHS[(month - 1) as usize];
-
-    Some(format!(
-        "{weekday}, {day:02} {month_name} {year:04} {hour:02}:{minute:02}:{second:02} GMT"
-    ))
-}
-
 fn format_iso8601_utc(time: SystemTime) -> Option<String> {
     let total_seconds = time.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
     let days = total_seconds.div_euclid(86_400);
@@ -2100,19 +2102,3 @@ fn format_iso8601_utc(time: SystemTime) -> Option<String> {
         "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z"
     ))
 }
-
-fn civil_from_days(days_since_epoch: i64) -> (i32, u32, u32) {
-    let z = days_since_epoch + 719_468;
-    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
-    let doe = z - era * 146_097;
-    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
-    let mut year = (yoe as i32) + era as i32 * 400;
-    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
-    let mp = (5 * doy + 2) / 153;
-    let day = doy - (153 * mp + 2) / 5 + 1;
-    let month = mp + if mp < 10 { 3 } else { -9 };
-    if month <= 2 {
-        year += 1;
-    }
-    (year, month as u32, day as u32)
-}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: MIT
+
+//! Admission control for blocking file-request work
+//!
+//! Directory listings, file opens and other filesystem-heavy handlers run on
+//! Tokio's blocking pool. That pool already scales itself: threads are started
+//! when work arrives and exit after sitting idle for a few seconds, so an idle
+//! server holds none. What it lacks is backpressure, since extra jobs simply
+//! pile up in its queue.
+//!
+//! With `--max-workers` set, at most that many handler jobs run at once. Up
+//! to `--worker-queue` further requests wait for a worker (at most
+//! [`QUEUE_WAIT`]); anything beyond that gets `503 Service Unavailable` with
+//! `Retry-After`. Busy workers and queue depth are reported under `workers`
+//! in the monitor JSON.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Default `--worker-queue`
+pub const DEFAULT_QUEUE: usize = 256;
+
+/// Longest a queued request waits for a worker
+pub const QUEUE_WAIT: Duration = Duration::from_secs(10);
+
+/// Worker accounting shared by every connection
+#[derive(Debug)]
+pub struct WorkerPool {
+    /// 0 means unlimited
+    max_workers: AtomicUsize,
+    queue_capacity: AtomicUsize,
+    busy: AtomicUsize,
+    queued: AtomicUsize,
+    completed: AtomicU64,
+    rejected: AtomicU64,
+    released: Notify,
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        Self {
+            max_workers: AtomicUsize::new(0),
+            queue_capacity: AtomicUsize::new(DEFAULT_QUEUE),
+            busy: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            released: Notify::new(),
+        }
+    }
+}
+
+/// Held while a job runs; frees the worker on drop
+#[derive(Debug)]
+struct Worker {
+    pool: Arc<WorkerPool>,
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.pool.busy.fetch_sub(1, Ordering::AcqRel);
+        self.pool.completed.fetch_add(1, Ordering::AcqRel);
+        self.pool.released.notify_one();
+    }
+}
+
+impl WorkerPool {
+    /// Set the worker limit (`None` = unlimited) and how many requests may wait
+    pub fn configure(&self, max_workers: Option<usize>, queue_capacity: usize) {
+        self.max_workers
+            .store(max_workers.unwrap_or(0), Ordering::Release);
+        self.queue_capacity.store(queue_capacity, Ordering::Release);
+    }
+
+    pub fn max_workers(&self) -> Option<usize> {
+        match self.max_workers.load(Ordering::Acquire) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    pub fn busy(&self) -> usize {
+        self.busy.load(Ordering::Acquire)
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Acquire)
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Acquire)
+    }
+
+    /// `"workers"` object for the monitor JSON
+    pub fn usage_json(&self) -> String {
+        format!(
+            r#""workers":{{"busy":{},"max":{},"queued":{},"queue_capacity":{},"completed":{},"rejected":{}}}"#,
+            self.busy(),
+            self.max_workers()
+                .map_or("null".to_string(), |limit| limit.to_string()),
+            self.queued(),
+            self.queue_capacity.load(Ordering::Acquire),
+            self.completed.load(Ordering::Acquire),
+            self.rejected()
+        )
+    }
+
+    fn try_acquire(self: &Arc<Self>) -> Option<Worker> {
+        let limit = self.max_workers();
+        let mut busy = self.busy.load(Ordering::Acquire);
+        loop {
+            if limit.is_some_and(|limit| busy >= limit) {
+                return None;
+            }
+            match self.busy.compare_exchange_weak(
+                busy,
+                busy + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    return Some(Worker {
+                        pool: Arc::clone(self),
+                    });
+                }
+                Err(current) => busy = current,
+            }
+        }
+    }
+
+    async fn acquire(self: &Arc<Self>, wait: Duration) -> Option<Worker> {
+        if let Some(worker) = self.try_acquire() {
+            return Some(worker);
+        }
+
+        let capacity = self.queue_capacity.load(Ordering::Acquire);
+        let position = self.queued.fetch_add(1, Ordering::AcqRel);
+        if position >= capacity {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+            self.rejected.fetch_add(1, Ordering::AcqRel);
+            return None;
+        }
+
+        let deadline = tokio::time::Instant::now() + wait;
+        let worker = loop {
+            // Register for wakeups before checking, so a release in between is not missed
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            if let Some(worker) = self.try_acquire() {
+                break Some(worker);
+            }
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                break self.try_acquire();
+            }
+        };
+
+        self.queued.fetch_sub(1, Ordering::AcqRel);
+        if worker.is_none() {
+            self.rejected.fetch_add(1, Ordering::AcqRel);
+        }
+        worker
+    }
+
+    /// Run `job` on the blocking pool once a worker is free
+    ///
+    /// Returns `None` when the queue is full or the wait timed out, which
+    /// callers answer with 503.
+    pub async fn run<F, R>(self: &Arc<Self>, job: F) -> Option<Result<R, tokio::task::JoinError>>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let worker = self.acquire(QUEUE_WAIT).await?;
+        let result = tokio::task::spawn_blocking(job).await;
+        drop(worker);
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_workers_queue_and_reject() {
+        let pool = Arc::new(WorkerPool::default());
+        pool.configure(Some(1), 1);
+
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let running = {
+            let pool = pool.clone();
+            tokio::spawn(async move { pool.run(move || release_rx.recv().is_ok()).await })
+        };
+        while pool.busy() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // One request fits in the queue and runs once the worker is free
+        let waiting = {
+            let pool = pool.clone();
+            tokio::spawn(async move { pool.run(|| 7).await })
+        };
+        while pool.queued() == 0 {
+            tokio::task::yield_now().await;
+        }
+        // The queue is full, so the next request is turned away immediately
+        assert!(pool.run(|| 0).await.is_none());
+        assert_eq!(pool.rejected(), 1);
+        assert!(pool.usage_json().contains(r#""busy":1,"max":1,"queued":1"#));
+
+        release_tx.send(()).unwrap();
+        assert!(running.await.unwrap().unwrap().unwrap());
+        assert_eq!(waiting.await.unwrap().unwrap().unwrap(), 7);
+        assert_eq!(pool.busy(), 0);
+        assert_eq!(pool.queued(), 0);
+        assert!(pool.usage_json().contains(r#""completed":2"#));
+    }
+}
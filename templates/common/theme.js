@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: MIT
+
+// IronDrop theme selection
+// Loaded in <head> so the saved theme applies before the first paint. The
+// server default comes from data-theme on <html> ("dark", "light" or "auto");
+// a choice made with the header toggle is kept in localStorage.
+(function () {
+    'use strict';
+
+    var STORAGE_KEY = 'irondrop-theme';
+    var root = document.documentElement;
+
+    function storedTheme() {
+        try {
+            var value = localStorage.getItem(STORAGE_KEY);
+            return value === 'light' || value === 'dark' ? value : null;
+        } catch (e) {
+            return null;
+        }
+    }
+
+    function systemTheme() {
+        return window.matchMedia && window.matchMedia('(prefers-color-scheme: light)').matches
+            ? 'light'
+            : 'dark';
+    }
+
+    var serverDefault = root.getAttribute('data-theme');
+    var initial = storedTheme()
+        || (serverDefault === 'light' || serverDefault === 'dark' ? serverDefault : systemTheme());
+    root.setAttribute('data-theme', initial);
+
+    function toggleTheme() {
+        var next = root.getAttribute('data-theme') === 'light' ? 'dark' : 'light';
+        root.setAttribute('data-theme', next);
+        try {
+            localStorage.setItem(STORAGE_KEY, next);
+        } catch (e) {
+            // Private browsing: the choice lasts for this page only
+        }
+    }
+
+    document.addEventListener('DOMContentLoaded', function () {
+        var button = document.getElementById('themeToggle');
+        if (button) {
+            button.addEventListener('click', toggleTheme);
+        }
+    });
+})();
@@ -646,7 +646,8 @@ document.addEventListener('DOMContentLoaded', function() {
                     return;
                 }
                 
-                const results = await response.json();
+                const payload = await response.json();
+                const results = payload.results || [];
                 console.log(`API search found ${results.length} results`);
                 
                 // Double-check query is still current after API response
@@ -658,7 +659,7 @@ document.addEventListener('DOMContentLoaded', function() {
                 
                 // Show dropdown with results
                 if (results.length > 0) {
-                    showDropdown(results, query);
+                    showDropdown(results, query, payload.index_state);
                     announceToScreenReader(`Found ${results.length} additional results in subdirectories`);
                 }
                 
@@ -669,18 +670,18 @@ document.addEventListener('DOMContentLoaded', function() {
         
         // Note: dropdown and selectedDropdownIndex are now global variables
         
-        function showDropdown(results, query) {
+        function showDropdown(results, query, indexState) {
             // Remove existing dropdown with proper cleanup
             if (dropdown) {
                 hideDropdown();
                 // Wait for cleanup to complete before creating new dropdown
-                setTimeout(() => createDropdown(results, query), 160);
+                setTimeout(() => createDropdown(results, query, indexState), 160);
             } else {
-                createDropdown(results, query);
+                createDropdown(results, query, indexState);
             }
         }
         
-        function createDropdown(results, query) {
+        function createDropdown(results, query, indexState) {
             if (results.length === 0) return;
             
             // Verify query is still current
@@ -696,7 +697,7 @@ document.addEventListener('DOMContentLoaded', function() {
             dropdown.style.transform = 'translateY(-10px)';
             dropdown.innerHTML = `
                 <div class="dropdown-header">
-                    <span>Files in subdirectories (${results.length})</span>
+                    <span>Files in subdirectories (${results.length})${indexState === 'building' ? ' · index building…' : ''}</span>
                 </div>
                 <div class="dropdown-results"></div>
             `;
@@ -709,19 +710,22 @@ document.addEventListener('DOMContentLoaded', function() {
                 item.className = 'dropdown-item';
                 
                 const icon = result.type === 'directory' ? '📁' : '📄';
-                const highlightedName = highlightText(result.name, query);
+                // Escaped by the server, with the matched parts in <mark>
+                const highlightedName = result.highlighted;
                 
                 item.innerHTML = `
                     <span class="dropdown-icon">${icon}</span>
                     <div class="dropdown-info">
                         <div class="dropdown-name">${highlightedName}</div>
-                        <div class="dropdown-path">${result.path}</div>
+                        <div class="dropdown-path"></div>
                     </div>
                     <div class="dropdown-size">${result.size}</div>
                 `;
+                item.querySelector('.dropdown-path').textContent = result.path;
                 
                 item.addEventListener('click', () => {
-                    window.location.href = basePath + result.path;
+                    // Percent-encoded and prefixed with the base path by the server
+                    window.location.href = result.url;
                 });
                 
                 dropdownResults.appendChild(item);
@@ -757,15 +761,6 @@ document.addEventListener('DOMContentLoaded', function() {
             }
         }
         
-        function highlightText(text, query) {
-            const index = text.toLowerCase().indexOf(query.toLowerCase());
-            if (index === -1) return text;
-            
-            return text.slice(0, index) + 
-                   '<mark>' + text.slice(index, index + query.length) + '</mark>' +
-                   text.slice(index + query.length);
-        }
-        
         // Dropdown navigation functions
         
         function navigateDropdown(direction) {
@@ -793,4 +788,195 @@ document.addEventListener('DOMContentLoaded', function() {
             selectedDropdownIndex = -1;
         }
     }
-});
\ No newline at end of file
+});
+
+// File management controls (rename/move and delete), present only when
+// the server runs with --enable-file-management
+document.addEventListener('click', function(event) {
+    const button = event.target.closest('.file-action');
+    if (!button) return;
+    event.preventDefault();
+
+    const basePath = window.__BASE_PATH || '';
+    const path = button.dataset.path;
+    const name = button.dataset.name;
+    let payload;
+
+    if (button.dataset.action === 'delete') {
+        if (!confirm(`Delete "${name}"? This cannot be undone.`)) return;
+        payload = { action: 'delete', path: path };
+    } else {
+        const to = prompt('New name or destination path:', path);
+        if (!to || to === path) return;
+        // A bare name renames in place; a path starting with "/" moves
+        const destination = to.startsWith('/')
+            ? to
+            : path.substring(0, path.lastIndexOf('/') + 1) + to;
+        payload = { action: 'move', path: path, to: destination };
+    }
+
+    button.disabled = true;
+    fetch(`${basePath}/_irondrop/files`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(payload)
+    })
+        .then(response => response.json()
+            .catch(() => ({}))
+            .then(body => ({ ok: response.ok, status: response.status, body: body })))
+        .then(result => {
+            if (result.ok) {
+                window.location.reload();
+                return;
+            }
+            const message = result.body.error || `Request failed (${result.status})`;
+            alert(message);
+            button.disabled = false;
+        })
+        .catch(error => {
+            alert(`Request failed: ${error.message}`);
+            button.disabled = false;
+        });
+});
+
+// "New folder", present only with --enable-file-management
+document.addEventListener('click', function(event) {
+    const button = event.target.closest('#new-folder');
+    if (!button) return;
+    event.preventDefault();
+
+    const name = prompt('New folder name:');
+    if (!name || !name.trim()) return;
+
+    const basePath = window.__BASE_PATH || '';
+    button.disabled = true;
+    fetch(`${basePath}/_api/mkdir`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ path: button.dataset.path, name: name.trim() })
+    })
+        .then(response => response.json()
+            .catch(() => ({}))
+            .then(body => ({ ok: response.ok, status: response.status, body: body })))
+        .then(result => {
+            if (result.ok) {
+                window.location.reload();
+                return;
+            }
+            alert(result.body.error || `Request failed (${result.status})`);
+            button.disabled = false;
+        })
+        .catch(error => {
+            alert(`Request failed: ${error.message}`);
+            button.disabled = false;
+        });
+});
+
+// Checked entries are fetched as one archive from /_api/archive
+function updateSelectionBar() {
+    const bar = document.getElementById('selection-bar');
+    if (!bar) return;
+    const count = document.querySelectorAll('.entry-select:checked').length;
+    bar.hidden = count === 0;
+    document.getElementById('selection-count').textContent =
+        count === 1 ? '1 item selected' : `${count} items selected`;
+}
+
+document.addEventListener('change', function(event) {
+    if (event.target.classList.contains('entry-select')) updateSelectionBar();
+});
+
+document.addEventListener('click', function(event) {
+    const button = event.target.closest('.selection-download');
+    if (!button) return;
+    event.preventDefault();
+
+    const basePath = window.__BASE_PATH || '';
+    const paths = Array.from(document.querySelectorAll('.entry-select:checked'))
+        .map(input => input.value);
+    if (paths.length === 0) return;
+
+    button.disabled = true;
+    fetch(`${basePath}/_api/archive`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ paths: paths, format: button.dataset.format })
+    })
+        .then(response => {
+            if (!response.ok) throw new Error(`Request failed (${response.status})`);
+            const disposition = response.headers.get('Content-Disposition') || '';
+            const match = disposition.match(/filename="([^"]+)"/);
+            const filename = match ? match[1] : `selection.${button.dataset.format}`;
+            return response.blob().then(blob => ({ blob: blob, filename: filename }));
+        })
+        .then(result => {
+            const url = URL.createObjectURL(result.blob);
+            const link = document.createElement('a');
+            link.href = url;
+            link.download = result.filename;
+            document.body.appendChild(link);
+            link.click();
+            link.remove();
+            setTimeout(() => URL.revokeObjectURL(url), 1000);
+        })
+        .catch(error => alert(error.message))
+        .finally(() => { button.disabled = false; });
+});
+
+// Gallery lightbox: a click on a thumbnail shows the full picture, arrow keys
+// step through the pictures of this page and Escape closes it
+(function() {
+    const lightbox = document.getElementById('lightbox');
+    if (!lightbox) return;
+    const image = lightbox.querySelector('.lightbox-image');
+    const caption = lightbox.querySelector('.lightbox-caption');
+    const links = Array.from(document.querySelectorAll('.gallery-link'));
+    let current = -1;
+    let opener = null;
+
+    function show(index) {
+        if (links.length === 0) return;
+        current = (index + links.length) % links.length;
+        const link = links[current];
+        const thumbnail = link.querySelector('img');
+        image.src = link.href;
+        image.alt = thumbnail ? thumbnail.alt : '';
+        caption.textContent = image.alt;
+    }
+
+    function close() {
+        lightbox.hidden = true;
+        image.removeAttribute('src');
+        current = -1;
+        if (opener) opener.focus();
+    }
+
+    links.forEach((link, index) => {
+        link.addEventListener('click', function(event) {
+            if (event.ctrlKey || event.metaKey || event.shiftKey) return;
+            event.preventDefault();
+            opener = link;
+            show(index);
+            lightbox.hidden = false;
+            lightbox.querySelector('.lightbox-close').focus();
+        });
+    });
+
+    lightbox.addEventListener('click', function(event) {
+        if (event.target.closest('.lightbox-prev')) show(current - 1);
+        else if (event.target.closest('.lightbox-next')) show(current + 1);
+        else if (event.target.closest('.lightbox-close') || event.target === lightbox) close();
+    });
+
+    // Registered for the capture phase so the listing's own keyboard
+    // navigation does not see keys while the lightbox is open
+    document.addEventListener('keydown', function(event) {
+        if (lightbox.hidden) return;
+        if (event.key === 'Escape') close();
+        else if (event.key === 'ArrowLeft') show(current - 1);
+        else if (event.key === 'ArrowRight') show(current + 1);
+        else return;
+        event.preventDefault();
+        event.stopPropagation();
+    }, true);
+})();
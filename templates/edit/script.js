@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MIT
+
+// Text file editor: saves with If-Match so concurrent changes are not lost
+(function() {
+    const form = document.getElementById('edit-form');
+    if (!form) return;
+    const editor = document.getElementById('editor');
+    const status = document.getElementById('edit-status');
+    const saveButton = document.getElementById('edit-save');
+    let saved = editor.value;
+
+    window.addEventListener('beforeunload', function(event) {
+        if (editor.value !== saved) {
+            event.preventDefault();
+            event.returnValue = '';
+        }
+    });
+
+    // Tab inserts a tab instead of leaving the textarea
+    editor.addEventListener('keydown', function(event) {
+        if (event.key !== 'Tab' || event.shiftKey || event.ctrlKey || event.metaKey) return;
+        event.preventDefault();
+        editor.setRangeText('\t', editor.selectionStart, editor.selectionEnd, 'end');
+    });
+
+    // Ctrl+S / Cmd+S saves
+    document.addEventListener('keydown', function(event) {
+        if ((event.ctrlKey || event.metaKey) && event.key === 's') {
+            event.preventDefault();
+            form.requestSubmit();
+        }
+    });
+
+    form.addEventListener('submit', function(event) {
+        event.preventDefault();
+        const text = editor.value;
+        // Textareas always use \n; keep the file's own line endings
+        const body = form.dataset.lineEnding === 'crlf' ? text.replace(/\n/g, '\r\n') : text;
+
+        saveButton.disabled = true;
+        status.textContent = 'Saving...';
+        fetch(form.dataset.saveHref, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'text/plain; charset=utf-8',
+                'If-Match': form.dataset.etag
+            },
+            body: body
+        })
+            .then(response => response.json()
+                .catch(() => ({}))
+                .then(result => ({ ok: response.ok, status: response.status, body: result })))
+            .then(result => {
+                if (result.ok) {
+                    form.dataset.etag = result.body.etag;
+                    saved = text;
+                    status.textContent = `Saved at ${new Date().toLocaleTimeString()}`;
+                } else if (result.status === 412) {
+                    status.textContent = 'Not saved: the file was changed elsewhere. Copy your text and reload.';
+                } else {
+                    status.textContent = result.body.error || `Save failed (${result.status})`;
+                }
+            })
+            .catch(error => {
+                status.textContent = `Save failed: ${error.message}`;
+            })
+            .finally(() => { saveButton.disabled = false; });
+    });
+})();
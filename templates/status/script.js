@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT
+// Status dashboard - polls /_status?json=1 and fills in the cards
+
+(function() {
+    const basePath = window.__BASE_PATH || '';
+    const REFRESH_MS = 5000;
+
+    function humanBytes(bytes) {
+        if (bytes === null || bytes === undefined) return 'n/a';
+        if (bytes < 1024) return bytes + ' B';
+        const units = ['KB', 'MB', 'GB', 'TB'];
+        let unitIndex = -1;
+        do {
+            bytes /= 1024;
+            unitIndex++;
+        } while (bytes >= 1024 && unitIndex < units.length - 1);
+        return bytes.toFixed(2) + ' ' + units[unitIndex];
+    }
+
+    function prettyUptime(seconds) {
+        const days = Math.floor(seconds / 86400);
+        const hours = Math.floor((seconds % 86400) / 3600);
+        const minutes = Math.floor((seconds % 3600) / 60);
+        const parts = [];
+        if (days) parts.push(days + 'd');
+        if (days || hours) parts.push(hours + 'h');
+        parts.push(minutes + 'm');
+        return parts.join(' ');
+    }
+
+    function setText(id, value) {
+        const element = document.getElementById(id);
+        if (element) element.textContent = value;
+    }
+
+    function setState(kind, text) {
+        const element = document.getElementById('status_state');
+        if (!element) return;
+        element.className = 'status-state ' + kind;
+        element.textContent = text;
+    }
+
+    function render(data) {
+        const r = data.requests;
+        setText('req_total', r.total.toLocaleString());
+        setText('req_successful', r.successful.toLocaleString());
+        setText('req_errors', r.errors.toLocaleString());
+        setText('req_timeouts', r.timeouts.toLocaleString());
+
+        const d = data.downloads;
+        setText('bytes_served', humanBytes(d.bytes_served));
+        setText('downloads_active', d.active);
+        setText('downloads_queued', d.queued);
+
+        const u = data.uploads;
+        setText('up_total', u.total_uploads.toLocaleString());
+        setText('up_failed', u.failed_uploads.toLocaleString());
+        setText('up_files', u.files_uploaded.toLocaleString());
+        setText('up_bytes', humanBytes(u.upload_bytes));
+        setText('up_concurrent', u.concurrent_uploads);
+
+        const m = data.memory;
+        setText('mem_current', m.available ? humanBytes(m.current_bytes) : 'n/a');
+        setText('mem_peak', m.available ? humanBytes(m.peak_bytes) : 'n/a');
+
+        const rl = data.rate_limiter;
+        setText('rl_entries', rl.available ? rl.entries.toLocaleString() : 'n/a');
+        setText('rl_memory', rl.available ? humanBytes(rl.memory_bytes) : 'n/a');
+        const limits = rl.limits;
+        const perMinute = v => (v === null ? 'unlimited' : v + '/min');
+        setText('rl_limit', limits ? limits.requests_per_minute + '/min, ' + limits.concurrent_per_ip + ' open' : 'disabled');
+        setText('rl_upload', limits ? perMinute(limits.routes.upload) : 'disabled');
+        setText('rl_search', limits ? perMinute(limits.routes.search) : 'disabled');
+
+        const s = data.search;
+        setText('search_entries', s.available ? s.entries.toLocaleString() : 'n/a');
+        setText('search_memory', s.available ? humanBytes(s.memory_bytes) : 'n/a');
+        setText('search_updating', s.available ? (s.updating ? 'yes' : 'no') : 'n/a');
+
+        const disk = data.disk;
+        setText('disk_free', disk.available ? humanBytes(disk.free_bytes) : 'n/a');
+        setText('disk_total', disk.available ? humanBytes(disk.total_bytes) : 'n/a');
+        setText('disk_reserve', disk.available ? humanBytes(disk.reserve_bytes) : 'n/a');
+        setText('disk_reserved', humanBytes(disk.reserved_bytes));
+
+        setText('uptime', prettyUptime(data.uptime_secs));
+    }
+
+    async function refresh() {
+        try {
+            const response = await fetch(basePath + '/_status?json=1', { cache: 'no-store' });
+            if (!response.ok) throw new Error('HTTP ' + response.status);
+            render(await response.json());
+            setState('ok', 'Online');
+        } catch (error) {
+            setState('error', 'Unavailable: ' + error.message);
+        } finally {
+            setText('last_updated', new Date().toLocaleTimeString());
+        }
+    }
+
+    document.addEventListener('DOMContentLoaded', function() {
+        refresh();
+        setInterval(refresh, REFRESH_MS);
+    });
+})();
@@ -401,11 +401,23 @@ class UploadManager {
         const urlParams = new URLSearchParams(window.location.search);
         const uploadTo = urlParams.get('upload_to');
 
+        const params = new URLSearchParams();
         if (uploadTo) {
-            return `${this.basePath}/_irondrop/upload?upload_to=${encodeURIComponent(uploadTo)}`;
-        } else {
-            return `${this.basePath}/_irondrop/upload`;
+            params.set('upload_to', uploadTo);
+        }
+
+        // Optional description and tags, stored next to each uploaded file
+        const description = document.getElementById('uploadDescription')?.value.trim();
+        const tags = document.getElementById('uploadTags')?.value.trim();
+        if (description) {
+            params.set('description', description);
         }
+        if (tags) {
+            params.set('tags', tags);
+        }
+
+        const query = params.toString();
+        return `${this.basePath}/_irondrop/upload${query ? `?${query}` : ''}`;
     }
 
     updateSummary() {
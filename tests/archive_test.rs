@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: MIT
+//! Directory downloads as `.tar` and `.tar.gz` (`?download=`) and archives of
+//! selected entries (`POST /_api/archive`).
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let docs = dir.path().join("docs");
+    fs::create_dir_all(docs.join("sub")).unwrap();
+    fs::create_dir_all(docs.join("private")).unwrap();
+    fs::write(docs.join("notes.txt"), "hello tar").unwrap();
+    fs::write(docs.join("sub").join("data.txt"), "nested").unwrap();
+    fs::write(docs.join("image.bin"), "not allowed").unwrap();
+    fs::write(docs.join(".hidden.txt"), "dotfile").unwrap();
+    fs::write(docs.join("private").join("secret.txt"), "secret").unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--allowed-extensions".to_string(),
+        "*.txt".to_string(),
+        "--auth-paths".to_string(),
+        "/docs/private=boss:pw".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        _dir: dir,
+    }
+}
+
+/// Names and contents of the entries of a ustar archive
+fn entries(tar: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while pos + 512 <= tar.len() && tar[pos..pos + 512].iter().any(|&b| b != 0) {
+        let header = &tar[pos..pos + 512];
+        let text = |range: std::ops::Range<usize>| {
+            let bytes = &header[range];
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).into_owned()
+        };
+        let size = usize::from_str_radix(&text(124..135), 8).unwrap();
+        let content = tar[pos + 512..pos + 512 + size].to_vec();
+        entries.push((text(0..100), content));
+        pos += 512 + size.div_ceil(512) * 512;
+    }
+    entries
+}
+
+#[test]
+fn test_directory_tar_download() {
+    let server = setup_server();
+    let client = Client::new();
+
+    let res = client
+        .get(format!("http://{}/docs/?download=tar", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/x-tar");
+    assert!(
+        res.headers()[CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .contains("filename=\"docs.tar\"")
+    );
+    let tar = res.bytes().unwrap();
+    let entries = entries(&tar);
+    let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+    // Dotfiles, disallowed extensions and password-protected folders stay out
+    assert_eq!(
+        names,
+        ["docs/", "docs/notes.txt", "docs/sub/", "docs/sub/data.txt"]
+    );
+    assert_eq!(entries[1].1, b"hello tar");
+    assert_eq!(entries[3].1, b"nested");
+}
+
+#[test]
+fn test_directory_tar_gz_download() {
+    let server = setup_server();
+    let client = Client::new();
+
+    let res = client
+        .get(format!("http://{}/docs/sub/?download=tar.gz", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/gzip");
+    assert!(
+        res.headers()[CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .contains("filename=\"sub.tar.gz\"")
+    );
+    let body = res.bytes().unwrap();
+    assert_eq!(&body[..2], &[0x1f, 0x8b]);
+
+    let res = client
+        .get(format!("http://{}/docs/?download=zip", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_selected_entries_archive() {
+    let server = setup_server();
+    let client = Client::new();
+    let url = format!("http://{}/_api/archive", server.addr);
+
+    let res = client
+        .post(&url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(r#"{"paths":["/docs/notes.txt","/docs/sub","/docs/notes.txt"]}"#)
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/x-tar");
+    assert!(
+        res.headers()[CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .contains("filename=\"docs-selection.tar\"")
+    );
+    let tar = res.bytes().unwrap();
+    let entries = entries(&tar);
+    let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["notes.txt", "sub/", "sub/data.txt"]);
+    assert_eq!(entries[0].1, b"hello tar");
+
+    let res = client
+        .post(&url)
+        .body(r#"{"paths":["/docs/sub/data.txt","/docs/notes.txt"],"format":"tar.gz"}"#)
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CONTENT_TYPE], "application/gzip");
+    assert_eq!(&res.bytes().unwrap()[..2], &[0x1f, 0x8b]);
+}
+
+#[test]
+fn test_selected_entries_archive_validation() {
+    let server = setup_server();
+    let client = Client::new();
+    let post = |body: &str| {
+        client
+            .post(format!("http://{}/_api/archive", server.addr))
+            .body(body.to_string())
+            .send()
+            .unwrap()
+            .status()
+    };
+
+    assert_eq!(
+        post(r#"{"paths":["/docs/../../etc/passwd"]}"#),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        post(r#"{"paths":["/docs/private"]}"#),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        post(r#"{"paths":["/docs/private/secret.txt"]}"#),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        post(r#"{"paths":["/docs/missing.txt"]}"#),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        post(r#"{"paths":["/docs/.hidden.txt"]}"#),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(post(r#"{"paths":["/"]}"#), StatusCode::BAD_REQUEST);
+    assert_eq!(post(r#"{"paths":[]}"#), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        post(r#"{"paths":["/docs/notes.txt"],"format":"zip"}"#),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(post("not json"), StatusCode::BAD_REQUEST);
+}
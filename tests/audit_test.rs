@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MIT
+//! Audit log (`--audit`). One server per process: the audit log is process-wide.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use tempfile::tempdir;
+
+#[test]
+fn test_audit_log_records_security_events() {
+    let served = tempdir().unwrap();
+    let logs = tempdir().unwrap();
+    let audit_file = logs.path().join("audit.jsonl");
+
+    let args = [
+        "irondrop",
+        "-d",
+        &served.path().to_string_lossy(),
+        "--listen",
+        "127.0.0.1",
+        "--port",
+        "0",
+        "--allowed-extensions",
+        "*.txt",
+        "--username",
+        "admin",
+        "--password",
+        "secret",
+        "--enable-upload",
+        "true",
+        "--enable-file-management",
+        "true",
+        "--share-secret",
+        "test-share-secret-0123456789",
+        "--auth-sessions",
+        "true",
+        "--audit",
+        "true",
+        "--audit-file",
+        &audit_file.to_string_lossy(),
+    ]
+    .map(str::to_string);
+    let cli = Cli::parse_from(args);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+    let client = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let url = |path: &str| format!("http://{addr}{path}");
+
+    // The unauthenticated challenge is not recorded, wrong credentials are
+    let res = client.get(url("/")).send().unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = client
+        .get(url("/"))
+        .basic_auth("admin", Some("wrong"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = client
+        .post(url("/_irondrop/login"))
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body("username=admin&password=secret&next=%2F")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+    let res = client
+        .post(url("/_irondrop/upload"))
+        .basic_auth("admin", Some("secret"))
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header("X-Filename", "report.txt")
+        .body("hello audit")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(url("/_api/share?path=/report.txt"))
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .delete(url("/_irondrop/files?path=/report.txt"))
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let _ = shutdown_tx.send(());
+    let _ = handle.join();
+
+    let records: Vec<serde_json::Value> = fs::read_to_string(&audit_file)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let events: Vec<&str> = records
+        .iter()
+        .map(|r| r["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        events,
+        [
+            "auth.failure",
+            "auth.login",
+            "upload.completed",
+            "share.created",
+            "file.deleted"
+        ]
+    );
+    for record in &records {
+        assert_eq!(record["user"], "admin");
+        assert_eq!(record["ip"], "127.0.0.1");
+        assert!(record["timestamp"].as_u64().unwrap() > 0);
+    }
+    assert_eq!(records[0]["result"], "failure");
+    assert_eq!(records[0]["path"], "/");
+    assert_eq!(records[0]["reason"], "invalid credentials");
+    assert_eq!(records[1]["result"], "success");
+    assert_eq!(records[1]["role"], "admin");
+    assert!(records[2]["path"].as_str().unwrap().ends_with("report.txt"));
+    assert_eq!(records[2]["size"], 11);
+    assert_eq!(records[3]["path"], "/report.txt");
+    assert_eq!(records[4]["path"], "/report.txt");
+    assert_eq!(records[4]["source"], "file_management");
+}
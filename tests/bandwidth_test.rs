@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: MIT
+//! Tests for global and per-connection download bandwidth limits.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tempfile::{TempDir, tempdir};
+
+const FILE_SIZE: usize = 200 * 1024;
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+fn setup_server(max_bandwidth: Option<u64>, per_conn_bandwidth: Option<u64>) -> TestServer {
+    let dir = tempdir().unwrap();
+    let data: Vec<u8> = (0..FILE_SIZE).map(|i| (i % 251) as u8).collect();
+    fs::write(dir.path().join("data.bin"), data).unwrap();
+
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(true),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth,
+        per_conn_bandwidth,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn download(addr: SocketAddr) -> Vec<u8> {
+    reqwest::blocking::get(format!("http://{addr}/data.bin"))
+        .unwrap()
+        .bytes()
+        .unwrap()
+        .to_vec()
+}
+
+#[test]
+fn test_per_connection_limit_slows_downloads() {
+    let server = setup_server(None, Some(100 * 1024));
+
+    let started = Instant::now();
+    let body = download(server.addr);
+    let elapsed = started.elapsed();
+
+    assert_eq!(body.len(), FILE_SIZE);
+    assert!(body.iter().enumerate().all(|(i, &b)| b == (i % 251) as u8));
+    // 200 KB at 100 KB/s, minus the small initial burst
+    assert!(elapsed >= Duration::from_millis(1700), "{elapsed:?}");
+}
+
+#[test]
+fn test_global_limit_is_shared_between_connections() {
+    let server = setup_server(Some(200 * 1024), None);
+    let addr = server.addr;
+
+    let started = Instant::now();
+    let downloads: Vec<_> = (0..2)
+        .map(|_| thread::spawn(move || download(addr)))
+        .collect();
+    for download in downloads {
+        assert_eq!(download.join().unwrap().len(), FILE_SIZE);
+    }
+    let elapsed = started.elapsed();
+
+    // 400 KB in total through a 200 KB/s bucket
+    assert!(elapsed >= Duration::from_millis(1700), "{elapsed:?}");
+}
+
+#[test]
+fn test_unlimited_server_is_not_throttled() {
+    let server = setup_server(None, None);
+
+    let started = Instant::now();
+    assert_eq!(download(server.addr).len(), FILE_SIZE);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
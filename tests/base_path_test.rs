@@ -69,6 +69,108 @@ where
         ssl_cert: None,
         ssl_key: None,
         base_path: Some(BASE.to_string()),
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let (shutdown_tx, shutdown_rx) = mpsc::channel();
@@ -759,6 +861,108 @@ fn test_cli_base_path_normalization() {
         ssl_cert: None,
         ssl_key: None,
         base_path: Some("/webstorage".to_string()),
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
     assert_eq!(cli.base_path.as_deref(), Some("/webstorage"));
 
@@ -783,6 +987,108 @@ fn test_cli_base_path_normalization() {
         ssl_cert: None,
         ssl_key: None,
         base_path: Some("/storage/".to_string()),
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
     assert!(cli2.base_path.is_some());
 }
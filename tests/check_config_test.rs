@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT
+//! `--check-config`: validation without starting and masked effective settings
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::config::Config;
+use irondrop::server::check_config;
+use std::fs;
+use tempfile::tempdir;
+
+fn load(args: &[&str]) -> Config {
+    let cli = Cli::parse_from(std::iter::once("irondrop").chain(args.iter().copied()));
+    Config::load(&cli).unwrap()
+}
+
+#[test]
+fn test_valid_config_passes_and_secrets_are_masked() {
+    let dir = tempdir().unwrap();
+    let dir_arg = dir.path().to_str().unwrap();
+    let config = load(&[
+        "-d",
+        dir_arg,
+        "--port",
+        "0",
+        "--username",
+        "admin",
+        "--password",
+        "hunter2",
+        "--auth-tokens",
+        "ci:s3cret",
+        "--check-config",
+    ]);
+
+    let check = check_config(&config);
+    assert!(check.is_ok(), "{:?}", check.errors);
+
+    let printed = format!("{:?}", config.masked());
+    assert!(!printed.contains("hunter2"));
+    assert!(!printed.contains("s3cret"));
+    assert!(printed.contains("ci:********"));
+    assert!(printed.contains("admin"));
+}
+
+#[test]
+fn test_invalid_settings_are_reported() {
+    let dir = tempdir().unwrap();
+    let bad_pem = dir.path().join("bad.pem");
+    fs::write(&bad_pem, "not a certificate").unwrap();
+    let config_file = dir.path().join("irondrop.ini");
+    fs::write(&config_file, "[security]\nallow_ips = 10.0.0.0/33\n").unwrap();
+
+    let config = load(&[
+        "-d",
+        dir.path().to_str().unwrap(),
+        "--config-file",
+        config_file.to_str().unwrap(),
+        "--port",
+        "0",
+        "--username",
+        "admin",
+        "--ssl-cert",
+        bad_pem.to_str().unwrap(),
+        "--ssl-key",
+        bad_pem.to_str().unwrap(),
+        "--auth-paths",
+        "/private:nocredentials",
+    ]);
+
+    let check = check_config(&config);
+    assert!(!check.is_ok());
+    let errors = check.errors.join("\n");
+    for expected in [
+        "10.0.0.0/33",
+        "No certificates found",
+        "username and password",
+        "/private",
+    ] {
+        assert!(
+            errors.contains(expected),
+            "missing '{expected}' in:\n{errors}"
+        );
+    }
+    assert_eq!(check.errors.len(), 4, "{errors}");
+}
@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: MIT
+//! Tests for the file checksum route.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+impl TestServer {
+    fn get(&self, path: &str) -> reqwest::blocking::Response {
+        reqwest::blocking::get(format!("http://{}{path}", self.addr)).unwrap()
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs").join("abc file.txt"), "abc").unwrap();
+    fs::write(dir.path().join("notes.md"), "# Notes\n").unwrap();
+    fs::write(dir.path().join("secret.key"), "hidden").unwrap();
+
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.txt,*.md".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(true),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+#[test]
+fn test_sha256_checksum_json() {
+    let server = setup_server();
+
+    let res = server.get("/_api/checksum?path=/docs/abc%20file.txt");
+    assert_eq!(res.status(), StatusCode::OK);
+    let json: serde_json::Value = res.json().unwrap();
+    assert_eq!(json["path"], "/docs/abc file.txt");
+    assert_eq!(json["algorithm"], "sha256");
+    assert_eq!(
+        json["checksum"],
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(json["size"], 3);
+
+    // The namespaced route shares the cache
+    let json: serde_json::Value = server
+        .get("/_irondrop/checksum?path=docs/abc%20file.txt&algo=sha256")
+        .json()
+        .unwrap();
+    assert_eq!(json["cached"], true);
+}
+
+#[test]
+fn test_md5_checksum_text_format() {
+    let server = setup_server();
+
+    let res = server.get("/_api/checksum?path=/docs/abc%20file.txt&algo=md5&format=text");
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.text().unwrap(),
+        "900150983cd24fb0d6963f7d28e17f72  abc file.txt\n"
+    );
+}
+
+#[test]
+fn test_checksum_rejections() {
+    let server = setup_server();
+
+    assert_eq!(
+        server.get("/_api/checksum").status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        server
+            .get("/_api/checksum?path=/notes.md&algo=crc32")
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        server.get("/_api/checksum?path=/missing.txt").status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        server.get("/_api/checksum?path=/docs").status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        server.get("/_api/checksum?path=/secret.key").status(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        server
+            .get("/_api/checksum?path=../../etc/passwd.txt")
+            .status(),
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[test]
+fn test_preview_links_to_checksums() {
+    let server = setup_server();
+
+    let html = server.get("/_preview/notes.md").text().unwrap();
+    assert!(html.contains("/_api/checksum?path=%2Fnotes.md&amp;algo=sha256&amp;format=text"));
+}
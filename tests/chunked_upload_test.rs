@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: MIT
+//! End-to-end tests for uploads sent with `Transfer-Encoding: chunked`.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+fn setup_upload_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.bin".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(true),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(true),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Send `body` as a chunked upload, `chunk` bytes per frame, and return the raw response
+fn chunked_upload(addr: SocketAddr, name: &str, body: &[u8], chunk: usize) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let head = format!(
+        "POST /_irondrop/upload HTTP/1.1\r\nHost: {addr}\r\n\
+         Content-Type: application/octet-stream\r\nX-Filename: {name}\r\n\
+         Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n"
+    );
+    stream.write_all(head.as_bytes()).unwrap();
+    // The server may answer and close early (e.g. 413), so write errors end the body
+    let mut send = || -> std::io::Result<()> {
+        for frame in body.chunks(chunk) {
+            stream.write_all(format!("{:x}\r\n", frame.len()).as_bytes())?;
+            stream.write_all(frame)?;
+            stream.write_all(b"\r\n")?;
+        }
+        stream.write_all(b"0\r\n\r\n")
+    };
+    let _ = send();
+
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    String::from_utf8_lossy(&response).into_owned()
+}
+
+#[test]
+fn test_small_chunked_upload_is_saved() {
+    let server = setup_upload_server();
+    let body = b"hello chunked world".to_vec();
+
+    let response = chunked_upload(server.addr, "small.bin", &body, 5);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert_eq!(
+        fs::read(server.temp_dir.path().join("small.bin")).unwrap(),
+        body
+    );
+}
+
+#[test]
+fn test_large_chunked_upload_streams_to_disk() {
+    let server = setup_upload_server();
+    // Larger than the in-memory threshold so the body spills to a temp file
+    let body: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+    let response = chunked_upload(server.addr, "large.bin", &body, 64 * 1024 + 7);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    let saved = fs::read(server.temp_dir.path().join("large.bin")).unwrap();
+    assert_eq!(saved.len(), body.len());
+    assert!(saved == body, "uploaded bytes differ from the saved file");
+}
+
+#[test]
+fn test_chunked_upload_over_size_limit_is_rejected() {
+    let server = setup_upload_server();
+    let body = vec![0u8; 11 * 1024 * 1024];
+
+    let response = chunked_upload(server.addr, "huge.bin", &body, 256 * 1024);
+    assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+    assert!(!server.temp_dir.path().join("huge.bin").exists());
+}
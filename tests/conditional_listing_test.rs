@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MIT
+//! Weak ETags and `304 Not Modified` for directory listings and search results.
+//! One server per process: the search index is process-wide.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::search::{DEFAULT_TRIGRAM_BUDGET, add_search_mount};
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+fn etag(res: &Response) -> String {
+    res.headers()[ETAG].to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_listing_and_search_revalidation() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("wombat_notes.txt"), "a").unwrap();
+    let other = tempdir().unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+    let client = Client::new();
+    let get = |path: &str, if_none_match: Option<&str>| {
+        let mut request = client.get(format!("http://{addr}{path}"));
+        if let Some(tag) = if_none_match {
+            request = request.header(IF_NONE_MATCH, tag);
+        }
+        request.send().unwrap()
+    };
+
+    // Wait for the first index build so the results stay put
+    let search = "/_irondrop/search?q=wombat";
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let res = loop {
+        let res = get(search, None);
+        let body: serde_json::Value = res.json().unwrap();
+        if body["total"] == 1 || Instant::now() > deadline {
+            break get(search, None);
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CACHE_CONTROL], "no-cache");
+    let tag = etag(&res);
+    assert!(tag.starts_with("W/\""), "{tag}");
+    let res = get(search, Some(&tag));
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(etag(&res), tag);
+    assert!(res.text().unwrap().is_empty());
+    assert_eq!(
+        get("/_irondrop/search?q=wombat&limit=5", Some(&tag)).status(),
+        StatusCode::OK
+    );
+    // A new index means new results
+    add_search_mount(
+        "other",
+        other.path().to_path_buf(),
+        false,
+        DEFAULT_TRIGRAM_BUDGET,
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(get(search, Some(&tag)).status(), StatusCode::OK);
+
+    let res = get("/", None);
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[CACHE_CONTROL], "no-cache");
+    let html_tag = etag(&res);
+    assert!(html_tag.starts_with("W/\""), "{html_tag}");
+    let res = get("/", Some(&html_tag));
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert!(res.text().unwrap().is_empty());
+
+    // Each representation and page has its own tag
+    let json_tag = etag(&get("/?format=json", None));
+    assert_ne!(json_tag, html_tag);
+    assert_eq!(
+        get("/?format=json", Some(&json_tag)).status(),
+        StatusCode::NOT_MODIFIED
+    );
+    assert_eq!(get("/?sort=size", Some(&html_tag)).status(), StatusCode::OK);
+
+    // A new entry changes the folder
+    fs::write(dir.path().join("new.txt"), "b").unwrap();
+    let res = get("/", Some(&html_tag));
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_ne!(etag(&res), html_tag);
+    assert!(res.text().unwrap().contains("new.txt"));
+
+    let _ = shutdown_tx.send(());
+    let _ = handle.join();
+}
@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MIT
+//! Tests for reloading the INI configuration while the server is running.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::config::reload::ConfigReloader;
+use irondrop::server::run_server_with_reload;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tempfile::{TempDir, tempdir};
+
+const LOCKED_DOWN: &str = "[server]\nlisten = 127.0.0.1\nport = 0\n\n\
+    [security]\nallowed_extensions = *.txt\n\n\
+    [auth]\nusername = alice\npassword = secret\n";
+
+const OPENED_UP: &str = "[server]\nlisten = 127.0.0.1\nport = 9\n\n\
+    [security]\nallowed_extensions = *.txt,*.bin\n";
+
+const UPLOADS: &str = "[server]\nlisten = 127.0.0.1\nport = 0\n\n\
+    [upload]\nenable_upload = true\n\n\
+    [security]\nallowed_extensions = *.txt\n";
+
+const UPLOADS_RELOADED: &str = "[server]\nlisten = 127.0.0.1\nport = 0\n\n\
+    [upload]\nenable_upload = true\n\n\
+    [security]\nallowed_extensions = *.txt,*.bin\n";
+
+const REDIRECTS: &str = "[server]\nlisten = 127.0.0.1\nport = 0\n\n\
+    [upload]\nenable_upload = true\n\n\
+    [security]\nallowed_extensions = *.txt\n\n\
+    [redirects]\n/old = /hello.txt\n";
+
+/// Loads fine; the bad redirect is only rejected while the server state is built
+const REDIRECTS_BROKEN: &str = "[server]\nlisten = 127.0.0.1\nport = 0\n\n\
+    [upload]\nenable_upload = true\nquota_total = 1\n\n\
+    [security]\nallowed_extensions = *.txt\n\n\
+    [redirects]\n/new = /hello.txt\n/broken = nowhere\n";
+
+const REDIRECTS_QUOTA: &str = "[server]\nlisten = 127.0.0.1\nport = 0\n\n\
+    [upload]\nenable_upload = true\nquota_total = 1\n\n\
+    [security]\nallowed_extensions = *.txt\n\n\
+    [redirects]\n/old = /hello.txt\n";
+
+struct TestServer {
+    addr: SocketAddr,
+    ini_path: PathBuf,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+fn setup_server() -> TestServer {
+    setup_server_with(LOCKED_DOWN)
+}
+
+fn setup_server_with(ini: &str) -> TestServer {
+    let dir = tempdir().unwrap();
+    let files = dir.path().join("files");
+    fs::create_dir(&files).unwrap();
+    fs::write(files.join("hello.txt"), "hello").unwrap();
+    fs::write(files.join("data.bin"), [1u8, 2, 3]).unwrap();
+    let ini_path = dir.path().join("irondrop.ini");
+    fs::write(&ini_path, ini).unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        files.to_string_lossy().into_owned(),
+        "--config-file".to_string(),
+        ini_path.to_string_lossy().into_owned(),
+    ]);
+    let reloader = ConfigReloader::new(cli);
+    let config = reloader.load().unwrap();
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) =
+            run_server_with_reload(config, Some(reloader), Some(shutdown_rx), Some(addr_tx))
+        {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        ini_path,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn status(addr: SocketAddr, path: &str, auth: bool) -> u16 {
+    let client = reqwest::blocking::Client::new();
+    let mut request = client.get(format!("http://{addr}{path}"));
+    if auth {
+        request = request.basic_auth("alice", Some("secret"));
+    }
+    request.send().unwrap().status().as_u16()
+}
+
+/// Poll until `path` answers with `expected`, giving the reloader time to notice
+fn wait_for_status(addr: SocketAddr, path: &str, expected: u16) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if status(addr, path, false) == expected {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    false
+}
+
+#[test]
+fn test_auth_and_extensions_update_without_restart() {
+    let server = setup_server();
+    assert_eq!(status(server.addr, "/hello.txt", false), 401);
+    assert_eq!(status(server.addr, "/hello.txt", true), 200);
+    assert_eq!(status(server.addr, "/data.bin", true), 403);
+
+    fs::write(&server.ini_path, OPENED_UP).unwrap();
+    assert!(wait_for_status(server.addr, "/hello.txt", 200));
+    assert_eq!(status(server.addr, "/data.bin", false), 200);
+}
+
+#[test]
+fn test_restart_only_settings_are_kept() {
+    let server = setup_server();
+    fs::write(&server.ini_path, OPENED_UP).unwrap();
+
+    // The new port only applies after a restart, so the server stays where it is
+    assert!(wait_for_status(server.addr, "/hello.txt", 200));
+
+    // A config file that fails to load keeps the settings that are already active
+    fs::remove_file(&server.ini_path).unwrap();
+    thread::sleep(Duration::from_secs(3));
+    assert_eq!(status(server.addr, "/hello.txt", false), 200);
+}
+
+#[test]
+fn test_upload_sessions_survive_a_reload() {
+    let server = setup_server_with(UPLOADS);
+    let client = reqwest::blocking::Client::new();
+    let session_url = format!("http://{}/_irondrop/upload/session", server.addr);
+    let chunk = |id: &str, range: &str, data: &'static [u8]| {
+        client
+            .put(format!("{session_url}/{id}"))
+            .header("Content-Range", range)
+            .body(data)
+            .send()
+            .unwrap()
+            .status()
+            .as_u16()
+    };
+
+    let created = client
+        .post(&session_url)
+        .header("X-Filename", "resume.txt")
+        .header("X-Upload-Length", "10")
+        .send()
+        .unwrap();
+    assert_eq!(created.status().as_u16(), 201);
+    let json: serde_json::Value = created.json().unwrap();
+    let id = json["id"].as_str().unwrap().to_string();
+    assert_eq!(chunk(&id, "bytes 0-4/10", b"01234"), 200);
+
+    fs::write(&server.ini_path, UPLOADS_RELOADED).unwrap();
+    assert!(wait_for_status(server.addr, "/data.bin", 200));
+
+    // The session opened before the reload is still there to finish
+    assert_eq!(chunk(&id, "bytes 5-9/10", b"56789"), 201);
+    let saved = fs::read(server.temp_dir.path().join("files").join("resume.txt")).unwrap();
+    assert_eq!(saved, b"0123456789");
+}
+
+#[test]
+fn test_rejected_reload_keeps_every_setting() {
+    let server = setup_server_with(REDIRECTS);
+    let client = reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let get = |path: &str| {
+        client
+            .get(format!("http://{}{path}", server.addr))
+            .send()
+            .unwrap()
+            .status()
+            .as_u16()
+    };
+    let upload = |name: &str| {
+        client
+            .post(format!("http://{}/_irondrop/upload", server.addr))
+            .header("X-Filename", name)
+            .body("0123456789")
+            .send()
+            .unwrap()
+            .status()
+            .as_u16()
+    };
+    assert_eq!(get("/old"), 301);
+
+    // Nothing of a configuration that fails to build may take effect
+    fs::write(&server.ini_path, REDIRECTS_BROKEN).unwrap();
+    thread::sleep(Duration::from_secs(3));
+    assert_eq!(get("/old"), 301);
+    assert_eq!(get("/new"), 404);
+    assert!((200..300).contains(&upload("before.txt")));
+
+    // The reloader is still watching, and the quota does apply once it is valid
+    fs::write(&server.ini_path, REDIRECTS_QUOTA).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while upload("after.txt") != 507 {
+        assert!(Instant::now() < deadline, "quota never applied");
+        thread::sleep(Duration::from_millis(200));
+    }
+    assert_eq!(get("/old"), 301);
+}
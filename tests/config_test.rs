@@ -1,4 +1,5 @@
 // SPDX-License-Identifier: MIT
+use clap::Parser;
 use irondrop::cli::Cli;
 use irondrop::config::{Config, ini_parser::IniConfig};
 use std::fs;
@@ -185,6 +186,108 @@ verbose = false
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let config = Config::load(&cli).expect("Failed to load config");
@@ -235,6 +338,108 @@ max_upload_size = 1GB
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let config = Config::load(&cli).expect("Failed to load config");
@@ -245,6 +450,52 @@ max_upload_size = 1GB
     assert_eq!(config.max_upload_size, 1024 * 1024 * 1024); // 1GB in bytes
 }
 
+#[test]
+fn test_explicit_cli_default_values_beat_ini() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_file = temp_dir.path().join("test.ini");
+    fs::write(
+        &config_file,
+        "[server]\nlisten = 0.0.0.0\nport = 9000\nthreads = 16\nchunk_size = 4096\n[upload]\nenable_upload = true\n",
+    )
+    .unwrap();
+    let args = |extra: &[&str]| {
+        let mut args = vec![
+            "irondrop".to_string(),
+            "-d".to_string(),
+            temp_dir.path().to_string_lossy().into_owned(),
+            "--config-file".to_string(),
+            config_file.to_string_lossy().into_owned(),
+        ];
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        Cli::parse_from(args)
+    };
+
+    let from_ini = Config::load(&args(&[])).unwrap();
+    assert_eq!(from_ini.port, 9000);
+    assert!(from_ini.enable_upload);
+
+    // Passing the built-in defaults explicitly must still override the file
+    let config = Config::load(&args(&[
+        "--listen",
+        "127.0.0.1",
+        "--port",
+        "8080",
+        "--threads",
+        "8",
+        "--chunk-size",
+        "1024",
+        "--enable-upload",
+        "false",
+    ]))
+    .unwrap();
+    assert_eq!(config.listen, "127.0.0.1");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.threads, 8);
+    assert_eq!(config.chunk_size, 1024);
+    assert!(!config.enable_upload);
+}
+
 #[test]
 fn test_config_defaults() {
     let temp_dir = TempDir::new().unwrap();
@@ -269,6 +520,108 @@ fn test_config_defaults() {
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let config = Config::load(&cli).expect("Failed to load config");
@@ -312,6 +665,108 @@ fn test_config_file_load_error() {
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let result = Config::load(&cli);
@@ -383,6 +838,108 @@ directory = {}
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let config = Config::load(&cli).expect("Failed to load config");
@@ -429,6 +986,108 @@ port = 9999
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let config = Config::load(&cli).expect("Failed to load config");
@@ -480,6 +1139,108 @@ fn test_config_invalid_port_values() {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         };
 
         let result = Config::load(&cli);
@@ -528,6 +1289,108 @@ fn test_config_invalid_port_values() {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         };
 
         let result = Config::load(&cli);
@@ -587,6 +1450,108 @@ fn test_config_invalid_file_size_formats() {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         };
 
         let result = Config::load(&cli);
@@ -666,6 +1631,108 @@ fn test_config_boolean_edge_cases() {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         };
 
         let result = Config::load(&cli);
@@ -717,6 +1784,108 @@ fn test_config_malformed_ini_syntax() {
             ssl_cert: None,
             ssl_key: None,
             base_path: None,
+            compression: None,
+            compression_min_size: None,
+            auth_tokens: None,
+            upload_quota_daily: None,
+            upload_quota_total: None,
+            allow_ips: None,
+            deny_ips: None,
+            log_rejected_ips: None,
+            enable_file_management: None,
+            search_content: None,
+            search_content_extensions: None,
+            search_content_max_size: None,
+            read_timeout: None,
+            write_timeout: None,
+            header_timeout: None,
+            max_bandwidth: None,
+            per_conn_bandwidth: None,
+            max_concurrent_downloads: None,
+            download_queue: None,
+            log_rotate_size: None,
+            log_rotate_age: None,
+            log_rotate_keep: None,
+            log_rotate_gzip: None,
+            mdns: None,
+            cors_origins: None,
+            cors_methods: None,
+            cors_headers: None,
+            cors_max_age: None,
+            cors_credentials: None,
+            security_headers: None,
+            content_security_policy: None,
+            frame_options: None,
+            referrer_policy: None,
+            hsts_max_age: None,
+            upload_scan_command: None,
+            webhook_urls: None,
+            webhook_events: None,
+            webhook_retries: None,
+            webhook_queue_size: None,
+            share_secret: None,
+            share_max_ttl: None,
+            auth_paths: None,
+            upload_organize: None,
+            upload_dedup: None,
+            template_dir: None,
+            theme: None,
+            custom_css: None,
+            rate_limit: None,
+            rate_limit_concurrent: None,
+            rate_limit_upload: None,
+            rate_limit_search: None,
+            trusted_proxies: None,
+            http2: None,
+            mmap_cache_size: None,
+            mmap_max_file: None,
+            tcp_backlog: None,
+            tcp_nodelay: None,
+            tcp_reuse_address: None,
+            tcp_reuse_port: None,
+            tcp_keepalive: None,
+            tcp_keepalive_interval: None,
+            tcp_keepalive_retries: None,
+            accept_batch: None,
+            max_workers: None,
+            worker_queue: None,
+            follow_symlinks: None,
+            hide_dotfiles: None,
+            ignore_patterns: None,
+            read_only: None,
+            mime_types: None,
+            default_charset: None,
+            force_download: None,
+            upload_conflict: None,
+            upload_disk_reserve: None,
+            search_exact_sizes: None,
+            search_trigram_budget: None,
+            search_exclude: None,
+            search_max_depth: None,
+            search_depth_limits: None,
+            max_upload_bandwidth: None,
+            per_upload_bandwidth: None,
+            service: None,
+            check_config: false,
+            password_hash: None,
+            auth_max_failures: None,
+            auth_lockout_secs: None,
+            users: None,
+            auth_sessions: None,
+            session_lifetime: None,
+            session_secret: None,
+            audit: None,
+            audit_file: None,
+            enable_edit: None,
+            quick_share: None,
+            index: None,
+            spa: None,
+            redirects: None,
+            redirects_file: None,
+            not_found_page: None,
+            file_cache_size: None,
+            file_cache_max_file: None,
+            max_request_body: None,
         };
 
         let _result = Config::load(&cli);
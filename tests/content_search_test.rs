@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: MIT
+//! Tests for the opt-in content index behind `scope=content|both`.
+
+use irondrop::cli::Cli;
+use irondrop::search::{ContentIndex, ContentIndexSettings, tokenize};
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+impl TestServer {
+    fn search(&self, query: &str) -> (StatusCode, Value) {
+        let response = Client::new()
+            .get(format!("http://{}/_irondrop/search?{query}", self.addr))
+            .send()
+            .unwrap();
+        let status = response.status();
+        (status, response.json().unwrap_or(Value::Null))
+    }
+}
+
+fn write_tree(root: &std::path::Path) {
+    fs::create_dir(root.join("notes")).unwrap();
+    fs::write(
+        root.join("notes").join("meeting.md"),
+        "Agenda: discuss the Wombat migration plan",
+    )
+    .unwrap();
+    fs::write(root.join("todo.txt"), "feed the wombat\nwater plants").unwrap();
+    fs::write(root.join("wombat_photo.jpg"), [0xff, 0xd8, 0, 0]).unwrap();
+    fs::write(root.join("binary.txt"), b"wombat\0\x01\x02").unwrap();
+    fs::write(
+        root.join("large.txt"),
+        format!("wombat {}", "x ".repeat(4096)),
+    )
+    .unwrap();
+}
+
+fn setup_server(search_content: bool) -> TestServer {
+    let dir = tempdir().unwrap();
+    write_tree(dir.path());
+
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: Some(search_content),
+        search_content_extensions: Some("md,txt".to_string()),
+        search_content_max_size: Some(1024),
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn result_paths(json: &Value) -> Vec<String> {
+    let mut paths: Vec<String> = json["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["path"].as_str().unwrap().to_string())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_tokenize_and_index_build() {
+    let words: Vec<String> = tokenize("Hello, WORLD! a x1 don't").collect();
+    assert_eq!(words, ["hello", "world", "x1", "don"]);
+
+    let dir = tempdir().unwrap();
+    write_tree(dir.path());
+    let settings = ContentIndexSettings {
+        extensions: vec!["md".to_string(), "txt".to_string()],
+        max_file_size: 1024,
+        ..Default::default()
+    };
+    let index = ContentIndex::build(dir.path(), &settings);
+    // The JPEG, the binary .txt and the oversized .txt are skipped
+    assert_eq!(index.document_count(), 2);
+
+    let paths: Vec<String> = index
+        .search("wombat", "/", 10)
+        .into_iter()
+        .map(|r| r.path)
+        .collect();
+    assert_eq!(paths.len(), 2);
+    assert!(paths.contains(&"/notes/meeting.md".to_string()));
+
+    // Every word must match; the last may be a prefix
+    let results = index.search("wombat migr", "/", 10);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "/notes/meeting.md");
+    assert_eq!(index.search("wombat", "/notes", 10).len(), 1);
+    assert!(index.search("platypus", "/", 10).is_empty());
+}
+
+#[test]
+fn test_search_scopes() {
+    let server = setup_server(true);
+
+    // The index is built in the background
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let content = loop {
+        let (status, json) = server.search("q=wombat&scope=content");
+        assert_eq!(status, StatusCode::OK);
+        if json["total"] == 2 || Instant::now() > deadline {
+            break json;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(content["scope"], "content");
+    assert_eq!(result_paths(&content), ["/notes/meeting.md", "/todo.txt"]);
+
+    let (_, names) = server.search("q=wombat");
+    assert_eq!(names["scope"], "name");
+    assert_eq!(result_paths(&names), ["/wombat_photo.jpg"]);
+
+    let (_, both) = server.search("q=wombat&scope=both");
+    assert_eq!(
+        result_paths(&both),
+        ["/notes/meeting.md", "/todo.txt", "/wombat_photo.jpg"]
+    );
+
+    assert_eq!(
+        server.search("q=wombat&scope=everything").0,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_content_scope_requires_opt_in() {
+    let server = setup_server(false);
+    assert_eq!(
+        server.search("q=wombat&scope=content").0,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(server.search("q=wombat").0, StatusCode::OK);
+}
@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MIT
+//! Tests for CORS headers and preflight handling on the JSON APIs.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::blocking::{Client, Response};
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+const ORIGIN: &str = "https://app.example.com";
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+impl TestServer {
+    fn request(&self, method: &str, path: &str, origin: Option<&str>, auth: bool) -> Response {
+        let method = reqwest::Method::from_bytes(method.as_bytes()).unwrap();
+        let mut request = Client::new().request(method, format!("http://{}{path}", self.addr));
+        if let Some(origin) = origin {
+            request = request
+                .header("Origin", origin)
+                .header("Access-Control-Request-Method", "GET");
+        }
+        if auth {
+            request = request.basic_auth("alice", Some("secret"));
+        }
+        request.send().unwrap()
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--username".to_string(),
+        "alice".to_string(),
+        "--password".to_string(),
+        "secret".to_string(),
+        "--cors-origins".to_string(),
+        ORIGIN.to_string(),
+        "--cors-credentials".to_string(),
+        "true".to_string(),
+        "--cors-max-age".to_string(),
+        "300".to_string(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().unwrap())
+}
+
+#[test]
+fn test_preflight_skips_auth() {
+    let server = setup_server();
+    let response = server.request("OPTIONS", "/_irondrop/search", Some(ORIGIN), false);
+    assert_eq!(response.status(), 204);
+    assert_eq!(
+        header(&response, "access-control-allow-origin"),
+        Some(ORIGIN)
+    );
+    assert_eq!(
+        header(&response, "access-control-allow-credentials"),
+        Some("true")
+    );
+    assert_eq!(header(&response, "access-control-max-age"), Some("300"));
+    assert!(
+        header(&response, "access-control-allow-methods")
+            .unwrap()
+            .contains("POST")
+    );
+
+    let upload = server.request("OPTIONS", "/_irondrop/upload", Some(ORIGIN), false);
+    assert_eq!(upload.status(), 204);
+
+    let rejected = server.request(
+        "OPTIONS",
+        "/_irondrop/search",
+        Some("https://evil.example"),
+        false,
+    );
+    assert_eq!(rejected.status(), 403);
+    assert_eq!(header(&rejected, "access-control-allow-origin"), None);
+}
+
+#[test]
+fn test_api_responses_carry_cors_headers() {
+    let server = setup_server();
+    let response = server.request("GET", "/_irondrop/search?q=notes", Some(ORIGIN), true);
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        header(&response, "access-control-allow-origin"),
+        Some(ORIGIN)
+    );
+    assert!(header(&response, "vary").unwrap().contains("Origin"));
+
+    // Browsers can only read an auth failure when it has CORS headers too
+    let unauthorized = server.request("GET", "/_irondrop/search?q=notes", Some(ORIGIN), false);
+    assert_eq!(unauthorized.status(), 401);
+    assert_eq!(
+        header(&unauthorized, "access-control-allow-origin"),
+        Some(ORIGIN)
+    );
+
+    // File downloads are not part of the API surface
+    let file = server.request("GET", "/notes.txt", Some(ORIGIN), true);
+    assert_eq!(file.status(), 200);
+    assert_eq!(header(&file, "access-control-allow-origin"), None);
+}
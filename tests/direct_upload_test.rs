@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MIT
 use irondrop::cli::Cli;
+use irondrop::error::AppError;
 use irondrop::http::{Request, RequestBody};
 use irondrop::upload::DirectUploadHandler;
 use std::collections::HashMap;
@@ -29,6 +30,108 @@ fn create_test_cli(upload_dir: PathBuf) -> Cli {
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     }
 }
 
@@ -53,8 +156,11 @@ fn test_direct_upload_small_file() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -86,6 +192,141 @@ fn test_direct_upload_small_file() {
     println!("Direct upload small file test passed");
 }
 
+fn organized_upload(cli: &Cli, filename: &str) -> String {
+    let mut upload_handler = DirectUploadHandler::new(cli).unwrap();
+    let mut headers = HashMap::new();
+    headers.insert("x-filename".to_string(), filename.to_string());
+    headers.insert("accept".to_string(), "application/json".to_string());
+    let request = Request {
+        method: "POST".to_string(),
+        path: "/upload".to_string(),
+        query: None,
+        headers,
+        body: Some(RequestBody::Memory(b"organized".to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
+    };
+    let response = upload_handler.handle_upload(&request, None).unwrap();
+    String::from_utf8(response.body).unwrap()
+}
+
+#[test]
+fn test_direct_upload_organize_by_type() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    cli.upload_organize = Some("type".to_string());
+
+    let body = organized_upload(&cli, "report.txt");
+    assert!(body.contains(r#""path": "txt/report.txt""#), "{body}");
+    assert!(temp_dir.path().join("txt/report.txt").is_file());
+
+    // Name conflicts are resolved inside the organized folder
+    let body = organized_upload(&cli, "report.txt");
+    assert!(body.contains(r#""path": "txt/report_1.txt""#), "{body}");
+    assert!(!temp_dir.path().join("report.txt").exists());
+}
+
+#[test]
+fn test_direct_upload_organize_by_date() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    cli.upload_organize = Some("date".to_string());
+
+    let body = organized_upload(&cli, "notes.txt");
+    let path = body
+        .split(r#""path": ""#)
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap();
+    let parts: Vec<&str> = path.split('/').collect();
+    assert_eq!(parts.len(), 4, "{path}");
+    assert_eq!(parts[0].len(), 4);
+    assert_eq!(parts[1].len(), 2);
+    assert_eq!(parts[2].len(), 2);
+    assert_eq!(parts[3], "notes.txt");
+    assert!(temp_dir.path().join(path).is_file());
+}
+
+#[test]
+fn test_direct_upload_dedup_links_identical_content() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    cli.upload_dedup = Some(true);
+    cli.upload_organize = Some("type".to_string());
+    fs::write(temp_dir.path().join("existing.txt"), b"organized").unwrap();
+    fs::write(temp_dir.path().join("different.txt"), b"organizeD").unwrap();
+
+    let body = organized_upload(&cli, "copy.txt");
+    assert!(body.contains(r#""deduplicated": true"#), "{body}");
+    let copy = temp_dir.path().join("txt/copy.txt");
+    assert_eq!(fs::read(&copy).unwrap(), b"organized");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let existing = fs::metadata(temp_dir.path().join("existing.txt")).unwrap();
+        assert_eq!(fs::metadata(&copy).unwrap().ino(), existing.ino());
+    }
+
+    // Without dedup the same content is stored again
+    cli.upload_dedup = None;
+    let body = organized_upload(&cli, "copy.txt");
+    assert!(body.contains(r#""deduplicated": false"#), "{body}");
+    assert!(temp_dir.path().join("txt/copy_1.txt").is_file());
+}
+
+#[test]
+fn test_direct_upload_metadata_sidecar() {
+    let temp_dir = TempDir::new().unwrap();
+    let cli = create_test_cli(temp_dir.path().to_path_buf());
+    let upload = |target: &str, tags: &str| {
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        let mut headers = HashMap::new();
+        headers.insert("x-filename".to_string(), "report.txt".to_string());
+        headers.insert("accept".to_string(), "application/json".to_string());
+        headers.insert("x-upload-tags".to_string(), tags.to_string());
+        let request = Request {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            query,
+            headers,
+            body: Some(RequestBody::Memory(b"numbers".to_vec())),
+            peer_addr: None,
+            forwarded_by: None,
+        };
+        DirectUploadHandler::new(&cli)
+            .unwrap()
+            .handle_upload(&request, None)
+    };
+
+    let response = upload("/upload?description=Q3%20numbers", "finance, 2024").unwrap();
+    let body = String::from_utf8_lossy(&response.body).to_string();
+    assert!(
+        body.contains(r#""metadata": {"description":"Q3 numbers","tags":["finance","2024"]}"#),
+        "{body}"
+    );
+    assert!(temp_dir.path().join(".report.txt.meta.json").is_file());
+
+    let listing = irondrop::fs::generate_directory_listing_json(
+        temp_dir.path(),
+        "/",
+        &irondrop::fs::ListingOptions::default(),
+    )
+    .unwrap();
+    assert!(!listing.contains("meta.json"), "{listing}");
+    assert!(
+        listing.contains(r#""metadata":{"description":"Q3 numbers","tags":["finance","2024"]}"#),
+        "{listing}"
+    );
+
+    // Invalid tags reject the upload before anything is written
+    assert!(upload("/upload", "../etc").is_err());
+    assert!(!temp_dir.path().join("report_1.txt").exists());
+}
+
 #[test]
 fn test_direct_upload_filename_from_url() {
     let temp_dir = TempDir::new().unwrap();
@@ -103,8 +344,11 @@ fn test_direct_upload_filename_from_url() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload/document.txt".to_string(), // Filename in URL path
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -144,8 +388,11 @@ fn test_direct_upload_filename_from_header() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -188,8 +435,11 @@ fn test_direct_upload_filename_conflict_resolution() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -225,6 +475,57 @@ fn test_direct_upload_filename_conflict_resolution() {
     println!("Direct upload filename conflict resolution test passed");
 }
 
+#[test]
+fn test_direct_upload_conflict_strategy_header() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    fs::write(temp_dir.path().join("notes.txt"), "v1").unwrap();
+    let upload = |cli: &Cli, strategy: &str, body: &str| {
+        let mut headers = HashMap::new();
+        headers.insert("x-filename".to_string(), "notes.txt".to_string());
+        headers.insert("x-conflict-strategy".to_string(), strategy.to_string());
+        let request = Request {
+            method: "POST".to_string(),
+            path: "/upload".to_string(),
+            query: None,
+            headers,
+            body: Some(RequestBody::Memory(body.as_bytes().to_vec())),
+            peer_addr: None,
+            forwarded_by: None,
+        };
+        DirectUploadHandler::new(cli)
+            .unwrap()
+            .handle_upload(&request, None)
+    };
+
+    upload(&cli, "version", "v2").unwrap();
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("notes.v2.txt")).unwrap(),
+        "v2"
+    );
+    assert!(matches!(
+        upload(&cli, "reject", "v3"),
+        Err(AppError::UploadConflict(_))
+    ));
+    assert!(matches!(
+        upload(&cli, "merge", "v3"),
+        Err(AppError::BadRequest)
+    ));
+
+    // Replacing files from the client side needs file management
+    assert!(matches!(
+        upload(&cli, "overwrite", "v3"),
+        Err(AppError::Forbidden)
+    ));
+    cli.enable_file_management = Some(true);
+    upload(&cli, "overwrite", "v3").unwrap();
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("notes.txt")).unwrap(),
+        "v3"
+    );
+    assert!(!temp_dir.path().join("notes_1.txt").exists());
+}
+
 #[test]
 fn test_direct_upload_extension_validation() {
     let temp_dir = TempDir::new().unwrap();
@@ -243,8 +544,11 @@ fn test_direct_upload_extension_validation() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -288,8 +592,11 @@ fn test_direct_upload_size_limit() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data)),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -328,8 +635,11 @@ fn test_direct_upload_method_validation() {
     let request = Request {
         method: "GET".to_string(), // Wrong method
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -361,11 +671,14 @@ fn test_direct_upload_large_file_streaming() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::File {
             path: temp_file.path().to_path_buf(),
             size: test_data.len() as u64,
         }),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -420,8 +733,11 @@ fn test_direct_upload_empty_file() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -470,8 +786,11 @@ fn test_direct_upload_path_traversal_prevention() {
         let request = Request {
             method: "POST".to_string(),
             path: "/upload".to_string(),
+            query: None,
             headers,
             body: Some(RequestBody::Memory(test_data.to_vec())),
+            peer_addr: None,
+            forwarded_by: None,
         };
 
         let response = upload_handler.handle_upload(&request, None);
@@ -528,8 +847,11 @@ fn test_direct_upload_filename_sanitization() {
         let request = Request {
             method: "POST".to_string(),
             path: "/upload".to_string(),
+            query: None,
             headers,
             body: Some(RequestBody::Memory(test_data.to_vec())),
+            peer_addr: None,
+            forwarded_by: None,
         };
 
         let response = upload_handler.handle_upload(&request, None);
@@ -583,8 +905,11 @@ fn test_direct_upload_concurrent_uploads() {
                 let request = Request {
                     method: "POST".to_string(),
                     path: "/upload".to_string(),
+                    query: None,
                     headers,
                     body: Some(RequestBody::Memory(test_data)),
+                    peer_addr: None,
+                    forwarded_by: None,
                 };
 
                 upload_handler.handle_upload(&request, None)
@@ -634,8 +959,11 @@ fn test_direct_upload_missing_content_type() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -676,8 +1004,11 @@ fn test_direct_upload_malformed_content_length() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(test_data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -720,8 +1051,11 @@ fn test_direct_upload_disk_space_simulation() {
     let request = Request {
         method: "POST".to_string(),
         path: "/upload".to_string(),
+        query: None,
         headers,
         body: Some(RequestBody::Memory(large_data.clone())),
+        peer_addr: None,
+        forwarded_by: None,
     };
 
     let response = upload_handler.handle_upload(&request, None);
@@ -752,3 +1086,109 @@ fn test_direct_upload_disk_space_simulation() {
 
     println!("Direct upload disk space simulation test passed");
 }
+
+#[test]
+fn test_direct_upload_disk_reserve() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    if irondrop::disk_space::disk_space(temp_dir.path()).is_none() {
+        return; // free space is not known on this platform
+    }
+    cli.upload_disk_reserve = Some(u64::MAX);
+    let mut headers = HashMap::new();
+    headers.insert("x-filename".to_string(), "kept-free.txt".to_string());
+    let request = Request {
+        method: "POST".to_string(),
+        path: "/upload".to_string(),
+        query: None,
+        headers,
+        body: Some(RequestBody::Memory(b"data".to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
+    };
+
+    let result = DirectUploadHandler::new(&cli)
+        .unwrap()
+        .handle_upload(&request, None);
+    assert!(matches!(result, Err(AppError::UploadDiskFull(_))));
+    assert!(!temp_dir.path().join("kept-free.txt").exists());
+
+    cli.upload_disk_reserve = Some(0);
+    assert!(
+        DirectUploadHandler::new(&cli)
+            .unwrap()
+            .handle_upload(&request, None)
+            .is_ok()
+    );
+}
+
+fn scan_request(filename: &str, body: RequestBody) -> Request {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "content-type".to_string(),
+        "application/octet-stream".to_string(),
+    );
+    headers.insert("x-filename".to_string(), filename.to_string());
+    Request {
+        method: "POST".to_string(),
+        path: "/upload".to_string(),
+        query: None,
+        headers,
+        body: Some(body),
+        peer_addr: None,
+        forwarded_by: None,
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_direct_upload_scan_command_rejects_and_cleans_up() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    // `test -s %f` fails for empty files, which stands in for a scanner verdict
+    cli.upload_scan_command = Some("test -s %f".to_string());
+    let mut upload_handler = DirectUploadHandler::new(&cli).unwrap();
+
+    let request = scan_request("empty.txt", RequestBody::Memory(Vec::new()));
+    let result = upload_handler.handle_upload(&request, None);
+    assert!(matches!(result, Err(AppError::UploadRejected(_))));
+
+    let request = scan_request("clean.txt", RequestBody::Memory(b"clean".to_vec()));
+    let response = upload_handler.handle_upload(&request, None).unwrap();
+    assert_eq!(response.status_code, 200);
+
+    // Streamed bodies are scanned before the fast-path rename and removed on rejection
+    let body_file = NamedTempFile::new().unwrap();
+    let body_path = body_file.path().to_path_buf();
+    let request = scan_request(
+        "streamed.bin",
+        RequestBody::File {
+            path: body_path.clone(),
+            size: 0,
+        },
+    );
+    let result = upload_handler.handle_upload(&request, None);
+    assert!(matches!(result, Err(AppError::UploadRejected(_))));
+    assert!(!body_path.exists());
+
+    let names: Vec<String> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, vec!["clean.txt".to_string()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_direct_upload_scan_command_fails_closed() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    cli.upload_scan_command = Some("/nonexistent/irondrop-scanner %f".to_string());
+    let mut upload_handler = DirectUploadHandler::new(&cli).unwrap();
+
+    let request = scan_request("file.txt", RequestBody::Memory(b"data".to_vec()));
+    let result = upload_handler.handle_upload(&request, None);
+    assert!(matches!(result, Err(AppError::UploadRejected(_))));
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: MIT
+//! Free-space checks before uploads and the disk section of `/_status`.
+//! One server per process: the settings are process-wide.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+/// More than any test machine has free
+const HUGE_RESERVE: &str = "1000000TB";
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--enable-upload".to_string(),
+        "true".to_string(),
+        "--upload-disk-reserve".to_string(),
+        HUGE_RESERVE.to_string(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+#[test]
+fn test_upload_beyond_reserve_is_refused_early() {
+    let server = setup_server();
+    let client = Client::new();
+
+    let status: Value = client
+        .get(format!("http://{}/_status?json=1", server.addr))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    let disk = &status["disk"];
+    if disk["available"] != true {
+        return; // free space is not known on this platform
+    }
+    assert!(disk["free_bytes"].as_u64().unwrap() > 0);
+    assert!(disk["total_bytes"].as_u64().unwrap() >= disk["free_bytes"].as_u64().unwrap());
+    assert_eq!(disk["reserve_bytes"], 1_000_000u64 << 40);
+
+    let response = client
+        .post(format!("http://{}/_irondrop/upload", server.addr))
+        .header("X-Filename", "small.txt")
+        .body("tiny")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+    assert!(!server.temp_dir.path().join("small.txt").exists());
+}
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MIT
+//! Tests for the concurrent large-download cap and its queue.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tempfile::{TempDir, tempdir};
+
+const LARGE_FILE_SIZE: usize = 1024 * 1024 + 1;
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+fn setup_server(
+    max_concurrent_downloads: Option<usize>,
+    download_queue: Option<usize>,
+) -> TestServer {
+    let dir = tempdir().unwrap();
+    let data: Vec<u8> = (0..LARGE_FILE_SIZE).map(|i| (i % 251) as u8).collect();
+    fs::write(dir.path().join("large.bin"), data).unwrap();
+    fs::write(dir.path().join("small.txt"), "small").unwrap();
+
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(true),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        // Slow every download to ~2s so requests overlap
+        per_conn_bandwidth: Some(LARGE_FILE_SIZE as u64 / 2),
+        max_concurrent_downloads,
+        download_queue,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn monitor_downloads(addr: SocketAddr) -> serde_json::Value {
+    let json: serde_json::Value =
+        reqwest::blocking::get(format!("http://{addr}/_irondrop/monitor?json=1"))
+            .unwrap()
+            .json()
+            .unwrap();
+    json["downloads"].clone()
+}
+
+/// Start a large download in the background once the server holds its slot
+fn start_large_download(addr: SocketAddr) -> JoinHandle<usize> {
+    let handle = thread::spawn(move || {
+        let response = reqwest::blocking::get(format!("http://{addr}/large.bin")).unwrap();
+        assert_eq!(response.status(), 200);
+        response.bytes().unwrap().len()
+    });
+    for _ in 0..100 {
+        if monitor_downloads(addr)["active"].as_u64() == Some(1) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    handle
+}
+
+#[test]
+fn test_busy_slots_return_503_with_retry_after() {
+    let server = setup_server(Some(1), None);
+    let first = start_large_download(server.addr);
+
+    let response = reqwest::blocking::get(format!("http://{}/large.bin", server.addr)).unwrap();
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.headers()["retry-after"], "5");
+
+    // Small files never need a slot
+    let response = reqwest::blocking::get(format!("http://{}/small.txt", server.addr)).unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().unwrap(), "small");
+
+    let downloads = monitor_downloads(server.addr);
+    assert_eq!(downloads["active"], 1);
+    assert_eq!(downloads["limit"], 1);
+    assert_eq!(downloads["rejected"], 1);
+
+    assert_eq!(first.join().unwrap(), LARGE_FILE_SIZE);
+    assert_eq!(monitor_downloads(server.addr)["active"], 0);
+}
+
+#[test]
+fn test_queued_download_waits_for_a_slot() {
+    let server = setup_server(Some(1), Some(1));
+    let first = start_large_download(server.addr);
+
+    let addr = server.addr;
+    let second = thread::spawn(move || {
+        let response = reqwest::blocking::get(format!("http://{addr}/large.bin")).unwrap();
+        (response.status().as_u16(), response.bytes().unwrap().len())
+    });
+    for _ in 0..100 {
+        if monitor_downloads(server.addr)["queued"].as_u64() == Some(1) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(monitor_downloads(server.addr)["queued"], 1);
+
+    assert_eq!(first.join().unwrap(), LARGE_FILE_SIZE);
+    assert_eq!(second.join().unwrap(), (200, LARGE_FILE_SIZE));
+    let downloads = monitor_downloads(server.addr);
+    assert_eq!(downloads["queued"], 0);
+    assert_eq!(downloads["rejected"], 0);
+}
+
+#[test]
+fn test_unlimited_by_default() {
+    let server = setup_server(None, None);
+    let downloads = monitor_downloads(server.addr);
+    assert!(downloads["limit"].is_null());
+    assert_eq!(downloads["active"], 0);
+}
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: MIT
+//! In-browser editing (`--enable-edit`, `/_edit/<path>`).
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder};
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server(with_auth: bool) -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "first <draft>\n").unwrap();
+    fs::write(dir.path().join("data.bin"), [0u8, 159, 146, 150]).unwrap();
+    fs::write(dir.path().join("binary.txt"), [0xff, 0xfe, 0x00]).unwrap();
+
+    let mut args = vec![
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--allowed-extensions".to_string(),
+        "*.txt".to_string(),
+        "--enable-edit".to_string(),
+        "true".to_string(),
+    ];
+    if with_auth {
+        args.extend(["--username", "admin", "--password", "secret"].map(str::to_string));
+    }
+    let cli = Cli::parse_from(args);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        dir,
+    }
+}
+
+fn save(client: &Client, server: &TestServer, path: &str, body: &str) -> RequestBuilder {
+    client
+        .post(format!("http://{}/_edit/{path}", server.addr))
+        .basic_auth("admin", Some("secret"))
+        .body(body.to_string())
+}
+
+#[test]
+fn test_edit_and_save_with_etag() {
+    let server = setup_server(true);
+    let client = Client::new();
+    let notes = server.dir.path().join("notes.txt");
+
+    let page = client
+        .get(format!("http://{}/_edit/notes.txt", server.addr))
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .unwrap();
+    assert_eq!(page.status(), StatusCode::OK);
+    let html = page.text().unwrap();
+    assert!(html.contains("first &lt;draft&gt;"));
+    let etag = html
+        .split(r#"data-etag=""#)
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap()
+        .replace("&quot;", "\"");
+
+    let res = save(&client, &server, "notes.txt", "lost").send().unwrap();
+    assert_eq!(res.status(), StatusCode::PRECONDITION_REQUIRED);
+    let res = save(&client, &server, "notes.txt", "lost")
+        .header("If-Match", "\"stale\"")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    let json: serde_json::Value = res.json().unwrap();
+    assert_eq!(json["etag"], etag);
+    assert_eq!(fs::read_to_string(&notes).unwrap(), "first <draft>\n");
+
+    let res = save(&client, &server, "notes.txt", "second version\n")
+        .header("If-Match", &etag)
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let json: serde_json::Value = res.json().unwrap();
+    assert_ne!(json["etag"], etag);
+    assert_eq!(fs::read_to_string(&notes).unwrap(), "second version\n");
+    // No temporary file is left behind
+    assert_eq!(fs::read_dir(server.dir.path()).unwrap().count(), 3);
+
+    // A second editor still holding the old ETag cannot overwrite it
+    let res = save(&client, &server, "notes.txt", "other editor")
+        .header("If-Match", &etag)
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    assert_eq!(fs::read_to_string(&notes).unwrap(), "second version\n");
+
+    let preview = client
+        .get(format!("http://{}/_preview/notes.txt", server.addr))
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert!(preview.contains(r#"href="/_edit/notes.txt""#));
+
+    // Without authentication the editor is not available
+    let open = setup_server(false);
+    let res = client
+        .get(format!("http://{}/_edit/notes.txt", open.addr))
+        .send()
+        .unwrap();
+    assert_ne!(res.status(), StatusCode::OK);
+    let res = client
+        .post(format!("http://{}/_edit/notes.txt", open.addr))
+        .header("If-Match", "*")
+        .body("changed")
+        .send()
+        .unwrap();
+    assert_ne!(res.status(), StatusCode::OK);
+    assert_eq!(
+        fs::read_to_string(open.dir.path().join("notes.txt")).unwrap(),
+        "first <draft>\n"
+    );
+}
+
+#[test]
+fn test_edit_rejects_unsuitable_files() {
+    let server = setup_server(true);
+    let client = Client::new();
+    let get = |path: &str| {
+        client
+            .get(format!("http://{}/_edit/{path}", server.addr))
+            .basic_auth("admin", Some("secret"))
+            .send()
+            .unwrap()
+            .status()
+    };
+
+    assert_eq!(get("data.bin"), StatusCode::FORBIDDEN);
+    assert_eq!(get("binary.txt"), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(get("missing.txt"), StatusCode::NOT_FOUND);
+
+    let res = save(&client, &server, "missing.txt", "new")
+        .header("If-Match", "*")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert!(!server.dir.path().join("missing.txt").exists());
+}
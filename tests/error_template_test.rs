@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MIT
+//! Tests for custom error page templates (`--template-dir`).
+//!
+//! The loaded pages are process-wide (`OnceLock`), so only one test in this
+//! file starts a server with a template directory.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use irondrop::templates::TemplateEngine;
+use reqwest::blocking::Client;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+    _template_dir: TempDir,
+}
+
+fn setup_server(template_dir: TempDir) -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--template-dir".to_string(),
+        template_dir.path().to_string_lossy().into_owned(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+        _template_dir: template_dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+#[test]
+fn test_load_and_render_error_templates() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("404.html"),
+        "<p>{{ERROR_CODE}} {{REQUEST_PATH}}</p>",
+    )
+    .unwrap();
+    fs::write(dir.path().join("500.HTML"), "oops").unwrap();
+    fs::write(dir.path().join("200.html"), "not an error").unwrap();
+    fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+    let pages = TemplateEngine::load_error_templates(dir.path()).unwrap();
+    let mut codes: Vec<u16> = pages.keys().copied().collect();
+    codes.sort_unstable();
+    assert_eq!(codes, vec![404, 500]);
+
+    let html = TemplateEngine::global().render_error_template(
+        &pages[&404],
+        404,
+        "Not Found",
+        "missing",
+        "/a/<b>.txt",
+    );
+    assert_eq!(html, "<p>404 /a/&lt;b&gt;.txt</p>");
+
+    assert!(TemplateEngine::load_error_templates(&dir.path().join("missing")).is_err());
+}
+
+#[test]
+fn test_custom_error_pages_with_fallback() {
+    let template_dir = tempdir().unwrap();
+    fs::write(
+        template_dir.path().join("404.html"),
+        "<h1>Custom {{ERROR_CODE}}: {{ERROR_MESSAGE}}</h1><p>{{REQUEST_PATH}} on {{SERVER_NAME}}</p>",
+    )
+    .unwrap();
+    let server = setup_server(template_dir);
+    let client = Client::new();
+
+    let response = client
+        .get(format!("http://{}/nothing/here.txt?q=1", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    let html = response.text().unwrap();
+    assert!(html.starts_with("<h1>Custom 404: Not Found</h1>"), "{html}");
+    assert!(html.contains("<p>/nothing/here.txt on "));
+    assert!(!html.contains("{{SERVER_NAME}}"));
+
+    // No 405 template: the built-in page is used
+    let response = client
+        .post(format!("http://{}/_irondrop/health", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), 405);
+    let html = response.text().unwrap();
+    assert!(!html.contains("Custom"));
+    assert!(html.contains("405"));
+}
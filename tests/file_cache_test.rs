@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: MIT
+//! In-memory cache of small files (`--file-cache-size`, `--file-cache-max-file`).
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server(cache_size: &str) -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("style.css"), "body { color: red }").unwrap();
+    fs::write(dir.path().join("large.bin"), vec![7u8; 8 * 1024]).unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--file-cache-size".to_string(),
+        cache_size.to_string(),
+        "--file-cache-max-file".to_string(),
+        "4KB".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        dir,
+    }
+}
+
+fn file_cache_stats(addr: SocketAddr) -> serde_json::Value {
+    let json: serde_json::Value =
+        reqwest::blocking::get(format!("http://{addr}/_irondrop/monitor?json=1"))
+            .unwrap()
+            .json()
+            .unwrap();
+    json["file_cache"].clone()
+}
+
+#[test]
+fn test_small_files_are_served_from_memory() {
+    let server = setup_server("1MB");
+    let client = Client::new();
+    let get = |path: &str| {
+        client
+            .get(format!("http://{}{path}", server.addr))
+            .send()
+            .unwrap()
+    };
+
+    for _ in 0..3 {
+        assert_eq!(get("/style.css").text().unwrap(), "body { color: red }");
+    }
+    let res = client
+        .get(format!("http://{}/style.css", server.addr))
+        .header(RANGE, "bytes=7-11")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.text().unwrap(), "color");
+
+    // Files over the per-file limit are streamed and not counted
+    assert_eq!(get("/large.bin").bytes().unwrap().len(), 8 * 1024);
+
+    let stats = file_cache_stats(server.addr);
+    assert_eq!(stats["enabled"], true);
+    assert_eq!(stats["hits"], 3);
+    assert_eq!(stats["misses"], 1);
+    assert_eq!(stats["entries"], 1);
+    assert_eq!(stats["bytes"], 19);
+    assert_eq!(stats["max_file_bytes"], 4 * 1024);
+
+    // A changed file is read again
+    fs::write(server.dir.path().join("style.css"), "body { color: blue }").unwrap();
+    assert_eq!(get("/style.css").text().unwrap(), "body { color: blue }");
+    let stats = file_cache_stats(server.addr);
+    assert_eq!(stats["misses"], 2);
+    assert_eq!(stats["bytes"], 20);
+}
+
+#[test]
+fn test_file_cache_disabled_by_default() {
+    let server = setup_server("0");
+    let res = reqwest::blocking::get(format!("http://{}/style.css", server.addr)).unwrap();
+    assert_eq!(res.text().unwrap(), "body { color: red }");
+
+    let stats = file_cache_stats(server.addr);
+    assert_eq!(stats["enabled"], false);
+    assert_eq!(stats["hits"], 0);
+    assert_eq!(stats["misses"], 0);
+}
@@ -0,0 +1,349 @@
+// SPDX-License-Identifier: MIT
+//! Tests for the `/_irondrop/files` delete/move endpoint and `/_api/mkdir`.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::blocking::Client;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+impl TestServer {
+    fn root(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    fn files_url(&self) -> String {
+        format!("http://{}/_irondrop/files", self.addr)
+    }
+}
+
+fn setup_server(enable_file_management: bool) -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs").join("old.txt"), "old").unwrap();
+
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.txt".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: Some(enable_file_management),
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn post_json(server: &TestServer, body: &str) -> reqwest::blocking::Response {
+    Client::new()
+        .post(server.files_url())
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .unwrap()
+}
+
+#[test]
+fn test_delete_file_and_directory() {
+    let server = setup_server(true);
+
+    let response = Client::new()
+        .delete(format!("{}?path=/notes.txt", server.files_url()))
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(!server.root().join("notes.txt").exists());
+
+    let response = post_json(&server, r#"{"action":"delete","path":"/docs"}"#);
+    assert_eq!(response.status(), 200);
+    assert!(!server.root().join("docs").exists());
+
+    let response = post_json(&server, r#"{"action":"delete","path":"/missing.txt"}"#);
+    assert_eq!(response.status(), 404);
+}
+
+#[test]
+fn test_move_and_rename() {
+    let server = setup_server(true);
+
+    let response = post_json(
+        &server,
+        r#"{"action":"move","path":"/notes.txt","to":"/docs/notes.txt"}"#,
+    );
+    assert_eq!(response.status(), 200);
+    let json: serde_json::Value = response.json().unwrap();
+    assert_eq!(json["to"], "/docs/notes.txt");
+    assert_eq!(
+        fs::read_to_string(server.root().join("docs").join("notes.txt")).unwrap(),
+        "notes"
+    );
+
+    // Destination exists
+    let response = post_json(
+        &server,
+        r#"{"action":"rename","path":"/docs/notes.txt","to":"/docs/old.txt"}"#,
+    );
+    assert_eq!(response.status(), 409);
+    assert!(server.root().join("docs").join("notes.txt").exists());
+
+    // Renamed files must still match the allowed extensions
+    let response = post_json(
+        &server,
+        r#"{"action":"rename","path":"/docs/old.txt","to":"/docs/old.exe"}"#,
+    );
+    assert_eq!(response.status(), 415);
+    assert!(server.root().join("docs").join("old.txt").exists());
+}
+
+#[test]
+fn test_rejects_traversal_and_bad_requests() {
+    let server = setup_server(true);
+
+    let response = post_json(
+        &server,
+        r#"{"action":"move","path":"/notes.txt","to":"/../escaped.txt"}"#,
+    );
+    assert_eq!(response.status(), 403);
+    assert!(server.root().join("notes.txt").exists());
+
+    let response = post_json(&server, r#"{"action":"delete","path":"/"}"#);
+    assert_eq!(response.status(), 403);
+
+    let response = post_json(&server, r#"{"action":"chmod","path":"/notes.txt"}"#);
+    assert_eq!(response.status(), 400);
+
+    let response = post_json(&server, "not json");
+    assert_eq!(response.status(), 400);
+}
+
+#[test]
+fn test_disabled_by_default() {
+    let server = setup_server(false);
+
+    let response = post_json(&server, r#"{"action":"delete","path":"/notes.txt"}"#);
+    assert_eq!(response.status(), 405);
+    assert!(server.root().join("notes.txt").exists());
+
+    let listing = Client::new()
+        .get(format!("http://{}/", server.addr))
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert!(!listing.contains("data-action=\"delete\""));
+}
+
+#[test]
+fn test_listing_shows_controls_when_enabled() {
+    let server = setup_server(true);
+    let listing = Client::new()
+        .get(format!("http://{}/docs/", server.addr))
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert!(listing.contains(r#"data-action="delete" data-path="/docs/old.txt""#));
+    assert!(listing.contains(">Actions</th>"));
+    assert!(listing.contains(r#"id="new-folder" data-path="/docs""#));
+}
+
+fn mkdir(server: &TestServer, body: &str) -> reqwest::blocking::Response {
+    Client::new()
+        .post(format!("http://{}/_api/mkdir", server.addr))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .unwrap()
+}
+
+#[test]
+fn test_mkdir() {
+    let server = setup_server(true);
+
+    let response = mkdir(&server, r#"{"path":"/docs","name":"reports"}"#);
+    assert_eq!(response.status(), 201);
+    let json: serde_json::Value = response.json().unwrap();
+    assert_eq!(json["path"], "/docs/reports");
+    assert!(server.root().join("docs").join("reports").is_dir());
+
+    // The parent defaults to the root
+    let response = mkdir(&server, r#"{"name":"inbox"}"#);
+    assert_eq!(response.status(), 201);
+    assert!(server.root().join("inbox").is_dir());
+
+    let response = mkdir(&server, r#"{"path":"/docs","name":"reports"}"#);
+    assert_eq!(response.status(), 409);
+    let response = mkdir(&server, r#"{"path":"/missing","name":"x"}"#);
+    assert_eq!(response.status(), 404);
+}
+
+#[test]
+fn test_mkdir_rejects_bad_names() {
+    let server = setup_server(true);
+
+    for name in ["..", "a/b", "a\\b", "con", "what?", ""] {
+        let body = serde_json::json!({ "path": "/", "name": name }).to_string();
+        let response = mkdir(&server, &body);
+        assert_eq!(response.status(), 400, "{name}");
+    }
+    let response = mkdir(&server, r#"{"path":"/..","name":"escaped"}"#);
+    assert_eq!(response.status(), 403);
+    assert!(!server.root().parent().unwrap().join("escaped").exists());
+    let response = mkdir(&server, r#"{"path":"/","name":".hidden"}"#);
+    assert_eq!(response.status(), 400);
+
+    let disabled = setup_server(false);
+    let response = mkdir(&disabled, r#"{"name":"inbox"}"#);
+    assert_eq!(response.status(), 405);
+    assert!(!disabled.root().join("inbox").exists());
+}
@@ -188,7 +188,12 @@ mod integration_tests {
         File::create(hidden_subdir.join("file_in_hidden_dir.txt")).unwrap();
 
         // Initialize search system for this directory
-        irondrop::search::initialize_search(temp_path.to_path_buf());
+        irondrop::search::initialize_search(
+            temp_path.to_path_buf(),
+            false,
+            irondrop::search::DEFAULT_TRIGRAM_BUDGET,
+            Default::default(),
+        );
 
         // Give indexing time to complete
         std::thread::sleep(std::time::Duration::from_millis(200));
@@ -200,6 +205,12 @@ mod integration_tests {
             limit: 50,
             offset: 0,
             case_sensitive: false,
+            sort: irondrop::search::SearchSort::Score,
+            order: irondrop::search::SortOrder::Desc,
+            scope: irondrop::search::SearchScope::Name,
+            mode: Default::default(),
+            filters: Default::default(),
+            mount: None,
         };
 
         let results = irondrop::search::perform_search(temp_path, &search_params).unwrap();
@@ -226,6 +237,12 @@ mod integration_tests {
             limit: 50,
             offset: 0,
             case_sensitive: false,
+            sort: irondrop::search::SearchSort::Score,
+            order: irondrop::search::SortOrder::Desc,
+            scope: irondrop::search::SearchScope::Name,
+            mode: Default::default(),
+            filters: Default::default(),
+            mount: None,
         };
 
         let broad_results =
@@ -246,6 +263,12 @@ mod integration_tests {
             limit: 50,
             offset: 0,
             case_sensitive: false,
+            sort: irondrop::search::SearchSort::Score,
+            order: irondrop::search::SortOrder::Desc,
+            scope: irondrop::search::SearchScope::Name,
+            mode: Default::default(),
+            filters: Default::default(),
+            mount: None,
         };
 
         let nested_results =
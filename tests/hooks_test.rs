@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: MIT
+//! End-to-end test for webhook notifications.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server(webhook_url: &str) -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.txt".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: Some("admin".to_string()),
+        password: Some("secret".to_string()),
+        enable_upload: Some(true),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: Some(true),
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: Some(webhook_url.to_string()),
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+/// Accept webhook POSTs and forward (event header, JSON body) pairs
+fn start_receiver() -> (String, mpsc::Receiver<(String, serde_json::Value)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/events", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            let mut reader = BufReader::new(stream);
+            let mut event = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                let lower = line.to_ascii_lowercase();
+                if let Some(value) = lower.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap_or(0);
+                } else if let Some(value) = lower.strip_prefix("x-irondrop-event:") {
+                    event = value.trim().to_string();
+                }
+            }
+            let mut body = vec![0u8; length];
+            let _ = reader.read_exact(&mut body);
+            let _ = reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+            if let Ok(json) = serde_json::from_slice(&body)
+                && tx.send((event, json)).is_err()
+            {
+                break;
+            }
+        }
+    });
+    (url, rx)
+}
+
+fn next_event(rx: &mpsc::Receiver<(String, serde_json::Value)>) -> (String, serde_json::Value) {
+    rx.recv_timeout(Duration::from_secs(10))
+        .expect("webhook was not delivered")
+}
+
+#[test]
+fn test_webhooks_for_upload_delete_and_auth_failure() {
+    let (webhook_url, events) = start_receiver();
+    let server = setup_server(&webhook_url);
+    let client = Client::new();
+
+    let res = client
+        .post(format!("http://{}/_irondrop/upload", server.addr))
+        .basic_auth("admin", Some("secret"))
+        .header("Content-Type", "application/octet-stream")
+        .header("X-Filename", "report.txt")
+        .body("hello webhook")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let (event, json) = next_event(&events);
+    assert_eq!(event, "upload.completed");
+    assert_eq!(json["event"], "upload.completed");
+    assert_eq!(json["data"]["name"], "report.txt");
+    assert_eq!(json["data"]["size"], 13);
+    assert_eq!(json["data"]["client"], "127.0.0.1");
+    assert!(json["timestamp"].as_u64().unwrap() > 0);
+
+    let res = client
+        .delete(format!(
+            "http://{}/_irondrop/files?path=/report.txt",
+            server.addr
+        ))
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let (event, json) = next_event(&events);
+    assert_eq!(event, "file.deleted");
+    assert_eq!(json["data"]["path"], "/report.txt");
+    assert_eq!(json["data"]["source"], "file_management");
+
+    // The normal unauthenticated challenge is not a failure; wrong credentials are
+    let res = client
+        .get(format!("http://{}/", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = client
+        .get(format!("http://{}/", server.addr))
+        .basic_auth("admin", Some("wrong"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let (event, json) = next_event(&events);
+    assert_eq!(event, "auth.failure");
+    assert_eq!(json["data"]["reason"], "invalid credentials");
+    assert_eq!(json["data"]["path"], "/");
+}
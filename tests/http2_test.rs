@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MIT
+//! HTTP/2 over TLS (`--http2`, needs the `http2` feature).
+#![cfg(feature = "http2")]
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use rcgen::generate_simple_self_signed;
+use reqwest::blocking::{Body, Client};
+use reqwest::{StatusCode, Version};
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+    _cert_dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server(http2: bool) -> TestServer {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("hello.txt"), "hello over h2").unwrap();
+
+    let cert_dir = tempdir().unwrap();
+    let cert = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_path = cert_dir.path().join("cert.pem");
+    let key_path = cert_dir.path().join("key.pem");
+    fs::write(&cert_path, cert.cert.pem()).unwrap();
+    fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--enable-upload".to_string(),
+        "true".to_string(),
+        "--ssl-cert".to_string(),
+        cert_path.to_string_lossy().into_owned(),
+        "--ssl-key".to_string(),
+        key_path.to_string_lossy().into_owned(),
+        "--http2".to_string(),
+        http2.to_string(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+        _cert_dir: cert_dir,
+    }
+}
+
+fn client() -> Client {
+    Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_http2_negotiated_via_alpn() {
+    let server = setup_server(true);
+    let client = client();
+    let base = format!("https://localhost:{}", server.addr.port());
+
+    let response = client.get(format!("{base}/hello.txt")).send().unwrap();
+    assert_eq!(response.version(), Version::HTTP_2);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-length"], "13");
+    assert!(response.headers().get("connection").is_none());
+    assert_eq!(response.text().unwrap(), "hello over h2");
+
+    // Several requests share the connection
+    let listing = client.get(format!("{base}/")).send().unwrap();
+    assert_eq!(listing.version(), Version::HTTP_2);
+    assert!(listing.text().unwrap().contains("hello.txt"));
+
+    // Larger than the initial flow-control window
+    let big: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+    fs::write(server.temp_dir.path().join("big.bin"), &big).unwrap();
+    let response = client.get(format!("{base}/big.bin")).send().unwrap();
+    assert_eq!(response.bytes().unwrap().as_ref(), big.as_slice());
+
+    let missing = client.get(format!("{base}/missing.txt")).send().unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    let head = client.head(format!("{base}/hello.txt")).send().unwrap();
+    assert_eq!(head.status(), StatusCode::OK);
+    assert_eq!(head.headers()["content-length"], "13");
+
+    // A body without Content-Length reaches the upload handler intact
+    let upload = client
+        .post(format!("{base}/_irondrop/upload"))
+        .header("X-Filename", "streamed.txt")
+        .header("Accept", "application/json")
+        .body(Body::new(std::io::Cursor::new(b"streamed body".to_vec())))
+        .send()
+        .unwrap();
+    assert_eq!(upload.version(), Version::HTTP_2);
+    assert!(upload.status().is_success(), "{}", upload.status());
+    assert_eq!(
+        fs::read_to_string(server.temp_dir.path().join("streamed.txt")).unwrap(),
+        "streamed body"
+    );
+
+    // HTTP/1.1 clients keep working on the same port
+    let http1 = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .http1_only()
+        .build()
+        .unwrap();
+    let response = http1.get(format!("{base}/hello.txt")).send().unwrap();
+    assert_eq!(response.version(), Version::HTTP_11);
+    assert_eq!(response.text().unwrap(), "hello over h2");
+}
+
+#[test]
+fn test_http2_not_offered_by_default() {
+    let server = setup_server(false);
+    let response = client()
+        .get(format!(
+            "https://localhost:{}/hello.txt",
+            server.addr.port()
+        ))
+        .send()
+        .unwrap();
+    assert_eq!(response.version(), Version::HTTP_11);
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -1,16 +1,21 @@
 // SPDX-License-Identifier: MIT
 
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::error::AppError;
 use irondrop::http::Request;
-use std::io::Write;
+use irondrop::server::run_server;
+use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
 use std::thread;
 use tokio::net::TcpStream as TokioTcpStream;
 
-fn serve_and_parse(request: &str) -> Result<Request, irondrop::error::AppError> {
+fn serve_and_parse(request: &str) -> Result<Request, AppError> {
     serve_and_parse_bytes(request.as_bytes())
 }
 
-fn serve_and_parse_bytes(request: &[u8]) -> Result<Request, irondrop::error::AppError> {
+fn serve_and_parse_bytes(request: &[u8]) -> Result<Request, AppError> {
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let addr = listener.local_addr().unwrap();
     let req_owned = request.to_vec();
@@ -200,9 +205,113 @@ fn test_malformed_headers() {
 fn test_extremely_long_request_line() {
     let long_path = "/".to_string() + &"x".repeat(65536); // 64KB path
     let req = format!("GET {} HTTP/1.1\r\nHost: x\r\n\r\n", long_path);
-    let _result = serve_and_parse(&req);
-    // Should either accept or reject with appropriate error (414 URI Too Long)
-    // This test ensures no panic or infinite loop occurs
+    let result = serve_and_parse(&req);
+    assert!(matches!(result, Err(AppError::UriTooLong)), "{result:?}");
+
+    // Over the URI limit but inside the header block
+    let req = format!("GET /{} HTTP/1.1\r\nHost: x\r\n\r\n", "x".repeat(9000));
+    let result = serve_and_parse(&req);
+    assert!(matches!(result, Err(AppError::UriTooLong)), "{result:?}");
+
+    let req = format!("GET /{} HTTP/1.1\r\nHost: x\r\n\r\n", "x".repeat(4000));
+    assert!(serve_and_parse(&req).is_ok());
+}
+
+#[test]
+fn test_header_limits() {
+    let too_many: String = (0..101).map(|i| format!("X-H{i}: v\r\n")).collect();
+    let result = serve_and_parse(&format!("GET / HTTP/1.1\r\n{too_many}\r\n"));
+    assert!(
+        matches!(result, Err(AppError::HeaderFieldsTooLarge)),
+        "{result:?}"
+    );
+    let enough: String = (0..100).map(|i| format!("X-H{i}: v\r\n")).collect();
+    assert!(serve_and_parse(&format!("GET / HTTP/1.1\r\n{enough}\r\n")).is_ok());
+
+    let long_header = format!("GET / HTTP/1.1\r\nCookie: {}\r\n\r\n", "c".repeat(9000));
+    let result = serve_and_parse(&long_header);
+    assert!(
+        matches!(result, Err(AppError::HeaderFieldsTooLarge)),
+        "{result:?}"
+    );
+
+    // Every header within its own limit, but too much in total
+    let big: String = (0..3)
+        .map(|i| format!("X-Big{i}: {}\r\n", "b".repeat(6000)))
+        .collect();
+    let result = serve_and_parse(&format!("GET / HTTP/1.1\r\n{big}\r\n"));
+    assert!(
+        matches!(result, Err(AppError::HeaderFieldsTooLarge)),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn test_pathological_headers_are_rejected() {
+    let test_cases = [
+        "GET / HTTP/1.1\r\nInvalid-Header-No-Colon\r\n\r\n",
+        "GET / HTTP/1.1\r\nHost: x\r\n: value-without-name\r\n\r\n",
+        "GET / HTTP/1.1\r\nHost : x\r\n\r\n",
+        "GET / HTTP/1.1\r\nHo(st: x\r\n\r\n",
+        "GET / HTTP/1.1\r\nHost: x\r\nX-Custom: a\r\n folded\r\n\r\n",
+        "GET / HTTP/1.1\r\nHost: x\rX-Injected: 1\r\n\r\n",
+        "POST / HTTP/1.1\r\nHost: x\r\nContent-Length: +4\r\n\r\nWiki",
+        "POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 0x4\r\n\r\nWiki",
+        "POST / HTTP/1.1\r\nHost: x\r\nContent-Length:\r\n\r\n",
+    ];
+    for req in test_cases {
+        let result = serve_and_parse(req);
+        assert!(
+            matches!(result, Err(AppError::BadRequest)),
+            "{req:?}: {result:?}"
+        );
+    }
+
+    let req = "GET / HTTP/1.1\r\nX-Tab:\tvalue\t\r\nX-Utf8: caf\u{e9}\r\n\r\n";
+    let request = serve_and_parse(req).unwrap();
+    assert_eq!(request.headers["x-tab"], "value");
+    assert_eq!(request.headers["x-utf8"], "caf\u{e9}");
+}
+
+#[test]
+fn test_header_limit_status_codes() {
+    let dir = tempfile::tempdir().unwrap();
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let server = thread::spawn(move || {
+        let _ = run_server(cli, Some(shutdown_rx), Some(addr_tx));
+    });
+    let addr = addr_rx.recv().unwrap();
+    let status_line = |request: String| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let _ = stream.write_all(request.as_bytes());
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response);
+        let response = String::from_utf8_lossy(&response).into_owned();
+        response.lines().next().unwrap_or_default().to_string()
+    };
+
+    let status = status_line(format!("GET /{} HTTP/1.1\r\n\r\n", "x".repeat(20000)));
+    assert!(status.starts_with("HTTP/1.1 414"), "{status}");
+    let status = status_line(format!(
+        "GET / HTTP/1.1\r\nCookie: {}\r\n\r\n",
+        "c".repeat(9000)
+    ));
+    assert!(status.starts_with("HTTP/1.1 431"), "{status}");
+    let status = status_line("GET / HTTP/1.1\r\nHost x\r\n\r\n".to_string());
+    assert!(status.starts_with("HTTP/1.1 400"), "{status}");
+
+    let _ = shutdown_tx.send(());
+    let _ = server.join();
 }
 
 #[test]
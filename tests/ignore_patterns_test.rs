@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT
+//! Dotfile hiding and `--ignore-patterns` across listings, search and direct access.
+//! One server per process: the rules are process-wide.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+impl TestServer {
+    fn get(&self, path: &str) -> reqwest::blocking::Response {
+        Client::new()
+            .get(format!("http://{}{path}", self.addr))
+            .send()
+            .unwrap()
+    }
+
+    fn listing(&self, path: &str) -> Vec<String> {
+        let json: Value = self.get(&format!("{path}?format=json")).json().unwrap();
+        let mut names: Vec<String> = json["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    for sub in [".git", "keys", "private", "public/private"] {
+        fs::create_dir_all(root.join(sub)).unwrap();
+    }
+    fs::write(root.join("report.txt"), "visible").unwrap();
+    fs::write(root.join(".env"), "SECRET=1").unwrap();
+    fs::write(root.join(".git").join("report.txt"), "git").unwrap();
+    fs::write(root.join("keys").join("report.key"), "key").unwrap();
+    fs::write(root.join("keys").join("report.txt"), "not a key").unwrap();
+    fs::write(root.join("private").join("report.txt"), "private").unwrap();
+    fs::write(
+        root.join("public").join("private").join("report.txt"),
+        "public",
+    )
+    .unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        root.to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "-a".to_string(),
+        "*".to_string(),
+        "--ignore-patterns".to_string(),
+        "*.key,/private/**".to_string(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+#[test]
+fn test_excluded_paths_are_hidden_everywhere() {
+    let server = setup_server();
+
+    assert_eq!(server.listing("/"), vec!["keys", "public", "report.txt"]);
+    assert_eq!(server.listing("/keys/"), vec!["report.txt"]);
+    // Anchored patterns only apply at the top of the served directory
+    assert_eq!(server.listing("/public/"), vec!["private"]);
+
+    for path in [
+        "/.env",
+        "/.git/report.txt",
+        "/keys/report.key",
+        "/private/",
+        "/private/report.txt",
+        "/keys/../private/report.txt",
+        "/_irondrop/preview/keys/report.key",
+    ] {
+        assert_eq!(server.get(path).status(), StatusCode::NOT_FOUND, "{path}");
+    }
+    assert_eq!(server.get("/report.txt").status(), StatusCode::OK);
+    assert_eq!(
+        server.get("/public/private/report.txt").status(),
+        StatusCode::OK
+    );
+
+    // The search index fills in the background
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let paths = loop {
+        let json: Value = server
+            .get("/_irondrop/search?q=report&path=/")
+            .json()
+            .unwrap();
+        let mut paths: Vec<String> = json["results"]
+            .as_array()
+            .map(|results| {
+                results
+                    .iter()
+                    .map(|r| r["path"].as_str().unwrap().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        if paths.len() >= 3 || Instant::now() > deadline {
+            break paths;
+        }
+        thread::sleep(Duration::from_millis(100));
+    };
+    assert_eq!(
+        paths,
+        vec![
+            "/keys/report.txt",
+            "/public/private/report.txt",
+            "/report.txt"
+        ]
+    );
+}
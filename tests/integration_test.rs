@@ -23,6 +23,14 @@ struct TestServer {
 
 /// Sets up and runs a server in a background thread for testing.
 fn setup_test_server(username: Option<String>, password: Option<String>) -> TestServer {
+    setup_test_server_with_auth_paths(username, password, None)
+}
+
+fn setup_test_server_with_auth_paths(
+    username: Option<String>,
+    password: Option<String>,
+    auth_paths: Option<String>,
+) -> TestServer {
     let dir = tempdir().unwrap();
     // Create a dummy file for testing downloads.
     let file_path = dir.path().join("test.txt");
@@ -52,6 +60,108 @@ fn setup_test_server(username: Option<String>, password: Option<String>) -> Test
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let (shutdown_tx, shutdown_rx) = mpsc::channel();
@@ -132,6 +242,50 @@ fn test_authentication_required() {
     assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
 }
 
+#[test]
+fn test_password_protected_directory() {
+    let server =
+        setup_test_server_with_auth_paths(None, None, Some("/private=alice:pw1".to_string()));
+    let private_dir = server._temp_dir.path().join("private");
+    std::fs::create_dir(&private_dir).unwrap();
+    std::fs::write(private_dir.join("secret.txt"), "top secret").unwrap();
+    let client = Client::new();
+
+    // Everything outside the prefix stays public
+    let res = client
+        .get(format!("http://{}/test.txt", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(format!("http://{}/private/secret.txt", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        res.headers()["www-authenticate"],
+        "Basic realm=\"IronDrop /private\""
+    );
+
+    let res = client
+        .get(format!(
+            "http://{}/test.txt/../Private/secret.txt",
+            server.addr
+        ))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = client
+        .get(format!("http://{}/private/secret.txt", server.addr))
+        .basic_auth("alice", Some("pw1"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().unwrap(), "top secret");
+}
+
 #[test]
 fn test_successful_authentication() {
     let server = setup_test_server(Some("user".to_string()), Some("pass".to_string()));
@@ -247,6 +401,239 @@ fn test_http_range_request_partial_content() {
     );
 }
 
+fn raw_get(addr: SocketAddr, path: &str, extra_headers: &str) -> (String, Vec<u8>) {
+    raw_request(addr, "GET", path, extra_headers)
+}
+
+fn raw_request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    extra_headers: &str,
+) -> (String, Vec<u8>) {
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: localhost\r\n{extra_headers}Connection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    // Header lines may end in a bare LF; the blank line before the body is always CRLF
+    let split = buf
+        .windows(3)
+        .position(|w| w == b"\n\r\n")
+        .expect("header terminator");
+    let head = String::from_utf8_lossy(&buf[..split]).to_string();
+    (head, buf[split + 3..].to_vec())
+}
+
+#[test]
+fn test_head_request_headers_without_body() {
+    let server = setup_test_server(None, None);
+    let (get_head, get_body) = raw_get(server.addr, "/test.txt", "");
+    let (head, body) = raw_request(server.addr, "HEAD", "/test.txt", "");
+
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    assert!(body.is_empty(), "HEAD must not send a body");
+    assert!(head.contains(&format!("Content-Length: {}", get_body.len())));
+    assert!(head.contains("Content-Type: text/plain"));
+    assert!(head.contains("Accept-Ranges: bytes"));
+    assert!(head.contains("Last-Modified: "));
+    let etag = |h: &str| {
+        h.lines()
+            .find(|line| line.starts_with("ETag: "))
+            .map(str::to_string)
+    };
+    assert!(etag(&head).is_some());
+    assert_eq!(etag(&head), etag(&get_head));
+
+    let (head, body) = raw_request(server.addr, "HEAD", "/test.txt", "Range: bytes=0-4\r\n");
+    assert!(head.starts_with("HTTP/1.1 206"), "{head}");
+    assert!(head.contains("Content-Length: 5"));
+    assert!(body.is_empty());
+
+    let (head, body) = raw_request(server.addr, "HEAD", "/", "");
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    assert!(body.is_empty());
+
+    let (head, body) = raw_request(server.addr, "HEAD", "/missing.txt", "");
+    assert!(head.starts_with("HTTP/1.1 404"), "{head}");
+    assert!(body.is_empty());
+}
+
+#[test]
+fn test_options_and_method_not_allowed_carry_allow_header() {
+    let server = setup_test_server(None, None);
+    let allow = |h: &str| {
+        h.lines()
+            .find(|line| line.starts_with("Allow: "))
+            .map(|line| line.trim_start_matches("Allow: ").trim().to_string())
+    };
+
+    let (head, body) = raw_request(server.addr, "OPTIONS", "/test.txt", "");
+    assert!(head.starts_with("HTTP/1.1 204"), "{head}");
+    assert_eq!(allow(&head).as_deref(), Some("OPTIONS, GET, HEAD"));
+    assert!(body.is_empty());
+
+    let (head, _) = raw_request(server.addr, "OPTIONS", "/_irondrop/health", "");
+    assert!(head.starts_with("HTTP/1.1 204"), "{head}");
+    assert_eq!(allow(&head).as_deref(), Some("OPTIONS, GET, HEAD"));
+
+    let (head, _) = raw_request(server.addr, "HEAD", "/_irondrop/health", "");
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+
+    let (head, _) = raw_request(server.addr, "POST", "/_irondrop/health", "");
+    assert!(head.starts_with("HTTP/1.1 405"), "{head}");
+    assert_eq!(allow(&head).as_deref(), Some("OPTIONS, GET, HEAD"));
+
+    let (head, _) = raw_request(server.addr, "DELETE", "/test.txt", "");
+    assert!(head.starts_with("HTTP/1.1 405"), "{head}");
+    assert_eq!(allow(&head).as_deref(), Some("OPTIONS, GET, HEAD"));
+
+    let (head, _) = raw_request(server.addr, "OPTIONS", "/_irondrop/nope", "");
+    assert!(head.starts_with("HTTP/1.1 404"), "{head}");
+}
+
+#[test]
+fn test_http_range_suffix_and_unsatisfiable() {
+    let server = setup_test_server(None, None);
+    // test.txt contains "hello from test file\n" (21 bytes)
+    let (head, body) = raw_get(server.addr, "/test.txt", "Range: bytes=-5\r\n");
+    assert!(head.starts_with("HTTP/1.1 206"), "{head}");
+    assert!(head.contains("Content-Range: bytes 16-20/21"), "{head}");
+    assert_eq!(body, b"file\n");
+
+    let (head, body) = raw_get(server.addr, "/test.txt", "Range: bytes=6-9\r\n");
+    assert!(head.starts_with("HTTP/1.1 206"), "{head}");
+    assert_eq!(body, b"from");
+
+    let (head, _) = raw_get(server.addr, "/test.txt", "Range: bytes=500-\r\n");
+    assert!(head.starts_with("HTTP/1.1 416"), "{head}");
+    assert!(head.contains("Content-Range: bytes */21"), "{head}");
+}
+
+#[test]
+fn test_http_if_range_validation() {
+    let server = setup_test_server(None, None);
+    let (head, _) = raw_get(server.addr, "/test.txt", "");
+    let last_modified = head
+        .lines()
+        .find_map(|l| l.strip_prefix("Last-Modified: "))
+        .expect("Last-Modified header")
+        .to_string();
+
+    let matching = format!("Range: bytes=0-4\r\nIf-Range: {last_modified}\r\n");
+    let (head, body) = raw_get(server.addr, "/test.txt", &matching);
+    assert!(head.starts_with("HTTP/1.1 206"), "{head}");
+    assert_eq!(body, b"hello");
+
+    let stale = "Range: bytes=0-4\r\nIf-Range: Mon, 01 Jan 2001 00:00:00 GMT\r\n";
+    let (head, body) = raw_get(server.addr, "/test.txt", stale);
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    assert_eq!(body.len(), 21);
+}
+
+#[test]
+fn test_conditional_get_returns_304() {
+    let server = setup_test_server(None, None);
+    let (head, _) = raw_get(server.addr, "/test.txt", "");
+    let header = |name: &str| {
+        head.lines()
+            .find_map(|l| l.trim().strip_prefix(name))
+            .unwrap_or_else(|| panic!("missing {name}"))
+            .to_string()
+    };
+    let etag = header("ETag: ");
+    let last_modified = header("Last-Modified: ");
+
+    let (head, body) = raw_get(
+        server.addr,
+        "/test.txt",
+        &format!("If-None-Match: \"other\", {etag}\r\n"),
+    );
+    assert!(head.starts_with("HTTP/1.1 304"), "{head}");
+    assert!(head.contains(&etag), "{head}");
+    assert!(body.is_empty());
+
+    let (head, _) = raw_get(
+        server.addr,
+        "/test.txt",
+        &format!("If-Modified-Since: {last_modified}\r\n"),
+    );
+    assert!(head.starts_with("HTTP/1.1 304"), "{head}");
+
+    // Stale validators get the full body
+    let (head, body) = raw_get(server.addr, "/test.txt", "If-None-Match: \"stale\"\r\n");
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    assert_eq!(body.len(), 21);
+    let (head, _) = raw_get(
+        server.addr,
+        "/test.txt",
+        "If-Modified-Since: Mon, 01 Jan 2001 00:00:00 GMT\r\n",
+    );
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+
+    // If-Range also accepts the ETag
+    let (head, body) = raw_get(
+        server.addr,
+        "/test.txt",
+        &format!("Range: bytes=0-4\r\nIf-Range: {etag}\r\n"),
+    );
+    assert!(head.starts_with("HTTP/1.1 206"), "{head}");
+    assert_eq!(body, b"hello");
+}
+
+#[test]
+fn test_gzip_compression_negotiation() {
+    let server = setup_test_server(None, None);
+
+    let (head, body) = raw_get(server.addr, "/", "Accept-Encoding: gzip, br\r\n");
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    assert!(head.contains("Content-Encoding: gzip"), "{head}");
+    assert!(head.contains("Vary: Accept-Encoding"), "{head}");
+    assert_eq!(&body[..2], &[0x1f, 0x8b]);
+    let length = head
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("Content-Length: "))
+        .expect("Content-Length header");
+    assert_eq!(length.parse::<usize>().unwrap(), body.len());
+
+    // No Accept-Encoding: identity body
+    let (head, body) = raw_get(server.addr, "/", "");
+    assert!(!head.contains("Content-Encoding"), "{head}");
+    assert!(String::from_utf8_lossy(&body).contains("test.txt"));
+
+    // Streamed file downloads are never compressed
+    let (head, body) = raw_get(server.addr, "/test.txt", "Accept-Encoding: gzip\r\n");
+    assert!(!head.contains("Content-Encoding"), "{head}");
+    assert_eq!(body, b"hello from test file\n");
+}
+
+#[test]
+fn test_security_headers_on_pages_and_errors() {
+    let server = setup_test_server(None, None);
+    let client = Client::new();
+
+    for (path, status) in [
+        ("/", StatusCode::OK),
+        ("/nonexistent.txt", StatusCode::NOT_FOUND),
+    ] {
+        let res = client
+            .get(format!("http://{}{path}", server.addr))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), status);
+        let headers = res.headers();
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+        assert_eq!(headers["referrer-policy"], "same-origin");
+        let csp = headers["content-security-policy"].to_str().unwrap();
+        assert!(csp.contains("frame-ancestors 'self'"));
+        assert!(!headers.contains_key("strict-transport-security"));
+    }
+}
+
 #[test]
 fn test_static_asset_headers_and_lengths() {
     use std::io::{Read, Write};
@@ -355,11 +742,10 @@ fn test_large_header_handling() {
     let mut status_line = String::new();
     reader.read_line(&mut status_line).unwrap();
 
-    // Should either accept (200) or reject with appropriate error (413/400)
+    // A header line over 8KB is refused as too large
     assert!(
-        status_line.starts_with("HTTP/1.1 200")
-            || status_line.starts_with("HTTP/1.1 413")
-            || status_line.starts_with("HTTP/1.1 400")
+        status_line.starts_with("HTTP/1.1 431"),
+        "unexpected status: {status_line}"
     );
 }
 
@@ -492,6 +878,108 @@ where
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let (shutdown_tx, shutdown_rx) = mpsc::channel();
@@ -556,6 +1044,209 @@ fn test_directory_trailing_slash_redirect_and_links() {
     assert!(!body.contains("/call_rec_data/23/"));
 }
 
+#[test]
+fn test_directory_listing_json() {
+    use serde_json::Value;
+    use std::fs::{create_dir_all, write};
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        create_dir_all(root.join("docs").join("sub dir")).unwrap();
+        write(root.join("docs").join("notes.txt"), "12345").unwrap();
+        write(root.join("docs").join(".hidden.txt"), "x").unwrap();
+        create_dir_all(root.join("odd").join("why?")).unwrap();
+        write(root.join("odd").join("what?.txt"), "question").unwrap();
+    });
+    let client = Client::new();
+
+    let res = client
+        .get(format!("http://{}/docs/?format=json", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(
+        res.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("application/json")
+    );
+    let json: Value = res.json().unwrap();
+    assert_eq!(json["path"], "/docs/");
+    assert_eq!(json["parent"], "/");
+    assert_eq!(json["total"], 2);
+    let entries = json["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["name"], "sub dir");
+    assert_eq!(entries[0]["type"], "directory");
+    assert_eq!(entries[0]["path"], "/docs/sub dir/");
+    assert_eq!(entries[0]["url"], "/docs/sub%20dir/");
+    assert_eq!(entries[1]["name"], "notes.txt");
+    assert_eq!(entries[1]["type"], "file");
+    assert_eq!(entries[1]["size_bytes"], 5);
+    assert!(entries[1]["modified"].as_u64().is_some());
+
+    // Accept header negotiation gives the same document
+    let res = client
+        .get(format!("http://{}/docs/", server.addr))
+        .header("Accept", "application/json")
+        .send()
+        .unwrap();
+    let negotiated: Value = res.json().unwrap();
+    assert_eq!(negotiated["entries"], json["entries"]);
+
+    // Browsers still get HTML
+    let res = client
+        .get(format!("http://{}/docs/", server.addr))
+        .header("Accept", "text/html,application/xhtml+xml")
+        .send()
+        .unwrap();
+    assert!(res.text().unwrap().contains("<html"));
+
+    // Links to names with a `?` lead back to them
+    let json: Value = client
+        .get(format!("http://{}/odd/?format=json", server.addr))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    let entries = json["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["url"], "/odd/why%3F/");
+    assert_eq!(entries[1]["url"], "/odd/what%3F.txt");
+    let res = client
+        .get(format!("http://{}/odd/what%3F.txt", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().unwrap(), "question");
+    let no_redirect = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let res = no_redirect
+        .get(format!("http://{}/odd/why%3F?format=json", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.headers()["location"], "/odd/why%3F/?format=json");
+}
+
+#[test]
+fn test_directory_listing_sort_and_filter() {
+    use serde_json::Value;
+    use std::fs::{create_dir_all, write};
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        create_dir_all(root.join("zeta")).unwrap();
+        write(root.join("alpha.txt"), "1").unwrap();
+        write(root.join("beta.txt"), "12345").unwrap();
+        write(root.join("gamma.log"), "123").unwrap();
+    });
+    let client = Client::new();
+    let names = |query: &str| -> Vec<String> {
+        let json: Value = client
+            .get(format!("http://{}/?format=json&{query}", server.addr))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        json["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // Folders stay first whatever the order
+    assert_eq!(
+        names("sort=name&order=desc"),
+        ["zeta", "test.txt", "gamma.log", "beta.txt", "alpha.txt"]
+    );
+    assert_eq!(
+        names("sort=size&order=desc"),
+        ["zeta", "test.txt", "beta.txt", "gamma.log", "alpha.txt"]
+    );
+    assert_eq!(names("filter=TXT"), ["alpha.txt", "beta.txt", "test.txt"]);
+    assert_eq!(
+        names("filter=.txt&sort=size"),
+        ["alpha.txt", "beta.txt", "test.txt"]
+    );
+
+    let res = client
+        .get(format!("http://{}/?sort=color", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    // Column headers link to the flipped order and keep the filter
+    let html = client
+        .get(format!("http://{}/?sort=size&filter=a", server.addr))
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert!(
+        html.contains(r#"aria-sort="ascending"><a href="?sort=size&amp;order=desc&amp;filter=a""#)
+    );
+    assert!(html.contains(r#"value="a""#));
+    assert!(html.contains(r#"href="/alpha.txt""#));
+}
+
+#[test]
+fn test_directory_gallery_view() {
+    use std::fs::{create_dir_all, write};
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        create_dir_all(root.join("photos")).unwrap();
+        write(root.join("photos").join("beach.jpg"), "jpg").unwrap();
+        write(root.join("photos").join("hill.png"), "png").unwrap();
+        write(root.join("photos").join("notes.txt"), "text").unwrap();
+    });
+    let client = Client::new();
+    let get = |path: &str, cookie: Option<&str>| {
+        let mut request = client.get(format!("http://{}{path}", server.addr));
+        if let Some(cookie) = cookie {
+            request = request.header("Cookie", cookie);
+        }
+        request.send().unwrap()
+    };
+
+    // Offered, but the list stays the default
+    let html = get("/photos/", None).text().unwrap();
+    assert!(html.contains(r#"href="?view=gallery""#));
+    assert!(!html.contains(r#"class="gallery-item""#));
+
+    let res = get("/photos/?view=gallery", None);
+    assert_eq!(res.status(), StatusCode::OK);
+    let cookie = res.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(cookie.starts_with("irondrop_view=gallery;"));
+    assert!(!cookie.contains("Max-Age"));
+    let html = res.text().unwrap();
+    assert!(html.contains(r#"<img src="/photos/beach.jpg" alt="beach.jpg" loading="lazy""#));
+    assert!(html.contains(r#"href="?view=list""#));
+    // Other files stay in the table
+    assert!(html.contains(r#"href="/photos/notes.txt" class="file-link""#));
+    assert!(!html.contains(r#"href="/photos/hill.png" class="file-link""#));
+
+    // The remembered preference applies without the parameter
+    let html = get("/photos/", Some("irondrop_view=gallery"))
+        .text()
+        .unwrap();
+    assert!(html.contains(r#"class="gallery-item""#));
+    let html = get("/photos/?view=list", Some("irondrop_view=gallery"))
+        .text()
+        .unwrap();
+    assert!(!html.contains(r#"class="gallery-item""#));
+
+    // Folders without many pictures never switch
+    let html = get("/", Some("irondrop_view=gallery")).text().unwrap();
+    assert!(!html.contains(r#"class="gallery-item""#));
+    assert!(!html.contains("Gallery view"));
+
+    assert_eq!(
+        get("/photos/?view=grid", None).status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
 #[test]
 fn test_search_api_directory_paths_have_trailing_slash() {
     use serde_json::Value;
@@ -579,7 +1270,7 @@ fn test_search_api_directory_paths_have_trailing_slash() {
     assert_eq!(res.status(), StatusCode::OK);
     let text = res.text().unwrap();
     let json: Value = serde_json::from_str(&text).unwrap();
-    if let Some(arr) = json.as_array() {
+    if let Some(arr) = json["results"].as_array() {
         // Look for directory entries and ensure trailing slash
         for item in arr {
             if item["type"] == "directory" {
@@ -589,3 +1280,311 @@ fn test_search_api_directory_paths_have_trailing_slash() {
         }
     }
 }
+
+#[test]
+fn test_search_api_sorting_and_pagination() {
+    use serde_json::Value;
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        std::fs::write(root.join("quokka_small.txt"), "a").unwrap();
+        std::fs::write(root.join("quokka_large.txt"), "a".repeat(300)).unwrap();
+        std::fs::write(root.join("quokka_medium.txt"), "a".repeat(20)).unwrap();
+    });
+    let client = Client::new();
+    let search = |query: &str| -> (StatusCode, Value) {
+        let res = client
+            .get(format!("http://{}/_irondrop/search?{query}", server.addr))
+            .send()
+            .unwrap();
+        let status = res.status();
+        (status, res.json().unwrap_or(Value::Null))
+    };
+
+    let (status, first) = search("q=quokka&sort=size&order=desc&limit=2");
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["total"], 3);
+    assert_eq!(first["sort"], "size");
+    assert_eq!(first["order"], "desc");
+    let names: Vec<&str> = first["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["quokka_large.txt", "quokka_medium.txt"]);
+    assert!(matches!(
+        first["index_state"].as_str(),
+        Some("building" | "updating" | "ready")
+    ));
+    assert_eq!(first["results"][0]["matches"], serde_json::json!([[0, 6]]));
+    assert_eq!(
+        first["results"][0]["highlighted"],
+        "<mark>quokka</mark>_large.txt"
+    );
+    // The index keeps sizes rounded to a power of two
+    assert!(first["results"][0]["size_bytes"].as_u64().unwrap() >= 256);
+
+    let token = first["next_token"].as_str().unwrap();
+    let (_, second) = search(&format!(
+        "q=quokka&sort=size&order=desc&limit=2&token={token}"
+    ));
+    assert_eq!(second["results"][0]["name"], "quokka_small.txt");
+    assert!(second["next_token"].is_null());
+
+    let (_, by_name) = search("q=quokka&sort=name");
+    assert_eq!(by_name["order"], "asc");
+    assert_eq!(by_name["results"][0]["name"], "quokka_large.txt");
+    assert_eq!(by_name["results"][2]["name"], "quokka_small.txt");
+
+    assert_eq!(search("q=quokka&sort=colour").0, StatusCode::BAD_REQUEST);
+    assert_eq!(search("q=quokka&order=up").0, StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_search_api_filters() {
+    use serde_json::Value;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        std::fs::write(root.join("wombat_notes.pdf"), "a".repeat(300)).unwrap();
+        std::fs::write(root.join("wombat_draft.docx"), "a".repeat(20)).unwrap();
+        std::fs::write(root.join("wombat_photo.jpg"), "a").unwrap();
+        std::fs::create_dir(root.join("wombat_archive")).unwrap();
+        // Older than the index can store exactly
+        std::fs::File::options()
+            .write(true)
+            .open(root.join("wombat_photo.jpg"))
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(1_600_000_000))
+            .unwrap();
+    });
+    let client = Client::new();
+    let search = |filters: &str| -> (StatusCode, Vec<String>) {
+        let res = client
+            .get(format!(
+                "http://{}/_irondrop/search?q=wombat&sort=name&{filters}",
+                server.addr
+            ))
+            .send()
+            .unwrap();
+        let status = res.status();
+        let json: Value = res.json().unwrap_or(Value::Null);
+        let names = json["results"]
+            .as_array()
+            .map(|results| {
+                results
+                    .iter()
+                    .map(|r| r["name"].as_str().unwrap().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        (status, names)
+    };
+
+    assert_eq!(search("type=dir").1, ["wombat_archive"]);
+    assert_eq!(
+        search("ext=PDF,.docx").1,
+        ["wombat_draft.docx", "wombat_notes.pdf"]
+    );
+    assert_eq!(search("min_size=100").1, ["wombat_notes.pdf"]);
+    assert_eq!(
+        search("type=file&max_size=25").1,
+        ["wombat_draft.docx", "wombat_photo.jpg"]
+    );
+    assert_eq!(search("modified_before=2021-01-01").1, ["wombat_photo.jpg"]);
+    assert_eq!(search("modified_after=2021-01-01&type=file").1.len(), 2);
+
+    assert_eq!(search("type=link").0, StatusCode::BAD_REQUEST);
+    assert_eq!(search("min_size=big").0, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        search("modified_after=yesterday").0,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_search_api_glob_and_regex_modes() {
+    use serde_json::Value;
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        std::fs::write(root.join("numbat_2023.log"), "a").unwrap();
+        std::fs::write(root.join("numbat_2024.log"), "a").unwrap();
+        std::fs::write(root.join("numbat_notes.txt"), "a").unwrap();
+    });
+    let client = Client::new();
+    let search = |query: &str| -> (StatusCode, Value) {
+        let res = client
+            .get(format!(
+                "http://{}/_irondrop/search?sort=name&{query}",
+                server.addr
+            ))
+            .send()
+            .unwrap();
+        let status = res.status();
+        (status, res.json().unwrap_or(Value::Null))
+    };
+    let names = |json: &Value| -> Vec<String> {
+        json["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let (status, json) = search("q=numbat_*.LOG&mode=glob");
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["mode"], "glob");
+    assert_eq!(names(&json), ["numbat_2023.log", "numbat_2024.log"]);
+
+    // ^numbat_\d{3}4\.
+    let (_, json) = search("q=%5Enumbat_%5Cd%7B3%7D4%5C.&mode=regex");
+    assert_eq!(names(&json), ["numbat_2024.log"]);
+
+    assert_eq!(search("q=numbat&mode=fuzzy").0, StatusCode::BAD_REQUEST);
+    assert_eq!(search("q=(numbat&mode=regex").0, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        search("q=*a*b*c*d*e*f*g*h*i*&mode=glob").0,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[test]
+fn test_liveness_and_readiness_probes() {
+    let server = setup_test_server(None, None);
+    let client = Client::new();
+
+    let res = client
+        .get(format!("http://{}/_health/live", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let live: serde_json::Value = res.json().unwrap();
+    assert_eq!(live["status"], "alive");
+
+    let res = client
+        .get(format!("http://{}/_health/ready", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let ready: serde_json::Value = res.json().unwrap();
+    assert_eq!(ready["status"], "ready");
+    let checks = ready["checks"].as_array().unwrap();
+    let status_of = |checks: &[serde_json::Value], name: &str| {
+        checks
+            .iter()
+            .find(|c| c["name"] == name)
+            .map(|c| c["status"].as_str().unwrap().to_string())
+            .unwrap()
+    };
+    assert_eq!(status_of(checks, "directory"), "pass");
+    assert_eq!(status_of(checks, "upload_directory"), "skip");
+    assert_eq!(status_of(checks, "search_index"), "pass");
+
+    // The served directory disappearing makes the instance not ready
+    std::fs::remove_dir_all(server._temp_dir.path()).unwrap();
+    let res = client
+        .get(format!("http://{}/_health/ready", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let ready: serde_json::Value = res.json().unwrap();
+    assert_eq!(ready["status"], "not_ready");
+    assert_eq!(
+        status_of(ready["checks"].as_array().unwrap(), "directory"),
+        "fail"
+    );
+}
+
+#[test]
+fn test_search_suggestions_and_saved_searches() {
+    use serde_json::Value;
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        std::fs::write(root.join("wombat_notes.txt"), "a").unwrap();
+    });
+    let client = Client::new();
+    let url = |path: &str| format!("http://{}{path}", server.addr);
+
+    let res = client
+        .get(url("/_irondrop/search?q=wombat"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let cookie = res.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(cookie.starts_with("irondrop_search=") && cookie.contains("HttpOnly"));
+    let session = cookie.split(';').next().unwrap().to_string();
+
+    let suggest = |query: &str, cookie: Option<&str>| -> Value {
+        let mut request = client.get(url(&format!("/_irondrop/search/suggest?q={query}")));
+        if let Some(cookie) = cookie {
+            request = request.header("Cookie", cookie);
+        }
+        request.send().unwrap().json().unwrap()
+    };
+    assert_eq!(
+        suggest("wom", Some(&session))["recent"],
+        serde_json::json!(["wombat"])
+    );
+    // Other browsers do not see the query
+    assert_eq!(suggest("wom", None)["recent"], serde_json::json!([]));
+
+    let name = format!("wombats-{}", server.addr.port());
+    let saved = client
+        .post(url(&format!(
+            "/_irondrop/search/saved?name={name}&q=wombat&ext=txt"
+        )))
+        .send()
+        .unwrap();
+    assert_eq!(saved.status(), StatusCode::CREATED);
+    let listed: Value = client
+        .get(url("/_irondrop/search/saved"))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    assert!(
+        listed["saved"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|s| s["name"] == name.as_str() && s["query"] == "q=wombat&ext=txt")
+    );
+    assert_eq!(suggest("wombats", None)["saved"][0]["name"], name.as_str());
+
+    let bad = client
+        .post(url("/_irondrop/search/saved?name=bad&q=wombat&mode=fuzzy"))
+        .send()
+        .unwrap();
+    assert_eq!(bad.status(), StatusCode::BAD_REQUEST);
+    let deleted = client
+        .delete(url(&format!("/_irondrop/search/saved?name={name}")))
+        .send()
+        .unwrap();
+    assert_eq!(deleted.status(), StatusCode::OK);
+}
+
+#[test]
+fn test_index_status_endpoint() {
+    use serde_json::Value;
+
+    let server = setup_test_server_with_tree(None, None, |root| {
+        std::fs::write(root.join("numbat.txt"), "a").unwrap();
+    });
+    let client = Client::new();
+    for route in ["/_api/index/status", "/_irondrop/index/status"] {
+        let res = client
+            .get(format!("http://{}{route}", server.addr))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let status: Value = res.json().unwrap();
+        assert!(matches!(
+            status["state"].as_str(),
+            Some("building" | "updating" | "ready")
+        ));
+        assert!(status["entries_scanned"].is_u64());
+        assert!(status["directories_remaining"].is_u64());
+        assert!(status["eta_secs"].is_null() || status["eta_secs"].is_u64());
+    }
+}
@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MIT
+//! Tests for CIDR allow/deny filtering of incoming connections.
+
+use irondrop::cli::Cli;
+use irondrop::server::{CidrBlock, IpFilter, run_server};
+use reqwest::blocking::Client;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_cidr_block_matching() {
+    let block = CidrBlock::parse("192.168.1.0/24").unwrap();
+    assert!(block.contains(ip("192.168.1.77")));
+    assert!(!block.contains(ip("192.168.2.1")));
+    assert!(block.contains(ip("::ffff:192.168.1.5")));
+
+    let host = CidrBlock::parse("10.0.0.5").unwrap();
+    assert!(host.contains(ip("10.0.0.5")));
+    assert!(!host.contains(ip("10.0.0.6")));
+
+    let any = CidrBlock::parse("0.0.0.0/0").unwrap();
+    assert!(any.contains(ip("8.8.8.8")));
+
+    let v6 = CidrBlock::parse("2001:db8::/32").unwrap();
+    assert!(v6.contains(ip("2001:db8:1::1")));
+    assert!(!v6.contains(ip("2001:db9::1")));
+    assert!(!v6.contains(ip("10.0.0.1")));
+
+    assert!(CidrBlock::parse("10.0.0.0/33").is_err());
+    assert!(CidrBlock::parse("not-an-ip").is_err());
+    assert!(CidrBlock::parse("10.0.0.0/x").is_err());
+}
+
+#[test]
+fn test_ip_filter_precedence() {
+    let filter = IpFilter::new(
+        vec![CidrBlock::parse("10.0.0.0/8").unwrap()],
+        vec![CidrBlock::parse("10.1.0.0/16").unwrap()],
+        false,
+    );
+    assert!(filter.is_allowed(ip("10.2.3.4")));
+    assert!(!filter.is_allowed(ip("10.1.3.4")));
+    assert!(!filter.is_allowed(ip("192.168.0.1")));
+
+    let open = IpFilter::default();
+    assert!(!open.is_active());
+    assert!(open.is_allowed(ip("203.0.113.9")));
+}
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+fn setup_filtered_server(allow: Option<&str>, deny: Option<&str>) -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.txt".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: allow.map(str::to_string),
+        deny_ips: deny.map(str::to_string),
+        log_rejected_ips: Some(true),
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn get_root(addr: SocketAddr) -> reqwest::Result<reqwest::blocking::Response> {
+    Client::new().get(format!("http://{addr}/")).send()
+}
+
+#[test]
+fn test_denied_peer_is_dropped_before_parsing() {
+    let server = setup_filtered_server(None, Some("127.0.0.0/8"));
+    assert!(get_root(server.addr).is_err());
+}
+
+#[test]
+fn test_allowlist_admits_matching_peer() {
+    let server = setup_filtered_server(Some("127.0.0.1/32"), None);
+    assert!(get_root(server.addr).unwrap().status().is_success());
+
+    let server = setup_filtered_server(Some("192.0.2.0/24"), None);
+    assert!(get_root(server.addr).is_err());
+}
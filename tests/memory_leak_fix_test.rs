@@ -33,6 +33,9 @@ mod tests {
                 file_type: "file".to_string(),
                 score: 50.0,
                 last_modified: Some(1234567890),
+                size_bytes: 1024,
+                matched_ranges: Vec::new(),
+                mount: irondrop::search::DEFAULT_MOUNT.to_string(),
             }];
             cache.put(query, std::sync::Arc::new(results));
         }
@@ -76,7 +79,12 @@ mod tests {
         }
 
         // Initialize search system
-        irondrop::search::initialize_search(test_dir.clone());
+        irondrop::search::initialize_search(
+            test_dir.clone(),
+            false,
+            irondrop::search::DEFAULT_TRIGRAM_BUDGET,
+            Default::default(),
+        );
 
         // Wait for initial indexing
         std::thread::sleep(Duration::from_millis(500));
@@ -1,9 +1,17 @@
 // SPDX-License-Identifier: MIT
 
 use base64::Engine;
+use irondrop::error::AppError;
 use irondrop::http::Request;
-use irondrop::middleware::{AuthMiddleware, Middleware};
+use irondrop::middleware::{
+    ApiToken, AuthMiddleware, CorsPolicy, Middleware, PathAuthRule, SecurityHeaders,
+};
+use irondrop::password::{PasswordHash, hash_password_with};
+use irondrop::server::RateLimiter;
+use irondrop::users::{Role, UserAccount};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 fn make_request_with_auth(header: Option<&str>) -> Request {
     let mut headers = HashMap::new();
@@ -13,8 +21,11 @@ fn make_request_with_auth(header: Option<&str>) -> Request {
     Request {
         method: "GET".to_string(),
         path: "/".to_string(),
+        query: None,
         headers,
         body: None,
+        peer_addr: None,
+        forwarded_by: None,
     }
 }
 
@@ -74,8 +85,11 @@ fn test_auth_middleware_case_insensitive_header() {
         let request = Request {
             method: "GET".to_string(),
             path: "/".to_string(),
+            query: None,
             headers,
             body: None,
+            peer_addr: None,
+            forwarded_by: None,
         };
 
         let result = mw.handle(&request);
@@ -247,3 +261,685 @@ fn test_auth_middleware_concurrent_access() {
         assert!(result.is_ok(), "Concurrent authentication should succeed");
     }
 }
+
+fn token_middleware(basic: bool) -> AuthMiddleware {
+    let tokens = ApiToken::parse_list(&["ci:s3cr3t".to_string(), "backup-key".to_string()]);
+    let (user, pass) = if basic {
+        (Some("user".into()), Some("pass".into()))
+    } else {
+        (None, None)
+    };
+    AuthMiddleware::new(user, pass).with_tokens(tokens)
+}
+
+#[test]
+fn test_api_token_parse_list_labels() {
+    let tokens = ApiToken::parse_list(&[
+        "ci:abc".to_string(),
+        "plain".to_string(),
+        "empty:".to_string(),
+    ]);
+    let labels: Vec<&str> = tokens.iter().map(|t| t.label.as_str()).collect();
+    assert_eq!(labels, vec!["ci", "token-2"]);
+}
+
+#[test]
+fn test_auth_middleware_bearer_and_api_key_tokens() {
+    let mw = token_middleware(false);
+    assert!(mw.is_enabled());
+    assert!(
+        mw.handle(&make_request_with_auth(Some("Bearer s3cr3t")))
+            .is_ok()
+    );
+    assert!(
+        mw.handle(&make_request_with_auth(Some("Bearer backup-key")))
+            .is_ok()
+    );
+    assert!(
+        mw.handle(&make_request_with_auth(Some("Bearer wrong")))
+            .is_err()
+    );
+    assert!(mw.handle(&make_request_with_auth(None)).is_err());
+
+    let mut request = make_request_with_auth(None);
+    request
+        .headers
+        .insert("x-api-key".to_string(), "s3cr3t".to_string());
+    assert!(mw.handle(&request).is_ok());
+
+    // Basic credentials are not accepted when only tokens are configured
+    let creds = base64::engine::general_purpose::STANDARD.encode("user:pass");
+    assert!(
+        mw.handle(&make_request_with_auth(Some(&format!("Basic {creds}"))))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_auth_middleware_tokens_alongside_basic() {
+    let mw = token_middleware(true);
+    let creds = base64::engine::general_purpose::STANDARD.encode("user:pass");
+    assert!(
+        mw.handle(&make_request_with_auth(Some(&format!("Basic {creds}"))))
+            .is_ok()
+    );
+    assert!(
+        mw.handle(&make_request_with_auth(Some("Bearer s3cr3t")))
+            .is_ok()
+    );
+    assert!(
+        mw.handle(&make_request_with_auth(Some("Bearer nope")))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_auth_middleware_password_hash() {
+    let argon2 = PasswordHash::parse(&hash_password_with("s3cret", 64, 1).unwrap()).unwrap();
+    let bcrypt =
+        PasswordHash::parse("$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW")
+            .unwrap();
+    for (hash, password) in [(argon2, "s3cret"), (bcrypt, "U*U")] {
+        let mw = AuthMiddleware::new(Some("user".into()), None).with_password_hash(Some(hash));
+        assert!(mw.is_enabled());
+        let good = basic(&format!("user:{password}"));
+        assert!(mw.handle(&make_request_with_auth(Some(&good))).is_ok());
+        // A remembered header passes again without another verification
+        assert!(mw.handle(&make_request_with_auth(Some(&good))).is_ok());
+        for bad in [
+            basic(&format!("other:{password}")),
+            basic("user:wrong"),
+            basic("user"),
+            "Basic !!!".to_string(),
+        ] {
+            assert!(matches!(
+                mw.handle(&make_request_with_auth(Some(&bad))),
+                Err(AppError::Unauthorized)
+            ));
+        }
+        assert!(mw.handle(&make_request_with_auth(None)).is_err());
+    }
+}
+
+#[test]
+fn test_auth_middleware_user_roles() {
+    let argon2 = hash_password_with("admin-pw", 64, 1).unwrap();
+    let accounts = UserAccount::parse_list(&[
+        format!("root=admin:{argon2}"),
+        "up=upload:up-pw".to_string(),
+        "viewer=read-only:view-pw".to_string(),
+    ]);
+    let mw =
+        AuthMiddleware::new(Some("owner".into()), Some("owner-pw".into())).with_users(accounts);
+    let request = |method: &str, path: &str, credentials: &str| {
+        let mut request = make_request_with_auth(Some(&basic(credentials)));
+        request.method = method.to_string();
+        request.path = path.to_string();
+        mw.handle(&request)
+    };
+
+    for credentials in ["owner:owner-pw", "root:admin-pw"] {
+        assert!(request("DELETE", "/old.txt", credentials).is_ok());
+        assert!(request("GET", "/monitor", credentials).is_ok());
+    }
+    assert!(request("PUT", "/new.txt", "up:up-pw").is_ok());
+    assert!(request("POST", "/_irondrop/upload", "up:up-pw").is_ok());
+    assert!(request("GET", "/_irondrop/share", "up:up-pw").is_ok());
+    for (method, path) in [("DELETE", "/old.txt"), ("POST", "/_api/mkdir")] {
+        assert!(matches!(
+            request(method, path, "up:up-pw"),
+            Err(AppError::Forbidden)
+        ));
+    }
+    assert!(request("GET", "/docs/", "viewer:view-pw").is_ok());
+    for (method, path) in [
+        ("PUT", "/new.txt"),
+        ("GET", "/monitor"),
+        ("GET", "/_irondrop/share"),
+        ("GET", "/_api/share"),
+    ] {
+        assert!(matches!(
+            request(method, path, "viewer:view-pw"),
+            Err(AppError::Forbidden)
+        ));
+    }
+    for bad in ["viewer:up-pw", "nobody:view-pw", "root:wrong"] {
+        assert!(matches!(
+            request("GET", "/", bad),
+            Err(AppError::Unauthorized)
+        ));
+    }
+}
+
+#[test]
+fn test_auth_lockout_after_failures() {
+    let limiter =
+        Arc::new(RateLimiter::new(1000, 100).with_auth_lockout(3, Duration::from_secs(60)));
+    let mw =
+        AuthMiddleware::new(Some("user".into()), Some("pass".into())).with_lockout(limiter.clone());
+    let from = |ip: &str, header: Option<&str>| {
+        let mut request = make_request_with_auth(header);
+        request.peer_addr = Some(format!("{ip}:40000").parse().unwrap());
+        request
+    };
+    let (good, bad) = (basic("user:pass"), basic("user:nope"));
+
+    // The browser challenge without credentials is not a failure
+    for _ in 0..5 {
+        assert!(matches!(
+            mw.handle(&from("10.0.0.1", None)),
+            Err(AppError::Unauthorized)
+        ));
+    }
+    // Logging in resets the count
+    for _ in 0..2 {
+        assert!(mw.handle(&from("10.0.0.1", Some(&bad))).is_err());
+    }
+    assert!(mw.handle(&from("10.0.0.1", Some(&good))).is_ok());
+    for _ in 0..2 {
+        assert!(mw.handle(&from("10.0.0.1", Some(&bad))).is_err());
+    }
+    assert!(mw.handle(&from("10.0.0.1", Some(&good))).is_ok());
+
+    for _ in 0..3 {
+        assert!(matches!(
+            mw.handle(&from("10.0.0.2", Some(&bad))),
+            Err(AppError::Unauthorized)
+        ));
+    }
+    // Locked out, even with the right password; other IPs are unaffected
+    match mw.handle(&from("10.0.0.2", Some(&good))) {
+        Err(AppError::TooManyRequests(retry_after)) => assert!((1..=60).contains(&retry_after)),
+        other => panic!("expected a lockout, got {other:?}"),
+    }
+    assert!(limiter.auth_lockout("10.0.0.2".parse().unwrap()).is_some());
+    assert!(mw.handle(&from("10.0.0.3", Some(&good))).is_ok());
+
+    // 0 failures turns the lockout off
+    let limiter =
+        Arc::new(RateLimiter::new(1000, 100).with_auth_lockout(0, Duration::from_secs(60)));
+    let mw = AuthMiddleware::new(Some("user".into()), Some("pass".into())).with_lockout(limiter);
+    for _ in 0..20 {
+        assert!(matches!(
+            mw.handle(&from("10.0.0.4", Some(&bad))),
+            Err(AppError::Unauthorized)
+        ));
+    }
+}
+
+#[test]
+fn test_auth_lockout_backoff() {
+    let limiter = RateLimiter::new(1000, 100).with_auth_lockout(2, Duration::from_secs(1));
+    let ip = "10.0.0.9".parse().unwrap();
+    let lock_out = |limiter: &RateLimiter| {
+        assert_eq!(limiter.record_auth_failure(ip), None);
+        limiter.record_auth_failure(ip)
+    };
+
+    // Each lockout in a row lasts twice as long as the one before
+    assert_eq!(lock_out(&limiter), Some(1));
+    std::thread::sleep(Duration::from_millis(1100));
+    assert_eq!(limiter.auth_lockout(ip), None);
+    assert_eq!(lock_out(&limiter), Some(2));
+    assert!(limiter.auth_lockout(ip).is_some());
+
+    // Logging in forgives the earlier lockouts
+    limiter.clear_auth_failures(ip);
+    assert_eq!(lock_out(&limiter), Some(1));
+}
+
+fn basic(user_pass: &str) -> String {
+    format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD.encode(user_pass)
+    )
+}
+
+fn path_request(target: &str, header: Option<&str>) -> Request {
+    let mut request = make_request_with_auth(header);
+    match target.split_once('?') {
+        Some((path, query)) => {
+            request.path = path.to_string();
+            request.query = Some(query.to_string());
+        }
+        None => request.path = target.to_string(),
+    }
+    request
+}
+
+fn path_middleware(global: bool) -> AuthMiddleware {
+    let rules = PathAuthRule::parse_list(&[
+        "/private=alice:pw1".to_string(),
+        "/private/=bob:pw2".to_string(),
+        "/private/reports=carol:pw3".to_string(),
+        "no-credentials".to_string(),
+        "/=root:pw".to_string(),
+    ]);
+    let (user, pass) = if global {
+        (Some("user".into()), Some("pass".into()))
+    } else {
+        (None, None)
+    };
+    AuthMiddleware::new(user, pass).with_path_rules(rules)
+}
+
+#[test]
+fn test_path_auth_rule_parse_list() {
+    let rules = PathAuthRule::parse_list(&[
+        "/a=alice:pw1".to_string(),
+        "/a/b/=bob:pw2".to_string(),
+        "/a/./=carol:pw3".to_string(),
+        "/broken".to_string(),
+        "/nouser=:pw".to_string(),
+    ]);
+    let prefixes: Vec<&str> = rules.iter().map(|r| r.prefix.as_str()).collect();
+    assert_eq!(prefixes, vec!["/a/b", "/a"]);
+    assert_eq!(rules[1].realm, "IronDrop /a");
+}
+
+#[test]
+fn test_path_auth_challenges_only_protected_prefix() {
+    let mw = path_middleware(false);
+    assert!(mw.is_enabled());
+
+    assert!(mw.handle(&path_request("/", None)).is_ok());
+    assert!(mw.handle(&path_request("/public/file.txt", None)).is_ok());
+    assert!(
+        mw.handle(&path_request("/privateer/file.txt", None))
+            .is_ok()
+    );
+
+    match mw.handle(&path_request("/private/file.txt", None)) {
+        Err(AppError::UnauthorizedRealm(realm)) => assert_eq!(realm, "IronDrop /private"),
+        other => panic!("expected realm challenge, got {other:?}"),
+    }
+    match mw.handle(&path_request("/private/reports/q1.pdf", None)) {
+        Err(AppError::UnauthorizedRealm(realm)) => {
+            assert_eq!(realm, "IronDrop /private/reports")
+        }
+        other => panic!("expected realm challenge, got {other:?}"),
+    }
+
+    let alice = basic("alice:pw1");
+    let bob = basic("bob:pw2");
+    let carol = basic("carol:pw3");
+    assert!(mw.handle(&path_request("/private", Some(&alice))).is_ok());
+    assert!(
+        mw.handle(&path_request("/private/a.txt", Some(&bob)))
+            .is_ok()
+    );
+    assert!(
+        mw.handle(&path_request("/private/reports/q1.pdf", Some(&carol)))
+            .is_ok()
+    );
+    // The most specific prefix decides
+    assert!(
+        mw.handle(&path_request("/private/reports/q1.pdf", Some(&alice)))
+            .is_err()
+    );
+    assert!(
+        mw.handle(&path_request("/private/a.txt", Some(&basic("alice:nope"))))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_path_auth_resists_traversal_and_indirect_paths() {
+    let mw = path_middleware(false);
+
+    for path in [
+        "/public/../private/file.txt",
+        "//private//file.txt",
+        "/./private/file.txt",
+        "/PRIVATE/File.txt",
+        "/_preview/private/file.txt",
+        "/_irondrop/preview/private/file.txt",
+        "/_irondrop/search?q=x&path=/private",
+        "/_irondrop/files?path=/public/../private/file.txt",
+    ] {
+        assert!(
+            matches!(
+                mw.handle(&path_request(path, None)),
+                Err(AppError::UnauthorizedRealm(_))
+            ),
+            "{path} should require the directory credentials"
+        );
+    }
+
+    let mut request = path_request("/public/file.txt", None);
+    request.method = "MOVE".to_string();
+    request.headers.insert(
+        "destination".to_string(),
+        "http://localhost:8080/private/file.txt".to_string(),
+    );
+    assert!(mw.handle(&request).is_err());
+
+    let mut request = path_request("/_irondrop/files", None);
+    request.method = "POST".to_string();
+    request.body = Some(irondrop::http::RequestBody::Memory(
+        br#"{"action":"rename","path":"/public/a.txt","to":"/private/a.txt"}"#.to_vec(),
+    ));
+    assert!(mw.handle(&request).is_err());
+}
+
+#[test]
+fn test_path_auth_with_global_credentials_and_connection_cache() {
+    let mw = path_middleware(true);
+    let global = basic("user:pass");
+    let alice = basic("alice:pw1");
+
+    // Global credentials open everything; directory credentials only their prefix
+    assert!(
+        mw.handle(&path_request("/private/a.txt", Some(&global)))
+            .is_ok()
+    );
+    assert!(
+        mw.handle(&path_request("/public/a.txt", Some(&global)))
+            .is_ok()
+    );
+    assert!(
+        mw.handle(&path_request("/public/a.txt", Some(&alice)))
+            .is_err()
+    );
+    assert!(matches!(
+        mw.handle(&path_request("/public/a.txt", None)),
+        Err(AppError::Unauthorized)
+    ));
+
+    let peer: std::net::SocketAddr = "127.0.0.1:40000".parse().unwrap();
+    let mut request = path_request("/private/a.txt", Some(&alice));
+    request.peer_addr = Some(peer);
+    assert!(mw.handle(&request).is_ok());
+    // Cached for the same connection and credentials only
+    assert!(mw.handle(&request).is_ok());
+    request
+        .headers
+        .insert("authorization".to_string(), basic("alice:bad"));
+    assert!(mw.handle(&request).is_err());
+    request.headers.remove("authorization");
+    assert!(mw.handle(&request).is_err());
+}
+
+fn preflight_request(origin: &str) -> Request {
+    let mut request = make_request_with_auth(None);
+    request.method = "OPTIONS".to_string();
+    request.path = "/_irondrop/search".to_string();
+    request
+        .headers
+        .insert("origin".to_string(), origin.to_string());
+    request.headers.insert(
+        "access-control-request-method".to_string(),
+        "GET".to_string(),
+    );
+    request.headers.insert(
+        "access-control-request-headers".to_string(),
+        "authorization, x-custom".to_string(),
+    );
+    request
+}
+
+#[test]
+fn test_cors_wildcard_origin() {
+    let policy = CorsPolicy::new(&["*".to_string()], "GET", "*", 60, false);
+    let mut headers = HashMap::new();
+    policy.apply(Some("https://anywhere.example"), &mut headers);
+    assert_eq!(headers["Access-Control-Allow-Origin"], "*");
+    assert!(!headers.contains_key("Vary"));
+
+    let response = policy
+        .preflight(&preflight_request("https://anywhere.example"))
+        .unwrap();
+    assert_eq!(response.status_code, 204);
+    assert_eq!(
+        response.headers["Access-Control-Allow-Headers"],
+        "authorization, x-custom"
+    );
+    assert_eq!(response.headers["Access-Control-Max-Age"], "60");
+
+    // Any origin is never echoed back with credentials allowed
+    let with_credentials = CorsPolicy::new(&["*".to_string()], "GET", "*", 60, true);
+    let mut headers = HashMap::new();
+    with_credentials.apply(Some("https://anywhere.example"), &mut headers);
+    assert_eq!(headers["Access-Control-Allow-Origin"], "*");
+    assert!(!headers.contains_key("Access-Control-Allow-Credentials"));
+}
+
+#[test]
+fn test_cors_origin_list() {
+    let policy = CorsPolicy::new(
+        &["https://app.example.com/".to_string()],
+        "GET, POST",
+        "Authorization",
+        600,
+        false,
+    );
+    let mut headers = HashMap::new();
+    headers.insert("Vary".to_string(), "Accept".to_string());
+    policy.apply(Some("https://app.example.com"), &mut headers);
+    assert_eq!(
+        headers["Access-Control-Allow-Origin"],
+        "https://app.example.com"
+    );
+    assert_eq!(headers["Vary"], "Accept, Origin");
+
+    let mut headers = HashMap::new();
+    policy.apply(Some("https://evil.example.com"), &mut headers);
+    assert!(headers.is_empty());
+    assert_eq!(
+        policy
+            .preflight(&preflight_request("https://evil.example.com"))
+            .unwrap()
+            .status_code,
+        403
+    );
+
+    // Requests without the preflight headers go through normal routing
+    assert!(policy.preflight(&make_request_with_auth(None)).is_none());
+    assert!(CorsPolicy::covers("/_irondrop/upload"));
+    assert!(CorsPolicy::covers("/_api/checksum"));
+    assert!(!CorsPolicy::covers("/docs/readme.md"));
+}
+
+#[test]
+fn test_security_headers_defaults_and_overrides() {
+    let mut headers = HashMap::new();
+    SecurityHeaders::new(false, None, None, None, None).apply(&mut headers);
+    assert_eq!(headers["X-Content-Type-Options"], "nosniff");
+    assert_eq!(headers["X-Frame-Options"], "SAMEORIGIN");
+    assert_eq!(headers["Referrer-Policy"], "same-origin");
+    assert!(headers["Content-Security-Policy"].starts_with("default-src 'self'"));
+    assert!(!headers.contains_key("Strict-Transport-Security"));
+
+    // Handlers keep headers they set themselves, and "off" drops one
+    let mut headers = HashMap::new();
+    headers.insert("x-frame-options".to_string(), "DENY".to_string());
+    SecurityHeaders::new(true, Some("off"), None, Some("no-referrer"), Some(60))
+        .apply(&mut headers);
+    assert_eq!(headers["x-frame-options"], "DENY");
+    assert!(!headers.contains_key("X-Frame-Options"));
+    assert!(!headers.contains_key("Content-Security-Policy"));
+    assert_eq!(headers["Referrer-Policy"], "no-referrer");
+    assert_eq!(headers["Strict-Transport-Security"], "max-age=60");
+
+    let mut headers = HashMap::new();
+    SecurityHeaders::new(true, None, None, None, Some(0)).apply(&mut headers);
+    assert!(!headers.contains_key("Strict-Transport-Security"));
+}
+
+#[test]
+fn test_identify_and_role_of_verified_requests() {
+    let users = UserAccount::parse_list(&["viewer=read-only:view-pw".to_string()]);
+    let mw = AuthMiddleware::new(Some("admin".into()), Some("secret".into()))
+        .with_users(users)
+        .with_tokens(vec![ApiToken::new("ci", "tok123")]);
+    let basic = |creds: &str| {
+        format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(creds)
+        )
+    };
+
+    let admin = make_request_with_auth(Some(&basic("admin:secret")));
+    let identity = mw.identify(&admin).unwrap();
+    assert_eq!(
+        (identity.key(), identity.role),
+        ("user:admin".into(), Role::Admin)
+    );
+    let viewer = make_request_with_auth(Some(&basic("viewer:view-pw")));
+    assert_eq!(mw.role_of(&viewer), Role::ReadOnly);
+    assert_eq!(mw.identify(&viewer).unwrap().key(), "user:viewer");
+    let token = make_request_with_auth(Some("Bearer tok123"));
+    assert_eq!(mw.identify(&token).unwrap().key(), "token:ci");
+
+    // Made-up or missing credentials get the lowest role, never admin
+    for header in [Some(basic("random:x")), Some(basic("admin:wrong")), None] {
+        let request = make_request_with_auth(header.as_deref());
+        assert_eq!(mw.identify(&request), None);
+        assert_eq!(mw.role_of(&request), Role::ReadOnly);
+    }
+
+    // Without accounts there are no roles to respect
+    let open = AuthMiddleware::new(None, None);
+    let request = make_request_with_auth(Some(&basic("random:x")));
+    assert_eq!(open.identify(&request), None);
+    assert_eq!(open.role_of(&request), Role::Admin);
+}
+
+#[test]
+fn test_path_auth_query_parameters_do_not_stand_in_for_the_url() {
+    let rules = PathAuthRule::parse_list(&["/private=bob:pw".to_string()]);
+    let mw =
+        AuthMiddleware::new(Some("admin".into()), Some("secret".into())).with_path_rules(rules);
+    let bob = basic("bob:pw");
+    let admin = basic("admin:secret");
+
+    // Directory credentials do not open other paths through a query parameter
+    for method in ["GET", "DELETE"] {
+        let mut request = path_request("/other.txt?path=/private", Some(&bob));
+        request.method = method.to_string();
+        assert!(
+            matches!(mw.handle(&request), Err(AppError::Unauthorized)),
+            "{method} with a foreign ?path= must need the global credentials"
+        );
+    }
+    assert!(mw.handle(&path_request("/other.txt", Some(&bob))).is_err());
+
+    // Routes that read `path` need the directory's and the global credentials
+    let checksum = "/_irondrop/checksum?path=/private/a.txt";
+    assert!(matches!(
+        mw.handle(&path_request(checksum, None)),
+        Err(AppError::UnauthorizedRealm(_))
+    ));
+    assert!(mw.handle(&path_request(checksum, Some(&bob))).is_err());
+    assert!(mw.handle(&path_request(checksum, Some(&admin))).is_ok());
+
+    // Moving out of the directory touches a path the rule does not cover
+    let mut request = path_request("/private/a.txt", Some(&bob));
+    request.method = "MOVE".to_string();
+    request.headers.insert(
+        "destination".to_string(),
+        "http://localhost:8080/public/a.txt".to_string(),
+    );
+    assert!(mw.handle(&request).is_err());
+    request.headers.insert(
+        "destination".to_string(),
+        "http://localhost:8080/private/b.txt".to_string(),
+    );
+    assert!(mw.handle(&request).is_ok());
+
+    // Full access inside the directory only, and no role anywhere else
+    assert_eq!(
+        mw.role_of(&path_request("/private/a.txt", Some(&bob))),
+        Role::Admin
+    );
+    let outside = path_request("/other.txt?path=/private", Some(&bob));
+    assert_eq!(mw.role_of(&outside), Role::ReadOnly);
+    assert_eq!(mw.identify(&outside).unwrap().role, Role::ReadOnly);
+}
+
+#[test]
+fn test_path_auth_covers_upload_targets() {
+    let mw = path_middleware(false);
+    let mut requests = vec![
+        path_request("/_irondrop/upload?upload_to=/private", None),
+        path_request("/_irondrop/upload?upload_to=private/reports", None),
+        path_request("/_irondrop/upload/session?upload_to=/private", None),
+        path_request("/private/new.txt", None),
+    ];
+    let mut with_header = path_request("/_irondrop/upload", None);
+    with_header
+        .headers
+        .insert("x-upload-to".to_string(), "/public/../private".to_string());
+    requests.push(with_header);
+    for mut request in requests {
+        request.method = if request.path.starts_with("/_irondrop/") {
+            "POST".to_string()
+        } else {
+            "PUT".to_string()
+        };
+        assert!(
+            matches!(mw.handle(&request), Err(AppError::UnauthorizedRealm(_))),
+            "{} {} should require the directory credentials",
+            request.method,
+            request.target()
+        );
+        request
+            .headers
+            .insert("authorization".to_string(), basic("alice:pw1"));
+        let allowed = mw.handle(&request).is_ok();
+        // `/private/reports` has its own user
+        assert_eq!(
+            allowed,
+            !request.query.as_deref().unwrap_or("").contains("reports"),
+            "{}",
+            request.target()
+        );
+    }
+    let mut plain = path_request("/_irondrop/upload?upload_to=/public", None);
+    plain.method = "POST".to_string();
+    assert!(mw.handle(&plain).is_ok());
+}
+
+#[test]
+fn test_locked_paths_follow_the_most_specific_rule() {
+    let mw = path_middleware(true);
+    let locked = mw.locked_paths(&path_request("/", Some(&basic("alice:pw1"))));
+    assert!(!locked.hides("/public/a.txt"));
+    assert!(!locked.hides("/private/a.txt"));
+    assert!(locked.hides("/private/reports/q1.pdf"));
+    assert!(locked.hides("/PRIVATE/../private/reports"));
+
+    let anonymous = mw.locked_paths(&path_request("/", None));
+    assert!(anonymous.hides("/private"));
+    let global = mw.locked_paths(&path_request("/", Some(&basic("user:pass"))));
+    assert!(!global.hides("/private/reports/q1.pdf"));
+}
+
+#[test]
+fn test_path_auth_matches_any_unicode_normalization_form() {
+    let composed = PathAuthRule::parse_list(&["/Privé=bob:pw".to_string()]);
+    let decomposed = PathAuthRule::parse_list(&["/Prive\u{301}=bob:pw".to_string()]);
+    for rule in [&composed[0], &decomposed[0]] {
+        for path in [
+            "/Privé/a.txt",
+            "/Prive\u{301}/a.txt",
+            "/PRIVÉ",
+            "/prive\u{301}",
+        ] {
+            assert!(rule.covers(path), "{} should cover {path}", rule.prefix);
+        }
+        assert!(!rule.covers("/Prive/a.txt"));
+        assert!(!rule.covers("/Privée"));
+    }
+
+    let mw = AuthMiddleware::new(None, None).with_path_rules(composed);
+    assert!(matches!(
+        mw.handle(&path_request("/Prive\u{301}/secret.txt", None)),
+        Err(AppError::UnauthorizedRealm(_))
+    ));
+    let merged = PathAuthRule::parse_list(&[
+        "/Privé=bob:pw".to_string(),
+        "/Prive\u{301}=carol:pw".to_string(),
+    ]);
+    assert_eq!(merged.len(), 1);
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT
+//! Tests for `--mmap-cache-size`.
+//!
+//! The cache is process-wide (`OnceLock`), so only one test in this file
+//! starts a server.
+#![cfg(all(unix, target_pointer_width = "64"))]
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--allowed-extensions".to_string(),
+        "*".to_string(),
+        "--mmap-cache-size".to_string(),
+        "1MB".to_string(),
+        "--mmap-max-file".to_string(),
+        "512KB".to_string(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+#[test]
+fn test_downloads_from_mmap_cache() {
+    let server = setup_server();
+    let root = server.temp_dir.path();
+    let data: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(root.join("hot.bin"), &data).unwrap();
+    fs::write(root.join("large.bin"), vec![7u8; 600 * 1024]).unwrap();
+    let client = Client::new();
+    let url = |name: &str| format!("http://{}/{name}", server.addr);
+
+    for _ in 0..2 {
+        let response = client.get(url("hot.bin")).send().unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-length"], "300000");
+        assert_eq!(response.bytes().unwrap().as_ref(), data.as_slice());
+    }
+
+    let cache = irondrop::mmap_cache::global().expect("cache enabled");
+    assert_eq!(cache.usage(), (1, 300_000));
+
+    let response = client
+        .get(url("hot.bin"))
+        .header("Range", "bytes=1000-1099")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.bytes().unwrap().as_ref(), &data[1000..1100]);
+
+    let response = client.head(url("hot.bin")).send().unwrap();
+    assert_eq!(response.headers()["content-length"], "300000");
+
+    // Replacing the file invalidates its mapping
+    fs::write(root.join("hot.tmp"), b"replaced").unwrap();
+    fs::rename(root.join("hot.tmp"), root.join("hot.bin")).unwrap();
+    let response = client.get(url("hot.bin")).send().unwrap();
+    assert_eq!(response.text().unwrap(), "replaced");
+
+    // Files over --mmap-max-file are streamed as usual
+    let response = client.get(url("large.bin")).send().unwrap();
+    assert_eq!(response.bytes().unwrap().len(), 600 * 1024);
+    assert_eq!(cache.usage(), (1, 8));
+}
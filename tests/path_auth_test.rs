@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MIT
+//! `[auth.paths]` directories against every way of reaching them indirectly.
+//!
+//! The search index is process-wide, so this file starts a single server
+//! and checks each way in turn.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::HeaderValue;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("Privé")).unwrap();
+    fs::write(dir.path().join("Privé").join("secret.txt"), "secret").unwrap();
+    fs::write(dir.path().join("public.txt"), "public").unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--allowed-extensions".to_string(),
+        "*.txt".to_string(),
+        "--enable-upload".to_string(),
+        "true".to_string(),
+        "--auth-paths".to_string(),
+        "/Privé=bob:pw".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        dir,
+    }
+}
+
+fn names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+/// The upload entry points, each writing `name` into `/Privé`
+const UPLOAD_ENTRY_POINTS: [&str; 4] = ["upload_to", "X-Upload-To", "session", "PUT"];
+
+fn upload(client: &Client, addr: SocketAddr, entry: &str, name: &str) -> RequestBuilder {
+    let base = format!("http://{addr}");
+    match entry {
+        "upload_to" => client
+            .post(format!("{base}/_irondrop/upload?upload_to=/Priv%C3%A9"))
+            .header("X-Filename", name)
+            .body("x"),
+        "X-Upload-To" => client
+            .post(format!("{base}/_irondrop/upload"))
+            .header(
+                "X-Upload-To",
+                HeaderValue::from_bytes("/Privé".as_bytes()).unwrap(),
+            )
+            .header("X-Filename", name)
+            .body("x"),
+        "session" => client
+            .post(format!(
+                "{base}/_irondrop/upload/session?upload_to=/Priv%C3%A9"
+            ))
+            .header("X-Filename", name)
+            .header("X-Upload-Length", "1"),
+        _ => client.put(format!("{base}/Priv%C3%A9/{name}")).body("x"),
+    }
+}
+
+#[test]
+fn test_protected_directories_cannot_be_reached_indirectly() {
+    let server = setup_server();
+    let client = Client::new();
+    uploads_respect_protected_directories(&server, &client);
+    search_hides_protected_directories(&server, &client);
+    other_normalization_forms_are_protected(&server, &client);
+}
+
+fn uploads_respect_protected_directories(server: &TestServer, client: &Client) {
+    for entry in UPLOAD_ENTRY_POINTS {
+        let res = upload(client, server.addr, entry, "anonymous.txt")
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{entry}");
+    }
+    assert_eq!(names(&server.dir.path().join("Privé")), ["secret.txt"]);
+
+    for entry in UPLOAD_ENTRY_POINTS {
+        let res = upload(client, server.addr, entry, "bob.txt")
+            .basic_auth("bob", Some("pw"))
+            .send()
+            .unwrap();
+        assert!(res.status().is_success(), "{entry}: {}", res.status());
+    }
+    assert!(server.dir.path().join("Privé").join("bob.txt").exists());
+    assert!(!server.dir.path().join("bob.txt").exists());
+}
+
+/// Paths in the results of searching for `query`
+fn search(client: &Client, addr: SocketAddr, query: &str, bob: bool) -> Vec<String> {
+    let mut request = client.get(format!("http://{addr}/_irondrop/search?q={query}"));
+    if bob {
+        request = request.basic_auth("bob", Some("pw"));
+    }
+    let res = request.send().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let json: serde_json::Value = res.json().unwrap();
+    let mut paths: Vec<String> = json["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["path"].as_str().unwrap().to_string())
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn search_hides_protected_directories(server: &TestServer, client: &Client) {
+    // Wait for the index to pick up the tree
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while search(client, server.addr, "public", false).is_empty() {
+        assert!(Instant::now() < deadline, "search index never became ready");
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    assert_eq!(
+        search(client, server.addr, "secret", false),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        search(client, server.addr, "Priv", false),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        search(client, server.addr, "secret", true),
+        ["/Privé/secret.txt"]
+    );
+}
+
+fn other_normalization_forms_are_protected(server: &TestServer, client: &Client) {
+    // `Privé` is served for its decomposed spelling too, as macOS sends it
+    for path in [
+        "/Priv%C3%A9/secret.txt",
+        "/Prive%CC%81/secret.txt",
+        "/PRIV%C3%89/secret.txt",
+    ] {
+        let url = format!("http://{}{path}", server.addr);
+        let res = client.get(&url).send().unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{path}");
+    }
+    let res = client
+        .get(format!("http://{}/Prive%CC%81/secret.txt", server.addr))
+        .basic_auth("bob", Some("pw"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().unwrap(), "secret");
+}
@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: MIT
+//! Request path decoding: escapes, `+`, encoded traversal and Unicode
+//! normalization, consistent between listing links, search results and the
+//! file handler. One server per process: the search index is process-wide.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+/// Status line for a request target sent exactly as given
+fn raw_status(addr: std::net::SocketAddr, target: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "GET {target} HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    let response = String::from_utf8_lossy(&response).into_owned();
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn test_paths_decode_the_same_everywhere() {
+    let dir = tempdir().unwrap();
+    // Decomposed on disk, as a Mac would have written it
+    fs::write(dir.path().join("cafe\u{301}.txt"), "coffee").unwrap();
+    fs::create_dir(dir.path().join("r\u{e9}sum\u{e9}")).unwrap();
+    fs::write(dir.path().join("r\u{e9}sum\u{e9}").join("cv.txt"), "cv").unwrap();
+    for name in ["a+b.txt", "a b.txt", "100%.txt", "what#.txt", "what?.txt"] {
+        fs::write(dir.path().join(name), name).unwrap();
+    }
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+    let client = Client::new();
+    let get = |target: &str| client.get(format!("http://{addr}{target}")).send().unwrap();
+
+    // Composed and decomposed spellings reach the same file
+    assert_eq!(get("/caf%C3%A9.txt").text().unwrap(), "coffee");
+    assert_eq!(get("/cafe%CC%81.txt").text().unwrap(), "coffee");
+    assert_eq!(get("/re%CC%81sume%CC%81/cv.txt").text().unwrap(), "cv");
+    // `+` is a plus in paths, `%2F` a separator
+    assert_eq!(get("/a+b.txt").text().unwrap(), "a+b.txt");
+    assert_eq!(get("/a%2Bb.txt").text().unwrap(), "a+b.txt");
+    assert_eq!(get("/a%20b.txt").text().unwrap(), "a b.txt");
+    assert_eq!(get("/100%25.txt").text().unwrap(), "100%.txt");
+    assert_eq!(get("/r%C3%A9sum%C3%A9%2Fcv.txt").text().unwrap(), "cv");
+    // An escaped `?` is part of the name; only a literal one starts the query
+    assert_eq!(get("/what%3F.txt").text().unwrap(), "what?.txt");
+    assert_eq!(get("/what%3F.txt?download=1").text().unwrap(), "what?.txt");
+    assert_eq!(get("/what?.txt").status(), StatusCode::NOT_FOUND);
+
+    // Listing links lead back to their entries
+    let listing: serde_json::Value = get("/?format=json").json().unwrap();
+    let entries = listing["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 7);
+    for entry in entries {
+        let url = entry["url"].as_str().unwrap();
+        assert!(url.is_ascii(), "{url}");
+        assert_eq!(get(url).status(), StatusCode::OK, "{url}");
+    }
+
+    // Search results carry the same links
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let results = loop {
+        let body: serde_json::Value = get("/_irondrop/search?q=caf").json().unwrap();
+        if body["total"] == 1 || Instant::now() > deadline {
+            break body["results"].clone();
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    let result = &results[0];
+    let listed = entries
+        .iter()
+        .find(|entry| entry["path"] == result["path"])
+        .unwrap();
+    assert_eq!(result["url"], listed["url"]);
+    assert_eq!(
+        get(result["url"].as_str().unwrap()).text().unwrap(),
+        "coffee"
+    );
+
+    // Encoded traversal and broken escapes are refused outright
+    for target in [
+        "/%2e%2e/%2e%2e/etc/passwd",
+        "/r%C3%A9sum%C3%A9/..%2f..%2fetc/passwd",
+        "/..%5c..%5cwindows",
+        "/%C0%AE%C0%AE/etc/passwd",
+        "/caf%C3.txt",
+        "/a%2",
+        "/nul%00.txt",
+    ] {
+        let status = raw_status(addr, target);
+        assert!(status.starts_with("HTTP/1.1 400"), "{target}: {status}");
+    }
+    let status = raw_status(addr, "/../etc/passwd");
+    assert!(status.starts_with("HTTP/1.1 403"), "{status}");
+
+    let _ = shutdown_tx.send(());
+    let _ = handle.join();
+}
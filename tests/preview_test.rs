@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: MIT
+//! Tests for the Markdown/text and media preview routes.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::blocking::Client;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+impl TestServer {
+    fn get(&self, path: &str) -> reqwest::blocking::Response {
+        Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap()
+            .get(format!("http://{}{path}", self.addr))
+            .send()
+            .unwrap()
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(
+        dir.path().join("docs").join("README.md"),
+        "# Guide\n\nSee [setup](setup.md) and <b>bold</b>.\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("server.log"), "started\n<error> failed\n").unwrap();
+    fs::write(
+        dir.path().join("image.png"),
+        [0x89, b'P', b'N', b'G', 0, 0, 0],
+    )
+    .unwrap();
+    fs::write(dir.path().join("secret.key"), "hidden").unwrap();
+    fs::write(dir.path().join("clip.mp4"), [0u8; 64]).unwrap();
+    fs::write(dir.path().join("song.mp3"), [0u8; 64]).unwrap();
+
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.md,*.log,*.png,*.mp4,*.mp3".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+#[test]
+fn test_markdown_preview_renders_html() {
+    let server = setup_server();
+    let response = server.get("/_preview/docs/README.md");
+    assert_eq!(response.status(), 200);
+    let html = response.text().unwrap();
+    assert!(html.contains(r#"<h1 id="guide">Guide</h1>"#));
+    assert!(html.contains(r#"<a href="setup.md">setup</a>"#));
+    assert!(html.contains("&lt;b&gt;bold&lt;/b&gt;"));
+    assert!(html.contains("markdown-body"));
+    assert!(html.contains(r#"href="/docs/README.md""#));
+
+    let namespaced = server.get("/_irondrop/preview/docs/README.md");
+    assert_eq!(namespaced.status(), 200);
+}
+
+#[test]
+fn test_text_preview_has_line_numbers() {
+    let server = setup_server();
+    let html = server.get("/_preview/server.log").text().unwrap();
+    assert!(html.contains(r##"<a class="line-number" href="#L2">2</a>&lt;error&gt; failed"##));
+    assert!(html.contains("text-body"));
+}
+
+#[test]
+fn test_preview_rejections_and_binary_redirect() {
+    let server = setup_server();
+
+    let binary = server.get("/_preview/image.png");
+    assert_eq!(binary.status(), 302);
+    assert_eq!(binary.headers()["location"], "/image.png");
+
+    assert_eq!(server.get("/_preview/secret.key").status(), 403);
+    assert_eq!(server.get("/_preview/missing.md").status(), 404);
+    assert_eq!(server.get("/_preview/docs").status(), 404);
+
+    // Sent raw: HTTP clients normalise dot segments before they reach the server
+    let mut stream = TcpStream::connect(server.addr).unwrap();
+    stream
+        .write_all(b"GET /_preview/../etc/passwd.md HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+}
+
+#[test]
+fn test_media_preview_and_inline_streaming() {
+    let server = setup_server();
+
+    let html = server.get("/_preview/clip.mp4").text().unwrap();
+    assert!(html.contains("<video"));
+    assert!(html.contains(r#"<source src="/clip.mp4" type="video/mp4">"#));
+    let html = server.get("/_preview/song.mp3").text().unwrap();
+    assert!(html.contains("<audio"));
+    assert!(html.contains(r#"type="audio/mpeg""#));
+
+    let listing = server.get("/").text().unwrap();
+    assert!(listing.contains(r#"href="/_preview/clip.mp4" class="media-play""#));
+    assert!(!listing.contains(r#"href="/_preview/server.log""#));
+
+    let response = server.get("/clip.mp4");
+    assert_eq!(response.headers()["content-type"], "video/mp4");
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    assert_eq!(
+        response.headers()["content-disposition"],
+        "inline; filename=\"clip.mp4\"; filename*=UTF-8''clip.mp4"
+    );
+    let ranged = Client::new()
+        .get(format!("http://{}/clip.mp4", server.addr))
+        .header("Range", "bytes=16-31")
+        .send()
+        .unwrap();
+    assert_eq!(ranged.status(), 206);
+    assert_eq!(ranged.headers()["content-range"], "bytes 16-31/64");
+    assert_eq!(ranged.bytes().unwrap().len(), 16);
+}
@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: MIT
+//! Uploads with a plain `PUT /<dir>/<name>` (`curl -T`) while WebDAV is off.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server(extra: &[&str]) -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+
+    let mut args = vec![
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--allowed-extensions".to_string(),
+        "*.txt".to_string(),
+    ];
+    args.extend(extra.iter().map(|arg| arg.to_string()));
+    let cli = Cli::parse_from(args);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        dir,
+    }
+}
+
+fn put(server: &TestServer, path: &str, body: &'static str) -> Response {
+    Client::new()
+        .put(format!("http://{}{path}", server.addr))
+        .body(body)
+        .send()
+        .unwrap()
+}
+
+#[test]
+fn test_put_upload_saves_file_from_url() {
+    let server = setup_server(&["--enable-upload", "true"]);
+    let docs = server.dir.path().join("docs");
+
+    let res = put(&server, "/docs/report.txt", "first");
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert!(
+        res.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain")
+    );
+    assert_eq!(res.text().unwrap(), "Uploaded report.txt (5 bytes)\n");
+    assert_eq!(
+        fs::read_to_string(docs.join("report.txt")).unwrap(),
+        "first"
+    );
+
+    // Name conflicts follow the upload strategy (rename by default)
+    let res = put(&server, "/docs/report.txt", "second");
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert!(res.text().unwrap().contains("renamed from report.txt"));
+    assert_eq!(
+        fs::read_to_string(docs.join("report.txt")).unwrap(),
+        "first"
+    );
+
+    let res = Client::new()
+        .put(format!("http://{}/my%20notes.txt", server.addr))
+        .header(ACCEPT, "application/json")
+        .body("notes")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let json: serde_json::Value = serde_json::from_str(&res.text().unwrap()).unwrap();
+    assert_eq!(json["file"]["name"], "my notes.txt");
+    assert!(server.dir.path().join("my notes.txt").is_file());
+
+    assert_eq!(
+        put(&server, "/a+b.txt", "plus").status(),
+        StatusCode::CREATED
+    );
+    assert!(server.dir.path().join("a+b.txt").is_file());
+}
+
+#[test]
+fn test_put_upload_validation_and_quota() {
+    let server = setup_server(&["--enable-upload", "true", "--upload-quota-total", "10"]);
+
+    assert_eq!(
+        put(&server, "/docs/", "no name").status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        put(&server, "/missing/new.txt", "x").status(),
+        StatusCode::NOT_FOUND
+    );
+    assert!(
+        put(&server, "/docs/tool.exe", "x")
+            .status()
+            .is_client_error()
+    );
+    assert!(!server.dir.path().join("docs").join("tool.exe").exists());
+
+    assert_eq!(
+        put(&server, "/docs/a.txt", "eight by").status(),
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        put(&server, "/docs/b.txt", "eight by").status(),
+        StatusCode::INSUFFICIENT_STORAGE
+    );
+    assert!(!server.dir.path().join("docs").join("b.txt").exists());
+}
+
+#[test]
+fn test_put_without_uploads_is_not_allowed() {
+    let server = setup_server(&[]);
+    assert_eq!(
+        put(&server, "/docs/report.txt", "x").status(),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    assert!(!server.dir.path().join("docs").join("report.txt").exists());
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT
+//! Tests for the QR code landing page.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::blocking::Client;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+#[test]
+fn test_qr_page_shows_share_url() {
+    let server = setup_server();
+    let response = Client::new()
+        .get(format!("http://{}/_qr", server.addr))
+        .header("Host", "files.example.com:8080")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    let html = response.text().unwrap();
+    assert!(html.contains("http://files.example.com:8080/"));
+    assert!(html.contains("<svg"));
+    assert!(html.contains("/_irondrop/static/qr/styles.css"));
+
+    let namespaced = Client::new()
+        .get(format!("http://{}/_irondrop/qr", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(namespaced.status(), 200);
+}
+
+#[test]
+fn test_qr_svg_format() {
+    let server = setup_server();
+    let response = Client::new()
+        .get(format!("http://{}/_qr?format=svg", server.addr))
+        .header("X-Forwarded-Proto", "https")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "image/svg+xml");
+    let svg = response.text().unwrap();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+
+    let css = Client::new()
+        .get(format!(
+            "http://{}/_irondrop/static/qr/styles.css",
+            server.addr
+        ))
+        .send()
+        .unwrap();
+    assert_eq!(css.status(), 200);
+}
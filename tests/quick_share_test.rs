@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MIT
+//! `irondrop serve --once`: one shared item, shut down after its downloads.
+
+use clap::Parser;
+use irondrop::quick_share::{ServeArgs, ShareOutcome, serve_once};
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tempfile::tempdir;
+
+type Share = JoinHandle<Result<ShareOutcome, irondrop::error::AppError>>;
+
+fn start_share(path: &Path, extra: &[&str]) -> (SocketAddr, Share) {
+    let mut args = vec![
+        "serve".to_string(),
+        "--once".to_string(),
+        path.to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ];
+    args.extend(extra.iter().map(|arg| arg.to_string()));
+    let args = ServeArgs::parse_from(args);
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || serve_once(&args, Some(addr_tx)));
+    (addr_rx.recv().unwrap(), handle)
+}
+
+#[test]
+fn test_quick_share_file_until_downloaded() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("my report.txt");
+    fs::write(&file, "quarterly numbers").unwrap();
+    fs::write(dir.path().join("private.txt"), "not shared").unwrap();
+
+    let (addr, share) = start_share(&file, &["--downloads", "2"]);
+    let client = Client::new();
+    let url = format!("http://{addr}/my%20report.txt");
+    let get = |path: &str| {
+        client
+            .get(format!("http://{addr}{path}"))
+            .send()
+            .unwrap()
+            .status()
+    };
+
+    assert_eq!(get("/private.txt"), StatusCode::NOT_FOUND);
+    assert_eq!(get("/"), StatusCode::NOT_FOUND);
+    assert_eq!(get("/_irondrop/health"), StatusCode::NOT_FOUND);
+    assert_eq!(
+        client.delete(&url).send().unwrap().status(),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+
+    // Neither HEAD nor a partial download uses up the share
+    assert_eq!(client.head(&url).send().unwrap().status(), StatusCode::OK);
+    let res = client.get(&url).header(RANGE, "bytes=0-8").send().unwrap();
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.text().unwrap(), "quarterly");
+
+    let res = client.get(&url).send().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().unwrap(), "quarterly numbers");
+    assert!(!share.is_finished());
+
+    let res = client.get(&url).send().unwrap();
+    assert_eq!(res.text().unwrap(), "quarterly numbers");
+    assert_eq!(share.join().unwrap().unwrap(), ShareOutcome::Downloaded(2));
+    assert!(client.get(&url).send().is_err());
+}
+
+#[test]
+fn test_quick_share_folder_as_archive() {
+    let dir = tempdir().unwrap();
+    let folder = dir.path().join("photos");
+    fs::create_dir(&folder).unwrap();
+    fs::write(folder.join("a.jpg"), "jpeg bytes").unwrap();
+
+    let (addr, share) = start_share(&folder, &["--format", "tar"]);
+    let client = Client::new();
+    assert_eq!(
+        client
+            .get(format!("http://{addr}/"))
+            .send()
+            .unwrap()
+            .status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        client
+            .get(format!("http://{addr}/a.jpg"))
+            .send()
+            .unwrap()
+            .status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let res = client
+        .get(format!("http://{addr}/?download=tar"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let bytes = res.bytes().unwrap();
+    assert!(bytes.windows(10).any(|window| window == b"jpeg bytes"));
+    assert_eq!(share.join().unwrap().unwrap(), ShareOutcome::Downloaded(1));
+}
+
+#[test]
+fn test_quick_share_timeout() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join(".env");
+    fs::write(&file, "TOKEN=1").unwrap();
+
+    let (addr, share) = start_share(&file, &["--timeout", "1s"]);
+    // Dotfiles can be shared even though listings hide them
+    let res = Client::new()
+        .head(format!("http://{addr}/.env"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(share.join().unwrap().unwrap(), ShareOutcome::TimedOut(0));
+}
+
+#[test]
+fn test_serve_args() {
+    let args = ServeArgs::try_parse_from(["serve", "--once", "a.txt", "--timeout", "10m"]).unwrap();
+    assert_eq!(args.timeout, Some(Duration::from_secs(600)));
+    assert_eq!(args.downloads, 1);
+    assert_eq!(args.listen, "0.0.0.0");
+    for bad in [
+        vec!["serve", "a.txt"],
+        vec!["serve", "--once", "a.txt", "--timeout", "soon"],
+        vec!["serve", "--once", "a.txt", "--timeout", "0"],
+        vec!["serve", "--once", "a.txt", "--downloads", "0"],
+        vec!["serve", "--once", "a.txt", "--format", "zip"],
+    ] {
+        assert!(ServeArgs::try_parse_from(&bad).is_err(), "{bad:?}");
+    }
+}
@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: MIT
+//! Tests for configurable rate limits and per-route limit classes.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::{RateLimiter, RouteClass, run_server};
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+fn setup_server(extra_args: &[&str]) -> TestServer {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+    let mut args = vec![
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ];
+    args.extend(extra_args.iter().map(|a| a.to_string()));
+    let cli = Cli::parse_from(args);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+#[test]
+fn test_route_classes() {
+    assert_eq!(
+        RouteClass::for_request("GET", "/_irondrop/search"),
+        Some(RouteClass::Search)
+    );
+    assert_eq!(
+        RouteClass::for_request("POST", "/_irondrop/upload"),
+        Some(RouteClass::Upload)
+    );
+    assert_eq!(
+        RouteClass::for_request("PATCH", "/_irondrop/upload/session/abc"),
+        Some(RouteClass::Upload)
+    );
+    assert_eq!(
+        RouteClass::for_request("PUT", "/docs/file.txt"),
+        Some(RouteClass::Upload)
+    );
+    assert_eq!(RouteClass::for_request("GET", "/_irondrop/upload"), None);
+    assert_eq!(RouteClass::for_request("GET", "/docs/file.txt"), None);
+}
+
+#[test]
+fn test_route_limit_is_per_ip_and_class() {
+    let limiter = RateLimiter::new(120, 10).with_route_limit(RouteClass::Search, Some(2));
+    let ip: IpAddr = "192.168.1.10".parse().unwrap();
+    let other: IpAddr = "192.168.1.11".parse().unwrap();
+
+    assert_eq!(limiter.route_limit(RouteClass::Search), Some(2));
+    assert_eq!(limiter.route_limit(RouteClass::Upload), None);
+    assert!(limiter.check_route_limit(ip, RouteClass::Search).is_ok());
+    assert!(limiter.check_route_limit(ip, RouteClass::Search).is_ok());
+    let retry_after = limiter
+        .check_route_limit(ip, RouteClass::Search)
+        .unwrap_err();
+    assert!((1..=60).contains(&retry_after));
+
+    // Other IPs and unlimited classes are unaffected
+    assert!(limiter.check_route_limit(other, RouteClass::Search).is_ok());
+    for _ in 0..10 {
+        assert!(limiter.check_route_limit(ip, RouteClass::Upload).is_ok());
+    }
+}
+
+#[test]
+fn test_search_limit_answers_429_and_status_reports_limits() {
+    let server = setup_server(&[
+        "--rate-limit",
+        "500",
+        "--rate-limit-concurrent",
+        "20",
+        "--rate-limit-search",
+        "2",
+    ]);
+    let client = Client::new();
+    let search = || {
+        client
+            .get(format!("http://{}/_irondrop/search?q=notes", server.addr))
+            .send()
+            .unwrap()
+    };
+
+    assert_eq!(search().status(), StatusCode::OK);
+    assert_eq!(search().status(), StatusCode::OK);
+    let limited = search();
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = limited.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+
+    // Downloads are only subject to the connection limits
+    for _ in 0..5 {
+        let res = client
+            .get(format!("http://{}/notes.txt", server.addr))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    let status: serde_json::Value = client
+        .get(format!("http://{}/_status?json=1", server.addr))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    let limits = &status["rate_limiter"]["limits"];
+    assert_eq!(limits["requests_per_minute"], 500);
+    assert_eq!(limits["concurrent_per_ip"], 20);
+    assert_eq!(limits["routes"]["search"], 2);
+    assert!(limits["routes"]["upload"].is_null());
+}
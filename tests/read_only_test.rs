@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: MIT
+//! `--read-only` and the `/_irondrop/read-only` runtime switch.
+//! One server per process: the mode is process-wide.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder};
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+impl TestServer {
+    fn request(&self, method: &str, path: &str) -> RequestBuilder {
+        Client::new()
+            .request(
+                method.parse().unwrap(),
+                format!("http://{}{path}", self.addr),
+            )
+            .basic_auth("admin", Some("secret"))
+    }
+
+    fn status(&self, request: RequestBuilder) -> StatusCode {
+        request.send().unwrap().status()
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "-a".to_string(),
+        "*.txt".to_string(),
+        "--username".to_string(),
+        "admin".to_string(),
+        "--password".to_string(),
+        "secret".to_string(),
+        "--enable-upload".to_string(),
+        "true".to_string(),
+        "--enable-webdav".to_string(),
+        "true".to_string(),
+        "--enable-file-management".to_string(),
+        "true".to_string(),
+        "--read-only".to_string(),
+        "true".to_string(),
+    ]);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+#[test]
+fn test_read_only_blocks_changes_until_switched_off() {
+    let server = setup_server();
+    let notes = server.temp_dir.path().join("notes.txt");
+
+    assert_eq!(
+        server.status(server.request("GET", "/notes.txt")),
+        StatusCode::OK
+    );
+    let listing = server.request("GET", "/").send().unwrap().text().unwrap();
+    assert!(!listing.contains("/_irondrop/upload"));
+
+    for request in [
+        server
+            .request("POST", "/_irondrop/upload")
+            .header("X-Filename", "new.txt")
+            .body("new"),
+        server.request("DELETE", "/_irondrop/files?path=/notes.txt"),
+        server.request("PUT", "/new.txt").body("new"),
+        server.request("DELETE", "/notes.txt"),
+        server.request("MKCOL", "/folder/"),
+    ] {
+        assert_eq!(server.status(request), StatusCode::FORBIDDEN);
+    }
+    assert!(notes.exists());
+    assert!(!server.temp_dir.path().join("new.txt").exists());
+
+    // The switch is behind the same credentials as everything else
+    let anonymous = Client::new()
+        .post(format!(
+            "http://{}/_irondrop/read-only?enabled=false",
+            server.addr
+        ))
+        .send()
+        .unwrap();
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        server.status(server.request("POST", "/_irondrop/read-only?enabled=maybe")),
+        StatusCode::BAD_REQUEST
+    );
+    let state = server
+        .request("GET", "/_irondrop/read-only")
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert_eq!(state, r#"{"read_only":true}"#);
+
+    let switched = server
+        .request("POST", "/_irondrop/read-only?enabled=false")
+        .send()
+        .unwrap();
+    assert_eq!(switched.status(), StatusCode::OK);
+    assert_eq!(switched.text().unwrap(), r#"{"read_only":false}"#);
+
+    assert!(
+        server
+            .status(server.request("PUT", "/new.txt").body("new"))
+            .is_success()
+    );
+    assert_eq!(
+        fs::read_to_string(server.temp_dir.path().join("new.txt")).unwrap(),
+        "new"
+    );
+}
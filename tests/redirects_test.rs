@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: MIT
+//! Redirect rules (`--redirects`, `--redirects-file`) and `--not-found-page`.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_TYPE, LOCATION};
+use reqwest::redirect::Policy;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+// Redirect rules are process-wide, so this binary runs a single server
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let site = dir.path().join("site");
+    fs::create_dir_all(site.join("posts")).unwrap();
+    fs::write(site.join("index.html"), "<h1>home</h1>").unwrap();
+    fs::write(site.join("404.html"), "<h1>lost?</h1>").unwrap();
+    fs::write(site.join("posts").join("kept.html"), "kept").unwrap();
+    fs::create_dir(site.join("old")).unwrap();
+    let rules = dir.path().join("_redirects");
+    fs::write(
+        &rules,
+        "# moved blog\n/blog/* /posts/:splat\n/posts/kept.html /elsewhere 302\n",
+    )
+    .unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        site.to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--index".to_string(),
+        "true".to_string(),
+        "--redirects".to_string(),
+        "/docs https://docs.example.com/ 307,/old/* /".to_string(),
+        "--redirects-file".to_string(),
+        rules.to_string_lossy().into_owned(),
+        "--not-found-page".to_string(),
+        "404.html".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        _dir: dir,
+    }
+}
+
+#[test]
+fn test_redirects_and_not_found_page() {
+    let server = setup_server();
+    let client = Client::builder().redirect(Policy::none()).build().unwrap();
+    let get = |path: &str| -> Response {
+        client
+            .get(format!("http://{}{path}", server.addr))
+            .send()
+            .unwrap()
+    };
+    let location = |res: &Response| res.headers()[LOCATION].to_str().unwrap().to_string();
+
+    let res = get("/blog/2024/first%20post?ref=feed");
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(location(&res), "/posts/2024/first%20post?ref=feed");
+    let res = get("/docs");
+    assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(location(&res), "https://docs.example.com/");
+
+    // Existing files and folders are never redirected
+    assert_eq!(get("/posts/kept.html").text().unwrap(), "kept");
+    assert_eq!(get("/old/").status(), StatusCode::NOT_FOUND);
+    assert_eq!(location(&get("/old/page")), "/");
+
+    let res = get("/nothing/here");
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert!(
+        res.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    assert_eq!(res.text().unwrap(), "<h1>lost?</h1>");
+    // A folder without index.html gets the page as well
+    assert_eq!(get("/old/").text().unwrap(), "<h1>lost?</h1>");
+}
+
+#[test]
+fn test_invalid_redirects_are_rejected() {
+    let parse = |rule: &str| {
+        Cli::try_parse_from(["irondrop", "-d", ".", "--redirects", rule]).map(|cli| cli.redirects)
+    };
+    assert!(parse("/a /b 302,/c /d").is_ok());
+    assert!(parse("/a /b 200").is_err());
+    assert!(parse("a /b").is_err());
+    assert!(parse("/a").is_err());
+}
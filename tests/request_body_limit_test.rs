@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MIT
+//! `--max-request-body` for requests other than uploads.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--enable-upload".to_string(),
+        "true".to_string(),
+        "--max-request-body".to_string(),
+        "1KB".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        dir,
+    }
+}
+
+/// Send raw request bytes and return the status line
+fn send_raw(addr: SocketAddr, request: &[u8]) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request).unwrap();
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    let response = String::from_utf8_lossy(&response);
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn test_request_body_limit_spares_uploads() {
+    let server = setup_server();
+
+    // The declared length is refused before any body is sent
+    let status = send_raw(
+        server.addr,
+        b"POST /_api/mkdir HTTP/1.1\r\nHost: x\r\nContent-Length: 1048576\r\nConnection: close\r\n\r\n",
+    );
+    assert!(status.contains("413"), "{status}");
+
+    // A chunked body is cut off once it passes the limit
+    let mut chunked = b"POST /_api/mkdir HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n".to_vec();
+    for _ in 0..4 {
+        chunked.extend_from_slice(b"200\r\n");
+        chunked.extend_from_slice(&[b'a'; 512]);
+        chunked.extend_from_slice(b"\r\n");
+    }
+    chunked.extend_from_slice(b"0\r\n\r\n");
+    let status = send_raw(server.addr, &chunked);
+    assert!(status.contains("413"), "{status}");
+
+    // Uploads are only bound by the upload limit
+    let res = Client::new()
+        .post(format!("http://{}/_irondrop/upload", server.addr))
+        .header("Content-Type", "application/octet-stream")
+        .header("X-Filename", "big.bin")
+        .body(vec![7u8; 64 * 1024])
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        std::fs::metadata(server.dir.path().join("big.bin"))
+            .unwrap()
+            .len(),
+        64 * 1024
+    );
+}
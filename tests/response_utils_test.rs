@@ -1,6 +1,11 @@
 // SPDX-License-Identifier: MIT
 
-use irondrop::response::{create_error_response, get_mime_type};
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::response::{
+    ByteRange, FileKind, create_error_response, get_file_content_type, get_file_kind,
+    get_mime_type, is_media_type, parse_range_header,
+};
 use std::net::{TcpListener, TcpStream};
 use std::path::Path;
 use std::thread;
@@ -17,6 +22,82 @@ fn test_get_mime_type_common() {
     );
 }
 
+#[test]
+fn test_media_mime_types() {
+    assert_eq!(get_mime_type(Path::new("a.webm")), "video/webm");
+    assert_eq!(get_mime_type(Path::new("a.mkv")), "video/x-matroska");
+    assert_eq!(get_mime_type(Path::new("a.mov")), "video/quicktime");
+    assert_eq!(get_mime_type(Path::new("a.flac")), "audio/flac");
+    assert_eq!(get_mime_type(Path::new("a.opus")), "audio/ogg");
+    assert_eq!(get_mime_type(Path::new("a.m4a")), "audio/mp4");
+    assert!(is_media_type("video/mp4"));
+    assert!(is_media_type("Audio/mpeg"));
+    assert!(!is_media_type("image/png"));
+    assert!(!is_media_type("application/octet-stream"));
+}
+
+#[test]
+fn test_get_file_content_type() {
+    let content_type = |name: &str, args: &[&str]| {
+        let cli = Cli::parse_from(["irondrop", "-d", "."].iter().chain(args));
+        get_file_content_type(Path::new(name), Some(&cli))
+    };
+
+    let plain = content_type("notes.TXT", &[]);
+    assert_eq!(plain.content_type, "text/plain; charset=utf-8");
+    assert!(!plain.attachment);
+    assert_eq!(content_type("a.png", &[]).content_type, "image/png");
+    assert_eq!(
+        content_type("a.txt", &["--default-charset", "none"]).content_type,
+        "text/plain"
+    );
+
+    let mapped = [
+        "--mime-types",
+        "md=text/markdown; charset=iso-8859-1,.LOG=text/plain",
+    ];
+    assert_eq!(
+        content_type("README.md", &mapped).content_type,
+        "text/markdown; charset=iso-8859-1"
+    );
+    assert_eq!(
+        content_type("app.log", &mapped).content_type,
+        "text/plain; charset=utf-8"
+    );
+
+    // Uploaded HTML is downloaded rather than rendered unless configured otherwise
+    assert!(!content_type("page.html", &[]).attachment);
+    assert!(content_type("page.html", &["--enable-upload", "true"]).attachment);
+    assert!(
+        !content_type(
+            "page.html",
+            &["--enable-upload", "true", "--force-download", ""]
+        )
+        .attachment
+    );
+    assert!(content_type("tool.exe", &["--force-download", "exe"]).attachment);
+    assert_eq!(
+        get_file_content_type(Path::new("page.html"), None).content_type,
+        "text/html; charset=utf-8"
+    );
+}
+
+#[test]
+fn test_get_file_kind() {
+    let kind = |name: &str| get_file_kind(Path::new(name));
+    assert_eq!(kind("photo.JPG"), FileKind::Image);
+    assert_eq!(kind("clip.webm"), FileKind::Video);
+    assert_eq!(kind("song.mp3"), FileKind::Audio);
+    assert_eq!(kind("backup.tar"), FileKind::Archive);
+    assert_eq!(kind("disk.7z"), FileKind::Archive);
+    assert_eq!(kind("main.rs"), FileKind::Code);
+    assert_eq!(kind("app.js"), FileKind::Code);
+    assert_eq!(kind("notes.md"), FileKind::Document);
+    assert_eq!(kind("report.docx"), FileKind::Document);
+    assert_eq!(kind("blob.bin"), FileKind::Other);
+    assert_eq!(kind("Makefile"), FileKind::Other);
+}
+
 #[test]
 fn test_create_error_response_headers_and_body() {
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
@@ -41,3 +122,34 @@ fn test_create_error_response_headers_and_body() {
     assert!(text.contains("text/html"));
     handle.join().unwrap();
 }
+
+#[test]
+fn test_parse_range_header() {
+    assert_eq!(
+        parse_range_header("bytes=0-9", 100),
+        ByteRange::Partial { start: 0, end: 9 }
+    );
+    assert_eq!(
+        parse_range_header("bytes=90-", 100),
+        ByteRange::Partial { start: 90, end: 99 }
+    );
+    assert_eq!(
+        parse_range_header("bytes=-10", 100),
+        ByteRange::Partial { start: 90, end: 99 }
+    );
+    assert_eq!(
+        parse_range_header("bytes=50-500", 100),
+        ByteRange::Partial { start: 50, end: 99 }
+    );
+    assert_eq!(
+        parse_range_header("bytes=100-", 100),
+        ByteRange::Unsatisfiable
+    );
+    assert_eq!(
+        parse_range_header("bytes=-0", 100),
+        ByteRange::Unsatisfiable
+    );
+    assert_eq!(parse_range_header("bytes=9-1", 100), ByteRange::Full);
+    assert_eq!(parse_range_header("bytes=0-1,5-6", 100), ByteRange::Full);
+    assert_eq!(parse_range_header("items=0-1", 100), ByteRange::Full);
+}
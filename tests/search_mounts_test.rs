@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MIT
+//! Searching extra directories with `add_search_mount` and `?mount=`.
+//! One server per process: the search index and its mounts are process-wide.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::search::{DEFAULT_TRIGRAM_BUDGET, add_search_mount, search_mounts};
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use tempfile::tempdir;
+
+fn paths_by_mount(results: &Value) -> Vec<(String, String)> {
+    let mut found: Vec<(String, String)> = results["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["mount"].as_str().unwrap().to_string(),
+                r["path"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+#[test]
+fn test_search_across_mounts() {
+    let served = tempdir().unwrap();
+    fs::write(served.path().join("wombat_notes.txt"), "a").unwrap();
+    let archive = tempdir().unwrap();
+    fs::create_dir(archive.path().join("2023")).unwrap();
+    fs::write(archive.path().join("2023").join("wombat_old.txt"), "b").unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        served.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    let mount = |name: &str| {
+        add_search_mount(
+            name,
+            archive.path().to_path_buf(),
+            false,
+            DEFAULT_TRIGRAM_BUDGET,
+            Default::default(),
+        )
+    };
+    mount("archive").unwrap();
+    assert!(mount("archive").is_err());
+    assert!(mount("root").is_err());
+    assert!(mount("no/slashes").is_err());
+    assert_eq!(search_mounts(), ["archive"]);
+
+    let client = Client::new();
+    let search = |query: &str| {
+        client
+            .get(format!("http://{addr}/_irondrop/search?q=wombat{query}"))
+            .send()
+            .unwrap()
+    };
+
+    let all: Value = search("").json().unwrap();
+    assert_eq!(
+        paths_by_mount(&all),
+        [
+            ("archive".to_string(), "/2023/wombat_old.txt".to_string()),
+            ("root".to_string(), "/wombat_notes.txt".to_string()),
+        ]
+    );
+
+    let archived: Value = search("&mount=archive").json().unwrap();
+    assert_eq!(
+        paths_by_mount(&archived),
+        [("archive".to_string(), "/2023/wombat_old.txt".to_string())]
+    );
+    let served_only: Value = search("&mount=root").json().unwrap();
+    assert_eq!(
+        paths_by_mount(&served_only),
+        [("root".to_string(), "/wombat_notes.txt".to_string())]
+    );
+
+    assert_eq!(search("&mount=missing").status(), StatusCode::BAD_REQUEST);
+    assert_eq!(search("&mount=..%2Fetc").status(), StatusCode::BAD_REQUEST);
+
+    let _ = shutdown_tx.send(());
+    let _ = handle.join();
+}
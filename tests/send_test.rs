@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: MIT
+//! The `irondrop send` client against a running server.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::send::{SendTarget, send_command};
+use irondrop::server::run_server;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server(with_auth: bool) -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("incoming")).unwrap();
+
+    let mut args = vec![
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--allowed-extensions".to_string(),
+        "*.txt,*.bin".to_string(),
+        "--enable-upload".to_string(),
+        "true".to_string(),
+    ];
+    if with_auth {
+        args.extend(["--username", "admin", "--password", "secret"].map(str::to_string));
+    }
+    let cli = Cli::parse_from(args);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        dir,
+    }
+}
+
+fn send(files: &[&Path], to: &str, extra: &[&str]) -> i32 {
+    let mut args = vec!["send".to_string()];
+    args.extend(files.iter().map(|f| f.to_string_lossy().into_owned()));
+    args.extend(["--to".to_string(), to.to_string(), "--quiet".to_string()]);
+    args.extend(extra.iter().map(|arg| arg.to_string()));
+    send_command(args)
+}
+
+#[test]
+fn test_send_small_and_chunked_files() {
+    let server = setup_server(false);
+    let local = tempdir().unwrap();
+    let small = local.path().join("notes.txt");
+    fs::write(&small, "hello over the wire").unwrap();
+    let large = local.path().join("data.bin");
+    let data: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(&large, &data).unwrap();
+
+    let status = send(
+        &[&small, &large],
+        &format!("http://{}/incoming/", server.addr),
+        &["--chunk-size", "1KB"],
+    );
+    assert_eq!(status, 0);
+    let incoming = server.dir.path().join("incoming");
+    assert_eq!(
+        fs::read_to_string(incoming.join("notes.txt")).unwrap(),
+        "hello over the wire"
+    );
+    assert_eq!(fs::read(incoming.join("data.bin")).unwrap(), data);
+    // No partial session file is left behind
+    assert_eq!(fs::read_dir(&incoming).unwrap().count(), 2);
+}
+
+#[test]
+fn test_send_reports_failures() {
+    let server = setup_server(true);
+    let local = tempdir().unwrap();
+    let file = local.path().join("notes.txt");
+    fs::write(&file, "secret notes").unwrap();
+    let rejected = local.path().join("tool.exe");
+    fs::write(&rejected, "binary").unwrap();
+    let url = format!("http://{}/", server.addr);
+
+    assert_eq!(send(&[&file], &url, &[]), 1);
+    assert!(!server.dir.path().join("notes.txt").exists());
+
+    // One refused file does not stop the others
+    assert_eq!(
+        send(&[&rejected, &file], &url, &["--user", "admin:secret"]),
+        1
+    );
+    assert!(server.dir.path().join("notes.txt").is_file());
+    assert!(!server.dir.path().join("tool.exe").exists());
+
+    assert_eq!(send(&[&file], &url.replace("http", "https"), &[]), 2);
+    assert_eq!(send(&[&local.path().join("missing.txt")], &url, &[]), 1);
+}
+
+#[test]
+fn test_send_target_parsing() {
+    let target = SendTarget::parse("http://192.168.1.20:8080/team%20docs/").unwrap();
+    assert_eq!(target.folder, "/team docs");
+    assert_eq!(SendTarget::parse("http://example.com").unwrap().folder, "/");
+    assert_eq!(
+        SendTarget::parse("http://[::1]:9000/in").unwrap().folder,
+        "/in"
+    );
+    assert!(SendTarget::parse("https://example.com/").is_err());
+    assert!(SendTarget::parse("ftp://example.com/").is_err());
+    assert!(SendTarget::parse("http://user@example.com/").is_err());
+    assert!(SendTarget::parse("http://example.com:port/").is_err());
+}
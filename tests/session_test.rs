@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MIT
+//! Login page and session cookies (`--auth-sessions`).
+//! One server per process: session mode is process-wide.
+
+use base64::Engine;
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE, WWW_AUTHENTICATE};
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use tempfile::tempdir;
+
+#[test]
+fn test_login_sessions() {
+    let served = tempdir().unwrap();
+    fs::create_dir(served.path().join("docs")).unwrap();
+    fs::write(served.path().join("docs").join("notes.txt"), "hello").unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        served.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--username".to_string(),
+        "admin".to_string(),
+        "--password".to_string(),
+        "secret".to_string(),
+        "--users".to_string(),
+        "viewer=read-only:view-pw".to_string(),
+        "--auth-sessions".to_string(),
+        "true".to_string(),
+        "--session-secret".to_string(),
+        "correct-horse-battery-staple".to_string(),
+        "--enable-upload".to_string(),
+        "true".to_string(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+    let client = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let url = |path: &str| format!("http://{addr}{path}");
+
+    // Browsers are sent to the login page, scripts still get a Basic challenge
+    let page = client
+        .get(url("/docs/"))
+        .header(ACCEPT, "text/html")
+        .send()
+        .unwrap();
+    assert_eq!(page.status(), StatusCode::SEE_OTHER);
+    assert_eq!(page.headers()[LOCATION], "/_irondrop/login?next=%2Fdocs%2F");
+    let api = client.get(url("/docs/notes.txt")).send().unwrap();
+    assert_eq!(api.status(), StatusCode::UNAUTHORIZED);
+    assert!(api.headers().contains_key(WWW_AUTHENTICATE));
+
+    let form = client
+        .get(url("/_irondrop/login?next=%2Fdocs%2F"))
+        .send()
+        .unwrap();
+    assert_eq!(form.status(), StatusCode::OK);
+    assert!(
+        form.text()
+            .unwrap()
+            .contains(r#"name="next" value="/docs/""#)
+    );
+    let stylesheet = client
+        .get(url("/_irondrop/static/common/base.css"))
+        .send()
+        .unwrap();
+    assert_eq!(stylesheet.status(), StatusCode::OK);
+
+    let login = |body: &str| {
+        client
+            .post(url("/_irondrop/login"))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body.to_string())
+            .send()
+            .unwrap()
+    };
+    let failed = login("username=admin&password=wrong&next=%2Fdocs%2F");
+    assert_eq!(failed.status(), StatusCode::UNAUTHORIZED);
+    assert!(!failed.headers().contains_key(SET_COOKIE));
+    assert!(!failed.headers().contains_key(WWW_AUTHENTICATE));
+
+    let offsite = login("username=admin&password=secret&next=%2F%2Fevil.example%2F");
+    assert_eq!(offsite.headers()[LOCATION], "/");
+
+    let ok = login("username=admin&password=secret&next=%2Fdocs%2F");
+    assert_eq!(ok.status(), StatusCode::SEE_OTHER);
+    assert_eq!(ok.headers()[LOCATION], "/docs/");
+    let set_cookie = ok.headers()[SET_COOKIE].to_str().unwrap().to_string();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+    let session = set_cookie.split(';').next().unwrap().to_string();
+
+    let listing = client
+        .get(url("/docs/"))
+        .header(ACCEPT, "text/html")
+        .header(COOKIE, &session)
+        .send()
+        .unwrap();
+    assert_eq!(listing.status(), StatusCode::OK);
+    let html = listing.text().unwrap();
+    assert!(html.contains("notes.txt"));
+    assert!(html.contains("Upload Files"));
+
+    // A tampered cookie is not accepted
+    let (_, rest) = session.split_once('.').unwrap();
+    let forged = format!(
+        "irondrop_session={}.{rest}",
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("viewer")
+    );
+    let refused = client
+        .get(url("/docs/notes.txt"))
+        .header(COOKIE, forged)
+        .send()
+        .unwrap();
+    assert_eq!(refused.status(), StatusCode::UNAUTHORIZED);
+
+    // [users] accounts keep their role in a session
+    let viewer = login("username=viewer&password=view-pw&next=%2F");
+    let viewer = viewer.headers()[SET_COOKIE].to_str().unwrap();
+    let viewer = viewer.split(';').next().unwrap().to_string();
+    let read = client
+        .get(url("/docs/notes.txt"))
+        .header(COOKIE, &viewer)
+        .send()
+        .unwrap();
+    assert_eq!(read.status(), StatusCode::OK);
+    let monitor = client
+        .get(url("/monitor"))
+        .header(COOKIE, &viewer)
+        .send()
+        .unwrap();
+    assert_eq!(monitor.status(), StatusCode::FORBIDDEN);
+    // and see only the controls the role allows
+    let html = client
+        .get(url("/docs/"))
+        .header(ACCEPT, "text/html")
+        .header(COOKIE, &viewer)
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert!(html.contains("notes.txt"));
+    assert!(!html.contains("Upload Files"));
+
+    // Basic Auth keeps working
+    let basic = client
+        .get(url("/docs/notes.txt"))
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .unwrap();
+    assert_eq!(basic.status(), StatusCode::OK);
+
+    let logout = client
+        .get(url("/_irondrop/logout"))
+        .header(COOKIE, &session)
+        .send()
+        .unwrap();
+    assert_eq!(logout.status(), StatusCode::OK);
+    assert!(
+        logout.headers()[SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .starts_with("irondrop_session=; Max-Age=0")
+    );
+
+    let _ = shutdown_tx.send(());
+    let _ = handle.join();
+}
@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MIT
+//! End-to-end tests for expiring share links.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use irondrop::share::ShareSigner;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+const SECRET: &str = "test-share-secret-0123456789";
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("report.txt"), "quarterly numbers").unwrap();
+    fs::write(dir.path().join("private.txt"), "not shared").unwrap();
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.txt".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: Some("admin".to_string()),
+        password: Some("secret".to_string()),
+        enable_upload: Some(false),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: Some(SECRET.to_string()),
+        share_max_ttl: Some(600),
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+fn mint(client: &Client, server: &TestServer, query: &str) -> serde_json::Value {
+    let res = client
+        .get(format!("http://{}/_api/share?{query}", server.addr))
+        .basic_auth("admin", Some("secret"))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    serde_json::from_str(&res.text().unwrap()).unwrap()
+}
+
+#[test]
+fn test_share_link_bypasses_auth_for_that_file_only() {
+    let server = setup_server();
+    let client = Client::new();
+
+    // Minting a link needs credentials
+    let res = client
+        .get(format!(
+            "http://{}/_api/share?path=/report.txt",
+            server.addr
+        ))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let json = mint(&client, &server, "path=/report.txt&ttl=3600");
+    assert_eq!(json["path"], "/report.txt");
+    assert_eq!(json["ttl"], 600, "ttl is capped at share_max_ttl");
+    let url = json["url"].as_str().unwrap();
+    assert!(url.starts_with(&format!("http://{}/report.txt?expires=", server.addr)));
+
+    let res = client.get(url).send().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().unwrap(), "quarterly numbers");
+
+    // The signature only covers the path it was issued for
+    let other = url.replace("/report.txt?", "/private.txt?");
+    assert_eq!(
+        client.get(other).send().unwrap().status(),
+        StatusCode::FORBIDDEN
+    );
+    let tampered = format!("{}0", url);
+    assert_eq!(
+        client.get(tampered).send().unwrap().status(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        client
+            .get(format!("http://{}/private.txt", server.addr))
+            .send()
+            .unwrap()
+            .status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[test]
+fn test_share_link_expiry_and_validation() {
+    let server = setup_server();
+    let client = Client::new();
+
+    let signer = ShareSigner::new(SECRET, 600);
+    let signature = signer.sign("/report.txt", 1);
+    let expired = format!(
+        "http://{}/report.txt?expires=1&signature={signature}",
+        server.addr
+    );
+    assert_eq!(
+        client.get(expired).send().unwrap().status(),
+        StatusCode::FORBIDDEN
+    );
+
+    for query in ["path=/missing.txt", "path=/report.txt&ttl=0", "ttl=60"] {
+        let res = client
+            .get(format!("http://{}/_api/share?{query}", server.addr))
+            .basic_auth("admin", Some("secret"))
+            .send()
+            .unwrap();
+        assert!(
+            res.status().is_client_error(),
+            "{query} should be rejected, got {}",
+            res.status()
+        );
+    }
+}
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: MIT
+//! Static site hosting (`--index`) and single-page app fallback (`--spa`).
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::ACCEPT;
+use reqwest::redirect::Policy;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _dir: TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn setup_server(mode: &str) -> TestServer {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("index.html"), "<h1>home</h1>").unwrap();
+    fs::write(dir.path().join("app.js"), "console.log(1)").unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs").join("index.html"), "<h1>docs</h1>").unwrap();
+    fs::create_dir(dir.path().join("assets")).unwrap();
+    fs::write(dir.path().join("assets").join("logo.txt"), "logo").unwrap();
+
+    let mut args = vec![
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+    ];
+    if !mode.is_empty() {
+        args.extend([mode.to_string(), "true".to_string()]);
+    }
+    let cli = Cli::parse_from(args);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    TestServer {
+        addr: addr_rx.recv().unwrap(),
+        shutdown_tx,
+        handle: Some(handle),
+        _dir: dir,
+    }
+}
+
+fn page(server: &TestServer, path: &str) -> Response {
+    Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap()
+        .get(format!("http://{}{path}", server.addr))
+        .header(ACCEPT, "text/html,application/xhtml+xml,*/*;q=0.8")
+        .send()
+        .unwrap()
+}
+
+#[test]
+fn test_index_replaces_directory_listings() {
+    let server = setup_server("--index");
+
+    let res = page(&server, "/");
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(
+        res.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    assert_eq!(res.text().unwrap(), "<h1>home</h1>");
+    assert_eq!(page(&server, "/docs/").text().unwrap(), "<h1>docs</h1>");
+    assert_eq!(
+        page(&server, "/docs").status(),
+        StatusCode::MOVED_PERMANENTLY
+    );
+
+    // A folder without an index page is not listed
+    assert_eq!(page(&server, "/assets/").status(), StatusCode::NOT_FOUND);
+    assert_eq!(page(&server, "/assets/logo.txt").text().unwrap(), "logo");
+    // Without --spa missing pages stay missing
+    assert_eq!(page(&server, "/settings").status(), StatusCode::NOT_FOUND);
+
+    // Listings are the default
+    let plain = setup_server("");
+    assert!(page(&plain, "/").text().unwrap().contains("app.js"));
+}
+
+#[test]
+fn test_spa_falls_back_to_root_index() {
+    let server = setup_server("--spa");
+    let client = Client::new();
+
+    let res = page(&server, "/settings/profile");
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().unwrap(), "<h1>home</h1>");
+    assert_eq!(page(&server, "/docs/").text().unwrap(), "<h1>docs</h1>");
+    assert_eq!(page(&server, "/app.js").text().unwrap(), "console.log(1)");
+
+    // Scripts and data that are not found are not answered with the page
+    let res = client
+        .get(format!("http://{}/missing.js", server.addr))
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(page(&server, "/assets/").status(), StatusCode::NOT_FOUND);
+}
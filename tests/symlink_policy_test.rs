@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MIT
+//! `--follow-symlinks` policy for downloads and directory listings.
+#![cfg(unix)]
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::fs;
+use std::net::SocketAddr;
+use std::os::unix::fs::symlink;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _dirs: (TempDir, TempDir),
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+impl TestServer {
+    fn status(&self, path: &str) -> StatusCode {
+        Client::new()
+            .get(format!("http://{}{path}", self.addr))
+            .send()
+            .unwrap()
+            .status()
+    }
+
+    /// `(name, symlink)` pairs from the JSON listing of the root
+    fn listing(&self) -> Vec<(String, bool)> {
+        let json: serde_json::Value = Client::new()
+            .get(format!("http://{}/?format=json", self.addr))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        let mut entries: Vec<(String, bool)> = json["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                (
+                    entry["name"].as_str().unwrap().to_string(),
+                    entry["symlink"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+fn setup_server(policy: Option<&str>) -> TestServer {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    fs::write(dir.path().join("inside.txt"), "inside").unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs").join("guide.txt"), "guide").unwrap();
+    fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+    symlink(dir.path().join("inside.txt"), dir.path().join("alias.txt")).unwrap();
+    symlink(dir.path().join("docs"), dir.path().join("manuals")).unwrap();
+    symlink(outside.path(), dir.path().join("escape")).unwrap();
+
+    let mut args = vec![
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "-a".to_string(),
+        "*.txt".to_string(),
+    ];
+    if let Some(policy) = policy {
+        args.extend(["--follow-symlinks".to_string(), policy.to_string()]);
+    }
+    let cli = Cli::parse_from(args);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _dirs: (dir, outside),
+    }
+}
+
+fn entry(name: &str, symlink: bool) -> (String, bool) {
+    (name.to_string(), symlink)
+}
+
+#[test]
+fn test_always_follows_every_link() {
+    let server = setup_server(None);
+    assert_eq!(server.status("/alias.txt"), StatusCode::OK);
+    assert_eq!(server.status("/manuals/guide.txt"), StatusCode::OK);
+    assert_eq!(server.status("/escape/secret.txt"), StatusCode::OK);
+    assert_eq!(
+        server.listing(),
+        vec![
+            entry("alias.txt", true),
+            entry("docs", false),
+            entry("escape", true),
+            entry("inside.txt", false),
+            entry("manuals", true),
+        ]
+    );
+}
+
+#[test]
+fn test_safe_blocks_links_leaving_the_directory() {
+    let server = setup_server(Some("safe"));
+    assert_eq!(server.status("/alias.txt"), StatusCode::OK);
+    assert_eq!(server.status("/manuals/guide.txt"), StatusCode::OK);
+    assert_eq!(server.status("/escape/secret.txt"), StatusCode::FORBIDDEN);
+    assert_eq!(server.status("/escape/"), StatusCode::FORBIDDEN);
+    assert_eq!(
+        server.listing(),
+        vec![
+            entry("alias.txt", true),
+            entry("docs", false),
+            entry("inside.txt", false),
+            entry("manuals", true),
+        ]
+    );
+}
+
+#[test]
+fn test_never_hides_all_links() {
+    let server = setup_server(Some("never"));
+    assert_eq!(server.status("/inside.txt"), StatusCode::OK);
+    assert_eq!(server.status("/alias.txt"), StatusCode::NOT_FOUND);
+    assert_eq!(server.status("/manuals/guide.txt"), StatusCode::NOT_FOUND);
+    assert_eq!(server.status("/escape/secret.txt"), StatusCode::NOT_FOUND);
+    assert_eq!(
+        server.listing(),
+        vec![entry("docs", false), entry("inside.txt", false)]
+    );
+}
@@ -132,6 +132,18 @@ fn test_embedded_static_assets() {
     assert_eq!(error_js_type, "application/javascript");
     assert!(error_js_content.contains("Keyboard shortcuts"));
 
+    // Test theme assets
+    let (themes_css, themes_type) = engine.get_static_asset("common/themes.css").unwrap();
+    assert_eq!(themes_type, "text/css");
+    assert!(themes_css.contains(":root[data-theme=\"light\"]"));
+    let (theme_js, theme_js_type) = engine.get_static_asset("common/theme.js").unwrap();
+    assert_eq!(theme_js_type, "application/javascript");
+    assert!(theme_js.contains("localStorage"));
+    assert!(
+        engine.get_static_asset("custom.css").is_none(),
+        "No custom CSS unless configured"
+    );
+
     // Test non-existent asset
     let nonexistent = engine.get_static_asset("nonexistent/file.css");
     assert!(
@@ -187,6 +199,14 @@ fn test_directory_listing_rendering() {
         "Should identify directories"
     );
     assert!(html.contains("file-type file"), "Should identify files");
+    assert!(
+        html.contains("file-type file kind-document"),
+        "Text files get the document icon"
+    );
+    assert!(
+        html.contains("file-type file kind-archive"),
+        "Zip files get the archive icon"
+    );
 
     // Should reference embedded assets
     assert!(
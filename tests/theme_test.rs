@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MIT
+//! Tests for web UI themes and the custom stylesheet.
+//!
+//! The theme is process-wide (`OnceLock`), so this file starts a single
+//! server configured with `--theme light` and `--custom-css`.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::blocking::Client;
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use tempfile::tempdir;
+
+#[test]
+fn test_light_theme_and_custom_css() {
+    let dir = tempdir().unwrap();
+    let css_dir = tempdir().unwrap();
+    let css_path = css_dir.path().join("brand.css");
+    fs::write(&css_path, ":root { --text-accent: #d9480f; }\n").unwrap();
+
+    let cli = Cli::parse_from([
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--theme".to_string(),
+        "light".to_string(),
+        "--custom-css".to_string(),
+        css_path.to_string_lossy().into_owned(),
+    ]);
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+    let client = Client::new();
+
+    let html = client
+        .get(format!("http://{addr}/"))
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert!(html.contains(r#"<html lang="en" data-theme="light">"#));
+    assert!(html.contains("/_irondrop/static/common/themes.css"));
+    assert!(html.contains("/_irondrop/static/common/theme.js"));
+    assert!(html.contains(r#"id="themeToggle""#));
+    assert!(html.contains(r#"href="/_irondrop/static/custom.css""#));
+
+    let css = client
+        .get(format!("http://{addr}/_irondrop/static/custom.css"))
+        .send()
+        .unwrap();
+    assert_eq!(css.status(), 200);
+    assert_eq!(css.headers()["content-type"], "text/css");
+    assert_eq!(css.text().unwrap(), ":root { --text-accent: #d9480f; }\n");
+
+    let monitor_page = client
+        .get(format!("http://{addr}/_irondrop/monitor"))
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+    assert!(monitor_page.contains(r#"data-theme="light""#));
+
+    shutdown_tx.send(()).unwrap();
+    handle.join().unwrap();
+}
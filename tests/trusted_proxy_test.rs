@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: MIT
+//! Tests for `--trusted-proxies` and forwarded client addresses.
+
+use clap::Parser;
+use irondrop::cli::Cli;
+use irondrop::server::{CidrBlock, TrustedProxies, run_server};
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    _temp_dir: TempDir,
+}
+
+fn setup_server(extra_args: &[&str]) -> TestServer {
+    let dir = tempdir().unwrap();
+    let mut args = vec![
+        "irondrop".to_string(),
+        "-d".to_string(),
+        dir.path().to_string_lossy().into_owned(),
+        "--listen".to_string(),
+        "127.0.0.1".to_string(),
+        "--port".to_string(),
+        "0".to_string(),
+        "--rate-limit-search".to_string(),
+        "1".to_string(),
+    ];
+    args.extend(extra_args.iter().map(|a| a.to_string()));
+    let cli = Cli::parse_from(args);
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        _temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn search_as(client: &Client, addr: SocketAddr, forwarded_for: &str) -> StatusCode {
+    client
+        .get(format!("http://{addr}/_irondrop/search?q=notes"))
+        .header("X-Forwarded-For", forwarded_for)
+        .send()
+        .unwrap()
+        .status()
+}
+
+#[test]
+fn test_client_ip_from_forwarding_headers() {
+    let proxies = TrustedProxies::new(vec![
+        CidrBlock::parse("127.0.0.1").unwrap(),
+        CidrBlock::parse("10.0.0.0/8").unwrap(),
+    ]);
+    let proxy = ip("127.0.0.1");
+
+    // Untrusted peers never get their headers believed
+    assert_eq!(
+        proxies.client_ip(
+            ip("203.0.113.9"),
+            &headers(&[("x-forwarded-for", "1.2.3.4")])
+        ),
+        None
+    );
+    // No header: the proxy itself is the client
+    assert_eq!(proxies.client_ip(proxy, &HashMap::new()), Some(proxy));
+    // Rightmost untrusted hop wins, so a spoofed leftmost entry is ignored
+    assert_eq!(
+        proxies.client_ip(
+            proxy,
+            &headers(&[("x-forwarded-for", "6.6.6.6, 198.51.100.4, 10.1.2.3")])
+        ),
+        Some(ip("198.51.100.4"))
+    );
+    // `Forwarded` takes precedence and may carry ports and IPv6 brackets
+    assert_eq!(
+        proxies.client_ip(
+            proxy,
+            &headers(&[
+                (
+                    "forwarded",
+                    r#"for="[2001:db8::7]:4711";proto=https, for=10.0.0.2"#
+                ),
+                ("x-forwarded-for", "1.2.3.4"),
+            ])
+        ),
+        Some(ip("2001:db8::7"))
+    );
+    assert_eq!(
+        proxies.client_ip(
+            proxy,
+            &headers(&[("forwarded", "for=192.0.2.60:8080;by=203.0.113.43")])
+        ),
+        Some(ip("192.0.2.60"))
+    );
+    // Obfuscated identifiers stop the walk at the last known hop
+    assert_eq!(
+        proxies.client_ip(
+            proxy,
+            &headers(&[("forwarded", "for=_hidden, for=10.0.0.9")])
+        ),
+        Some(ip("10.0.0.9"))
+    );
+}
+
+#[test]
+fn test_trusted_proxy_limits_each_forwarded_client() {
+    let server = setup_server(&["--trusted-proxies", "127.0.0.1"]);
+    let client = Client::new();
+
+    assert_eq!(
+        search_as(&client, server.addr, "198.51.100.1"),
+        StatusCode::OK
+    );
+    assert_eq!(
+        search_as(&client, server.addr, "198.51.100.2"),
+        StatusCode::OK
+    );
+    assert_eq!(
+        search_as(&client, server.addr, "198.51.100.1"),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[test]
+fn test_forwarding_headers_ignored_without_trusted_proxies() {
+    let server = setup_server(&[]);
+    let client = Client::new();
+
+    assert_eq!(
+        search_as(&client, server.addr, "198.51.100.1"),
+        StatusCode::OK
+    );
+    assert_eq!(
+        search_as(&client, server.addr, "198.51.100.2"),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+fn listing_with_prefix(client: &Client, addr: SocketAddr, path: &str, prefix: &str) -> String {
+    let response = client
+        .get(format!("http://{addr}{path}"))
+        .header("X-Forwarded-Prefix", prefix)
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.text().unwrap()
+}
+
+#[test]
+fn test_forwarded_prefix_from_trusted_proxy() {
+    let server = setup_server(&["--trusted-proxies", "127.0.0.1"]);
+    std::fs::create_dir(server._temp_dir.path().join("docs")).unwrap();
+    let client = Client::new();
+
+    let html = listing_with_prefix(&client, server.addr, "/", "/files/");
+    assert!(html.contains(r#"href="/files/docs/""#), "{html}");
+    assert!(html.contains("/files/_irondrop/static/"));
+
+    // Redirects and error pages point under the prefix too
+    let response = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+        .get(format!("http://{}/docs", server.addr))
+        .header("X-Forwarded-Prefix", "/files")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.headers()["location"], "/files/docs/");
+
+    let response = client
+        .get(format!("http://{}/missing.txt", server.addr))
+        .header("X-Forwarded-Prefix", "/files")
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(
+        response
+            .text()
+            .unwrap()
+            .contains("/files/_irondrop/static/")
+    );
+
+    // Malformed prefixes are ignored
+    let html = listing_with_prefix(&client, server.addr, "/", "files\"><b>");
+    assert!(html.contains(r#"href="/docs/""#));
+    assert!(!html.contains("/files"));
+}
+
+#[test]
+fn test_forwarded_prefix_ignored_without_trusted_proxies() {
+    let server = setup_server(&[]);
+    std::fs::create_dir(server._temp_dir.path().join("docs")).unwrap();
+    let client = Client::new();
+
+    let html = listing_with_prefix(&client, server.addr, "/", "/files");
+    assert!(html.contains(r#"href="/docs/""#), "{html}");
+    assert!(!html.contains("/files/"));
+}
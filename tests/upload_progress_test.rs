@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MIT
+//! Tests for `/_irondrop/upload/progress/<id>`.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::blocking::Client;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+fn setup_upload_server() -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.bin".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(true),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(true),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn progress(addr: SocketAddr, id: &str) -> (u16, serde_json::Value) {
+    let response = Client::new()
+        .get(format!("http://{addr}/_irondrop/upload/progress/{id}"))
+        .send()
+        .unwrap();
+    let status = response.status().as_u16();
+    (status, response.json().unwrap_or(serde_json::Value::Null))
+}
+
+#[test]
+fn test_progress_is_reported_while_receiving() {
+    let server = setup_upload_server();
+    let total = 3 * 1024 * 1024;
+    let body = vec![7u8; total];
+
+    let mut stream = TcpStream::connect(server.addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let head = format!(
+        "POST /_irondrop/upload HTTP/1.1\r\nHost: x\r\nX-Filename: big.bin\r\n\
+         X-Upload-Id: upload-42\r\nContent-Type: application/octet-stream\r\n\
+         Content-Length: {total}\r\nConnection: close\r\n\r\n"
+    );
+    stream.write_all(head.as_bytes()).unwrap();
+    stream.write_all(&body[..1024 * 1024]).unwrap();
+
+    // Wait until the server has published part of the body
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let json = loop {
+        let (status, json) = progress(server.addr, "upload-42");
+        if status == 200 && json["bytes_received"].as_u64().unwrap_or(0) >= 512 * 1024 {
+            break json;
+        }
+        assert!(Instant::now() < deadline, "no progress reported: {json}");
+        thread::sleep(Duration::from_millis(20));
+    };
+    assert_eq!(json["stage"], "receiving");
+    assert_eq!(json["total_bytes"], total as u64);
+    assert!(json["percent"].as_f64().unwrap() < 100.0);
+
+    stream.write_all(&body[1024 * 1024..]).unwrap();
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    assert!(response.starts_with(b"HTTP/1.1 200"));
+    assert_eq!(
+        std::fs::metadata(server.temp_dir.path().join("big.bin"))
+            .unwrap()
+            .len(),
+        total as u64
+    );
+
+    let (status, json) = progress(server.addr, "upload-42");
+    assert_eq!(status, 200);
+    assert_eq!(json["stage"], "completed");
+    assert_eq!(json["bytes_received"], total as u64);
+    assert_eq!(json["percent"], 100.0);
+}
+
+#[test]
+fn test_progress_event_stream_and_unknown_ids() {
+    let server = setup_upload_server();
+    let response = Client::new()
+        .post(format!("http://{}/_irondrop/upload", server.addr))
+        .header("X-Filename", "small.bin")
+        .header("X-Upload-Id", "sse-1")
+        .body(vec![1u8; 4096])
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let events = Client::new()
+        .get(format!(
+            "http://{}/_irondrop/upload/progress/sse-1",
+            server.addr
+        ))
+        .header("Accept", "text/event-stream")
+        .send()
+        .unwrap();
+    assert_eq!(
+        events.headers()["content-type"].to_str().unwrap(),
+        "text/event-stream"
+    );
+    let text = events.text().unwrap();
+    assert!(text.starts_with("event: progress\ndata: {"), "{text}");
+    assert!(text.contains(r#""stage":"completed""#), "{text}");
+
+    assert_eq!(progress(server.addr, "never-started").0, 404);
+}
@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MIT
+//! End-to-end tests for per-client upload quotas.
+
+use irondrop::cli::Cli;
+use irondrop::server::run_server;
+use reqwest::StatusCode;
+use reqwest::blocking::Client;
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tempfile::{TempDir, tempdir};
+
+struct TestServer {
+    addr: SocketAddr,
+    shutdown_tx: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    temp_dir: TempDir,
+}
+
+fn setup_quota_server(daily: Option<u64>, total: Option<u64>) -> TestServer {
+    let dir = tempdir().unwrap();
+    let cli = Cli {
+        directory: dir.path().to_path_buf(),
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(0),
+        allowed_extensions: Some("*.txt".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(true),
+        max_upload_size: Some(10),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: daily,
+        upload_quota_total: total,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    };
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        if let Err(e) = run_server(cli, Some(shutdown_rx), Some(addr_tx)) {
+            eprintln!("Server thread failed: {e}");
+        }
+    });
+    let addr = addr_rx.recv().unwrap();
+
+    TestServer {
+        addr,
+        shutdown_tx,
+        handle: Some(handle),
+        temp_dir: dir,
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = self.shutdown_tx.send(());
+            let _ = handle.join();
+        }
+    }
+}
+
+fn upload(
+    client: &Client,
+    addr: SocketAddr,
+    name: &str,
+    body: &'static [u8],
+) -> (StatusCode, String) {
+    let res = client
+        .post(format!("http://{addr}/_irondrop/upload"))
+        .header("Content-Type", "application/octet-stream")
+        .header("X-Filename", name)
+        .body(body)
+        .send()
+        .unwrap();
+    let status = res.status();
+    (status, res.text().unwrap())
+}
+
+#[test]
+fn test_total_quota_returns_507_and_is_reported() {
+    let server = setup_quota_server(None, Some(30));
+    let client = Client::new();
+
+    let (status, _) = upload(&client, server.addr, "one.txt", b"twenty bytes of data");
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = upload(&client, server.addr, "two.txt", b"another twenty bytes");
+    assert_eq!(status, StatusCode::INSUFFICIENT_STORAGE);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["error"], "upload_quota_exceeded");
+    assert_eq!(json["quota"], "total");
+    assert_eq!(json["used_bytes"], 20);
+    assert_eq!(json["remaining_bytes"], 10);
+
+    let monitor: serde_json::Value = client
+        .get(format!("http://{}/_irondrop/monitor?json=1", server.addr))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    assert_eq!(monitor["quota"]["enabled"], true);
+    assert_eq!(monitor["quota"]["total_limit_bytes"], 30);
+    assert_eq!(monitor["quota"]["clients"][0]["client"], "ip:127.0.0.1");
+    assert_eq!(monitor["quota"]["clients"][0]["total_bytes"], 20);
+}
+
+#[test]
+fn test_daily_quota_returns_429_with_retry_after() {
+    let server = setup_quota_server(Some(25), None);
+    let client = Client::new();
+
+    let (status, _) = upload(&client, server.addr, "one.txt", b"twenty bytes of data");
+    assert_eq!(status, StatusCode::OK);
+
+    let res = client
+        .post(format!("http://{}/_irondrop/upload", server.addr))
+        .header("Content-Type", "application/octet-stream")
+        .header("X-Filename", "two.txt")
+        .body("more than five")
+        .send()
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(res.headers().contains_key("retry-after"));
+    let json: serde_json::Value = res.json().unwrap();
+    assert_eq!(json["quota"], "daily");
+}
+
+#[test]
+fn test_unverified_user_names_share_the_ip_quota() {
+    let server = setup_quota_server(Some(25), None);
+    let client = Client::new();
+
+    let (status, _) = upload(&client, server.addr, "one.txt", b"twenty bytes of data");
+    assert_eq!(status, StatusCode::OK);
+    // Without authentication, a made-up Basic Auth user is still the same client
+    for n in 0..3 {
+        let res = client
+            .post(format!("http://{}/_irondrop/upload", server.addr))
+            .basic_auth(format!("rand{n}"), Some("x"))
+            .header("Content-Type", "application/octet-stream")
+            .header("X-Filename", format!("fresh{n}.txt"))
+            .body("more than five")
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(
+            !server
+                .temp_dir
+                .path()
+                .join(format!("fresh{n}.txt"))
+                .exists()
+        );
+    }
+
+    let monitor: serde_json::Value = client
+        .get(format!("http://{}/_irondrop/monitor?json=1", server.addr))
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    let clients = monitor["quota"]["clients"].as_array().unwrap();
+    assert_eq!(clients.len(), 1);
+    assert_eq!(clients[0]["client"], "ip:127.0.0.1");
+}
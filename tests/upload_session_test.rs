@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: MIT
+use irondrop::cli::Cli;
+use irondrop::http::{Request, RequestBody};
+use irondrop::upload::DirectUploadHandler;
+use irondrop::upload_session::UploadSessionManager;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn create_test_cli(upload_dir: PathBuf) -> Cli {
+    Cli {
+        directory: upload_dir,
+        listen: Some("127.0.0.1".to_string()),
+        port: Some(8080),
+        allowed_extensions: Some("*.txt,*.bin".to_string()),
+        threads: Some(4),
+        chunk_size: Some(1024),
+        verbose: Some(false),
+        detailed_logging: Some(false),
+        username: None,
+        password: None,
+        enable_upload: Some(true),
+        max_upload_size: Some(100),
+        enable_webdav: Some(false),
+        disable_rate_limit: Some(false),
+        config_file: None,
+        log_dir: None,
+        ssl_cert: None,
+        ssl_key: None,
+        base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
+    }
+}
+
+fn open_request(filename: &str, size: u64) -> Request {
+    let mut headers = HashMap::new();
+    headers.insert("x-filename".to_string(), filename.to_string());
+    headers.insert("x-upload-length".to_string(), size.to_string());
+    Request {
+        method: "POST".to_string(),
+        path: "/_irondrop/upload/session".to_string(),
+        query: None,
+        headers,
+        body: None,
+        peer_addr: None,
+        forwarded_by: None,
+    }
+}
+
+fn chunk_request(id: &str, start: u64, data: &[u8], total: u64) -> Request {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "content-range".to_string(),
+        format!(
+            "bytes {}-{}/{}",
+            start,
+            start + data.len() as u64 - 1,
+            total
+        ),
+    );
+    Request {
+        method: "PUT".to_string(),
+        path: format!("/_irondrop/upload/session/{id}"),
+        query: None,
+        headers,
+        body: Some(RequestBody::Memory(data.to_vec())),
+        peer_addr: None,
+        forwarded_by: None,
+    }
+}
+
+fn session_id(body: &[u8]) -> String {
+    let json: serde_json::Value = serde_json::from_slice(body).unwrap();
+    json["id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_resumable_upload_in_chunks() {
+    let temp_dir = TempDir::new().unwrap();
+    let cli = create_test_cli(temp_dir.path().to_path_buf());
+    let handler = DirectUploadHandler::new(&cli).unwrap();
+    let manager = UploadSessionManager::default();
+
+    let data = b"0123456789abcdefghij";
+    let created = manager
+        .create_session(&open_request("resume.txt", data.len() as u64), &handler)
+        .unwrap();
+    assert_eq!(created.status_code, 201);
+    let id = session_id(&created.body);
+
+    let first = manager
+        .write_chunk(&id, &chunk_request(&id, 0, &data[..8], 20), &cli, None)
+        .unwrap();
+    assert_eq!(first.status_code, 200);
+
+    // Interrupted client asks where to resume
+    let status = manager.session_status(&id).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&status.body).unwrap();
+    assert_eq!(json["offset"], 8);
+
+    let last = manager
+        .write_chunk(&id, &chunk_request(&id, 8, &data[8..], 20), &cli, None)
+        .unwrap();
+    assert_eq!(last.status_code, 201);
+    assert_eq!(manager.session_count(), 0);
+
+    let saved = fs::read(temp_dir.path().join("resume.txt")).unwrap();
+    assert_eq!(saved, data);
+}
+
+#[test]
+fn test_out_of_order_chunk_reports_offset() {
+    let temp_dir = TempDir::new().unwrap();
+    let cli = create_test_cli(temp_dir.path().to_path_buf());
+    let handler = DirectUploadHandler::new(&cli).unwrap();
+    let manager = UploadSessionManager::default();
+
+    let created = manager
+        .create_session(&open_request("gap.bin", 10), &handler)
+        .unwrap();
+    let id = session_id(&created.body);
+
+    let response = manager
+        .write_chunk(&id, &chunk_request(&id, 5, b"56789", 10), &cli, None)
+        .unwrap();
+    assert_eq!(response.status_code, 409);
+    assert!(
+        response
+            .headers
+            .iter()
+            .any(|(k, v)| k == "Upload-Offset" && v == "0")
+    );
+}
+
+#[test]
+fn test_session_rejects_disallowed_extension() {
+    let temp_dir = TempDir::new().unwrap();
+    let cli = create_test_cli(temp_dir.path().to_path_buf());
+    let handler = DirectUploadHandler::new(&cli).unwrap();
+    let manager = UploadSessionManager::default();
+
+    let result = manager.create_session(&open_request("evil.exe", 10), &handler);
+    assert!(result.is_err());
+    assert_eq!(manager.session_count(), 0);
+}
+
+#[test]
+fn test_abort_and_expiry_remove_partial_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let cli = create_test_cli(temp_dir.path().to_path_buf());
+    let handler = DirectUploadHandler::new(&cli).unwrap();
+
+    let partial_count = || {
+        fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".part"))
+            .count()
+    };
+
+    let manager = UploadSessionManager::default();
+    let created = manager
+        .create_session(&open_request("abort.txt", 10), &handler)
+        .unwrap();
+    let id = session_id(&created.body);
+    assert_eq!(partial_count(), 1);
+    assert_eq!(manager.abort_session(&id).unwrap().status_code, 204);
+    assert_eq!(partial_count(), 0);
+
+    let expiring = UploadSessionManager::new(Duration::from_millis(0));
+    expiring
+        .create_session(&open_request("expire.txt", 10), &handler)
+        .unwrap();
+    assert_eq!(partial_count(), 1);
+    assert_eq!(expiring.cleanup_expired(), 1);
+    assert_eq!(partial_count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_session_final_chunk_is_scanned() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cli = create_test_cli(temp_dir.path().to_path_buf());
+    cli.upload_scan_command = Some("false".to_string());
+    let handler = DirectUploadHandler::new(&cli).unwrap();
+    let manager = UploadSessionManager::default();
+
+    let created = manager
+        .create_session(&open_request("infected.txt", 4), &handler)
+        .unwrap();
+    let id = session_id(&created.body);
+
+    let result = manager.write_chunk(&id, &chunk_request(&id, 0, b"data", 4), &cli, None);
+    assert!(matches!(
+        result,
+        Err(irondrop::error::AppError::UploadRejected(_))
+    ));
+    assert_eq!(manager.session_count(), 0);
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
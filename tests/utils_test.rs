@@ -1,6 +1,13 @@
 // SPDX-License-Identifier: MIT
 
-use irondrop::utils::{parse_query_params, percent_encode_path, resolve_upload_directory};
+use irondrop::error::AppError;
+use irondrop::utils::{
+    decode_path, decode_query_component, decode_request_target, find_unicode_variant,
+    format_http_date, has_unusable_component, is_unusable_file_name, is_windows_reserved_name,
+    parse_http_date, parse_json_string_lists, parse_json_string_map, parse_query_params,
+    parse_query_string, percent_decode, percent_encode_path, resolve_upload_directory,
+    unicode_path_key,
+};
 use std::fs;
 use std::path::Path;
 
@@ -103,8 +110,8 @@ fn test_parse_query_params_special_encoding() {
 fn test_parse_query_params_unicode() {
     let params = parse_query_params("/path?name=%E6%96%87%E4%BB%B6&value=%D1%84%D0%B0%D0%B9%D0%BB");
     // Should handle UTF-8 encoded parameters
-    assert!(params.contains_key("name"));
-    assert!(params.contains_key("value"));
+    assert_eq!(params.get("name").unwrap(), "文件");
+    assert_eq!(params.get("value").unwrap(), "файл");
 }
 
 #[test]
@@ -175,3 +182,236 @@ fn test_resolve_upload_directory_special_characters() {
         let _ = resolve_upload_directory(base, Some(&encoded_path));
     }
 }
+
+#[test]
+fn test_http_date_round_trip() {
+    use std::time::{Duration, UNIX_EPOCH};
+    let parsed = parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+    assert_eq!(parsed, UNIX_EPOCH + Duration::from_secs(784_111_777));
+
+    let leap = UNIX_EPOCH + Duration::from_secs(1_709_208_000); // 2024-02-29 12:00:00
+    let formatted = format_http_date(leap).unwrap();
+    assert_eq!(formatted, "Thu, 29 Feb 2024 12:00:00 GMT");
+    assert_eq!(parse_http_date(&formatted), Some(leap));
+
+    assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+    assert_eq!(parse_http_date("garbage"), None);
+}
+
+#[test]
+fn test_parse_json_string_map() {
+    let map = parse_json_string_map(
+        r#" {"action": "move", "path":"/a \"b\".txt", "to":"/caf\u00e9\ud83d\ude00"} "#,
+    )
+    .unwrap();
+    assert_eq!(map["action"], "move");
+    assert_eq!(map["path"], "/a \"b\".txt");
+    assert_eq!(map["to"], "/caf\u{e9}\u{1f600}");
+    assert!(parse_json_string_map("{}").unwrap().is_empty());
+
+    assert_eq!(parse_json_string_map(r#"{"n": 1}"#), None);
+    assert_eq!(parse_json_string_map(r#"{"a":"b",}"#), None);
+    assert_eq!(parse_json_string_map(r#"{"a":"b"} trailing"#), None);
+    assert_eq!(parse_json_string_map("[]"), None);
+}
+
+#[test]
+fn test_parse_json_string_lists() {
+    let map =
+        parse_json_string_lists(r#"{"paths": ["/a.txt", "/dir \"x\""], "format":"tar", "e":[]}"#)
+            .unwrap();
+    assert_eq!(map["paths"], ["/a.txt", "/dir \"x\""]);
+    assert_eq!(map["format"], ["tar"]);
+    assert!(map["e"].is_empty());
+
+    assert_eq!(parse_json_string_lists(r#"{"paths":["/a",]}"#), None);
+    assert_eq!(parse_json_string_lists(r#"{"paths":[1]}"#), None);
+    assert_eq!(parse_json_string_lists(r#"{"paths":[["/a"]]}"#), None);
+}
+
+#[test]
+fn test_decode_path_matrix() {
+    let accepted = [
+        ("/", "/"),
+        ("/a%20b.txt", "/a b.txt"),
+        ("/a+b.txt", "/a+b.txt"),
+        ("/a%2Bb.txt", "/a+b.txt"),
+        ("/caf%C3%A9.txt", "/caf\u{e9}.txt"),
+        ("/cafe%CC%81.txt", "/cafe\u{301}.txt"),
+        ("/caf\u{e9}.txt", "/caf\u{e9}.txt"),
+        ("/%F0%9F%A6%80", "/\u{1f980}"),
+        ("/sub%2Fx.txt", "/sub/x.txt"),
+        ("/100%25.txt", "/100%.txt"),
+        ("/test%2Efile.txt", "/test.file.txt"),
+        ("/...%2e", "/...."),
+        // Plain dot segments are resolved later by normalize_path
+        ("/a/../b", "/a/../b"),
+    ];
+    for (raw, decoded) in accepted {
+        assert_eq!(decode_path(raw).unwrap(), decoded, "{raw}");
+    }
+
+    let rejected = [
+        "/%2e%2e/etc/passwd",
+        "/%2E%2E/etc/passwd",
+        "/.%2e/secret",
+        "/%2e",
+        "/..%2fetc",
+        "/a%2f..%2fb",
+        "/..%5cwindows",
+        "/%C0%AE%C0%AE/etc",
+        "/%E0%80%AE",
+        "/%C3",
+        "/%FF.txt",
+        "/%zz",
+        "/trailing%2",
+        "/trailing%",
+        "/nul%00.txt",
+        "/line%0Abreak",
+        "/del%7F",
+    ];
+    for raw in rejected {
+        assert!(
+            matches!(decode_path(raw), Err(AppError::BadRequest)),
+            "{raw} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn test_decode_request_target_keeps_query() {
+    let (path, query) = decode_request_target("/caf%C3%A9/?q=a%26b%3Dc+d&path=%2F").unwrap();
+    assert_eq!(path, "/caf\u{e9}/");
+    assert_eq!(query.as_deref(), Some("q=a%26b%3Dc+d&path=%2F"));
+    let params = parse_query_string(&query.unwrap());
+    assert_eq!(params["q"], "a&b=c d");
+    assert_eq!(params["path"], "/");
+    assert!(decode_request_target("/%2e%2e/?x=1").is_err());
+
+    // An escaped `?` is part of the name, not the start of the query
+    assert_eq!(
+        decode_request_target("/what%3F.txt?download=1").unwrap(),
+        ("/what?.txt".to_string(), Some("download=1".to_string()))
+    );
+    assert_eq!(
+        decode_request_target("/what%3F.txt").unwrap(),
+        ("/what?.txt".to_string(), None)
+    );
+
+    assert_eq!(decode_query_component("a+b%2Bc"), "a b+c");
+    assert_eq!(percent_decode("a+b%2Bc%zz%"), "a+b+c%zz%");
+    assert_eq!(percent_decode("caf%C3%A9"), "caf\u{e9}");
+}
+
+#[test]
+fn test_percent_encode_path_round_trips() {
+    for name in [
+        "plain.txt",
+        "a b+c.txt",
+        "100% sure?.txt",
+        "#hash \"quoted\" <tag>.md",
+        "caf\u{e9}/r\u{e9}sum\u{e9}.pdf",
+        "\u{d55c}\u{ae00}.txt",
+        "tab\there",
+    ] {
+        let encoded = percent_encode_path(Path::new(name));
+        assert!(
+            encoded.is_ascii() && !encoded.contains(['?', '#', ' ', '\t']),
+            "{encoded}"
+        );
+        if name.contains('\t') {
+            // Control characters encode but are refused on the way in
+            assert!(decode_path(&encoded).is_err());
+        } else {
+            assert_eq!(decode_path(&encoded).unwrap(), name);
+        }
+    }
+}
+
+#[test]
+fn test_unicode_path_key_equates_normalization_forms() {
+    let pairs = [
+        ("caf\u{e9}", "cafe\u{301}"),
+        ("\u{c5}ngstr\u{f6}m", "A\u{30a}ngstro\u{308}m"),
+        // Two marks, inner one first
+        ("Vi\u{1ec7}t", "Vie\u{323}\u{302}t"),
+        ("\u{1ea4}", "A\u{302}\u{301}"),
+        ("\u{d55c}", "\u{1112}\u{1161}\u{11ab}"),
+        ("\u{ac00}", "\u{1100}\u{1161}"),
+    ];
+    for (composed, decomposed) in pairs {
+        assert_eq!(unicode_path_key(composed), unicode_path_key(decomposed));
+        assert_eq!(unicode_path_key(decomposed), decomposed);
+    }
+    assert_ne!(unicode_path_key("caf\u{e9}"), unicode_path_key("cafe"));
+    assert_ne!(unicode_path_key("caf\u{e8}"), unicode_path_key("caf\u{e9}"));
+    assert_eq!(unicode_path_key("ascii.txt"), "ascii.txt");
+}
+
+#[test]
+fn test_find_unicode_variant() {
+    let tmp = tempfile::tempdir().unwrap();
+    let base = tmp.path();
+    fs::create_dir(base.join("re\u{301}sume\u{301}")).unwrap();
+    fs::write(base.join("re\u{301}sume\u{301}").join("caf\u{e9}.txt"), "x").unwrap();
+
+    assert_eq!(
+        find_unicode_variant(base, Path::new("r\u{e9}sum\u{e9}/cafe\u{301}.txt")),
+        Some(Path::new("re\u{301}sume\u{301}").join("caf\u{e9}.txt"))
+    );
+    // Exact spellings, ASCII names and missing files need no variant
+    assert_eq!(
+        find_unicode_variant(base, Path::new("re\u{301}sume\u{301}/caf\u{e9}.txt")),
+        None
+    );
+    assert_eq!(find_unicode_variant(base, Path::new("resume")), None);
+    assert_eq!(
+        find_unicode_variant(base, Path::new("r\u{e9}sum\u{e9}/caf\u{e8}.txt")),
+        None
+    );
+}
+
+#[test]
+fn test_windows_reserved_names() {
+    for name in [
+        "CON",
+        "con",
+        "Prn.txt",
+        "aux.tar.gz",
+        "NUL ",
+        "nul .txt",
+        "COM1",
+        "com9.log",
+        "LPT0",
+        "lpt\u{b2}.txt",
+        "CONIN$",
+        "conout$.log",
+    ] {
+        assert!(is_windows_reserved_name(name), "{name}");
+    }
+    for name in [
+        "CONSOLE",
+        "com10",
+        "COM",
+        "lptx.txt",
+        "auxiliary.md",
+        "prn_report.pdf",
+        "thesis.aux",
+        "",
+    ] {
+        assert!(!is_windows_reserved_name(name), "{name}");
+    }
+
+    // Only Windows cannot hold these
+    for name in ["CON", "report.txt.", "notes "] {
+        assert_eq!(is_unusable_file_name(name), cfg!(windows), "{name}");
+    }
+    assert!(!is_unusable_file_name("report.txt"));
+    assert_eq!(
+        has_unusable_component(Path::new("docs/aux/readme.md")),
+        cfg!(windows)
+    );
+    assert!(!has_unusable_component(Path::new(
+        "docs/auxiliary/readme.md"
+    )));
+}
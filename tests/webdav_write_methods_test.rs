@@ -47,6 +47,108 @@ where
         ssl_cert: None,
         ssl_key: None,
         base_path: None,
+        compression: None,
+        compression_min_size: None,
+        auth_tokens: None,
+        upload_quota_daily: None,
+        upload_quota_total: None,
+        allow_ips: None,
+        deny_ips: None,
+        log_rejected_ips: None,
+        enable_file_management: None,
+        search_content: None,
+        search_content_extensions: None,
+        search_content_max_size: None,
+        read_timeout: None,
+        write_timeout: None,
+        header_timeout: None,
+        max_bandwidth: None,
+        per_conn_bandwidth: None,
+        max_concurrent_downloads: None,
+        download_queue: None,
+        log_rotate_size: None,
+        log_rotate_age: None,
+        log_rotate_keep: None,
+        log_rotate_gzip: None,
+        mdns: None,
+        cors_origins: None,
+        cors_methods: None,
+        cors_headers: None,
+        cors_max_age: None,
+        cors_credentials: None,
+        security_headers: None,
+        content_security_policy: None,
+        frame_options: None,
+        referrer_policy: None,
+        hsts_max_age: None,
+        upload_scan_command: None,
+        webhook_urls: None,
+        webhook_events: None,
+        webhook_retries: None,
+        webhook_queue_size: None,
+        share_secret: None,
+        share_max_ttl: None,
+        auth_paths: None,
+        upload_organize: None,
+        upload_dedup: None,
+        template_dir: None,
+        theme: None,
+        custom_css: None,
+        rate_limit: None,
+        rate_limit_concurrent: None,
+        rate_limit_upload: None,
+        rate_limit_search: None,
+        trusted_proxies: None,
+        http2: None,
+        mmap_cache_size: None,
+        mmap_max_file: None,
+        tcp_backlog: None,
+        tcp_nodelay: None,
+        tcp_reuse_address: None,
+        tcp_reuse_port: None,
+        tcp_keepalive: None,
+        tcp_keepalive_interval: None,
+        tcp_keepalive_retries: None,
+        accept_batch: None,
+        max_workers: None,
+        worker_queue: None,
+        follow_symlinks: None,
+        hide_dotfiles: None,
+        ignore_patterns: None,
+        read_only: None,
+        mime_types: None,
+        default_charset: None,
+        force_download: None,
+        upload_conflict: None,
+        upload_disk_reserve: None,
+        search_exact_sizes: None,
+        search_trigram_budget: None,
+        search_exclude: None,
+        search_max_depth: None,
+        search_depth_limits: None,
+        max_upload_bandwidth: None,
+        per_upload_bandwidth: None,
+        service: None,
+        check_config: false,
+        password_hash: None,
+        auth_max_failures: None,
+        auth_lockout_secs: None,
+        users: None,
+        auth_sessions: None,
+        session_lifetime: None,
+        session_secret: None,
+        audit: None,
+        audit_file: None,
+        enable_edit: None,
+        quick_share: None,
+        index: None,
+        spa: None,
+        redirects: None,
+        redirects_file: None,
+        not_found_page: None,
+        file_cache_size: None,
+        file_cache_max_file: None,
+        max_request_body: None,
     };
 
     let (shutdown_tx, shutdown_rx) = mpsc::channel();
@@ -178,6 +280,102 @@ fn test_put_creates_and_updates_file() {
     );
 }
 
+#[test]
+fn test_put_content_range_assembles_and_finalizes() {
+    let server = setup_test_server_with_tree(|root| {
+        fs::write(root.join("big.bin"), "old").unwrap();
+    });
+    let client = Client::new();
+    let url = format!("http://{}/big.bin", server.addr);
+    let put_range = |range: &str, body: &'static str| {
+        client
+            .request(Method::PUT, &url)
+            .header("Content-Range", range)
+            .body(body)
+            .send()
+            .unwrap()
+    };
+
+    // Out-of-order pieces are accepted; Upload-Offset counts the contiguous prefix
+    let response = put_range("bytes 5-9/10", "world");
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert_eq!(response.headers()["upload-offset"], "0");
+
+    // Finalizing too early leaves the existing file untouched
+    let response = put_range("bytes */10", "");
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(
+        fs::read_to_string(server.root.join("big.bin")).unwrap(),
+        "old"
+    );
+
+    // Body length must match the range
+    let response = put_range("bytes 0-4/10", "hi");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = put_range("bytes 0-4/10", "hello");
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert_eq!(response.headers()["upload-offset"], "10");
+
+    let response = put_range("bytes */10", "");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        fs::read_to_string(server.root.join("big.bin")).unwrap(),
+        "helloworld"
+    );
+    let leftovers = fs::read_dir(&server.root)
+        .unwrap()
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with('.')
+        })
+        .count();
+    assert_eq!(leftovers, 0, "staging file should be gone");
+
+    // A finalize without pending pieces has nothing to commit
+    let response = put_range("bytes */10", "");
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+/// Deduplicated uploads are hard links, so overwriting one must not rewrite its twins
+#[cfg(unix)]
+#[test]
+fn test_put_over_hard_link_leaves_twin_unchanged() {
+    let server = setup_test_server_with_tree(|root| {
+        fs::write(root.join("original.txt"), "shared").unwrap();
+        for twin in ["replaced.txt", "emptied.txt", "finalized.txt"] {
+            fs::hard_link(root.join("original.txt"), root.join(twin)).unwrap();
+        }
+    });
+    let client = Client::new();
+    let put = |name: &str, range: Option<&str>, body: &'static str| {
+        let mut request = client
+            .request(Method::PUT, format!("http://{}/{name}", server.addr))
+            .body(body);
+        if let Some(range) = range {
+            request = request.header("Content-Range", range);
+        }
+        request.send().unwrap().status()
+    };
+
+    assert_eq!(put("replaced.txt", None, "new"), StatusCode::NO_CONTENT);
+    assert_eq!(put("emptied.txt", None, ""), StatusCode::NO_CONTENT);
+    assert_eq!(
+        put("finalized.txt", Some("bytes */0"), ""),
+        StatusCode::NO_CONTENT
+    );
+
+    let read = |name: &str| fs::read_to_string(server.root.join(name)).unwrap();
+    assert_eq!(read("replaced.txt"), "new");
+    assert_eq!(read("emptied.txt"), "");
+    assert_eq!(read("finalized.txt"), "");
+    assert_eq!(read("original.txt"), "shared");
+}
+
 #[test]
 fn test_put_missing_parent_conflict() {
     let server = setup_test_server_with_tree(|_root| {});